@@ -74,7 +74,37 @@ pub enum CreateInstanceResponse {
     },
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Copy, JsonSchema)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RawReplayJournal {
+    pub journal_file: PathBuf,
+}
+
+/// The first journaled operation whose resulting state label differs
+/// from the state label after re-executing the operation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct JournalDivergence {
+    /// Index of the operation in the journal (starting from 0).
+    pub index: usize,
+    pub operation_id: String,
+    pub expected_state_label: String,
+    pub actual_state_label: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum ReplayJournalResponse {
+    Replayed {
+        instance_id: InstanceId,
+        topology: Topology,
+        /// Number of re-executed operations.
+        replayed_operations: usize,
+        divergence: Option<JournalDivergence>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Copy, Eq, PartialEq, JsonSchema)]
 pub struct RawTime {
     pub nanos_since_epoch: u64,
 }
@@ -544,6 +574,15 @@ pub struct InstanceConfig {
     pub nonmainnet_features: bool,
     pub log_level: Option<String>,
     pub bitcoind_addr: Option<Vec<SocketAddr>>,
+    /// File to which all mutating operations on the instance are journaled.
+    pub journal_file: Option<PathBuf>,
+    /// Record the cost of every completed canister message.
+    #[serde(default)]
+    pub message_profiling: bool,
+    /// Directory to which a Wasm-level profile (collapsed stacks) of the executed code
+    /// is written for every canister.
+    #[serde(default)]
+    pub wasm_profile_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
@@ -926,3 +965,187 @@ impl From<MockCanisterHttpResponse> for RawMockCanisterHttpResponse {
         }
     }
 }
+
+/// The effect of an XNet fault on the matching inter-canister messages.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Hash, Eq, PartialEq, JsonSchema)]
+pub enum XNetFaultKind {
+    /// Messages are lost: the destination subnet acknowledges them without inducting them.
+    /// Callbacks for lost best-effort messages eventually expire while calls whose
+    /// guaranteed-response messages are lost never complete.
+    Drop,
+    /// Messages are delayed by the given number of rounds once they reach the head of their stream.
+    /// Subsequent messages of the same stream are delayed, too.
+    Delay { rounds: u64 },
+    /// Requests are rejected with `SYS_TRANSIENT` as if the input queue of the receiver was full.
+    /// Responses are not affected.
+    Reject,
+    /// No messages or signals are delivered (for a canister: no messages sent by or to the canister
+    /// and no subsequent messages of the same stream are delivered) as if the source subnet was unavailable.
+    Unavailable,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, JsonSchema)]
+pub enum RawXNetFaultTarget {
+    Stream {
+        source: RawSubnetId,
+        destination: RawSubnetId,
+    },
+    Subnet(RawSubnetId),
+    Canister(RawCanisterId),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, JsonSchema)]
+pub struct RawXNetFault {
+    pub target: RawXNetFaultTarget,
+    pub kind: XNetFaultKind,
+}
+
+/// The inter-canister messages affected by an XNet fault.
+#[derive(Clone, Serialize, Deserialize, Debug, Hash, Eq, PartialEq)]
+pub enum XNetFaultTarget {
+    /// All messages from the source subnet to the destination subnet.
+    Stream {
+        source: SubnetId,
+        destination: SubnetId,
+    },
+    /// All messages from the subnet to all other subnets and from all other subnets to the subnet.
+    Subnet(SubnetId),
+    /// All messages sent by or to the canister from or to a different subnet.
+    Canister(Principal),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Hash, Eq, PartialEq)]
+pub struct XNetFault {
+    pub target: XNetFaultTarget,
+    pub kind: XNetFaultKind,
+}
+
+impl From<RawXNetFault> for XNetFault {
+    fn from(raw_xnet_fault: RawXNetFault) -> Self {
+        let target = match raw_xnet_fault.target {
+            RawXNetFaultTarget::Stream {
+                source,
+                destination,
+            } => XNetFaultTarget::Stream {
+                source: candid::Principal::from_slice(&source.subnet_id),
+                destination: candid::Principal::from_slice(&destination.subnet_id),
+            },
+            RawXNetFaultTarget::Subnet(subnet_id) => {
+                XNetFaultTarget::Subnet(candid::Principal::from_slice(&subnet_id.subnet_id))
+            }
+            RawXNetFaultTarget::Canister(canister_id) => {
+                XNetFaultTarget::Canister(candid::Principal::from_slice(&canister_id.canister_id))
+            }
+        };
+        Self {
+            target,
+            kind: raw_xnet_fault.kind,
+        }
+    }
+}
+
+impl From<XNetFault> for RawXNetFault {
+    fn from(xnet_fault: XNetFault) -> Self {
+        let target = match xnet_fault.target {
+            XNetFaultTarget::Stream {
+                source,
+                destination,
+            } => RawXNetFaultTarget::Stream {
+                source: RawSubnetId {
+                    subnet_id: source.as_slice().to_vec(),
+                },
+                destination: RawSubnetId {
+                    subnet_id: destination.as_slice().to_vec(),
+                },
+            },
+            XNetFaultTarget::Subnet(subnet_id) => RawXNetFaultTarget::Subnet(RawSubnetId {
+                subnet_id: subnet_id.as_slice().to_vec(),
+            }),
+            XNetFaultTarget::Canister(canister_id) => RawXNetFaultTarget::Canister(RawCanisterId {
+                canister_id: canister_id.as_slice().to_vec(),
+            }),
+        };
+        Self {
+            target,
+            kind: xnet_fault.kind,
+        }
+    }
+}
+
+/// The cost of executing canister messages.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct MessageCost {
+    /// The number of instructions executed.
+    pub instructions: u64,
+    /// The net amount of cycles charged per use case (e.g., `Instructions`).
+    /// Use cases with no charge are omitted.
+    pub cycles_by_use_case: BTreeMap<String, u128>,
+    /// The number of (OS) pages of the Wasm heap dirtied.
+    pub heap_dirty_pages: u64,
+    /// The number of (OS) pages of the stable memory dirtied.
+    pub stable_dirty_pages: u64,
+    /// The number of downstream calls made.
+    pub downstream_calls: u64,
+}
+
+impl MessageCost {
+    /// Returns the total amount of cycles charged.
+    pub fn cycles(&self) -> u128 {
+        self.cycles_by_use_case.values().sum()
+    }
+}
+
+/// The cost of executing a single canister message.
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MessageProfile {
+    pub canister_id: RawCanisterId,
+    /// The method executed by the message. A response is attributed to the method
+    /// that made the corresponding call and a canister task (e.g., a timer)
+    /// is attributed to the corresponding system method (e.g., `canister_global_timer`).
+    pub method_name: String,
+    /// The hex-encoded ID of the ingress message whose call context the message belongs to (if any).
+    pub ingress_message_id: Option<String>,
+    /// The time at which the execution of the message started.
+    pub time: RawTime,
+    pub cost: MessageCost,
+}
+
+/// The aggregated cost of all messages attributed to a canister method.
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MethodProfile {
+    pub canister_id: RawCanisterId,
+    pub method_name: String,
+    /// The number of messages attributed to the method.
+    pub messages: u64,
+    pub cost: MessageCost,
+}
+
+/// The cost of all canister messages completed while message profiling was enabled.
+/// Only the most recent 100,000 messages per subnet are retained.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct MessageProfiles {
+    /// The individual messages in the order of their execution.
+    pub messages: Vec<MessageProfile>,
+    /// The messages aggregated per canister method and sorted by canister and method name.
+    pub methods: Vec<MethodProfile>,
+}
+
+impl MessageProfiles {
+    /// Returns the aggregated cost of the given canister method.
+    pub fn get_method(&self, canister_id: Principal, method_name: &str) -> Option<&MethodProfile> {
+        self.methods.iter().find(|method| {
+            method.canister_id.canister_id == canister_id.as_slice()
+                && method.method_name == method_name
+        })
+    }
+
+    /// Returns the messages belonging to the call context of the given ingress message
+    /// (including the ingress message itself).
+    pub fn get_ingress_message(&self, message_id: &RawMessageId) -> Vec<&MessageProfile> {
+        let message_id = hex::encode(&message_id.message_id);
+        self.messages
+            .iter()
+            .filter(|message| message.ingress_message_id.as_ref() == Some(&message_id))
+            .collect()
+    }
+}
@@ -55,8 +55,8 @@
 use crate::{
     common::rest::{
         BlobCompression, BlobId, CanisterHttpRequest, ExtendedSubnetConfigSet, HttpsConfig,
-        InstanceId, MockCanisterHttpResponse, RawEffectivePrincipal, RawMessageId, SubnetId,
-        SubnetKind, SubnetSpec, Topology,
+        InstanceId, MessageProfiles, MockCanisterHttpResponse, RawEffectivePrincipal, RawMessageId,
+        SubnetId, SubnetKind, SubnetSpec, Topology, XNetFault,
     },
     nonblocking::PocketIc as PocketIcAsync,
 };
@@ -114,6 +114,9 @@ pub struct PocketIcBuilder {
     nonmainnet_features: bool,
     log_level: Option<Level>,
     bitcoind_addr: Option<Vec<SocketAddr>>,
+    journal_file: Option<PathBuf>,
+    message_profiling: bool,
+    wasm_profile_dir: Option<PathBuf>,
 }
 
 #[allow(clippy::new_without_default)]
@@ -129,6 +132,9 @@ impl PocketIcBuilder {
             nonmainnet_features: false,
             log_level: None,
             bitcoind_addr: None,
+            journal_file: None,
+            message_profiling: false,
+            wasm_profile_dir: None,
         }
     }
 
@@ -149,6 +155,9 @@ impl PocketIcBuilder {
             self.nonmainnet_features,
             self.log_level,
             self.bitcoind_addr,
+            self.journal_file,
+            self.message_profiling,
+            self.wasm_profile_dir,
         )
     }
 
@@ -163,6 +172,9 @@ impl PocketIcBuilder {
             self.nonmainnet_features,
             self.log_level,
             self.bitcoind_addr,
+            self.journal_file,
+            self.message_profiling,
+            self.wasm_profile_dir,
         )
         .await
     }
@@ -215,6 +227,33 @@ impl PocketIcBuilder {
         }
     }
 
+    /// Journal all mutating operations on the instance to the given file
+    /// (the file is created if it does not exist and truncated otherwise).
+    /// The journal can be replayed against a fresh instance using
+    /// the `POST` endpoint `/instances/replay` of the PocketIC server.
+    /// Note that the provided path must be accessible for the PocketIC server process.
+    pub fn with_journal_file(mut self, journal_file: PathBuf) -> Self {
+        self.journal_file = Some(journal_file);
+        self
+    }
+
+    /// Record the cost (instructions, cycles, dirtied pages, and downstream calls)
+    /// of every completed canister message. The recorded cost can be retrieved
+    /// using `PocketIc::get_message_profiles`.
+    pub fn with_message_profiling(mut self) -> Self {
+        self.message_profiling = true;
+        self
+    }
+
+    /// Profile the executed canister code under the IC cost model and append
+    /// a flamegraph (in the collapsed-stack format) per canister to the file
+    /// `<canister_id>.folded` in the given directory.
+    /// Note that the provided path must be accessible for the PocketIC server process.
+    pub fn with_wasm_profile_dir(mut self, wasm_profile_dir: PathBuf) -> Self {
+        self.wasm_profile_dir = Some(wasm_profile_dir);
+        self
+    }
+
     /// Add an empty NNS subnet unless an NNS subnet has already been added.
     pub fn with_nns_subnet(mut self) -> Self {
         let mut config = self.config.unwrap_or_default();
@@ -396,6 +435,30 @@ impl PocketIc {
         }
     }
 
+    /// Creates a new PocketIC instance starting from the current state of this PocketIC instance.
+    /// The two instances evolve independently afterwards, i.e., changes to one of them
+    /// are not visible in the other one.
+    #[instrument(skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn fork(&self) -> Self {
+        let (tx, rx) = channel();
+        let thread = thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            tx.send(rt).unwrap();
+        });
+        let runtime = rx.recv().unwrap();
+
+        let pocket_ic = self.runtime.block_on(async { self.pocket_ic.fork().await });
+
+        Self {
+            pocket_ic,
+            runtime: Arc::new(runtime),
+            thread: Some(thread),
+        }
+    }
+
     pub(crate) fn from_components(
         subnet_config_set: impl Into<ExtendedSubnetConfigSet>,
         server_url: Option<Url>,
@@ -406,6 +469,9 @@ impl PocketIc {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoind_addr: Option<Vec<SocketAddr>>,
+        journal_file: Option<PathBuf>,
+        message_profiling: bool,
+        wasm_profile_dir: Option<PathBuf>,
     ) -> Self {
         let (tx, rx) = channel();
         let thread = thread::spawn(move || {
@@ -428,6 +494,9 @@ impl PocketIc {
                 nonmainnet_features,
                 log_level,
                 bitcoind_addr,
+                journal_file,
+                message_profiling,
+                wasm_profile_dir,
             )
             .await
         });
@@ -1234,6 +1303,23 @@ impl PocketIc {
                 .await
         })
     }
+
+    /// Inject faults into the inter-canister messages between subnets of the PocketIC instance,
+    /// replacing all previously injected faults (an empty vector removes all faults).
+    #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn set_xnet_faults(&self, xnet_faults: Vec<XNetFault>) {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.set_xnet_faults(xnet_faults).await })
+    }
+
+    /// Get the cost of all canister messages completed on the PocketIC instance
+    /// (individually and aggregated per canister method).
+    /// Message profiling must be enabled using `PocketIcBuilder::with_message_profiling`.
+    #[instrument(skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn get_message_profiles(&self) -> MessageProfiles {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.get_message_profiles().await })
+    }
 }
 
 impl Default for PocketIc {
@@ -1,11 +1,11 @@
 use crate::common::rest::{
     ApiResponse, AutoProgressConfig, BlobCompression, BlobId, CanisterHttpRequest,
     CreateHttpGatewayResponse, CreateInstanceResponse, ExtendedSubnetConfigSet, HttpGatewayBackend,
-    HttpGatewayConfig, HttpGatewayInfo, HttpsConfig, InstanceConfig, InstanceId,
+    HttpGatewayConfig, HttpGatewayInfo, HttpsConfig, InstanceConfig, InstanceId, MessageProfiles,
     MockCanisterHttpResponse, RawAddCycles, RawCanisterCall, RawCanisterHttpRequest, RawCanisterId,
     RawCanisterResult, RawCycles, RawEffectivePrincipal, RawIngressStatusArgs, RawMessageId,
     RawMockCanisterHttpResponse, RawPrincipalId, RawSetStableMemory, RawStableMemory, RawSubnetId,
-    RawTime, RawVerifyCanisterSigArg, SubnetId, TickConfigs, Topology,
+    RawTime, RawVerifyCanisterSigArg, RawXNetFault, SubnetId, TickConfigs, Topology, XNetFault,
 };
 pub use crate::DefaultEffectiveCanisterIdError;
 use crate::{
@@ -137,6 +137,9 @@ impl PocketIc {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoind_addr: Option<Vec<SocketAddr>>,
+        journal_file: Option<PathBuf>,
+        message_profiling: bool,
+        wasm_profile_dir: Option<PathBuf>,
     ) -> Self {
         let server_url = if let Some(server_url) = server_url {
             server_url
@@ -185,6 +188,9 @@ impl PocketIc {
             nonmainnet_features,
             log_level: log_level.map(|l| l.to_string()),
             bitcoind_addr,
+            journal_file,
+            message_profiling,
+            wasm_profile_dir,
         };
 
         let test_driver_pid = std::process::id();
@@ -218,6 +224,44 @@ impl PocketIc {
         }
     }
 
+    /// Creates a new PocketIC instance starting from the current state of this PocketIC instance.
+    /// The two instances evolve independently afterwards, i.e., changes to one of them
+    /// are not visible in the other one.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub async fn fork(&self) -> Self {
+        let instance_id = match self
+            .reqwest_client
+            .post(self.instance_url().join("fork").unwrap())
+            .send()
+            .await
+            .expect("Failed to get result")
+            .json::<CreateInstanceResponse>()
+            .await
+            .expect("Could not parse response for fork instance request")
+        {
+            CreateInstanceResponse::Created { instance_id, .. } => instance_id,
+            CreateInstanceResponse::Error { message } => panic!("{}", message),
+        };
+        debug!(
+            "instance_id={} New instance forked from instance {}.",
+            instance_id, self.instance_id
+        );
+
+        let test_driver_pid = std::process::id();
+        let log_guard = setup_tracing(test_driver_pid);
+
+        Self {
+            instance_id,
+            max_request_time_ms: self.max_request_time_ms,
+            http_gateway: None,
+            server_url: self.server_url.clone(),
+            reqwest_client: reqwest::Client::new(),
+            owns_instance: true,
+            _log_guard: log_guard,
+            _temp_dir: None,
+        }
+    }
+
     /// Returns the URL of the PocketIC server on which this PocketIC instance is running.
     pub fn get_server_url(&self) -> Url {
         self.server_url.clone()
@@ -1647,6 +1691,25 @@ impl PocketIc {
             mock_canister_http_response.into();
         self.post(endpoint, raw_mock_canister_http_response).await
     }
+
+    /// Inject faults into the inter-canister messages between subnets of the PocketIC instance,
+    /// replacing all previously injected faults (an empty vector removes all faults).
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub async fn set_xnet_faults(&self, xnet_faults: Vec<XNetFault>) {
+        let endpoint = "update/set_xnet_faults";
+        let raw_xnet_faults: Vec<RawXNetFault> =
+            xnet_faults.into_iter().map(|fault| fault.into()).collect();
+        self.post::<(), _>(endpoint, raw_xnet_faults).await
+    }
+
+    /// Get the cost of all canister messages completed on the PocketIC instance
+    /// (individually and aggregated per canister method).
+    /// Message profiling must be enabled using `PocketIcBuilder::with_message_profiling`.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub async fn get_message_profiles(&self) -> MessageProfiles {
+        let endpoint = "read/get_message_profiles";
+        self.get(endpoint).await
+    }
 }
 
 /// Call a canister candid method, authenticated. The sender can be impersonated (i.e., the
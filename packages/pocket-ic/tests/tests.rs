@@ -13,7 +13,7 @@ use pocket_ic::common::rest::{BlockmakerConfigs, RawSubnetBlockmaker, TickConfig
 use pocket_ic::{
     common::rest::{
         BlobCompression, CanisterHttpReply, CanisterHttpResponse, MockCanisterHttpResponse,
-        RawEffectivePrincipal, RawMessageId, SubnetKind,
+        RawEffectivePrincipal, RawMessageId, SubnetKind, XNetFault, XNetFaultKind, XNetFaultTarget,
     },
     query_candid, update_candid, DefaultEffectiveCanisterIdError, ErrorCode, IngressStatusResult,
     PocketIc, PocketIcBuilder, RejectCode,
@@ -2118,6 +2118,45 @@ fn create_instance_from_existing() {
     assert_eq!(reply, vec![3, 0, 0, 0]);
 }
 
+#[test]
+fn fork_instance() {
+    let pic = PocketIc::new();
+    let canister_id = deploy_counter_canister(&pic);
+
+    // Bump the counter value and submit another update call without executing it.
+    let reply = call_counter_canister(&pic, canister_id, "write");
+    assert_eq!(reply, vec![1, 0, 0, 0]);
+    let message_id = pic
+        .submit_call(
+            canister_id,
+            Principal::anonymous(),
+            "write",
+            encode_one(()).unwrap(),
+        )
+        .unwrap();
+
+    let fork = pic.fork();
+    assert_ne!(fork.instance_id(), pic.instance_id());
+    assert_eq!(fork.topology(), pic.topology());
+
+    // The pending update call is executed on both instances.
+    let reply = pic.await_call(message_id.clone()).unwrap();
+    assert_eq!(reply, vec![2, 0, 0, 0]);
+    let reply = fork.await_call(message_id).unwrap();
+    assert_eq!(reply, vec![2, 0, 0, 0]);
+
+    // The two instances evolve independently.
+    let reply = call_counter_canister(&fork, canister_id, "write");
+    assert_eq!(reply, vec![3, 0, 0, 0]);
+    let reply = call_counter_canister(&pic, canister_id, "read");
+    assert_eq!(reply, vec![2, 0, 0, 0]);
+
+    // Dropping the fork does not affect the original instance.
+    drop(fork);
+    let reply = call_counter_canister(&pic, canister_id, "write");
+    assert_eq!(reply, vec![3, 0, 0, 0]);
+}
+
 #[test]
 fn ingress_status() {
     let pic = PocketIcBuilder::new()
@@ -2353,6 +2392,146 @@ fn many_intersubnet_calls() {
     }
 }
 
+#[test]
+fn xnet_faults() {
+    let pic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_application_subnet()
+        .build();
+    let subnet_1 = pic.topology().get_app_subnets()[0];
+    let subnet_2 = pic.topology().get_app_subnets()[1];
+    let canister_1 = pic.create_canister_on_subnet(None, None, subnet_1);
+    pic.add_cycles(canister_1, INIT_CYCLES);
+    pic.install_canister(canister_1, test_canister_wasm(), vec![], None);
+    let canister_2 = pic.create_canister_on_subnet(None, None, subnet_2);
+    pic.add_cycles(canister_2, INIT_CYCLES);
+    pic.install_canister(canister_2, test_canister_wasm(), vec![], None);
+
+    let xnet_call = |caller: Principal, callee: Principal| {
+        pic.update_call(
+            caller,
+            Principal::anonymous(),
+            "call_with_large_blob",
+            Encode!(&callee, &42_usize).unwrap(),
+        )
+    };
+
+    // Requests from the first subnet to the second subnet are rejected.
+    pic.set_xnet_faults(vec![XNetFault {
+        target: XNetFaultTarget::Stream {
+            source: subnet_1,
+            destination: subnet_2,
+        },
+        kind: XNetFaultKind::Reject,
+    }]);
+    // The test canister traps if the inter-canister call is rejected.
+    let err = xnet_call(canister_1, canister_2).unwrap_err();
+    assert_eq!(err.error_code, ErrorCode::CanisterCalledTrap);
+    assert!(err.reject_message.contains("SysTransient"));
+    // Requests in the opposite direction are not affected.
+    let reply = xnet_call(canister_2, canister_1).unwrap();
+    assert_eq!(Decode!(&reply, usize).unwrap(), 42);
+
+    // Messages sent by or to the second canister are delayed.
+    pic.set_xnet_faults(vec![XNetFault {
+        target: XNetFaultTarget::Canister(canister_2),
+        kind: XNetFaultKind::Delay { rounds: 10 },
+    }]);
+    let msg_id = pic
+        .submit_call(
+            canister_1,
+            Principal::anonymous(),
+            "call_with_large_blob",
+            Encode!(&canister_2, &42_usize).unwrap(),
+        )
+        .unwrap();
+    for _ in 0..5 {
+        pic.tick();
+    }
+    assert!(pic.ingress_status(msg_id.clone()).is_none());
+    let reply = pic.await_call(msg_id).unwrap();
+    assert_eq!(Decode!(&reply, usize).unwrap(), 42);
+
+    // The second subnet is unavailable.
+    pic.set_xnet_faults(vec![XNetFault {
+        target: XNetFaultTarget::Subnet(subnet_2),
+        kind: XNetFaultKind::Unavailable,
+    }]);
+    let msg_id = pic
+        .submit_call(
+            canister_1,
+            Principal::anonymous(),
+            "call_with_large_blob",
+            Encode!(&canister_2, &42_usize).unwrap(),
+        )
+        .unwrap();
+    for _ in 0..20 {
+        pic.tick();
+    }
+    assert!(pic.ingress_status(msg_id.clone()).is_none());
+
+    // All faults are removed.
+    pic.set_xnet_faults(vec![]);
+    let reply = pic.await_call(msg_id).unwrap();
+    assert_eq!(Decode!(&reply, usize).unwrap(), 42);
+    let reply = xnet_call(canister_1, canister_2).unwrap();
+    assert_eq!(Decode!(&reply, usize).unwrap(), 42);
+}
+
+#[test]
+fn message_profiling() {
+    let pic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_message_profiling()
+        .build();
+    let canister_1 = pic.create_canister();
+    pic.add_cycles(canister_1, INIT_CYCLES);
+    pic.install_canister(canister_1, test_canister_wasm(), vec![], None);
+    let canister_2 = pic.create_canister();
+    pic.add_cycles(canister_2, INIT_CYCLES);
+    pic.install_canister(canister_2, test_canister_wasm(), vec![], None);
+
+    let msg_id = pic
+        .submit_call(
+            canister_1,
+            Principal::anonymous(),
+            "call_with_large_blob",
+            Encode!(&canister_2, &42_usize).unwrap(),
+        )
+        .unwrap();
+    let reply = pic.await_call(msg_id.clone()).unwrap();
+    assert_eq!(Decode!(&reply, usize).unwrap(), 42);
+
+    let profiles = pic.get_message_profiles();
+
+    // The ingress message and the response to its downstream call
+    // are both attributed to the method of the ingress message.
+    let messages = profiles.get_ingress_message(&msg_id);
+    assert_eq!(messages.len(), 2);
+    for message in &messages {
+        assert_eq!(message.canister_id.canister_id, canister_1.as_slice());
+        assert_eq!(message.method_name, "call_with_large_blob");
+        assert!(message.cost.instructions > 0);
+        assert!(message.cost.cycles_by_use_case["Instructions"] > 0);
+    }
+    assert_eq!(messages[0].cost.downstream_calls, 1);
+    assert_eq!(messages[1].cost.downstream_calls, 0);
+
+    let caller = profiles
+        .get_method(canister_1, "call_with_large_blob")
+        .unwrap();
+    assert_eq!(caller.messages, 2);
+    assert_eq!(
+        caller.cost.instructions,
+        messages[0].cost.instructions + messages[1].cost.instructions
+    );
+    assert_eq!(caller.cost.downstream_calls, 1);
+    let callee = profiles.get_method(canister_2, "blob_len").unwrap();
+    assert_eq!(callee.messages, 1);
+    assert_eq!(callee.cost.downstream_calls, 0);
+    assert!(callee.cost.cycles() > 0);
+}
+
 #[test]
 fn test_reject_response_type() {
     let pic = PocketIc::new();
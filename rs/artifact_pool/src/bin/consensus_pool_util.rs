@@ -1,25 +1,32 @@
-use clap::{arg, Arg, Command};
+use clap::{arg, value_parser, Arg, ArgMatches, Command};
 use ic_artifact_pool::{
     certification_pool::CertificationPoolImpl,
     consensus_pool::{PoolSectionOps, UncachedConsensusPoolImpl},
 };
 use ic_config::artifact_pool::ArtifactPoolConfig;
+use ic_consensus_utils::finalization_trace::{BufferedSink, FinalizationTracer};
 use ic_interfaces::consensus_pool::*;
 use ic_logger::{LoggerImpl, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
 use ic_types::{
-    consensus::{certification::CertificationMessage, CatchUpPackage, ConsensusMessageHashable},
+    consensus::{
+        certification::CertificationMessage, Block, CatchUpPackage, ConsensusMessageHashable,
+        HasBlockHash, HasRank,
+    },
+    crypto::CryptoHashOf,
     time::current_time,
-    NodeId, PrincipalId,
+    Height, NodeId, PrincipalId, Time,
 };
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use serde_bytes_repr::{ByteFmtDeserializer, ByteFmtSerializer};
 use serde_json::{Deserializer, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 use std::io::BufRead;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 fn main() {
     let mut app = Command::new("ic-consensus-pool-util")
@@ -49,6 +56,44 @@ fn main() {
                         .num_args(1),
                 ),
         )
+        .subcommand(
+            Command::new("notarizers")
+                .about("List the nodes that notarized blocks at the given height")
+                .arg(
+                    Arg::new("height")
+                        .long("height")
+                        .value_name("HEIGHT")
+                        .help("Height to inspect")
+                        .value_parser(value_parser!(u64))
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(format_arg()),
+        )
+        .subcommand(
+            Command::new("rank-distribution")
+                .about("Count finalized blocks by rank over a height range")
+                .args(height_range_args())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            Command::new("notarization-latency")
+                .about("Show the notarization latency of each height in a range")
+                .args(height_range_args())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            Command::new("finalization-trace")
+                .about("Replay the per-phase finalization latency of each height in a range")
+                .args(height_range_args())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            Command::new("missing-shares")
+                .about("Count the notarization and finalization shares missing per node")
+                .args(height_range_args())
+                .arg(format_arg()),
+        )
         .arg(arg!(<PATH>       "PATH to the consensus pool directory"));
     let mut help = Vec::new();
     app.write_help(&mut help)
@@ -63,6 +108,16 @@ fn main() {
         import(path)
     } else if let Some(matches) = matches.subcommand_matches("export-cup-proto") {
         export_cup_proto(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("notarizers") {
+        notarizers(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("rank-distribution") {
+        rank_distribution(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("notarization-latency") {
+        notarization_latency(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("finalization-trace") {
+        finalization_trace(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("missing-shares") {
+        missing_shares(path, matches)
     } else {
         eprintln!(
             "{}",
@@ -270,3 +325,577 @@ fn export_cup_proto(path: &str, matches: &clap::ArgMatches) {
     file.write_all(&buf)
         .unwrap_or_else(|err| panic!("Cannot write to file {}: {:?}", filename, err));
 }
+
+fn format_arg() -> Arg {
+    Arg::new("format")
+        .short('f')
+        .long("format")
+        .value_name("FORMAT")
+        .help("Output format")
+        .value_parser(["table", "csv"])
+        .default_value("table")
+        .num_args(1)
+}
+
+fn height_range_args() -> [Arg; 2] {
+    [
+        Arg::new("from")
+            .long("from")
+            .value_name("HEIGHT")
+            .help("First height to analyze (defaults to the lowest height in the pool)")
+            .value_parser(value_parser!(u64))
+            .num_args(1),
+        Arg::new("to")
+            .long("to")
+            .value_name("HEIGHT")
+            .help("Last height to analyze (defaults to the highest height in the pool)")
+            .value_parser(value_parser!(u64))
+            .num_args(1),
+    ]
+}
+
+/// Returns the heights selected by the `--from` and `--to` arguments, clamped
+/// to the given range of heights available in the pool.
+fn parse_height_range(matches: &ArgMatches, available: Option<HeightRange>) -> Vec<Height> {
+    let Some(available) = available else {
+        return vec![];
+    };
+    let from = matches
+        .get_one::<u64>("from")
+        .map_or(available.min, |h| Height::from(*h).max(available.min));
+    let to = matches
+        .get_one::<u64>("to")
+        .map_or(available.max, |h| Height::from(*h).min(available.max));
+    (from.get()..=to.get()).map(Height::from).collect()
+}
+
+/// The union of two optional height ranges.
+fn merge_height_ranges(a: Option<HeightRange>, b: Option<HeightRange>) -> Option<HeightRange> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(HeightRange::new(a.min.min(b.min), a.max.max(b.max))),
+        (a, b) => a.or(b),
+    }
+}
+
+fn hash_to_string(hash: &CryptoHashOf<Block>) -> String {
+    hash.get_ref()
+        .0
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn time_to_string(time: Option<Time>) -> String {
+    time.map_or_else(String::new, |time| {
+        time.as_nanos_since_unix_epoch().to_string()
+    })
+}
+
+fn duration_to_string(from: Option<Time>, to: Option<Time>) -> String {
+    match (from, to) {
+        (Some(from), Some(to)) => to.saturating_duration_since(from).as_millis().to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Rows of a summary, printed either as an aligned table or as CSV.
+struct Report {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Report {
+    fn new(header: Vec<&'static str>) -> Self {
+        Self {
+            header,
+            rows: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, row: Vec<String>) {
+        assert_eq!(row.len(), self.header.len(), "Malformed report row");
+        self.rows.push(row);
+    }
+
+    fn print(&self, matches: &ArgMatches) {
+        let format = matches
+            .get_one::<String>("format")
+            .map_or("table", |format| format.as_str());
+        let mut out = std::io::stdout().lock();
+        let result = match format {
+            "csv" => self.write_csv(&mut out),
+            _ => self.write_table(&mut out),
+        };
+        result.unwrap_or_else(|err| panic!("Cannot write to stdout: {:?}", err));
+    }
+
+    fn write_table<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let mut widths: Vec<usize> = self.header.iter().map(|name| name.len()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        let header: Vec<String> = self.header.iter().map(|name| name.to_string()).collect();
+        let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        for row in [&header, &separator].into_iter().chain(&self.rows) {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn write_csv<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let escape = |cell: &str| {
+            if cell.contains([',', '"', '\n']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        };
+        writeln!(out, "{}", self.header.join(","))?;
+        for row in &self.rows {
+            let line = row
+                .iter()
+                .map(|cell| escape(cell))
+                .collect::<Vec<_>>()
+                .join(",");
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists every node that signed a notarization share or an aggregated
+/// notarization at the given height, together with the block it notarized.
+fn notarizers(path: &str, matches: &ArgMatches) {
+    let height = Height::from(
+        *matches
+            .get_one::<u64>("height")
+            .expect("Missing height to inspect"),
+    );
+    let consensus_pool = open_consensus_pool(path, true);
+    notarizers_report(consensus_pool.validated(), height).print(matches);
+}
+
+fn notarizers_report(
+    validated: &dyn PoolSection<ValidatedConsensusArtifact>,
+    height: Height,
+) -> Report {
+    // (block hash, node) -> (signed a share, part of the aggregate, share timestamp)
+    let mut notarizers = BTreeMap::<(String, NodeId), (bool, bool, Option<Time>)>::new();
+    for share in validated.notarization_share().get_by_height(height) {
+        let timestamp = validated.get_timestamp(&share.get_id());
+        let entry = notarizers
+            .entry((hash_to_string(share.block_hash()), share.signature.signer))
+            .or_default();
+        entry.0 = true;
+        entry.2 = timestamp;
+    }
+    for notarization in validated.notarization().get_by_height(height) {
+        let hash = hash_to_string(notarization.block_hash());
+        for signer in &notarization.signature.signers {
+            notarizers.entry((hash.clone(), *signer)).or_default().1 = true;
+        }
+    }
+
+    let mut report = Report::new(vec!["node", "block", "share", "aggregated", "share_time"]);
+    for ((hash, node), (share, aggregated, timestamp)) in notarizers {
+        report.push(vec![
+            node.to_string(),
+            hash,
+            share.to_string(),
+            aggregated.to_string(),
+            time_to_string(timestamp),
+        ]);
+    }
+    report
+}
+
+/// Counts the finalized blocks per rank. Finalized blocks whose proposal is no
+/// longer in the pool are counted under an empty rank.
+fn rank_distribution(path: &str, matches: &ArgMatches) {
+    let consensus_pool = open_consensus_pool(path, true);
+    let validated = consensus_pool.validated();
+    let heights = parse_height_range(matches, validated.finalization().height_range());
+    rank_distribution_report(validated, heights).print(matches);
+}
+
+fn rank_distribution_report(
+    validated: &dyn PoolSection<ValidatedConsensusArtifact>,
+    heights: Vec<Height>,
+) -> Report {
+    let mut ranks = BTreeMap::<Option<u64>, usize>::new();
+    let mut finalized = 0;
+    for height in heights {
+        for finalization in validated.finalization().get_by_height(height) {
+            let rank = validated
+                .block_proposal()
+                .get_by_height(height)
+                .find(|proposal| proposal.block_hash() == finalization.block_hash())
+                .map(|proposal| proposal.rank().0);
+            *ranks.entry(rank).or_default() += 1;
+            finalized += 1;
+        }
+    }
+
+    let mut report = Report::new(vec!["rank", "blocks", "percentage"]);
+    for (rank, count) in ranks {
+        report.push(vec![
+            rank.map_or_else(String::new, |rank| rank.to_string()),
+            count.to_string(),
+            format!("{:.2}", 100.0 * count as f64 / finalized as f64),
+        ]);
+    }
+    report
+}
+
+/// Shows, for every height, how long it took from receiving the first block
+/// proposal to the notarization, and the time elapsed since the notarization of
+/// the previous height. All times are the local pool insertion timestamps, in
+/// milliseconds.
+fn notarization_latency(path: &str, matches: &ArgMatches) {
+    let consensus_pool = open_consensus_pool(path, true);
+    let validated = consensus_pool.validated();
+    let heights = parse_height_range(matches, validated.notarization().height_range());
+    notarization_latency_report(validated, heights).print(matches);
+}
+
+fn notarization_latency_report(
+    validated: &dyn PoolSection<ValidatedConsensusArtifact>,
+    heights: Vec<Height>,
+) -> Report {
+    let notarized_at = |height: Height| {
+        validated
+            .notarization()
+            .get_by_height(height)
+            .filter_map(|notarization| validated.get_timestamp(&notarization.get_id()))
+            .min()
+    };
+    let mut report = Report::new(vec![
+        "height",
+        "proposals",
+        "first_proposal_time",
+        "notarization_time",
+        "proposal_to_notarization_ms",
+        "since_previous_notarization_ms",
+    ]);
+    let mut previous = heights
+        .first()
+        .and_then(|height| height.get().checked_sub(1))
+        .and_then(|height| notarized_at(Height::from(height)));
+    for height in heights {
+        let proposal_times: Vec<Time> = validated
+            .block_proposal()
+            .get_by_height(height)
+            .filter_map(|proposal| validated.get_timestamp(&proposal.get_id()))
+            .collect();
+        let first_proposal = proposal_times.iter().min().copied();
+        let notarized = notarized_at(height);
+        report.push(vec![
+            height.to_string(),
+            proposal_times.len().to_string(),
+            time_to_string(first_proposal),
+            time_to_string(notarized),
+            duration_to_string(first_proposal, notarized),
+            duration_to_string(previous, notarized),
+        ]);
+        previous = notarized;
+    }
+    report
+}
+
+/// Replays the consensus phases of every height from the local pool insertion
+/// timestamps, as they are traced by a running replica. Phases after
+/// finalization are not part of the pool and therefore not shown.
+fn finalization_trace(path: &str, matches: &ArgMatches) {
+    let consensus_pool = open_consensus_pool(path, true);
+    let validated = consensus_pool.validated();
+    let heights = parse_height_range(matches, validated.notarization().height_range());
+    let Some(first) = heights.first().copied() else {
+        return;
+    };
+
+    let sink = Arc::new(BufferedSink::default());
+    let tracer = FinalizationTracer::new(sink.clone());
+    // The random beacon phase starts with the notarization of the previous height.
+    let previous = first.get().checked_sub(1).map(Height::from);
+    tracer.record_from_pool(validated, previous.into_iter().chain(heights));
+
+    let mut report = Report::new(vec!["height", "phase", "start_time", "duration_ms"]);
+    for span in sink.take().into_iter().filter(|span| span.height >= first) {
+        report.push(vec![
+            span.height.to_string(),
+            span.phase.as_str().to_string(),
+            time_to_string(Some(span.start)),
+            span.duration().as_millis().to_string(),
+        ]);
+    }
+    report.print(matches);
+}
+
+/// Counts, for every node, the heights at which neither a share nor an
+/// aggregated signature of the node was found. The set of nodes is derived
+/// from all signers seen in the analyzed range.
+fn missing_shares(path: &str, matches: &ArgMatches) {
+    let consensus_pool = open_consensus_pool(path, true);
+    let validated = consensus_pool.validated();
+    let heights = parse_height_range(
+        matches,
+        merge_height_ranges(
+            validated.notarization_share().height_range(),
+            validated.notarization().height_range(),
+        ),
+    );
+    missing_shares_report(validated, heights).print(matches);
+}
+
+fn missing_shares_report(
+    validated: &dyn PoolSection<ValidatedConsensusArtifact>,
+    heights: Vec<Height>,
+) -> Report {
+    let mut nodes = BTreeSet::new();
+    // Per node: heights with a notarization resp. finalization contribution.
+    let mut notarized = BTreeMap::<NodeId, usize>::new();
+    let mut finalized = BTreeMap::<NodeId, usize>::new();
+    for height in &heights {
+        let mut notarizers: BTreeSet<NodeId> = validated
+            .notarization_share()
+            .get_by_height(*height)
+            .map(|share| share.signature.signer)
+            .collect();
+        for notarization in validated.notarization().get_by_height(*height) {
+            notarizers.extend(notarization.signature.signers);
+        }
+        let mut finalizers: BTreeSet<NodeId> = validated
+            .finalization_share()
+            .get_by_height(*height)
+            .map(|share| share.signature.signer)
+            .collect();
+        for finalization in validated.finalization().get_by_height(*height) {
+            finalizers.extend(finalization.signature.signers);
+        }
+        for node in &notarizers {
+            *notarized.entry(*node).or_default() += 1;
+        }
+        for node in &finalizers {
+            *finalized.entry(*node).or_default() += 1;
+        }
+        nodes.extend(notarizers);
+        nodes.extend(finalizers);
+    }
+
+    let mut report = Report::new(vec![
+        "node",
+        "heights",
+        "missing_notarization_shares",
+        "missing_finalization_shares",
+    ]);
+    for node in nodes {
+        let count = |map: &BTreeMap<NodeId, usize>| map.get(&node).copied().unwrap_or_default();
+        report.push(vec![
+            node.to_string(),
+            heights.len().to_string(),
+            (heights.len() - count(&notarized)).to_string(),
+            (heights.len() - count(&finalized)).to_string(),
+        ]);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_test_utilities::artifact_pool_config::with_test_pool_config;
+    use ic_test_utilities_consensus::{fake::*, make_genesis};
+    use ic_test_utilities_types::ids::node_test_id;
+    use ic_types::{
+        consensus::{
+            dkg::Summary, BlockProposal, ConsensusMessage, Finalization, FinalizationContent,
+            FinalizationShare, Notarization, NotarizationContent, NotarizationShare, Rank,
+        },
+        crypto::crypto_hash,
+        time::UNIX_EPOCH,
+    };
+    use std::time::Duration;
+
+    fn at(millis: u64) -> Time {
+        UNIX_EPOCH + Duration::from_millis(millis)
+    }
+
+    fn nanos(millis: u64) -> String {
+        time_to_string(Some(at(millis)))
+    }
+
+    fn block(parent: &Block, rank: u64) -> Block {
+        let mut block = Block::from_parent(parent);
+        block.rank = Rank(rank);
+        block
+    }
+
+    fn notarization(block: &Block, signers: &[u64]) -> ConsensusMessage {
+        let mut notarization =
+            Notarization::fake(NotarizationContent::new(block.height, crypto_hash(block)));
+        notarization.signature.signers = signers.iter().map(|id| node_test_id(*id)).collect();
+        notarization.into_message()
+    }
+
+    fn finalization(block: &Block, signers: &[u64]) -> ConsensusMessage {
+        let mut finalization =
+            Finalization::fake(FinalizationContent::new(block.height, crypto_hash(block)));
+        finalization.signature.signers = signers.iter().map(|id| node_test_id(*id)).collect();
+        finalization.into_message()
+    }
+
+    /// Runs `test` against a pool of 4 nodes with the following artifacts:
+    /// - height 1: a rank 0 proposal, notarization shares and finalization
+    ///   shares of nodes 0, 1, 2, and a notarization signed by them.
+    /// - height 2: a rank 0 and a rank 1 proposal, a notarization share of
+    ///   node 1, a notarization of the rank 1 block signed by nodes 1, 2, 3,
+    ///   and finalization shares of nodes 1, 2.
+    /// - height 3: a finalization signed by node 3 whose proposal is missing.
+    fn with_test_pool(test: impl FnOnce(&dyn PoolSection<ValidatedConsensusArtifact>)) {
+        with_test_pool_config(|config| {
+            let mut pool = UncachedConsensusPoolImpl::new(config, no_op_logger());
+            let genesis = make_genesis(Summary::fake()).content.block.as_ref().clone();
+            let b1 = block(&genesis, 0);
+            let (b2_0, b2_1) = (block(&b1, 0), block(&b1, 1));
+            let b3 = block(&b2_1, 0);
+
+            let mut artifacts = vec![
+                (
+                    BlockProposal::fake(b1.clone(), node_test_id(0)).into_message(),
+                    100,
+                ),
+                (notarization(&b1, &[0, 1, 2]), 300),
+                (
+                    BlockProposal::fake(b2_0.clone(), node_test_id(0)).into_message(),
+                    400,
+                ),
+                (
+                    BlockProposal::fake(b2_1.clone(), node_test_id(1)).into_message(),
+                    450,
+                ),
+                (
+                    NotarizationShare::fake(&b2_1, node_test_id(1)).into_message(),
+                    600,
+                ),
+                (notarization(&b2_1, &[1, 2, 3]), 700),
+                (finalization(&b3, &[3]), 1000),
+            ];
+            for node in 0..3 {
+                artifacts.push((
+                    NotarizationShare::fake(&b1, node_test_id(node)).into_message(),
+                    200,
+                ));
+                artifacts.push((
+                    FinalizationShare::fake(&b1, node_test_id(node)).into_message(),
+                    350,
+                ));
+            }
+            for node in 1..3 {
+                artifacts.push((
+                    FinalizationShare::fake(&b2_1, node_test_id(node)).into_message(),
+                    800,
+                ));
+            }
+            artifacts.push((finalization(&b1, &[0, 1, 2]), 400));
+            artifacts.push((finalization(&b2_1, &[]), 900));
+
+            let mut ops = PoolSectionOps::new();
+            for (msg, millis) in artifacts {
+                ops.insert(ValidatedConsensusArtifact {
+                    msg,
+                    timestamp: at(millis),
+                });
+            }
+            pool.validated.mutate(ops);
+            test(pool.validated());
+        })
+    }
+
+    fn heights(range: std::ops::RangeInclusive<u64>) -> Vec<Height> {
+        range.map(Height::from).collect()
+    }
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    #[test]
+    fn test_notarizers_report_merges_shares_and_aggregates() {
+        with_test_pool(|validated| {
+            let rows = notarizers_report(validated, Height::from(2)).rows;
+            let node = |id: u64| node_test_id(id).to_string();
+            let hash = |row: &Vec<String>| row[1].clone();
+            assert_eq!(rows.len(), 3);
+            assert!(rows.iter().all(|row| hash(row) == hash(&rows[0])));
+            let mut rows: Vec<_> = rows
+                .into_iter()
+                .map(|row| (row[0].clone(), row[2..].to_vec()))
+                .collect();
+            rows.sort();
+            let mut expected = vec![
+                (node(1), row(&["true", "true", &nanos(600)])),
+                (node(2), row(&["false", "true", ""])),
+                (node(3), row(&["false", "true", ""])),
+            ];
+            expected.sort();
+            assert_eq!(rows, expected);
+        });
+    }
+
+    #[test]
+    fn test_rank_distribution_report_counts_finalized_ranks() {
+        with_test_pool(|validated| {
+            assert_eq!(
+                rank_distribution_report(validated, heights(1..=3)).rows,
+                vec![
+                    row(&["", "1", "33.33"]),
+                    row(&["0", "1", "33.33"]),
+                    row(&["1", "1", "33.33"]),
+                ]
+            );
+            assert_eq!(
+                rank_distribution_report(validated, heights(2..=2)).rows,
+                vec![row(&["1", "1", "100.00"])]
+            );
+        });
+    }
+
+    #[test]
+    fn test_notarization_latency_report() {
+        with_test_pool(|validated| {
+            assert_eq!(
+                notarization_latency_report(validated, heights(1..=2)).rows,
+                vec![
+                    row(&["1", "1", &nanos(100), &nanos(300), "200", ""]),
+                    row(&["2", "2", &nanos(400), &nanos(700), "300", "400"]),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_missing_shares_report_counts_heights_without_contribution() {
+        with_test_pool(|validated| {
+            let node = |id: u64| node_test_id(id).to_string();
+            let mut rows = missing_shares_report(validated, heights(1..=3)).rows;
+            rows.sort();
+            let mut expected = vec![
+                row(&[&node(0), "3", "2", "2"]),
+                row(&[&node(1), "3", "1", "1"]),
+                row(&[&node(2), "3", "1", "1"]),
+                row(&[&node(3), "3", "2", "2"]),
+            ];
+            expected.sort();
+            assert_eq!(rows, expected);
+        });
+    }
+}
@@ -108,6 +108,7 @@ mod tests {
                 allocated_guaranteed_response_message_bytes: NumBytes::new(2000),
                 instance_stats: InstanceStats::default(),
                 system_api_call_counters: SystemApiCallCounters::default(),
+                wasm_profile: None,
             },
             state: StateModifications {
                 execution_state_modifications: Some(ExecutionStateModifications {
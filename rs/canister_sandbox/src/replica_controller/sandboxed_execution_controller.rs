@@ -13,7 +13,8 @@ use ic_embedders::wasm_executor::{
     PausedWasmExecution, SliceExecutionOutput, WasmExecutionResult, WasmExecutor,
 };
 use ic_embedders::{
-    wasm_utils::WasmImportsDetails, CompilationCache, CompilationResult, WasmExecutionInput,
+    wasm_utils::{profiling::write_wasm_profile, WasmImportsDetails},
+    CompilationCache, CompilationResult, WasmExecutionInput,
 };
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult, InstanceStats};
 use ic_interfaces_state_manager::StateReader;
@@ -830,6 +831,9 @@ pub struct SandboxedExecutionController {
     max_sandbox_idle_time: Duration,
     max_sandboxes_rss: NumBytes,
     trace_execution: FlagStatus,
+    /// The directory to which the Wasm profiles returned by the sandbox
+    /// processes are written.
+    wasm_profiling_output_dir: Option<PathBuf>,
     logger: ReplicaLogger,
     /// Executable and arguments to be passed to `canister_sandbox` which are
     /// the same for all canisters.
@@ -1280,6 +1284,7 @@ impl SandboxedExecutionController {
         let max_sandbox_idle_time = embedder_config.max_sandbox_idle_time;
         let max_sandboxes_rss = embedder_config.max_sandboxes_rss;
         let trace_execution = embedder_config.trace_execution;
+        let wasm_profiling_output_dir = embedder_config.wasm_profiling_output_dir.clone();
         let sandbox_exec_argv =
             create_sandbox_argv(embedder_config).expect("No canister_sandbox binary found");
         let backends = Arc::new(Mutex::new(HashMap::new()));
@@ -1331,6 +1336,7 @@ impl SandboxedExecutionController {
             max_sandbox_idle_time,
             max_sandboxes_rss,
             trace_execution,
+            wasm_profiling_output_dir,
             logger,
             sandbox_exec_argv,
             metrics,
@@ -1664,6 +1670,10 @@ impl SandboxedExecutionController {
             }
         };
 
+        if let Some(output_dir) = &self.wasm_profiling_output_dir {
+            write_wasm_profile(&self.logger, output_dir, canister_id, &mut exec_output.wasm);
+        }
+
         // If sandbox is compromised this value could be larger than the initial limit.
         if exec_output.wasm.num_instructions_left > message_instruction_limit {
             exec_output.wasm.num_instructions_left = message_instruction_limit;
@@ -136,6 +136,7 @@ impl Execution {
                 allocated_guaranteed_response_message_bytes,
                 instance_stats,
                 system_api_call_counters,
+                wasm_profile,
             },
             deltas,
             mut instance_or_system_api,
@@ -208,6 +209,7 @@ impl Execution {
                     num_instructions_left,
                     instance_stats,
                     system_api_call_counters,
+                    wasm_profile,
                 };
                 self.sandbox_manager.controller.execution_finished(
                     protocol::ctlsvc::ExecutionFinishedRequest {
@@ -239,6 +241,7 @@ impl Execution {
                     allocated_guaranteed_response_message_bytes,
                     instance_stats,
                     system_api_call_counters,
+                    wasm_profile,
                 };
 
                 self.sandbox_manager.controller.execution_finished(
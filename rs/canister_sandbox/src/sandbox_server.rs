@@ -168,7 +168,7 @@ mod tests {
     use ic_interfaces::execution_environment::{ExecutionMode, SubnetAvailableMemory};
     use ic_limits::SMALL_APP_SUBNET_MAX_SIZE;
     use ic_logger::replica_logger::no_op_logger;
-    use ic_management_canister_types_private::Global;
+    use ic_management_canister_types_private::{CanisterLogLevel, Global};
     use ic_registry_subnet_type::SubnetType;
     use ic_replicated_state::{
         MessageMemoryUsage, NetworkTopology, NumWasmPages, PageIndex, PageMap,
@@ -179,7 +179,8 @@ mod tests {
         messages::{CallContextId, RequestMetadata},
         methods::{FuncRef, WasmMethod},
         time::Time,
-        CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
+        CanisterTimer, CanisterTimers, ComputeAllocation, Cycles, MemoryAllocation, NumBytes,
+        NumInstructions,
     };
     use ic_wasm_types::BinaryEncodedWasm;
     use mockall::*;
@@ -242,11 +243,13 @@ mod tests {
             SMALL_APP_SUBNET_MAX_SIZE,
             SchedulerConfig::application_subnet().dirty_page_overhead,
             CanisterTimer::Inactive,
+            CanisterTimers::default(),
             0,
             BTreeSet::from([controller]),
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
             caller,
             0,
+            CanisterLogLevel::default(),
             IS_WASM64_EXECUTION,
             NetworkTopology::default(),
         )
@@ -23,8 +23,8 @@ use ic_test_utilities_types::ids::{canister_test_id, subnet_test_id};
 use ic_types::{
     crypto::CryptoHash,
     messages::{
-        CallbackId, Payload, RejectContext, Request, RequestMetadata, RequestOrResponse, Response,
-        NO_DEADLINE,
+        CallbackId, Payload, PayloadChunk, RejectContext, Request, RequestMetadata,
+        RequestOrResponse, Response, NO_DEADLINE,
     },
     nominal_cycles::NominalCycles,
     time::CoarseTime,
@@ -527,6 +527,89 @@ fn canonical_encoding_request_v18_plus() {
     }
 }
 
+/// Canonical CBOR encoding of:
+///
+/// ```no_run
+/// RequestOrResponse::Request(
+///     Request {
+///         receiver: canister_test_id(1),
+///         sender: canister_test_id(2),
+///         sender_reply_callback: CallbackId::from(3),
+///         payment: Cycles::new(4),
+///         method_name: "test".to_string(),
+///         method_payload: vec![6],
+///         metadata: RequestMetadata::new(13, Time::from_nanos_since_unix_epoch(101))
+///             .with_payload_chunk(PayloadChunk { index: 1, count: 3 }),
+///         deadline: NO_DEADLINE,
+///     }
+/// )
+/// ```
+///
+/// Expected:
+///
+/// ```text
+/// A1                            # map(1)
+///    00                         # field_index(RequestOrResponse::request)
+///    A7                         # map(7)
+///       00                      # field_index(Request::receiver)
+///       4A                      # bytes(10)
+///          00000000000000010101 # "\x00\x00\x00\x00\x00\x00\x00\x01\x01\x01"
+///       01                      # field_index(Request::sender)
+///       4A                      # bytes(10)
+///          00000000000000020101 # "\x00\x00\x00\x00\x00\x00\x00\x02\x01\x01"
+///       02                      # field_index(Request::sender_reply_callback)
+///       03                      # unsigned(3)
+///       03                      # field_index(Request::payment)
+///       A1                      # map(1)
+///          00                   # field_index(Funds::cycles)
+///          A1                   # map(1)
+///             00                # field_index(Cycles::raw)
+///             04                # unsigned(4)
+///       04                      # field_index(Request::method_name)
+///       64                      # text(4)
+///          74657374             # "test"
+///       05                      # field_index(Request::method_payload)
+///       41                      # bytes(1)
+///          06                   # "\x06"
+///       07                      # field_index(Request::metadata)
+///       A3                      # map(3)
+///          00                   # field_index(RequestMetadata::call_tree_depth)
+///          0D                   # unsigned(13)
+///          01                   # field_index(RequestMetadata::call_tree_start_time)
+///          18 65                # unsigned(101)
+///          03                   # field_index(RequestMetadata::payload_chunk)
+///          A2                   # map(2)
+///             00                # field_index(PayloadChunk::index)
+///             01                # unsigned(1)
+///             01                # field_index(PayloadChunk::count)
+///             03                # unsigned(3)
+/// ```
+/// Used http://cbor.me/ for printing the human friendly output.
+#[test]
+fn canonical_encoding_request_with_payload_chunk_v20_plus() {
+    for certification_version in
+        all_supported_versions().filter(|v| v >= &CertificationVersion::V20)
+    {
+        let request: RequestOrResponse = Request {
+            receiver: canister_test_id(1),
+            sender: canister_test_id(2),
+            sender_reply_callback: CallbackId::from(3),
+            payment: Cycles::new(4),
+            method_name: "test".to_string(),
+            method_payload: vec![6],
+            metadata: RequestMetadata::new(13, Time::from_nanos_since_unix_epoch(101))
+                .with_payload_chunk(PayloadChunk { index: 1, count: 3 }),
+            deadline: NO_DEADLINE,
+        }
+        .into();
+
+        assert_eq!(
+            "A1 00 A7 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06 07 A3 00 0D 01 18 65 03 A2 00 01 01 03",
+            as_hex(&encode_message(&request, certification_version))
+        );
+    }
+}
+
 /// Canonical CBOR encoding of:
 ///
 /// ```no_run
@@ -107,6 +107,16 @@ pub struct RequestMetadata {
     pub call_tree_start_time_u64: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub call_subtree_deadline_u64: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_chunk: Option<PayloadChunk>,
+}
+
+/// Canonical representation of `ic_types::messages::PayloadChunk`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PayloadChunk {
+    pub index: u32,
+    pub count: u32,
 }
 
 /// Canonical representation of `ic_types::messages::Request`.
@@ -489,16 +499,25 @@ impl From<&ic_types::messages::RequestMetadata> for RequestMetadata {
                 metadata.call_tree_start_time().as_nanos_since_unix_epoch(),
             ),
             call_subtree_deadline_u64: None,
+            payload_chunk: metadata.payload_chunk().map(|chunk| PayloadChunk {
+                index: chunk.index,
+                count: chunk.count,
+            }),
         }
     }
 }
 
 impl From<RequestMetadata> for ic_types::messages::RequestMetadata {
     fn from(metadata: RequestMetadata) -> Self {
-        ic_types::messages::RequestMetadata::new(
+        let request_metadata = ic_types::messages::RequestMetadata::new(
             metadata.call_tree_depth.unwrap_or(0),
             Time::from_nanos_since_unix_epoch(metadata.call_tree_start_time_u64.unwrap_or(0)),
-        )
+        );
+        match metadata.payload_chunk {
+            Some(PayloadChunk { index, count }) => request_metadata
+                .with_payload_chunk(ic_types::messages::PayloadChunk { index, count }),
+            None => request_metadata,
+        }
     }
 }
 
@@ -510,6 +529,15 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for Request {
         debug_assert!(
             request.deadline == NO_DEADLINE || certification_version >= CertificationVersion::V18
         );
+        // Replicas with certification version < 20 should not send chunked calls.
+        debug_assert!(
+            request.metadata.payload_chunk().is_none()
+                || certification_version >= CertificationVersion::V20
+        );
+        let mut metadata: RequestMetadata = (&request.metadata).into();
+        if certification_version < CertificationVersion::V20 {
+            metadata.payload_chunk = None;
+        }
 
         let funds = Funds {
             cycles: (&request.payment, certification_version).into(),
@@ -524,7 +552,7 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for Request {
             method_name: request.method_name.clone(),
             method_payload: request.method_payload.clone(),
             cycles_payment: None,
-            metadata: Some((&request.metadata).into()),
+            metadata: Some(metadata),
             deadline: request.deadline.as_secs_since_unix_epoch(),
         }
     }
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
 use ic_base_types::NumBytes;
@@ -69,6 +70,10 @@ pub(crate) const DIRTY_PAGE_COPY_OVERHEAD: NumInstructions = NumInstructions::ne
 /// The overhead for dirty pages in Wasm64.
 pub const WASM64_DIRTY_PAGE_OVERHEAD_MULTIPLIER: u64 = 4;
 
+/// The default number of instructions between two call-stack samples of the
+/// Wasm profiler.
+const DEFAULT_WASM_PROFILING_SAMPLE_INTERVAL: NumInstructions = NumInstructions::new(10_000);
+
 #[allow(non_upper_case_globals)]
 const KiB: u64 = 1024;
 #[allow(non_upper_case_globals)]
@@ -156,6 +161,34 @@ pub struct FeatureFlags {
     pub best_effort_responses: BestEffortResponsesFeature,
     /// Collect a backtrace from the canister when it panics.
     pub canister_backtrace: FlagStatus,
+    /// Instrument canister code to record call-stack samples (see
+    /// `Config::wasm_profiling_sample_interval`). This is only meant for
+    /// local tooling and must not be enabled on replicated subnets.
+    pub wasm_profiling: FlagStatus,
+    /// Indicates whether the Wasm exception-handling proposal (`try_table`,
+    /// `throw` and `throw_ref`) is supported by validation and
+    /// instrumentation. The legacy exception-handling instructions are
+    /// rejected regardless of this flag. Wasmtime must support the proposal
+    /// as well before this flag can be enabled.
+    pub wasm_exceptions: FlagStatus,
+    /// Accept Wasm components that import the `ic:canister/ic0` WIT
+    /// interface. Such components are translated to core modules before
+    /// validation.
+    pub wasm_components: FlagStatus,
+    /// Indicates whether `ic0.call_with_chunked_payload`,
+    /// `ic0.msg_arg_chunk_index` and `ic0.msg_arg_chunk_count` are available.
+    /// Until certification version 20 is current, chunked calls can only be
+    /// made to canisters on the same subnet: the stream builder rejects
+    /// chunked requests to other subnets with `DestinationInvalid`.
+    pub chunked_call_payloads: FlagStatus,
+    /// Indicates whether `ic0.timer_set`, `ic0.timer_name_size` and
+    /// `ic0.timer_name_copy` are available. Expired named timers are executed
+    /// via `canister_global_timer` and take precedence over the global timer,
+    /// which is deferred to a later round while a named timer is due.
+    pub named_timers: FlagStatus,
+    /// Indicates whether `ic0.log` is available, i.e., whether canisters can
+    /// write log records with a level and fields.
+    pub canister_log_levels: FlagStatus,
 }
 
 impl Default for FeatureFlags {
@@ -166,6 +199,12 @@ impl Default for FeatureFlags {
             wasm64: FlagStatus::Enabled,
             best_effort_responses: BestEffortResponsesFeature::ApplicationSubnetsOnly,
             canister_backtrace: FlagStatus::Enabled,
+            wasm_profiling: FlagStatus::Disabled,
+            wasm_exceptions: FlagStatus::Disabled,
+            wasm_components: FlagStatus::Disabled,
+            chunked_call_payloads: FlagStatus::Disabled,
+            named_timers: FlagStatus::Disabled,
+            canister_log_levels: FlagStatus::Disabled,
         }
     }
 }
@@ -283,6 +322,16 @@ pub struct Config {
 
     /// The maximum size of the stable memory.
     pub max_stable_memory_size: NumBytes,
+
+    /// If the `wasm_profiling` feature flag is enabled, then a call-stack
+    /// sample is recorded every time this many instructions are executed.
+    pub wasm_profiling_sample_interval: NumInstructions,
+
+    /// The directory to which the replica writes the call-stack samples of
+    /// each canister in the collapsed-stack format (the sandbox processes
+    /// return their samples with the execution output). No samples are
+    /// written if this is not set.
+    pub wasm_profiling_output_dir: Option<PathBuf>,
 }
 
 impl Config {
@@ -322,6 +371,8 @@ impl Config {
             max_wasm64_memory_size: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
             max_stable_memory_size: NumBytes::new(MAX_STABLE_MEMORY_IN_BYTES),
             wasm64_dirty_page_overhead_multiplier: WASM64_DIRTY_PAGE_OVERHEAD_MULTIPLIER,
+            wasm_profiling_sample_interval: DEFAULT_WASM_PROFILING_SAMPLE_INTERVAL,
+            wasm_profiling_output_dir: None,
         }
     }
 }
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::default::Default;
+use std::{collections::BTreeMap, default::Default, num::NonZeroU64};
 
 /// The transport format specified in the ic.json
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
@@ -14,6 +14,9 @@ pub struct TransportConfig {
     /// Transport creates 'max_streams' logical streams/channels between two peers.
     /// Channel ids should be within [0..max_streams).
     pub max_streams: usize,
+
+    /// Limits on the outgoing bandwidth of the transport.
+    pub traffic_shaping: TrafficShapingConfig,
 }
 
 impl Default for TransportConfig {
@@ -23,6 +26,66 @@ impl Default for TransportConfig {
             node_ip: String::default(),
             listening_port: u16::default(),
             max_streams: 1,
+            traffic_shaping: TrafficShapingConfig::default(),
         }
     }
 }
+
+/// A token bucket that is refilled at `bytes_per_second` and holds at most
+/// `burst_bytes`. A rate of zero is rejected when the config is loaded since
+/// the bucket would never be refilled.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
+pub struct TokenBucketConfig {
+    pub bytes_per_second: NonZeroU64,
+
+    pub burst_bytes: u64,
+}
+
+/// Shaping of the outgoing traffic of every peer connection. Nothing is shaped
+/// by default.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TrafficShapingConfig {
+    /// Limits the total outgoing traffic towards each peer. Messages sent with
+    /// high priority, e.g. consensus artifacts, are accounted for but never
+    /// held back by this limit.
+    pub per_peer: Option<TokenBucketConfig>,
+
+    /// Limits the outgoing traffic towards each peer per route, keyed by the
+    /// URI path of the route, e.g. `/state-sync/chunk`.
+    pub per_route: BTreeMap<String, TokenBucketConfig>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traffic_shaping_config_is_parsed() {
+        let config: TransportConfig = json5::from_str(
+            r#"{
+                traffic_shaping: {
+                    per_peer: { bytes_per_second: 1000, burst_bytes: 500 },
+                    per_route: { "/state-sync/chunk": { bytes_per_second: 100, burst_bytes: 100 } },
+                },
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.traffic_shaping.per_peer,
+            Some(TokenBucketConfig {
+                bytes_per_second: NonZeroU64::new(1000).unwrap(),
+                burst_bytes: 500,
+            })
+        );
+        assert_eq!(config.traffic_shaping.per_route.len(), 1);
+    }
+
+    #[test]
+    fn zero_rate_is_rejected() {
+        let result = json5::from_str::<TransportConfig>(
+            r#"{ traffic_shaping: { per_peer: { bytes_per_second: 0, burst_bytes: 500 } } }"#,
+        );
+        assert!(result.is_err());
+    }
+}
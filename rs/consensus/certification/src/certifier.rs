@@ -1,12 +1,16 @@
 use crate::{CertificationCrypto, VerifierImpl};
 use ic_consensus_utils::{
-    active_high_threshold_nidkg_id, aggregate, bouncer_metrics::BouncerMetrics,
-    membership::Membership, registry_version_at_height, MINIMUM_CHAIN_LENGTH,
+    active_high_threshold_nidkg_id, aggregate,
+    bouncer_metrics::BouncerMetrics,
+    finalization_trace::{FinalizationPhase, FinalizationTracer},
+    membership::Membership,
+    registry_version_at_height, MINIMUM_CHAIN_LENGTH,
 };
 use ic_interfaces::{
     certification::{CertificationPool, ChangeAction, Mutations, Verifier, VerifierError},
     consensus_pool::ConsensusPoolCache,
     p2p::consensus::{Bouncer, BouncerFactory, BouncerValue, PoolMutationsProducer},
+    time_source::system_time_now,
     validation::ValidationError,
 };
 use ic_interfaces_registry::RegistryClient;
@@ -44,6 +48,7 @@ pub struct CertifierImpl {
     crypto: Arc<dyn CertificationCrypto>,
     state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
     consensus_pool_cache: Arc<dyn ConsensusPoolCache>,
+    finalization_tracer: Arc<FinalizationTracer>,
     metrics: CertifierMetrics,
     /// The highest height that has been purged. Used to avoid redundant purging.
     highest_purged_height: RefCell<Height>,
@@ -136,6 +141,7 @@ impl<T: CertificationPool> PoolMutationsProducer<T> for CertifierImpl {
         // it's dropped.
         let _timer = self.metrics.execution_time.start_timer();
         let start = Instant::now();
+        let now = system_time_now();
 
         // First, we iterate over requested heights and deliver certifications to the
         // state manager, if they're available or return those hashes which do not have
@@ -144,6 +150,13 @@ impl<T: CertificationPool> PoolMutationsProducer<T> for CertifierImpl {
             .state_manager
             .list_state_hashes_to_certify()
             .into_iter()
+            // The state manager does not expose when a state became ready to be
+            // certified, so the execution milestone is the first time the certifier
+            // sees the state hash, i.e. it includes up to one polling interval.
+            .inspect(|(height, _)| {
+                self.finalization_tracer
+                    .record(*height, FinalizationPhase::Execution, now)
+            })
             .filter_map(
                 |(height, hash)| match certification_pool.certification_at_height(height) {
                     // if we have a valid certification, deliver it to the state manager and skip
@@ -154,6 +167,11 @@ impl<T: CertificationPool> PoolMutationsProducer<T> for CertifierImpl {
                         self.state_manager
                             .deliver_state_certification(certification);
                         self.metrics.last_certified_height.set(height.get() as i64);
+                        self.finalization_tracer.record(
+                            height,
+                            FinalizationPhase::Certification,
+                            now,
+                        );
                         debug!(&self.log, "Delivered certification for height {}", height);
 
                         self.max_certified_height_tx.send_if_modified(|h| {
@@ -264,6 +282,7 @@ impl CertifierImpl {
         crypto: Arc<dyn CertificationCrypto>,
         state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
         consensus_pool_cache: Arc<dyn ConsensusPoolCache>,
+        finalization_tracer: Arc<FinalizationTracer>,
         metrics_registry: MetricsRegistry,
         log: ReplicaLogger,
         max_certified_height_tx: watch::Sender<Height>,
@@ -280,6 +299,7 @@ impl CertifierImpl {
             crypto,
             state_manager,
             consensus_pool_cache,
+            finalization_tracer,
             metrics: CertifierMetrics {
                 shares_created: metrics_registry.int_counter(
                     "certification_shares_created",
@@ -715,6 +735,7 @@ mod tests {
                     crypto,
                     state_manager.clone(),
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     metrics_registry.clone(),
                     log,
                     max_certified_height_tx,
@@ -779,6 +800,7 @@ mod tests {
                     crypto,
                     state_manager.clone(),
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     metrics_registry,
                     log,
                     max_certified_height_tx,
@@ -914,6 +936,7 @@ mod tests {
                     crypto,
                     state_manager.clone(),
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     metrics_registry,
                     log,
                     max_certified_height_tx,
@@ -993,6 +1016,7 @@ mod tests {
                     crypto,
                     state_manager.clone(),
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     metrics_registry,
                     log,
                     max_certified_height_tx,
@@ -1064,6 +1088,7 @@ mod tests {
                     crypto,
                     state_manager,
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     metrics_registry,
                     log,
                     max_certified_height_tx,
@@ -1130,6 +1155,7 @@ mod tests {
                     crypto,
                     state_manager.clone(),
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     metrics_registry.clone(),
                     log,
                     max_certified_height_tx,
@@ -1196,6 +1222,7 @@ mod tests {
                     crypto,
                     state_manager,
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     MetricsRegistry::new(),
                     log,
                     max_certified_height_tx,
@@ -1257,6 +1284,7 @@ mod tests {
                     crypto,
                     state_manager.clone(),
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     metrics_registry,
                     log,
                     max_certified_height_tx,
@@ -1367,6 +1395,7 @@ mod tests {
                     crypto,
                     state_manager.clone(),
                     pool.get_cache(),
+                    Arc::new(FinalizationTracer::default()),
                     metrics_registry,
                     log,
                     max_certified_height_tx,
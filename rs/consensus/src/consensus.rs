@@ -3,6 +3,7 @@
 
 pub mod batch_delivery;
 pub(crate) mod block_maker;
+mod block_rate;
 pub mod bounds;
 mod catchup_package_maker;
 mod finalizer;
@@ -30,7 +31,8 @@ use crate::consensus::{
 };
 use ic_consensus_dkg::DkgKeyManager;
 use ic_consensus_utils::{
-    bouncer_metrics::BouncerMetrics, crypto::ConsensusCrypto, get_notarization_delay_settings,
+    bouncer_metrics::BouncerMetrics, crypto::ConsensusCrypto,
+    finalization_trace::FinalizationTracer, get_notarization_delay_settings,
     membership::Membership, pool_reader::PoolReader, RoundRobin,
 };
 use ic_interfaces::{
@@ -55,7 +57,7 @@ use ic_replicated_state::ReplicatedState;
 use ic_types::{
     artifact::ConsensusMessageId, consensus::ConsensusMessageHashable,
     malicious_flags::MaliciousFlags, replica_config::ReplicaConfig,
-    replica_version::ReplicaVersion, Time,
+    replica_version::ReplicaVersion, Height, Time,
 };
 pub use metrics::ValidatorMetrics;
 use std::{
@@ -117,6 +119,7 @@ pub struct ConsensusImpl {
     registry_client: Arc<dyn RegistryClient>,
     state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
     dkg_key_manager: Arc<Mutex<DkgKeyManager>>,
+    finalization_tracer: Arc<FinalizationTracer>,
     last_invoked: RefCell<BTreeMap<ConsensusSubcomponent, Time>>,
     schedule: RoundRobin,
     replica_config: ReplicaConfig,
@@ -148,6 +151,7 @@ impl ConsensusImpl {
         time_source: Arc<dyn TimeSource>,
         registry_poll_delay_duration_ms: u64,
         malicious_flags: MaliciousFlags,
+        finalization_tracer: Arc<FinalizationTracer>,
         metrics_registry: MetricsRegistry,
         logger: ReplicaLogger,
     ) -> Self {
@@ -203,6 +207,7 @@ impl ConsensusImpl {
                 crypto.clone(),
                 message_routing.clone(),
                 ingress_selector.clone(),
+                finalization_tracer.clone(),
                 logger.clone(),
                 metrics_registry.clone(),
             ),
@@ -275,6 +280,7 @@ impl ConsensusImpl {
             state_manager,
             malicious_flags,
             replica_config,
+            finalization_tracer,
             last_invoked: RefCell::new(last_invoked),
             schedule: RoundRobin::default(),
         }
@@ -426,6 +432,15 @@ impl<T: ConsensusPool> PoolMutationsProducer<T> for ConsensusImpl {
             self.dkgs_available(&pool_reader)
         );
 
+        // Trace the consensus phases of the heights that are not finalized yet,
+        // including the round that is about to start.
+        self.finalization_tracer.record_from_pool(
+            pool.validated(),
+            (pool_reader.get_finalized_height().get()
+                ..=pool_reader.get_notarized_height().increment().get())
+                .map(Height::from),
+        );
+
         let time_now = self.time_source.get_relative_time();
         let finalize = || {
             self.call_with_metrics(ConsensusSubcomponent::Finalizer, || {
@@ -694,6 +709,7 @@ mod tests {
             time_source.clone(),
             0,
             MaliciousFlags::default(),
+            Arc::new(FinalizationTracer::default()),
             metrics_registry,
             no_op_logger(),
         );
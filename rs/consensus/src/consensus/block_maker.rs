@@ -1,5 +1,6 @@
 #![deny(missing_docs)]
 use crate::consensus::{
+    block_rate::get_block_rate,
     metrics::BlockMakerMetrics,
     status::{self, Status},
     ConsensusCrypto,
@@ -529,8 +530,9 @@ fn count_non_rank_0_blocks(pool: &PoolReader, block: Block) -> usize {
         .count()
 }
 
-/// Calculate the required delay for block making based on the block maker's
-/// rank and the number of non-0-rank blocks in its ancestry.
+/// Calculate the required delay for block making based on the block rate
+/// strategy of the subnet, the block maker's rank and the number of non-0-rank
+/// blocks in its ancestry.
 pub(super) fn get_block_maker_delay(
     log: &ReplicaLogger,
     registry_client: &dyn RegistryClient,
@@ -543,6 +545,8 @@ pub(super) fn get_block_maker_delay(
 ) -> Duration {
     let settings =
         get_notarization_delay_settings(log, registry_client, subnet_id, registry_version);
+    let round_delays = get_block_rate(log, registry_client, subnet_id, registry_version, &settings)
+        .round_delays(pool, &parent);
     // If this is not a Rank-0 block maker, check how many non-rank-0 blocks have been notarized in
     // the past, and increase the delay if there have been too many.
     let dynamic_delay = if rank > Rank(0)
@@ -556,7 +560,7 @@ pub(super) fn get_block_maker_delay(
         Duration::ZERO
     };
 
-    settings.unit_delay * rank.0 as u32 + dynamic_delay
+    round_delays.block_maker_delay + settings.unit_delay * rank.0 as u32 + dynamic_delay
 }
 
 /// Return true if the time since round start is greater than the required block
@@ -603,8 +607,10 @@ mod tests {
     use super::*;
     use ic_consensus_mocks::{dependencies_with_subnet_params, Dependencies, MockPayloadBuilder};
     use ic_interfaces::consensus_pool::ConsensusPool;
+    use ic_limits::INITIAL_NOTARY_DELAY;
     use ic_logger::replica_logger::no_op_logger;
     use ic_metrics::MetricsRegistry;
+    use ic_protobuf::registry::subnet::v1::{BlockRateConfig, BlockRateStrategy, SubnetRecord};
     use ic_test_utilities_consensus::IDkgStatsNoOp;
     use ic_test_utilities_registry::{add_subnet_record, SubnetRecordBuilder};
     use ic_test_utilities_types::ids::{node_test_id, subnet_test_id};
@@ -1115,13 +1121,52 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case(Rank(0), Duration::from_millis(2000) - INITIAL_NOTARY_DELAY)]
+    #[case(Rank(1), Duration::from_millis(2000) - INITIAL_NOTARY_DELAY + Duration::from_secs(1))]
+    fn get_block_maker_delay_load_adaptive_idle_subnet(
+        #[case] rank: Rank,
+        #[case] expected_block_maker_delay: Duration,
+    ) {
+        let node_ids: Vec<_> = (0..100).map(node_test_id).collect();
+        let record = SubnetRecordBuilder::from(&node_ids)
+            .with_unit_delay(Duration::from_secs(1))
+            .with_block_rate_config(BlockRateConfig {
+                strategy: BlockRateStrategy::LoadAdaptive.into(),
+                min_round_delay_millis: 200,
+                max_round_delay_millis: 2000,
+            })
+            .build();
+
+        // Empty blocks indicate an idle subnet, so the block maker waits until
+        // the maximum round delay is reached.
+        assert_eq!(
+            block_maker_delay_test_case_with_record(&[Rank(0); 5], rank, record),
+            expected_block_maker_delay,
+        );
+    }
+
     fn block_maker_delay_test_case(
         past_block_ranks: &[Rank],
         block_maker_rank: Rank,
         unit_delay: Duration,
     ) -> Duration {
-        let subnet_id = subnet_test_id(0);
         let node_ids: Vec<_> = (0..100).map(node_test_id).collect();
+        block_maker_delay_test_case_with_record(
+            past_block_ranks,
+            block_maker_rank,
+            SubnetRecordBuilder::from(&node_ids)
+                .with_unit_delay(unit_delay)
+                .build(),
+        )
+    }
+
+    fn block_maker_delay_test_case_with_record(
+        past_block_ranks: &[Rank],
+        block_maker_rank: Rank,
+        record: SubnetRecord,
+    ) -> Duration {
+        let subnet_id = subnet_test_id(0);
         let registry_version = 1;
 
         ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
@@ -1130,12 +1175,7 @@ mod tests {
             } = dependencies_with_subnet_params(
                 pool_config,
                 subnet_id,
-                vec![(
-                    registry_version,
-                    SubnetRecordBuilder::from(&node_ids)
-                        .with_unit_delay(unit_delay)
-                        .build(),
-                )],
+                vec![(registry_version, record)],
             );
 
             for rank in past_block_ranks {
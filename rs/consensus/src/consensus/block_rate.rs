@@ -0,0 +1,221 @@
+//! Strategies that determine how long block makers and notaries wait in a
+//! round, i.e. the block rate of the subnet.
+//!
+//! The strategy and its bounds are configured in the subnet record. Because
+//! validators check that a block maker waited long enough before proposing a
+//! block, the delays must be computed deterministically from the chain. The
+//! load of the subnet is therefore estimated from the payloads of the most
+//! recent ancestor blocks: full blocks indicate that the ingress pool and the
+//! XNet streams have more to deliver than fits into a block, empty blocks
+//! indicate an idle subnet.
+use ic_consensus_utils::pool_reader::PoolReader;
+use ic_interfaces_registry::RegistryClient;
+use ic_logger::{warn, ReplicaLogger};
+use ic_registry_client_helpers::subnet::{
+    BlockRateStrategy, NotarizationDelaySettings, SubnetRegistry,
+};
+use ic_types::{
+    batch::BatchPayload,
+    consensus::{Block, HasHeight},
+    CountBytes, Height, RegistryVersion, SubnetId,
+};
+use num_traits::ops::saturating::SaturatingSub;
+use std::time::Duration;
+
+/// The number of ancestor blocks considered when estimating the load.
+const LOAD_LOOK_BACK_DISTANCE: Height = Height::new(10);
+
+/// The delays applied in a round, on top of the rank-based delays.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RoundDelays {
+    /// The additional delay before a block maker of any rank proposes a block.
+    pub(crate) block_maker_delay: Duration,
+    /// The delay before a notary notarizes a rank-0 block.
+    pub(crate) initial_notary_delay: Duration,
+}
+
+/// A strategy that determines the delays of the round following the given
+/// parent block.
+pub(crate) trait BlockRate {
+    fn round_delays(&self, pool: &PoolReader<'_>, parent: &Block) -> RoundDelays;
+}
+
+/// Always use the notarization delay settings of the subnet record.
+pub(crate) struct FixedBlockRate {
+    initial_notary_delay: Duration,
+}
+
+impl BlockRate for FixedBlockRate {
+    fn round_delays(&self, _pool: &PoolReader<'_>, _parent: &Block) -> RoundDelays {
+        RoundDelays {
+            block_maker_delay: Duration::ZERO,
+            initial_notary_delay: self.initial_notary_delay,
+        }
+    }
+}
+
+/// Target a round duration between `min_round_delay` (if recent blocks were
+/// full) and `max_round_delay` (if recent blocks were empty).
+pub(crate) struct LoadAdaptiveBlockRate {
+    initial_notary_delay: Duration,
+    min_round_delay: Duration,
+    max_round_delay: Duration,
+    max_block_payload_size: u64,
+}
+
+impl LoadAdaptiveBlockRate {
+    /// Return the average utilization of the payloads of the data blocks in
+    /// the look back window ending at the given block, between 0 and 1.
+    fn utilization(&self, pool: &PoolReader<'_>, block: &Block) -> f64 {
+        let max_height = block.height();
+        let min_height = max_height.saturating_sub(&LOAD_LOOK_BACK_DISTANCE);
+        let sizes: Vec<_> = pool
+            .get_range(block.clone(), min_height, max_height)
+            .filter(|block| !block.payload.is_summary())
+            .map(|block| payload_size(&block.payload.as_ref().as_data().batch))
+            .collect();
+        average_utilization(&sizes, self.max_block_payload_size)
+    }
+}
+
+impl BlockRate for LoadAdaptiveBlockRate {
+    fn round_delays(&self, pool: &PoolReader<'_>, parent: &Block) -> RoundDelays {
+        let target = target_round_delay(
+            self.min_round_delay,
+            self.max_round_delay,
+            self.utilization(pool, parent),
+        );
+        split_round_delay(target, self.initial_notary_delay)
+    }
+}
+
+/// Return the number of bytes of the given batch that count towards the load
+/// of the subnet.
+fn payload_size(batch: &BatchPayload) -> u64 {
+    (batch.ingress.count_bytes() + batch.xnet.size_bytes()) as u64
+}
+
+fn average_utilization(sizes: &[u64], max_size: u64) -> f64 {
+    if sizes.is_empty() || max_size == 0 {
+        return 0.0;
+    }
+    let total: f64 = sizes
+        .iter()
+        .map(|size| (*size).min(max_size) as f64 / max_size as f64)
+        .sum();
+    total / sizes.len() as f64
+}
+
+/// Interpolate linearly between the maximum round delay at zero utilization
+/// and the minimum round delay at full utilization.
+fn target_round_delay(min: Duration, max: Duration, utilization: f64) -> Duration {
+    let range = max.saturating_sub(min);
+    max.saturating_sub(range.mul_f64(utilization.clamp(0.0, 1.0)))
+}
+
+/// Reach the target round duration by shortening the initial notary delay if
+/// the target is shorter, and by delaying the block makers otherwise.
+fn split_round_delay(target: Duration, initial_notary_delay: Duration) -> RoundDelays {
+    RoundDelays {
+        block_maker_delay: target.saturating_sub(initial_notary_delay),
+        initial_notary_delay: initial_notary_delay.min(target),
+    }
+}
+
+/// Return the block rate strategy configured in the subnet record at the given
+/// registry version. Falls back to [FixedBlockRate] if the configuration
+/// cannot be retrieved.
+pub(crate) fn get_block_rate(
+    log: &ReplicaLogger,
+    registry_client: &dyn RegistryClient,
+    subnet_id: SubnetId,
+    registry_version: RegistryVersion,
+    settings: &NotarizationDelaySettings,
+) -> Box<dyn BlockRate> {
+    let fixed: Box<dyn BlockRate> = Box::new(FixedBlockRate {
+        initial_notary_delay: settings.initial_notary_delay,
+    });
+    let strategy = match registry_client.get_block_rate_strategy(subnet_id, registry_version) {
+        Ok(Some(strategy)) => strategy,
+        Ok(None) => return fixed,
+        Err(err) => {
+            warn!(
+                every_n_seconds => 300,
+                log,
+                "Could not retrieve the block rate strategy from the registry: {:?}", err
+            );
+            return fixed;
+        }
+    };
+    match strategy {
+        BlockRateStrategy::Fixed => fixed,
+        BlockRateStrategy::LoadAdaptive {
+            min_round_delay,
+            max_round_delay,
+        } => match registry_client.get_max_block_payload_size_bytes(subnet_id, registry_version) {
+            Ok(Some(max_block_payload_size)) => Box::new(LoadAdaptiveBlockRate {
+                initial_notary_delay: settings.initial_notary_delay,
+                min_round_delay,
+                max_round_delay,
+                max_block_payload_size,
+            }),
+            result => {
+                warn!(
+                    every_n_seconds => 300,
+                    log,
+                    "Could not retrieve the max block payload size from the registry: {:?}",
+                    result
+                );
+                fixed
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_utilization() {
+        assert_eq!(average_utilization(&[], 100), 0.0);
+        assert_eq!(average_utilization(&[50], 0), 0.0);
+        assert_eq!(average_utilization(&[0, 0], 100), 0.0);
+        assert_eq!(average_utilization(&[100, 50], 100), 0.75);
+        // Sizes above the maximum count as full blocks.
+        assert_eq!(average_utilization(&[200, 100], 100), 1.0);
+    }
+
+    #[test]
+    fn test_target_round_delay() {
+        let min = Duration::from_millis(200);
+        let max = Duration::from_millis(2200);
+        assert_eq!(target_round_delay(min, max, 0.0), max);
+        assert_eq!(target_round_delay(min, max, 1.0), min);
+        assert_eq!(
+            target_round_delay(min, max, 0.5),
+            Duration::from_millis(1200)
+        );
+        assert_eq!(target_round_delay(min, max, 2.0), min);
+        assert_eq!(target_round_delay(max, max, 0.5), max);
+    }
+
+    #[test]
+    fn test_split_round_delay() {
+        let initial_notary_delay = Duration::from_millis(300);
+        assert_eq!(
+            split_round_delay(Duration::from_millis(1000), initial_notary_delay),
+            RoundDelays {
+                block_maker_delay: Duration::from_millis(700),
+                initial_notary_delay,
+            }
+        );
+        assert_eq!(
+            split_round_delay(Duration::from_millis(100), initial_notary_delay),
+            RoundDelays {
+                block_maker_delay: Duration::ZERO,
+                initial_notary_delay: Duration::from_millis(100),
+            }
+        );
+    }
+}
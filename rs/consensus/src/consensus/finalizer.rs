@@ -21,7 +21,10 @@ use crate::consensus::{
     metrics::{BatchStats, BlockStats, FinalizerMetrics},
 };
 use ic_consensus_utils::{
-    crypto::ConsensusCrypto, membership::Membership, pool_reader::PoolReader,
+    crypto::ConsensusCrypto,
+    finalization_trace::{FinalizationPhase, FinalizationTracer},
+    membership::Membership,
+    pool_reader::PoolReader,
 };
 use ic_interfaces::{
     ingress_manager::IngressSelector,
@@ -45,6 +48,7 @@ pub struct Finalizer {
     pub(crate) crypto: Arc<dyn ConsensusCrypto>,
     message_routing: Arc<dyn MessageRouting>,
     ingress_selector: Arc<dyn IngressSelector>,
+    finalization_tracer: Arc<FinalizationTracer>,
     pub(crate) log: ReplicaLogger,
     metrics: FinalizerMetrics,
     prev_finalized_height: RefCell<Height>,
@@ -60,6 +64,7 @@ impl Finalizer {
         crypto: Arc<dyn ConsensusCrypto>,
         message_routing: Arc<dyn MessageRouting>,
         ingress_selector: Arc<dyn IngressSelector>,
+        finalization_tracer: Arc<FinalizationTracer>,
         log: ReplicaLogger,
         metrics_registry: MetricsRegistry,
     ) -> Self {
@@ -70,6 +75,7 @@ impl Finalizer {
             crypto,
             message_routing,
             ingress_selector,
+            finalization_tracer,
             log,
             metrics: FinalizerMetrics::new(metrics_registry),
             prev_finalized_height: RefCell::new(Height::from(0)),
@@ -132,6 +138,11 @@ impl Finalizer {
                         .observe(now.duration_since(last_batch_delivered_at).as_secs_f64());
                 }
                 self.last_batch_delivered_at.borrow_mut().replace(now);
+                self.finalization_tracer.record(
+                    Height::from(block_stats.block_height),
+                    FinalizationPhase::BatchDelivery,
+                    system_time_now(),
+                );
                 // Batch creation time is essentially wall time (on some replica), so the median
                 // duration across the subnet is meaningful.
                 self.metrics.batch_delivery_latency.observe(
@@ -296,6 +307,7 @@ mod tests {
                 crypto,
                 message_routing.clone(),
                 ingress_selector,
+                Arc::new(FinalizationTracer::default()),
                 no_op_logger(),
                 MetricsRegistry::new(),
             );
@@ -386,6 +398,7 @@ mod tests {
                 crypto,
                 message_routing.clone(),
                 ingress_selector,
+                Arc::new(FinalizationTracer::default()),
                 no_op_logger(),
                 metrics_registry,
             );
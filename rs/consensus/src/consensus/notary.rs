@@ -23,7 +23,7 @@
 //! * A node must not issue new notarization share for any round older than the
 //!   latest round, which would break security if it has already finality-signed
 //!   for that round.
-use crate::consensus::metrics::NotaryMetrics;
+use crate::consensus::{block_rate::get_block_rate, metrics::NotaryMetrics};
 use ic_consensus_utils::{
     crypto::ConsensusCrypto,
     find_lowest_ranked_non_disqualified_proposals, get_notarization_delay_settings,
@@ -250,13 +250,26 @@ fn get_adjusted_notary_delay(
     height: Height,
     rank: Rank,
 ) -> Option<Duration> {
+    let registry_version = pool.registry_version(height)?;
+    let mut settings = get_notarization_delay_settings(
+        log,
+        &*membership.registry_client,
+        membership.subnet_id,
+        registry_version,
+    );
+    // The notary delay is not validated by other nodes, so it is sufficient to
+    // estimate the load from the finalized tip rather than the block's parent.
+    settings.initial_notary_delay = get_block_rate(
+        log,
+        &*membership.registry_client,
+        membership.subnet_id,
+        registry_version,
+        &settings,
+    )
+    .round_delays(pool, &pool.get_finalized_tip())
+    .initial_notary_delay;
     match get_adjusted_notary_delay_from_settings(
-        get_notarization_delay_settings(
-            log,
-            &*membership.registry_client,
-            membership.subnet_id,
-            pool.registry_version(height)?,
-        ),
+        settings,
         pool,
         state_manager,
         membership,
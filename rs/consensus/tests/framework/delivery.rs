@@ -15,6 +15,39 @@ fn get_instance_with_least_outgoing_message_timestamp<'a, 'b>(
     })
 }
 
+/// Deliver a message from one instance to another, postponing or dropping it
+/// according to the faults injected into the network.
+fn deliver(
+    runner: &dyn ConsensusInstances<'_>,
+    from: &ConsensusInstance<'_>,
+    to: &ConsensusInstance<'_>,
+    msg: Message,
+) {
+    let logger = runner.logger();
+    let Some(timestamp) = runner
+        .faults()
+        .delivery_time(from.index, to.index, msg.timestamp)
+    else {
+        trace!(
+            logger,
+            "Drop message from instance {} to {}: {:?}",
+            from.deps.replica_config.node_id,
+            to.deps.replica_config.node_id,
+            msg,
+        );
+        return;
+    };
+    let msg = Message { timestamp, ..msg };
+    trace!(
+        logger,
+        "Deliver from instance {} to {}: {:?}",
+        from.deps.replica_config.node_id,
+        to.deps.replica_config.node_id,
+        msg,
+    );
+    to.in_queue.borrow_mut().push(Input::Message(msg));
+}
+
 /// Pick the next message that has the least timestamp(m_out(i)) value among all
 /// nodes, and always set receiving timestamp to be 1 unit greater than this
 /// timestamp. It ensures globally that messages are always received in the
@@ -30,7 +63,6 @@ impl Sequential {
 
 impl DeliveryStrategy for Sequential {
     fn deliver_next(&self, runner: &dyn ConsensusInstances<'_>) -> bool {
-        let logger = runner.logger();
         let instances = runner.instances();
         if let Some(instance) = get_instance_with_least_outgoing_message_timestamp(instances) {
             if let Some(x) = instance.out_queue.borrow_mut().pop() {
@@ -42,15 +74,7 @@ impl DeliveryStrategy for Sequential {
                 };
                 for other in instances.iter() {
                     if other.deps.replica_config.node_id != instance.deps.replica_config.node_id {
-                        trace!(
-                            logger,
-                            "Deliver from instance {} to {}: {:?}",
-                            instance.deps.replica_config.node_id,
-                            other.deps.replica_config.node_id,
-                            msg,
-                        );
-                        let mut in_queue = other.in_queue.borrow_mut();
-                        in_queue.push(Input::Message(msg.clone()));
+                        deliver(runner, instance, other, msg.clone());
                     }
                 }
                 return true;
@@ -65,39 +89,42 @@ impl DeliveryStrategy for Sequential {
 /// messages are received in the order they are sent.
 #[derive(Debug)]
 pub struct RandomReceive {
+    /// The min time lapse in milliseconds before a message reaches a node.
+    min_delta: u64,
     /// The max time lapse in milliseconds before a message reaches all nodes.
     max_delta: u64,
 }
 
 impl RandomReceive {
-    pub fn new(max_delta: u64) -> Box<RandomReceive> {
-        Box::new(RandomReceive { max_delta })
+    pub fn new(min_delta: u64, max_delta: u64) -> Box<RandomReceive> {
+        let min_delta = min_delta.max(UNIT_TIME_STEP);
+        assert!(
+            min_delta < max_delta,
+            "Invalid message latency range {}..{}",
+            min_delta,
+            max_delta
+        );
+        Box::new(RandomReceive {
+            min_delta,
+            max_delta,
+        })
     }
 }
 
 impl DeliveryStrategy for RandomReceive {
     fn deliver_next(&self, runner: &dyn ConsensusInstances<'_>) -> bool {
-        let logger = runner.logger();
         let instances = runner.instances();
         if let Some(instance) = get_instance_with_least_outgoing_message_timestamp(instances) {
             if let Some(x) = instance.out_queue.borrow_mut().pop() {
                 let mut rng = runner.rng();
                 for other in instances.iter() {
                     if other.deps.replica_config.node_id != instance.deps.replica_config.node_id {
-                        let mut in_queue = other.in_queue.borrow_mut();
-                        let delay = rng.gen_range(UNIT_TIME_STEP..self.max_delta);
+                        let delay = rng.gen_range(self.min_delta..self.max_delta);
                         let msg = Message {
                             message: x.message.clone(),
                             timestamp: x.timestamp + Duration::from_millis(delay),
                         };
-                        trace!(
-                            logger,
-                            "Deliver from instance {} to {}: {:?}",
-                            instance.deps.replica_config.node_id,
-                            other.deps.replica_config.node_id,
-                            msg,
-                        );
-                        in_queue.push(Input::Message(msg));
+                        deliver(runner, instance, other, msg);
                     }
                 }
                 return true;
@@ -118,27 +145,18 @@ pub struct RandomGraph {
 
 impl DeliveryStrategy for RandomGraph {
     fn deliver_next(&self, runner: &dyn ConsensusInstances<'_>) -> bool {
-        let logger = runner.logger();
         let instances = runner.instances();
         if let Some(instance) = get_instance_with_least_outgoing_message_timestamp(instances) {
             if let Some(x) = instance.out_queue.borrow_mut().pop() {
                 for other in instances.iter() {
                     if other.deps.replica_config.node_id != instance.deps.replica_config.node_id {
-                        let mut in_queue = other.in_queue.borrow_mut();
                         let delay =
                             self.distances[instance.index][other.index] as u32 * self.unit_latency;
                         let msg = Message {
                             message: x.message.clone(),
                             timestamp: x.timestamp + delay,
                         };
-                        trace!(
-                            logger,
-                            "Deliver from instance {} to {}: {:?}",
-                            instance.deps.replica_config.node_id,
-                            other.deps.replica_config.node_id,
-                            msg,
-                        );
-                        in_queue.push(Input::Message(msg));
+                        deliver(runner, instance, other, msg);
                     }
                 }
                 return true;
@@ -1,4 +1,4 @@
-use super::types::*;
+use super::{faults::FaultSchedule, types::*};
 use ic_consensus::consensus::bounds::validated_pool_within_bounds;
 use ic_consensus_utils::pool_reader::PoolReader;
 use ic_interfaces::p2p::consensus::{BouncerValue, MutablePool, UnvalidatedArtifact};
@@ -11,11 +11,33 @@ use std::time::Duration;
 fn execute_instance(
     instance: &ConsensusInstance,
     use_priority_fn: bool,
+    faults: &FaultSchedule,
     logger: &ReplicaLogger,
 ) -> Option<Time> {
     let mut in_queue = instance.in_queue.borrow_mut();
     let mut out_queue = instance.out_queue.borrow_mut();
     if let Some(inp) = in_queue.pop() {
+        // A paused instance does not execute. Its pending inputs are processed
+        // once it recovers, or never if it doesn't.
+        let input_time = inp.timestamp();
+        if faults.is_paused(instance.index, input_time) {
+            trace!(
+                logger,
+                "Instance {} is paused, postpone {:?}",
+                instance.deps.replica_config.node_id,
+                inp,
+            );
+            if let Some(time) = faults.running_time(instance.index, input_time) {
+                in_queue.push(match inp {
+                    Input::Message(x) => Input::Message(Message {
+                        timestamp: time,
+                        ..x
+                    }),
+                    Input::TimerExpired(_) => Input::TimerExpired(time),
+                });
+            }
+            return Some(input_time);
+        }
         trace!(
             logger,
             "Execute instance {} in({}), out({}): {:?}",
@@ -159,7 +181,9 @@ impl ExecutionStrategy for GlobalMessage {
                 let t_j = j.in_queue.borrow().peek().map(|x| x.timestamp());
                 compare_timestamp(t_i, t_j)
             })
-            .and_then(|instance| execute_instance(instance, self.use_priority_fn, logger))
+            .and_then(|instance| {
+                execute_instance(instance, self.use_priority_fn, runner.faults(), logger)
+            })
     }
 }
 
@@ -181,7 +205,7 @@ impl ExecutionStrategy for RandomExecute {
         let mut rng = runner.rng();
         instances.shuffle(&mut *rng);
         while let Some(instance) = instances.pop() {
-            let result = execute_instance(instance, self.use_priority_fn, logger);
+            let result = execute_instance(instance, self.use_priority_fn, runner.faults(), logger);
             if result.is_some() {
                 return result;
             }
@@ -212,6 +236,8 @@ impl ExecutionStrategy for GlobalClock {
                 let t_j = j.in_queue.borrow().peek().map(|_| *j.clock.borrow());
                 compare_timestamp(t_i, t_j)
             })
-            .and_then(|instance| execute_instance(instance, self.use_priority_fn, logger))
+            .and_then(|instance| {
+                execute_instance(instance, self.use_priority_fn, runner.faults(), logger)
+            })
     }
 }
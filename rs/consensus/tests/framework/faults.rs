@@ -0,0 +1,195 @@
+use ic_types::time::{Time, UNIX_EPOCH};
+use std::{collections::BTreeSet, time::Duration};
+
+/// A window of virtual time during which a fault is in effect. Both ends are
+/// relative to the start of the simulation. A window without an end lasts
+/// forever.
+#[derive(Clone, Debug)]
+pub struct FaultWindow {
+    start: Duration,
+    end: Option<Duration>,
+}
+
+impl FaultWindow {
+    pub fn new(start: Duration, end: Option<Duration>) -> Self {
+        assert!(
+            end.map_or(true, |end| start < end),
+            "Fault window must not be empty"
+        );
+        Self { start, end }
+    }
+
+    fn contains(&self, time: Time) -> bool {
+        let elapsed = time.saturating_duration_since(UNIX_EPOCH);
+        self.start <= elapsed && self.end.map_or(true, |end| elapsed < end)
+    }
+
+    fn end_time(&self) -> Option<Time> {
+        self.end.map(|end| UNIX_EPOCH + end)
+    }
+}
+
+/// The network is split into groups of instances (given by their index) that
+/// can only talk to instances of the same group. Instances not listed in any
+/// group are cut off from everyone else.
+#[derive(Clone, Debug)]
+struct Partition {
+    groups: Vec<BTreeSet<usize>>,
+    window: FaultWindow,
+}
+
+impl Partition {
+    fn separates(&self, i: usize, j: usize) -> bool {
+        !self
+            .groups
+            .iter()
+            .any(|group| group.contains(&i) && group.contains(&j))
+    }
+}
+
+/// An instance (given by its index) that neither executes nor receives any
+/// messages. A paused instance keeps its artifact pools and in-memory state,
+/// i.e. it resumes from the state it had when pausing. This models a stalled
+/// replica, not a crash, which would restart from the latest CUP.
+#[derive(Clone, Debug)]
+struct Pause {
+    index: usize,
+    window: FaultWindow,
+}
+
+/// Network partitions and instance pauses to be injected into a simulation.
+///
+/// Since P2P eventually delivers every artifact to all honest nodes, a message
+/// that cannot be delivered due to a fault is postponed until the fault ends,
+/// and is only dropped if the fault never ends.
+#[derive(Clone, Debug, Default)]
+pub struct FaultSchedule {
+    partitions: Vec<Partition>,
+    pauses: Vec<Pause>,
+}
+
+impl FaultSchedule {
+    /// Split the network into the given groups of instances for the duration
+    /// of the given window.
+    pub fn with_partition(mut self, groups: Vec<Vec<usize>>, window: FaultWindow) -> Self {
+        self.partitions.push(Partition {
+            groups: groups
+                .into_iter()
+                .map(|group| group.into_iter().collect())
+                .collect(),
+            window,
+        });
+        self
+    }
+
+    /// Pause the instance with the given index for the duration of the given
+    /// window.
+    pub fn with_pause(mut self, index: usize, window: FaultWindow) -> Self {
+        self.pauses.push(Pause { index, window });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.partitions.is_empty() && self.pauses.is_empty()
+    }
+
+    /// Return true if the instance with the given index is paused at the
+    /// given time.
+    pub fn is_paused(&self, index: usize, time: Time) -> bool {
+        self.pauses
+            .iter()
+            .any(|pause| pause.index == index && pause.window.contains(time))
+    }
+
+    /// Return the earliest time at or after the given time at which the
+    /// instance with the given index is running, or None if it never recovers.
+    pub fn running_time(&self, index: usize, time: Time) -> Option<Time> {
+        self.earliest_time(time, |time| {
+            self.pauses
+                .iter()
+                .find(|pause| pause.index == index && pause.window.contains(time))
+                .map(|pause| &pause.window)
+        })
+    }
+
+    /// Return the time at which a message sent from instance `from` to
+    /// instance `to`, which would otherwise arrive at the given time, is
+    /// delivered, or None if it is never delivered.
+    pub fn delivery_time(&self, from: usize, to: usize, time: Time) -> Option<Time> {
+        self.earliest_time(time, |time| {
+            self.partitions
+                .iter()
+                .find(|partition| partition.separates(from, to) && partition.window.contains(time))
+                .map(|partition| &partition.window)
+                .or_else(|| {
+                    self.pauses
+                        .iter()
+                        .find(|pause| pause.index == to && pause.window.contains(time))
+                        .map(|pause| &pause.window)
+                })
+        })
+    }
+
+    /// Postpone the given time until it is no longer within any of the
+    /// windows returned by `blocking`.
+    fn earliest_time<'a>(
+        &'a self,
+        mut time: Time,
+        blocking: impl Fn(Time) -> Option<&'a FaultWindow>,
+    ) -> Option<Time> {
+        while let Some(window) = blocking(time) {
+            time = window.end_time()?;
+        }
+        Some(time)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(millis: u64) -> Time {
+        UNIX_EPOCH + Duration::from_millis(millis)
+    }
+
+    fn window(start: u64, end: Option<u64>) -> FaultWindow {
+        FaultWindow::new(Duration::from_millis(start), end.map(Duration::from_millis))
+    }
+
+    #[test]
+    fn test_partition_postpones_delivery_until_healed() {
+        let faults = FaultSchedule::default()
+            .with_partition(vec![vec![0, 1], vec![2, 3]], window(10, Some(20)));
+        assert_eq!(faults.delivery_time(0, 1, at(15)), Some(at(15)));
+        assert_eq!(faults.delivery_time(0, 2, at(5)), Some(at(5)));
+        assert_eq!(faults.delivery_time(0, 2, at(15)), Some(at(20)));
+        assert_eq!(faults.delivery_time(3, 1, at(10)), Some(at(20)));
+        assert_eq!(faults.delivery_time(3, 1, at(20)), Some(at(20)));
+        // Instances not listed in any group are isolated.
+        assert_eq!(faults.delivery_time(4, 0, at(15)), Some(at(20)));
+    }
+
+    #[test]
+    fn test_permanent_faults_drop_messages() {
+        let faults = FaultSchedule::default()
+            .with_partition(vec![vec![0], vec![1]], window(10, None))
+            .with_pause(2, window(0, None));
+        assert_eq!(faults.delivery_time(0, 1, at(5)), Some(at(5)));
+        assert_eq!(faults.delivery_time(0, 1, at(10)), None);
+        assert_eq!(faults.delivery_time(0, 2, at(5)), None);
+        assert_eq!(faults.running_time(2, at(5)), None);
+    }
+
+    #[test]
+    fn test_overlapping_faults_are_combined() {
+        let faults = FaultSchedule::default()
+            .with_partition(vec![vec![0], vec![1]], window(10, Some(20)))
+            .with_pause(1, window(15, Some(30)))
+            .with_pause(1, window(30, Some(40)));
+        assert_eq!(faults.delivery_time(0, 1, at(12)), Some(at(40)));
+        assert!(faults.is_paused(1, at(35)));
+        assert!(!faults.is_paused(1, at(40)));
+        assert_eq!(faults.running_time(1, at(15)), Some(at(40)));
+        assert_eq!(faults.running_time(0, at(15)), Some(at(15)));
+    }
+}
@@ -0,0 +1,90 @@
+use super::types::ConsensusInstance;
+use ic_interfaces::consensus_pool::{ConsensusPool, HeightRange};
+use ic_types::{
+    consensus::{Block, CatchUpPackage, HasHeight},
+    crypto::CryptoHashOf,
+    Height,
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// Safety invariants that must hold across all consensus instances at all
+/// times, no matter how messages are delivered and which faults are injected:
+///
+/// 1. No two different blocks are finalized at the same height.
+/// 2. All catch-up packages at the same height are identical, and consist of
+///    the block finalized at that height.
+#[derive(Default)]
+pub(crate) struct SafetyInvariants {
+    /// The block finalized at each height, by any instance.
+    finalized: RefCell<BTreeMap<Height, CryptoHashOf<Block>>>,
+    /// The first catch-up package observed at each height.
+    cups: RefCell<BTreeMap<Height, CatchUpPackage>>,
+    /// The highest finalization already checked, for each instance.
+    checked_heights: RefCell<BTreeMap<usize, Height>>,
+}
+
+impl SafetyInvariants {
+    /// Check the invariants against the current state of all instances, and
+    /// panic if any of them is violated.
+    pub(crate) fn check(&self, instances: &[ConsensusInstance<'_>]) {
+        for instance in instances {
+            self.check_finalizations(instance);
+            self.check_catch_up_package(instance);
+        }
+    }
+
+    /// Check the finalizations above the highest height checked previously.
+    fn check_finalizations(&self, instance: &ConsensusInstance<'_>) {
+        let pool = instance.driver.consensus_pool.read().unwrap();
+        let finalizations = pool.validated().finalization();
+        let Some(max_height) = finalizations.max_height() else {
+            return;
+        };
+        let mut checked_heights = self.checked_heights.borrow_mut();
+        let checked_height = checked_heights.entry(instance.index).or_default();
+        if max_height <= *checked_height {
+            return;
+        }
+        let range = HeightRange::new(checked_height.increment(), max_height);
+        let mut finalized = self.finalized.borrow_mut();
+        for finalization in finalizations.get_by_height_range(range) {
+            let height = finalization.height();
+            let hash = finalization.content.block;
+            let existing = finalized.entry(height).or_insert_with(|| hash.clone());
+            assert_eq!(
+                *existing, hash,
+                "violated safety! instance {} finalized a different block at height {}",
+                instance.node_id, height,
+            );
+        }
+        *checked_height = max_height;
+    }
+
+    /// Check the highest catch-up package of the given instance.
+    fn check_catch_up_package(&self, instance: &ConsensusInstance<'_>) {
+        let pool = instance.driver.consensus_pool.read().unwrap();
+        let cup = pool.as_cache().catch_up_package();
+        let height = cup.height();
+        if let Some(hash) = self.finalized.borrow().get(&height) {
+            assert_eq!(
+                cup.content.block.get_hash(),
+                hash,
+                "violated safety! instance {} has a catch-up package at height {} \
+                 that does not match the finalized block",
+                instance.node_id,
+                height,
+            );
+        }
+        let mut cups = self.cups.borrow_mut();
+        let existing = cups.entry(height).or_insert_with(|| cup.clone());
+        assert!(
+            existing.content.block.get_hash() == cup.content.block.get_hash()
+                && existing.content.random_beacon.get_hash()
+                    == cup.content.random_beacon.get_hash()
+                && existing.content.state_hash == cup.content.state_hash,
+            "violated safety! instance {} has an inconsistent catch-up package at height {}",
+            instance.node_id,
+            height,
+        );
+    }
+}
@@ -3,10 +3,13 @@
 mod delivery;
 mod driver;
 mod execution;
+mod faults;
+mod invariants;
 pub mod malicious;
 mod runner;
 mod types;
 
+pub use faults::{FaultSchedule, FaultWindow};
 use ic_consensus_dkg::get_dkg_summary_from_cup_contents;
 pub use runner::ConsensusRunner;
 pub use types::{
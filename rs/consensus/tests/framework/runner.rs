@@ -1,9 +1,12 @@
-use super::{delivery::*, execution::*, types::*};
+use super::{
+    delivery::*, execution::*, faults::FaultSchedule, invariants::SafetyInvariants, types::*,
+};
 use ic_config::artifact_pool::ArtifactPoolConfig;
 use ic_consensus_certification::{CertificationCrypto, CertifierImpl};
 use ic_consensus_dkg::DkgKeyManager;
 use ic_consensus_utils::{
-    crypto::ConsensusCrypto, membership::Membership, pool_reader::PoolReader,
+    crypto::ConsensusCrypto, finalization_trace::FinalizationTracer, membership::Membership,
+    pool_reader::PoolReader,
 };
 use ic_interfaces::{consensus_pool::ConsensusPoolCache, time_source::TimeSource};
 use ic_logger::{info, warn, ReplicaLogger};
@@ -44,6 +47,7 @@ pub struct ConsensusRunner<'a> {
     pub(crate) logger: ReplicaLogger,
     pub(crate) rng: RefCell<ChaChaRng>,
     pub(crate) config: ConsensusRunnerConfig,
+    pub(crate) invariants: SafetyInvariants,
 }
 
 impl<'a> ConsensusInstances<'a> for ConsensusRunner<'a> {
@@ -59,6 +63,9 @@ impl<'a> ConsensusInstances<'a> for ConsensusRunner<'a> {
     fn time_source(&self) -> &dyn TimeSource {
         self.time.as_ref()
     }
+    fn faults(&self) -> &FaultSchedule {
+        &self.config.faults
+    }
 }
 
 const SLOG_ASYNC_CHAN_SIZE: usize = 10000;
@@ -111,6 +118,7 @@ impl<'a> ConsensusRunner<'a> {
             logger,
             config,
             rng,
+            invariants: SafetyInvariants::default(),
         }
     }
 
@@ -143,6 +151,7 @@ impl<'a> ConsensusRunner<'a> {
             pool_reader,
         )));
         let malicious_flags = MaliciousFlags::default();
+        let finalization_tracer = Arc::new(FinalizationTracer::default());
         let consensus = ic_consensus::consensus::ConsensusImpl::new(
             deps.replica_config.clone(),
             Arc::clone(&deps.registry_client),
@@ -162,6 +171,7 @@ impl<'a> ConsensusRunner<'a> {
             Arc::clone(&self.time) as Arc<_>,
             0,
             malicious_flags.clone(),
+            finalization_tracer.clone(),
             deps.metrics_registry.clone(),
             replica_logger.clone(),
         );
@@ -192,6 +202,7 @@ impl<'a> ConsensusRunner<'a> {
             certification_crypto,
             deps.state_manager.clone(),
             deps.consensus_pool.read().unwrap().get_cache(),
+            finalization_tracer,
             deps.metrics_registry.clone(),
             replica_logger.clone(),
             watch::channel(Height::from(0)).0,
@@ -267,9 +278,16 @@ impl<'a> ConsensusRunner<'a> {
 
         let now = self.time.get_instant();
 
+        // Safety must hold after every step, regardless of injected faults.
+        self.invariants.check(&self.instances);
+
+        let current_time = self.time.get_relative_time();
         let mut stopped = true;
         for instance in self.instances.iter() {
-            // only stop when all instances satisfy StopPredicate
+            // only stop when all running instances satisfy StopPredicate
+            if self.config.faults.is_paused(instance.index, current_time) {
+                continue;
+            }
             if !(self.stop_predicate)(instance) {
                 stopped = false;
                 break;
@@ -293,6 +311,7 @@ impl<'a> ConsensusRunner<'a> {
 impl Default for ConsensusRunnerConfig {
     fn default() -> Self {
         ConsensusRunnerConfig {
+            min_delta: UNIT_TIME_STEP,
             max_delta: 1000,
             random_seed: 0,
             num_nodes: 10,
@@ -302,6 +321,7 @@ impl Default for ConsensusRunnerConfig {
             stall_clocks: false,
             execution: GlobalMessage::new(false),
             delivery: Sequential::new(),
+            faults: FaultSchedule::default(),
         }
     }
 }
@@ -376,21 +396,26 @@ impl ConsensusRunnerConfig {
             ],
             vec![
                 Sequential::new(),
-                RandomReceive::new(self.max_delta),
+                RandomReceive::new(self.min_delta, self.max_delta),
                 RandomGraph::new(self.num_nodes, self.degree, self.max_delta, rng),
             ],
         )
     }
 
     /// Parse and update configuration from environment: NUM_NODES,
-    /// NUM_ROUNDS, MAX_DELTA, DEGREE, USE_PRIORITY_FN, STALL_CLOCKS, EXECUTION and DELIVERY
+    /// NUM_ROUNDS, MIN_DELTA, MAX_DELTA, DEGREE, USE_PRIORITY_FN, STALL_CLOCKS, EXECUTION and DELIVERY
     /// (except RANDOM_SEED, which should be used when first creating the config).
     /// Return the updated config if parsing is successful, or an error message
     /// in string otherwise.
     pub fn parse_extra_config(mut self) -> Result<Self, String> {
-        // Parse environment max_delta, num_rounds, and degree.
+        // Parse environment min_delta, max_delta, num_rounds, and degree.
         for (key, value) in std::env::vars() {
             match key.to_ascii_lowercase().as_str() {
+                "min_delta" => {
+                    self.min_delta = value
+                        .parse()
+                        .map_err(|_| "MIN_DELTA must be an unsigned integer (in milliseconds)")?;
+                }
                 "max_delta" => {
                     self.max_delta = value
                         .parse()
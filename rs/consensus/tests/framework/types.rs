@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+use super::faults::FaultSchedule;
 use ic_artifact_pool::{
     canister_http_pool, certification_pool::CertificationPoolImpl,
     consensus_pool::ConsensusPoolImpl, dkg_pool, idkg_pool,
@@ -389,11 +390,13 @@ pub trait ConsensusInstances<'a> {
     fn logger(&self) -> &ReplicaLogger;
     fn rng(&self) -> RefMut<'_, ChaChaRng>;
     fn time_source(&self) -> &dyn TimeSource;
+    fn faults(&self) -> &FaultSchedule;
 }
 
 /// Configuration parameters that will be read from command line argument or
 /// environment.
 pub struct ConsensusRunnerConfig {
+    pub min_delta: u64,
     pub max_delta: u64,
     pub random_seed: u64,
     pub num_nodes: usize,
@@ -403,14 +406,17 @@ pub struct ConsensusRunnerConfig {
     pub stall_clocks: bool,
     pub execution: Box<dyn ExecutionStrategy>,
     pub delivery: Box<dyn DeliveryStrategy>,
+    pub faults: FaultSchedule,
 }
 
 impl fmt::Display for ConsensusRunnerConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "ConsensusRunnerConfig {{ max_delta: {}, random_seed: {}, \
-             num_nodes: {}, num_rounds: {}, degree: {}, use_priority_fn: {}, execution: {}, delivery: {} }}",
+            "ConsensusRunnerConfig {{ min_delta: {}, max_delta: {}, random_seed: {}, \
+             num_nodes: {}, num_rounds: {}, degree: {}, use_priority_fn: {}, execution: {}, delivery: {}, \
+             faults: {:?} }}",
+            self.min_delta,
             self.max_delta,
             self.random_seed,
             self.num_nodes,
@@ -418,7 +424,8 @@ impl fmt::Display for ConsensusRunnerConfig {
             self.degree,
             self.use_priority_fn,
             get_name(&self.execution),
-            get_name(&self.delivery)
+            get_name(&self.delivery),
+            self.faults,
         )
     }
 }
@@ -3,7 +3,7 @@ mod framework;
 
 use crate::framework::{
     malicious, setup_subnet, ComponentModifier, ConsensusDependencies, ConsensusInstance,
-    ConsensusRunner, ConsensusRunnerConfig, StopPredicate,
+    ConsensusRunner, ConsensusRunnerConfig, FaultSchedule, FaultWindow, StopPredicate,
 };
 use framework::test_master_public_key_ids;
 use ic_consensus_utils::pool_reader::PoolReader;
@@ -16,7 +16,7 @@ use ic_types::{
 };
 use rand::Rng;
 use rand_chacha::{rand_core::SeedableRng, ChaChaRng};
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+use std::{cell::RefCell, rc::Rc, sync::Arc, time::Duration};
 
 #[test]
 fn multiple_nodes_are_live() -> Result<(), String> {
@@ -230,6 +230,52 @@ fn stalled_clocks_with_f_malicious_would_pass() -> Result<(), String> {
         })
 }
 
+#[test]
+fn minority_paused_nodes_would_pass() -> Result<(), String> {
+    ConsensusRunnerConfig::new_from_env(4, 0)
+        .and_then(|config| config.parse_extra_config())
+        .map(|mut config| {
+            let f = (config.num_nodes - 1) / 3;
+            assert!(f > 0, "This test requires NUM_NODES >= 4");
+            config.faults = (0..f).fold(FaultSchedule::default(), |faults, index| {
+                faults.with_pause(index, FaultWindow::new(Duration::ZERO, None))
+            });
+            run_n_rounds_and_collect_hashes(config, Vec::new(), true);
+        })
+}
+
+#[test]
+fn majority_paused_nodes_would_recover() -> Result<(), String> {
+    ConsensusRunnerConfig::new_from_env(4, 0)
+        .and_then(|config| config.parse_extra_config())
+        .map(|mut config| {
+            // Pause more than f nodes for a while, during which the subnet cannot
+            // make progress, but must remain safe.
+            let pause = FaultWindow::new(Duration::from_secs(2), Some(Duration::from_secs(10)));
+            config.faults = (0..config.num_nodes / 3 + 1)
+                .fold(FaultSchedule::default(), |faults, index| {
+                    faults.with_pause(index, pause.clone())
+                });
+            run_n_rounds_and_collect_hashes(config, Vec::new(), true);
+        })
+}
+
+#[test]
+fn partitioned_nodes_would_recover_after_healing() -> Result<(), String> {
+    ConsensusRunnerConfig::new_from_env(4, 0)
+        .and_then(|config| config.parse_extra_config())
+        .map(|mut config| {
+            // Split the subnet into two halves, neither of which can make progress
+            // on its own.
+            let (left, right) = (0..config.num_nodes).partition(|index| index % 2 == 0);
+            config.faults = FaultSchedule::default().with_partition(
+                vec![left, right],
+                FaultWindow::new(Duration::from_secs(1), Some(Duration::from_secs(10))),
+            );
+            run_n_rounds_and_collect_hashes(config, Vec::new(), true);
+        })
+}
+
 fn run_test(
     config: ConsensusRunnerConfig,
     mut modifiers: Vec<ComponentModifier>,
@@ -0,0 +1,348 @@
+//! Per-height breakdown of the finalization latency.
+//!
+//! Every height goes through a sequence of [FinalizationPhase]s, from waiting
+//! for the random beacon until the resulting state is certified. The end of
+//! each phase is recorded as a milestone in the [FinalizationTracer], which
+//! emits a [PhaseSpan] from the previous milestone of the same height to the
+//! sink it was created with. The default sink emits `tracing` spans, leaving
+//! the choice of exporter to the subscriber configured by the replica.
+//!
+//! The milestones of the consensus phases are derived from the timestamps of
+//! the validated consensus pool, so they can also be replayed from a pool
+//! export. The milestones of the execution and certification phases are
+//! recorded by the certifier when it polls the state manager, so they are
+//! late by up to one polling interval of the certifier.
+use ic_interfaces::consensus_pool::{PoolSection, ValidatedConsensusArtifact};
+use ic_types::{consensus::ConsensusMessageHashable, Height, Time};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The maximum number of heights for which milestones are kept. Heights that
+/// never reach the last phase are dropped once this limit is exceeded.
+const MAX_TRACED_HEIGHTS: usize = 500;
+
+/// The phases a height goes through until it is certified, in order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FinalizationPhase {
+    /// From the notarization of the previous height to the start of the round,
+    /// i.e. until the random beacon of the previous height is available.
+    RandomBeacon,
+    /// From the start of the round until the first block proposal is received.
+    BlockProposal,
+    /// Until the first block is notarized.
+    Notarization,
+    /// Until a block is finalized.
+    Finalization,
+    /// Until the batch is delivered to message routing.
+    BatchDelivery,
+    /// Until the state resulting from the batch is ready to be certified, as
+    /// observed by the next poll of the certifier.
+    Execution,
+    /// Until the certification of the state is delivered to the state manager.
+    Certification,
+}
+
+impl FinalizationPhase {
+    /// The phases derived from the consensus pool.
+    pub const CONSENSUS: [FinalizationPhase; 4] = [
+        FinalizationPhase::RandomBeacon,
+        FinalizationPhase::BlockProposal,
+        FinalizationPhase::Notarization,
+        FinalizationPhase::Finalization,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FinalizationPhase::RandomBeacon => "random_beacon",
+            FinalizationPhase::BlockProposal => "block_proposal",
+            FinalizationPhase::Notarization => "notarization",
+            FinalizationPhase::Finalization => "finalization",
+            FinalizationPhase::BatchDelivery => "batch_delivery",
+            FinalizationPhase::Execution => "execution",
+            FinalizationPhase::Certification => "certification",
+        }
+    }
+}
+
+/// The time a height spent in a phase.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhaseSpan {
+    pub height: Height,
+    pub phase: FinalizationPhase,
+    pub start: Time,
+    pub end: Time,
+}
+
+impl PhaseSpan {
+    pub fn duration(&self) -> Duration {
+        self.end.saturating_duration_since(self.start)
+    }
+}
+
+/// A destination for the spans emitted by the [FinalizationTracer].
+pub trait FinalizationTraceSink: Send + Sync {
+    fn record(&self, span: &PhaseSpan);
+}
+
+/// Emits every span as a `tracing` span, carrying the actual start and
+/// duration of the phase as fields.
+#[derive(Default)]
+pub struct TracingSink;
+
+impl FinalizationTraceSink for TracingSink {
+    fn record(&self, span: &PhaseSpan) {
+        let _span = tracing::info_span!(
+            target: "ic_consensus::finalization_latency",
+            "finalization_phase",
+            height = span.height.get(),
+            phase = span.phase.as_str(),
+            start_nanos = span.start.as_nanos_since_unix_epoch(),
+            duration_millis = span.duration().as_millis() as u64,
+        );
+    }
+}
+
+/// Keeps all spans in memory, e.g. to print them after a replay.
+#[derive(Default)]
+pub struct BufferedSink {
+    spans: Mutex<Vec<PhaseSpan>>,
+}
+
+impl BufferedSink {
+    /// Return and clear all spans recorded so far.
+    pub fn take(&self) -> Vec<PhaseSpan> {
+        std::mem::take(&mut *self.spans.lock().unwrap())
+    }
+}
+
+impl FinalizationTraceSink for BufferedSink {
+    fn record(&self, span: &PhaseSpan) {
+        self.spans.lock().unwrap().push(span.clone());
+    }
+}
+
+/// Records the milestones of every height and emits a span for each phase
+/// whose start is known.
+pub struct FinalizationTracer {
+    sink: Arc<dyn FinalizationTraceSink>,
+    milestones: Mutex<BTreeMap<Height, BTreeMap<FinalizationPhase, Time>>>,
+}
+
+impl Default for FinalizationTracer {
+    fn default() -> Self {
+        Self::new(Arc::new(TracingSink))
+    }
+}
+
+impl FinalizationTracer {
+    pub fn new(sink: Arc<dyn FinalizationTraceSink>) -> Self {
+        Self {
+            sink,
+            milestones: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record that the given height completed the given phase at the given
+    /// time. Only the first milestone of each phase is kept. The span of the
+    /// phase starts at the latest earlier milestone of the same height, or at
+    /// the notarization of the previous height for the random beacon phase.
+    pub fn record(&self, height: Height, phase: FinalizationPhase, time: Time) {
+        let mut milestones = self.milestones.lock().unwrap();
+        if milestones
+            .get(&height)
+            .is_some_and(|phases| phases.contains_key(&phase))
+        {
+            return;
+        }
+        let start = match phase {
+            FinalizationPhase::RandomBeacon => height
+                .get()
+                .checked_sub(1)
+                .and_then(|previous| milestones.get(&Height::from(previous)))
+                .and_then(|phases| phases.get(&FinalizationPhase::Notarization))
+                .copied(),
+            _ => milestones
+                .get(&height)
+                .and_then(|phases| phases.range(..phase).next_back())
+                .map(|(_, time)| *time),
+        };
+        if let Some(start) = start {
+            self.sink.record(&PhaseSpan {
+                height,
+                phase,
+                start,
+                end: time,
+            });
+        }
+        milestones.entry(height).or_default().insert(phase, time);
+
+        if phase == FinalizationPhase::Certification {
+            // Keep the certified height to trace the random beacon phase of
+            // the next height.
+            milestones.retain(|h, _| *h >= height);
+        }
+        while milestones.len() > MAX_TRACED_HEIGHTS {
+            milestones.pop_first();
+        }
+    }
+
+    /// Record the milestones of the consensus phases of the given heights, as
+    /// far as they are available in the given validated pool section. The
+    /// pool is only queried for phases that have no milestone yet.
+    pub fn record_from_pool(
+        &self,
+        pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+        heights: impl IntoIterator<Item = Height>,
+    ) {
+        for height in heights {
+            for phase in self.missing_phases(height, &FinalizationPhase::CONSENSUS) {
+                if let Some(time) = consensus_milestone(pool, height, phase) {
+                    self.record(height, phase, time);
+                }
+            }
+        }
+    }
+
+    /// Return those of the given phases for which the given height has no
+    /// milestone yet.
+    fn missing_phases(
+        &self,
+        height: Height,
+        phases: &[FinalizationPhase],
+    ) -> Vec<FinalizationPhase> {
+        let milestones = self.milestones.lock().unwrap();
+        let recorded = milestones.get(&height);
+        phases
+            .iter()
+            .copied()
+            .filter(|phase| !recorded.is_some_and(|recorded| recorded.contains_key(phase)))
+            .collect()
+    }
+}
+
+/// Return the time at which the given height completed the given consensus
+/// phase, according to the timestamps of the validated pool section.
+fn consensus_milestone(
+    pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+    height: Height,
+    phase: FinalizationPhase,
+) -> Option<Time> {
+    fn first<T: ConsensusMessageHashable>(
+        pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+        artifacts: Box<dyn Iterator<Item = T> + '_>,
+    ) -> Option<Time> {
+        artifacts
+            .filter_map(|artifact| pool.get_timestamp(&artifact.get_id()))
+            .min()
+    }
+
+    match phase {
+        FinalizationPhase::RandomBeacon => {
+            let previous = height.get().checked_sub(1)?.into();
+            let beacon = first(pool, pool.random_beacon().get_by_height(previous))?;
+            let notarization = first(pool, pool.notarization().get_by_height(previous))?;
+            Some(beacon.max(notarization))
+        }
+        FinalizationPhase::BlockProposal => {
+            first(pool, pool.block_proposal().get_by_height(height))
+        }
+        FinalizationPhase::Notarization => first(pool, pool.notarization().get_by_height(height)),
+        FinalizationPhase::Finalization => first(pool, pool.finalization().get_by_height(height)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_types::time::UNIX_EPOCH;
+
+    fn at(millis: u64) -> Time {
+        UNIX_EPOCH + Duration::from_millis(millis)
+    }
+
+    fn span(height: u64, phase: FinalizationPhase, start: u64, end: u64) -> PhaseSpan {
+        PhaseSpan {
+            height: Height::from(height),
+            phase,
+            start: at(start),
+            end: at(end),
+        }
+    }
+
+    #[test]
+    fn test_spans_start_at_previous_milestone() {
+        use FinalizationPhase::*;
+        let sink = Arc::new(BufferedSink::default());
+        let tracer = FinalizationTracer::new(sink.clone());
+        let (h1, h2) = (Height::from(1), Height::from(2));
+
+        tracer.record(h1, Notarization, at(100));
+        tracer.record(h2, RandomBeacon, at(150));
+        tracer.record(h2, BlockProposal, at(400));
+        // Later milestones of the same phase are ignored.
+        tracer.record(h2, BlockProposal, at(450));
+        tracer.record(h2, Notarization, at(700));
+        // Phases without a milestone are skipped.
+        tracer.record(h2, BatchDelivery, at(900));
+        tracer.record(h2, Finalization, at(800));
+
+        assert_eq!(
+            sink.take(),
+            vec![
+                span(2, RandomBeacon, 100, 150),
+                span(2, BlockProposal, 150, 400),
+                span(2, Notarization, 400, 700),
+                span(2, BatchDelivery, 700, 900),
+                span(2, Finalization, 700, 800),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_phases_skip_recorded_milestones() {
+        use FinalizationPhase::*;
+        let tracer = FinalizationTracer::new(Arc::new(BufferedSink::default()));
+        let height = Height::from(5);
+        assert_eq!(
+            tracer.missing_phases(height, &FinalizationPhase::CONSENSUS),
+            FinalizationPhase::CONSENSUS.to_vec()
+        );
+
+        tracer.record(height, BlockProposal, at(100));
+        tracer.record(height, Finalization, at(300));
+        assert_eq!(
+            tracer.missing_phases(height, &FinalizationPhase::CONSENSUS),
+            vec![RandomBeacon, Notarization]
+        );
+        assert_eq!(
+            tracer.missing_phases(Height::from(6), &[Notarization]),
+            vec![Notarization]
+        );
+    }
+
+    #[test]
+    fn test_certification_drops_older_heights() {
+        use FinalizationPhase::*;
+        let sink = Arc::new(BufferedSink::default());
+        let tracer = FinalizationTracer::new(sink.clone());
+
+        for height in 1..=3 {
+            tracer.record(Height::from(height), Notarization, at(height * 100));
+        }
+        tracer.record(Height::from(2), Certification, at(1000));
+        assert_eq!(
+            tracer
+                .milestones
+                .lock()
+                .unwrap()
+                .keys()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![Height::from(2), Height::from(3)]
+        );
+    }
+}
@@ -24,6 +24,7 @@ use std::collections::{BTreeMap, BTreeSet};
 
 pub mod bouncer_metrics;
 pub mod crypto;
+pub mod finalization_trace;
 pub mod membership;
 pub mod pool_reader;
 
@@ -1,17 +1,22 @@
 //! Standalone interface for testing application canisters.
 
 use crate::message::{msg_stream_from_file, Message};
+use crate::multi_subnet::{load_topology, MultiSubnet};
+use crate::scenario::{is_scenario_file, load_scenario, Scenario, ScenarioRunner};
 use hex::encode;
-use ic_config::{subnet_config::SubnetConfig, Config};
+use ic_config::{
+    execution_environment::Config as HypervisorConfig, subnet_config::SubnetConfig, Config,
+};
 use ic_crypto_test_utils_ni_dkg::dummy_initial_dkg_transcript_with_master_key;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
 use ic_execution_environment::ExecutionServices;
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
 use ic_interfaces::{
-    execution_environment::{IngressHistoryReader, QueryExecutionError},
+    execution_environment::{IngressHistoryReader, QueryExecutionError, QueryExecutionService},
     messaging::MessageRouting,
 };
+use ic_interfaces_state_manager::StateReader;
 use ic_messaging::MessageRoutingImpl;
 use ic_metrics::MetricsRegistry;
 use ic_protobuf::registry::{
@@ -38,9 +43,10 @@ use ic_types::malicious_flags::MaliciousFlags;
 use ic_types::{
     batch::Batch,
     ingress::{IngressState, IngressStatus, WasmResult},
-    messages::{MessageId, SignedIngress},
+    messages::{MessageId, Query, SignedIngress},
     replica_config::ReplicaConfig,
-    time, CanisterId, NodeId, NumInstructions, PrincipalId, Randomness, RegistryVersion, SubnetId,
+    time::{self, expiry_time_from_now},
+    CanisterId, NodeId, NumInstructions, PrincipalId, Randomness, RegistryVersion, SubnetId, Time,
 };
 use ic_types::{
     batch::{BatchMessages, BlockmakerMetrics},
@@ -52,12 +58,14 @@ use rand::SeedableRng;
 use slog::{Drain, Logger};
 use std::collections::BTreeMap;
 use std::fs::OpenOptions;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::{thread::sleep, time::Duration};
 use tower::util::ServiceExt;
 
 mod message;
+mod multi_subnet;
+mod scenario;
 
 // drun will panic if it takes more than this many batches
 // until a response for a message is received
@@ -65,6 +73,14 @@ const MAX_BATCHES_UNTIL_RESPONSE: u64 = 100_000;
 // how long to wait between batches
 const WAIT_PER_BATCH: Duration = Duration::from_millis(5);
 
+/// The input of a drun run.
+enum Input<M> {
+    /// A text file containing one message per line.
+    Messages(M),
+    /// A structured scenario (see [`Scenario`]).
+    Scenario(Scenario),
+}
+
 pub struct DrunOptions {
     pub msg_filename: String,
     pub cfg: Config,
@@ -72,22 +88,212 @@ pub struct DrunOptions {
     pub log_file: Option<PathBuf>,
     pub instruction_limit: Option<u64>,
     pub subnet_type: SubnetType,
+    /// A JSON or YAML file describing the topology of multiple subnets.
+    /// If no topology is given, drun runs a single subnet of type `subnet_type`.
+    pub topology: Option<PathBuf>,
 }
 
-/// Deliver a single message to the Message Routing layer
-fn deliver_message(
-    msg: SignedIngress,
-    message_routing: &dyn MessageRouting,
-    ingress_hist_reader: &dyn IngressHistoryReader,
+/// The subnet(s) that drun delivers messages to.
+///
+/// A backend with multiple subnets additionally overrides the methods taking
+/// subnet indices; their defaults describe a single subnet.
+pub(crate) trait Backend {
+    /// Executes an ingress message until it has completed on the subnet
+    /// hosting its (effective) canister.
+    fn execute_ingress(&mut self, msg: SignedIngress) -> Result<WasmResult, UserError>;
+
+    /// Executes a query on the subnet hosting its receiver.
+    async fn execute_query(&mut self, query: Query) -> Result<WasmResult, UserError>;
+
+    /// Returns the cycles balance of a canister or `None` if the canister does not exist.
+    fn cycles_balance(&self, canister_id: CanisterId) -> Option<u128>;
+
+    /// Advances the time of all subnets and executes a round
+    /// (so that due timers and heartbeats are executed).
+    fn advance_time(&mut self, duration: Duration);
+
+    /// Returns the ingress expiry time for new messages.
+    fn expiry_time(&self) -> Time;
+
+    /// Returns the number of subnets.
+    fn num_subnets(&self) -> usize {
+        1
+    }
+
+    /// Executes an ingress message creating a canister on the subnet with the given index.
+    fn create_canister(
+        &mut self,
+        msg: SignedIngress,
+        _subnet: usize,
+    ) -> Result<WasmResult, UserError> {
+        self.execute_ingress(msg)
+    }
+
+    /// Migrates a canister to the subnet with the given index.
+    fn migrate_canister(&mut self, _canister_id: CanisterId, _subnet: usize) -> Result<(), String> {
+        Err("Migrating a canister requires a topology with multiple subnets.".to_string())
+    }
+}
+
+/// A single subnet whose components are driven directly through Message Routing.
+struct SingleSubnet {
+    message_routing: MessageRoutingImpl,
+    ingress_hist_reader: Box<dyn IngressHistoryReader>,
+    query_handler: QueryExecutionService,
+    state_manager: Arc<StateManagerImpl>,
+    subnet_id: SubnetId,
     extra_batches: u64,
-) {
-    let message_id = msg.id();
+    // The offset of the time of the subnet from the wall-clock time
+    // accumulated by advancing the time.
+    time_offset: Duration,
+    _metrics_endpoint: MetricsHttpEndpoint,
+}
+
+impl SingleSubnet {
+    /// Sets up the components of a subnet of the given type. Must be called
+    /// within a Tokio runtime, which serves the metrics endpoint.
+    fn new(
+        mut cfg: Config,
+        subnet_type: SubnetType,
+        instruction_limit: Option<u64>,
+        extra_batches: u64,
+        log: Logger,
+    ) -> Self {
+        let subnet_config = subnet_config(subnet_type, instruction_limit, &mut cfg.hypervisor);
+
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(0));
+        let root_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        let replica_config = ReplicaConfig {
+            node_id: NodeId::from(PrincipalId::new_node_test_id(27)),
+            subnet_id,
+        };
+
+        let metrics_registry = MetricsRegistry::global();
+        let registry = get_registry(
+            &metrics_registry,
+            subnet_id,
+            root_subnet_id,
+            subnet_type,
+            &[replica_config.node_id],
+        );
+
+        let cycles_account_manager = Arc::new(CyclesAccountManager::new(
+            subnet_config.scheduler_config.max_instructions_per_message,
+            subnet_type,
+            subnet_id,
+            subnet_config.cycles_account_manager_config,
+        ));
 
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
-    // print result after waiting, to not interleave the result
-    // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
-    print_ingress_result(&message_id, ingress_hist_reader);
+        let state_manager = Arc::new(StateManagerImpl::new(
+            Arc::new(FakeVerifier::new()),
+            replica_config.subnet_id,
+            subnet_type,
+            log.clone().into(),
+            &metrics_registry,
+            &cfg.state_manager,
+            None,
+            ic_types::malicious_flags::MaliciousFlags::default(),
+        ));
+
+        let (completed_execution_messages_tx, _) = tokio::sync::mpsc::channel(1);
+
+        let (_, ingress_history_writer, ingress_hist_reader, query_handler, scheduler) =
+            ExecutionServices::setup_execution(
+                log.clone().into(),
+                &metrics_registry,
+                replica_config.subnet_id,
+                subnet_type,
+                subnet_config.scheduler_config,
+                cfg.hypervisor.clone(),
+                Arc::clone(&cycles_account_manager),
+                Arc::clone(&state_manager) as Arc<_>,
+                state_manager.get_fd_factory(),
+                completed_execution_messages_tx,
+                &state_manager.state_layout().tmp(),
+            )
+            .into_parts();
+
+        let runtime = tokio::runtime::Handle::current();
+        let metrics_endpoint =
+            MetricsHttpEndpoint::new(runtime.clone(), cfg.metrics, metrics_registry.clone(), &log);
+
+        let message_routing = MessageRoutingImpl::new(
+            Arc::clone(&state_manager) as _,
+            Arc::clone(&state_manager) as _,
+            Arc::clone(&ingress_history_writer) as _,
+            scheduler,
+            cfg.hypervisor,
+            cycles_account_manager,
+            replica_config.subnet_id,
+            &metrics_registry,
+            log.clone().into(),
+            Arc::clone(&registry) as _,
+            MaliciousFlags::default(),
+        );
+
+        SingleSubnet {
+            message_routing,
+            ingress_hist_reader,
+            query_handler,
+            state_manager,
+            subnet_id: replica_config.subnet_id,
+            extra_batches,
+            time_offset: Duration::ZERO,
+            _metrics_endpoint: metrics_endpoint,
+        }
+    }
+}
+
+impl Backend for SingleSubnet {
+    fn execute_ingress(&mut self, msg: SignedIngress) -> Result<WasmResult, UserError> {
+        let message_id = msg.id();
+        let result = execute_ingress_message(
+            &self.message_routing,
+            msg,
+            &message_id,
+            self.ingress_hist_reader.as_ref(),
+            self.time_offset,
+        );
+        // return the result after waiting, to not interleave the result
+        // with debug.print messages from subsequent calls. revise after DFN-1269.
+        wait_extra_batches(&self.message_routing, self.extra_batches, self.time_offset);
+        result
+    }
+
+    async fn execute_query(&mut self, query: Query) -> Result<WasmResult, UserError> {
+        let (_ni_dkg_transcript, secret_key) =
+            dummy_initial_dkg_transcript_with_master_key(&mut StdRng::seed_from_u64(42));
+        certify_latest_state_helper(self.state_manager.clone(), &secret_key, self.subnet_id);
+        match self
+            .query_handler
+            .clone()
+            .oneshot((query, None))
+            .await
+            .unwrap()
+        {
+            Ok((result, _)) => result,
+            Err(QueryExecutionError::CertifiedStateUnavailable) => {
+                panic!("Certified state unavailable for query call.")
+            }
+        }
+    }
+
+    fn cycles_balance(&self, canister_id: CanisterId) -> Option<u128> {
+        self.state_manager
+            .get_latest_state()
+            .get_ref()
+            .canister_state(&canister_id)
+            .map(|canister| canister.system_state.balance().get())
+    }
+
+    fn advance_time(&mut self, duration: Duration) {
+        self.time_offset += duration;
+        wait_extra_batches(&self.message_routing, 1, self.time_offset);
+    }
+
+    fn expiry_time(&self) -> Time {
+        expiry_time_from_now() + self.time_offset
+    }
 }
 
 fn setup_logger(log_file: PathBuf) -> Logger {
@@ -163,15 +369,13 @@ fn get_registry(
     registry_client
 }
 
-pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
-    let DrunOptions {
-        msg_filename,
-        mut cfg,
-        extra_batches,
-        log_file,
-        instruction_limit,
-        subnet_type,
-    } = uo;
+/// Returns the configuration of a subnet of the given type
+/// and updates the hypervisor configuration accordingly.
+fn subnet_config(
+    subnet_type: SubnetType,
+    instruction_limit: Option<u64>,
+    hypervisor_config: &mut HypervisorConfig,
+) -> SubnetConfig {
     // Hardcoded magic values to create a ReplicaConfig that parses.
     let mut subnet_config = SubnetConfig::new(subnet_type);
 
@@ -185,7 +389,7 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
         subnet_config
             .scheduler_config
             .max_instructions_per_message_without_dts = instruction_limit;
-        cfg.hypervisor.max_query_call_graph_instructions = instruction_limit;
+        hypervisor_config.max_query_call_graph_instructions = instruction_limit;
     }
 
     // DTS aborts uncompleted messsages if they reach a checkpoint and retries them
@@ -195,130 +399,87 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
     if subnet_type == SubnetType::System {
         disable_dts(&mut subnet_config);
     }
+    subnet_config
+}
+
+pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
+    let DrunOptions {
+        msg_filename,
+        cfg,
+        extra_batches,
+        log_file,
+        instruction_limit,
+        subnet_type,
+        topology,
+    } = uo;
 
-    let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(0));
-    let root_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
-    let replica_config = ReplicaConfig {
-        node_id: NodeId::from(PrincipalId::new_node_test_id(27)),
-        subnet_id,
+    let input = if is_scenario_file(&msg_filename) {
+        Input::Scenario(load_scenario(Path::new(&msg_filename))?)
+    } else {
+        Input::Messages(msg_stream_from_file(&msg_filename)?)
     };
 
-    let msg_stream = msg_stream_from_file(&msg_filename)?;
+    if let Some(topology) = topology {
+        let topology = load_topology(&topology)?;
+        // The subnets are `StateMachine`s that block on their own runtimes
+        // and thus must neither be used nor dropped in an async context.
+        return tokio::task::block_in_place(|| {
+            let mut backend =
+                MultiSubnet::new(&topology, cfg.hypervisor, instruction_limit, extra_batches)?;
+            futures::executor::block_on(run_input(&mut backend, input))
+        });
+    }
+
     let log = match log_file {
         Some(log_file) => setup_logger(log_file),
         None => slog::Logger::root(slog::Discard, slog::o!()),
     };
+    let mut backend = SingleSubnet::new(cfg, subnet_type, instruction_limit, extra_batches, log);
+    run_input(&mut backend, input).await
+}
 
-    let metrics_registry = MetricsRegistry::global();
-    let registry = get_registry(
-        &metrics_registry,
-        subnet_id,
-        root_subnet_id,
-        subnet_type,
-        &[replica_config.node_id],
-    );
-
-    let cycles_account_manager = Arc::new(CyclesAccountManager::new(
-        subnet_config.scheduler_config.max_instructions_per_message,
-        subnet_type,
-        subnet_id,
-        subnet_config.cycles_account_manager_config,
-    ));
-
-    let state_manager = Arc::new(StateManagerImpl::new(
-        Arc::new(FakeVerifier::new()),
-        replica_config.subnet_id,
-        subnet_type,
-        log.clone().into(),
-        &metrics_registry,
-        &cfg.state_manager,
-        None,
-        ic_types::malicious_flags::MaliciousFlags::default(),
-    ));
-
-    let (completed_execution_messages_tx, _) = tokio::sync::mpsc::channel(1);
-
-    let (_, ingress_history_writer, ingress_hist_reader, query_handler, scheduler) =
-        ExecutionServices::setup_execution(
-            log.clone().into(),
-            &metrics_registry,
-            replica_config.subnet_id,
-            subnet_type,
-            subnet_config.scheduler_config,
-            cfg.hypervisor.clone(),
-            Arc::clone(&cycles_account_manager),
-            Arc::clone(&state_manager) as Arc<_>,
-            state_manager.get_fd_factory(),
-            completed_execution_messages_tx,
-            &state_manager.state_layout().tmp(),
-        )
-        .into_parts();
-
-    let runtime = tokio::runtime::Handle::current();
-    let _metrics_endpoint =
-        MetricsHttpEndpoint::new(runtime.clone(), cfg.metrics, metrics_registry.clone(), &log);
-
-    let message_routing = MessageRoutingImpl::new(
-        Arc::clone(&state_manager) as _,
-        Arc::clone(&state_manager) as _,
-        Arc::clone(&ingress_history_writer) as _,
-        scheduler,
-        cfg.hypervisor,
-        cycles_account_manager,
-        replica_config.subnet_id,
-        &metrics_registry,
-        log.clone().into(),
-        Arc::clone(&registry) as _,
-        MaliciousFlags::default(),
-    );
-
-    for parse_result in msg_stream {
-        match parse_result? {
-            Message::Install(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
-            }
+async fn run_input<B: Backend>(
+    backend: &mut B,
+    input: Input<impl Iterator<Item = Result<Message, String>>>,
+) -> Result<(), String> {
+    match input {
+        Input::Scenario(scenario) => ScenarioRunner::new(backend).run(scenario).await,
+        Input::Messages(msg_stream) => {
+            for parse_result in msg_stream {
+                match parse_result? {
+                    Message::Install(msg) | Message::Ingress(msg) => {
+                        print_ingress_result(backend.execute_ingress(msg));
+                    }
 
-            Message::Query(q) => {
-                let (_ni_dkg_transcript, secret_key) =
-                    dummy_initial_dkg_transcript_with_master_key(&mut StdRng::seed_from_u64(42));
-                certify_latest_state_helper(
-                    state_manager.clone(),
-                    &secret_key,
-                    replica_config.subnet_id,
-                );
-                let query_result = match query_handler.clone().oneshot((q, None)).await.unwrap() {
-                    Ok((result, _)) => result,
-                    Err(QueryExecutionError::CertifiedStateUnavailable) => {
-                        panic!("Certified state unavailable for query call.")
+                    Message::Query(q) => {
+                        print_query_result(backend.execute_query(q).await);
                     }
-                };
-                print_query_result(query_result);
-            }
 
-            Message::Ingress(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
-            }
+                    Message::Create(msg, subnet) => {
+                        check_subnet_index(backend, subnet)?;
+                        print_ingress_result(backend.create_canister(msg, subnet));
+                    }
 
-            Message::Create(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
+                    Message::Migrate(canister_id, subnet) => {
+                        check_subnet_index(backend, subnet)?;
+                        backend.migrate_canister(canister_id, subnet)?;
+                        println!("migrate Ok");
+                    }
+                }
             }
+            Ok(())
         }
     }
+}
+
+fn check_subnet_index<B: Backend>(backend: &B, subnet: usize) -> Result<(), String> {
+    if subnet >= backend.num_subnets() {
+        return Err(format!(
+            "Subnet index {} is out of range (there are {} subnets).",
+            subnet,
+            backend.num_subnets()
+        ));
+    }
     Ok(())
 }
 
@@ -341,22 +502,14 @@ fn print_query_result(res: Result<WasmResult, UserError>) {
     }
 }
 
-fn print_ingress_result(message_id: &MessageId, ingress_hist_reader: &dyn IngressHistoryReader) {
-    let status = (ingress_hist_reader.get_latest_status())(message_id);
+fn print_ingress_result(res: Result<WasmResult, UserError>) {
     print!("ingress ");
-    match status {
-        IngressStatus::Known {
-            state: IngressState::Completed(result),
-            ..
-        } => {
+    match res {
+        Ok(result) => {
             print!("Completed: ");
             print_wasm_result(result)
         }
-        IngressStatus::Known {
-            state: IngressState::Failed(error),
-            ..
-        } => println!("Err: {}", error),
-        _ => panic!("Ingress message has not finished processing."),
+        Err(error) => println!("Err: {}", error),
     };
 }
 
@@ -374,7 +527,11 @@ fn get_random_seed() -> [u8; 32] {
     seed.try_into().unwrap()
 }
 
-fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -> Batch {
+fn build_batch(
+    message_routing: &dyn MessageRouting,
+    msgs: Vec<SignedIngress>,
+    time_offset: Duration,
+) -> Batch {
     Batch {
         batch_number: message_routing.expected_batch_height(),
         batch_summary: None,
@@ -388,7 +545,7 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         idkg_pre_signature_ids: BTreeMap::new(),
         ni_dkg_ids: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
-        time: time::current_time(),
+        time: time::current_time() + time_offset,
         consensus_responses: vec![],
         blockmaker_metrics: BlockmakerMetrics::new_for_test(),
         replica_version: ReplicaVersion::default(),
@@ -403,8 +560,9 @@ fn execute_ingress_message(
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
+    time_offset: Duration,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut batch = build_batch(message_routing, vec![msg], time_offset);
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
         // In the first batch we try to send the ingress message itself. If it fails, we
         // repeat with the same batch.
@@ -415,7 +573,7 @@ fn execute_ingress_message(
         // potential inter-canister messages that the ingress message may have
         // triggered.
         if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
+            batch = build_batch(message_routing, vec![], time_offset)
         }
         sleep(WAIT_PER_BATCH);
 
@@ -461,10 +619,14 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(
+    message_routing: &dyn MessageRouting,
+    extra_batches: u64,
+    time_offset: Duration,
+) {
     for _ in 0..extra_batches {
         loop {
-            let batch = build_batch(message_routing, vec![]);
+            let batch = build_batch(message_routing, vec![], time_offset);
             let ok = message_routing.deliver_batch(batch).is_ok();
             sleep(WAIT_PER_BATCH);
             if ok {
@@ -20,6 +20,8 @@ const ARG_MESSAGES: &str = "messages";
 const ARG_EXTRA_BATCHES: &str = "extra-batches";
 const ARG_INSTRUCTION_LIMIT: &str = "instruction-limit";
 const ARG_SUBNET_TYPE: &str = "subnet-type";
+const ARG_TOPOLOGY: &str = "topology";
+const ARG_WASM_PROFILE_DIR: &str = "wasm-profile-dir";
 
 const GB: u64 = 1024 * 1024 * 1024;
 const MAIN_MEMORY_CAPACITY: NumBytes = NumBytes::new(16 * GB);
@@ -73,6 +75,13 @@ async fn drun_main() -> Result<(), String> {
         hypervisor_config.max_canister_memory_size_wasm64 =
             hypervisor_config.embedders_config.max_wasm64_memory_size
                 + hypervisor_config.embedders_config.max_stable_memory_size;
+        if let Some(dir) = matches.get_one::<String>(ARG_WASM_PROFILE_DIR) {
+            hypervisor_config
+                .embedders_config
+                .feature_flags
+                .wasm_profiling = FlagStatus::Enabled;
+            hypervisor_config.embedders_config.wasm_profiling_output_dir = Some(PathBuf::from(dir));
+        }
 
         let cfg = Config::load_with_default(&source, default_config).unwrap_or_else(|err| {
             eprintln!("Failed to load config:\n  {}", err);
@@ -108,6 +117,8 @@ async fn drun_main() -> Result<(), String> {
             })
             .unwrap_or(SubnetType::System);
 
+        let topology = matches.get_one::<String>(ARG_TOPOLOGY).map(PathBuf::from);
+
         let uo = DrunOptions {
             msg_filename: matches.get_one::<String>(ARG_MESSAGES).unwrap().clone(),
             cfg,
@@ -115,6 +126,7 @@ async fn drun_main() -> Result<(), String> {
             log_file,
             instruction_limit,
             subnet_type,
+            topology,
         };
         run_drun(uo).await
     })
@@ -149,7 +161,10 @@ fn get_arg_matches() -> ArgMatches {
             Arg::new(ARG_MESSAGES)
                 .required(true)
                 .value_name("Query/Ingress Messages")
-                .help("Text file containing one message per line."),
+                .help(
+                    "Text file containing one message per line \
+                     or a JSON/YAML scenario (*.json, *.yaml, *.yml).",
+                ),
         )
         .arg(
             Arg::new(ARG_LOG_FILE)
@@ -172,5 +187,25 @@ fn get_arg_matches() -> ArgMatches {
                 .value_name("Subnet Type")
                 .num_args(1),
         )
+        .arg(
+            Arg::new(ARG_TOPOLOGY)
+                .long(ARG_TOPOLOGY)
+                .help(
+                    "JSON/YAML file describing multiple subnets exchanging XNet messages \
+                     (the subnet type is then given per subnet).",
+                )
+                .value_name("Topology")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(ARG_WASM_PROFILE_DIR)
+                .long(ARG_WASM_PROFILE_DIR)
+                .help(
+                    "Profile the executed canister code and write one flamegraph \
+                     (collapsed stacks) per canister to the given directory.",
+                )
+                .value_name("Directory")
+                .num_args(1),
+        )
         .get_matches()
 }
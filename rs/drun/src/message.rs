@@ -5,10 +5,11 @@ use ic_execution_environment::execution::upgrade::ENHANCED_ORTHOGONAL_PERSISTENC
 use ic_management_canister_types_private::{
     self as ic00, CanisterInstallModeV2, CanisterUpgradeOptions, Payload, WasmMemoryPersistence,
 };
+use ic_test_utilities_types::messages::SignedIngressBuilder;
 use ic_types::{
     messages::{Query, QuerySource, SignedIngress},
     time::expiry_time_from_now,
-    PrincipalId, UserId,
+    PrincipalId, Time, UserId,
 };
 
 use std::{
@@ -24,7 +25,10 @@ pub(crate) enum Message {
     Ingress(SignedIngress),
     Query(Query),
     Install(SignedIngress),
-    Create(SignedIngress),
+    /// Creates a canister on the subnet with the given index.
+    Create(SignedIngress, usize),
+    /// Migrates a canister to the subnet with the given index.
+    Migrate(CanisterId, usize),
 }
 
 #[derive(Debug)]
@@ -152,33 +156,37 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     match &tokens[..] {
         [] => Err("Too few arguments.".to_string()),
         ["ingress", canister_id, method_name, payload] => {
-            use ic_test_utilities_types::messages::SignedIngressBuilder;
-
             let canister_id = parse_canister_id(canister_id)?;
             let method_name = validate_method_name(method_name)?;
             let method_payload = parse_octet_string(payload)?;
 
-            let signed_ingress = SignedIngressBuilder::new()
-                // `source` should become a self-authenticating id according
-                // to https://internetcomputer.org/docs/current/references/ic-interface-spec#id-classes
-                .canister_id(canister_id)
-                .method_name(method_name)
-                .method_payload(method_payload)
-                .nonce(nonce)
-                .build();
-            Ok(Message::Ingress(signed_ingress))
+            Ok(Message::Ingress(ingress_message(
+                canister_id,
+                method_name,
+                method_payload,
+                nonce,
+                expiry_time_from_now(),
+            )))
         }
-        ["query", canister_id, method_name, payload] => Ok(Message::Query(Query {
-            source: QuerySource::User {
-                user_id: UserId::from(PrincipalId::new_anonymous()),
-                ingress_expiry: expiry_time_from_now().as_nanos_since_unix_epoch(),
-                nonce: Some(nonce.to_le_bytes().to_vec()),
-            },
-            receiver: parse_canister_id(canister_id)?,
-            method_name: validate_method_name(method_name)?,
-            method_payload: parse_octet_string(payload)?,
-        })),
-        ["create"] => parse_create(nonce),
+        ["query", canister_id, method_name, payload] => Ok(Message::Query(query_message(
+            parse_canister_id(canister_id)?,
+            validate_method_name(method_name)?,
+            parse_octet_string(payload)?,
+            nonce,
+            expiry_time_from_now(),
+        ))),
+        ["create"] => Ok(Message::Create(
+            create_canister_message(None, nonce, expiry_time_from_now()),
+            0,
+        )),
+        ["create", subnet] => Ok(Message::Create(
+            create_canister_message(None, nonce, expiry_time_from_now()),
+            parse_subnet_index(subnet)?,
+        )),
+        ["migrate", canister_id, subnet] => Ok(Message::Migrate(
+            parse_canister_id(canister_id)?,
+            parse_subnet_index(subnet)?,
+        )),
         ["install", canister_id, wasm_file, payload] => {
             parse_install(nonce, canister_id, payload, wasm_file, "install")
         }
@@ -195,7 +203,7 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     }
 }
 
-fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
+pub(crate) fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
     use std::str::FromStr;
     match PrincipalId::from_str(canister_id) {
         Ok(id) => Ok(CanisterId::unchecked_from_principal(id)),
@@ -206,17 +214,81 @@ fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
     }
 }
 
-fn parse_create(nonce: u64) -> Result<Message, String> {
-    use ic_test_utilities_types::messages::SignedIngressBuilder;
+fn parse_subnet_index(subnet: &str) -> Result<usize, String> {
+    subnet
+        .parse()
+        .map_err(|e| format!("Failed to parse subnet index {}: {}", subnet, e))
+}
+
+/// Returns an ingress message calling the given method of the given canister.
+pub(crate) fn ingress_message(
+    canister_id: CanisterId,
+    method_name: String,
+    method_payload: Vec<u8>,
+    nonce: u64,
+    expiry_time: Time,
+) -> SignedIngress {
+    SignedIngressBuilder::new()
+        // `source` should become a self-authenticating id according
+        // to https://internetcomputer.org/docs/current/references/ic-interface-spec#id-classes
+        .canister_id(canister_id)
+        .method_name(method_name)
+        .method_payload(method_payload)
+        .nonce(nonce)
+        .expiry_time(expiry_time)
+        .build()
+}
 
-    let signed_ingress = SignedIngressBuilder::new()
+/// Returns a query calling the given method of the given canister.
+pub(crate) fn query_message(
+    receiver: CanisterId,
+    method_name: String,
+    method_payload: Vec<u8>,
+    nonce: u64,
+    expiry_time: Time,
+) -> Query {
+    Query {
+        source: QuerySource::User {
+            user_id: UserId::from(PrincipalId::new_anonymous()),
+            ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+            nonce: Some(nonce.to_le_bytes().to_vec()),
+        },
+        receiver,
+        method_name,
+        method_payload,
+    }
+}
+
+/// Returns an ingress message creating a canister with the given amount of cycles
+/// (or the default amount of cycles if no amount is given).
+pub(crate) fn create_canister_message(
+    cycles: Option<u128>,
+    nonce: u64,
+    expiry_time: Time,
+) -> SignedIngress {
+    SignedIngressBuilder::new()
         .method_name(ic00::Method::ProvisionalCreateCanisterWithCycles)
         .canister_id(ic00::IC_00)
-        .method_payload(ic00::ProvisionalCreateCanisterWithCyclesArgs::new(None, None).encode())
+        .method_payload(ic00::ProvisionalCreateCanisterWithCyclesArgs::new(cycles, None).encode())
         .nonce(nonce)
-        .build();
+        .expiry_time(expiry_time)
+        .build()
+}
 
-    Ok(Message::Create(signed_ingress))
+/// Returns an ingress message topping up the given canister with the given amount of cycles.
+pub(crate) fn top_up_canister_message(
+    canister_id: CanisterId,
+    cycles: u128,
+    nonce: u64,
+    expiry_time: Time,
+) -> SignedIngress {
+    SignedIngressBuilder::new()
+        .method_name(ic00::Method::ProvisionalTopUpCanister)
+        .canister_id(ic00::IC_00)
+        .method_payload(ic00::ProvisionalTopUpCanisterArgs::new(canister_id, cycles).encode())
+        .nonce(nonce)
+        .expiry_time(expiry_time)
+        .build()
 }
 
 fn contains_icp_private_custom_section(wasm_binary: &[u8], name: &str) -> Result<bool, String> {
@@ -241,8 +313,29 @@ fn parse_install(
     wasm_file: &str,
     mode: &str,
 ) -> Result<Message, String> {
-    use ic_test_utilities_types::messages::SignedIngressBuilder;
+    let canister_id = parse_canister_id(canister_id)?;
+    let payload = parse_octet_string(payload)?;
+    install_code_message(
+        canister_id,
+        wasm_file,
+        payload,
+        mode,
+        nonce,
+        expiry_time_from_now(),
+    )
+    .map(Message::Install)
+}
 
+/// Returns an ingress message installing the given Wasm file on the given canister
+/// in the given mode (`install`, `reinstall` or `upgrade`).
+pub(crate) fn install_code_message(
+    canister_id: CanisterId,
+    wasm_file: &str,
+    payload: Vec<u8>,
+    mode: &str,
+    nonce: u64,
+    expiry_time: Time,
+) -> Result<SignedIngress, String> {
     let mut wasm_data = Vec::new();
     let mut wasm_file = File::open(wasm_file)
         .map_err(|e| format!("Could not open wasm file: {} - Error: {}", wasm_file, e))?;
@@ -250,9 +343,6 @@ fn parse_install(
         .read_to_end(&mut wasm_data)
         .map_err(|e| e.to_string())?;
 
-    let canister_id = parse_canister_id(canister_id)?;
-    let payload = parse_octet_string(payload)?;
-
     let install_mode = match mode {
         "install" => CanisterInstallModeV2::Install,
         "reinstall" => CanisterInstallModeV2::Reinstall,
@@ -271,11 +361,11 @@ fn parse_install(
             }))
         }
         _ => {
-            return Err(String::from("Unsupported install mode: {mode}"));
+            return Err(format!("Unsupported install mode: {mode}"));
         }
     };
 
-    let signed_ingress = SignedIngressBuilder::new()
+    Ok(SignedIngressBuilder::new()
         // `source` should become a self-authenticating id according
         // to https://internetcomputer.org/docs/current/references/ic-interface-spec#id-classes
         .canister_id(ic00::IC_00)
@@ -285,8 +375,8 @@ fn parse_install(
                 .encode(),
         )
         .nonce(nonce)
-        .build();
-    Ok(Message::Install(signed_ingress))
+        .expiry_time(expiry_time)
+        .build())
 }
 
 fn validate_method_name(method_name: &str) -> Result<String, String> {
@@ -380,7 +470,7 @@ fn parse_escape(chars: &mut Chars<'_>, radix: Radix) -> Result<u8, String> {
     }
 }
 
-fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+pub(crate) fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
     if let Some(s) = s.strip_prefix("0x") {
         decode(s).map_err(|e| e.to_string())
     } else {
@@ -397,7 +487,7 @@ enum Radix {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ic_test_utilities_types::{ids::canister_test_id, messages::SignedIngressBuilder};
+    use ic_test_utilities_types::ids::canister_test_id;
     use std::io::Cursor;
 
     const APP_CANISTER_URL: &str = "ryjl3-tyaaa-aaaaa-aaaba-cai";
@@ -492,6 +582,24 @@ mod tests {
         assert!(parse_message(s, 0).is_err());
     }
 
+    #[test]
+    fn test_parse_create_and_migrate() {
+        match parse_message("create", 0).unwrap() {
+            Message::Create(_, subnet) => assert_eq!(subnet, 0),
+            msg => panic!("parse_message() returned an unexpected message: {:?}", msg),
+        }
+        match parse_message("create 2", 0).unwrap() {
+            Message::Create(_, subnet) => assert_eq!(subnet, 2),
+            msg => panic!("parse_message() returned an unexpected message: {:?}", msg),
+        }
+        assert_eq!(
+            parse_message(&format!("migrate {} 1", APP_CANISTER_URL), 0).unwrap(),
+            Message::Migrate(canister_test_id(APP_CANISTER_ID), 1)
+        );
+        assert!(parse_message("create -1", 0).is_err());
+        assert!(parse_message(&format!("migrate {}", APP_CANISTER_URL), 0).is_err());
+    }
+
     #[test]
     fn test_illegal_method_name_must_fail() {
         let s = &format!("query {} 0read \"\\xzz\"", APP_CANISTER_URL);
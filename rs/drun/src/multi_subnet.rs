@@ -0,0 +1,580 @@
+//! Multiple subnets exchanging XNet messages.
+//!
+//! Every subnet of a [`Topology`] is backed by a `StateMachine`. The subnets share
+//! a registry holding the routing table of the topology and induct the XNet messages
+//! of the other subnets from certified stream slices, i.e., inter-canister calls across
+//! subnets go through the same stream encoding as on the IC.
+//!
+//! ```yaml
+//! subnets:
+//!   - subnet_type: system
+//!   - subnet_type: application
+//!     canister_ranges:
+//!       - { start: "rwlgt-iiaaa-aaaaa-aaaaa-cai", end: "rrkah-fqaaa-aaaaa-aaaaq-cai" }
+//! ```
+
+use crate::message::parse_canister_id;
+use crate::scenario::parse_json_or_yaml;
+use crate::{subnet_config, Backend, MAX_BATCHES_UNTIL_RESPONSE};
+use ic_config::execution_environment::Config as HypervisorConfig;
+use ic_error_types::{ErrorCode, UserError};
+use ic_management_canister_types_private::{self as ic00, Method, Payload};
+use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
+use ic_registry_routing_table::{
+    CanisterIdRange, CanisterIdRanges, RoutingTable, CANISTER_IDS_PER_SUBNET,
+};
+use ic_registry_subnet_type::SubnetType;
+use ic_state_machine_tests::{
+    add_global_registry_records, add_initial_registry_records, StateMachine, StateMachineBuilder,
+    StateMachineConfig, SubmitIngressError, Subnets,
+};
+use ic_types::{
+    ingress::{IngressState, IngressStatus, WasmResult},
+    messages::{extract_effective_canister_id, Query, QuerySource, SignedIngress},
+    time::expiry_time_from_now,
+    CanisterId, PrincipalId, SubnetId, Time,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// The subnets run by drun in multi-subnet mode.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Topology {
+    /// The first subnet is the root (NNS) subnet.
+    pub subnets: Vec<SubnetSpec>,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SubnetSpec {
+    #[serde(default)]
+    pub subnet_type: SubnetType,
+    /// The canister ID ranges routed to the subnet. If no ranges are given,
+    /// then the `i`-th subnet gets the `i`-th range of `CANISTER_IDS_PER_SUBNET` canister IDs.
+    #[serde(default)]
+    pub canister_ranges: Vec<CanisterRange>,
+}
+
+/// An inclusive range of textual canister IDs.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CanisterRange {
+    pub start: String,
+    pub end: String,
+}
+
+impl CanisterRange {
+    fn parse(&self) -> Result<CanisterIdRange, String> {
+        Ok(CanisterIdRange {
+            start: parse_canister_id(&self.start)?,
+            end: parse_canister_id(&self.end)?,
+        })
+    }
+}
+
+/// Loads a topology from a JSON or YAML file (depending on its extension).
+pub(crate) fn load_topology(path: &Path) -> Result<Topology, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read topology {}: {}", path.display(), e))?;
+    parse_json_or_yaml(&contents, path.extension().and_then(|ext| ext.to_str()))
+        .map_err(|e| format!("Failed to parse topology {}: {}", path.display(), e))
+}
+
+/// Returns the routing table of the topology.
+fn routing_table(topology: &Topology, subnet_ids: &[SubnetId]) -> Result<RoutingTable, String> {
+    let mut routing_table = RoutingTable::new();
+    for (index, (spec, subnet_id)) in topology.subnets.iter().zip(subnet_ids).enumerate() {
+        let ranges = if spec.canister_ranges.is_empty() {
+            let index = index as u64;
+            vec![CanisterIdRange {
+                start: CanisterId::from_u64(index * CANISTER_IDS_PER_SUBNET),
+                end: CanisterId::from_u64((index + 1) * CANISTER_IDS_PER_SUBNET - 1),
+            }]
+        } else {
+            spec.canister_ranges
+                .iter()
+                .map(CanisterRange::parse)
+                .collect::<Result<_, _>>()?
+        };
+        for range in ranges {
+            routing_table
+                .insert(range, *subnet_id)
+                .map_err(|e| format!("Invalid canister range {:?}: {:?}", range, e))?;
+        }
+    }
+    Ok(routing_table)
+}
+
+#[derive(Default)]
+struct SubnetsImpl {
+    subnets: RwLock<BTreeMap<SubnetId, Arc<StateMachine>>>,
+}
+
+impl Subnets for SubnetsImpl {
+    fn insert(&self, state_machine: Arc<StateMachine>) {
+        self.subnets
+            .write()
+            .unwrap()
+            .insert(state_machine.get_subnet_id(), state_machine);
+    }
+
+    fn get(&self, subnet_id: SubnetId) -> Option<Arc<StateMachine>> {
+        self.subnets.read().unwrap().get(&subnet_id).cloned()
+    }
+}
+
+/// Multiple subnets (in the order of the topology) executing rounds in lockstep.
+pub(crate) struct MultiSubnet {
+    subnets: Vec<Arc<StateMachine>>,
+    routing_table: RoutingTable,
+    extra_batches: u64,
+    // The offset of the time of the subnets from the wall-clock time
+    // accumulated by advancing the time.
+    time_offset: Duration,
+}
+
+impl MultiSubnet {
+    pub(crate) fn new(
+        topology: &Topology,
+        mut hypervisor_config: HypervisorConfig,
+        instruction_limit: Option<u64>,
+        extra_batches: u64,
+    ) -> Result<Self, String> {
+        if topology.subnets.is_empty() || topology.subnets.len() > u8::MAX as usize {
+            return Err(format!(
+                "A topology must have between 1 and {} subnets.",
+                u8::MAX
+            ));
+        }
+
+        let registry_data_provider = Arc::new(ProtoRegistryDataProvider::new());
+        let pool = Arc::new(SubnetsImpl::default());
+        let subnets: Vec<_> = topology
+            .subnets
+            .iter()
+            .enumerate()
+            .map(|(index, spec)| {
+                let subnet_config =
+                    subnet_config(spec.subnet_type, instruction_limit, &mut hypervisor_config);
+                StateMachineBuilder::new()
+                    .with_config(Some(StateMachineConfig::new(
+                        subnet_config,
+                        hypervisor_config.clone(),
+                    )))
+                    .with_subnet_type(spec.subnet_type)
+                    .with_subnet_seed([index as u8 + 1; 32])
+                    .with_registry_data_provider(registry_data_provider.clone())
+                    .with_current_time()
+                    .build_with_subnets(pool.clone())
+            })
+            .collect();
+
+        let subnet_ids: Vec<_> = subnets.iter().map(|sm| sm.get_subnet_id()).collect();
+        let routing_table = routing_table(topology, &subnet_ids)?;
+        add_initial_registry_records(registry_data_provider.clone());
+        add_global_registry_records(
+            subnet_ids[0],
+            routing_table.clone(),
+            subnet_ids.clone(),
+            BTreeMap::new(),
+            registry_data_provider,
+        );
+        // Reload the registry on all subnets so that they have a consistent view of the topology.
+        for sm in subnets.iter() {
+            sm.reload_registry();
+        }
+
+        for (index, subnet_id) in subnet_ids.iter().enumerate() {
+            println!("subnet {}: {}", index, subnet_id);
+        }
+
+        Ok(Self {
+            subnets,
+            routing_table,
+            extra_batches,
+            time_offset: Duration::ZERO,
+        })
+    }
+
+    /// Returns the index of the subnet hosting the given canister (if any).
+    fn route(&self, canister_id: CanisterId) -> Option<usize> {
+        let subnet_id = self.routing_table.route(canister_id.get())?;
+        self.subnets
+            .iter()
+            .position(|sm| sm.get_subnet_id() == subnet_id)
+    }
+
+    /// Sets the time of all subnets to the wall-clock time (plus the time offset)
+    /// unless their time is already ahead.
+    fn sync_time(&self) {
+        let time = SystemTime::now() + self.time_offset;
+        for sm in self.subnets.iter() {
+            if sm.time() < time {
+                sm.set_time(time);
+            }
+        }
+    }
+
+    /// Executes a round on every subnet. XNet messages produced by a subnet
+    /// are inducted by their destination subnets in subsequent rounds.
+    fn execute_round(&self) {
+        for sm in self.subnets.iter() {
+            sm.execute_round();
+        }
+    }
+
+    /// Executes an ingress message until it has completed on the subnet hosting
+    /// its (effective) canister or, if the message has no effective canister
+    /// (e.g., it creates a canister), on the subnet with the given index.
+    fn execute_ingress_on(
+        &mut self,
+        msg: SignedIngress,
+        subnet: usize,
+    ) -> Result<WasmResult, UserError> {
+        self.sync_time();
+        let subnet = effective_canister_id(&msg, self.subnets[0].get_subnet_id())
+            .and_then(|canister_id| self.route(canister_id))
+            .unwrap_or(subnet);
+        let sm = self.subnets[subnet].clone();
+        let message_id = match sm.submit_signed_ingress(msg) {
+            Ok(message_id) => message_id,
+            Err(SubmitIngressError::UserError(err)) => return Err(err),
+            Err(SubmitIngressError::HttpError(err)) => {
+                return Err(UserError::new(ErrorCode::CanisterRejectedMessage, err))
+            }
+        };
+
+        for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
+            self.execute_round();
+            let result = match sm.ingress_status(&message_id) {
+                IngressStatus::Known { state, .. } => match state {
+                    IngressState::Completed(result) => Ok(result),
+                    IngressState::Failed(error) => Err(error),
+                    IngressState::Done => Err(UserError::new(
+                        ErrorCode::SubnetOversubscribed,
+                        "The call has completed but the reply/reject data has been pruned.",
+                    )),
+                    IngressState::Received | IngressState::Processing => continue,
+                },
+                IngressStatus::Unknown => continue,
+            };
+            for _ in 0..self.extra_batches {
+                self.execute_round();
+            }
+            return result;
+        }
+        panic!(
+            "Ingress message did not finish executing within {} rounds, panicking",
+            MAX_BATCHES_UNTIL_RESPONSE
+        );
+    }
+}
+
+/// Returns the canister whose subnet executes the given ingress message (if any).
+fn effective_canister_id(msg: &SignedIngress, subnet_id: SubnetId) -> Option<CanisterId> {
+    if msg.canister_id() != ic00::IC_00 {
+        return Some(msg.canister_id());
+    }
+    match Method::from_str(&msg.method_name()) {
+        Ok(Method::ProvisionalTopUpCanister) => {
+            ic00::ProvisionalTopUpCanisterArgs::decode(msg.arg())
+                .ok()
+                .map(|args| args.get_canister_id())
+        }
+        _ => extract_effective_canister_id(msg.content(), subnet_id)
+            .ok()
+            .flatten(),
+    }
+}
+
+impl Backend for MultiSubnet {
+    fn execute_ingress(&mut self, msg: SignedIngress) -> Result<WasmResult, UserError> {
+        self.execute_ingress_on(msg, 0)
+    }
+
+    async fn execute_query(&mut self, query: Query) -> Result<WasmResult, UserError> {
+        let sender = match query.source {
+            QuerySource::User { user_id, .. } => user_id.get(),
+            QuerySource::Anonymous => PrincipalId::new_anonymous(),
+        };
+        let subnet = self.route(query.receiver).unwrap_or_default();
+        self.subnets[subnet].query_as(
+            sender,
+            query.receiver,
+            query.method_name,
+            query.method_payload,
+        )
+    }
+
+    fn cycles_balance(&self, canister_id: CanisterId) -> Option<u128> {
+        let subnet = self.route(canister_id)?;
+        self.subnets[subnet]
+            .get_latest_state()
+            .canister_state(&canister_id)
+            .map(|canister| canister.system_state.balance().get())
+    }
+
+    fn advance_time(&mut self, duration: Duration) {
+        self.time_offset += duration;
+        self.sync_time();
+        self.execute_round();
+    }
+
+    fn expiry_time(&self) -> Time {
+        expiry_time_from_now() + self.time_offset
+    }
+
+    fn num_subnets(&self) -> usize {
+        self.subnets.len()
+    }
+
+    fn create_canister(
+        &mut self,
+        msg: SignedIngress,
+        subnet: usize,
+    ) -> Result<WasmResult, UserError> {
+        self.execute_ingress_on(msg, subnet)
+    }
+
+    /// Marks the canister as being migrated, reroutes it to the destination subnet
+    /// and moves its state (through a checkpoint) to the destination subnet.
+    /// The migration is not completed (i.e., the canister migrations entry remains
+    /// in the registry) so that XNet messages in flight are still rerouted.
+    fn migrate_canister(&mut self, canister_id: CanisterId, subnet: usize) -> Result<(), String> {
+        let source = self
+            .route(canister_id)
+            .ok_or_else(|| format!("Canister {} is not routed to any subnet.", canister_id))?;
+        if source == subnet {
+            return Err(format!(
+                "Canister {} is already hosted by subnet {}.",
+                canister_id, subnet
+            ));
+        }
+        let from = &self.subnets[source];
+        let to = &self.subnets[subnet];
+        let range = canister_id..=canister_id;
+
+        // All subnets share the registry and thus it suffices to update the registry
+        // via one subnet and to reload it on all the other subnets.
+        from.prepare_canister_migrations(range.clone(), from.get_subnet_id(), to.get_subnet_id());
+        from.reroute_canister_range(range, to.get_subnet_id());
+        for sm in self.subnets.iter() {
+            sm.reload_registry();
+        }
+        from.move_canister_state_to(to, canister_id)?;
+
+        self.routing_table
+            .assign_ranges(
+                CanisterIdRanges::try_from(vec![CanisterIdRange {
+                    start: canister_id,
+                    end: canister_id,
+                }])
+                .unwrap(),
+                to.get_subnet_id(),
+            )
+            .map_err(|e| format!("Failed to reroute canister {}: {:?}", canister_id, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{create_canister_message, ingress_message, install_code_message};
+    use ic_config::flag_status::FlagStatus;
+    use ic_universal_canister::{wasm, CallArgs};
+
+    /// Returns two application subnets exchanging XNet messages.
+    fn two_subnets() -> MultiSubnet {
+        let topology = Topology {
+            subnets: vec![
+                SubnetSpec {
+                    subnet_type: SubnetType::Application,
+                    canister_ranges: vec![],
+                },
+                SubnetSpec {
+                    subnet_type: SubnetType::Application,
+                    canister_ranges: vec![],
+                },
+            ],
+        };
+        let hypervisor_config = HypervisorConfig {
+            canister_sandboxing_flag: FlagStatus::Disabled,
+            ..HypervisorConfig::default()
+        };
+        MultiSubnet::new(&topology, hypervisor_config, None, 0).unwrap()
+    }
+
+    /// Creates a universal canister on the subnet with the given index.
+    fn install_universal_canister(
+        subnets: &mut MultiSubnet,
+        subnet: usize,
+        nonce: u64,
+    ) -> CanisterId {
+        let msg = create_canister_message(None, nonce, subnets.expiry_time());
+        let canister_id = match subnets.create_canister(msg, subnet).unwrap() {
+            WasmResult::Reply(bytes) => ic00::CanisterIdRecord::decode(&bytes)
+                .unwrap()
+                .get_canister_id(),
+            WasmResult::Reject(reject) => panic!("Failed to create a canister: {}", reject),
+        };
+        let wasm_file = std::env::var("UNIVERSAL_CANISTER_WASM_PATH")
+            .expect("UNIVERSAL_CANISTER_WASM_PATH not set");
+        let msg = install_code_message(
+            canister_id,
+            &wasm_file,
+            vec![],
+            "install",
+            nonce + 1,
+            subnets.expiry_time(),
+        )
+        .unwrap();
+        subnets.execute_ingress(msg).unwrap();
+        canister_id
+    }
+
+    fn update(
+        subnets: &mut MultiSubnet,
+        canister_id: CanisterId,
+        payload: Vec<u8>,
+        nonce: u64,
+    ) -> WasmResult {
+        let msg = ingress_message(
+            canister_id,
+            "update".to_string(),
+            payload,
+            nonce,
+            subnets.expiry_time(),
+        );
+        subnets.execute_ingress(msg).unwrap()
+    }
+
+    #[test]
+    fn test_xnet_call() {
+        let mut subnets = two_subnets();
+        let caller = install_universal_canister(&mut subnets, 0, 0);
+        let callee = install_universal_canister(&mut subnets, 1, 2);
+        assert_eq!(subnets.route(caller), Some(0));
+        assert_eq!(subnets.route(callee), Some(1));
+
+        let payload = wasm()
+            .inter_update(
+                callee,
+                CallArgs::default().other_side(wasm().reply_data(b"pong")),
+            )
+            .build();
+        assert_eq!(
+            update(&mut subnets, caller, payload, 4),
+            WasmResult::Reply(b"pong".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_ingress_reaches_migrated_canister() {
+        let mut subnets = two_subnets();
+        let canister_id = install_universal_canister(&mut subnets, 0, 0);
+        let payload = wasm().set_global_data(b"state").reply().build();
+        update(&mut subnets, canister_id, payload, 2);
+
+        subnets.migrate_canister(canister_id, 1).unwrap();
+        assert_eq!(subnets.route(canister_id), Some(1));
+        assert!(subnets.subnets[0]
+            .get_latest_state()
+            .canister_state(&canister_id)
+            .is_none());
+
+        // The ingress message is executed by the destination subnet
+        // on the state moved from the source subnet.
+        let payload = wasm().get_global_data().append_and_reply().build();
+        assert_eq!(
+            update(&mut subnets, canister_id, payload, 3),
+            WasmResult::Reply(b"state".to_vec())
+        );
+        assert!(subnets.subnets[1]
+            .get_latest_state()
+            .canister_state(&canister_id)
+            .is_some());
+        assert_eq!(
+            subnets.migrate_canister(canister_id, 1),
+            Err(format!(
+                "Canister {} is already hosted by subnet 1.",
+                canister_id
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_topology() {
+        let topology: Topology = parse_json_or_yaml(
+            r#"
+subnets:
+  - subnet_type: system
+  - canister_ranges:
+      - { start: "rwlgt-iiaaa-aaaaa-aaaaa-cai", end: "rrkah-fqaaa-aaaaa-aaaaq-cai" }
+"#,
+            Some("yaml"),
+        )
+        .unwrap();
+        assert_eq!(
+            topology,
+            Topology {
+                subnets: vec![
+                    SubnetSpec {
+                        subnet_type: SubnetType::System,
+                        canister_ranges: vec![],
+                    },
+                    SubnetSpec {
+                        subnet_type: SubnetType::Application,
+                        canister_ranges: vec![CanisterRange {
+                            start: "rwlgt-iiaaa-aaaaa-aaaaa-cai".to_string(),
+                            end: "rrkah-fqaaa-aaaaa-aaaaq-cai".to_string(),
+                        }],
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_routing_table() {
+        let subnet_ids = [
+            SubnetId::from(PrincipalId::new_subnet_test_id(1)),
+            SubnetId::from(PrincipalId::new_subnet_test_id(2)),
+        ];
+        let topology = Topology {
+            subnets: vec![SubnetSpec::default(), SubnetSpec::default()],
+        };
+        let routing_table = routing_table(&topology, &subnet_ids).unwrap();
+        assert_eq!(
+            routing_table.route(CanisterId::from_u64(0).get()),
+            Some(subnet_ids[0])
+        );
+        assert_eq!(
+            routing_table.route(CanisterId::from_u64(CANISTER_IDS_PER_SUBNET).get()),
+            Some(subnet_ids[1])
+        );
+
+        // Overlapping ranges are rejected.
+        let range = CanisterRange {
+            start: CanisterId::from_u64(0).to_string(),
+            end: CanisterId::from_u64(10).to_string(),
+        };
+        let topology = Topology {
+            subnets: vec![
+                SubnetSpec {
+                    subnet_type: SubnetType::Application,
+                    canister_ranges: vec![range.clone()],
+                },
+                SubnetSpec {
+                    subnet_type: SubnetType::Application,
+                    canister_ranges: vec![range],
+                },
+            ],
+        };
+        assert!(routing_table(&topology, &subnet_ids).is_err());
+    }
+}
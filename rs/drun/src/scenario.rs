@@ -0,0 +1,816 @@
+//! Structured scenarios with expected outcomes.
+//!
+//! A scenario is a JSON or YAML file holding a list of named canisters
+//! that are created and installed up-front and a list of steps that are
+//! executed in order. Every step can be followed by an expectation on its
+//! outcome and on the cycles balances of named canisters. A scenario whose
+//! expectations are not met fails so that drun can be used as a golden-test
+//! runner.
+//!
+//! ```yaml
+//! canisters:
+//!   - name: counter
+//!     wasm: counter.wasm
+//!     cycles: 100000000000000
+//! steps:
+//!   - update: { canister: counter, method: inc, arg: { candid: "(1 : nat)" } }
+//!     expect:
+//!       reply: { candid: "(1 : nat)" }
+//!       cycles_delta:
+//!         counter: { min: -10000000, max: 0 }
+//!   - advance_time: { seconds: 60 }
+//!   - top_up: { canister: counter, cycles: 1000 }
+//!     expect:
+//!       cycles_delta:
+//!         counter: { min: 1000, max: 1000 }
+//!   - query: { canister: counter, method: missing }
+//!     expect:
+//!       reject_code: 3
+//! ```
+
+use crate::message::{
+    create_canister_message, ingress_message, install_code_message, parse_canister_id, parse_hex,
+    query_message, top_up_canister_message,
+};
+use crate::{print_wasm_result, Backend};
+use candid::{IDLArgs, TypeEnv};
+use ic_error_types::{RejectCode, UserError};
+use ic_management_canister_types_private::{CanisterIdRecord, Payload as _};
+use ic_types::{ingress::WasmResult, CanisterId, Time};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Returns `true` if the file should be interpreted as a scenario
+/// (as opposed to a text file containing one message per line).
+pub(crate) fn is_scenario_file(filename: &str) -> bool {
+    matches!(
+        Path::new(filename).extension().and_then(|ext| ext.to_str()),
+        Some("json" | "yaml" | "yml")
+    )
+}
+
+/// Loads a scenario from a JSON or YAML file (depending on its extension).
+/// Relative Wasm file paths are resolved relative to the directory of the scenario.
+pub(crate) fn load_scenario(path: &Path) -> Result<Scenario, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read scenario {}: {}", path.display(), e))?;
+    let mut scenario: Scenario =
+        parse_json_or_yaml(&contents, path.extension().and_then(|ext| ext.to_str()))
+            .map_err(|e| format!("Failed to parse scenario {}: {}", path.display(), e))?;
+    if let Some(dir) = path.parent() {
+        scenario.resolve_paths(dir);
+    }
+    Ok(scenario)
+}
+
+/// Parses JSON if the extension is `json` and YAML otherwise.
+pub(crate) fn parse_json_or_yaml<T: DeserializeOwned>(
+    contents: &str,
+    extension: Option<&str>,
+) -> Result<T, String> {
+    match extension {
+        Some("json") => serde_json::from_str(contents).map_err(|e| e.to_string()),
+        _ => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Scenario {
+    /// The canisters that are created and installed (in this order) before executing any step.
+    #[serde(default)]
+    pub canisters: Vec<CanisterSpec>,
+    pub steps: Vec<Step>,
+}
+
+impl Scenario {
+    fn resolve_paths(&mut self, dir: &Path) {
+        let resolve = |wasm: &mut PathBuf| {
+            if wasm.is_relative() {
+                *wasm = dir.join(&*wasm);
+            }
+        };
+        for canister in self.canisters.iter_mut() {
+            resolve(&mut canister.wasm);
+        }
+        for step in self.steps.iter_mut() {
+            if let Action::Upgrade(upgrade) = &mut step.action {
+                resolve(&mut upgrade.wasm);
+            }
+        }
+    }
+}
+
+/// A named canister. Steps refer to canisters by their names
+/// (or by their textual canister IDs).
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CanisterSpec {
+    pub name: String,
+    pub wasm: PathBuf,
+    /// The install argument (defaults to an empty Candid argument list).
+    #[serde(default)]
+    pub arg: Argument,
+    /// The initial cycles balance (defaults to the default of `provisional_create_canister_with_cycles`).
+    pub cycles: Option<u128>,
+    /// The index of the subnet (in the topology) on which the canister is created
+    /// (defaults to the first subnet).
+    pub subnet: Option<usize>,
+}
+
+/// The payload of a call: either Candid in its textual representation
+/// (e.g., `(42 : nat, "hello")`) or raw bytes as a `0x`-prefixed hex-string.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Argument {
+    Candid(String),
+    Hex(String),
+}
+
+impl Default for Argument {
+    fn default() -> Self {
+        Argument::Candid("()".to_string())
+    }
+}
+
+impl Argument {
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Argument::Candid(text) => parse_candid(text)?
+                .to_bytes()
+                .map_err(|e| format!("Failed to encode Candid {}: {}", text, e)),
+            Argument::Hex(hex) => parse_hex(hex),
+        }
+    }
+}
+
+fn parse_candid(text: &str) -> Result<IDLArgs, String> {
+    candid_parser::parse_idl_args(text)
+        .map_err(|e| format!("Failed to parse Candid {}: {}", text, e))
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub(crate) struct Step {
+    #[serde(flatten)]
+    pub action: Action,
+    #[serde(default)]
+    pub expect: Expectation,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Action {
+    /// An update call submitted as an ingress message.
+    Update(Call),
+    Query(Call),
+    /// Upgrades a canister to the given Wasm file.
+    Upgrade(Upgrade),
+    /// Advances the time of the subnet and executes a round
+    /// (so that due timers and heartbeats are executed).
+    AdvanceTime(AdvanceTime),
+    /// Adds cycles to the balance of a canister.
+    TopUp(TopUp),
+    /// Migrates a canister to another subnet (requires a topology with multiple subnets).
+    Migrate(Migrate),
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Call {
+    pub canister: String,
+    pub method: String,
+    #[serde(default)]
+    pub arg: Argument,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Upgrade {
+    pub canister: String,
+    pub wasm: PathBuf,
+    #[serde(default)]
+    pub arg: Argument,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AdvanceTime {
+    #[serde(default)]
+    pub seconds: u64,
+    #[serde(default)]
+    pub nanos: u64,
+}
+
+impl AdvanceTime {
+    fn duration(&self) -> Duration {
+        Duration::from_secs(self.seconds) + Duration::from_nanos(self.nanos)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TopUp {
+    pub canister: String,
+    pub cycles: u128,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Migrate {
+    pub canister: String,
+    /// The index of the destination subnet in the topology.
+    pub subnet: usize,
+}
+
+/// The expected outcome of a step. Omitted fields are not checked.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Expectation {
+    /// The expected reply. Candid replies are compared by value
+    /// and hex replies are compared byte by byte.
+    pub reply: Option<Argument>,
+    /// The expected reject code (e.g., `4` for `CANISTER_REJECT` and `5` for `CANISTER_ERROR`).
+    pub reject_code: Option<u64>,
+    /// The expected change of the cycles balance of the given canisters during the step.
+    #[serde(default)]
+    pub cycles_delta: BTreeMap<String, CyclesDelta>,
+}
+
+/// An inclusive range of cycles balance changes. Omitted bounds are not checked.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CyclesDelta {
+    pub min: Option<i128>,
+    pub max: Option<i128>,
+}
+
+impl CyclesDelta {
+    fn contains(&self, delta: i128) -> bool {
+        self.min.is_none_or(|min| min <= delta) && self.max.is_none_or(|max| delta <= max)
+    }
+}
+
+impl Expectation {
+    /// Returns a description of every expectation that is not met
+    /// by the given outcome and cycles balance changes.
+    fn mismatches(
+        &self,
+        outcome: Option<&Result<WasmResult, UserError>>,
+        cycles_deltas: &BTreeMap<String, i128>,
+    ) -> Vec<String> {
+        let mut mismatches = vec![];
+        if self.reply.is_some() || self.reject_code.is_some() {
+            match outcome {
+                None => mismatches.push("the step has no reply or reject".to_string()),
+                Some(Ok(WasmResult::Reply(bytes))) => {
+                    if let Some(reply) = &self.reply {
+                        if let Err(err) = check_reply(reply, bytes) {
+                            mismatches.push(err);
+                        }
+                    }
+                    if let Some(code) = self.reject_code {
+                        mismatches.push(format!("expected reject code {} but got a reply", code));
+                    }
+                }
+                Some(Ok(WasmResult::Reject(message))) => {
+                    check_reject(self, RejectCode::CanisterReject, message, &mut mismatches)
+                }
+                Some(Err(err)) => {
+                    check_reject(self, err.reject_code(), err.description(), &mut mismatches)
+                }
+            }
+        }
+        for (canister, expected) in self.cycles_delta.iter() {
+            let delta = cycles_deltas[canister];
+            if !expected.contains(delta) {
+                mismatches.push(format!(
+                    "expected the cycles balance of {} to change by {} but it changed by {}",
+                    canister,
+                    format_range(expected),
+                    delta
+                ));
+            }
+        }
+        mismatches
+    }
+}
+
+fn check_reply(expected: &Argument, actual: &[u8]) -> Result<(), String> {
+    match expected {
+        Argument::Hex(hex) => {
+            let expected = parse_hex(hex)?;
+            if expected != actual {
+                return Err(format!(
+                    "expected reply 0x{} but got 0x{}",
+                    hex::encode(expected),
+                    hex::encode(actual)
+                ));
+            }
+        }
+        Argument::Candid(text) => {
+            let actual = IDLArgs::from_bytes(actual).map_err(|e| {
+                format!(
+                    "expected Candid reply {} but got 0x{} ({})",
+                    text,
+                    hex::encode(actual),
+                    e
+                )
+            })?;
+            // The expected values are annotated with the types of the actual reply
+            // so that, e.g., a literal `1` matches a reply of type `nat8`.
+            let expected = parse_candid(text)?
+                .annotate_types(true, &TypeEnv::new(), &actual.get_types())
+                .map_err(|e| {
+                    format!("expected Candid reply {} but got {} ({})", text, actual, e)
+                })?;
+            if expected.args != actual.args {
+                return Err(format!(
+                    "expected Candid reply {} but got {}",
+                    expected, actual
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_reject(
+    expectation: &Expectation,
+    code: RejectCode,
+    message: &str,
+    mismatches: &mut Vec<String>,
+) {
+    if expectation.reply.is_some() {
+        mismatches.push(format!(
+            "expected a reply but got reject code {}: {}",
+            code as u64, message
+        ));
+    }
+    if let Some(expected) = expectation.reject_code {
+        if expected != code as u64 {
+            mismatches.push(format!(
+                "expected reject code {} but got reject code {}: {}",
+                expected, code as u64, message
+            ));
+        }
+    }
+}
+
+fn format_range(range: &CyclesDelta) -> String {
+    let bound = |bound: Option<i128>| bound.map_or("..".to_string(), |b| b.to_string());
+    format!("[{}, {}]", bound(range.min), bound(range.max))
+}
+
+/// Executes the steps of a scenario and checks their expectations.
+pub(crate) struct ScenarioRunner<'a, B: Backend> {
+    backend: &'a mut B,
+    canister_ids: BTreeMap<String, CanisterId>,
+    nonce: u64,
+}
+
+impl<'a, B: Backend> ScenarioRunner<'a, B> {
+    pub(crate) fn new(backend: &'a mut B) -> Self {
+        Self {
+            backend,
+            canister_ids: BTreeMap::new(),
+            nonce: 0,
+        }
+    }
+
+    /// Runs the scenario and returns an error if setting up a canister fails
+    /// or if the expectation of any step is not met.
+    pub(crate) async fn run(&mut self, scenario: Scenario) -> Result<(), String> {
+        for canister in scenario.canisters.iter() {
+            self.setup_canister(canister)?;
+        }
+
+        let mut failed_steps = 0;
+        for (index, step) in scenario.steps.iter().enumerate() {
+            let mismatches = self.run_step(step).await?;
+            for mismatch in mismatches.iter() {
+                println!("step {} failed: {}", index, mismatch);
+            }
+            if !mismatches.is_empty() {
+                failed_steps += 1;
+            }
+        }
+
+        if failed_steps > 0 {
+            return Err(format!(
+                "{} out of {} steps did not meet their expectations",
+                failed_steps,
+                scenario.steps.len()
+            ));
+        }
+        Ok(())
+    }
+
+    fn setup_canister(&mut self, canister: &CanisterSpec) -> Result<(), String> {
+        if self.canister_ids.contains_key(&canister.name) {
+            return Err(format!("Duplicate canister name {}", canister.name));
+        }
+        let subnet = self.subnet_index(canister.subnet.unwrap_or_default())?;
+        let msg = create_canister_message(canister.cycles, self.next_nonce(), self.expiry_time());
+        let canister_id = match self.backend.create_canister(msg, subnet) {
+            Ok(WasmResult::Reply(bytes)) => CanisterIdRecord::decode(&bytes)
+                .map_err(|e| format!("Failed to decode canister ID: {}", e))?
+                .get_canister_id(),
+            result => {
+                return Err(format!(
+                    "Failed to create canister {}: {:?}",
+                    canister.name, result
+                ))
+            }
+        };
+        println!("canister {}: {}", canister.name, canister_id);
+        self.canister_ids.insert(canister.name.clone(), canister_id);
+
+        let msg = install_code_message(
+            canister_id,
+            &canister.wasm.display().to_string(),
+            canister.arg.encode()?,
+            "install",
+            self.next_nonce(),
+            self.expiry_time(),
+        )?;
+        match self.backend.execute_ingress(msg) {
+            Ok(WasmResult::Reply(_)) => Ok(()),
+            result => Err(format!(
+                "Failed to install canister {}: {:?}",
+                canister.name, result
+            )),
+        }
+    }
+
+    /// Executes a step and returns a description of every expectation that is not met.
+    async fn run_step(&mut self, step: &Step) -> Result<Vec<String>, String> {
+        let balances_before = self.cycles_balances(&step.expect)?;
+
+        let outcome = match &step.action {
+            Action::Update(call) => {
+                let msg = ingress_message(
+                    self.canister_id(&call.canister)?,
+                    call.method.clone(),
+                    call.arg.encode()?,
+                    self.next_nonce(),
+                    self.expiry_time(),
+                );
+                Some(self.backend.execute_ingress(msg))
+            }
+            Action::Query(call) => {
+                let query = query_message(
+                    self.canister_id(&call.canister)?,
+                    call.method.clone(),
+                    call.arg.encode()?,
+                    self.next_nonce(),
+                    self.expiry_time(),
+                );
+                Some(self.backend.execute_query(query).await)
+            }
+            Action::Upgrade(upgrade) => {
+                let msg = install_code_message(
+                    self.canister_id(&upgrade.canister)?,
+                    &upgrade.wasm.display().to_string(),
+                    upgrade.arg.encode()?,
+                    "upgrade",
+                    self.next_nonce(),
+                    self.expiry_time(),
+                )?;
+                Some(self.backend.execute_ingress(msg))
+            }
+            Action::AdvanceTime(advance_time) => {
+                self.backend.advance_time(advance_time.duration());
+                None
+            }
+            Action::TopUp(top_up) => {
+                let msg = top_up_canister_message(
+                    self.canister_id(&top_up.canister)?,
+                    top_up.cycles,
+                    self.next_nonce(),
+                    self.expiry_time(),
+                );
+                Some(self.backend.execute_ingress(msg))
+            }
+            Action::Migrate(migrate) => {
+                let canister_id = self.canister_id(&migrate.canister)?;
+                let subnet = self.subnet_index(migrate.subnet)?;
+                self.backend.migrate_canister(canister_id, subnet)?;
+                None
+            }
+        };
+        match &outcome {
+            Some(Ok(result)) => print_wasm_result(result.clone()),
+            Some(Err(err)) => println!("Err: {}", err),
+            None => println!("Ok"),
+        }
+
+        let balances_after = self.cycles_balances(&step.expect)?;
+        let cycles_deltas = balances_before
+            .iter()
+            .map(|(name, before)| {
+                let delta = balances_after[name] as i128 - *before as i128;
+                (name.clone(), delta)
+            })
+            .collect();
+        Ok(step.expect.mismatches(outcome.as_ref(), &cycles_deltas))
+    }
+
+    /// Returns the cycles balances of the canisters referred to by the given expectation.
+    fn cycles_balances(&self, expectation: &Expectation) -> Result<BTreeMap<String, u128>, String> {
+        expectation
+            .cycles_delta
+            .keys()
+            .map(|name| {
+                let balance = self
+                    .backend
+                    .cycles_balance(self.canister_id(name)?)
+                    .ok_or_else(|| format!("Canister {} does not exist", name))?;
+                Ok((name.clone(), balance))
+            })
+            .collect()
+    }
+
+    /// Resolves a canister name or a textual canister ID.
+    fn canister_id(&self, canister: &str) -> Result<CanisterId, String> {
+        match self.canister_ids.get(canister) {
+            Some(canister_id) => Ok(*canister_id),
+            None => {
+                parse_canister_id(canister).map_err(|_| format!("Unknown canister {}", canister))
+            }
+        }
+    }
+
+    fn subnet_index(&self, subnet: usize) -> Result<usize, String> {
+        if subnet >= self.backend.num_subnets() {
+            return Err(format!(
+                "Subnet index {} is out of range (there are {} subnets)",
+                subnet,
+                self.backend.num_subnets()
+            ));
+        }
+        Ok(subnet)
+    }
+
+    fn expiry_time(&self) -> Time {
+        self.backend.expiry_time()
+    }
+
+    fn next_nonce(&mut self) -> u64 {
+        self.nonce += 1;
+        self.nonce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SingleSubnet;
+    use ic_config::{flag_status::FlagStatus, Config};
+    use ic_error_types::ErrorCode;
+    use ic_registry_subnet_type::SubnetType;
+
+    // A counter whose update `inc` increments and replies with the (one byte)
+    // counter and whose query `read` replies with the counter.
+    const COUNTER_WAT: &str = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+            (func $inc
+                (i32.store8 (i32.const 0) (i32.add (i32.load8_u (i32.const 0)) (i32.const 1)))
+                (call $read))
+            (func $read
+                (call $msg_reply_data_append (i32.const 0) (i32.const 1))
+                (call $msg_reply))
+            (memory 1)
+            (export "canister_update inc" (func $inc))
+            (export "canister_query read" (func $read)))"#;
+
+    /// Runs the given steps against the installed counter canister on a single subnet.
+    async fn run_counter_scenario(steps: &str) -> Result<(), String> {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("counter.wasm"),
+            wat::parse_str(COUNTER_WAT).unwrap(),
+        )
+        .unwrap();
+        let path = dir.path().join("scenario.yaml");
+        std::fs::write(
+            &path,
+            format!(
+                "canisters:\n  - name: counter\n    wasm: counter.wasm\nsteps:\n{}",
+                steps
+            ),
+        )
+        .unwrap();
+        let scenario = load_scenario(&path)?;
+
+        let (mut cfg, _config_dir) = Config::temp_config();
+        cfg.hypervisor.canister_sandboxing_flag = FlagStatus::Disabled;
+        let log = slog::Logger::root(slog::Discard, slog::o!());
+        let mut backend = SingleSubnet::new(cfg, SubnetType::System, None, 0, log);
+        ScenarioRunner::new(&mut backend).run(scenario).await
+    }
+
+    #[tokio::test]
+    async fn test_run_scenario_on_single_subnet() {
+        let steps = r#"
+  - update: { canister: counter, method: inc }
+    expect:
+      reply: { hex: "0x01" }
+  - update: { canister: counter, method: inc }
+    expect:
+      reply: { hex: "0x02" }
+  - query: { canister: counter, method: read }
+    expect:
+      reply: { hex: "0x02" }
+  - query: { canister: counter, method: missing }
+    expect:
+      reject_code: 3
+  - advance_time: { seconds: 60 }
+  - top_up: { canister: counter, cycles: 1000 }
+    expect:
+      cycles_delta:
+        counter: { min: 1000, max: 1000 }
+"#;
+        assert_eq!(run_counter_scenario(steps).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_run_scenario_with_unmet_expectation_fails() {
+        let steps = r#"
+  - update: { canister: counter, method: inc }
+    expect:
+      reply: { hex: "0x01" }
+  - update: { canister: counter, method: inc }
+    expect:
+      reply: { hex: "0x05" }
+"#;
+        assert_eq!(
+            run_counter_scenario(steps).await,
+            Err("1 out of 2 steps did not meet their expectations".to_string())
+        );
+    }
+
+    const SCENARIO: &str = r#"
+canisters:
+  - name: counter
+    wasm: counter.wasm
+    cycles: 1000
+steps:
+  - update: { canister: counter, method: inc, arg: { candid: "(1 : nat)" } }
+    expect:
+      reply: { hex: "0x01" }
+      cycles_delta:
+        counter: { max: 0 }
+  - advance_time: { seconds: 60 }
+  - top_up: { canister: counter, cycles: 5 }
+  - query: { canister: counter, method: read }
+    expect:
+      reject_code: 5
+"#;
+
+    #[test]
+    fn test_parse_yaml_scenario() {
+        let scenario = parse_json_or_yaml::<Scenario>(SCENARIO, Some("yaml")).unwrap();
+        assert_eq!(
+            scenario.canisters,
+            vec![CanisterSpec {
+                name: "counter".to_string(),
+                wasm: PathBuf::from("counter.wasm"),
+                arg: Argument::default(),
+                cycles: Some(1000),
+                subnet: None,
+            }]
+        );
+        assert_eq!(scenario.steps.len(), 4);
+        assert_eq!(
+            scenario.steps[0],
+            Step {
+                action: Action::Update(Call {
+                    canister: "counter".to_string(),
+                    method: "inc".to_string(),
+                    arg: Argument::Candid("(1 : nat)".to_string()),
+                }),
+                expect: Expectation {
+                    reply: Some(Argument::Hex("0x01".to_string())),
+                    reject_code: None,
+                    cycles_delta: BTreeMap::from([(
+                        "counter".to_string(),
+                        CyclesDelta {
+                            min: None,
+                            max: Some(0)
+                        }
+                    )]),
+                },
+            }
+        );
+        assert_eq!(
+            scenario.steps[1].action,
+            Action::AdvanceTime(AdvanceTime {
+                seconds: 60,
+                nanos: 0
+            })
+        );
+        assert_eq!(scenario.steps[1].expect, Expectation::default());
+    }
+
+    #[test]
+    fn test_parse_json_scenario() {
+        let scenario = parse_json_or_yaml::<Scenario>(
+            r#"{"steps": [{"top_up": {"canister": "aaaaa-aa", "cycles": 10}}]}"#,
+            Some("json"),
+        )
+        .unwrap();
+        assert!(scenario.canisters.is_empty());
+        assert_eq!(
+            scenario.steps[0].action,
+            Action::TopUp(TopUp {
+                canister: "aaaaa-aa".to_string(),
+                cycles: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_migrate_step() {
+        let scenario = parse_json_or_yaml::<Scenario>(
+            "steps:\n  - migrate: { canister: counter, subnet: 1 }\n",
+            Some("yml"),
+        )
+        .unwrap();
+        assert_eq!(
+            scenario.steps[0].action,
+            Action::Migrate(Migrate {
+                canister: "counter".to_string(),
+                subnet: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_scenario_with_unknown_field_fails() {
+        assert!(parse_json_or_yaml::<Scenario>(
+            r#"{"steps": [{"top_up": {"canister": "aaaaa-aa", "amount": 10}}]}"#,
+            Some("json")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_is_scenario_file() {
+        assert!(is_scenario_file("test.json"));
+        assert!(is_scenario_file("dir/test.yaml"));
+        assert!(is_scenario_file("test.yml"));
+        assert!(!is_scenario_file("in.txt"));
+        assert!(!is_scenario_file("messages"));
+    }
+
+    #[test]
+    fn test_candid_reply_is_compared_by_value() {
+        let reply = Argument::Candid("(42 : nat8, \"hello\")".to_string())
+            .encode()
+            .unwrap();
+        let expected = |text: &str| Argument::Candid(text.to_string());
+        assert!(check_reply(&expected("(42, \"hello\")"), &reply).is_ok());
+        assert!(check_reply(&expected("(43, \"hello\")"), &reply).is_err());
+        assert!(check_reply(&expected("(42)"), &reply).is_err());
+        assert!(check_reply(&Argument::Hex(format!("0x{}", hex::encode(&reply))), &reply).is_ok());
+        assert!(check_reply(&Argument::Hex("0x00".to_string()), &reply).is_err());
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let expectation = Expectation {
+            reply: None,
+            reject_code: Some(5),
+            cycles_delta: BTreeMap::from([(
+                "counter".to_string(),
+                CyclesDelta {
+                    min: Some(-100),
+                    max: Some(0),
+                },
+            )]),
+        };
+        let canister_error = Err(UserError::new(ErrorCode::CanisterTrapped, "trapped"));
+        let deltas = BTreeMap::from([("counter".to_string(), -10)]);
+        assert!(expectation
+            .mismatches(Some(&canister_error), &deltas)
+            .is_empty());
+
+        let deltas = BTreeMap::from([("counter".to_string(), -1000)]);
+        assert_eq!(
+            expectation.mismatches(Some(&Ok(WasmResult::Reply(vec![]))), &deltas),
+            vec![
+                "expected reject code 5 but got a reply".to_string(),
+                "expected the cycles balance of counter to change by [-100, 0] but it changed by -1000"
+                    .to_string(),
+            ]
+        );
+        assert_eq!(
+            expectation.mismatches(None, &BTreeMap::from([("counter".to_string(), 0)])),
+            vec!["the step has no reply or reject".to_string()]
+        );
+    }
+}
@@ -60,6 +60,7 @@ pub(crate) fn system_api_imports(config: EmbeddersConfig) -> SystemApiImportStor
             num_stable_dirty_pages_from_non_native_writes: ic_types::NumOsPages::from(0),
             limits: StoreLimits::default(),
             canister_backtrace: config.feature_flags.canister_backtrace,
+            profiler: None,
         },
     );
     let mut linker: wasmtime::Linker<StoreData> = wasmtime::Linker::new(&engine);
@@ -22,7 +22,9 @@ use wasmtime::Module;
 use crate::wasmtime_embedder::CanisterMemoryType;
 use crate::OnDiskSerializedModule;
 use crate::{
-    wasm_utils::{compile, decoding::decode_wasm, Segments, WasmImportsDetails},
+    wasm_utils::{
+        compile, decoding::decode_wasm, profiling::write_wasm_profile, Segments, WasmImportsDetails,
+    },
     wasmtime_embedder::WasmtimeInstance,
     CompilationCache, CompilationResult, WasmExecutionInput, WasmtimeEmbedder,
 };
@@ -221,13 +223,14 @@ impl WasmExecutor for WasmExecutorImpl {
             self.observe_metrics(&serialized_module.imports_details);
         }
 
+        let canister_id = sandbox_safe_system_state.canister_id();
         let wasm_reserved_pages = get_wasm_reserved_pages(execution_state);
         let mut wasm_memory = execution_state.wasm_memory.clone();
         let mut stable_memory = execution_state.stable_memory.clone();
 
         let (
             slice_execution_output,
-            wasm_execution_output,
+            mut wasm_execution_output,
             wasm_state_changes,
             instance_or_system_api,
         ) = process(
@@ -248,6 +251,15 @@ impl WasmExecutor for WasmExecutorImpl {
             Rc::new(DefaultOutOfInstructionsHandler::default()),
         );
 
+        if let Some(output_dir) = &self.wasm_embedder.config().wasm_profiling_output_dir {
+            write_wasm_profile(
+                &self.log,
+                output_dir,
+                canister_id,
+                &mut wasm_execution_output,
+            );
+        }
+
         // Collect logs only when the flag is enabled to avoid producing too much data.
         if EMIT_STATE_HASHES_FOR_DEBUGGING == FlagStatus::Enabled {
             self.emit_state_hashes_for_debugging(&wasm_state_changes, &wasm_execution_output);
@@ -481,6 +493,7 @@ pub fn wasm_execution_error(
             allocated_guaranteed_response_message_bytes: NumBytes::new(0),
             instance_stats: InstanceStats::default(),
             system_api_call_counters: SystemApiCallCounters::default(),
+            wasm_profile: None,
         },
         CanisterStateChanges {
             execution_state_changes: None,
@@ -648,6 +661,7 @@ pub fn process(
                     allocated_guaranteed_response_message_bytes: NumBytes::new(0),
                     instance_stats: InstanceStats::default(),
                     system_api_call_counters: SystemApiCallCounters::default(),
+                    wasm_profile: None,
                 },
                 None,
                 Err(system_api.unwrap()), // should be safe because we've passed Some(api) to new_instance
@@ -663,6 +677,14 @@ pub fn process(
     // slices before it returns.
     let run_result = instance.run(func_ref);
 
+    // The samples are returned to the caller, which writes them to disk.
+    let wasm_profile = instance
+        .store_data_mut()
+        .profiler
+        .take()
+        .filter(|profiler| !profiler.is_empty())
+        .map(|profiler| profiler.to_collapsed_stacks());
+
     // Get the executed/remaining instructions for the message and the slice.
     let instruction_counter = instance.instruction_counter();
     let instance_stats = instance.get_stats();
@@ -705,6 +727,7 @@ pub fn process(
                         allocated_guaranteed_response_message_bytes: NumBytes::new(0),
                         instance_stats,
                         system_api_call_counters,
+                        wasm_profile,
                     },
                     None,
                     Ok(instance),
@@ -802,6 +825,7 @@ pub fn process(
             allocated_guaranteed_response_message_bytes,
             instance_stats,
             system_api_call_counters,
+            wasm_profile,
         },
         wasm_state_changes,
         Ok(instance),
@@ -3,7 +3,7 @@ use std::{
     time::Instant,
 };
 
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_interfaces::execution_environment::HypervisorResult;
 use ic_replicated_state::{
     canister_state::{execution_state::WasmMetadata, WASM_PAGE_SIZE_IN_BYTES},
@@ -15,14 +15,19 @@ use ic_wasm_types::{BinaryEncodedWasm, WasmInstrumentationError};
 use serde::{Deserialize, Serialize};
 
 use self::{
-    instrumentation::instrument, validation::has_wasm64_memory, validation::validate_wasm_binary,
+    component::{is_component, translate_component},
+    instrumentation::instrument,
+    validation::has_wasm64_memory,
+    validation::validate_wasm_binary,
 };
 use crate::wasmtime_embedder::StoreData;
 use crate::{serialized_module::SerializedModule, CompilationResult, WasmtimeEmbedder};
 use wasmtime::InstancePre;
 
+mod component;
 pub mod decoding;
 pub mod instrumentation;
+pub mod profiling;
 mod system_api_replacements;
 pub mod validation;
 
@@ -206,6 +211,17 @@ fn validate_and_instrument(
     wasm: &BinaryEncodedWasm,
     config: &EmbeddersConfig,
 ) -> HypervisorResult<(WasmValidationDetails, InstrumentationOutput)> {
+    // Components are translated to the core module they instantiate, which is
+    // then validated and instrumented like any other canister module.
+    let translated_wasm;
+    let wasm = if config.feature_flags.wasm_components == FlagStatus::Enabled
+        && is_component(wasm.as_slice())
+    {
+        translated_wasm = translate_component(wasm)?;
+        &translated_wasm
+    } else {
+        wasm
+    };
     let (wasm_validation_details, module) = validate_wasm_binary(wasm, config)?;
     // Instrumentation bytemap depends on the Wasm memory size, so for larger heaps we need
     // to pass in the corresponding Wasm64 heap memory size.
@@ -222,6 +238,7 @@ fn validate_and_instrument(
         config.dirty_page_overhead,
         max_wasm_memory_size,
         config.max_stable_memory_size,
+        config.feature_flags.wasm_profiling,
     )?;
     Ok((wasm_validation_details, instrumentation_output))
 }
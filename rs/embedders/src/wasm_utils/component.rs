@@ -0,0 +1,805 @@
+//! Support for canisters written as Wasm components.
+//!
+//! A component canister imports the System API as the WIT interface
+//! `ic:canister/ic0` (see `rs/embedders/wit/ic0.wit`) and exports its
+//! canister methods as component functions. Since the rest of the embedder
+//! only deals with core modules, the component is translated into the single
+//! core module it instantiates:
+//!
+//! * every import of the core module must be satisfied by a function of the
+//!   `ic:canister/ic0` interface lowered with the canonical ABI; it is
+//!   rewritten to the corresponding `ic0` import,
+//! * every exported component function must be lifted from an export of the
+//!   core module; it is added as a core export following the canister method
+//!   naming convention (`update-foo` becomes `canister_update foo`),
+//! * custom sections of the component named `icp:*` are copied to the core
+//!   module.
+//!
+//! The canonical ABI of the functions of `ic0.wit` coincides with the
+//! signatures of the `ic0` imports: `list<u8>` and `string` parameters are
+//! lowered to a pointer and a length into the memory given by the `memory`
+//! canonical option, which must be the (first) memory of the core module.
+//! So the translation does not need to generate any adapter code.
+//!
+//! Functions lowered with a `memory` option cannot be passed directly to
+//! the core module, since the memory only exists once the core module is
+//! instantiated. Toolchains (e.g. `wit-component`) therefore instantiate two
+//! glue modules: a shim module whose exported trampolines are imported by
+//! the core module and call the functions of a table, and a fix-up module
+//! that stores the lowered functions in that table once the core module is
+//! instantiated. A trampoline exported as `<n>` is resolved to the lowered
+//! function that is passed to the fix-up module as `<n>`, and the glue
+//! modules are dropped from the translated module. Components that contain
+//! nested components, or whose canister methods are not lifted from a single
+//! core module, are rejected.
+
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+use ic_wasm_transform::Module;
+use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
+use wasmparser::{
+    CanonicalFunction, CanonicalOption, ComponentAlias, ComponentExternalKind,
+    ComponentOuterAliasKind, ComponentTypeRef, Export, ExternalKind, Instance,
+    InstantiationArgKind, Parser, Payload, Validator, WasmFeatures,
+};
+
+use super::validation::{API_VERSION_IC0, WASM_VALID_SYSTEM_FUNCTIONS};
+
+/// The name under which a component imports the System API.
+const IC0_INTERFACE_NAME: &str = "ic:canister/ic0";
+
+/// The prefix of custom sections that are copied to the core module.
+const ICP_CUSTOM_SECTION_PREFIX: &str = "icp:";
+
+/// Export name prefixes of component functions that are canister methods.
+const CANISTER_METHOD_PREFIXES: [(&str, &str); 3] = [
+    ("update-", "canister_update "),
+    ("query-", "canister_query "),
+    ("composite-query-", "canister_composite_query "),
+];
+
+/// Returns true if the binary is encoded as a component rather than as a
+/// core module.
+pub(super) fn is_component(wasm: &[u8]) -> bool {
+    Parser::is_component(wasm)
+}
+
+/// An entry of the component function index space.
+enum ComponentFunc<'a> {
+    /// A function exported by an imported component instance.
+    InstanceExport { instance_index: u32, name: &'a str },
+    /// A core function lifted with the canonical ABI.
+    Lifted { core_func_index: u32 },
+    /// A function re-exported by the component.
+    Exported { func_index: u32 },
+    /// A function that is not supported by the translation.
+    Unsupported,
+}
+
+/// An entry of the core function index space of the component.
+enum CoreFunc<'a> {
+    /// A function exported by a core instance.
+    InstanceExport { instance_index: u32, name: &'a str },
+    /// A component function lowered with the canonical ABI, using the
+    /// given entry of the core memory index space (if any).
+    Lowered {
+        func_index: u32,
+        memory: Option<u32>,
+    },
+}
+
+/// An `ic0` function lowered to a core function.
+struct LoweredIc0Function<'a> {
+    name: &'a str,
+    /// The name of the export of the core module that provides the memory
+    /// of the lowering (if any).
+    memory: Option<&'a str>,
+}
+
+fn invalid(message: String) -> WasmValidationError {
+    WasmValidationError::InvalidComponent(message)
+}
+
+/// Translates a component canister into a core module canister.
+pub(super) fn translate_component(
+    wasm: &BinaryEncodedWasm,
+) -> Result<BinaryEncodedWasm, WasmValidationError> {
+    let bytes = wasm.as_slice();
+    Validator::new_with_features(WasmFeatures::default() | WasmFeatures::COMPONENT_MODEL)
+        .validate_all(bytes)
+        .map_err(|err| invalid(format!("Validation failed: {}", err)))?;
+
+    // Imported component instances: `Some(name)` for instance imports and
+    // `None` for all other entries of the index space.
+    let mut component_instances: Vec<Option<&str>> = vec![];
+    let mut component_funcs: Vec<ComponentFunc> = vec![];
+    let mut core_funcs: Vec<CoreFunc> = vec![];
+    // The core memory index space: the core instance and the name of the
+    // export each memory is aliased from.
+    let mut core_memories: Vec<(u32, &str)> = vec![];
+    let mut core_instances: Vec<Instance> = vec![];
+    let mut modules: Vec<Range<usize>> = vec![];
+    let mut exports: Vec<(&str, u32)> = vec![];
+    let mut custom_sections: Vec<(&str, &[u8])> = vec![];
+
+    // Only the payloads of the outermost component are processed. The
+    // payloads of nested core modules are skipped.
+    let mut depth = 0;
+    for payload in Parser::new(0).parse_all(bytes) {
+        let payload = payload.map_err(|err| invalid(format!("Decoding failed: {}", err)))?;
+        match payload {
+            Payload::Version { .. } => {
+                depth += 1;
+                continue;
+            }
+            Payload::End(_) => {
+                depth -= 1;
+                continue;
+            }
+            _ if depth > 1 => continue,
+            _ => {}
+        }
+        match payload {
+            Payload::ModuleSection {
+                unchecked_range, ..
+            } => modules.push(unchecked_range),
+            Payload::ComponentImportSection(reader) => {
+                for import in reader {
+                    let import = import.map_err(|err| invalid(err.to_string()))?;
+                    match import.ty {
+                        ComponentTypeRef::Instance(_) => {
+                            component_instances.push(Some(import.name.0))
+                        }
+                        ComponentTypeRef::Func(_) => {
+                            component_funcs.push(ComponentFunc::Unsupported)
+                        }
+                        ComponentTypeRef::Type(_) => {}
+                        _ => {
+                            return Err(invalid(format!("Unsupported import '{}'.", import.name.0)))
+                        }
+                    }
+                }
+            }
+            Payload::ComponentAliasSection(reader) => {
+                for alias in reader {
+                    match alias.map_err(|err| invalid(err.to_string()))? {
+                        ComponentAlias::InstanceExport {
+                            kind,
+                            instance_index,
+                            name,
+                        } => match kind {
+                            ComponentExternalKind::Func => {
+                                component_funcs.push(ComponentFunc::InstanceExport {
+                                    instance_index,
+                                    name,
+                                })
+                            }
+                            ComponentExternalKind::Instance => component_instances.push(None),
+                            ComponentExternalKind::Type => {}
+                            _ => {
+                                return Err(invalid(format!(
+                                    "Unsupported alias of export '{}'.",
+                                    name
+                                )))
+                            }
+                        },
+                        ComponentAlias::CoreInstanceExport {
+                            kind,
+                            instance_index,
+                            name,
+                        } => match kind {
+                            ExternalKind::Func => core_funcs.push(CoreFunc::InstanceExport {
+                                instance_index,
+                                name,
+                            }),
+                            ExternalKind::Memory => core_memories.push((instance_index, name)),
+                            // Aliases of tables, globals and tags are only
+                            // referenced by the glue instances or by
+                            // instances that are rejected below.
+                            _ => {}
+                        },
+                        ComponentAlias::Outer { kind, .. } => match kind {
+                            ComponentOuterAliasKind::CoreType | ComponentOuterAliasKind::Type => {}
+                            _ => {
+                                return Err(invalid(
+                                    "Outer aliases of modules and components are not supported."
+                                        .to_string(),
+                                ))
+                            }
+                        },
+                    }
+                }
+            }
+            Payload::ComponentCanonicalSection(reader) => {
+                for function in reader {
+                    match function.map_err(|err| invalid(err.to_string()))? {
+                        CanonicalFunction::Lift {
+                            core_func_index,
+                            options,
+                            ..
+                        } => {
+                            canonical_memory(&options)?;
+                            component_funcs.push(ComponentFunc::Lifted { core_func_index });
+                        }
+                        CanonicalFunction::Lower {
+                            func_index,
+                            options,
+                        } => {
+                            let memory = canonical_memory(&options)?;
+                            core_funcs.push(CoreFunc::Lowered { func_index, memory });
+                        }
+                        _ => {
+                            return Err(invalid(
+                                "Only `canon lift` and `canon lower` are supported.".to_string(),
+                            ))
+                        }
+                    }
+                }
+            }
+            Payload::InstanceSection(reader) => {
+                for instance in reader {
+                    core_instances.push(instance.map_err(|err| invalid(err.to_string()))?);
+                }
+            }
+            Payload::ComponentExportSection(reader) => {
+                for export in reader {
+                    let export = export.map_err(|err| invalid(err.to_string()))?;
+                    match export.kind {
+                        ComponentExternalKind::Func => {
+                            exports.push((export.name.0, export.index));
+                            component_funcs.push(ComponentFunc::Exported {
+                                func_index: export.index,
+                            });
+                        }
+                        ComponentExternalKind::Instance => component_instances.push(None),
+                        ComponentExternalKind::Type => {}
+                        _ => {
+                            return Err(invalid(format!("Unsupported export '{}'.", export.name.0)))
+                        }
+                    }
+                }
+            }
+            Payload::CustomSection(reader) => {
+                if reader.name().starts_with(ICP_CUSTOM_SECTION_PREFIX) {
+                    custom_sections.push((reader.name(), reader.data()));
+                }
+            }
+            Payload::ComponentSection { .. } => {
+                return Err(invalid("Nested components are not supported.".to_string()))
+            }
+            Payload::ComponentInstanceSection(_) => {
+                return Err(invalid(
+                    "Component instantiation is not supported.".to_string(),
+                ))
+            }
+            Payload::ComponentStartSection { .. } => {
+                return Err(invalid("Start functions are not supported.".to_string()))
+            }
+            // Type definitions are checked by the validator and do not
+            // affect the translation.
+            _ => {}
+        }
+    }
+
+    let resolver = Resolver {
+        component_instances: &component_instances,
+        component_funcs: &component_funcs,
+        core_funcs: &core_funcs,
+        core_memories: &core_memories,
+        core_instances: &core_instances,
+    };
+
+    // Maps the exported component functions to canister methods.
+    let mut lifted_exports: Vec<(u32, &str, String)> = vec![];
+    for (name, func_index) in exports {
+        let canister_name = canister_export_name(name).ok_or_else(|| {
+            invalid(format!(
+                "Export '{}' is not a canister method. Methods must be exported as \
+                 'update-<name>', 'query-<name>', 'composite-query-<name>' or as a system method.",
+                name
+            ))
+        })?;
+        let (instance_index, core_name) = resolver.lifted_export(func_index)?;
+        lifted_exports.push((instance_index, core_name, canister_name));
+    }
+
+    // The canister methods are lifted from the main core instance. All
+    // other instantiated core modules are glue modules.
+    let instantiations: Vec<usize> = core_instances
+        .iter()
+        .enumerate()
+        .filter(|(_, instance)| matches!(instance, Instance::Instantiate { .. }))
+        .map(|(index, _)| index)
+        .collect();
+    let main_instance_index = match (instantiations.as_slice(), lifted_exports.first()) {
+        ([index], _) => *index as u32,
+        (_, Some((index, _, _))) => *index,
+        _ => {
+            return Err(invalid(
+                "The canister methods must be lifted from an instantiated core module.".to_string(),
+            ))
+        }
+    };
+    if lifted_exports
+        .iter()
+        .any(|(index, _, _)| *index != main_instance_index)
+    {
+        return Err(invalid(
+            "All canister methods must be lifted from the same core module.".to_string(),
+        ));
+    }
+    let (module_index, args) = match core_instances.get(main_instance_index as usize) {
+        Some(Instance::Instantiate { module_index, args }) => (*module_index, args),
+        _ => {
+            return Err(invalid(
+                "The canister methods must be lifted from an instantiated core module.".to_string(),
+            ))
+        }
+    };
+
+    // Maps the imports of the core module to the `ic0` functions.
+    let mut import_names: HashMap<(&str, &str), String> = HashMap::new();
+    let mut memory_exports: BTreeSet<&str> = BTreeSet::new();
+    for arg in args.iter() {
+        let exports: &[Export] = match (arg.kind, core_instances.get(arg.index as usize)) {
+            (InstantiationArgKind::Instance, Some(Instance::FromExports(exports))) => exports,
+            _ => {
+                return Err(invalid(format!(
+                    "The imports of module '{}' must be provided by lowered functions of '{}'.",
+                    arg.name, IC0_INTERFACE_NAME
+                )))
+            }
+        };
+        for export in exports {
+            let function = match export.kind {
+                ExternalKind::Func => resolver.ic0_function(export.index, main_instance_index)?,
+                _ => {
+                    return Err(invalid(format!(
+                        "Unsupported import '{}.{}'. Only functions can be imported.",
+                        arg.name, export.name
+                    )))
+                }
+            };
+            memory_exports.extend(function.memory);
+            import_names.insert((arg.name, export.name), function.name.replace('-', "_"));
+        }
+    }
+
+    let module_bytes = modules
+        .get(module_index as usize)
+        .map(|range| &bytes[range.clone()])
+        .ok_or_else(|| invalid(format!("Unknown module {}.", module_index)))?;
+    let mut module = Module::parse(module_bytes, false)
+        .map_err(|err| invalid(format!("Failed to parse the core module: {}", err)))?;
+
+    // The System API reads the data of lowered functions from the first
+    // memory of the canister.
+    for memory in memory_exports {
+        if !module.exports.iter().any(|export| {
+            export.name == memory && export.kind == ExternalKind::Memory && export.index == 0
+        }) {
+            return Err(invalid(format!(
+                "The canonical option `memory` must refer to the first memory of the core \
+                 module, but it refers to '{}'.",
+                memory
+            )));
+        }
+    }
+
+    for import in module.imports.iter_mut() {
+        let name = import_names
+            .get(&(import.module, import.name))
+            .ok_or_else(|| {
+                invalid(format!(
+                    "Import '{}.{}' of the core module is not provided by '{}'.",
+                    import.module, import.name, IC0_INTERFACE_NAME
+                ))
+            })?;
+        import.module = API_VERSION_IC0;
+        import.name = name.as_str();
+    }
+
+    for (_, core_name, canister_name) in lifted_exports.iter() {
+        let index = module
+            .exports
+            .iter()
+            .find(|export| export.name == *core_name && export.kind == ExternalKind::Func)
+            .map(|export| export.index)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "The core module does not export function '{}'.",
+                    core_name
+                ))
+            })?;
+        module.exports.push(Export {
+            name: canister_name.as_str(),
+            kind: ExternalKind::Func,
+            index,
+        });
+    }
+
+    module.custom_sections.extend(custom_sections);
+
+    let binary = module
+        .encode()
+        .map_err(|err| invalid(format!("Failed to encode the core module: {}", err)))?;
+    Ok(BinaryEncodedWasm::new(binary))
+}
+
+/// Returns the `memory` canonical option. Only the options that do not
+/// require adapter code are supported.
+fn canonical_memory(options: &[CanonicalOption]) -> Result<Option<u32>, WasmValidationError> {
+    let mut memory = None;
+    for option in options {
+        match option {
+            CanonicalOption::Memory(index) => memory = Some(*index),
+            CanonicalOption::UTF8 | CanonicalOption::Realloc(_) => {}
+            _ => {
+                return Err(invalid(format!(
+                    "Unsupported canonical option {:?}.",
+                    option
+                )))
+            }
+        }
+    }
+    Ok(memory)
+}
+
+/// Returns the canister export name of a component export, e.g.
+/// `canister_update foo_bar` for `update-foo-bar` and `canister_init` for
+/// `init`.
+fn canister_export_name(name: &str) -> Option<String> {
+    for (prefix, method_prefix) in CANISTER_METHOD_PREFIXES {
+        if let Some(method) = name.strip_prefix(prefix) {
+            return Some(format!("{}{}", method_prefix, method.replace('-', "_")));
+        }
+    }
+    let system_method = format!("canister_{}", name.replace('-', "_"));
+    WASM_VALID_SYSTEM_FUNCTIONS
+        .contains(&system_method.as_str())
+        .then_some(system_method)
+}
+
+/// Follows the index spaces of the component to the definition of functions.
+struct Resolver<'r, 'a> {
+    component_instances: &'r [Option<&'a str>],
+    component_funcs: &'r [ComponentFunc<'a>],
+    core_funcs: &'r [CoreFunc<'a>],
+    core_memories: &'r [(u32, &'a str)],
+    core_instances: &'r [Instance<'a>],
+}
+
+impl<'a> Resolver<'_, 'a> {
+    /// Follows re-exports of component functions.
+    fn component_func(
+        &self,
+        mut func_index: u32,
+    ) -> Result<&ComponentFunc<'a>, WasmValidationError> {
+        loop {
+            match self.component_funcs.get(func_index as usize) {
+                Some(ComponentFunc::Exported { func_index: index }) => func_index = *index,
+                Some(func) => return Ok(func),
+                None => return Err(invalid(format!("Unknown function {}.", func_index))),
+            }
+        }
+    }
+
+    /// Returns the `ic0` function that is lowered to the given core
+    /// function, either directly or through a trampoline of a glue module.
+    fn ic0_function(
+        &self,
+        core_func_index: u32,
+        main_instance_index: u32,
+    ) -> Result<LoweredIc0Function<'a>, WasmValidationError> {
+        let unsupported = || {
+            invalid(format!(
+                "Core function {} is not a lowered function of '{}'.",
+                core_func_index, IC0_INTERFACE_NAME
+            ))
+        };
+        let lowered = match self.core_funcs.get(core_func_index as usize) {
+            Some(CoreFunc::InstanceExport {
+                instance_index,
+                name,
+            }) if *instance_index != main_instance_index => self
+                .trampoline_target(name)
+                .and_then(|index| self.core_funcs.get(index as usize)),
+            lowered => lowered,
+        };
+        let (func_index, memory) = match lowered {
+            Some(CoreFunc::Lowered { func_index, memory }) => (*func_index, *memory),
+            _ => return Err(unsupported()),
+        };
+        let memory = match memory.map(|index| self.core_memories.get(index as usize)) {
+            None => None,
+            Some(Some((instance_index, name))) if *instance_index == main_instance_index => {
+                Some(*name)
+            }
+            Some(_) => {
+                return Err(invalid(
+                    "The canonical option `memory` must refer to a memory of the core module."
+                        .to_string(),
+                ))
+            }
+        };
+        match self.component_func(func_index)? {
+            ComponentFunc::InstanceExport {
+                instance_index,
+                name,
+            } => match self.component_instances.get(*instance_index as usize) {
+                Some(Some(instance_name)) if is_ic0_interface(instance_name) => {
+                    Ok(LoweredIc0Function { name, memory })
+                }
+                _ => Err(unsupported()),
+            },
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Returns the core function that a fix-up module stores in the table
+    /// for the trampoline exported as `name` by the shim module, i.e. the
+    /// function passed to the fix-up module under the same name.
+    fn trampoline_target(&self, name: &str) -> Option<u32> {
+        self.core_instances
+            .iter()
+            .filter_map(|instance| match instance {
+                Instance::Instantiate { args, .. } => Some(args),
+                Instance::FromExports(_) => None,
+            })
+            .flat_map(|args| args.iter())
+            .filter(|arg| arg.kind == InstantiationArgKind::Instance)
+            .filter_map(|arg| match self.core_instances.get(arg.index as usize) {
+                Some(Instance::FromExports(exports)) => Some(exports),
+                _ => None,
+            })
+            .flat_map(|exports| exports.iter())
+            .find(|export| export.name == name && export.kind == ExternalKind::Func)
+            .map(|export| export.index)
+    }
+
+    /// Returns the core instance and the name of its export that is lifted
+    /// to the given component function.
+    fn lifted_export(&self, func_index: u32) -> Result<(u32, &'a str), WasmValidationError> {
+        let unsupported = || {
+            invalid(format!(
+                "Function {} is not lifted from an export of the core module.",
+                func_index
+            ))
+        };
+        let core_func_index = match self.component_func(func_index)? {
+            ComponentFunc::Lifted { core_func_index } => *core_func_index,
+            _ => return Err(unsupported()),
+        };
+        match self.core_funcs.get(core_func_index as usize) {
+            Some(CoreFunc::InstanceExport {
+                instance_index,
+                name,
+            }) => Ok((*instance_index, *name)),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+/// Returns true if the import name refers to any version of the
+/// `ic:canister/ic0` interface.
+fn is_ic0_interface(name: &str) -> bool {
+    let name = name.split_once('@').map_or(name, |(name, _version)| name);
+    name == IC0_INTERFACE_NAME
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm_utils::validation::{
+        get_valid_system_apis_32_only, get_valid_system_apis_common,
+    };
+    use ic_config::embedders::Config as EmbeddersConfig;
+    use wasmparser::{TypeRef, ValType};
+
+    /// Returns the flattened core types of a WIT type.
+    fn wit_type(ty: &str) -> Vec<ValType> {
+        match ty {
+            "u32" => vec![ValType::I32],
+            "u64" => vec![ValType::I64],
+            // A pointer and a length.
+            "string" | "list<u8>" => vec![ValType::I32, ValType::I32],
+            _ => panic!("Unexpected WIT type {}", ty),
+        }
+    }
+
+    /// Returns the functions declared in `ic0.wit` with their core signature.
+    fn wit_functions() -> HashMap<String, (Vec<ValType>, Vec<ValType>)> {
+        include_str!("../../wit/ic0.wit")
+            .lines()
+            .filter_map(|line| line.trim().split_once(": func("))
+            .map(|(name, signature)| {
+                let (params, results) = signature.trim_end_matches(';').split_once(')').unwrap();
+                let params = params
+                    .split(", ")
+                    .filter(|param| !param.is_empty())
+                    .flat_map(|param| wit_type(param.split_once(": ").unwrap().1))
+                    .collect();
+                let results = results
+                    .strip_prefix(" -> ")
+                    .map(wit_type)
+                    .unwrap_or_default();
+                (name.replace('-', "_"), (params, results))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wit_interface_matches_system_api() {
+        let mut system_api = get_valid_system_apis_common(ValType::I32);
+        system_api.extend(get_valid_system_apis_32_only());
+        let system_api: HashMap<String, (Vec<ValType>, Vec<ValType>)> = system_api
+            .into_iter()
+            .map(|(name, signatures)| {
+                let signature = &signatures[API_VERSION_IC0];
+                (
+                    name,
+                    (signature.param_types.clone(), signature.return_type.clone()),
+                )
+            })
+            .collect();
+        assert_eq!(wit_functions(), system_api);
+    }
+
+    #[test]
+    fn canister_export_names() {
+        assert_eq!(
+            canister_export_name("update-get-balance").as_deref(),
+            Some("canister_update get_balance")
+        );
+        assert_eq!(
+            canister_export_name("query-read").as_deref(),
+            Some("canister_query read")
+        );
+        assert_eq!(
+            canister_export_name("composite-query-read").as_deref(),
+            Some("canister_composite_query read")
+        );
+        assert_eq!(
+            canister_export_name("pre-upgrade").as_deref(),
+            Some("canister_pre_upgrade")
+        );
+        assert_eq!(
+            canister_export_name("on-low-wasm-memory").as_deref(),
+            Some("canister_on_low_wasm_memory")
+        );
+        assert_eq!(canister_export_name("start"), None);
+    }
+
+    /// Returns a component with the structure generated by `wit-component`:
+    /// `msg-reply-data-append` takes a `list<u8>` and is lowered with the
+    /// memory of the main module, so the main module imports it through the
+    /// trampoline of a shim module, which a fix-up module points to the
+    /// lowered function.
+    fn component_with_memory(ic0_import: &str, memory: &str) -> BinaryEncodedWasm {
+        let other_memory = match memory {
+            "memory" => String::new(),
+            _ => format!(r#"(memory (export "{memory}") 1)"#),
+        };
+        let wat = format!(
+            r#"
+            (component
+                (import "{ic0_import}" (instance $ic0
+                    (export "msg-reply" (func))
+                    (export "msg-reply-data-append" (func (param "data" (list u8))))
+                ))
+                (core module $main
+                    (import "ic0" "msg_reply" (func $msg_reply))
+                    (import "ic0" "msg_reply_data_append" (func $append (param i32 i32)))
+                    (memory (export "memory") 1)
+                    {other_memory}
+                    (func (export "go")
+                        (call $append (i32.const 0) (i32.const 0))
+                        (call $msg_reply)
+                    )
+                )
+                (core module $shim
+                    (type $append (func (param i32 i32)))
+                    (table (export "$imports") 1 1 funcref)
+                    (func (export "0") (type $append)
+                        (call_indirect (type $append)
+                            (local.get 0) (local.get 1) (i32.const 0))
+                    )
+                )
+                (core module $fixup
+                    (type $append (func (param i32 i32)))
+                    (import "" "0" (func $append (type $append)))
+                    (import "" "$imports" (table 1 1 funcref))
+                    (elem (i32.const 0) func $append)
+                )
+                (core instance $shim_instance (instantiate $shim))
+                (alias export $ic0 "msg-reply" (func $reply))
+                (core func $reply_lowered (canon lower (func $reply)))
+                (alias core export $shim_instance "0" (core func $append_trampoline))
+                (core instance $ic0_core
+                    (export "msg_reply" (func $reply_lowered))
+                    (export "msg_reply_data_append" (func $append_trampoline))
+                )
+                (core instance $i (instantiate $main (with "ic0" (instance $ic0_core))))
+                (alias core export $i "{memory}" (core memory $memory))
+                (alias export $ic0 "msg-reply-data-append" (func $append))
+                (core func $append_lowered (canon lower (func $append) (memory $memory)))
+                (alias core export $shim_instance "$imports" (core table $imports))
+                (core instance $fixup_args
+                    (export "$imports" (table $imports))
+                    (export "0" (func $append_lowered))
+                )
+                (core instance (instantiate $fixup (with "" (instance $fixup_args))))
+                (func $go (canon lift (core func $i "go")))
+                (export "update-go" (func $go))
+                (@custom "icp:public candid:service" "service : {{ go : () -> () }}")
+            )
+            "#
+        );
+        BinaryEncodedWasm::new(wat::parse_str(wat).unwrap())
+    }
+
+    fn component(ic0_import: &str) -> BinaryEncodedWasm {
+        component_with_memory(ic0_import, "memory")
+    }
+
+    #[test]
+    fn component_is_translated_to_core_module() {
+        let wasm = component("ic:canister/ic0@0.1.0");
+        assert!(is_component(wasm.as_slice()));
+
+        let translated = translate_component(&wasm).unwrap();
+        assert!(!is_component(translated.as_slice()));
+        let module = Module::parse(translated.as_slice(), false).unwrap();
+        let imports: Vec<_> = module
+            .imports
+            .iter()
+            .map(|import| (import.module, import.name, import.ty))
+            .collect();
+        assert_eq!(
+            imports,
+            vec![
+                ("ic0", "msg_reply", TypeRef::Func(0)),
+                ("ic0", "msg_reply_data_append", TypeRef::Func(1)),
+            ]
+        );
+        assert!(module
+            .exports
+            .iter()
+            .any(|export| export.name == "canister_update go"));
+        assert_eq!(
+            module.custom_sections,
+            vec![(
+                "icp:public candid:service",
+                "service : { go : () -> () }".as_bytes()
+            )]
+        );
+
+        let config = EmbeddersConfig::default();
+        let (details, _) =
+            super::super::validation::validate_wasm_binary(&translated, &config).unwrap();
+        assert_eq!(details.wasm_metadata.custom_sections().len(), 1);
+    }
+
+    #[test]
+    fn component_lowering_with_other_memory_is_rejected() {
+        let wasm = component_with_memory("ic:canister/ic0", "other-memory");
+        match translate_component(&wasm).map(|_| ()) {
+            Err(WasmValidationError::InvalidComponent(err)) => {
+                assert!(err.contains("must refer to the first memory of the core module"))
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn component_importing_other_interface_is_rejected() {
+        let wasm = component("ic:canister/other");
+        match translate_component(&wasm).map(|_| ()) {
+            Err(WasmValidationError::InvalidComponent(err)) => {
+                assert!(err.contains("is not a lowered function of 'ic:canister/ic0'"))
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+}
@@ -110,6 +110,7 @@
 //! ```
 //!
 
+use super::profiling::inject_profile_sampling;
 use super::system_api_replacements::replacement_functions;
 use super::validation::API_VERSION_IC0;
 use super::{InstrumentationOutput, Segments, SystemApiFunc};
@@ -182,6 +183,7 @@ pub fn instruction_to_cost(i: &Operator, mem_type: WasmMemoryType) -> u64 {
         Operator::Else => 0,
         Operator::End => 0,
         Operator::Loop { .. } => 0,
+        Operator::TryTable { .. } => 0,
 
         // The following instructions generate register/immediate code most of the time,
         // so we assign 1 cost to them because these are not very costly to execute,
@@ -433,6 +435,10 @@ pub fn instruction_to_cost(i: &Operator, mem_type: WasmMemoryType) -> u64 {
         // Return, drop, unreachable and nop instructions are of cost 1.
         Operator::Return { .. } | Operator::Drop | Operator::Unreachable | Operator::Nop => 1,
 
+        // Throwing an exception allocates the exception object and unwinds
+        // the stack in the runtime, so it is as expensive as `memory.grow`.
+        Operator::Throw { .. } | Operator::ThrowRef => 300,
+
         // Branching instructions should be of cost 2.
         Operator::If { .. }
         | Operator::Br { .. }
@@ -706,7 +712,7 @@ pub fn instruction_to_cost(i: &Operator, mem_type: WasmMemoryType) -> u64 {
     }
 }
 
-const INSTRUMENTED_FUN_MODULE: &str = "__";
+pub(super) const INSTRUMENTED_FUN_MODULE: &str = "__";
 const OUT_OF_INSTRUCTIONS_FUN_NAME: &str = "out_of_instructions";
 const TRY_GROW_WASM_MEMORY_FUN_NAME: &str = "try_grow_wasm_memory";
 const TRY_GROW_STABLE_MEMORY_FUN_NAME: &str = "try_grow_stable_memory";
@@ -727,7 +733,7 @@ fn max_memory_size_in_wasm_pages(memory_size: NumBytes) -> u64 {
     memory_size.get() / (WASM_PAGE_SIZE as u64)
 }
 
-fn add_func_type(module: &mut Module, ty: FuncType) -> u32 {
+pub(super) fn add_func_type(module: &mut Module, ty: FuncType) -> u32 {
     for (idx, existing_subtype) in module.types.iter().enumerate() {
         if let CompositeInnerType::Func(existing_ty) = &existing_subtype.composite_type.inner {
             if *existing_ty == ty {
@@ -746,7 +752,7 @@ fn add_func_type(module: &mut Module, ty: FuncType) -> u32 {
     (module.types.len() - 1) as u32
 }
 
-fn mutate_function_indices(module: &mut Module, f: impl Fn(u32) -> u32) {
+pub(super) fn mutate_function_indices(module: &mut Module, f: impl Fn(u32) -> u32) {
     fn mutate_instruction(f: &impl Fn(u32) -> u32, op: &mut Operator) {
         match op {
             Operator::Call { function_index }
@@ -925,6 +931,7 @@ pub(super) struct SpecialIndices {
 ///
 /// Returns an [`InstrumentationOutput`] or an error if the input binary could
 /// not be instrumented.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn instrument(
     module: Module<'_>,
     cost_to_compile_wasm_instruction: NumInstructions,
@@ -933,10 +940,14 @@ pub(super) fn instrument(
     dirty_page_overhead: NumInstructions,
     max_wasm_memory_size: NumBytes,
     max_stable_memory_size: NumBytes,
+    wasm_profiling: FlagStatus,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
     let main_memory_type = main_memory_type(&module);
     let stable_memory_index;
     let mut module = inject_helper_functions(module, main_memory_type);
+    // Only the functions of the original module are profiled, not the helper
+    // functions added by the instrumentation below.
+    let num_original_functions = module.code_sections.len();
     module = export_table(module);
     (module, stable_memory_index) = update_memories(
         module,
@@ -1077,6 +1088,12 @@ pub(super) fn instrument(
         wasm_instruction_count += 2;
     }
 
+    // The profiling instrumentation is added last so that it affects neither
+    // the metering nor the compilation cost of the module.
+    if wasm_profiling == FlagStatus::Enabled {
+        inject_profile_sampling(&mut module, num_original_functions);
+    }
+
     let result = module.encode().map_err(|err| {
         WasmInstrumentationError::WasmSerializeError(WasmError::new(err.to_string()))
     })?;
@@ -1822,7 +1839,12 @@ fn injections(code: &[Operator], mem_type: WasmMemoryType) -> Vec<InjectionPoint
                 res.push(curr);
                 curr = InjectionPoint::new_static_cost(position + 1, Scope::BlockEnd, 0);
             }
-            Return | Unreachable | ReturnCall { .. } | ReturnCallIndirect { .. } => {
+            Return
+            | Unreachable
+            | ReturnCall { .. }
+            | ReturnCallIndirect { .. }
+            | Throw { .. }
+            | ThrowRef => {
                 res.push(curr);
                 // This injection point will be unreachable itself (most likely empty)
                 // but we create it to keep the algorithm uniform
@@ -1854,6 +1876,11 @@ fn injections(code: &[Operator], mem_type: WasmMemoryType) -> Vec<InjectionPoint
                     CostOperandOnStack::X32Bit,
                 ));
             }
+            // The catch clauses of `try_table` branch to labels of enclosing
+            // blocks and loops, whose targets already start a new code block.
+            // The instructions following a call that throws have been charged
+            // upfront, just like for a call that traps.
+            TryTable { .. } => (),
             // Nothing special to be done for other instructions.
             _ => (),
         }
@@ -2069,3 +2096,83 @@ fn export_mutable_globals<'a>(
 
     module
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the positions and costs of the static injection points of the
+    /// last function of the module.
+    fn static_costs(wat: &str) -> Vec<(usize, u64)> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = Module::parse(&wasm, false).unwrap();
+        let body = module.code_sections.last().unwrap();
+        injections(&body.instructions, WasmMemoryType::Wasm32)
+            .into_iter()
+            .filter_map(|point| match point.cost_detail {
+                InjectionPointCostDetail::StaticCost { cost, .. } => Some((point.position, cost)),
+                InjectionPointCostDetail::DynamicCost { .. } => None,
+            })
+            .collect()
+    }
+
+    fn cost(i: Operator) -> u64 {
+        instruction_to_cost(&i, WasmMemoryType::Wasm32)
+    }
+
+    #[test]
+    fn throw_ends_a_code_block() {
+        let costs = static_costs(
+            r#"(module
+                (tag $e)
+                (func
+                    (block $handler
+                        (try_table (catch_all $handler)
+                            (throw $e)
+                            (nop)
+                        )
+                    )
+                    (nop)
+                )
+            )"#,
+        );
+        assert_eq!(cost(Operator::Throw { tag_index: 0 }), 300);
+        // block, try_table, throw | nop, end | end | nop, end
+        assert_eq!(
+            costs,
+            vec![
+                (
+                    0,
+                    1 + cost(Operator::Block {
+                        blockty: BlockType::Empty
+                    }) + 300
+                ),
+                (3, cost(Operator::Nop)),
+                (5, 0),
+                (6, cost(Operator::Nop)),
+            ]
+        );
+    }
+
+    #[test]
+    fn throw_ref_ends_a_code_block() {
+        let costs = static_costs(
+            r#"(module
+                (func (param exnref)
+                    (local.get 0)
+                    (throw_ref)
+                    (nop)
+                )
+            )"#,
+        );
+        assert_eq!(cost(Operator::ThrowRef), 300);
+        // local.get, throw_ref | nop, end
+        assert_eq!(
+            costs,
+            vec![
+                (0, 1 + cost(Operator::LocalGet { local_index: 0 }) + 300),
+                (2, cost(Operator::Nop)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Support for sampling profiling of canister code.
+//!
+//! When the `wasm_profiling` feature flag is enabled, the instrumentation
+//! injects a call to the `__.profile_sample` import at the beginning of every
+//! function of the original module and at the beginning of every loop body.
+//! On each such call, the [`WasmProfiler`] checks how many instructions have
+//! been executed since the previous call (as reported by the instruction
+//! counter maintained by the metering instrumentation) and, once a full
+//! sampling interval has elapsed, records the current call stack. Samples are
+//! therefore weighted by the instructions of the IC cost model rather than by
+//! wall-clock time.
+//!
+//! The injected calls are added after metering and after computing the
+//! compilation cost, so they neither consume instructions nor change the
+//! cost of compiling the module. The profiler is meant for local tooling
+//! (tests, PocketIC, `drun`) only and must never be enabled on a replica
+//! taking part in consensus.
+//!
+//! The samples are emitted in the collapsed-stack format understood by
+//! `flamegraph.pl` and `inferno`: one line per distinct stack, with frames
+//! ordered from the outermost to the innermost and separated by `;`,
+//! followed by the number of samples. Frames are named after the `name`
+//! section of the module, falling back to the function index.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use ic_interfaces::execution_environment::{CanisterBacktrace, WasmExecutionOutput};
+use ic_logger::{warn, ReplicaLogger};
+use ic_types::{CanisterId, NumInstructions};
+use ic_wasm_transform::Module;
+use wasmparser::{FuncType, Import, Operator, TypeRef};
+
+use super::instrumentation::{add_func_type, mutate_function_indices, INSTRUMENTED_FUN_MODULE};
+
+/// The name of the injected import that takes a sample.
+pub(crate) const PROFILE_SAMPLE_FUN_NAME: &str = "profile_sample";
+
+/// The file extension of the collapsed stacks written by the profiler.
+const COLLAPSED_STACKS_EXTENSION: &str = "folded";
+
+/// Injects calls to the `__.profile_sample` import into the first
+/// `num_profiled_functions` locally defined functions of the module.
+///
+/// The import is appended after all other imports, so the indices of the
+/// injected imports of the instrumentation stay unchanged and only the
+/// locally defined functions are shifted by one.
+pub(super) fn inject_profile_sampling(module: &mut Module<'_>, num_profiled_functions: usize) {
+    let num_imported_functions = module
+        .imports
+        .iter()
+        .filter(|imp| matches!(imp.ty, TypeRef::Func(_)))
+        .count() as u32;
+
+    let type_idx = add_func_type(module, FuncType::new([], []));
+    module.imports.push(Import {
+        module: INSTRUMENTED_FUN_MODULE,
+        name: PROFILE_SAMPLE_FUN_NAME,
+        ty: TypeRef::Func(type_idx),
+    });
+    mutate_function_indices(module, |idx| {
+        if idx >= num_imported_functions {
+            idx + 1
+        } else {
+            idx
+        }
+    });
+
+    let sample = Operator::Call {
+        function_index: num_imported_functions,
+    };
+    for body in module.code_sections.iter_mut().take(num_profiled_functions) {
+        let mut instructions = Vec::with_capacity(body.instructions.len() + 1);
+        instructions.push(sample.clone());
+        for op in body.instructions.drain(..) {
+            let is_loop = matches!(op, Operator::Loop { .. });
+            instructions.push(op);
+            if is_loop {
+                instructions.push(sample.clone());
+            }
+        }
+        body.instructions = instructions;
+    }
+}
+
+/// Collects call-stack samples of a single Wasm execution.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct WasmProfiler {
+    /// The number of instructions between two consecutive samples.
+    sample_interval: u64,
+    /// The value of the instruction counter at the previous sample point.
+    last_instruction_counter: Option<i64>,
+    /// The number of instructions executed since the last recorded sample.
+    pending_instructions: u64,
+    /// The number of samples per call stack. Frames are ordered from the
+    /// outermost to the innermost one.
+    samples: BTreeMap<Vec<String>, u64>,
+}
+
+impl WasmProfiler {
+    pub fn new(sample_interval: NumInstructions) -> Self {
+        Self {
+            sample_interval: sample_interval.get().max(1),
+            last_instruction_counter: None,
+            pending_instructions: 0,
+            samples: BTreeMap::new(),
+        }
+    }
+
+    /// Updates the profiler with the current value of the instruction
+    /// counter and returns the number of samples that are due at this point.
+    ///
+    /// The instruction counter counts down. It is reset to a higher value at
+    /// the beginning of each execution slice, in which case no instructions
+    /// are attributed to the current sample point.
+    pub fn observe(&mut self, instruction_counter: i64) -> u64 {
+        if let Some(last) = self.last_instruction_counter {
+            if instruction_counter < last {
+                self.pending_instructions = self
+                    .pending_instructions
+                    .saturating_add(last.abs_diff(instruction_counter));
+            }
+        }
+        self.last_instruction_counter = Some(instruction_counter);
+        let num_samples = self.pending_instructions / self.sample_interval;
+        self.pending_instructions %= self.sample_interval;
+        num_samples
+    }
+
+    /// Records `num_samples` samples of the given backtrace. The backtrace is
+    /// ordered from the innermost to the outermost frame.
+    pub fn record(&mut self, backtrace: &CanisterBacktrace, num_samples: u64) {
+        let stack = backtrace
+            .0
+            .iter()
+            .rev()
+            .map(|(index, name)| frame_name(*index, name.as_deref()))
+            .collect();
+        let count = self.samples.entry(stack).or_default();
+        *count = count.saturating_add(num_samples);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns the samples in the collapsed-stack format.
+    pub fn to_collapsed_stacks(&self) -> String {
+        let mut output = String::new();
+        for (stack, count) in &self.samples {
+            output.push_str(&stack.join(";"));
+            output.push(' ');
+            output.push_str(&count.to_string());
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Appends the collapsed stacks to `<output_dir>/<canister_id>.folded` and
+/// returns the path of the file.
+pub fn write_collapsed_stacks(
+    output_dir: &Path,
+    canister_id: CanisterId,
+    collapsed_stacks: &str,
+) -> std::io::Result<PathBuf> {
+    let path = output_dir.join(format!("{}.{}", canister_id, COLLAPSED_STACKS_EXTENSION));
+    std::fs::create_dir_all(output_dir)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    file.write_all(collapsed_stacks.as_bytes())?;
+    Ok(path)
+}
+
+/// Takes the call-stack samples out of the execution output (if any) and
+/// appends them to the profile of the canister in `output_dir`.
+///
+/// The samples are collected by the process executing the canister code,
+/// which is the sandbox process if canister sandboxing is enabled, so they
+/// are written by the replica once the execution has finished.
+pub fn write_wasm_profile(
+    log: &ReplicaLogger,
+    output_dir: &Path,
+    canister_id: CanisterId,
+    output: &mut WasmExecutionOutput,
+) {
+    let Some(collapsed_stacks) = output.wasm_profile.take() else {
+        return;
+    };
+    if let Err(err) = write_collapsed_stacks(output_dir, canister_id, &collapsed_stacks) {
+        warn!(
+            log,
+            "Failed to write the Wasm profile of canister {} to {}: {}",
+            canister_id,
+            output_dir.display(),
+            err
+        );
+    }
+}
+
+/// Returns the name of a frame. Characters that have a special meaning in the
+/// collapsed-stack format are replaced.
+fn frame_name(index: u32, name: Option<&str>) -> String {
+    match name {
+        Some(name) => name.replace([';', ' ', '\n'], "_"),
+        None => format!("func[{}]", index),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_interfaces::execution_environment::{InstanceStats, SystemApiCallCounters};
+    use ic_types::NumBytes;
+
+    fn backtrace(frames: &[(u32, Option<&str>)]) -> CanisterBacktrace {
+        CanisterBacktrace(
+            frames
+                .iter()
+                .map(|(index, name)| (*index, name.map(str::to_string)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn observe_returns_samples_per_elapsed_interval() {
+        let mut profiler = WasmProfiler::new(NumInstructions::from(100));
+        assert_eq!(profiler.observe(1_000), 0);
+        assert_eq!(profiler.observe(950), 0);
+        assert_eq!(profiler.observe(850), 1);
+        assert_eq!(profiler.observe(600), 2);
+        // The remaining 50 instructions are carried over.
+        assert_eq!(profiler.observe(550), 1);
+    }
+
+    #[test]
+    fn observe_ignores_instruction_counter_resets() {
+        let mut profiler = WasmProfiler::new(NumInstructions::from(100));
+        assert_eq!(profiler.observe(120), 0);
+        assert_eq!(profiler.observe(60), 0);
+        // A new slice starts with a fresh instruction counter.
+        assert_eq!(profiler.observe(1_000), 0);
+        assert_eq!(profiler.observe(960), 1);
+    }
+
+    #[test]
+    fn collapsed_stacks_are_ordered_from_outermost_frame() {
+        let mut profiler = WasmProfiler::new(NumInstructions::from(1));
+        let inner = backtrace(&[
+            (7, Some("inner")),
+            (6, None),
+            (5, Some("canister_update go")),
+        ]);
+        profiler.record(&inner, 2);
+        profiler.record(&inner, 1);
+        profiler.record(&backtrace(&[(5, Some("canister_update go"))]), 4);
+
+        assert_eq!(
+            profiler.to_collapsed_stacks(),
+            "canister_update_go 4\ncanister_update_go;func[6];inner 3\n"
+        );
+    }
+
+    #[test]
+    fn write_wasm_profile_appends_and_takes_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let canister_id = CanisterId::from_u64(42);
+        let mut output = WasmExecutionOutput {
+            wasm_result: Ok(None),
+            num_instructions_left: NumInstructions::from(0),
+            allocated_bytes: NumBytes::from(0),
+            allocated_guaranteed_response_message_bytes: NumBytes::from(0),
+            instance_stats: InstanceStats::default(),
+            system_api_call_counters: SystemApiCallCounters::default(),
+            wasm_profile: Some("go 1\n".to_string()),
+        };
+        let log = ic_logger::no_op_logger();
+
+        write_wasm_profile(&log, dir.path(), canister_id, &mut output);
+        assert_eq!(output.wasm_profile, None);
+        output.wasm_profile = Some("go;inner 2\n".to_string());
+        write_wasm_profile(&log, dir.path(), canister_id, &mut output);
+
+        let path = dir.path().join(format!("{}.folded", canister_id));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "go 1\ngo;inner 2\n");
+    }
+}
@@ -3,7 +3,10 @@
 
 use super::{Complexity, WasmImportsDetails, WasmValidationDetails};
 
-use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_config::{
+    embedders::{Config as EmbeddersConfig, FeatureFlags},
+    flag_status::FlagStatus,
+};
 use ic_replicated_state::canister_state::execution_state::{
     CustomSection, CustomSectionType, WasmMetadata,
 };
@@ -58,7 +61,7 @@ pub const MAX_CODE_SECTION_SIZE_IN_BYTES: u32 = 11 * 1024 * 1024;
 
 // Represents the expected function signature for any System APIs the Internet
 // Computer provides or any special exported user functions.
-struct FunctionSignature {
+pub(super) struct FunctionSignature {
     pub param_types: Vec<ValType>,
     pub return_type: Vec<ValType>,
 }
@@ -74,7 +77,8 @@ pub(super) const API_VERSION_IC0: &str = "ic0";
 // modules vs the case where the function exists but is imported from the wrong
 // module.
 // Returns system api functions available only in wasm32 mode
-fn get_valid_system_apis_32_only() -> HashMap<String, HashMap<String, FunctionSignature>> {
+pub(super) fn get_valid_system_apis_32_only() -> HashMap<String, HashMap<String, FunctionSignature>>
+{
     let valid_system_apis = vec![
         (
             "call_cycles_add",
@@ -184,7 +188,9 @@ fn get_valid_system_apis_32_only() -> HashMap<String, HashMap<String, FunctionSi
 
 // Returns system api functions available both in wasm32 and wasm64
 #[allow(non_snake_case)]
-fn get_valid_system_apis_common(I: ValType) -> HashMap<String, HashMap<String, FunctionSignature>> {
+pub(super) fn get_valid_system_apis_common(
+    I: ValType,
+) -> HashMap<String, HashMap<String, FunctionSignature>> {
     let valid_system_apis = vec![
         (
             // Public methods
@@ -389,6 +395,16 @@ fn get_valid_system_apis_common(I: ValType) -> HashMap<String, HashMap<String, F
                 },
             )],
         ),
+        (
+            "log",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValType::I32, I, I, I, I],
+                    return_type: vec![],
+                },
+            )],
+        ),
         (
             "stable64_size",
             vec![(
@@ -449,6 +465,36 @@ fn get_valid_system_apis_common(I: ValType) -> HashMap<String, HashMap<String, F
                 },
             )],
         ),
+        (
+            "timer_set",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![I, I, ValType::I64, ValType::I64],
+                    return_type: vec![ValType::I64],
+                },
+            )],
+        ),
+        (
+            "timer_name_size",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![I],
+                },
+            )],
+        ),
+        (
+            "timer_name_copy",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![I, I, I],
+                    return_type: vec![],
+                },
+            )],
+        ),
         (
             "performance_counter",
             vec![(
@@ -659,6 +705,36 @@ fn get_valid_system_apis_common(I: ValType) -> HashMap<String, HashMap<String, F
                 },
             )],
         ),
+        (
+            "call_with_chunked_payload",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "msg_arg_chunk_index",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValType::I32],
+                },
+            )],
+        ),
+        (
+            "msg_arg_chunk_count",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValType::I32],
+                },
+            )],
+        ),
         (
             "subnet_self_size",
             vec![(
@@ -879,18 +955,38 @@ fn set_imports_details(import_details: &mut WasmImportsDetails, import_module: &
     }
 }
 
+// System API functions that are only available if
+// `FeatureFlags::chunked_call_payloads` is enabled.
+const CHUNKED_CALL_PAYLOAD_SYSTEM_APIS: [&str; 3] = [
+    "call_with_chunked_payload",
+    "msg_arg_chunk_index",
+    "msg_arg_chunk_count",
+];
+
+// System API functions that are only available if
+// `FeatureFlags::named_timers` is enabled.
+const NAMED_TIMER_SYSTEM_APIS: [&str; 3] = ["timer_set", "timer_name_size", "timer_name_copy"];
+
+// System API functions that are only available if
+// `FeatureFlags::canister_log_levels` is enabled.
+const CANISTER_LOG_LEVEL_SYSTEM_APIS: [&str; 1] = ["log"];
+
 // Performs the following checks for the import section:
 // * If we import memory or table, we can only import from “env”.
 // * Any imported functions that appear in `valid_system_apis` have the correct
 //   signatures.
+// * System API functions behind a disabled feature flag are not imported.
 //
 // Returns information about what IC0 methods are imported via
 // `WasmImportsDetails`.
-fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmValidationError> {
+fn validate_import_section(
+    module: &Module,
+    feature_flags: &FeatureFlags,
+) -> Result<WasmImportsDetails, WasmValidationError> {
     let mut imports_details = WasmImportsDetails::default();
 
     if !module.imports.is_empty() {
-        let valid_system_apis = match main_memory_type(module) {
+        let mut valid_system_apis = match main_memory_type(module) {
             WasmMemoryType::Wasm32 => {
                 let mut all = get_valid_system_apis_common(ValType::I32);
                 all.extend(get_valid_system_apis_32_only());
@@ -898,6 +994,26 @@ fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmVa
             }
             WasmMemoryType::Wasm64 => get_valid_system_apis_common(ValType::I64),
         };
+        for (flag, names) in [
+            (
+                feature_flags.chunked_call_payloads,
+                CHUNKED_CALL_PAYLOAD_SYSTEM_APIS.as_slice(),
+            ),
+            (
+                feature_flags.named_timers,
+                NAMED_TIMER_SYSTEM_APIS.as_slice(),
+            ),
+            (
+                feature_flags.canister_log_levels,
+                CANISTER_LOG_LEVEL_SYSTEM_APIS.as_slice(),
+            ),
+        ] {
+            if flag == FlagStatus::Disabled {
+                for name in names {
+                    valid_system_apis.remove(*name);
+                }
+            }
+        }
         for entry in &module.imports {
             let import_module = entry.module;
             let field = entry.name;
@@ -1146,6 +1262,36 @@ fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), Wa
     Ok(())
 }
 
+// Checks that the module declares exception tags only if the
+// exception-handling proposal is enabled.
+fn validate_tag_section(
+    module: &Module,
+    wasm_exceptions: FlagStatus,
+) -> Result<(), WasmValidationError> {
+    if !module.tags.is_empty() && wasm_exceptions == FlagStatus::Disabled {
+        return Err(WasmValidationError::InvalidTagSection(
+            "Exception handling is not supported.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Returns the text format name of an instruction of the exception-handling
+// proposal.
+fn exception_instruction_name(instruction: &Operator<'_>) -> &'static str {
+    match instruction {
+        Operator::TryTable { .. } => "try_table",
+        Operator::Throw { .. } => "throw",
+        Operator::ThrowRef => "throw_ref",
+        Operator::Try { .. } => "try",
+        Operator::Catch { .. } => "catch",
+        Operator::CatchAll => "catch_all",
+        Operator::Delegate { .. } => "delegate",
+        Operator::Rethrow { .. } => "rethrow",
+        _ => "unknown",
+    }
+}
+
 // Checks that no more than `max_functions` are defined in the
 // module.
 fn validate_function_section(
@@ -1333,12 +1479,34 @@ fn validate_custom_section(
 fn wasm_function_complexity(
     index: usize,
     body: &Body<'_>,
+    wasm_exceptions: FlagStatus,
 ) -> Result<Complexity, WasmValidationError> {
     use Operator::*;
 
     let mut complexity: u64 = 0;
     for instruction in &body.instructions {
         complexity = complexity.saturating_add(match instruction {
+            TryTable { .. } | Throw { .. } | ThrowRef => match wasm_exceptions {
+                FlagStatus::Enabled => 50,
+                FlagStatus::Disabled => {
+                    return Err(WasmValidationError::UnsupportedWasmInstruction {
+                        index,
+                        instruction: exception_instruction_name(instruction).into(),
+                    });
+                }
+            },
+            // Only the final version of the exception-handling proposal is
+            // supported. Modules using the legacy instructions can be
+            // converted with `wasm-opt --translate-to-exnref`.
+            Try { .. } | Catch { .. } | CatchAll | Delegate { .. } | Rethrow { .. } => {
+                return Err(WasmValidationError::UnsupportedWasmInstruction {
+                    index,
+                    instruction: format!(
+                        "{} (legacy exception handling)",
+                        exception_instruction_name(instruction)
+                    ),
+                });
+            }
             Block { .. }
             | Loop { .. }
             | If { .. }
@@ -1494,13 +1662,14 @@ fn wasm_function_complexity(
 
 fn validate_code_section(
     module: &Module,
+    wasm_exceptions: FlagStatus,
 ) -> Result<(NumInstructions, Complexity), WasmValidationError> {
     let mut max_function_size = NumInstructions::new(0);
     let mut max_complexity = Complexity(0);
 
     for (index, func_body) in module.code_sections.iter().enumerate() {
         let size = func_body.instructions.len();
-        let complexity = wasm_function_complexity(index, func_body)?;
+        let complexity = wasm_function_complexity(index, func_body, wasm_exceptions)?;
         if complexity > WASM_FUNCTION_COMPLEXITY_LIMIT {
             return Err(WasmValidationError::FunctionComplexityTooHigh {
                 index,
@@ -1540,7 +1709,11 @@ pub fn wasmtime_validation_config(embedders_config: &EmbeddersConfig) -> wasmtim
     config.generate_address_map(false);
     // The signal handler uses Posix signals, not Mach ports on MacOS.
     config.macos_use_mach_ports(false);
-    config.wasm_backtrace(embedders_config.feature_flags.canister_backtrace == FlagStatus::Enabled);
+    // The Wasm profiler captures backtraces to record call-stack samples.
+    config.wasm_backtrace(
+        embedders_config.feature_flags.canister_backtrace == FlagStatus::Enabled
+            || embedders_config.feature_flags.wasm_profiling == FlagStatus::Enabled,
+    );
     config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Disable);
     config.wasm_bulk_memory(true);
     config.wasm_function_references(false);
@@ -1644,7 +1817,7 @@ pub(super) fn validate_wasm_binary<'a>(
     can_compile(wasm, config)?;
     let module = Module::parse(wasm.as_slice(), false)
         .map_err(|err| WasmValidationError::DecodingError(format!("{}", err)))?;
-    let imports_details = validate_import_section(&module)?;
+    let imports_details = validate_import_section(&module, &config.feature_flags)?;
     validate_export_section(
         &module,
         config.max_number_exported_functions,
@@ -1661,7 +1834,9 @@ pub(super) fn validate_wasm_binary<'a>(
         config.max_wasm_memory_size
     };
     validate_initial_wasm_memory_size(&module, max_wasm_memory_size)?;
-    let (largest_function_instruction_count, max_complexity) = validate_code_section(&module)?;
+    validate_tag_section(&module, config.feature_flags.wasm_exceptions)?;
+    let (largest_function_instruction_count, max_complexity) =
+        validate_code_section(&module, config.feature_flags.wasm_exceptions)?;
     let wasm_metadata = validate_custom_section(&module, config)?;
     Ok((
         WasmValidationDetails {
@@ -1674,3 +1849,88 @@ pub(super) fn validate_wasm_binary<'a>(
         module,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    const EXCEPTIONS_WAT: &str = r#"(module
+        (tag $e (param i32))
+        (func $throw (throw $e (i32.const 1)))
+        (func $rethrow
+            (block $handler (result exnref)
+                (try_table (catch_all_ref $handler)
+                    (call $throw)
+                )
+                (return)
+            )
+            (throw_ref)
+        )
+    )"#;
+
+    fn with_module<T>(wat: &str, f: impl FnOnce(&Module) -> T) -> T {
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = Module::parse(&wasm, false).unwrap();
+        f(&module)
+    }
+
+    #[test]
+    fn exception_handling_is_accepted_when_enabled() {
+        with_module(EXCEPTIONS_WAT, |module| {
+            assert_eq!(validate_tag_section(module, FlagStatus::Enabled), Ok(()));
+            assert_matches!(validate_code_section(module, FlagStatus::Enabled), Ok(_));
+        });
+    }
+
+    #[test]
+    fn exception_handling_is_rejected_when_disabled() {
+        with_module(EXCEPTIONS_WAT, |module| {
+            assert_matches!(
+                validate_tag_section(module, FlagStatus::Disabled),
+                Err(WasmValidationError::InvalidTagSection(_))
+            );
+            assert_eq!(
+                validate_code_section(module, FlagStatus::Disabled),
+                Err(WasmValidationError::UnsupportedWasmInstruction {
+                    index: 0,
+                    instruction: "throw".to_string(),
+                })
+            );
+        });
+    }
+
+    #[test]
+    fn legacy_exception_handling_is_rejected() {
+        let wat = r#"(module
+            (tag $e)
+            (func (try (do (throw $e)) (catch $e)))
+        )"#;
+        with_module(wat, |module| {
+            assert_eq!(
+                validate_code_section(module, FlagStatus::Enabled),
+                Err(WasmValidationError::UnsupportedWasmInstruction {
+                    index: 0,
+                    instruction: "try (legacy exception handling)".to_string(),
+                })
+            );
+        });
+    }
+
+    #[test]
+    fn wasmtime_rejects_exception_handling() {
+        // The engine does not enable the proposal regardless of the flag.
+        let config = EmbeddersConfig {
+            feature_flags: FeatureFlags {
+                wasm_exceptions: FlagStatus::Enabled,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let wasm = BinaryEncodedWasm::new(wat::parse_str(EXCEPTIONS_WAT).unwrap());
+        assert_matches!(
+            validate_wasm_binary(&wasm, &config).err(),
+            Some(WasmValidationError::WasmtimeValidation(_))
+        );
+    }
+}
@@ -37,6 +37,7 @@ use crate::wasm_utils::instrumentation::{
     WasmMemoryType, ACCESSED_PAGES_COUNTER_GLOBAL_NAME, DIRTY_PAGES_COUNTER_GLOBAL_NAME,
     INSTRUCTIONS_COUNTER_GLOBAL_NAME,
 };
+use crate::wasm_utils::profiling::WasmProfiler;
 use crate::{
     serialized_module::SerializedModuleBytes, wasm_utils::validation::wasmtime_validation_config,
 };
@@ -432,6 +433,12 @@ impl WasmtimeEmbedder {
                     .table_elements(MAX_STORE_TABLE_ELEMENTS)
                     .build(),
                 canister_backtrace: self.config.feature_flags.canister_backtrace,
+                profiler: match self.config.feature_flags.wasm_profiling {
+                    FlagStatus::Enabled => Some(WasmProfiler::new(
+                        self.config.wasm_profiling_sample_interval,
+                    )),
+                    FlagStatus::Disabled => None,
+                },
             },
         );
         store.limiter(|state| &mut state.limits);
@@ -757,6 +764,8 @@ pub struct StoreData {
     pub num_stable_dirty_pages_from_non_native_writes: NumOsPages,
     pub limits: StoreLimits,
     pub canister_backtrace: FlagStatus,
+    /// Collects call-stack samples if the `wasm_profiling` feature is enabled.
+    pub profiler: Option<WasmProfiler>,
 }
 
 impl StoreData {
@@ -1,5 +1,5 @@
 use crate::{
-    wasm_utils::instrumentation::WasmMemoryType,
+    wasm_utils::{instrumentation::WasmMemoryType, profiling::PROFILE_SAMPLE_FUN_NAME},
     wasmtime_embedder::{
         convert_backtrace,
         system_api::SystemApiImpl,
@@ -600,6 +600,33 @@ pub fn syscalls<
         })
         .unwrap();
 
+    if feature_flags.canister_log_levels == FlagStatus::Enabled {
+        linker
+            .func_wrap("ic0", "log", {
+                move |mut caller: Caller<'_, StoreData>,
+                      level: u32,
+                      src: I,
+                      size: I,
+                      fields_src: I,
+                      fields_size: I| {
+                    let src: usize = src.try_into().expect("Failed to convert I to usize");
+                    let size: usize = size.try_into().expect("Failed to convert I to usize");
+                    let fields_src: usize =
+                        fields_src.try_into().expect("Failed to convert I to usize");
+                    let fields_size: usize = fields_size
+                        .try_into()
+                        .expect("Failed to convert I to usize");
+                    let num_bytes =
+                        logging_charge_bytes(&mut caller, size.saturating_add(fields_size) as u64)?;
+                    charge_for_cpu_and_mem(&mut caller, overhead::LOG, num_bytes as usize)?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_log(level, src, size, fields_src, fields_size, memory)
+                    })
+                }
+            })
+            .unwrap();
+    }
+
     linker
         .func_wrap("ic0", "trap", {
             move |mut caller: Caller<'_, StoreData>, offset: I, length: I| -> Result<(), _> {
@@ -755,6 +782,66 @@ pub fn syscalls<
         })
         .unwrap();
 
+    if feature_flags.named_timers == FlagStatus::Enabled {
+        linker
+            .func_wrap("ic0", "timer_set", {
+                move |mut caller: Caller<'_, StoreData>,
+                      name_src: I,
+                      name_size: I,
+                      time: u64,
+                      interval_nanos: u64| {
+                    let name_src: usize =
+                        name_src.try_into().expect("Failed to convert I to usize");
+                    let name_size: usize =
+                        name_size.try_into().expect("Failed to convert I to usize");
+                    charge_for_cpu_and_mem(&mut caller, overhead::TIMER_SET, name_size)?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_timer_set(
+                            name_src,
+                            name_size,
+                            Time::from_nanos_since_unix_epoch(time),
+                            interval_nanos,
+                            memory,
+                        )
+                    })
+                    .map(|s| s.as_nanos_since_unix_epoch())
+                }
+            })
+            .unwrap();
+
+        linker
+            .func_wrap("ic0", "timer_name_size", {
+                move |mut caller: Caller<'_, StoreData>| {
+                    charge_for_cpu(&mut caller, overhead::TIMER_NAME_SIZE)?;
+                    with_system_api(&mut caller, |s| s.ic0_timer_name_size()).and_then(|s| {
+                        I::try_from(s).map_err(|e| {
+                            anyhow::Error::msg(format!("ic0::timer_name_size failed: {}", e))
+                        })
+                    })
+                }
+            })
+            .unwrap();
+
+        linker
+            .func_wrap("ic0", "timer_name_copy", {
+                move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                    let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                    let offset: usize = offset.try_into().expect("Failed to convert I to usize");
+                    let size: usize = size.try_into().expect("Failed to convert I to usize");
+                    charge_for_cpu_and_mem(&mut caller, overhead::TIMER_NAME_COPY, size)?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_timer_name_copy(dst, offset, size, memory)
+                    })?;
+                    if feature_flags.write_barrier == FlagStatus::Enabled {
+                        mark_writes_on_bytemap(&mut caller, dst, size)
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .unwrap();
+    }
+
     linker
         .func_wrap("ic0", "performance_counter", {
             move |mut caller: Caller<'_, StoreData>, counter_type: u32| {
@@ -915,6 +1002,30 @@ pub fn syscalls<
         })
         .unwrap();
 
+    if feature_flags.wasm_profiling == FlagStatus::Enabled {
+        linker
+            .func_wrap("__", PROFILE_SAMPLE_FUN_NAME, {
+                move |mut caller: Caller<'_, StoreData>| -> Result<(), _> {
+                    with_error_handling(&mut caller, |c| {
+                        let global = get_num_instructions_global(c)?;
+                        let instruction_counter = load_value(&global, c)?;
+                        let num_samples = match c.data_mut().profiler.as_mut() {
+                            Some(profiler) => profiler.observe(instruction_counter),
+                            None => 0,
+                        };
+                        if num_samples > 0 {
+                            let backtrace = convert_backtrace(&WasmBacktrace::capture(&*c));
+                            if let Some(profiler) = c.data_mut().profiler.as_mut() {
+                                profiler.record(&backtrace, num_samples);
+                            }
+                        }
+                        Ok(())
+                    })
+                }
+            })
+            .unwrap();
+    }
+
     linker
         .func_wrap("ic0", "subnet_self_size", {
             move |mut caller: Caller<'_, StoreData>| {
@@ -1237,6 +1348,37 @@ pub fn syscalls<
         })
         .unwrap();
 
+    if feature_flags.chunked_call_payloads == FlagStatus::Enabled {
+        linker
+            .func_wrap("ic0", "call_with_chunked_payload", {
+                move |mut caller: Caller<'_, StoreData>| {
+                    charge_for_cpu(&mut caller, overhead::CALL_WITH_CHUNKED_PAYLOAD)?;
+                    with_system_api(&mut caller, |system_api| {
+                        system_api.ic0_call_with_chunked_payload()
+                    })
+                }
+            })
+            .unwrap();
+
+        linker
+            .func_wrap("ic0", "msg_arg_chunk_index", {
+                move |mut caller: Caller<'_, StoreData>| {
+                    charge_for_cpu(&mut caller, overhead::MSG_ARG_CHUNK_INDEX)?;
+                    with_system_api(&mut caller, |s| s.ic0_msg_arg_chunk_index())
+                }
+            })
+            .unwrap();
+
+        linker
+            .func_wrap("ic0", "msg_arg_chunk_count", {
+                move |mut caller: Caller<'_, StoreData>| {
+                    charge_for_cpu(&mut caller, overhead::MSG_ARG_CHUNK_COUNT)?;
+                    with_system_api(&mut caller, |s| s.ic0_msg_arg_chunk_count())
+                }
+            })
+            .unwrap();
+    }
+
     // Stable memory APIs are implemented natively in Wasm. Link the functions here to dummy
     // versions that will trap if ever called to satisfy the Wasm module requirements (the alternative
     // would be to change the imports in the Wasm to link to a dummy function but this approach here
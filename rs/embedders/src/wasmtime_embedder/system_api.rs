@@ -14,8 +14,8 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_management_canister_types_private::{
-    EcdsaCurve, EcdsaKeyId, MasterPublicKeyId, SchnorrAlgorithm, SchnorrKeyId, VetKdCurve,
-    VetKdKeyId,
+    CanisterLogField, CanisterLogLevel, EcdsaCurve, EcdsaKeyId, MasterPublicKeyId,
+    SchnorrAlgorithm, SchnorrKeyId, VetKdCurve, VetKdKeyId,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::execution_state::WasmExecutionMode;
@@ -25,14 +25,18 @@ use ic_replicated_state::{
 };
 use ic_types::{
     ingress::WasmResult,
-    messages::{CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
+    messages::{
+        CallContextId, PayloadChunk, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
+    },
     methods::{SystemMethod, WasmClosure},
-    CanisterId, CanisterLog, CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumBytes,
-    NumInstructions, NumOsPages, PrincipalId, SubnetId, Time, MAX_STABLE_MEMORY_IN_BYTES,
+    time::UNIX_EPOCH,
+    CanisterId, CanisterLog, CanisterTimer, CanisterTimers, ComputeAllocation, Cycles,
+    MemoryAllocation, NumBytes, NumInstructions, NumOsPages, PrincipalId, SubnetId, Time,
+    MAX_STABLE_MEMORY_IN_BYTES,
 };
 use ic_utils::deterministic_operations::deterministic_copy_from_slice;
 use ic_wasm_types::doc_ref;
-use request_in_prep::{into_request, RequestInPrep};
+use request_in_prep::{into_request, RequestInPrep, RequestWithPrepayment};
 use sandbox_safe_system_state::{
     CanisterStatusView, SandboxSafeSystemState, SystemStateModifications,
 };
@@ -43,6 +47,7 @@ use std::{
     convert::{From, TryFrom},
     rc::Rc,
     str,
+    time::Duration,
 };
 
 pub mod cycles_balance_change;
@@ -254,6 +259,9 @@ pub enum ApiType {
         time: Time,
         #[serde(with = "serde_bytes")]
         incoming_payload: Vec<u8>,
+        /// The position of `incoming_payload` within the payload of a chunked
+        /// call; `PayloadChunk::WHOLE` if the call is not chunked.
+        incoming_payload_chunk: PayloadChunk,
         incoming_cycles: Cycles,
         caller: PrincipalId,
         call_context_id: CallContextId,
@@ -364,6 +372,9 @@ pub enum ApiType {
         /// Optional outgoing request under construction. If `None` no outgoing
         /// request is currently under construction.
         outgoing_request: Option<RequestInPrep>,
+        /// The name of the expired named timer that triggered the execution
+        /// of `canister_global_timer`, if any.
+        named_timer: Option<String>,
     },
 
     /// For executing the `call_on_cleanup` callback.
@@ -406,7 +417,17 @@ impl ApiType {
             call_context_id,
             outgoing_request: None,
             system_task,
+            named_timer: None,
+        }
+    }
+
+    /// Marks a `SystemTask` as triggered by the expired named timer with the
+    /// given name. Has no effect on other API types.
+    pub fn with_named_timer(mut self, name: String) -> Self {
+        if let Self::SystemTask { named_timer, .. } = &mut self {
+            *named_timer = Some(name);
         }
+        self
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -420,6 +441,7 @@ impl ApiType {
         Self::Update {
             time,
             incoming_payload,
+            incoming_payload_chunk: PayloadChunk::WHOLE,
             incoming_cycles,
             caller,
             call_context_id,
@@ -430,6 +452,19 @@ impl ApiType {
         }
     }
 
+    /// Marks the payload of an `Update` as the given chunk of the payload of a
+    /// chunked call. Has no effect on other API types.
+    pub fn with_incoming_payload_chunk(mut self, chunk: PayloadChunk) -> Self {
+        if let Self::Update {
+            incoming_payload_chunk,
+            ..
+        } = &mut self
+        {
+            *incoming_payload_chunk = chunk;
+        }
+        self
+    }
+
     pub fn replicated_query(
         time: Time,
         incoming_payload: Vec<u8>,
@@ -705,6 +740,9 @@ impl std::fmt::Display for ApiType {
 enum ExecutionMemoryType {
     WasmMemory,
     StableMemory,
+    /// Memory used by the named timers of the canister. It counts towards the
+    /// canister memory usage but not towards the Wasm or stable memory size.
+    NamedTimers,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -836,7 +874,8 @@ impl MemoryUsage {
         }
     }
 
-    /// Tries to allocate the requested amount of the Wasm or stable memory.
+    /// Tries to allocate the requested amount of the Wasm or stable memory (or
+    /// of the memory used by named timers).
     ///
     /// If the canister has memory allocation, then this function doesn't allocate
     /// bytes, but only increases `current_usage`.
@@ -947,6 +986,7 @@ impl MemoryUsage {
             ExecutionMemoryType::StableMemory => {
                 add_memory(&mut self.stable_memory_usage, execution_bytes)
             }
+            ExecutionMemoryType::NamedTimers => Ok(()),
         }
     }
 
@@ -1529,7 +1569,12 @@ impl SystemApiImpl {
         } {
             system_state_modifications
                 .canister_log
-                .add_record(time.as_nanos_since_unix_epoch(), log_message.into_bytes());
+                .add_structured_record(
+                    time.as_nanos_since_unix_epoch(),
+                    CanisterLogLevel::Error,
+                    vec![],
+                    log_message.into_bytes(),
+                );
         }
     }
 
@@ -1550,6 +1595,7 @@ impl SystemApiImpl {
                 request_slots_used: BTreeMap::new(),
                 requests: vec![],
                 new_global_timer: None,
+                new_named_timers: None,
                 canister_log: Default::default(),
                 on_low_wasm_memory_hook_condition_check_result: None,
                 should_bump_canister_version: false,
@@ -1567,6 +1613,7 @@ impl SystemApiImpl {
                 request_slots_used: system_state_modifications.request_slots_used,
                 requests: system_state_modifications.requests,
                 new_global_timer: None,
+                new_named_timers: None,
                 canister_log: Default::default(),
                 on_low_wasm_memory_hook_condition_check_result: None,
                 should_bump_canister_version: false,
@@ -1587,6 +1634,7 @@ impl SystemApiImpl {
                         request_slots_used: BTreeMap::new(),
                         requests: vec![],
                         new_global_timer: None,
+                        new_named_timers: None,
                         canister_log: system_state_modifications.canister_log,
                         on_low_wasm_memory_hook_condition_check_result: None,
                         should_bump_canister_version: false,
@@ -1604,6 +1652,7 @@ impl SystemApiImpl {
                     request_slots_used: BTreeMap::new(),
                     requests: vec![],
                     new_global_timer: None,
+                    new_named_timers: None,
                     canister_log: system_state_modifications.canister_log,
                     on_low_wasm_memory_hook_condition_check_result: None,
                     should_bump_canister_version: true,
@@ -1629,6 +1678,7 @@ impl SystemApiImpl {
                         request_slots_used: BTreeMap::new(),
                         requests: vec![],
                         new_global_timer: None,
+                        new_named_timers: None,
                         canister_log: system_state_modifications.canister_log,
                         on_low_wasm_memory_hook_condition_check_result: None,
                         should_bump_canister_version: false,
@@ -1660,6 +1710,7 @@ impl SystemApiImpl {
                         request_slots_used: BTreeMap::new(),
                         requests: vec![],
                         new_global_timer: None,
+                        new_named_timers: None,
                         canister_log: system_state_modifications.canister_log,
                         on_low_wasm_memory_hook_condition_check_result: None,
                         should_bump_canister_version: false,
@@ -1670,6 +1721,27 @@ impl SystemApiImpl {
         }
     }
 
+    /// Returns the position of the incoming payload within the payload of a
+    /// chunked call. Fails in the same contexts as `ic0_msg_arg_data_size`.
+    fn incoming_payload_chunk(&self, method_name: &str) -> HypervisorResult<PayloadChunk> {
+        match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. } => Err(self.error_for(method_name)),
+            ApiType::Update {
+                incoming_payload_chunk,
+                ..
+            } => Ok(*incoming_payload_chunk),
+            ApiType::Init { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. }
+            | ApiType::NonReplicatedQuery { .. } => Ok(PayloadChunk::WHOLE),
+        }
+    }
+
     /// Wrapper around `self.sandbox_safe_system_state.push_output_request()` that
     /// tries to allocate memory for the `Request` before pushing it.
     ///
@@ -1683,9 +1755,31 @@ impl SystemApiImpl {
         req: Request,
         prepayment_for_response_execution: Cycles,
         prepayment_for_response_transmission: Cycles,
+    ) -> HypervisorResult<i32> {
+        self.push_request_with_prepayment(RequestWithPrepayment {
+            request: req,
+            prepayment_for_response_execution,
+            prepayment_for_response_transmission,
+            pending_chunks_size: NumBytes::new(0),
+            pending_chunks_payment: Cycles::zero(),
+        })
+    }
+
+    /// Same as `push_output_request()`, but for chunked calls also allocates
+    /// guaranteed response message memory for the chunks that remain to be
+    /// sent and refunds the held back cycles on failure.
+    fn push_request_with_prepayment(
+        &mut self,
+        RequestWithPrepayment {
+            request: req,
+            prepayment_for_response_execution,
+            prepayment_for_response_transmission,
+            pending_chunks_size,
+            pending_chunks_payment,
+        }: RequestWithPrepayment,
     ) -> HypervisorResult<i32> {
         let abort = |request: Request, sandbox_safe_system_state: &mut SandboxSafeSystemState| {
-            sandbox_safe_system_state.refund_cycles(request.payment);
+            sandbox_safe_system_state.refund_cycles(request.payment + pending_chunks_payment);
             sandbox_safe_system_state.unregister_callback(request.sender_reply_callback);
         };
 
@@ -1694,7 +1788,9 @@ impl SystemApiImpl {
             // Effectively disable the memory limit checks on system subnets.
             MessageMemoryUsage::ZERO
         } else {
-            memory_usage_of_request(&req)
+            let mut memory_usage = memory_usage_of_request(&req);
+            memory_usage.guaranteed_response += pending_chunks_size;
+            memory_usage
         };
         if let Err(_err) = self.memory_usage.allocate_message_memory(
             memory_usage_of_request,
@@ -1748,6 +1844,39 @@ impl SystemApiImpl {
         );
     }
 
+    /// Appends the specified bytes on the heap to the canister's logs as a
+    /// record with the given level and fields (see `ic0_log`).
+    fn save_structured_log_message(
+        &mut self,
+        level: u32,
+        src: usize,
+        size: usize,
+        fields_src: usize,
+        fields_size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()> {
+        let level =
+            CanisterLogLevel::try_from(level).map_err(|error| ToolchainContractViolation {
+                error: format!("ic0_log failed: {}", error),
+            })?;
+        let content = valid_subslice(
+            "ic0.log",
+            InternalAddress::new(src),
+            InternalAddress::new(size),
+            heap,
+        )?
+        .to_vec();
+        let fields = parse_log_fields(valid_subslice(
+            "ic0.log",
+            InternalAddress::new(fields_src),
+            InternalAddress::new(fields_size),
+            heap,
+        )?)?;
+        self.sandbox_safe_system_state
+            .append_structured_canister_log(self.api_type.time(), level, fields, content);
+        Ok(())
+    }
+
     /// Takes collected canister log records.
     pub fn take_canister_log(&mut self) -> CanisterLog {
         self.sandbox_safe_system_state.take_canister_log()
@@ -2601,11 +2730,7 @@ impl SystemApi for SystemApiImpl {
                     *time,
                 )?;
 
-                self.push_output_request(
-                    req.request,
-                    req.prepayment_for_response_execution,
-                    req.prepayment_for_response_transmission,
-                )
+                self.push_request_with_prepayment(req)
             }
         };
         trace_syscall!(self, CallPerform, result);
@@ -2671,6 +2796,148 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_timer_set(
+        &mut self,
+        name_src: usize,
+        name_size: usize,
+        time: Time,
+        interval_nanos: u64,
+        heap: &[u8],
+    ) -> HypervisorResult<Time> {
+        let result = match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_timer_set")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. } => {
+                // Reply and reject callbacks can be executed in non-replicated mode
+                // iff from within a composite query call. Disallow in that case.
+                if self.execution_parameters.execution_mode == ExecutionMode::NonReplicated {
+                    return Err(self.error_for("ic0_timer_set"));
+                }
+
+                let name = valid_subslice(
+                    "ic0.timer_set name",
+                    InternalAddress::new(name_src),
+                    InternalAddress::new(name_size),
+                    heap,
+                )?;
+                let name = str::from_utf8(name)
+                    .map_err(|_| ToolchainContractViolation {
+                        error: "ic0.timer_set failed because the timer name is not valid UTF-8."
+                            .to_string(),
+                    })?
+                    .to_string();
+
+                // A new timer is charged for like any other canister memory.
+                // Replacing or cancelling a timer does not allocate memory.
+                let named_timers = self.sandbox_safe_system_state.named_timers();
+                if time != UNIX_EPOCH && named_timers.get(&name).is_none() {
+                    named_timers
+                        .can_set(&name)
+                        .map_err(|err| UserContractViolation {
+                            error: format!("ic0.timer_set failed: {}.", err),
+                            suggestion: "Cancel timers that are no longer needed or use \
+                            shorter timer names."
+                                .to_string(),
+                            doc_link: "".to_string(),
+                        })?;
+                    self.memory_usage.allocate_execution_memory(
+                        CanisterTimers::timer_memory_usage(&name),
+                        &self.api_type,
+                        &mut self.sandbox_safe_system_state,
+                        &self.execution_parameters.subnet_memory_saturation,
+                        ExecutionMemoryType::NamedTimers,
+                    )?;
+                }
+
+                let interval =
+                    (interval_nanos != 0).then_some(Duration::from_nanos(interval_nanos));
+                let previous = self
+                    .sandbox_safe_system_state
+                    .set_named_timer(name, time, interval)
+                    .expect("The limits were checked above.");
+                Ok(previous.unwrap_or(UNIX_EPOCH))
+            }
+        };
+        trace_syscall!(
+            self,
+            TimerSet,
+            result,
+            name_src,
+            name_size,
+            time,
+            interval_nanos
+        );
+        result
+    }
+
+    fn ic0_timer_name_size(&self) -> HypervisorResult<usize> {
+        let result = match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_timer_name_size")),
+            ApiType::SystemTask { named_timer, .. } => {
+                Ok(named_timer.as_ref().map_or(0, |name| name.len()))
+            }
+        };
+        trace_syscall!(self, TimerNameSize, result);
+        result
+    }
+
+    fn ic0_timer_name_copy(
+        &self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let result = match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_timer_name_copy")),
+            ApiType::SystemTask { named_timer, .. } => {
+                let name = named_timer.as_deref().unwrap_or_default();
+                valid_subslice(
+                    "ic0.timer_name_copy heap",
+                    InternalAddress::new(dst),
+                    InternalAddress::new(size),
+                    heap,
+                )?;
+                let name_subslice = valid_subslice(
+                    "ic0.timer_name_copy name",
+                    InternalAddress::new(offset),
+                    InternalAddress::new(size),
+                    name.as_bytes(),
+                )?;
+                deterministic_copy_from_slice(&mut heap[dst..dst + size], name_subslice);
+                Ok(())
+            }
+        };
+        trace_syscall!(self, TimerNameCopy, result, dst, offset, size);
+        result
+    }
+
     fn ic0_performance_counter(
         &self,
         performance_counter_type: PerformanceCounterType,
@@ -3333,6 +3600,21 @@ impl SystemApi for SystemApiImpl {
         Ok(())
     }
 
+    fn ic0_log(
+        &mut self,
+        level: u32,
+        src: usize,
+        size: usize,
+        fields_src: usize,
+        fields_size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()> {
+        let result =
+            self.save_structured_log_message(level, src, size, fields_src, fields_size, heap);
+        trace_syscall!(self, Log, result, level, summarize(heap, src, size));
+        result
+    }
+
     fn ic0_trap(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()> {
         const MAX_ERROR_MESSAGE_SIZE: usize = 16 * 1024;
         let size = size.min(MAX_ERROR_MESSAGE_SIZE);
@@ -3446,6 +3728,12 @@ impl SystemApi for SystemApiImpl {
                             .to_string(),
                     }),
 
+                Some(request) if request.is_chunked() =>
+                    Err(HypervisorError::ToolchainContractViolation {
+                        error: "ic0_call_with_best_effort_response failed because the call has a chunked payload."
+                            .to_string(),
+                    }),
+
                 Some(request) => {
                     // No-op if the feature is disabled on this subnet.
                     if self.best_effort_responses.is_enabled_on(subnet_id, subnet_type) {
@@ -3461,6 +3749,73 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_call_with_chunked_payload(&mut self) -> HypervisorResult<()> {
+        // The chunks following the first one are sent by the system as the
+        // previous ones are replied to, which is only possible in replicated
+        // execution.
+        let replicated = self.api_type.execution_mode() == ExecutionMode::Replicated;
+        let result = match &mut self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::InspectMessage { .. } => {
+                Err(self.error_for("ic0_call_with_chunked_payload"))
+            }
+            ApiType::ReplyCallback { .. } | ApiType::RejectCallback { .. } if !replicated => {
+                Err(self.error_for("ic0_call_with_chunked_payload"))
+            }
+            ApiType::Update {
+                outgoing_request, ..
+            }
+            | ApiType::SystemTask {
+                outgoing_request, ..
+            }
+            | ApiType::ReplyCallback {
+                outgoing_request, ..
+            }
+            | ApiType::RejectCallback {
+                outgoing_request, ..
+            } => match outgoing_request {
+                None => Err(HypervisorError::ToolchainContractViolation {
+                    error:
+                        "ic0.call_with_chunked_payload called when no call is under construction."
+                            .to_string(),
+                }),
+                Some(request) if request.is_timeout_set() => {
+                    Err(HypervisorError::ToolchainContractViolation {
+                        error: "ic0.call_with_chunked_payload failed because a timeout is set."
+                            .to_string(),
+                    })
+                }
+                Some(request) => {
+                    request.set_chunked();
+                    Ok(())
+                }
+            },
+        };
+        trace_syscall!(self, CallWithChunkedPayload, result);
+        result
+    }
+
+    fn ic0_msg_arg_chunk_index(&self) -> HypervisorResult<i32> {
+        let result = self
+            .incoming_payload_chunk("ic0_msg_arg_chunk_index")
+            .map(|chunk| chunk.index as i32);
+        trace_syscall!(self, MsgArgChunkIndex, result);
+        result
+    }
+
+    fn ic0_msg_arg_chunk_count(&self) -> HypervisorResult<i32> {
+        let result = self
+            .incoming_payload_chunk("ic0_msg_arg_chunk_count")
+            .map(|chunk| chunk.count as i32);
+        trace_syscall!(self, MsgArgChunkCount, result);
+        result
+    }
+
     fn ic0_msg_deadline(&self) -> HypervisorResult<u64> {
         let result = match self.api_type {
             ApiType::Start { .. }
@@ -3844,6 +4199,32 @@ pub(crate) fn copy_cycles_to_heap(
     Ok(())
 }
 
+/// Parses the fields of a structured log record, which are given as
+/// newline-separated `key=value` pairs. Empty lines are ignored.
+fn parse_log_fields(bytes: &[u8]) -> HypervisorResult<Vec<CanisterLogField>> {
+    let text = std::str::from_utf8(bytes).map_err(|err| ToolchainContractViolation {
+        error: format!(
+            "ic0_log failed because the fields are not valid UTF-8: {}",
+            err
+        ),
+    })?;
+    text.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok(CanisterLogField {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(ToolchainContractViolation {
+                error: format!(
+                    "ic0_log failed because the field {:?} is not of the form key=value",
+                    line
+                ),
+            }),
+        })
+        .collect()
+}
+
 pub(crate) fn valid_subslice<'a>(
     ctx: &str,
     src: InternalAddress,
@@ -4,14 +4,16 @@ use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_logger::ReplicaLogger;
 use ic_types::Time;
 use ic_types::{
-    messages::{CallContextId, Request, NO_DEADLINE},
-    methods::{Callback, WasmClosure},
+    messages::{
+        CallContextId, PayloadChunk, Request, MAX_CHUNKED_CALL_PAYLOAD_IN_BYTES, NO_DEADLINE,
+    },
+    methods::{Callback, PendingPayloadChunks, WasmClosure},
     time::CoarseTime,
     CanisterId, Cycles, NumBytes, PrincipalId,
 };
 use ic_wasm_types::doc_ref;
 use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, time::Duration};
+use std::{collections::VecDeque, convert::TryFrom, time::Duration};
 
 const PAYLOAD_SIZE_SUGGESTION: &str = "Check the canister for errors or redesign the target \
                 API to allow shorter messages";
@@ -57,6 +59,9 @@ pub struct RequestInPrep {
     multiplier_max_size_local_subnet: u64,
     /// If `Some(_)`, this is a best-effort call.
     timeout_seconds: Option<u32>,
+    /// If `true`, the payload may exceed the maximum message size and is sent
+    /// in chunks of at most `max_size_remote_subnet` bytes each.
+    chunked: bool,
 }
 
 impl RequestInPrep {
@@ -138,6 +143,7 @@ impl RequestInPrep {
             max_size_remote_subnet,
             multiplier_max_size_local_subnet,
             timeout_seconds: None,
+            chunked: false,
         })
     }
 
@@ -164,8 +170,7 @@ impl RequestInPrep {
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let current_size = self.method_name.len() + self.method_payload.len();
-        let max_size_local_subnet =
-            self.max_size_remote_subnet * self.multiplier_max_size_local_subnet;
+        let max_size_local_subnet = self.max_payload_size();
         if size as u64 > max_size_local_subnet.get() - current_size as u64 {
             Err(HypervisorError::UserContractViolation {
                 error: format!(
@@ -201,12 +206,37 @@ impl RequestInPrep {
     pub(crate) fn add_cycles(&mut self, cycles: Cycles) {
         self.cycles += cycles;
     }
+
+    pub(crate) fn is_chunked(&self) -> bool {
+        self.chunked
+    }
+
+    pub(crate) fn set_chunked(&mut self) {
+        self.chunked = true;
+    }
+
+    /// The maximum size of the payload (including the method name): that of a
+    /// message to a canister on the same subnet or, for chunked calls, that of
+    /// the whole chunked payload.
+    fn max_payload_size(&self) -> NumBytes {
+        if self.chunked {
+            MAX_CHUNKED_CALL_PAYLOAD_IN_BYTES
+        } else {
+            self.max_size_remote_subnet * self.multiplier_max_size_local_subnet
+        }
+    }
 }
 
 pub(crate) struct RequestWithPrepayment {
     pub request: Request,
     pub prepayment_for_response_execution: Cycles,
     pub prepayment_for_response_transmission: Cycles,
+    /// The byte size of the chunks that remain to be sent after `request`, if
+    /// this is a chunked call.
+    pub pending_chunks_size: NumBytes,
+    /// The cycles attached to the call, if they are held back to be sent along
+    /// with the last chunk of a chunked call rather than with `request`.
+    pub pending_chunks_payment: Cycles,
 }
 
 /// Turns a `RequestInPrep` into a `Request`.
@@ -220,10 +250,11 @@ pub(crate) fn into_request(
         on_cleanup,
         cycles,
         method_name,
-        method_payload,
+        mut method_payload,
         max_size_remote_subnet,
         multiplier_max_size_local_subnet,
         timeout_seconds,
+        chunked,
     }: RequestInPrep,
     call_context_id: CallContextId,
     sandbox_safe_system_state: &mut SandboxSafeSystemState,
@@ -234,7 +265,11 @@ pub(crate) fn into_request(
 
     let payload_size = (method_name.len() + method_payload.len()) as u64;
     {
-        let max_size_local_subnet = max_size_remote_subnet * multiplier_max_size_local_subnet;
+        let max_size_local_subnet = if chunked {
+            MAX_CHUNKED_CALL_PAYLOAD_IN_BYTES
+        } else {
+            max_size_remote_subnet * multiplier_max_size_local_subnet
+        };
         if payload_size > max_size_local_subnet.get() {
             return Err(HypervisorError::UserContractViolation {
                 error: format!(
@@ -275,7 +310,7 @@ pub(crate) fn into_request(
         NO_DEADLINE
     };
 
-    let callback_id = sandbox_safe_system_state.register_callback(Callback::new(
+    let mut callback = Callback::new(
         call_context_id,
         sender,
         destination_canister,
@@ -286,7 +321,38 @@ pub(crate) fn into_request(
         on_reject,
         on_cleanup,
         deadline,
-    ))?;
+    );
+    let mut metadata = sandbox_safe_system_state.request_metadata.clone();
+    let mut pending_chunks_size = NumBytes::new(0);
+    let mut payment = cycles;
+
+    // A chunked payload that does not fit into a single message to another
+    // subnet is split into chunks, each of which is sent along with the method
+    // name. Only the first chunk is sent now, the others are kept with the
+    // callback until the previous chunk is replied to. The attached cycles are
+    // sent along with the last chunk.
+    let chunk_size = (max_size_remote_subnet.get() as usize)
+        .saturating_sub(method_name.len())
+        .max(1);
+    if chunked && method_payload.len() > chunk_size {
+        let mut chunks: VecDeque<Vec<u8>> = method_payload
+            .chunks(chunk_size)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        let count = chunks.len() as u32;
+        method_payload = chunks.pop_front().unwrap_or_default();
+        pending_chunks_size = NumBytes::new(chunks.iter().map(|chunk| chunk.len() as u64).sum());
+        callback = callback.with_pending_chunks(PendingPayloadChunks {
+            method_name: method_name.clone(),
+            metadata: metadata.clone(),
+            count,
+            chunks,
+        });
+        metadata = metadata.with_payload_chunk(PayloadChunk { index: 0, count });
+        payment = Cycles::zero();
+    }
+
+    let callback_id = sandbox_safe_system_state.register_callback(callback)?;
 
     let req = Request {
         sender,
@@ -294,14 +360,14 @@ pub(crate) fn into_request(
         method_name,
         method_payload,
         sender_reply_callback: callback_id,
-        payment: cycles,
-        metadata: sandbox_safe_system_state.request_metadata.clone(),
+        payment,
+        metadata,
         deadline,
     };
     // We cannot call `Request::payload_size_bytes()` before constructing the
     // request, so ensure our separate calculation matches the actual size.
     debug_assert_eq!(
-        req.payload_size_bytes().get(),
+        req.payload_size_bytes().get() + pending_chunks_size.get(),
         payload_size,
         "Inconsistent request payload size calculation"
     );
@@ -310,6 +376,8 @@ pub(crate) fn into_request(
         request: req,
         prepayment_for_response_execution,
         prepayment_for_response_transmission,
+        pending_chunks_size,
+        pending_chunks_payment: cycles - payment,
     })
 }
 
@@ -148,3 +148,14 @@ fn test_set_timeout() {
     assert_eq!(req_in_prep.timeout_seconds, Some(10));
     assert!(req_in_prep.is_timeout_set());
 }
+
+#[test]
+fn chunked_payloads_larger_than_intra_limit_accepted() {
+    let heap = vec![0; 1024];
+    let mut req_in_prep = make_request_in_prep();
+    assert!(!req_in_prep.is_chunked());
+    req_in_prep.set_chunked();
+    assert!(req_in_prep.is_chunked());
+    req_in_prep.extend_method_payload(0, 100, &heap).unwrap();
+    assert_eq!(req_in_prep.method_payload.len(), 100);
+}
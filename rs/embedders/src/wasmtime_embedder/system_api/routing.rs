@@ -7,14 +7,15 @@ use ic_error_types::UserError;
 use ic_management_canister_types_private::{
     BitcoinGetBalanceArgs, BitcoinGetBlockHeadersArgs, BitcoinGetCurrentFeePercentilesArgs,
     BitcoinGetUtxosArgs, BitcoinSendTransactionArgs, CanisterIdRecord, CanisterInfoRequest,
-    ClearChunkStoreArgs, ComputeInitialIDkgDealingsArgs, DeleteCanisterSnapshotArgs,
-    ECDSAPublicKeyArgs, InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs,
-    LoadCanisterSnapshotArgs, MasterPublicKeyId, Method as Ic00Method, NodeMetricsHistoryArgs,
-    Payload, ProvisionalTopUpCanisterArgs, ReadCanisterSnapshotDataArgs,
-    ReadCanisterSnapshotMetadataArgs, ReshareChainKeyArgs, SchnorrPublicKeyArgs, SignWithECDSAArgs,
-    SignWithSchnorrArgs, StoredChunksArgs, SubnetInfoArgs, TakeCanisterSnapshotArgs,
-    UninstallCodeArgs, UpdateSettingsArgs, UploadCanisterSnapshotDataArgs,
-    UploadCanisterSnapshotMetadataArgs, UploadChunkArgs, VetKdDeriveKeyArgs, VetKdPublicKeyArgs,
+    CanisterSnapshotDiffArgs, ClearChunkStoreArgs, CloneCanisterSnapshotArgs,
+    ComputeInitialIDkgDealingsArgs, DeleteCanisterSnapshotArgs, ECDSAPublicKeyArgs,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
+    MasterPublicKeyId, Method as Ic00Method, NodeMetricsHistoryArgs, Payload,
+    ProvisionalTopUpCanisterArgs, ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotMetadataArgs,
+    ReshareChainKeyArgs, SchnorrPublicKeyArgs, SignWithECDSAArgs, SignWithSchnorrArgs,
+    StoredChunksArgs, SubnetInfoArgs, TakeCanisterSnapshotArgs, UninstallCodeArgs,
+    UpdateSettingsArgs, UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs,
+    UploadChunkArgs, VetKdDeriveKeyArgs, VetKdPublicKeyArgs,
 };
 use ic_replicated_state::NetworkTopology;
 use itertools::Itertools;
@@ -66,7 +67,8 @@ pub(super) fn resolve_destination(
         | Ok(Ic00Method::ProvisionalCreateCanisterWithCycles)
         | Ok(Ic00Method::HttpRequest)
         | Ok(Ic00Method::BitcoinSendTransactionInternal)
-        | Ok(Ic00Method::BitcoinGetSuccessors) => Ok(own_subnet.get()),
+        | Ok(Ic00Method::BitcoinGetSuccessors)
+        | Ok(Ic00Method::CloneCanisterSnapshotChunk) => Ok(own_subnet.get()),
         // This message needs to be routed to the NNS subnet.  We assume that
         // this message can only be sent by canisters on the NNS subnet hence
         // returning `own_subnet` here is fine.
@@ -98,6 +100,7 @@ pub(super) fn resolve_destination(
             )
         }
         Ok(Ic00Method::CanisterStatus)
+        | Ok(Ic00Method::CanisterMethodProfile)
         | Ok(Ic00Method::StartCanister)
         | Ok(Ic00Method::StopCanister)
         | Ok(Ic00Method::DeleteCanister)
@@ -302,6 +305,24 @@ pub(super) fn resolve_destination(
                 network_topology,
             )
         }
+        Ok(Ic00Method::CanisterSnapshotDiff) => {
+            let args = CanisterSnapshotDiffArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            route_canister_id(
+                canister_id,
+                Ic00Method::CanisterSnapshotDiff,
+                network_topology,
+            )
+        }
+        Ok(Ic00Method::CloneCanisterSnapshot) => {
+            let args = CloneCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            route_canister_id(
+                canister_id,
+                Ic00Method::CloneCanisterSnapshot,
+                network_topology,
+            )
+        }
         Ok(Ic00Method::ReadCanisterSnapshotMetadata) => {
             let args = ReadCanisterSnapshotMetadataArgs::decode(payload)?;
             let canister_id = args.get_canister_id();
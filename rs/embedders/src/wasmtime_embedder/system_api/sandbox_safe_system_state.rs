@@ -13,9 +13,10 @@ use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_limits::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_logger::{info, ReplicaLogger};
 use ic_management_canister_types_private::{
-    CanisterStatusType, CreateCanisterArgs, InstallChunkedCodeArgs, InstallCodeArgsV2,
-    LoadCanisterSnapshotArgs, MasterPublicKeyId, Method as Ic00Method, Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, UninstallCodeArgs, UpdateSettingsArgs, IC_00,
+    CanisterLogField, CanisterLogLevel, CanisterStatusType, CreateCanisterArgs,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, LoadCanisterSnapshotArgs, MasterPublicKeyId,
+    Method as Ic00Method, Payload, ProvisionalCreateCanisterWithCyclesArgs, UninstallCodeArgs,
+    UpdateSettingsArgs, IC_00,
 };
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
@@ -27,14 +28,17 @@ use ic_replicated_state::{
     CallOrigin, ExecutionTask, MessageMemoryUsage, NetworkTopology, SystemState,
 };
 use ic_types::{
+    canister_timers::CanisterTimersError,
     messages::{CallContextId, CallbackId, RejectContext, Request, RequestMetadata, NO_DEADLINE},
     methods::Callback,
-    time::CoarseTime,
-    CanisterLog, CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumInstructions, Time,
+    time::{CoarseTime, UNIX_EPOCH},
+    CanisterLog, CanisterTimer, CanisterTimers, ComputeAllocation, Cycles, MemoryAllocation,
+    NumInstructions, Time,
 };
 use ic_wasm_types::WasmEngineError;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use std::time::Duration;
 
 /// The information that canisters can see about their own status.
 #[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
@@ -76,6 +80,7 @@ pub struct SystemStateModifications {
     pub(super) request_slots_used: BTreeMap<CanisterId, usize>,
     pub(super) requests: Vec<Request>,
     pub(super) new_global_timer: Option<CanisterTimer>,
+    pub(super) new_named_timers: Option<CanisterTimers>,
     pub(super) canister_log: CanisterLog,
     pub on_low_wasm_memory_hook_condition_check_result: Option<bool>,
     pub(super) should_bump_canister_version: bool,
@@ -93,6 +98,7 @@ impl Default for SystemStateModifications {
             request_slots_used: BTreeMap::new(),
             requests: vec![],
             new_global_timer: None,
+            new_named_timers: None,
             canister_log: Default::default(),
             on_low_wasm_memory_hook_condition_check_result: None,
             should_bump_canister_version: false,
@@ -272,12 +278,16 @@ impl SystemStateModifications {
             | Ok(Ic00Method::NodeMetricsHistory)
             | Ok(Ic00Method::SubnetInfo)
             | Ok(Ic00Method::FetchCanisterLogs)
+            | Ok(Ic00Method::CanisterMethodProfile)
             | Ok(Ic00Method::UploadChunk)
             | Ok(Ic00Method::StoredChunks)
             | Ok(Ic00Method::ClearChunkStore)
             | Ok(Ic00Method::TakeCanisterSnapshot)
             | Ok(Ic00Method::ListCanisterSnapshots)
             | Ok(Ic00Method::DeleteCanisterSnapshot)
+            | Ok(Ic00Method::CanisterSnapshotDiff)
+            | Ok(Ic00Method::CloneCanisterSnapshot)
+            | Ok(Ic00Method::CloneCanisterSnapshotChunk)
             | Ok(Ic00Method::ReadCanisterSnapshotMetadata)
             | Ok(Ic00Method::ReadCanisterSnapshotData)
             | Ok(Ic00Method::UploadCanisterSnapshotMetadata)
@@ -495,6 +505,11 @@ impl SystemStateModifications {
             system_state.global_timer = new_global_timer;
         }
 
+        // Update canister named timers.
+        if let Some(new_named_timers) = self.new_named_timers.take() {
+            system_state.named_timers = new_named_timers;
+        }
+
         // Append canister log.
         system_state.canister_log.append(&mut self.canister_log);
 
@@ -612,12 +627,14 @@ pub struct SandboxSafeSystemState {
     ic00_available_request_slots: usize,
     ic00_aliases: BTreeSet<CanisterId>,
     global_timer: CanisterTimer,
+    named_timers: CanisterTimers,
     canister_version: u64,
     controllers: BTreeSet<PrincipalId>,
     pub(super) request_metadata: RequestMetadata,
     caller: Option<PrincipalId>,
     pub is_wasm64_execution: bool,
     network_topology: NetworkTopology,
+    log_min_level: CanisterLogLevel,
 }
 
 impl SandboxSafeSystemState {
@@ -646,11 +663,13 @@ impl SandboxSafeSystemState {
         subnet_size: usize,
         dirty_page_overhead: NumInstructions,
         global_timer: CanisterTimer,
+        named_timers: CanisterTimers,
         canister_version: u64,
         controllers: BTreeSet<PrincipalId>,
         request_metadata: RequestMetadata,
         caller: Option<PrincipalId>,
         next_canister_log_record_idx: u64,
+        log_min_level: CanisterLogLevel,
         is_wasm64_execution: bool,
         network_topology: NetworkTopology,
     ) -> Self {
@@ -683,12 +702,14 @@ impl SandboxSafeSystemState {
             ic00_available_request_slots,
             ic00_aliases,
             global_timer,
+            named_timers,
             canister_version,
             controllers,
             request_metadata,
             caller,
             is_wasm64_execution,
             network_topology,
+            log_min_level,
         }
     }
 
@@ -797,11 +818,13 @@ impl SandboxSafeSystemState {
             subnet_size,
             dirty_page_overhead,
             system_state.global_timer,
+            system_state.named_timers.clone(),
             system_state.canister_version,
             system_state.controllers.clone(),
             request_metadata,
             caller,
             system_state.canister_log.next_idx(),
+            system_state.log_min_level,
             is_wasm64_execution,
             network_topology.clone(),
         )
@@ -825,6 +848,29 @@ impl SandboxSafeSystemState {
         self.global_timer = timer;
     }
 
+    pub fn named_timers(&self) -> &CanisterTimers {
+        &self.named_timers
+    }
+
+    /// Sets (or replaces) the named timer with the given name; a deadline of
+    /// `0` cancels the timer. Returns the previous deadline of the timer, if
+    /// any.
+    pub fn set_named_timer(
+        &mut self,
+        name: String,
+        deadline: Time,
+        interval: Option<Duration>,
+    ) -> Result<Option<Time>, CanisterTimersError> {
+        let previous = if deadline == UNIX_EPOCH {
+            self.named_timers.cancel(&name)
+        } else {
+            self.named_timers.set(name, deadline, interval)?
+        };
+        // Update both sandbox named timers and the changes.
+        self.system_state_modifications.new_named_timers = Some(self.named_timers.clone());
+        Ok(previous.map(|timer| timer.deadline))
+    }
+
     pub fn take_changes(&mut self) -> SystemStateModifications {
         std::mem::take(&mut self.system_state_modifications)
     }
@@ -1377,6 +1423,25 @@ impl SandboxSafeSystemState {
             .add_record(time.as_nanos_since_unix_epoch(), content);
     }
 
+    /// Appends a log record with the given level and fields to the system
+    /// state changes unless its level is below the minimum log level of the
+    /// canister. Only records written by `ic0.log` are filtered: records of
+    /// `ic0.debug_print` and of traps are always kept.
+    pub fn append_structured_canister_log(
+        &mut self,
+        time: &Time,
+        level: CanisterLogLevel,
+        fields: Vec<CanisterLogField>,
+        content: Vec<u8>,
+    ) {
+        if level < self.log_min_level {
+            return;
+        }
+        self.system_state_modifications
+            .canister_log
+            .add_structured_record(time.as_nanos_since_unix_epoch(), level, fields, content);
+    }
+
     /// Takes collected canister log records.
     pub fn take_canister_log(&mut self) -> CanisterLog {
         std::mem::take(&mut self.system_state_modifications.canister_log)
@@ -1443,11 +1508,13 @@ mod tests {
     use ic_types::{
         messages::{RequestMetadata, NO_DEADLINE},
         time::CoarseTime,
-        CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
-        Time,
+        CanisterTimer, CanisterTimers, ComputeAllocation, Cycles, MemoryAllocation, NumBytes,
+        NumInstructions, Time,
     };
 
-    use super::{CanisterStatusView, SandboxSafeSystemState, SystemStateModifications};
+    use super::{
+        CanisterLogLevel, CanisterStatusView, SandboxSafeSystemState, SystemStateModifications,
+    };
     use crate::wasmtime_embedder::system_api::cycles_balance_change::CyclesBalanceChange;
 
     #[test]
@@ -1540,11 +1607,13 @@ mod tests {
             SMALL_APP_SUBNET_MAX_SIZE,
             SchedulerConfig::application_subnet().dirty_page_overhead,
             CanisterTimer::Inactive,
+            CanisterTimers::default(),
             0,
             BTreeSet::new(),
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
             None,
             0,
+            CanisterLogLevel::default(),
             // Wasm32 execution environment. Sufficient in testing.
             false,
             NetworkTopology::default(),
@@ -1591,11 +1660,13 @@ mod tests {
             SMALL_APP_SUBNET_MAX_SIZE,
             SchedulerConfig::application_subnet().dirty_page_overhead,
             CanisterTimer::Inactive,
+            CanisterTimers::default(),
             0,
             BTreeSet::new(),
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
             None,
             0,
+            CanisterLogLevel::default(),
             // Wasm32 execution environment. Sufficient in testing.
             false,
             NetworkTopology::default(),
@@ -28,6 +28,7 @@ use ic_test_utilities_types::{
     messages::RequestBuilder,
 };
 use ic_types::{
+    canister_timers::MAX_NAMED_TIMERS,
     messages::{
         CallbackId, RejectContext, RequestOrResponse, MAX_RESPONSE_COUNT_BYTES, NO_DEADLINE,
     },
@@ -38,7 +39,7 @@ use ic_types::{
 };
 use maplit::btreemap;
 use more_asserts::assert_le;
-use std::{collections::BTreeSet, convert::From, rc::Rc};
+use std::{collections::BTreeSet, convert::From, rc::Rc, time::Duration};
 use strum::IntoEnumIterator;
 
 mod common;
@@ -183,6 +184,8 @@ fn is_supported(api_type: SystemApiCallId, context: &str) -> bool {
     let matrix = btreemap! {
         SystemApiCallId::MsgArgDataSize => vec!["I", "U", "RQ", "NRQ", "CQ", "Ry", "CRy", "F"],
         SystemApiCallId::MsgArgDataCopy => vec!["I", "U", "RQ", "NRQ", "CQ", "Ry", "CRy", "F"],
+        SystemApiCallId::MsgArgChunkIndex => vec!["I", "U", "RQ", "NRQ", "CQ", "Ry", "CRy", "F"],
+        SystemApiCallId::MsgArgChunkCount => vec!["I", "U", "RQ", "NRQ", "CQ", "Ry", "CRy", "F"],
         SystemApiCallId::MsgCallerSize => vec!["*"],
         SystemApiCallId::MsgCallerCopy => vec!["*"],
         SystemApiCallId::MsgRejectCode => vec!["Ry", "Rt", "CRy", "CRt"],
@@ -216,6 +219,7 @@ fn is_supported(api_type: SystemApiCallId, context: &str) -> bool {
         SystemApiCallId::CallCyclesAdd128 => vec!["U", "Ry", "Rt", "T"],
         SystemApiCallId::CallPerform => vec!["U", "CQ", "Ry", "Rt", "CRy", "CRt", "T"],
         SystemApiCallId::CallWithBestEffortResponse => vec!["U", "CQ", "Ry", "Rt", "CRy", "CRt", "T"],
+        SystemApiCallId::CallWithChunkedPayload => vec!["U", "Ry", "Rt", "T"],
         SystemApiCallId::StableSize => vec!["*", "s"],
         SystemApiCallId::StableGrow => vec!["*", "s"],
         SystemApiCallId::StableWrite => vec!["*", "s"],
@@ -230,6 +234,9 @@ fn is_supported(api_type: SystemApiCallId, context: &str) -> bool {
         SystemApiCallId::DataCertificateCopy => vec!["NRQ", "CQ"],
         SystemApiCallId::Time => vec!["*"],
         SystemApiCallId::GlobalTimerSet => vec!["I", "G", "U", "Ry", "Rt", "C", "T"],
+        SystemApiCallId::TimerSet => vec!["I", "G", "U", "Ry", "Rt", "C", "T"],
+        SystemApiCallId::TimerNameSize => vec!["T"],
+        SystemApiCallId::TimerNameCopy => vec!["T"],
         SystemApiCallId::PerformanceCounter => vec!["*", "s"],
         SystemApiCallId::IsController => vec!["*", "s"],
         SystemApiCallId::InReplicatedExecution => vec!["*", "s"],
@@ -240,6 +247,7 @@ fn is_supported(api_type: SystemApiCallId, context: &str) -> bool {
         SystemApiCallId::CostSignWithSchnorr=> vec!["*", "s"],
         SystemApiCallId::CostVetkdDeriveKey => vec!["*", "s"],
         SystemApiCallId::DebugPrint => vec!["*", "s"],
+        SystemApiCallId::Log => vec!["*", "s"],
         SystemApiCallId::Trap => vec!["*", "s"],
         SystemApiCallId::MintCycles => vec!["U", "Ry", "Rt", "T"],
         SystemApiCallId::MintCycles128 => vec!["U", "Ry", "Rt", "T"],
@@ -299,6 +307,26 @@ fn api_availability_test(
                 context,
             );
         }
+        SystemApiCallId::MsgArgChunkIndex => {
+            assert_api_availability(
+                |api| api.ic0_msg_arg_chunk_index(),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::MsgArgChunkCount => {
+            assert_api_availability(
+                |api| api.ic0_msg_arg_chunk_count(),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
         SystemApiCallId::MsgMethodNameSize => {
             assert_api_availability(
                 |api| api.ic0_msg_method_name_size(),
@@ -469,6 +497,19 @@ fn api_availability_test(
                 context,
             );
         }
+        SystemApiCallId::CallWithChunkedPayload => {
+            assert_api_availability(
+                |mut api| {
+                    let _ = api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[42; 128]);
+                    api.ic0_call_with_chunked_payload()
+                },
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
         SystemApiCallId::CallOnCleanup => {
             assert_api_availability(
                 |mut api| {
@@ -551,6 +592,36 @@ fn api_availability_test(
                 context,
             );
         }
+        SystemApiCallId::TimerSet => {
+            assert_api_availability(
+                |mut api| api.ic0_timer_set(0, 0, time::UNIX_EPOCH, 0, &[42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::TimerNameSize => {
+            assert_api_availability(
+                |api| api.ic0_timer_name_size(),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::TimerNameCopy => {
+            assert_api_availability(
+                |api| api.ic0_timer_name_copy(0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
         SystemApiCallId::PerformanceCounter => {
             assert_api_availability(
                 |api| api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -711,6 +782,16 @@ fn api_availability_test(
             let mut api = get_system_api(api_type, &system_state, cycles_account_manager);
             assert_api_not_supported(api.ic0_mint_cycles128(Cycles::zero(), 0, &mut [0u8; 16]));
         }
+        SystemApiCallId::Log => {
+            assert_api_availability(
+                |mut api| api.ic0_log(1, 0, 0, 0, 0, &[42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
         SystemApiCallId::IsController => {
             assert_api_availability(
                 |api| api.ic0_is_controller(0, 0, &[42; 128]),
@@ -1867,6 +1948,95 @@ fn ic0_global_timer_set_is_propagated_from_sandbox() {
     );
 }
 
+#[test]
+fn ic0_timer_set_is_propagated_from_sandbox() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut system_state = SystemStateBuilder::default().build();
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    let heap = b"ticktock";
+
+    assert_eq!(
+        api.ic0_timer_set(0, 4, Time::from_nanos_since_unix_epoch(1), 10, heap)
+            .unwrap(),
+        time::UNIX_EPOCH
+    );
+    assert_eq!(
+        api.ic0_timer_set(4, 4, Time::from_nanos_since_unix_epoch(3), 0, heap)
+            .unwrap(),
+        time::UNIX_EPOCH
+    );
+    assert_eq!(
+        api.ic0_timer_set(0, 4, Time::from_nanos_since_unix_epoch(2), 10, heap)
+            .unwrap(),
+        Time::from_nanos_since_unix_epoch(1)
+    );
+    // A zero deadline cancels the timer.
+    assert_eq!(
+        api.ic0_timer_set(4, 4, time::UNIX_EPOCH, 0, heap).unwrap(),
+        Time::from_nanos_since_unix_epoch(3)
+    );
+
+    // Propagate system state changes
+    assert!(system_state.named_timers.is_empty());
+    let system_state_modifications = api.take_system_state_modifications();
+    system_state_modifications
+        .apply_changes(
+            UNIX_EPOCH,
+            &mut system_state,
+            &default_network_topology(),
+            subnet_test_id(1),
+            &no_op_logger(),
+        )
+        .unwrap();
+    assert_eq!(system_state.named_timers.len(), 1);
+    let tick = system_state.named_timers.get("tick").unwrap();
+    assert_eq!(tick.deadline, Time::from_nanos_since_unix_epoch(2));
+    assert_eq!(tick.interval, Some(Duration::from_nanos(10)));
+}
+
+#[test]
+fn ic0_timer_set_fails_when_too_many_timers() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let system_state = SystemStateBuilder::default().build();
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    let deadline = Time::from_nanos_since_unix_epoch(1);
+
+    for i in 0..MAX_NAMED_TIMERS {
+        let name = i.to_string();
+        api.ic0_timer_set(0, name.len(), deadline, 0, name.as_bytes())
+            .unwrap();
+    }
+    let err = api.ic0_timer_set(0, 3, deadline, 0, b"new").unwrap_err();
+    assert!(matches!(err, HypervisorError::UserContractViolation { .. }));
+
+    // Replacing an existing timer is still possible.
+    api.ic0_timer_set(0, 1, deadline, 0, b"0").unwrap();
+}
+
+#[test]
+fn ic0_timer_name_is_exposed_to_system_task() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let system_state = SystemStateBuilder::default().build();
+    let api = get_system_api(
+        ApiTypeBuilder::build_system_task_api().with_named_timer("tick".to_string()),
+        &system_state,
+        cycles_account_manager,
+    );
+
+    assert_eq!(api.ic0_timer_name_size().unwrap(), 4);
+    let mut heap = [0; 4];
+    api.ic0_timer_name_copy(0, 0, 4, &mut heap).unwrap();
+    assert_eq!(&heap, b"tick");
+}
+
 #[test]
 fn ic0_is_controller_test() {
     let mut system_state = SystemStateBuilder::default().build();
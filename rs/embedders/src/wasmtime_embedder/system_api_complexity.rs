@@ -25,6 +25,7 @@ pub mod overhead {
     pub const CALL_ON_CLEANUP: NumInstructions = NumInstructions::new(500);
     pub const CALL_PERFORM: NumInstructions = NumInstructions::new(5_000);
     pub const CALL_WITH_BEST_EFFORT_RESPONSE: NumInstructions = NumInstructions::new(500);
+    pub const CALL_WITH_CHUNKED_PAYLOAD: NumInstructions = NumInstructions::new(500);
     pub const CYCLES_BURN128: NumInstructions = NumInstructions::new(500);
     pub const CANISTER_CYCLE_BALANCE: NumInstructions = NumInstructions::new(500);
     pub const CANISTER_CYCLE_BALANCE128: NumInstructions = NumInstructions::new(500);
@@ -49,8 +50,11 @@ pub mod overhead {
     pub const GLOBAL_TIMER_SET: NumInstructions = NumInstructions::new(500);
     pub const IS_CONTROLLER: NumInstructions = NumInstructions::new(1_000);
     pub const IN_REPLICATED_EXECUTION: NumInstructions = NumInstructions::new(500);
+    pub const LOG: NumInstructions = NumInstructions::new(100);
     pub const MSG_ARG_DATA_COPY: NumInstructions = NumInstructions::new(500);
     pub const MSG_ARG_DATA_SIZE: NumInstructions = NumInstructions::new(500);
+    pub const MSG_ARG_CHUNK_COUNT: NumInstructions = NumInstructions::new(500);
+    pub const MSG_ARG_CHUNK_INDEX: NumInstructions = NumInstructions::new(500);
     pub const MSG_CALLER_COPY: NumInstructions = NumInstructions::new(500);
     pub const MSG_CALLER_SIZE: NumInstructions = NumInstructions::new(500);
     pub const MSG_CYCLES_ACCEPT: NumInstructions = NumInstructions::new(500);
@@ -80,6 +84,9 @@ pub mod overhead {
     pub const STABLE64_SIZE: NumInstructions = NumInstructions::new(20);
     pub const STABLE64_WRITE: NumInstructions = NumInstructions::new(20);
     pub const TIME: NumInstructions = NumInstructions::new(500);
+    pub const TIMER_NAME_COPY: NumInstructions = NumInstructions::new(500);
+    pub const TIMER_NAME_SIZE: NumInstructions = NumInstructions::new(500);
+    pub const TIMER_SET: NumInstructions = NumInstructions::new(500);
     pub const TRAP: NumInstructions = NumInstructions::new(500);
 }
 
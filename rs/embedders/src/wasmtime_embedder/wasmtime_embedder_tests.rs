@@ -110,6 +110,7 @@ fn test_wasmtime_system_api() {
             num_stable_dirty_pages_from_non_native_writes: ic_types::NumOsPages::from(0),
             limits: StoreLimits::default(),
             canister_backtrace: config.feature_flags.canister_backtrace,
+            profiler: None,
         },
     );
 
@@ -1362,3 +1362,58 @@ fn test_wasm64_costs_similar_to_wasm32_for_arithmetic_instructions() {
     // Check that the cost in Wasm64 mode is similar to Wasm32 mode.
     assert_eq!(total_cost, total_cost_wasm32);
 }
+
+#[test]
+fn wasm_profiling_records_samples_without_changing_costs() {
+    let wat = r#"
+        (module
+            (func $inner (param $n i32) (result i32)
+                (local $i i32)
+                (local $acc i32)
+                (loop $loop
+                    (local.set $acc (i32.add (local.get $acc) (local.get $i)))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $loop (i32.lt_s (local.get $i) (local.get $n)))
+                )
+                (local.get $acc)
+            )
+            (func $test (export "canister_update test")
+                (drop (call $inner (i32.const 1000)))
+            )
+        )"#;
+
+    let run = |wasm_profiling| {
+        let mut config = EmbeddersConfig::default();
+        config.feature_flags.wasm_profiling = wasm_profiling;
+        config.wasm_profiling_sample_interval = NumInstructions::new(100);
+        let mut instance = WasmtimeInstanceBuilder::new()
+            .with_config(config)
+            .with_wat(wat)
+            .with_num_instructions(NumInstructions::new(1_000_000))
+            .build();
+        instance.run(func_ref("test")).unwrap();
+        let instructions_used = instr_used(&mut instance);
+        (instructions_used, instance.store_data_mut().profiler.take())
+    };
+
+    let (expected_instructions_used, profiler) = run(FlagStatus::Disabled);
+    assert!(profiler.is_none());
+
+    let (instructions_used, profiler) = run(FlagStatus::Enabled);
+    // The injected sampling calls are free under the IC cost model.
+    assert_eq!(instructions_used, expected_instructions_used);
+
+    let stacks = profiler.unwrap().to_collapsed_stacks();
+    assert!(
+        stacks.lines().any(|line| line.starts_with("test;inner ")),
+        "{}",
+        stacks
+    );
+    let num_samples: u64 = stacks
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+        .sum();
+    // The instructions executed after the last sample point are not sampled.
+    assert!(num_samples <= instructions_used / 100);
+    assert!(num_samples + 1 >= instructions_used / 100);
+}
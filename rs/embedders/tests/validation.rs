@@ -82,6 +82,82 @@ fn can_validate_import_section_with_invalid_imported_function() {
     );
 }
 
+#[test]
+fn chunked_call_payload_imports_require_feature_flag() {
+    use ic_config::{embedders::FeatureFlags, flag_status::FlagStatus};
+
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "call_with_chunked_payload" (func))
+                (import "ic0" "msg_arg_chunk_index" (func (result i32)))
+                (import "ic0" "msg_arg_chunk_count" (func (result i32))))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidImportSection(_))
+    );
+
+    let config = EmbeddersConfig {
+        feature_flags: FeatureFlags {
+            chunked_call_payloads: FlagStatus::Enabled,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_matches!(validate_wasm_binary(&wasm, &config), Ok(_));
+}
+
+#[test]
+fn named_timer_imports_require_feature_flag() {
+    use ic_config::{embedders::FeatureFlags, flag_status::FlagStatus};
+
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "timer_set" (func (param i32 i32 i64 i64) (result i64)))
+                (import "ic0" "timer_name_size" (func (result i32)))
+                (import "ic0" "timer_name_copy" (func (param i32 i32 i32))))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidImportSection(_))
+    );
+
+    let config = EmbeddersConfig {
+        feature_flags: FeatureFlags {
+            named_timers: FlagStatus::Enabled,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_matches!(validate_wasm_binary(&wasm, &config), Ok(_));
+}
+
+#[test]
+fn log_import_requires_feature_flag() {
+    use ic_config::{embedders::FeatureFlags, flag_status::FlagStatus};
+
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "log" (func (param i32 i32 i32 i32 i32))))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidImportSection(_))
+    );
+
+    let config = EmbeddersConfig {
+        feature_flags: FeatureFlags {
+            canister_log_levels: FlagStatus::Enabled,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_matches!(validate_wasm_binary(&wasm, &config), Ok(_));
+}
+
 #[test]
 fn can_validate_valid_export_section() {
     let wasm = wat2wasm(
@@ -21,12 +21,13 @@ use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{IngressHistoryWriter, SubnetAvailableMemory};
 use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_management_canister_types_private::{
-    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterSnapshotResponse,
-    CanisterStatusResultV2, CanisterStatusType, ChunkHash, GlobalTimer, Method as Ic00Method,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2,
+    CanisterMethodProfileRecord, CanisterMethodProfileResponse, CanisterSnapshotChunk,
+    CanisterSnapshotDiffResponse, CanisterSnapshotResponse, CanisterStatusResultV2,
+    CanisterStatusType, ChunkHash, Method as Ic00Method, PageRange,
     ReadCanisterSnapshotMetadataResponse, StoredChunksReply, UploadChunkReply,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
-use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
 use ic_replicated_state::{
     canister_snapshots::CanisterSnapshot,
     canister_state::{
@@ -39,7 +40,7 @@ use ic_replicated_state::{
         NextExecution,
     },
     metadata_state::subnet_call_context_manager::InstallCodeCallId,
-    page_map::PageAllocatorFileDescriptor,
+    page_map::{PageAllocatorFileDescriptor, PAGE_SIZE},
     CallOrigin, CanisterState, MessageMemoryUsage, NetworkTopology, ReplicatedState,
     SchedulerState, SystemState,
 };
@@ -57,11 +58,20 @@ use ic_wasm_types::WasmHash;
 use num_traits::{SaturatingAdd, SaturatingSub};
 use prometheus::IntCounter;
 use std::path::PathBuf;
-use std::{convert::TryFrom, str::FromStr, sync::Arc};
+use std::{convert::TryFrom, ops::Range, str::FromStr, sync::Arc};
 
 use types::*;
 pub(crate) mod types;
 
+/// The maximum number of page ranges per memory returned by
+/// `canister_snapshot_diff`, which keeps the response within the size limits.
+const MAX_SNAPSHOT_DIFF_PAGE_RANGES: usize = 20_000;
+
+/// The instructions charged per delta page traversed when comparing a
+/// snapshot with the canister state: a lookup in the other persistent delta
+/// map and a pointer comparison, see `CanisterSnapshot::diff_cost`.
+const SNAPSHOT_DIFF_INSTRUCTIONS_PER_DELTA_PAGE: u64 = 1_000;
+
 /// The entity responsible for managing canisters (creation, installing, etc.)
 pub(crate) struct CanisterManager {
     hypervisor: Arc<Hypervisor>,
@@ -135,7 +145,10 @@ impl CanisterManager {
             | Ok(Ic00Method::BitcoinSendTransactionInternal)
             | Ok(Ic00Method::BitcoinGetCurrentFeePercentiles)
             | Ok(Ic00Method::NodeMetricsHistory)
-            | Ok(Ic00Method::SubnetInfo) => Err(UserError::new(
+            | Ok(Ic00Method::SubnetInfo)
+            // Cloning a snapshot is paid for with the cycles attached to the call.
+            | Ok(Ic00Method::CloneCanisterSnapshot)
+            | Ok(Ic00Method::CloneCanisterSnapshotChunk) => Err(UserError::new(
                 ErrorCode::CanisterRejectedMessage,
                 format!("Only canisters can call ic00 method {}", method_name),
             )),
@@ -144,6 +157,7 @@ impl CanisterManager {
             // of the canister. We assume that the canister always wants to
             // accept messages from its controller.
             Ok(Ic00Method::CanisterStatus)
+            | Ok(Ic00Method::CanisterMethodProfile)
             | Ok(Ic00Method::StartCanister)
             | Ok(Ic00Method::UninstallCode)
             | Ok(Ic00Method::StopCanister)
@@ -158,6 +172,7 @@ impl CanisterManager {
             | Ok(Ic00Method::LoadCanisterSnapshot)
             | Ok(Ic00Method::ListCanisterSnapshots)
             | Ok(Ic00Method::DeleteCanisterSnapshot)
+            | Ok(Ic00Method::CanisterSnapshotDiff)
             | Ok(Ic00Method::ReadCanisterSnapshotMetadata)
             | Ok(Ic00Method::ReadCanisterSnapshotData)
             | Ok(Ic00Method::UploadCanisterSnapshotMetadata)
@@ -295,6 +310,9 @@ impl CanisterManager {
         if let Some(wasm_memory_limit) = settings.wasm_memory_limit() {
             canister.system_state.wasm_memory_limit = Some(wasm_memory_limit);
         }
+        if let Some(log_min_level) = settings.log_min_level() {
+            canister.system_state.log_min_level = log_min_level;
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -765,6 +783,7 @@ impl CanisterManager {
         let log_visibility = canister.system_state.log_visibility.clone();
         let wasm_memory_limit = canister.system_state.wasm_memory_limit;
         let wasm_memory_threshold = canister.system_state.wasm_memory_threshold;
+        let log_min_level = canister.system_state.log_min_level;
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -811,9 +830,39 @@ impl CanisterManager {
                 .egress_payload_size,
             wasm_memory_limit.map(|x| x.get()),
             wasm_memory_threshold.get(),
+            log_min_level,
         ))
     }
 
+    /// Returns the execution statistics per exported method of the canister.
+    ///
+    /// Only the controllers of the canister can read its method profile.
+    pub(crate) fn get_canister_method_profile(
+        &self,
+        sender: PrincipalId,
+        canister: &CanisterState,
+    ) -> Result<CanisterMethodProfileResponse, CanisterManagerError> {
+        // Check sender is a controller.
+        validate_controller(canister, &sender)?;
+
+        let methods = canister
+            .system_state
+            .canister_metrics
+            .method_profile
+            .iter()
+            .map(|(method_name, stats)| CanisterMethodProfileRecord {
+                method_name: method_name.clone(),
+                num_calls: stats.num_calls,
+                instructions: stats.instructions.get(),
+                max_instructions: stats.max_instructions.get(),
+                cycles: stats.cycles.get().into(),
+                dirty_pages: stats.dirty_pages,
+                instruction_histogram: stats.instruction_histogram.to_vec(),
+            })
+            .collect();
+        Ok(CanisterMethodProfileResponse { methods })
+    }
+
     /// Permanently deletes a canister from `ReplicatedState`.
     ///
     /// The canister must be `Stopped` and only the controller of the canister
@@ -1473,6 +1522,14 @@ impl CanisterManager {
     /// and delete it before creating a new one.
     /// Failure to do so will result in the creation of a new snapshot being unsuccessful.
     ///
+    /// If the `base_snapshot` parameter is `Some`, the new snapshot is taken
+    /// incrementally against that snapshot: the memory pages physically shared
+    /// with it are not charged for, and the base snapshot cannot be deleted or
+    /// replaced while the new snapshot exists. Finding the shared pages costs
+    /// instructions proportional to the memory modified since the last
+    /// checkpoint, on top of the cost of a full snapshot, and is charged for
+    /// before any comparison is done.
+    ///
     /// If the new snapshot cannot be created, an appropriate error will be returned.
     pub(crate) fn take_canister_snapshot(
         &self,
@@ -1480,6 +1537,7 @@ impl CanisterManager {
         sender: PrincipalId,
         canister: &mut CanisterState,
         replace_snapshot: Option<SnapshotId>,
+        base_snapshot: Option<SnapshotId>,
         state: &mut ReplicatedState,
         round_limits: &mut RoundLimits,
         resource_saturation: &ResourceSaturation,
@@ -1517,6 +1575,28 @@ impl CanisterManager {
                                 NumInstructions::new(0),
                             );
                         }
+                        // Snapshots that incremental snapshots depend on cannot be replaced,
+                        // including the base of the new snapshot.
+                        if state.canister_snapshots.is_base_snapshot(replace_snapshot)
+                            || base_snapshot == Some(replace_snapshot)
+                        {
+                            return (
+                                Err(CanisterManagerError::CanisterSnapshotInUseAsBase {
+                                    canister_id: canister.canister_id(),
+                                    snapshot_id: replace_snapshot,
+                                }),
+                                NumInstructions::new(0),
+                            );
+                        }
+                        if snapshot.import_in_progress() {
+                            return (
+                                Err(CanisterManagerError::CanisterSnapshotImportInProgress {
+                                    canister_id: canister.canister_id(),
+                                    snapshot_id: replace_snapshot,
+                                }),
+                                NumInstructions::new(0),
+                            );
+                        }
                         snapshot.size()
                     }
                 }
@@ -1554,29 +1634,55 @@ impl CanisterManager {
             );
         }
 
-        let new_snapshot_size = canister.snapshot_size_bytes();
+        // Check that the base snapshot exists and belongs to this canister if provided.
+        let base_snapshot = match base_snapshot {
+            None => None,
+            Some(base_snapshot_id) => match state.canister_snapshots.get(base_snapshot_id) {
+                None => {
+                    return (
+                        Err(CanisterManagerError::CanisterSnapshotNotFound {
+                            canister_id: canister.canister_id(),
+                            snapshot_id: base_snapshot_id,
+                        }),
+                        NumInstructions::new(0),
+                    );
+                }
+                Some(snapshot) if snapshot.canister_id() != canister.canister_id() => {
+                    return (
+                        Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                            canister_id: canister.canister_id(),
+                            snapshot_id: base_snapshot_id,
+                        }),
+                        NumInstructions::new(0),
+                    );
+                }
+                Some(snapshot) if snapshot.import_in_progress() => {
+                    return (
+                        Err(CanisterManagerError::CanisterSnapshotImportInProgress {
+                            canister_id: canister.canister_id(),
+                            snapshot_id: base_snapshot_id,
+                        }),
+                        NumInstructions::new(0),
+                    );
+                }
+                Some(snapshot) => Some((base_snapshot_id, Arc::clone(snapshot))),
+            },
+        };
 
-        let old_memory_usage = canister.memory_usage();
-        let new_memory_usage = canister
-            .memory_usage()
-            .saturating_add(&new_snapshot_size)
-            .saturating_sub(&replace_snapshot_size);
-        if let Err(err) = self.memory_usage_checks(
-            subnet_size,
-            canister,
-            round_limits,
-            new_memory_usage,
-            old_memory_usage,
-            resource_saturation,
-        ) {
-            return (Err(err), NumInstructions::from(0));
-        }
+        let full_snapshot_size = canister.snapshot_size_bytes();
 
-        // Charge for taking a snapshot of the canister.
+        // Charge for taking a snapshot of the canister, including the comparison
+        // with the base snapshot, before doing any of the work.
+        let diff_instructions = base_snapshot.as_ref().map_or(0, |(_, snapshot)| {
+            snapshot
+                .diff_cost(canister)
+                .saturating_mul(SNAPSHOT_DIFF_INSTRUCTIONS_PER_DELTA_PAGE)
+        });
         let instructions = self
             .config
             .canister_snapshot_baseline_instructions
-            .saturating_add(&new_snapshot_size.get().into());
+            .saturating_add(&full_snapshot_size.get().into())
+            .saturating_add(&diff_instructions.into());
 
         if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
             &sender,
@@ -1595,34 +1701,51 @@ impl CanisterManager {
             );
         };
 
+        let base_snapshot = base_snapshot
+            .map(|(base_snapshot_id, snapshot)| (base_snapshot_id, snapshot.diff(canister)));
+        let new_snapshot_size = match &base_snapshot {
+            None => full_snapshot_size,
+            Some((_, diff)) => full_snapshot_size.saturating_sub(&diff.shared_bytes),
+        };
+
+        let old_memory_usage = canister.memory_usage();
+        let new_memory_usage = canister
+            .memory_usage()
+            .saturating_add(&new_snapshot_size)
+            .saturating_sub(&replace_snapshot_size);
+        if let Err(err) = self.memory_usage_checks(
+            subnet_size,
+            canister,
+            round_limits,
+            new_memory_usage,
+            old_memory_usage,
+            resource_saturation,
+        ) {
+            return (Err(err), instructions);
+        }
+
         // Create new snapshot.
         let new_snapshot = match CanisterSnapshot::from_canister(canister, state.time())
             .map_err(CanisterManagerError::from)
         {
-            Ok(s) => s,
+            Ok(s) => match base_snapshot {
+                None => s,
+                Some((base_snapshot_id, diff)) => {
+                    s.with_base_snapshot(base_snapshot_id, diff.shared_bytes)
+                }
+            },
             Err(err) => return (Err(err), instructions),
         };
+        debug_assert_eq!(new_snapshot.size(), new_snapshot_size);
 
         // Delete old snapshot identified by `replace_snapshot` ID.
         if let Some(replace_snapshot) = replace_snapshot {
-            state.canister_snapshots.remove(replace_snapshot);
-            canister.system_state.snapshots_memory_usage = canister
-                .system_state
-                .snapshots_memory_usage
-                .get()
-                .saturating_sub(replace_snapshot_size.get())
-                .into();
-            // Confirm that `snapshots_memory_usage` is updated correctly.
-            debug_assert_eq!(
-                canister.system_state.snapshots_memory_usage,
-                state
-                    .canister_snapshots
-                    .compute_memory_usage_by_canister(canister.canister_id()),
-            );
-            round_limits.subnet_available_memory.increment(
+            remove_replaced_snapshot(
+                canister,
+                replace_snapshot,
                 replace_snapshot_size,
-                NumBytes::from(0),
-                NumBytes::from(0),
+                state,
+                round_limits,
             );
         }
 
@@ -1716,6 +1839,16 @@ impl CanisterManager {
                         NumInstructions::new(0),
                     );
                 }
+                // A snapshot still being cloned from another subnet is incomplete.
+                if snapshot.import_in_progress() {
+                    return (
+                        Err(CanisterManagerError::CanisterSnapshotImportInProgress {
+                            canister_id,
+                            snapshot_id,
+                        }),
+                        NumInstructions::new(0),
+                    );
+                }
                 snapshot
             }
         };
@@ -1947,6 +2080,16 @@ impl CanisterManager {
                         snapshot_id: delete_snapshot_id,
                     });
                 }
+                // Verify that no incremental snapshot depends on it.
+                if state
+                    .canister_snapshots
+                    .is_base_snapshot(delete_snapshot_id)
+                {
+                    return Err(CanisterManagerError::CanisterSnapshotInUseAsBase {
+                        canister_id: canister.canister_id(),
+                        snapshot_id: delete_snapshot_id,
+                    });
+                }
             }
         }
         let old_snapshot = state.canister_snapshots.remove(delete_snapshot_id);
@@ -1973,6 +2116,520 @@ impl CanisterManager {
         Ok(())
     }
 
+    /// Compares the specified canister snapshot with the current state of the
+    /// canister and reports which parts differ.
+    ///
+    /// Comparing a snapshot can only be initiated by the controllers. The
+    /// instructions charged depend on the memory modified since the last
+    /// checkpoint and are charged before the comparison is done.
+    pub(crate) fn canister_snapshot_diff(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        snapshot_id: SnapshotId,
+        state: &ReplicatedState,
+    ) -> (
+        Result<CanisterSnapshotDiffResponse, CanisterManagerError>,
+        NumInstructions,
+    ) {
+        // Check sender is a controller.
+        if let Err(err) = validate_controller(canister, &sender) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        // If not found, the operation fails due to invalid parameters.
+        let Some(snapshot) = state.canister_snapshots.get(snapshot_id) else {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotFound {
+                    canister_id: canister.canister_id(),
+                    snapshot_id,
+                }),
+                NumInstructions::new(0),
+            );
+        };
+        // Verify the provided `snapshot_id` belongs to this canister.
+        if snapshot.canister_id() != canister.canister_id() {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                    canister_id: canister.canister_id(),
+                    snapshot_id,
+                }),
+                NumInstructions::new(0),
+            );
+        }
+        if snapshot.import_in_progress() {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotImportInProgress {
+                    canister_id: canister.canister_id(),
+                    snapshot_id,
+                }),
+                NumInstructions::new(0),
+            );
+        }
+
+        // Charge for the delta pages to traverse.
+        let instructions = self
+            .config
+            .canister_snapshot_baseline_instructions
+            .saturating_add(
+                &snapshot
+                    .diff_cost(canister)
+                    .saturating_mul(SNAPSHOT_DIFF_INSTRUCTIONS_PER_DELTA_PAGE)
+                    .into(),
+            );
+        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
+            &sender,
+            canister,
+            instructions,
+            subnet_size,
+            // Comparing memories does not depend on whether this is a Wasm64 or Wasm32 module.
+            WasmExecutionMode::Wasm32,
+        ) {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
+                NumInstructions::new(0),
+            );
+        };
+
+        let diff = snapshot.diff(canister);
+        let truncated = diff.wasm_memory.len() > MAX_SNAPSHOT_DIFF_PAGE_RANGES
+            || diff.stable_memory.len() > MAX_SNAPSHOT_DIFF_PAGE_RANGES;
+        let page_ranges = |ranges: Vec<Range<u64>>| {
+            ranges
+                .into_iter()
+                .take(MAX_SNAPSHOT_DIFF_PAGE_RANGES)
+                .map(|range| PageRange {
+                    start: range.start,
+                    count: range.end - range.start,
+                })
+                .collect()
+        };
+        (
+            Ok(CanisterSnapshotDiffResponse {
+                page_size: PAGE_SIZE as u64,
+                wasm_memory: page_ranges(diff.wasm_memory),
+                stable_memory: page_ranges(diff.stable_memory),
+                wasm_module_changed: diff.wasm_module_changed,
+                wasm_chunk_store_changed: diff.wasm_chunk_store_changed,
+                truncated,
+            }),
+            instructions,
+        )
+    }
+
+    /// Returns the snapshot `snapshot_id` of `canister` to be cloned into
+    /// another canister by `sender`.
+    ///
+    /// Only the controllers of the canister can clone its snapshots, and only
+    /// complete snapshots can be cloned.
+    pub(crate) fn get_canister_snapshot_to_clone(
+        &self,
+        sender: PrincipalId,
+        canister: &CanisterState,
+        snapshot_id: SnapshotId,
+        state: &ReplicatedState,
+    ) -> Result<Arc<CanisterSnapshot>, CanisterManagerError> {
+        // Check sender is a controller.
+        validate_controller(canister, &sender)?;
+        // If not found, the operation fails due to invalid parameters.
+        let Some(snapshot) = state.canister_snapshots.get(snapshot_id) else {
+            return Err(CanisterManagerError::CanisterSnapshotNotFound {
+                canister_id: canister.canister_id(),
+                snapshot_id,
+            });
+        };
+        // Verify the provided `snapshot_id` belongs to this canister.
+        if snapshot.canister_id() != canister.canister_id() {
+            return Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                canister_id: canister.canister_id(),
+                snapshot_id,
+            });
+        }
+        if snapshot.import_in_progress() {
+            return Err(CanisterManagerError::CanisterSnapshotImportInProgress {
+                canister_id: canister.canister_id(),
+                snapshot_id,
+            });
+        }
+        Ok(Arc::clone(snapshot))
+    }
+
+    /// Returns the cycles charged to the caller of `clone_canister_snapshot`
+    /// for cloning `snapshot`: the cost of copying the snapshot and, if it is
+    /// cloned into a canister on another subnet, the cost of transmitting it
+    /// in chunks of at most `max_chunk_size` bytes.
+    pub(crate) fn clone_canister_snapshot_fee(
+        &self,
+        snapshot: &CanisterSnapshot,
+        cross_subnet: bool,
+        max_chunk_size: usize,
+        subnet_size: usize,
+    ) -> (Cycles, Cycles) {
+        let instructions = self
+            .config
+            .canister_snapshot_baseline_instructions
+            .saturating_add(&snapshot.size().get().into());
+        let execution_fee = self.cycles_account_manager.execution_cost(
+            instructions,
+            subnet_size,
+            // Copying a snapshot does not depend on whether this is a Wasm64 or Wasm32 module.
+            WasmExecutionMode::Wasm32,
+        );
+        let transmission_fee = if cross_subnet {
+            self.cycles_account_manager
+                .xnet_call_performed_fee(subnet_size)
+                * snapshot.export_chunk_count(max_chunk_size)
+                + self
+                    .cycles_account_manager
+                    .xnet_call_bytes_transmitted_fee(snapshot.size(), subnet_size)
+        } else {
+            Cycles::zero()
+        };
+        (execution_fee, transmission_fee)
+    }
+
+    /// Adds `snapshot`, cloned from a snapshot of another canister, to the
+    /// snapshots of `canister`.
+    ///
+    /// The new snapshot is either complete (when cloned on the same subnet) or
+    /// an empty snapshot to be filled in chunk by chunk (when cloned from
+    /// another subnet, see `write_cloned_canister_snapshot_chunk`). In both
+    /// cases its full size is accounted for upfront.
+    ///
+    /// Like taking a snapshot, this can only be initiated by the controllers
+    /// and replaces the snapshot `replace_snapshot`, if provided. The cost of
+    /// the operation is borne by the caller of `clone_canister_snapshot`.
+    ///
+    /// A snapshot cloned from another subnet only replaces `replace_snapshot`
+    /// once all its chunks have arrived, so that the replaced snapshot is kept
+    /// if the clone fails. Until then both snapshots are accounted for.
+    ///
+    /// `source_snapshot_id` is the snapshot the new one shares its contents
+    /// with, if it was cloned on the same subnet.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn add_cloned_canister_snapshot(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        replace_snapshot: Option<SnapshotId>,
+        source_snapshot_id: Option<SnapshotId>,
+        snapshot: CanisterSnapshot,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        resource_saturation: &ResourceSaturation,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        // Check sender is a controller.
+        validate_controller(canister, &sender)?;
+
+        let replace_snapshot_size = match replace_snapshot {
+            Some(replace_snapshot) => match state.canister_snapshots.get(replace_snapshot) {
+                // If not found, the operation fails due to invalid parameters.
+                None => {
+                    return Err(CanisterManagerError::CanisterSnapshotNotFound {
+                        canister_id: canister.canister_id(),
+                        snapshot_id: replace_snapshot,
+                    })
+                }
+                Some(old_snapshot) if old_snapshot.canister_id() != canister.canister_id() => {
+                    return Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                        canister_id: canister.canister_id(),
+                        snapshot_id: replace_snapshot,
+                    })
+                }
+                Some(_) if state.canister_snapshots.is_base_snapshot(replace_snapshot) => {
+                    return Err(CanisterManagerError::CanisterSnapshotInUseAsBase {
+                        canister_id: canister.canister_id(),
+                        snapshot_id: replace_snapshot,
+                    })
+                }
+                Some(old_snapshot) if old_snapshot.import_in_progress() => {
+                    return Err(CanisterManagerError::CanisterSnapshotImportInProgress {
+                        canister_id: canister.canister_id(),
+                        snapshot_id: replace_snapshot,
+                    })
+                }
+                Some(old_snapshot) => old_snapshot.size(),
+            },
+            None => {
+                if state
+                    .canister_snapshots
+                    .count_by_canister(&canister.canister_id())
+                    >= self.config.max_number_of_snapshots_per_canister
+                {
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id: canister.canister_id(),
+                        limit: self.config.max_number_of_snapshots_per_canister,
+                    });
+                }
+                NumBytes::from(0)
+            }
+        };
+
+        let (replace_snapshot, replace_snapshot_size) = match snapshot.import_in_progress() {
+            true => (None, NumBytes::from(0)),
+            false => (replace_snapshot, replace_snapshot_size),
+        };
+        let new_snapshot_size = snapshot.size();
+        let old_memory_usage = canister.memory_usage();
+        let new_memory_usage = canister
+            .memory_usage()
+            .saturating_add(&new_snapshot_size)
+            .saturating_sub(&replace_snapshot_size);
+        self.memory_usage_checks(
+            subnet_size,
+            canister,
+            round_limits,
+            new_memory_usage,
+            old_memory_usage,
+            resource_saturation,
+        )?;
+
+        // Delete old snapshot identified by `replace_snapshot` ID.
+        if let Some(replace_snapshot) = replace_snapshot {
+            remove_replaced_snapshot(
+                canister,
+                replace_snapshot,
+                replace_snapshot_size,
+                state,
+                round_limits,
+            );
+        }
+
+        // Actually deduct memory from the subnet. It's safe to unwrap
+        // here because we already checked the available memory above.
+        round_limits.subnet_available_memory
+            .try_decrement(new_snapshot_size, NumBytes::from(0), NumBytes::from(0))
+            .expect("Error: Cannot fail to decrement SubnetAvailableMemory after checking for availability");
+
+        let snapshot_id =
+            SnapshotId::from((canister.canister_id(), canister.new_local_snapshot_id()));
+        let taken_at_timestamp = *snapshot.taken_at_timestamp();
+        match source_snapshot_id {
+            Some(source_snapshot_id) => state.canister_snapshots.push_duplicate(
+                source_snapshot_id,
+                snapshot_id,
+                Arc::new(snapshot),
+            ),
+            None => state
+                .canister_snapshots
+                .push_import(snapshot_id, Arc::new(snapshot)),
+        };
+        canister.system_state.snapshots_memory_usage = canister
+            .system_state
+            .snapshots_memory_usage
+            .saturating_add(&new_snapshot_size);
+        Ok(CanisterSnapshotResponse::new(
+            &snapshot_id,
+            taken_at_timestamp.as_nanos_since_unix_epoch(),
+            new_snapshot_size,
+        ))
+    }
+
+    /// Adds an empty snapshot to the snapshots of `canister`, to be filled in
+    /// with the contents of a snapshot cloned from another subnet and
+    /// described by `metadata`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn import_canister_snapshot(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        replace_snapshot: Option<SnapshotId>,
+        metadata: &ReadCanisterSnapshotMetadataResponse,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        resource_saturation: &ResourceSaturation,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let chunk_store_size = (metadata.wasm_chunk_store.len() as u64)
+            .saturating_mul(wasm_chunk_store::chunk_size().get());
+        if chunk_store_size > self.config.wasm_chunk_store_max_size.get() {
+            return Err(CanisterManagerError::CanisterSnapshotInvalidMetadata {
+                canister_id: canister.canister_id(),
+                reason: format!(
+                    "The wasm chunk store of {} bytes exceeds the limit of {} bytes",
+                    chunk_store_size, self.config.wasm_chunk_store_max_size
+                ),
+            });
+        }
+        let snapshot = CanisterSnapshot::new_import(
+            canister.canister_id(),
+            state.time(),
+            metadata,
+            Arc::clone(&self.fd_factory),
+        )
+        .map_err(
+            |reason| CanisterManagerError::CanisterSnapshotInvalidMetadata {
+                canister_id: canister.canister_id(),
+                reason,
+            },
+        )?;
+        self.add_cloned_canister_snapshot(
+            subnet_size,
+            sender,
+            canister,
+            replace_snapshot,
+            None,
+            snapshot,
+            state,
+            round_limits,
+            resource_saturation,
+        )
+    }
+
+    /// Writes a chunk of a snapshot cloned from another subnet into the
+    /// snapshot `snapshot_id` of `canister`, which must still be importing.
+    /// The `Finish` chunk completes the import and deletes the snapshot
+    /// `replace_snapshot`, if provided and still present.
+    ///
+    /// `sender` is the caller of `clone_canister_snapshot` and must be a
+    /// controller of the canister.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn write_cloned_canister_snapshot_chunk(
+        &self,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        snapshot_id: SnapshotId,
+        replace_snapshot: Option<SnapshotId>,
+        chunk: CanisterSnapshotChunk,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let canister_id = canister.canister_id();
+        // Check sender is a controller.
+        validate_controller(canister, &sender)?;
+        // Check that the replaced snapshot can still be removed before the
+        // import completes, so that a failure discards the import instead.
+        let replaced_snapshot = match chunk {
+            CanisterSnapshotChunk::Finish => {
+                self.snapshot_replaced_by_import(canister, replace_snapshot, state)?
+            }
+            _ => None,
+        };
+        let snapshot = match state.canister_snapshots.get_mut(snapshot_id) {
+            None => {
+                return Err(CanisterManagerError::CanisterSnapshotNotFound {
+                    canister_id,
+                    snapshot_id,
+                })
+            }
+            Some(snapshot) if snapshot.canister_id() != canister_id => {
+                return Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                    canister_id,
+                    snapshot_id,
+                })
+            }
+            Some(snapshot) => snapshot,
+        };
+        let import_failed = |reason: String| CanisterManagerError::CanisterSnapshotImportFailed {
+            canister_id,
+            snapshot_id,
+            reason,
+        };
+        if !snapshot.import_in_progress() {
+            return Err(import_failed(
+                "the snapshot is not being cloned".to_string(),
+            ));
+        }
+
+        let snapshot = Arc::make_mut(snapshot);
+        let written_bytes = match chunk {
+            CanisterSnapshotChunk::WasmModule { offset, data } => {
+                snapshot
+                    .write_wasm_module(offset, &data)
+                    .map_err(import_failed)?;
+                data.len()
+            }
+            CanisterSnapshotChunk::MainMemory { offset, data } => {
+                snapshot
+                    .write_wasm_memory(offset, &data)
+                    .map_err(import_failed)?;
+                data.len()
+            }
+            CanisterSnapshotChunk::StableMemory { offset, data } => {
+                snapshot
+                    .write_stable_memory(offset, &data)
+                    .map_err(import_failed)?;
+                data.len()
+            }
+            CanisterSnapshotChunk::WasmChunk { data } => {
+                snapshot
+                    .chunk_store_mut()
+                    .insert_chunk(self.config.wasm_chunk_store_max_size, &data)
+                    .map_err(import_failed)?;
+                data.len()
+            }
+            CanisterSnapshotChunk::Finish => {
+                snapshot.finish_import();
+                0
+            }
+            CanisterSnapshotChunk::Metadata(_) | CanisterSnapshotChunk::Abort => {
+                return Err(import_failed("unexpected chunk".to_string()));
+            }
+        };
+        let response = CanisterSnapshotResponse::new(
+            &snapshot_id,
+            snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            snapshot.size(),
+        );
+
+        state.canister_snapshots.add_import_operation(snapshot_id);
+        state.metadata.heap_delta_estimate = state
+            .metadata
+            .heap_delta_estimate
+            .saturating_add(&NumBytes::from(written_bytes as u64));
+
+        if let Some((replace_snapshot, replace_snapshot_size)) = replaced_snapshot {
+            remove_replaced_snapshot(
+                canister,
+                replace_snapshot,
+                replace_snapshot_size,
+                state,
+                round_limits,
+            );
+        }
+        Ok(response)
+    }
+
+    /// Returns the snapshot, and its size, to be removed once a snapshot
+    /// cloned from another subnet with `replace_snapshot` is complete.
+    ///
+    /// The ownership of `replace_snapshot` was validated when the import
+    /// started. If it was deleted in the meantime, the import adds a snapshot
+    /// instead, which must not exceed the maximum number of snapshots.
+    fn snapshot_replaced_by_import(
+        &self,
+        canister: &CanisterState,
+        replace_snapshot: Option<SnapshotId>,
+        state: &ReplicatedState,
+    ) -> Result<Option<(SnapshotId, NumBytes)>, CanisterManagerError> {
+        let canister_id = canister.canister_id();
+        match replace_snapshot
+            .and_then(|snapshot_id| Some((snapshot_id, state.canister_snapshots.get(snapshot_id)?)))
+        {
+            None => {
+                // The importing snapshot is already counted.
+                if state.canister_snapshots.count_by_canister(&canister_id)
+                    > self.config.max_number_of_snapshots_per_canister
+                {
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id,
+                        limit: self.config.max_number_of_snapshots_per_canister,
+                    });
+                }
+                Ok(None)
+            }
+            Some((snapshot_id, _)) if state.canister_snapshots.is_base_snapshot(snapshot_id) => {
+                Err(CanisterManagerError::CanisterSnapshotInUseAsBase {
+                    canister_id,
+                    snapshot_id,
+                })
+            }
+            Some((snapshot_id, snapshot)) => Ok(Some((snapshot_id, snapshot.size()))),
+        }
+    }
+
     /// Depending on the canister architecture (Wasm32 or Wasm64), returns the
     /// maximum memory size that can be allocated by a canister.
     pub(crate) fn get_max_canister_memory_size(
@@ -2008,35 +2665,47 @@ impl CanisterManager {
                 snapshot_id,
             });
         }
+        if snapshot.import_in_progress() {
+            return Err(CanisterManagerError::CanisterSnapshotImportInProgress {
+                canister_id: canister.canister_id(),
+                snapshot_id,
+            });
+        }
 
-        Ok(ReadCanisterSnapshotMetadataResponse {
-            source: snapshot.source(),
-            taken_at_timestamp: snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
-            wasm_module_size: snapshot.execution_snapshot().wasm_binary.len() as u64,
-            exported_globals: snapshot.exported_globals().clone(),
-            wasm_memory_size: snapshot.execution_snapshot().wasm_memory.size.get() as u64
-                * WASM_PAGE_SIZE_IN_BYTES as u64,
-            stable_memory_size: snapshot.execution_snapshot().stable_memory.size.get() as u64
-                * WASM_PAGE_SIZE_IN_BYTES as u64,
-            wasm_chunk_store: snapshot
-                .chunk_store()
-                .keys()
-                .cloned()
-                .map(|x| ChunkHash { hash: x.to_vec() })
-                .collect(),
-            canister_version: snapshot.canister_version(),
-            certified_data: snapshot.certified_data().clone(),
-            global_timer: snapshot
-                .execution_snapshot()
-                .global_timer
-                .map(GlobalTimer::from),
-            on_low_wasm_memory_hook_status: snapshot
-                .execution_snapshot()
-                .on_low_wasm_memory_hook_status,
-        })
+        Ok(snapshot.metadata())
     }
 }
 
+/// Removes the snapshot `replace_snapshot` of `canister` that is being replaced
+/// by a new snapshot and releases the memory it used.
+fn remove_replaced_snapshot(
+    canister: &mut CanisterState,
+    replace_snapshot: SnapshotId,
+    replace_snapshot_size: NumBytes,
+    state: &mut ReplicatedState,
+    round_limits: &mut RoundLimits,
+) {
+    state.canister_snapshots.remove(replace_snapshot);
+    canister.system_state.snapshots_memory_usage = canister
+        .system_state
+        .snapshots_memory_usage
+        .get()
+        .saturating_sub(replace_snapshot_size.get())
+        .into();
+    // Confirm that `snapshots_memory_usage` is updated correctly.
+    debug_assert_eq!(
+        canister.system_state.snapshots_memory_usage,
+        state
+            .canister_snapshots
+            .compute_memory_usage_by_canister(canister.canister_id()),
+    );
+    round_limits.subnet_available_memory.increment(
+        replace_snapshot_size,
+        NumBytes::from(0),
+        NumBytes::from(0),
+    );
+}
+
 /// Uninstalls a canister.
 ///
 /// See https://internetcomputer.org/docs/current/references/ic-interface-spec#ic-uninstall_code
@@ -2062,8 +2731,9 @@ pub fn uninstall_canister(
     // Drop its certified data.
     canister.system_state.certified_data = Vec::new();
 
-    // Deactivate global timer.
+    // Deactivate global timer and cancel named timers.
     canister.system_state.global_timer = CanisterTimer::Inactive;
+    canister.system_state.named_timers.clear();
     // Increment canister version.
     canister.system_state.canister_version += 1;
     match add_canister_change {
@@ -478,6 +478,23 @@ pub(crate) enum CanisterManagerError {
         limit: usize,
     },
     CanisterSnapshotNotEnoughCycles(CanisterOutOfCyclesError),
+    CanisterSnapshotInUseAsBase {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    CanisterSnapshotImportInProgress {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    CanisterSnapshotImportFailed {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+        reason: String,
+    },
+    CanisterSnapshotInvalidMetadata {
+        canister_id: CanisterId,
+        reason: String,
+    },
     LongExecutionAlreadyInProgress {
         canister_id: CanisterId,
     },
@@ -648,6 +665,23 @@ impl AsErrorHelp for CanisterManagerError {
                 suggestion: "".to_string(),
                 doc_link: "".to_string(),
             },
+            CanisterManagerError::CanisterSnapshotInUseAsBase { .. } => ErrorHelp::UserError {
+                suggestion: "Delete the incremental snapshots taken against this snapshot first."
+                    .to_string(),
+                doc_link: "".to_string(),
+            },
+            CanisterManagerError::CanisterSnapshotImportInProgress { .. } => ErrorHelp::UserError {
+                suggestion: "Wait for the `clone_canister_snapshot` call to complete.".to_string(),
+                doc_link: "".to_string(),
+            },
+            CanisterManagerError::CanisterSnapshotImportFailed { .. } => ErrorHelp::UserError {
+                suggestion: "".to_string(),
+                doc_link: "".to_string(),
+            },
+            CanisterManagerError::CanisterSnapshotInvalidMetadata { .. } => ErrorHelp::UserError {
+                suggestion: "".to_string(),
+                doc_link: "".to_string(),
+            },
             CanisterManagerError::LongExecutionAlreadyInProgress { .. } => ErrorHelp::UserError {
                 suggestion: "Try waiting for the long execution to complete.".to_string(),
                 doc_link: doc_ref("long-execution-already-in-progress"),
@@ -962,6 +996,38 @@ impl From<CanisterManagerError> for UserError {
                     format!("Canister snapshotting failed with `{}`{additional_help}", err),
                 )
             }
+            CanisterSnapshotInUseAsBase { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "The snapshot {} of canister {} is the base of an incremental snapshot and cannot be deleted or replaced.{additional_help}", snapshot_id, canister_id,
+                    )
+                )
+            }
+            CanisterSnapshotImportInProgress { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "The snapshot {} of canister {} is still being cloned from another canister.{additional_help}", snapshot_id, canister_id,
+                    )
+                )
+            }
+            CanisterSnapshotImportFailed { canister_id, snapshot_id, reason } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Failed to clone the snapshot {} into canister {}: {}.{additional_help}", snapshot_id, canister_id, reason,
+                    )
+                )
+            }
+            CanisterSnapshotInvalidMetadata { canister_id, reason } => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!(
+                        "Invalid metadata of the snapshot cloned into canister {}: {}.{additional_help}", canister_id, reason,
+                    )
+                )
+            }
             LongExecutionAlreadyInProgress { canister_id } => {
                 Self::new(
                     ErrorCode::CanisterRejectedMessage,
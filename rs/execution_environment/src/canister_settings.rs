@@ -3,7 +3,9 @@ use ic_config::execution_environment::MINIMUM_FREEZING_THRESHOLD;
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, UserError};
 use ic_interfaces::execution_environment::SubnetAvailableMemory;
-use ic_management_canister_types_private::{CanisterSettingsArgs, LogVisibilityV2};
+use ic_management_canister_types_private::{
+    CanisterLogLevel, CanisterSettingsArgs, LogVisibilityV2,
+};
 use ic_replicated_state::MessageMemoryUsage;
 use ic_types::{
     ComputeAllocation, Cycles, InvalidComputeAllocationError, InvalidMemoryAllocationError,
@@ -29,6 +31,7 @@ pub(crate) struct CanisterSettings {
     pub(crate) reserved_cycles_limit: Option<Cycles>,
     pub(crate) log_visibility: Option<LogVisibilityV2>,
     pub(crate) wasm_memory_limit: Option<NumBytes>,
+    pub(crate) log_min_level: Option<CanisterLogLevel>,
 }
 
 impl CanisterSettings {
@@ -41,6 +44,7 @@ impl CanisterSettings {
         reserved_cycles_limit: Option<Cycles>,
         log_visibility: Option<LogVisibilityV2>,
         wasm_memory_limit: Option<NumBytes>,
+        log_min_level: Option<CanisterLogLevel>,
     ) -> Self {
         Self {
             controllers,
@@ -51,6 +55,7 @@ impl CanisterSettings {
             reserved_cycles_limit,
             log_visibility,
             wasm_memory_limit,
+            log_min_level,
         }
     }
 
@@ -85,6 +90,10 @@ impl CanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn log_min_level(&self) -> Option<CanisterLogLevel> {
+        self.log_min_level
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -174,6 +183,7 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             reserved_cycles_limit,
             input.log_visibility,
             wasm_memory_limit,
+            input.log_min_level,
         ))
     }
 }
@@ -198,6 +208,7 @@ pub(crate) struct CanisterSettingsBuilder {
     reserved_cycles_limit: Option<Cycles>,
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<NumBytes>,
+    log_min_level: Option<CanisterLogLevel>,
 }
 
 #[allow(dead_code)]
@@ -212,6 +223,7 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: None,
             log_visibility: None,
             wasm_memory_limit: None,
+            log_min_level: None,
         }
     }
 
@@ -225,6 +237,7 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
+            log_min_level: self.log_min_level,
         }
     }
 
@@ -283,6 +296,13 @@ impl CanisterSettingsBuilder {
             ..self
         }
     }
+
+    pub fn with_log_min_level(self, log_min_level: CanisterLogLevel) -> Self {
+        Self {
+            log_min_level: Some(log_min_level),
+            ..self
+        }
+    }
 }
 
 pub enum UpdateSettingsError {
@@ -378,6 +398,7 @@ pub(crate) struct ValidatedCanisterSettings {
     reservation_cycles: Cycles,
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<NumBytes>,
+    log_min_level: Option<CanisterLogLevel>,
 }
 
 impl ValidatedCanisterSettings {
@@ -416,6 +437,10 @@ impl ValidatedCanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn log_min_level(&self) -> Option<CanisterLogLevel> {
+        self.log_min_level
+    }
 }
 
 /// Validates the new canisters settings:
@@ -608,5 +633,6 @@ pub(crate) fn validate_canister_settings(
         reservation_cycles,
         log_visibility: settings.log_visibility().cloned(),
         wasm_memory_limit: settings.wasm_memory_limit(),
+        log_min_level: settings.log_min_level(),
     })
 }
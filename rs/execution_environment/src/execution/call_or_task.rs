@@ -164,13 +164,22 @@ pub fn execute_call_or_task(
     };
 
     let api_type = match &original.call_or_task {
-        CanisterCallOrTask::Update(msg) => ApiType::update(
-            time,
-            msg.method_payload().to_vec(),
-            msg.cycles(),
-            *msg.sender(),
-            helper.call_context_id(),
-        ),
+        CanisterCallOrTask::Update(msg) => {
+            let api_type = ApiType::update(
+                time,
+                msg.method_payload().to_vec(),
+                msg.cycles(),
+                *msg.sender(),
+                helper.call_context_id(),
+            );
+            match msg {
+                CanisterCall::Request(request) => match request.metadata.payload_chunk() {
+                    Some(chunk) => api_type.with_incoming_payload_chunk(*chunk),
+                    None => api_type,
+                },
+                CanisterCall::Ingress(_) => api_type,
+            }
+        }
         CanisterCallOrTask::Query(msg) => ApiType::replicated_query(
             time,
             msg.method_payload().to_vec(),
@@ -183,12 +192,18 @@ pub fn execute_call_or_task(
             time,
             helper.call_context_id(),
         ),
-        CanisterCallOrTask::Task(CanisterTask::GlobalTimer) => ApiType::system_task(
-            IC_00.get(),
-            SystemMethod::CanisterGlobalTimer,
-            time,
-            helper.call_context_id(),
-        ),
+        CanisterCallOrTask::Task(CanisterTask::GlobalTimer) => {
+            let api_type = ApiType::system_task(
+                IC_00.get(),
+                SystemMethod::CanisterGlobalTimer,
+                time,
+                helper.call_context_id(),
+            );
+            match helper.named_timer() {
+                Some(name) => api_type.with_named_timer(name.to_string()),
+                None => api_type,
+            }
+        }
         CanisterCallOrTask::Task(CanisterTask::OnLowWasmMemory) => ApiType::system_task(
             IC_00.get(),
             SystemMethod::CanisterOnLowWasmMemory,
@@ -340,6 +355,8 @@ struct CallOrTaskHelper {
     canister: CanisterState,
     call_context_id: CallContextId,
     initial_cycles_balance: Cycles,
+    /// The name of the expired named timer executed by this global timer task.
+    named_timer: Option<String>,
     deallocation_sender: DeallocationSender,
 }
 
@@ -399,6 +416,7 @@ impl CallOrTaskHelper {
             .unwrap();
 
         let initial_cycles_balance = canister.system_state.balance();
+        let mut named_timer = None;
 
         match original.call_or_task {
             CanisterCallOrTask::Update(_)
@@ -406,8 +424,16 @@ impl CallOrTaskHelper {
             | CanisterCallOrTask::Task(CanisterTask::Heartbeat)
             | CanisterCallOrTask::Task(CanisterTask::OnLowWasmMemory) => {}
             CanisterCallOrTask::Task(CanisterTask::GlobalTimer) => {
-                // The global timer is one-off.
-                canister.system_state.global_timer = CanisterTimer::Inactive;
+                // Expired named timers take precedence. The global timer
+                // stays active and fires in a later round.
+                named_timer = canister
+                    .system_state
+                    .named_timers
+                    .take_next_expired(original.time);
+                if named_timer.is_none() {
+                    // The global timer is one-off.
+                    canister.system_state.global_timer = CanisterTimer::Inactive;
+                }
             }
         }
 
@@ -415,6 +441,7 @@ impl CallOrTaskHelper {
             canister,
             call_context_id,
             initial_cycles_balance,
+            named_timer,
             deallocation_sender: deallocation_sender.clone(),
         })
     }
@@ -529,7 +556,7 @@ impl CallOrTaskHelper {
             );
         }
 
-        let heap_delta = match original.call_or_task {
+        let (heap_delta, stable_memory_delta) = match original.call_or_task {
             // Update methods and tasks can persist changes to the canister's state.
             CanisterCallOrTask::Update(_) | CanisterCallOrTask::Task(_) => {
                 apply_canister_state_changes(
@@ -549,9 +576,13 @@ impl CallOrTaskHelper {
                 );
 
                 if output.wasm_result.is_ok() {
-                    NumBytes::from((output.instance_stats.dirty_pages() * ic_sys::PAGE_SIZE) as u64)
+                    let stats = &output.instance_stats;
+                    (
+                        NumBytes::from((stats.dirty_pages() * ic_sys::PAGE_SIZE) as u64),
+                        NumBytes::from((stats.stable_dirty_pages * ic_sys::PAGE_SIZE) as u64),
+                    )
                 } else {
-                    NumBytes::from(0)
+                    (NumBytes::from(0), NumBytes::from(0))
                 }
             }
             // Query methods only persist certain changes to the canister's state.
@@ -574,7 +605,7 @@ impl CallOrTaskHelper {
                         round,
                     );
                 }
-                NumBytes::from(0)
+                (NumBytes::from(0), NumBytes::from(0))
             }
         };
 
@@ -664,6 +695,7 @@ impl CallOrTaskHelper {
             response,
             instructions_used,
             heap_delta,
+            stable_memory_delta,
             call_duration: call_context
                 .map(|call_context| round.time.saturating_duration_since(call_context.time())),
         }
@@ -676,6 +708,10 @@ impl CallOrTaskHelper {
     fn call_context_id(&self) -> CallContextId {
         self.call_context_id
     }
+
+    fn named_timer(&self) -> Option<&str> {
+        self.named_timer.as_deref()
+    }
 }
 
 #[derive(Debug)]
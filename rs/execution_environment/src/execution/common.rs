@@ -582,6 +582,7 @@ pub(crate) fn finish_call_with_error(
         response,
         instructions_used,
         heap_delta: NumBytes::from(0),
+        stable_memory_delta: NumBytes::from(0),
         call_duration: Some(Duration::from_secs(0)),
     }
 }
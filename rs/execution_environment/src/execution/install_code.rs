@@ -148,9 +148,11 @@ impl InstallCodeHelper {
         self.canister.system_state.certified_data = Vec::new();
     }
 
+    /// Deactivates the global timer and cancels all named timers.
     pub fn deactivate_global_timer(&mut self) {
         self.steps.push(InstallCodeStep::DeactivateGlobalTimer);
         self.canister.system_state.global_timer = CanisterTimer::Inactive;
+        self.canister.system_state.named_timers.clear();
     }
 
     pub fn bump_canister_version(&mut self) {
@@ -518,6 +520,7 @@ impl InstallCodeHelper {
                 reserved_cycles_limit: None,
                 log_visibility: None,
                 wasm_memory_limit: None,
+                log_min_level: None,
             },
             self.canister.memory_usage(),
             self.canister.message_memory_usage(),
@@ -4,6 +4,7 @@
 use std::sync::Arc;
 
 use ic_base_types::CanisterId;
+use ic_error_types::RejectCode;
 use ic_limits::LOG_CANISTER_OPERATION_CYCLES_THRESHOLD;
 use ic_replicated_state::canister_state::system_state::CyclesUseCase;
 
@@ -14,15 +15,15 @@ use ic_embedders::{
     wasmtime_embedder::system_api::{ApiType, ExecutionParameters},
 };
 use ic_interfaces::execution_environment::{
-    CanisterOutOfCyclesError, HypervisorError, WasmExecutionOutput,
+    CanisterOutOfCyclesError, HypervisorError, InstanceStats, WasmExecutionOutput,
 };
 use ic_logger::{error, info, ReplicaLogger};
 use ic_replicated_state::{CallContext, CallOrigin, CanisterState};
 use ic_sys::PAGE_SIZE;
 use ic_types::ingress::WasmResult;
 use ic_types::messages::{
-    CallContextId, CallbackId, CanisterMessage, CanisterMessageOrTask, Payload, RequestMetadata,
-    Response,
+    CallContextId, CallbackId, CanisterMessage, CanisterMessageOrTask, Payload, RejectContext,
+    RequestMetadata, Response,
 };
 use ic_types::methods::{Callback, FuncRef, WasmClosure};
 use ic_types::Cycles;
@@ -142,8 +143,14 @@ impl ResponseHelper {
         //
         // Therefore, the cycles in the response must not exceed the cycles in
         // the request. Otherwise, there might be potentially malicious faults.
+        //
+        // The cycles attached to a chunked call are sent along with the last
+        // chunk. If the call ends before the last chunk is sent, they are
+        // refunded in full.
         debug_assert!(response.refund <= original.callback.cycles_sent);
-        let refund_for_sent_cycles = if response.refund > original.callback.cycles_sent {
+        let refund_for_sent_cycles = if original.callback.pending_chunks.is_some() {
+            original.callback.cycles_sent
+        } else if response.refund > original.callback.cycles_sent {
             round.counters.response_cycles_refund_error.inc();
             error!(
                 round.log,
@@ -245,6 +252,7 @@ impl ResponseHelper {
                 return Err(ExecuteMessageResult::Finished {
                     canister: self.canister,
                     heap_delta: NumBytes::from(0),
+                    stable_memory_delta: NumBytes::from(0),
                     instructions_used: NumInstructions::from(0),
                     response: ExecutionResponse::Empty,
                     call_duration: Some(round.time.saturating_duration_since(call_context.time())),
@@ -424,7 +432,7 @@ impl ResponseHelper {
             Ok(_) => Ok(self.finish(
                 output.wasm_result,
                 instructions_available,
-                NumBytes::from((output.instance_stats.dirty_pages() * PAGE_SIZE) as u64),
+                &output.instance_stats,
                 original,
                 round,
                 round_limits,
@@ -498,7 +506,7 @@ impl ResponseHelper {
                 self.finish(
                     Err(callback_err),
                     output.num_instructions_left,
-                    NumBytes::from((output.instance_stats.dirty_pages() * PAGE_SIZE) as u64),
+                    &output.instance_stats,
                     original,
                     round,
                     round_limits,
@@ -512,7 +520,7 @@ impl ResponseHelper {
                 self.finish(
                     result,
                     output.num_instructions_left,
-                    NumBytes::from(0),
+                    &InstanceStats::default(),
                     original,
                     round,
                     round_limits,
@@ -529,12 +537,15 @@ impl ResponseHelper {
         mut self,
         result: Result<Option<WasmResult>, HypervisorError>,
         instructions_left: NumInstructions,
-        heap_delta: NumBytes,
+        instance_stats: &InstanceStats,
         original: &OriginalContext,
         round: &RoundContext,
         round_limits: &mut RoundLimits,
     ) -> ExecuteMessageResult {
         self.revert_subnet_memory_reservation(original, round_limits);
+        let heap_delta = NumBytes::from((instance_stats.dirty_pages() * PAGE_SIZE) as u64);
+        let stable_memory_delta =
+            NumBytes::from((instance_stats.stable_dirty_pages * PAGE_SIZE) as u64);
 
         let instructions_used = NumInstructions::from(
             original
@@ -603,6 +614,7 @@ impl ResponseHelper {
             response,
             instructions_used,
             heap_delta,
+            stable_memory_delta,
             call_duration: call_context
                 .map(|call_context| round.time.saturating_duration_since(call_context.time())),
         }
@@ -620,7 +632,7 @@ impl ResponseHelper {
         self.finish(
             result,
             original.message_instruction_limit,
-            NumBytes::from(0),
+            &InstanceStats::default(),
             original,
             round,
             round_limits,
@@ -921,12 +933,54 @@ pub fn execute_response(
                     canister: clean_canister,
                     instructions_used: NumInstructions::from(0),
                     heap_delta: NumBytes::from(0),
+                    stable_memory_delta: NumBytes::from(0),
                     response: ExecutionResponse::Empty,
                     call_duration: None,
                 };
             }
         };
 
+    // A reply to a chunk of a chunked call other than the last one is not
+    // delivered to the canister. Instead, the next chunk is sent.
+    let (clean_canister, response, callback) = if callback.pending_chunks.is_some()
+        && !call_context.is_deleted()
+        && matches!(response.response_payload, Payload::Data(_))
+    {
+        match send_next_payload_chunk(
+            clean_canister,
+            &response,
+            &callback,
+            callback_id,
+            time,
+            subnet_size,
+            &round,
+        ) {
+            Ok(canister) => {
+                return ExecuteMessageResult::Finished {
+                    canister,
+                    instructions_used: NumInstructions::from(0),
+                    heap_delta: NumBytes::from(0),
+                    stable_memory_delta: NumBytes::from(0),
+                    response: ExecutionResponse::Empty,
+                    call_duration: None,
+                };
+            }
+            // The next chunk could not be sent: deliver a reject instead, to
+            // the callback as it is now.
+            Err((canister, reject)) => {
+                let callback = canister
+                    .system_state
+                    .call_context_manager()
+                    .and_then(|ccm| ccm.callback(callback_id))
+                    .cloned()
+                    .unwrap_or(callback);
+                (canister, Arc::new(reject), callback)
+            }
+        }
+    } else {
+        (clean_canister, response, callback)
+    };
+
     let freezing_threshold = round.cycles_account_manager.freeze_threshold_cycles(
         clean_canister.system_state.freeze_threshold,
         clean_canister.system_state.memory_allocation,
@@ -1035,6 +1089,109 @@ pub fn execute_response(
     )
 }
 
+/// Handles a reply to a chunk of a chunked call other than the last one by
+/// sending the next chunk. The canister is refunded for the unused part of the
+/// response transmission prepayment and pays for the transmission of the next
+/// chunk, as if it was a separate call.
+///
+/// Returns the updated canister or, if the next chunk could not be sent, the
+/// canister along with a reject response to deliver instead.
+#[allow(clippy::result_large_err)]
+fn send_next_payload_chunk(
+    mut canister: CanisterState,
+    response: &Response,
+    callback: &Callback,
+    callback_id: CallbackId,
+    time: Time,
+    subnet_size: usize,
+    round: &RoundContext,
+) -> Result<CanisterState, (CanisterState, Response)> {
+    // Only the last chunk carries cycles, so there is nothing to refund.
+    debug_assert!(response.refund.is_zero());
+    let refund_for_response_transmission = round
+        .cycles_account_manager
+        .refund_for_response_transmission(
+            round.log,
+            round.counters.response_cycles_refund_error,
+            response,
+            callback.prepayment_for_response_transmission,
+            subnet_size,
+        );
+    canister.system_state.add_cycles(
+        refund_for_response_transmission,
+        CyclesUseCase::RequestAndResponseTransmission,
+    );
+
+    let pending_chunks = match &callback.pending_chunks {
+        Some(pending_chunks) => pending_chunks,
+        None => return Ok(canister),
+    };
+    let next_chunk_index = pending_chunks.count - pending_chunks.chunks.len() as u32;
+    let next_chunk_size = pending_chunks.method_name.len()
+        + pending_chunks.chunks.front().map_or(0, |chunk| chunk.len());
+    let transmission_fee = round.cycles_account_manager.xnet_total_transmission_fee(
+        NumBytes::new(next_chunk_size as u64),
+        subnet_size,
+        callback.prepayment_for_response_transmission,
+    );
+
+    let memory_usage = canister.memory_usage();
+    let message_memory_usage = canister.message_memory_usage();
+    let compute_allocation = canister.compute_allocation();
+    let result = round
+        .cycles_account_manager
+        .consume_cycles(
+            &mut canister.system_state,
+            memory_usage,
+            message_memory_usage,
+            compute_allocation,
+            transmission_fee,
+            subnet_size,
+            CyclesUseCase::RequestAndResponseTransmission,
+            false,
+        )
+        .map_err(|err| (err.to_string(), Cycles::zero()))
+        .and_then(|()| {
+            canister
+                .system_state
+                .push_next_chunk_request(callback_id, time)
+                .map_err(|(err, payment)| {
+                    canister.system_state.add_cycles(
+                        transmission_fee,
+                        CyclesUseCase::RequestAndResponseTransmission,
+                    );
+                    (err.to_string(), payment)
+                })
+        });
+
+    match result {
+        Ok(_) => Ok(canister),
+        Err((err, payment)) => {
+            // The reject takes the place of the reply to the last chunk sent,
+            // so the response transmission prepayment is refunded based on it.
+            canister.system_state.remove_cycles(
+                refund_for_response_transmission,
+                CyclesUseCase::RequestAndResponseTransmission,
+            );
+            let reject = Response {
+                originator: response.originator,
+                respondent: response.respondent,
+                originator_reply_callback: callback_id,
+                refund: payment,
+                response_payload: Payload::Reject(RejectContext::new(
+                    RejectCode::SysTransient,
+                    format!(
+                        "Failed to send chunk {} of {} of the call payload: {}",
+                        next_chunk_index, pending_chunks.count, err
+                    ),
+                )),
+                deadline: response.deadline,
+            };
+            Err((canister, reject))
+        }
+    }
+}
+
 // Reserves a percentage of message instructions limit for a cleanup callback execution.
 fn reserve_cleanup_instructions(
     mut execution_parameters: ExecutionParameters,
@@ -1193,7 +1350,7 @@ fn process_response_result(
                             helper.finish(
                                 Err(err),
                                 instructions_left,
-                                NumBytes::from(0),
+                                &InstanceStats::default(),
                                 &original,
                                 &round,
                                 round_limits,
@@ -18,7 +18,10 @@ use ic_test_utilities_metrics::fetch_int_counter;
 use ic_test_utilities_types::messages::ResponseBuilder;
 use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
-    messages::{CallbackId, MessageId, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, NO_DEADLINE},
+    messages::{
+        CallbackId, MessageId, PayloadChunk, RequestOrResponse,
+        MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, NO_DEADLINE,
+    },
     CanisterId, ComputeAllocation, Cycles, MemoryAllocation, NumInstructions, SubnetId, Time,
 };
 use ic_types_test_utils::ids::{SUBNET_1, SUBNET_42};
@@ -3144,3 +3147,113 @@ fn test_ic0_msg_deadline_best_effort_responses_feature_flag() {
         );
     }
 }
+
+// A Wasm module making a chunked call with a 3 MiB payload to canister 777 and
+// replying to the ingress message.
+const CALL_CHUNKED_WAT: &str = r#"(module
+                  (import "ic0" "call_new"
+                    (func $ic0_call_new
+                      (param i32 i32)
+                      (param $method_name_src i32)    (param $method_name_len i32)
+                      (param $reply_fun i32)          (param $reply_env i32)
+                      (param $reject_fun i32)         (param $reject_env i32)
+                  ))
+                  (import "ic0" "call_data_append" (func $ic0_call_data_append (param $src i32) (param $size i32)))
+                  (import "ic0" "call_with_chunked_payload" (func $ic0_call_with_chunked_payload))
+                  (import "ic0" "call_perform" (func $ic0_call_perform (result i32)))
+                  (import "ic0" "msg_reply" (func $msg_reply))
+                  (func $test
+                    (call $ic0_call_new
+                        (i32.const 100) (i32.const 10)  ;; callee canister id = 777
+                        (i32.const 0) (i32.const 6)     ;; refers to "upload" on the heap
+                        (i32.const 11) (i32.const 22)   ;; fictive on_reply closure
+                        (i32.const 33) (i32.const 44)   ;; fictive on_reject closure
+                    )
+                    (call $ic0_call_with_chunked_payload)
+                    (call $ic0_call_data_append
+                        (i32.const 0) (i32.const 3145728) ;; 3 MiB of the heap
+                    )
+                    (drop (call $ic0_call_perform))
+                    (call $msg_reply)
+                  )
+                  (export "canister_update test" (func $test))
+                  (memory $memory 64)
+                  (export "memory" (memory $memory))
+                  (data (i32.const 0) "upload")
+                  (data (i32.const 100) "\00\00\00\00\00\00\03\09\01\01")
+            )"#;
+
+#[test]
+fn chunked_call_sends_next_chunk_on_reply() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_manual_execution()
+        .with_chunked_call_payloads()
+        .build();
+    let a_id = test
+        .canister_from_cycles_and_wat(Cycles::new(1_000_000_000_000), CALL_CHUNKED_WAT)
+        .unwrap();
+    let b_id = CanisterId::from_u64(777);
+    let callback_id = CallbackId::from(1);
+    let chunk_size = MAX_INTER_CANISTER_PAYLOAD_IN_BYTES.get() as usize - "upload".len();
+
+    test.ingress_raw(a_id, "test", vec![]);
+    test.execute_message(a_id);
+
+    // Only the first chunk is sent, the rest is kept with the callback.
+    let pop_request = |test: &mut ExecutionTest| match test
+        .canister_state_mut(a_id)
+        .system_state
+        .queues_mut()
+        .pop_canister_output(&b_id)
+    {
+        Some(RequestOrResponse::Request(request)) => request,
+        message => panic!("Expected a request, got {:?}", message),
+    };
+    let first = pop_request(&mut test);
+    assert_eq!("upload", first.method_name);
+    assert_eq!(chunk_size, first.method_payload.len());
+    assert_eq!(
+        Some(&PayloadChunk { index: 0, count: 2 }),
+        first.metadata.payload_chunk()
+    );
+    let pending_chunks_memory_usage = |test: &ExecutionTest| {
+        test.canister_state(a_id)
+            .system_state
+            .call_context_manager()
+            .unwrap()
+            .pending_chunks_memory_usage()
+    };
+    assert_eq!((3 << 20) - chunk_size, pending_chunks_memory_usage(&test));
+
+    // A reply to the first chunk is not delivered to the canister: the second
+    // chunk is sent instead.
+    let response = ResponseBuilder::new()
+        .originator(a_id)
+        .respondent(b_id)
+        .originator_reply_callback(callback_id)
+        .build();
+    let instructions_before = test.canister_executed_instructions(a_id);
+    assert_matches!(
+        test.execute_response(a_id, response),
+        ExecutionResponse::Empty
+    );
+    assert_eq!(
+        instructions_before,
+        test.canister_executed_instructions(a_id)
+    );
+    let second = pop_request(&mut test);
+    assert_eq!(callback_id, second.sender_reply_callback);
+    assert_eq!((3 << 20) - chunk_size, second.method_payload.len());
+    assert_eq!(
+        Some(&PayloadChunk { index: 1, count: 2 }),
+        second.metadata.payload_chunk()
+    );
+    assert_eq!(0, pending_chunks_memory_usage(&test));
+    assert!(test
+        .canister_state(a_id)
+        .system_state
+        .call_context_manager()
+        .unwrap()
+        .callback(callback_id)
+        .is_some());
+}
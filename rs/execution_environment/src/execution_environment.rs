@@ -16,6 +16,7 @@ use crate::{
     },
     hypervisor::Hypervisor,
     ic00_permissions::Ic00MethodPermissions,
+    message_profiler::MessageProfiler,
     metrics::{CallTreeMetrics, CallTreeMetricsImpl, IngressFilterMetrics},
 };
 use candid::Encode;
@@ -39,11 +40,13 @@ use ic_limits::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SI
 use ic_logger::{error, info, warn, ReplicaLogger};
 use ic_management_canister_types_private::{
     CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest,
-    CanisterInfoResponse, CanisterStatusType, ClearChunkStoreArgs, ComputeInitialIDkgDealingsArgs,
-    CreateCanisterArgs, DeleteCanisterSnapshotArgs, ECDSAPublicKeyArgs, ECDSAPublicKeyResponse,
-    EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs,
-    LoadCanisterSnapshotArgs, MasterPublicKeyId, Method as Ic00Method, NodeMetricsHistoryArgs,
-    Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
+    CanisterInfoResponse, CanisterSnapshotChunk, CanisterSnapshotDiffArgs,
+    CanisterSnapshotResponse, CanisterStatusType, ClearChunkStoreArgs, CloneCanisterSnapshotArgs,
+    CloneCanisterSnapshotChunkArgs, ComputeInitialIDkgDealingsArgs, CreateCanisterArgs,
+    DeleteCanisterSnapshotArgs, ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EmptyBlob,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
+    MasterPublicKeyId, Method as Ic00Method, NodeMetricsHistoryArgs, Payload as Ic00Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
     ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotMetadataArgs, ReshareChainKeyArgs,
     SchnorrAlgorithm, SchnorrPublicKeyArgs, SchnorrPublicKeyResponse, SetupInitialDKGArgs,
     SignWithECDSAArgs, SignWithSchnorrArgs, SignWithSchnorrAux, StoredChunksArgs, SubnetInfoArgs,
@@ -55,15 +58,16 @@ use ic_metrics::MetricsRegistry;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
+    canister_snapshots::CanisterSnapshotCloneCursor,
     canister_state::{
         execution_state::WasmExecutionMode,
         system_state::{CyclesUseCase, PausedExecutionId},
         NextExecution,
     },
     metadata_state::subnet_call_context_manager::{
-        EcdsaArguments, InstallCodeCall, InstallCodeCallId, ReshareChainKeyContext,
-        SchnorrArguments, SetupInitialDkgContext, SignWithThresholdContext, StopCanisterCall,
-        SubnetCallContext, ThresholdArguments, VetKdArguments,
+        CanisterSnapshotCloneContext, EcdsaArguments, InstallCodeCall, InstallCodeCallId,
+        ReshareChainKeyContext, SchnorrArguments, SetupInitialDkgContext, SignWithThresholdContext,
+        StopCanisterCall, SubnetCallContext, ThresholdArguments, VetKdArguments,
     },
     page_map::PageAllocatorFileDescriptor,
     CanisterState, ExecutionTask, NetworkTopology, ReplicatedState,
@@ -81,12 +85,12 @@ use ic_types::{
         extract_effective_canister_id, CanisterCall, CanisterCallOrTask, CanisterMessage,
         CanisterMessageOrTask, CanisterTask, Payload, RejectContext, Request, Response,
         SignedIngressContent, StopCanisterCallId, StopCanisterContext,
-        MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
+        MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, NO_DEADLINE,
     },
     methods::SystemMethod,
     nominal_cycles::NominalCycles,
     CanisterId, Cycles, ExecutionRound, Height, NumBytes, NumInstructions, ReplicaVersion,
-    SubnetId, Time,
+    SnapshotId, SubnetId, Time,
 };
 use ic_types::{messages::MessageId, methods::WasmMethod};
 use ic_utils_thread::deallocator_thread::{DeallocationSender, DeallocatorThread};
@@ -136,6 +140,9 @@ pub enum ExecuteMessageResult {
         /// The size of the heap delta the canister produced
         heap_delta: NumBytes,
 
+        /// The part of `heap_delta` produced by writes to the stable memory.
+        stable_memory_delta: NumBytes,
+
         /// The call duration, if the call context completed.
         call_duration: Option<Duration>,
     },
@@ -344,6 +351,8 @@ pub struct ExecutionEnvironment {
     // the number of scheduler cores.
     resource_saturation_scaling: usize,
     deallocator_thread: DeallocatorThread,
+    // Records the cost of completed canister messages (if enabled).
+    message_profiler: Arc<MessageProfiler>,
 }
 
 /// This is a helper enum that indicates whether the current DTS execution of
@@ -440,9 +449,15 @@ impl ExecutionEnvironment {
             paused_execution_registry: Default::default(),
             resource_saturation_scaling,
             deallocator_thread,
+            message_profiler: Default::default(),
         }
     }
 
+    /// Returns the profiler recording the cost of completed canister messages.
+    pub fn message_profiler(&self) -> Arc<MessageProfiler> {
+        Arc::clone(&self.message_profiler)
+    }
+
     pub fn state_changes_error(&self) -> &IntCounter {
         &self.metrics.state_changes_error
     }
@@ -525,6 +540,12 @@ impl ExecutionEnvironment {
                     .retrieve_context(response.originator_reply_callback, &self.log);
                 return match context {
                     None => (state, Some(NumInstructions::from(0))),
+                    Some(SubnetCallContext::CanisterSnapshotClone(context)) => {
+                        self.handle_canister_snapshot_clone_response(
+                            context, &response, &mut state,
+                        );
+                        (state, Some(NumInstructions::from(0)))
+                    }
                     Some(context) => {
                         let time_elapsed =
                             state.time().saturating_duration_since(context.get_time());
@@ -855,6 +876,17 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::CanisterMethodProfile) => {
+                let res = CanisterIdRecord::decode(payload).and_then(|args| {
+                    self.get_canister_method_profile(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|res| (res, Some(args.get_canister_id())))
+                });
+                ExecuteSubnetMessageResult::Finished {
+                    response: res,
+                    refund: msg.take_cycles(),
+                }
+            }
+
             Ok(Ic00Method::CanisterInfo) => match &msg {
                 CanisterCall::Request(_) => {
                     let res = CanisterInfoRequest::decode(payload).and_then(|record| {
@@ -1656,6 +1688,74 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::CanisterSnapshotDiff) => match CanisterSnapshotDiffArgs::decode(payload)
+            {
+                Err(err) => ExecuteSubnetMessageResult::Finished {
+                    response: Err(err),
+                    refund: msg.take_cycles(),
+                },
+                Ok(args) => {
+                    let canister_id = args.get_canister_id();
+                    let (result, instructions_used) = self.canister_snapshot_diff(
+                        *msg.sender(),
+                        &mut state,
+                        args,
+                        registry_settings.subnet_size,
+                    );
+                    let msg_result = ExecuteSubnetMessageResult::Finished {
+                        response: result.map(|res| (res, Some(canister_id))),
+                        refund: msg.take_cycles(),
+                    };
+
+                    let state = self.finish_subnet_message_execution(state, msg, msg_result, since);
+                    return (state, Some(instructions_used));
+                }
+            },
+
+            Ok(Ic00Method::CloneCanisterSnapshot) => match &msg {
+                CanisterCall::Request(request) => {
+                    match CloneCanisterSnapshotArgs::decode(payload) {
+                        Err(err) => ExecuteSubnetMessageResult::Finished {
+                            response: Err(err),
+                            refund: msg.take_cycles(),
+                        },
+                        Ok(args) => self.clone_canister_snapshot(
+                            request,
+                            args,
+                            &mut state,
+                            registry_settings.subnet_size,
+                            round_limits,
+                        ),
+                    }
+                }
+                CanisterCall::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::CloneCanisterSnapshot)
+                }
+            },
+
+            Ok(Ic00Method::CloneCanisterSnapshotChunk) => match &msg {
+                CanisterCall::Request(_) => {
+                    let res = CloneCanisterSnapshotChunkArgs::decode(payload).and_then(|args| {
+                        let canister_id = args.get_canister_id();
+                        self.clone_canister_snapshot_chunk(
+                            *msg.sender(),
+                            args,
+                            &mut state,
+                            registry_settings.subnet_size,
+                            round_limits,
+                        )
+                        .map(|res| (res, Some(canister_id)))
+                    });
+                    ExecuteSubnetMessageResult::Finished {
+                        response: res,
+                        refund: msg.take_cycles(),
+                    }
+                }
+                CanisterCall::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::CloneCanisterSnapshotChunk)
+                }
+            },
+
             Ok(Ic00Method::ReadCanisterSnapshotMetadata) => {
                 let res = ReadCanisterSnapshotMetadataArgs::decode(payload).and_then(|args| {
                     match self.config.canister_snapshot_download {
@@ -1907,6 +2007,7 @@ impl ExecutionEnvironment {
                     response: ExecutionResponse::Request(_),
                     instructions_used: _,
                     heap_delta: _,
+                    stable_memory_delta: _,
                     call_duration: Some(duration),
                 } = &result
                 {
@@ -2160,6 +2261,20 @@ impl ExecutionEnvironment {
             .map_err(|err| err.into())
     }
 
+    fn get_canister_method_profile(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = get_canister(canister_id, state)?;
+
+        self.canister_manager
+            .get_canister_method_profile(sender, canister)
+            .map(|profile| profile.encode())
+            .map_err(|err| err.into())
+    }
+
     fn get_canister_info(
         &self,
         canister_id: CanisterId,
@@ -2321,11 +2436,13 @@ impl ExecutionEnvironment {
         let resource_saturation =
             self.subnet_memory_saturation(&round_limits.subnet_available_memory);
         let replace_snapshot = args.replace_snapshot();
+        let base_snapshot = args.base_snapshot();
         let (result, instructions_used) = self.canister_manager.take_canister_snapshot(
             subnet_size,
             sender,
             &mut canister,
             replace_snapshot,
+            base_snapshot,
             state,
             round_limits,
             &resource_saturation,
@@ -2448,6 +2565,515 @@ impl ExecutionEnvironment {
         result
     }
 
+    /// Compares the specified canister snapshot with the current state of the canister.
+    fn canister_snapshot_diff(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: CanisterSnapshotDiffArgs,
+        subnet_size: usize,
+    ) -> (Result<Vec<u8>, UserError>, NumInstructions) {
+        let canister_id = args.get_canister_id();
+        // Take canister out.
+        let mut canister = match state.take_canister_state(&canister_id) {
+            None => {
+                return (
+                    Err(UserError::new(
+                        ErrorCode::CanisterNotFound,
+                        format!("Canister {} not found.", &canister_id),
+                    )),
+                    NumInstructions::new(0),
+                )
+            }
+            Some(canister) => canister,
+        };
+
+        let (result, instructions_used) = self.canister_manager.canister_snapshot_diff(
+            subnet_size,
+            sender,
+            &mut canister,
+            args.get_snapshot_id(),
+            state,
+        );
+        // Put canister back.
+        state.put_canister_state(canister);
+
+        match result {
+            Ok(response) => (Ok(response.encode()), instructions_used),
+            Err(err) => (Err(err.into()), instructions_used),
+        }
+    }
+
+    /// Clones a snapshot into another canister. If the target canister is
+    /// hosted by this subnet, the snapshot is cloned directly. Otherwise it is
+    /// sent to the subnet hosting the target canister chunk by chunk, see
+    /// `handle_canister_snapshot_clone_response`.
+    ///
+    /// The caller pays for copying and, if applicable, transmitting the
+    /// snapshot with the cycles attached to the request.
+    fn clone_canister_snapshot(
+        &self,
+        request: &Request,
+        args: CloneCanisterSnapshotArgs,
+        state: &mut ReplicatedState,
+        subnet_size: usize,
+        round_limits: &mut RoundLimits,
+    ) -> ExecuteSubnetMessageResult {
+        let sender = request.sender.get();
+        let snapshot_id = args.get_snapshot_id();
+        let target_canister_id = args.get_target_canister_id();
+        let reject = |err: UserError| ExecuteSubnetMessageResult::Finished {
+            response: Err(err),
+            refund: request.payment,
+        };
+
+        let snapshot = match get_canister(args.get_canister_id(), state).and_then(|canister| {
+            self.canister_manager
+                .get_canister_snapshot_to_clone(sender, canister, snapshot_id, state)
+                .map_err(UserError::from)
+        }) {
+            Ok(snapshot) => snapshot,
+            Err(err) => return reject(err),
+        };
+        let Some(target_subnet_id) = state
+            .metadata
+            .network_topology
+            .routing_table
+            .route(target_canister_id.get())
+        else {
+            return reject(UserError::new(
+                ErrorCode::CanisterNotFound,
+                format!("Canister {} not found.", &target_canister_id),
+            ));
+        };
+
+        let cross_subnet = target_subnet_id != self.own_subnet_id;
+        let (execution_fee, transmission_fee) = self.canister_manager.clone_canister_snapshot_fee(
+            &snapshot,
+            cross_subnet,
+            CLONE_CANISTER_SNAPSHOT_CHUNK_SIZE,
+            subnet_size,
+        );
+        let fee = execution_fee + transmission_fee;
+        if request.payment < fee {
+            return reject(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "{} request sent with {} cycles, but {} cycles are required.",
+                    Ic00Method::CloneCanisterSnapshot,
+                    request.payment,
+                    fee
+                ),
+            ));
+        }
+
+        if !cross_subnet {
+            let mut target_canister = match state.take_canister_state(&target_canister_id) {
+                None => {
+                    return reject(UserError::new(
+                        ErrorCode::CanisterNotFound,
+                        format!("Canister {} not found.", &target_canister_id),
+                    ))
+                }
+                Some(canister) => canister,
+            };
+            let resource_saturation =
+                self.subnet_memory_saturation(&round_limits.subnet_available_memory);
+            let result = self.canister_manager.add_cloned_canister_snapshot(
+                subnet_size,
+                sender,
+                &mut target_canister,
+                args.replace_snapshot(),
+                Some(snapshot_id),
+                snapshot.cloned_into(target_canister_id, state.time()),
+                state,
+                round_limits,
+                &resource_saturation,
+            );
+            state.put_canister_state(target_canister);
+
+            return match result {
+                Ok(response) => {
+                    observe_clone_canister_snapshot_fee(state, execution_fee, transmission_fee);
+                    ExecuteSubnetMessageResult::Finished {
+                        response: Ok((response.encode(), Some(target_canister_id))),
+                        refund: request.payment - fee,
+                    }
+                }
+                Err(err) => reject(err.into()),
+            };
+        }
+
+        // The metadata is always the first piece of a snapshot.
+        let (chunk, cursor) = snapshot
+            .export_chunk(
+                CanisterSnapshotCloneCursor::Metadata,
+                CLONE_CANISTER_SNAPSHOT_CHUNK_SIZE,
+            )
+            .unwrap();
+        let mut context_request = request.clone();
+        context_request.payment -= fee;
+        let context = CanisterSnapshotCloneContext {
+            request: context_request,
+            snapshot_id,
+            target_canister_id,
+            replace_snapshot_id: args.replace_snapshot(),
+            target_snapshot_id: None,
+            cursor,
+            time: state.time(),
+        };
+        match self.push_canister_snapshot_clone_chunk(context, chunk, state) {
+            Ok(()) => {
+                observe_clone_canister_snapshot_fee(state, execution_fee, transmission_fee);
+                ExecuteSubnetMessageResult::Processing
+            }
+            Err(err) => reject(err),
+        }
+    }
+
+    /// Sends `chunk` of the snapshot being cloned to the subnet hosting the
+    /// target canister and keeps track of `context` until the subnet
+    /// acknowledges the chunk.
+    fn push_canister_snapshot_clone_chunk(
+        &self,
+        context: CanisterSnapshotCloneContext,
+        chunk: CanisterSnapshotChunk,
+        state: &mut ReplicatedState,
+    ) -> Result<(), UserError> {
+        let target_canister_id = context.target_canister_id;
+        let Some(target_subnet_id) = state
+            .metadata
+            .network_topology
+            .routing_table
+            .route(target_canister_id.get())
+        else {
+            return Err(UserError::new(
+                ErrorCode::CanisterNotFound,
+                format!("Canister {} not found.", &target_canister_id),
+            ));
+        };
+        let method_payload = CloneCanisterSnapshotChunkArgs::new(
+            context.request.sender.get(),
+            target_canister_id,
+            context.target_snapshot_id,
+            context.replace_snapshot_id,
+            chunk,
+        )
+        .encode();
+        let metadata = context.request.metadata.clone();
+        let callback_id = state
+            .metadata
+            .subnet_call_context_manager
+            .push_context(SubnetCallContext::CanisterSnapshotClone(context));
+
+        let request = Request {
+            receiver: CanisterId::from(target_subnet_id),
+            sender: CanisterId::from(self.own_subnet_id),
+            sender_reply_callback: callback_id,
+            payment: Cycles::zero(),
+            method_name: Ic00Method::CloneCanisterSnapshotChunk.to_string(),
+            method_payload,
+            metadata,
+            deadline: NO_DEADLINE,
+        };
+        let time = state.time();
+        if let Err((err, _)) = state.push_subnet_output_request(Arc::new(request), time) {
+            state
+                .metadata
+                .subnet_call_context_manager
+                .retrieve_context(callback_id, &self.log);
+            return Err(UserError::new(
+                ErrorCode::CanisterQueueFull,
+                format!(
+                    "Failed to send the snapshot to subnet {}: {}",
+                    target_subnet_id, err
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Handles the response of the target subnet to a chunk of a snapshot
+    /// being cloned: sends the next chunk or, once the clone has completed or
+    /// failed, replies to the caller of `clone_canister_snapshot`.
+    fn handle_canister_snapshot_clone_response(
+        &self,
+        context: CanisterSnapshotCloneContext,
+        response: &Response,
+        state: &mut ReplicatedState,
+    ) {
+        let request = context.request.clone();
+        let time_elapsed = state.time().saturating_duration_since(context.time);
+        let response_payload = match self.continue_canister_snapshot_clone(context, response, state)
+        {
+            Ok(None) => return,
+            Ok(Some(data)) => Payload::Data(data),
+            Err(err) => Payload::Reject(err.into()),
+        };
+
+        self.metrics.observe_subnet_message(
+            &request.method_name,
+            time_elapsed.as_secs_f64(),
+            &match &response_payload {
+                Payload::Data(_) => Ok(()),
+                Payload::Reject(_) => Err(ErrorCode::CanisterRejectedMessage),
+            },
+        );
+        state.push_subnet_output_response(
+            Response {
+                originator: request.sender,
+                respondent: CanisterId::from(self.own_subnet_id),
+                originator_reply_callback: request.sender_reply_callback,
+                refund: request.payment,
+                response_payload,
+                deadline: request.deadline,
+            }
+            .into(),
+        );
+    }
+
+    /// Sends the next chunk of a snapshot being cloned after the previous one
+    /// was acknowledged by the target subnet.
+    ///
+    /// Returns the reply to the caller of `clone_canister_snapshot` once the
+    /// clone has completed, or `None` while it is still in progress. If the
+    /// clone fails, the target subnet is asked to discard the partially cloned
+    /// snapshot, see `discard_canister_snapshot_clone`.
+    fn continue_canister_snapshot_clone(
+        &self,
+        mut context: CanisterSnapshotCloneContext,
+        response: &Response,
+        state: &mut ReplicatedState,
+    ) -> Result<Option<Vec<u8>>, UserError> {
+        // The caller was already replied to when the clone failed.
+        if context.cursor == CanisterSnapshotCloneCursor::Discarded {
+            return Ok(None);
+        }
+        let data = match &response.response_payload {
+            Payload::Data(data) => data,
+            Payload::Reject(reject) => {
+                let err = UserError::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Failed to clone snapshot {} into canister {}: {}",
+                        context.snapshot_id,
+                        context.target_canister_id,
+                        reject.message()
+                    ),
+                );
+                // The chunk may not have reached the target subnet (e.g. a
+                // reject generated by message routing), so the partially
+                // cloned snapshot may still exist.
+                self.discard_canister_snapshot_clone(context, state);
+                return Err(err);
+            }
+        };
+        match context.cursor {
+            CanisterSnapshotCloneCursor::Done => return Ok(Some(data.clone())),
+            CanisterSnapshotCloneCursor::Aborted => {
+                return Err(UserError::new(
+                    ErrorCode::CanisterSnapshotNotFound,
+                    format!(
+                        "Snapshot {} was deleted while being cloned into canister {}.",
+                        context.snapshot_id, context.target_canister_id
+                    ),
+                ))
+            }
+            _ => {}
+        }
+
+        if context.target_snapshot_id.is_none() {
+            let target_snapshot_id = CanisterSnapshotResponse::decode(data)
+                .ok()
+                .and_then(|response| SnapshotId::try_from(&response.id).ok())
+                .ok_or_else(|| {
+                    UserError::new(
+                        ErrorCode::CanisterContractViolation,
+                        format!(
+                            "Invalid response from the subnet hosting canister {}.",
+                            context.target_canister_id
+                        ),
+                    )
+                })?;
+            context.target_snapshot_id = Some(target_snapshot_id);
+        }
+
+        let (chunk, cursor) = match state.canister_snapshots.get(context.snapshot_id) {
+            // Only clones that are done or aborted have nothing left to send.
+            Some(snapshot) => snapshot
+                .export_chunk(context.cursor, CLONE_CANISTER_SNAPSHOT_CHUNK_SIZE)
+                .unwrap(),
+            // The source snapshot was deleted in the meantime, discard the partial clone.
+            None => (
+                CanisterSnapshotChunk::Abort,
+                CanisterSnapshotCloneCursor::Aborted,
+            ),
+        };
+        context.cursor = cursor;
+        if let Err(err) = self.push_canister_snapshot_clone_chunk(context.clone(), chunk, state) {
+            self.discard_canister_snapshot_clone(context, state);
+            return Err(err);
+        }
+        Ok(None)
+    }
+
+    /// Asks the subnet hosting the target canister to delete the partially
+    /// cloned snapshot after the clone failed on this subnet. The caller of
+    /// `clone_canister_snapshot` is replied to with the failure right away, so
+    /// the response to the `Abort` chunk is ignored.
+    ///
+    /// If the target subnet has not created the snapshot yet, there is nothing
+    /// to discard. If the `Abort` chunk cannot be sent either, the snapshot
+    /// remains with the target canister until its controllers delete it.
+    fn discard_canister_snapshot_clone(
+        &self,
+        mut context: CanisterSnapshotCloneContext,
+        state: &mut ReplicatedState,
+    ) {
+        if context.target_snapshot_id.is_none() {
+            return;
+        }
+        context.request.payment = Cycles::zero();
+        context.cursor = CanisterSnapshotCloneCursor::Discarded;
+        let _ =
+            self.push_canister_snapshot_clone_chunk(context, CanisterSnapshotChunk::Abort, state);
+    }
+
+    /// Executes a chunk of a snapshot being cloned from a canister on another
+    /// subnet into one of the canisters of this subnet.
+    ///
+    /// If writing the chunk fails, the partially cloned snapshot is deleted.
+    fn clone_canister_snapshot_chunk(
+        &self,
+        sender: PrincipalId,
+        args: CloneCanisterSnapshotChunkArgs,
+        state: &mut ReplicatedState,
+        subnet_size: usize,
+        round_limits: &mut RoundLimits,
+    ) -> Result<Vec<u8>, UserError> {
+        if !state
+            .metadata
+            .network_topology
+            .subnets
+            .contains_key(&SubnetId::from(sender))
+        {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "{} can only be called by other subnets.",
+                    Ic00Method::CloneCanisterSnapshotChunk
+                ),
+            ));
+        }
+
+        let canister_id = args.get_canister_id();
+        let snapshot_id = args.get_snapshot_id();
+        let replace_snapshot = args.replace_snapshot();
+        let caller = args.caller;
+        // Take canister out.
+        let mut canister = match state.take_canister_state(&canister_id) {
+            None => {
+                return Err(UserError::new(
+                    ErrorCode::CanisterNotFound,
+                    format!("Canister {} not found.", &canister_id),
+                ))
+            }
+            Some(canister) => canister,
+        };
+
+        let result = match (args.chunk, snapshot_id) {
+            (CanisterSnapshotChunk::Metadata(metadata), _) => {
+                let resource_saturation =
+                    self.subnet_memory_saturation(&round_limits.subnet_available_memory);
+                self.canister_manager
+                    .import_canister_snapshot(
+                        subnet_size,
+                        caller,
+                        &mut canister,
+                        replace_snapshot,
+                        &metadata,
+                        state,
+                        round_limits,
+                        &resource_saturation,
+                    )
+                    .map(|response| response.encode())
+                    .map_err(UserError::from)
+            }
+            (CanisterSnapshotChunk::Abort, snapshot_id) => {
+                if let Some(snapshot_id) = snapshot_id {
+                    self.discard_canister_snapshot_import(
+                        caller,
+                        &mut canister,
+                        snapshot_id,
+                        state,
+                        round_limits,
+                    );
+                }
+                Ok(EmptyBlob.encode())
+            }
+            (_, None) => Err(UserError::new(
+                ErrorCode::InvalidManagementPayload,
+                "Missing the ID of the snapshot being cloned.".to_string(),
+            )),
+            (chunk, Some(snapshot_id)) => {
+                let result = self.canister_manager.write_cloned_canister_snapshot_chunk(
+                    caller,
+                    &mut canister,
+                    snapshot_id,
+                    replace_snapshot,
+                    chunk,
+                    state,
+                    round_limits,
+                );
+                if result.is_err() {
+                    self.discard_canister_snapshot_import(
+                        caller,
+                        &mut canister,
+                        snapshot_id,
+                        state,
+                        round_limits,
+                    );
+                }
+                result
+                    .map(|response| response.encode())
+                    .map_err(UserError::from)
+            }
+        };
+        // Put canister back.
+        state.put_canister_state(canister);
+
+        result
+    }
+
+    /// Deletes the snapshot `snapshot_id` of `canister` if it is still being
+    /// cloned from another subnet.
+    fn discard_canister_snapshot_import(
+        &self,
+        caller: PrincipalId,
+        canister: &mut CanisterState,
+        snapshot_id: SnapshotId,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) {
+        let importing = state
+            .canister_snapshots
+            .get(snapshot_id)
+            .is_some_and(|snapshot| {
+                snapshot.canister_id() == canister.canister_id() && snapshot.import_in_progress()
+            });
+        if importing {
+            // Only fails if the caller is no longer a controller, in which case
+            // the controllers can delete the snapshot themselves.
+            let _ = self.canister_manager.delete_canister_snapshot(
+                caller,
+                canister,
+                snapshot_id,
+                state,
+                round_limits,
+            );
+        }
+    }
+
     fn read_canister_snapshot_metadata(
         &self,
         sender: PrincipalId,
@@ -3672,6 +4298,47 @@ impl ExecutionEnvironment {
         }
     }
 
+    /// Records the instructions, cycles and dirty pages of a completed
+    /// execution in the method profile of the canister.
+    fn observe_method_profile(
+        &self,
+        method_name: Option<String>,
+        result: &mut ExecuteMessageResult,
+        subnet_size: usize,
+    ) {
+        let (method_name, canister, instructions_used, heap_delta) = match (method_name, result) {
+            (
+                Some(method_name),
+                ExecuteMessageResult::Finished {
+                    canister,
+                    instructions_used,
+                    heap_delta,
+                    ..
+                },
+            ) => (method_name, canister, *instructions_used, *heap_delta),
+            _ => return,
+        };
+        let wasm_execution_mode = canister
+            .execution_state
+            .as_ref()
+            .map_or(WasmExecutionMode::Wasm32, |es| es.wasm_execution_mode);
+        let cycles = self.cycles_account_manager.execution_cost(
+            instructions_used,
+            subnet_size,
+            wasm_execution_mode,
+        );
+        canister
+            .system_state
+            .canister_metrics
+            .method_profile
+            .observe(
+                &method_name,
+                instructions_used,
+                cycles,
+                heap_delta.get() / ic_sys::PAGE_SIZE as u64,
+            );
+    }
+
     /// If the given result corresponds to a finished execution, then it processes
     /// the response and return the ingress status (if any). Otherwise, it registers
     /// the paused execution and adds it to the task queue.
@@ -3690,6 +4357,7 @@ impl ExecutionEnvironment {
                 response,
                 instructions_used,
                 heap_delta,
+                stable_memory_delta: _,
                 call_duration,
             } => {
                 let ingress_status = match response {
@@ -3982,6 +4650,10 @@ pub enum CompilationCostHandling {
 /// The expected speed up of deserializing a module compared to compiling it.
 const DESERIALIZATION_SPEED_UP_FACTOR: u64 = 100;
 
+/// The maximum amount of snapshot data sent in a single
+/// `clone_canister_snapshot_chunk` request.
+const CLONE_CANISTER_SNAPSHOT_CHUNK_SIZE: usize = 1024 * 1024;
+
 impl CompilationCostHandling {
     /// Adjusts the compilation cost based on how it should be handled. Only public for use in tests.
     #[doc(hidden)]
@@ -4004,6 +4676,25 @@ pub(crate) fn subnet_memory_capacity(config: &ExecutionConfig) -> SubnetAvailabl
     )
 }
 
+/// Records the cycles consumed by a `clone_canister_snapshot` call.
+fn observe_clone_canister_snapshot_fee(
+    state: &mut ReplicatedState,
+    execution_fee: Cycles,
+    transmission_fee: Cycles,
+) {
+    let subnet_metrics = &mut state.metadata.subnet_metrics;
+    subnet_metrics.observe_consumed_cycles_with_use_case(
+        CyclesUseCase::Instructions,
+        NominalCycles::from(execution_fee),
+    );
+    if !transmission_fee.is_zero() {
+        subnet_metrics.observe_consumed_cycles_with_use_case(
+            CyclesUseCase::RequestAndResponseTransmission,
+            NominalCycles::from(transmission_fee),
+        );
+    }
+}
+
 fn get_canister(
     canister_id: CanisterId,
     state: &ReplicatedState,
@@ -4030,6 +4721,29 @@ fn get_canister_mut(
     }
 }
 
+/// Returns the name under which the execution of the given input is recorded
+/// in the method profile of the canister: the name of the exported method for
+/// calls and the name of the system method for tasks. Responses and calls of
+/// methods that the canister does not export are not recorded.
+fn profiled_method_name(canister: &CanisterState, input: &CanisterMessageOrTask) -> Option<String> {
+    let method_name = match input {
+        CanisterMessageOrTask::Message(CanisterMessage::Ingress(ingress)) => &ingress.method_name,
+        CanisterMessageOrTask::Message(CanisterMessage::Request(request)) => &request.method_name,
+        CanisterMessageOrTask::Message(CanisterMessage::Response(_)) => return None,
+        CanisterMessageOrTask::Task(task) => {
+            return Some(SystemMethod::from(task.clone()).to_string())
+        }
+    };
+    [
+        WasmMethod::Update(method_name.clone()),
+        WasmMethod::Query(method_name.clone()),
+        WasmMethod::CompositeQuery(method_name.clone()),
+    ]
+    .iter()
+    .any(|method| canister.exports_method(method))
+    .then(|| method_name.clone())
+}
+
 /// The result of `execute_canister()`.
 pub struct ExecuteCanisterResult {
     pub canister: CanisterState,
@@ -4055,7 +4769,9 @@ fn execute_canister_input(
     subnet_size: usize,
 ) -> ExecuteCanisterResult {
     let info = input.to_string();
-    let result = exec_env.execute_canister_input(
+    exec_env.message_profiler.start(&canister, &input, time);
+    let method_name = profiled_method_name(&canister, &input);
+    let mut result = exec_env.execute_canister_input(
         canister,
         instruction_limits,
         max_instructions_per_message_without_dts,
@@ -4066,6 +4782,8 @@ fn execute_canister_input(
         round_limits,
         subnet_size,
     );
+    exec_env.message_profiler.observe(&result);
+    exec_env.observe_method_profile(method_name, &mut result, subnet_size);
     let (canister, instructions_used, heap_delta, ingress_status) = exec_env.process_result(result);
     ExecuteCanisterResult {
         canister,
@@ -4123,7 +4841,8 @@ pub fn execute_canister(
                     log: &exec_env.log,
                     time,
                 };
-                let result = paused.resume(
+                let method_name = profiled_method_name(&canister, &paused.input());
+                let mut result = paused.resume(
                     canister,
                     round_context,
                     round_limits,
@@ -4131,6 +4850,8 @@ pub fn execute_canister(
                     &exec_env.call_tree_metrics,
                     exec_env.deallocator_thread.sender(),
                 );
+                exec_env.message_profiler.observe(&result);
+                exec_env.observe_method_profile(method_name, &mut result, subnet_size);
                 let (canister, instructions_used, heap_delta, ingress_status) =
                     exec_env.process_result(result);
                 return ExecuteCanisterResult {
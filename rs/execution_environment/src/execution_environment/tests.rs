@@ -4,13 +4,13 @@ use ic_config::embedders::BestEffortResponsesFeature;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_management_canister_types_private::{
     self as ic00, BitcoinGetUtxosArgs, BitcoinNetwork, BoundedHttpHeaders, CanisterChange,
-    CanisterHttpRequestArgs, CanisterIdRecord, CanisterSettingsArgsBuilder, CanisterStatusResultV2,
-    CanisterStatusType, ClearChunkStoreArgs, DerivationPath, EcdsaKeyId, EmptyBlob,
-    FetchCanisterLogsRequest, HttpMethod, LogVisibilityV2, MasterPublicKeyId, Method,
-    OnLowWasmMemoryHookStatus, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    ProvisionalTopUpCanisterArgs, SchnorrAlgorithm, SchnorrKeyId, TakeCanisterSnapshotArgs,
-    TransformContext, TransformFunc, UpdateSettingsArgs, UploadChunkArgs, VetKdCurve, VetKdKeyId,
-    IC_00,
+    CanisterHttpRequestArgs, CanisterIdRecord, CanisterMethodProfileResponse,
+    CanisterSettingsArgsBuilder, CanisterStatusResultV2, CanisterStatusType, ClearChunkStoreArgs,
+    DerivationPath, EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, HttpMethod, LogVisibilityV2,
+    MasterPublicKeyId, Method, OnLowWasmMemoryHookStatus, Payload as Ic00Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SchnorrAlgorithm,
+    SchnorrKeyId, TakeCanisterSnapshotArgs, TransformContext, TransformFunc, UpdateSettingsArgs,
+    UploadChunkArgs, VetKdCurve, VetKdKeyId, IC_00,
 };
 use ic_registry_routing_table::{canister_id_into_u64, CanisterIdRange, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
@@ -794,6 +794,46 @@ fn get_running_canister_status_from_another_canister() {
     );
 }
 
+#[test]
+fn canister_method_profile_records_executed_methods() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    for _ in 0..2 {
+        let result = test.ingress(canister_id, "update", wasm().reply().build());
+        get_reply(result);
+    }
+    // Calls of methods that the canister does not export are not recorded.
+    test.ingress(canister_id, "unknown", vec![]).unwrap_err();
+
+    let payload = CanisterIdRecord::from(canister_id).encode();
+    let result = test.subnet_message(Method::CanisterMethodProfile, payload.clone());
+    let response = CanisterMethodProfileResponse::decode(&get_reply(result)).unwrap();
+    assert_eq!(response.methods.len(), 1);
+    let record = &response.methods[0];
+    assert_eq!(record.method_name, "update");
+    assert_eq!(record.num_calls, 2);
+    assert_gt!(record.instructions, 0);
+    assert_eq!(record.instruction_histogram.iter().sum::<u64>(), 2);
+    assert_eq!(
+        record.instructions,
+        test.canister_state(canister_id)
+            .system_state
+            .canister_metrics
+            .method_profile
+            .get("update")
+            .unwrap()
+            .instructions
+            .get()
+    );
+
+    // Only controllers can read the method profile.
+    test.set_user_id(user_test_id(13));
+    let err = test
+        .subnet_message(Method::CanisterMethodProfile, payload)
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+}
+
 #[test]
 fn get_canister_status_from_another_canister_when_memory_low() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -1058,6 +1098,7 @@ fn get_canister_status_memory_metrics_snapshots_size() {
         TakeCanisterSnapshotArgs {
             canister_id: canister_id.into(),
             replace_snapshot: None,
+            base_snapshot: None,
         }
         .encode(),
     )
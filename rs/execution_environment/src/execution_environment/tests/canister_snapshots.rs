@@ -6,14 +6,17 @@ use ic_cycles_account_manager::ResourceSaturation;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_management_canister_types_private::{
     self as ic00, CanisterChange, CanisterChangeDetails, CanisterSettingsArgsBuilder,
-    CanisterSnapshotResponse, ClearChunkStoreArgs, DeleteCanisterSnapshotArgs, GlobalTimer,
-    ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, Method, OnLowWasmMemoryHookStatus,
-    Payload as Ic00Payload, ReadCanisterSnapshotMetadataArgs, ReadCanisterSnapshotMetadataResponse,
-    SnapshotSource, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
+    CanisterSnapshotChunk, CanisterSnapshotDiffArgs, CanisterSnapshotDiffResponse,
+    CanisterSnapshotResponse, ClearChunkStoreArgs, CloneCanisterSnapshotArgs,
+    DeleteCanisterSnapshotArgs, GlobalTimer, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
+    Method, OnLowWasmMemoryHookStatus, PageRange, Payload as Ic00Payload,
+    ReadCanisterSnapshotMetadataArgs, ReadCanisterSnapshotMetadataResponse, SnapshotSource,
+    TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
 };
+use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_snapshots::SnapshotOperation,
+    canister_snapshots::{CanisterSnapshotCloneCursor, SnapshotOperation},
     canister_state::{
         execution_state::{WasmBinary, WasmExecutionMode},
         system_state::CyclesUseCase,
@@ -28,15 +31,16 @@ use ic_test_utilities_execution_environment::{
 use ic_test_utilities_types::ids::{canister_test_id, subnet_test_id};
 use ic_types::{
     ingress::WasmResult,
-    messages::{Payload, RejectContext, RequestOrResponse},
+    messages::{Payload, RejectContext, RequestOrResponse, Response},
     time::UNIX_EPOCH,
     CanisterId, Cycles, NumInstructions, SnapshotId,
 };
 use ic_types_test_utils::ids::user_test_id;
 use ic_universal_canister::{wasm, UNIVERSAL_CANISTER_WASM};
+use maplit::btreemap;
 use more_asserts::assert_gt;
 use serde_bytes::ByteBuf;
-use std::borrow::Borrow;
+use std::{borrow::Borrow, sync::Arc};
 
 const WASM_EXECUTION_MODE: WasmExecutionMode = WasmExecutionMode::Wasm32;
 
@@ -69,6 +73,7 @@ fn take_canister_snapshot_decode_fails() {
     let args = ic00::TakeCanisterSnapshotArgs {
         canister_id: canister_id.get(),
         replace_snapshot: Some(ByteBuf::from(vec![4, 5, 6, 6])), // Invalid snapshot ID.
+        base_snapshot: None,
     };
     let encoded_args = args.encode();
     let err = TakeCanisterSnapshotArgs::decode(encoded_args.as_slice()).unwrap_err();
@@ -2205,6 +2210,551 @@ fn read_canister_snapshot_metadata_fails_invalid_controller() {
     assert_eq!(error.code(), ErrorCode::CanisterInvalidController);
 }
 
+fn write_stable_memory(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    offset: u64,
+    data: &[u8],
+) {
+    let result = test
+        .ingress(
+            canister_id,
+            "update",
+            wasm().stable64_write(offset, data).reply().build(),
+        )
+        .unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+}
+
+fn helper_take_incremental_snapshot(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    base_snapshot: SnapshotId,
+) -> SnapshotId {
+    let args = TakeCanisterSnapshotArgs::new(canister_id, None).with_base_snapshot(base_snapshot);
+    let result = test
+        .subnet_message("take_canister_snapshot", args.encode())
+        .unwrap();
+    CanisterSnapshotResponse::decode(&result.bytes())
+        .unwrap()
+        .snapshot_id()
+}
+
+#[test]
+fn take_canister_snapshot_decode_round_trip_with_base_snapshot() {
+    let base_snapshot = SnapshotId::from((canister_test_id(4), 6));
+    let args =
+        TakeCanisterSnapshotArgs::new(canister_test_id(4), None).with_base_snapshot(base_snapshot);
+    let decoded_args = TakeCanisterSnapshotArgs::decode(args.encode().as_slice()).unwrap();
+    assert_eq!(decoded_args.base_snapshot(), Some(base_snapshot));
+}
+
+#[test]
+fn incremental_snapshot_is_charged_for_changed_data_only() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    grow_stable_memory(&mut test, canister_id, WASM_PAGE_SIZE_IN_BYTES as u64, 10);
+
+    let (base_snapshot_id, _) = helper_take_snapshot(&mut test, canister_id);
+    let base_snapshot_size = test
+        .state()
+        .canister_snapshots
+        .get(base_snapshot_id)
+        .unwrap()
+        .size();
+
+    write_stable_memory(&mut test, canister_id, 0, &[1, 2, 3]);
+    let snapshot_id = helper_take_incremental_snapshot(&mut test, canister_id, base_snapshot_id);
+    let snapshot = test.state().canister_snapshots.get(snapshot_id).unwrap();
+    assert_eq!(snapshot.base_snapshot(), Some(base_snapshot_id));
+    // The Wasm module and the unchanged stable memory pages are shared.
+    assert_gt!(
+        base_snapshot_size.get(),
+        snapshot.size().get() + 9 * WASM_PAGE_SIZE_IN_BYTES as u64
+    );
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .snapshots_memory_usage,
+        test.state()
+            .canister_snapshots
+            .compute_memory_usage_by_canister(canister_id),
+    );
+}
+
+#[test]
+fn base_snapshot_cannot_be_deleted_or_replaced_while_in_use() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+
+    let (base_snapshot_id, _) = helper_take_snapshot(&mut test, canister_id);
+    let snapshot_id = helper_take_incremental_snapshot(&mut test, canister_id, base_snapshot_id);
+
+    let expected_error = format!(
+        "The snapshot {} of canister {} is the base of an incremental snapshot and cannot be deleted or replaced.",
+        base_snapshot_id, canister_id
+    );
+    let args = DeleteCanisterSnapshotArgs::new(canister_id, base_snapshot_id);
+    let error = test
+        .subnet_message("delete_canister_snapshot", args.encode())
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::CanisterRejectedMessage);
+    assert!(error.description().starts_with(&expected_error));
+
+    let args = TakeCanisterSnapshotArgs::new(canister_id, Some(base_snapshot_id));
+    let error = test
+        .subnet_message("take_canister_snapshot", args.encode())
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::CanisterRejectedMessage);
+    assert!(error.description().starts_with(&expected_error));
+
+    // Once the incremental snapshot is gone, the base snapshot can be deleted.
+    helper_delete_snapshot(&mut test, canister_id, snapshot_id);
+    helper_delete_snapshot(&mut test, canister_id, base_snapshot_id);
+    assert_eq!(test.state().canister_snapshots.count(), 0);
+}
+
+#[test]
+fn canister_snapshot_diff_reports_changed_pages() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    grow_stable_memory(&mut test, canister_id, WASM_PAGE_SIZE_IN_BYTES as u64, 10);
+    let (snapshot_id, _) = helper_take_snapshot(&mut test, canister_id);
+
+    // Write to the last byte of page 5 and the whole of page 6.
+    let page_size = 4096;
+    write_stable_memory(&mut test, canister_id, 6 * page_size - 1, &[1; 4097]);
+
+    let args = CanisterSnapshotDiffArgs::new(canister_id, snapshot_id);
+    let result = test
+        .subnet_message("canister_snapshot_diff", args.encode())
+        .unwrap();
+    let response = CanisterSnapshotDiffResponse::decode(&result.bytes()).unwrap();
+    assert_eq!(response.page_size, page_size);
+    assert_eq!(
+        response.stable_memory,
+        vec![PageRange { start: 5, count: 2 }]
+    );
+    assert!(!response.wasm_module_changed);
+    assert!(!response.wasm_chunk_store_changed);
+    assert!(!response.truncated);
+}
+
+#[test]
+fn canister_snapshot_diff_fails_invalid_controller() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_cycles_and_binary(
+            Cycles::new(1_000_000_000_000),
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+        )
+        .unwrap();
+    let (snapshot_id, _) = helper_take_snapshot(&mut test, canister_id);
+
+    // Non-controller user tries to compare the snapshot.
+    test.set_user_id(user_test_id(42));
+    let args = CanisterSnapshotDiffArgs::new(canister_id, snapshot_id);
+    let error = test
+        .subnet_message("canister_snapshot_diff", args.encode())
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::CanisterInvalidController);
+}
+
+fn helper_clone_canister_snapshot_response(
+    test: &mut ExecutionTest,
+    args: CloneCanisterSnapshotArgs,
+    payment: Cycles,
+) -> Response {
+    test.inject_call_to_ic00(Method::CloneCanisterSnapshot, args.encode(), payment);
+    test.execute_subnet_message();
+    match get_output_messages(test.state_mut()).pop().unwrap() {
+        (_, RequestOrResponse::Response(response)) => response.as_ref().clone(),
+        (_, RequestOrResponse::Request(request)) => panic!("Unexpected request: {:?}", request),
+    }
+}
+
+/// Returns all data chunks of the snapshot, i.e. all but the metadata.
+fn export_snapshot_data(
+    test: &ExecutionTest,
+    snapshot_id: SnapshotId,
+) -> Vec<CanisterSnapshotChunk> {
+    let snapshot = test.state().canister_snapshots.get(snapshot_id).unwrap();
+    let mut cursor = CanisterSnapshotCloneCursor::WasmModule { offset: 0 };
+    let mut chunks = vec![];
+    while let Some((chunk, next)) = snapshot.export_chunk(cursor, 1 << 20) {
+        chunks.push(chunk);
+        cursor = next;
+    }
+    chunks
+}
+
+#[test]
+fn clone_canister_snapshot_into_canister_on_same_subnet() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    let controllers = vec![caller_canister.get(), test.user_id().get()];
+    let source_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    let target_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    for canister_id in [source_id, target_id] {
+        test.canister_update_controller(canister_id, controllers.clone())
+            .unwrap();
+    }
+    let (snapshot_id, _) = helper_take_snapshot(&mut test, source_id);
+    test.state_mut().canister_snapshots.take_unflushed_changes();
+
+    let args = CloneCanisterSnapshotArgs::new(source_id, snapshot_id, target_id, None);
+    let response = helper_clone_canister_snapshot_response(&mut test, args, CYCLES);
+    // The caller is charged for copying the snapshot.
+    assert_gt!(CYCLES, response.refund);
+    let Payload::Data(data) = &response.response_payload else {
+        panic!("Unexpected reject: {:?}", response.response_payload);
+    };
+    let cloned_snapshot_id = CanisterSnapshotResponse::decode(data)
+        .unwrap()
+        .snapshot_id();
+    assert_eq!(cloned_snapshot_id.get_canister_id(), target_id);
+
+    let cloned_snapshot = test
+        .state()
+        .canister_snapshots
+        .get(cloned_snapshot_id)
+        .unwrap();
+    assert!(!cloned_snapshot.import_in_progress());
+    assert_eq!(
+        export_snapshot_data(&test, cloned_snapshot_id),
+        export_snapshot_data(&test, snapshot_id)
+    );
+    assert_eq!(
+        test.canister_state(target_id)
+            .system_state
+            .snapshots_memory_usage,
+        test.state()
+            .canister_snapshots
+            .compute_memory_usage_by_canister(target_id),
+    );
+    assert_eq!(
+        test.state_mut().canister_snapshots.take_unflushed_changes(),
+        vec![SnapshotOperation::Duplicate(
+            snapshot_id,
+            cloned_snapshot_id
+        )]
+    );
+}
+
+#[test]
+fn clone_canister_snapshot_fails_invalid_target_controller() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    let source_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    let target_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    // The caller only controls the source canister.
+    test.canister_update_controller(source_id, vec![caller_canister.get(), test.user_id().get()])
+        .unwrap();
+    let (snapshot_id, _) = helper_take_snapshot(&mut test, source_id);
+
+    let args = CloneCanisterSnapshotArgs::new(source_id, snapshot_id, target_id, None);
+    let response = helper_clone_canister_snapshot_response(&mut test, args, CYCLES);
+    assert_eq!(response.refund, CYCLES);
+    assert_matches!(response.response_payload, Payload::Reject(_));
+    assert_eq!(
+        test.state()
+            .canister_snapshots
+            .count_by_canister(&target_id),
+        0
+    );
+}
+
+/// Sets up a canister with a snapshot spanning several chunks on one subnet
+/// and a target canister on another subnet, both controlled by the caller.
+///
+/// Returns the tests of both subnets and the IDs of the source canister, its
+/// snapshot and the target canister.
+fn setup_snapshot_clone_across_subnets() -> (
+    ExecutionTest,
+    ExecutionTest,
+    CanisterId,
+    SnapshotId,
+    CanisterId,
+) {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let source_subnet = subnet_test_id(1);
+    let target_subnet = subnet_test_id(2);
+    let caller_canister = canister_test_id(1);
+    let mut source_test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(source_subnet)
+        .with_caller(target_subnet, caller_canister)
+        .build();
+    let mut target_test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(target_subnet)
+        .with_caller(source_subnet, caller_canister)
+        .build();
+
+    // Set up a snapshot spanning several chunks, including a Wasm chunk.
+    let source_id = source_test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    let controllers = vec![caller_canister.get(), source_test.user_id().get()];
+    source_test
+        .canister_update_controller(source_id, controllers)
+        .unwrap();
+    grow_stable_memory(
+        &mut source_test,
+        source_id,
+        WASM_PAGE_SIZE_IN_BYTES as u64,
+        20,
+    );
+    write_stable_memory(&mut source_test, source_id, 1 << 20, &[1, 2, 3]);
+    let upload_args = UploadChunkArgs {
+        canister_id: source_id.into(),
+        chunk: vec![4, 5, 6],
+    };
+    source_test
+        .subnet_message("upload_chunk", upload_args.encode())
+        .unwrap();
+    let (snapshot_id, _) = helper_take_snapshot(&mut source_test, source_id);
+
+    // Canister IDs are allocated from the same range on both subnets.
+    target_test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    let target_id = target_test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    let controllers = vec![caller_canister.get(), target_test.user_id().get()];
+    target_test
+        .canister_update_controller(target_id, controllers)
+        .unwrap();
+    source_test
+        .state_mut()
+        .metadata
+        .network_topology
+        .routing_table = Arc::new(
+        RoutingTable::try_from(btreemap! {
+            CanisterIdRange { start: caller_canister, end: caller_canister } => target_subnet,
+            CanisterIdRange { start: source_id, end: source_id } => source_subnet,
+            CanisterIdRange { start: target_id, end: target_id } => target_subnet,
+        })
+        .unwrap(),
+    );
+
+    (source_test, target_test, source_id, snapshot_id, target_id)
+}
+
+/// Executes the chunk `request` on the target subnet and returns its response.
+fn relay_snapshot_clone_chunk(
+    target_test: &mut ExecutionTest,
+    request: RequestOrResponse,
+) -> RequestOrResponse {
+    target_test
+        .state_mut()
+        .push_input(request, &mut (i64::MAX / 2))
+        .unwrap();
+    target_test.execute_subnet_message();
+    let mut messages = get_output_messages(target_test.state_mut());
+    assert_eq!(messages.len(), 1);
+    messages.pop().unwrap().1
+}
+
+#[test]
+fn clone_canister_snapshot_into_canister_on_other_subnet() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let (mut source_test, mut target_test, source_id, snapshot_id, target_id) =
+        setup_snapshot_clone_across_subnets();
+    let target_subnet = target_test.get_own_subnet_id();
+
+    // Relay the chunks between the two subnets until the caller gets a reply.
+    source_test.inject_call_to_ic00(
+        Method::CloneCanisterSnapshot,
+        CloneCanisterSnapshotArgs::new(source_id, snapshot_id, target_id, None).encode(),
+        CYCLES,
+    );
+    source_test.execute_subnet_message();
+    let mut num_chunks = 0;
+    let response = loop {
+        let mut messages = get_output_messages(source_test.state_mut());
+        assert_eq!(messages.len(), 1);
+        let request = match messages.pop().unwrap() {
+            (_, RequestOrResponse::Response(response)) => break response,
+            (receiver, request) => {
+                assert_eq!(receiver, CanisterId::from(target_subnet));
+                request
+            }
+        };
+        num_chunks += 1;
+        let ack = relay_snapshot_clone_chunk(&mut target_test, request);
+        source_test
+            .state_mut()
+            .push_input(ack, &mut (i64::MAX / 2))
+            .unwrap();
+        source_test.execute_subnet_message();
+    };
+
+    let snapshot = source_test
+        .state()
+        .canister_snapshots
+        .get(snapshot_id)
+        .unwrap();
+    assert_eq!(num_chunks, snapshot.export_chunk_count(1 << 20));
+    // The caller is charged for copying and transmitting the snapshot.
+    assert_gt!(CYCLES, response.refund);
+    let Payload::Data(data) = &response.response_payload else {
+        panic!("Unexpected reject: {:?}", response.response_payload);
+    };
+    let cloned_snapshot_id = CanisterSnapshotResponse::decode(data)
+        .unwrap()
+        .snapshot_id();
+    assert_eq!(cloned_snapshot_id.get_canister_id(), target_id);
+    assert!(source_test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_snapshot_clone_contexts
+        .is_empty());
+
+    let cloned_snapshot = target_test
+        .state()
+        .canister_snapshots
+        .get(cloned_snapshot_id)
+        .unwrap();
+    assert!(!cloned_snapshot.import_in_progress());
+    assert_eq!(cloned_snapshot.size(), snapshot.size());
+    assert_eq!(
+        export_snapshot_data(&target_test, cloned_snapshot_id),
+        export_snapshot_data(&source_test, snapshot_id)
+    );
+    assert_eq!(
+        target_test
+            .state_mut()
+            .canister_snapshots
+            .take_unflushed_changes(),
+        vec![SnapshotOperation::Import(cloned_snapshot_id)]
+    );
+}
+
+#[test]
+fn failed_clone_into_canister_on_other_subnet_keeps_replaced_snapshot() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let (mut source_test, mut target_test, source_id, snapshot_id, target_id) =
+        setup_snapshot_clone_across_subnets();
+    let (replace_snapshot_id, _) = helper_take_snapshot(&mut target_test, target_id);
+
+    source_test.inject_call_to_ic00(
+        Method::CloneCanisterSnapshot,
+        CloneCanisterSnapshotArgs::new(
+            source_id,
+            snapshot_id,
+            target_id,
+            Some(replace_snapshot_id),
+        )
+        .encode(),
+        CYCLES,
+    );
+    source_test.execute_subnet_message();
+
+    // The metadata chunk creates the snapshot on the target subnet, but the
+    // replaced snapshot is kept until the clone is complete.
+    let (_, metadata) = get_output_messages(source_test.state_mut()).pop().unwrap();
+    let ack = relay_snapshot_clone_chunk(&mut target_test, metadata);
+    assert_eq!(
+        target_test
+            .state()
+            .canister_snapshots
+            .count_by_canister(&target_id),
+        2
+    );
+    source_test
+        .state_mut()
+        .push_input(ack, &mut (i64::MAX / 2))
+        .unwrap();
+    source_test.execute_subnet_message();
+
+    // The next chunk is rejected without reaching the target subnet.
+    let (_, chunk) = get_output_messages(source_test.state_mut()).pop().unwrap();
+    let RequestOrResponse::Request(chunk) = chunk else {
+        panic!("Expected a request, got {:?}", chunk);
+    };
+    let reject = Response {
+        originator: chunk.sender,
+        respondent: chunk.receiver,
+        originator_reply_callback: chunk.sender_reply_callback,
+        refund: Cycles::zero(),
+        response_payload: Payload::Reject(RejectContext::new(
+            RejectCode::SysTransient,
+            "Stream rejected",
+        )),
+        deadline: chunk.deadline,
+    };
+    source_test
+        .state_mut()
+        .push_input(reject.into(), &mut (i64::MAX / 2))
+        .unwrap();
+    source_test.execute_subnet_message();
+
+    // The caller is replied to right away and the target subnet is asked to
+    // discard the partially cloned snapshot.
+    let mut abort = None;
+    for (receiver, message) in get_output_messages(source_test.state_mut()) {
+        match message {
+            RequestOrResponse::Response(response) => {
+                assert_matches!(response.response_payload, Payload::Reject(_));
+            }
+            request => {
+                assert_eq!(receiver, CanisterId::from(target_test.get_own_subnet_id()));
+                abort = Some(request);
+            }
+        }
+    }
+    let ack = relay_snapshot_clone_chunk(&mut target_test, abort.unwrap());
+    source_test
+        .state_mut()
+        .push_input(ack, &mut (i64::MAX / 2))
+        .unwrap();
+    source_test.execute_subnet_message();
+    assert!(get_output_messages(source_test.state_mut()).is_empty());
+    assert!(source_test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_snapshot_clone_contexts
+        .is_empty());
+
+    let snapshot_ids = target_test
+        .state()
+        .canister_snapshots
+        .list_snapshots(target_id)
+        .into_iter()
+        .map(|(snapshot_id, _)| snapshot_id)
+        .collect::<Vec<_>>();
+    assert_eq!(snapshot_ids, vec![replace_snapshot_id]);
+}
+
 /// Early warning system / stumbling block forcing the authors of changes adding
 /// or removing canister state fields to think about and/or ask the Execution
 /// team to think about any repercussions to the canister snapshot logic.
@@ -16,6 +16,7 @@ use ic_state_machine_tests::StateMachine;
 use ic_state_machine_tests::{StateMachineBuilder, StateMachineConfig, WasmResult};
 use ic_test_utilities_execution_environment::{wat_compilation_cost, ExecutionTestBuilder};
 use ic_test_utilities_metrics::fetch_int_counter_vec;
+use ic_types::canister_timers::NAMED_TIMER_OVERHEAD_BYTES;
 use ic_types::messages::CanisterTask;
 use ic_types::Cycles;
 use ic_types::{CanisterId, NumBytes};
@@ -58,6 +59,63 @@ fn global_timer_is_executed() {
     );
 }
 
+#[test]
+fn named_timers_are_executed_in_deadline_order() {
+    let mut test = ExecutionTestBuilder::new().with_named_timers().build();
+    // The timer method grows the Wasm memory by as many pages as the name of
+    // the expired timer is long.
+    let wat = r#"(module
+            (import "ic0" "timer_set"
+                (func $timer_set (param i32 i32 i64 i64) (result i64))
+            )
+            (import "ic0" "timer_name_size"
+                (func $timer_name_size (result i32))
+            )
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update set")
+                (drop (call $timer_set (i32.const 0) (i32.const 2) (i64.const 1) (i64.const 0)))
+                (drop (call $timer_set (i32.const 2) (i32.const 3) (i64.const 1) (i64.const 1000000000000)))
+                (call $msg_reply)
+            )
+            (func (export "canister_global_timer")
+                (drop (memory.grow (call $timer_name_size)))
+            )
+            (memory 1 20)
+            (data (i32.const 0) "zzabc")
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.ingress(canister_id, "set", vec![]).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).named_timers_memory_usage(),
+        NumBytes::new(2 + 3 + 2 * NAMED_TIMER_OVERHEAD_BYTES)
+    );
+    test.advance_time(Duration::from_secs(1));
+
+    // The periodic timer "abc" comes first by name.
+    test.canister_task(canister_id, CanisterTask::GlobalTimer);
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(4)
+    );
+
+    // The one-off timer "zz" is executed and removed.
+    test.canister_task(canister_id, CanisterTask::GlobalTimer);
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(6)
+    );
+    let named_timers = &test.canister_state(canister_id).system_state.named_timers;
+    assert_eq!(named_timers.len(), 1);
+    assert!(!named_timers.has_reached_deadline(test.time()));
+
+    // No named timer is due anymore.
+    test.canister_task(canister_id, CanisterTask::GlobalTimer);
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(6)
+    );
+}
+
 #[test]
 fn ic0_global_timer_set_is_supported_in_pre_upgrade() {
     let env = StateMachine::new();
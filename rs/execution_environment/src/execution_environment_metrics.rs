@@ -196,6 +196,7 @@ impl ExecutionEnvironmentMetrics {
                     | ic00::Method::NodeMetricsHistory
                     | ic00::Method::SubnetInfo
                     | ic00::Method::FetchCanisterLogs
+                    | ic00::Method::CanisterMethodProfile
                     | ic00::Method::ProvisionalCreateCanisterWithCycles
                     | ic00::Method::ProvisionalTopUpCanister
                     | ic00::Method::UploadChunk
@@ -205,6 +206,8 @@ impl ExecutionEnvironmentMetrics {
                     | ic00::Method::LoadCanisterSnapshot
                     | ic00::Method::ListCanisterSnapshots
                     | ic00::Method::DeleteCanisterSnapshot
+                    | ic00::Method::CanisterSnapshotDiff
+                    | ic00::Method::CloneCanisterSnapshotChunk
                     | ic00::Method::ReadCanisterSnapshotMetadata
                     | ic00::Method::ReadCanisterSnapshotData
                     | ic00::Method::UploadCanisterSnapshotMetadata
@@ -225,7 +228,8 @@ impl ExecutionEnvironmentMetrics {
                     | ic00::Method::ComputeInitialIDkgDealings
                     | ic00::Method::ReshareChainKey
                     | ic00::Method::BitcoinSendTransactionInternal
-                    | ic00::Method::BitcoinGetSuccessors => String::from("slow"),
+                    | ic00::Method::BitcoinGetSuccessors
+                    | ic00::Method::CloneCanisterSnapshot => String::from("slow"),
                 };
                 (format!("ic00_{}", method_name), speed_label)
             }
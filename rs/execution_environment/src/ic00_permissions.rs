@@ -25,6 +25,7 @@ impl Ic00MethodPermissions {
     pub fn new(method: Ic00Method) -> Self {
         match method {
             Ic00Method::CanisterStatus
+            | Ic00Method::CanisterMethodProfile
             | Ic00Method::CanisterInfo
             | Ic00Method::DepositCycles
             | Ic00Method::ECDSAPublicKey
@@ -109,7 +110,11 @@ impl Ic00MethodPermissions {
                 does_not_run_on_aborted_canister: false,
                 installs_code: false,
             },
-            Ic00Method::UploadChunk | Ic00Method::TakeCanisterSnapshot => Self {
+            Ic00Method::UploadChunk
+            | Ic00Method::TakeCanisterSnapshot
+            | Ic00Method::CanisterSnapshotDiff
+            | Ic00Method::CloneCanisterSnapshot
+            | Ic00Method::CloneCanisterSnapshotChunk => Self {
                 method,
                 allow_remote_subnet_sender: true,
                 allow_only_nns_subnet_sender: false,
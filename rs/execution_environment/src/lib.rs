@@ -8,6 +8,7 @@ mod history;
 mod hypervisor;
 mod ic00_permissions;
 mod ingress_filter;
+mod message_profiler;
 mod metrics;
 mod query_handler;
 mod scheduler;
@@ -38,6 +39,9 @@ use ic_types::{
     messages::{CallContextId, MessageId},
     Height, SubnetId,
 };
+pub use message_profiler::{
+    MessageProfile, MessageProfiler, MAX_MESSAGE_PROFILES, UNKNOWN_METHOD_NAME,
+};
 pub use metrics::IngressFilterMetrics;
 pub use query_handler::InternalHttpQueryHandler;
 use query_handler::{HttpQueryHandler, QueryScheduler, QuerySchedulerFlag};
@@ -86,6 +90,7 @@ pub struct ExecutionServices {
     pub https_outcalls_service: QueryExecutionService,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
     pub query_stats_payload_builder: QueryStatsPayloadBuilderParams,
+    pub message_profiler: Arc<MessageProfiler>,
 }
 
 impl ExecutionServices {
@@ -190,6 +195,7 @@ impl ExecutionServices {
             ingress_filter_metrics.clone(),
         );
 
+        let message_profiler = exec_env.message_profiler();
         let scheduler = Box::new(SchedulerImpl::new(
             scheduler_config,
             own_subnet_id,
@@ -212,6 +218,7 @@ impl ExecutionServices {
             https_outcalls_service,
             scheduler,
             query_stats_payload_builder,
+            message_profiler,
         }
     }
 
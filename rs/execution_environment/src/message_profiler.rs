@@ -0,0 +1,490 @@
+//! Opt-in profiling of the cost of executing canister messages.
+//!
+//! If profiling is enabled, then a [`MessageProfile`] is recorded for every
+//! canister message or task whose execution completes in replicated mode.
+//! Profiling is meant for testing environments (such as PocketIC) and is
+//! disabled by default. Only the most recent [`MAX_MESSAGE_PROFILES`] profiles
+//! are retained.
+
+use crate::ExecuteMessageResult;
+use ic_replicated_state::{canister_state::system_state::CyclesUseCase, CallOrigin, CanisterState};
+use ic_types::{
+    messages::{CallContextId, CanisterMessage, CanisterMessageOrTask, MessageId},
+    methods::SystemMethod,
+    nominal_cycles::NominalCycles,
+    CanisterId, Cycles, NumBytes, NumInstructions, Time,
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+/// The method name recorded for responses to calls made in call contexts
+/// that were created before profiling was enabled.
+pub const UNKNOWN_METHOD_NAME: &str = "<unknown>";
+
+/// The maximum number of message profiles retained by the profiler. Once the
+/// limit is reached, the oldest profile is discarded for every new one.
+pub const MAX_MESSAGE_PROFILES: usize = 100_000;
+
+/// The cost of executing a single canister message or task.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MessageProfile {
+    /// The canister that executed the message.
+    pub canister_id: CanisterId,
+    /// The method executed by the message. For a response, this is the
+    /// method whose call context the response belongs to and for a task,
+    /// this is the corresponding system method (e.g., `canister_global_timer`).
+    pub method_name: String,
+    /// The ingress message whose call context the message belongs to (if any).
+    pub ingress_message_id: Option<MessageId>,
+    /// The time at which the execution of the message started.
+    pub time: Time,
+    /// The number of instructions executed (summed over all slices).
+    pub instructions: NumInstructions,
+    /// The net amount of cycles charged for the message per use case.
+    /// Use cases with no charge are omitted.
+    pub cycles_by_use_case: BTreeMap<CyclesUseCase, Cycles>,
+    /// The number of (OS) pages of the Wasm heap dirtied by the message.
+    pub heap_dirty_pages: u64,
+    /// The number of (OS) pages of the stable memory dirtied by the message.
+    pub stable_dirty_pages: u64,
+    /// The number of downstream calls made by the message.
+    pub downstream_calls: u64,
+}
+
+/// A message whose execution has started, but has not completed yet.
+struct StartedMessage {
+    method_name: String,
+    ingress_message_id: Option<MessageId>,
+    time: Time,
+    consumed_cycles_by_use_cases: BTreeMap<CyclesUseCase, NominalCycles>,
+    next_callback_id: u64,
+}
+
+#[derive(Default)]
+struct MessageProfilerState {
+    profiles: VecDeque<MessageProfile>,
+    // A canister has at most one message whose execution has been paused (DTS).
+    started: BTreeMap<CanisterId, StartedMessage>,
+    // The methods of call contexts with outstanding calls
+    // so that responses can be attributed to the method
+    // that made the corresponding call.
+    call_contexts: BTreeMap<(CanisterId, CallContextId), String>,
+}
+
+/// Records a [`MessageProfile`] for every completed canister message
+/// if profiling is enabled.
+#[derive(Default)]
+pub struct MessageProfiler {
+    enabled: AtomicBool,
+    state: Mutex<MessageProfilerState>,
+}
+
+impl MessageProfiler {
+    /// Enables or disables profiling. Disabling profiling does not clear
+    /// the message profiles recorded so far.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Returns the (at most [`MAX_MESSAGE_PROFILES`] most recent) message
+    /// profiles recorded so far in the order in which the messages completed.
+    pub fn profiles(&self) -> Vec<MessageProfile> {
+        Vec::from(self.state.lock().unwrap().profiles.clone())
+    }
+
+    /// Records the state of the canister before executing the given input.
+    /// If the execution of an input of the canister has already started
+    /// (and was aborted), then its start is preserved.
+    pub(crate) fn start(
+        &self,
+        canister: &CanisterState,
+        input: &CanisterMessageOrTask,
+        time: Time,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let canister_id = canister.canister_id();
+        let mut state = self.state.lock().unwrap();
+        if state.started.contains_key(&canister_id) {
+            return;
+        }
+        let call_context_manager = canister.system_state.call_context_manager();
+        let (method_name, ingress_message_id) = match input {
+            CanisterMessageOrTask::Message(CanisterMessage::Ingress(ingress)) => (
+                ingress.method_name.clone(),
+                Some(ingress.message_id.clone()),
+            ),
+            CanisterMessageOrTask::Message(CanisterMessage::Request(request)) => {
+                (request.method_name.clone(), None)
+            }
+            CanisterMessageOrTask::Message(CanisterMessage::Response(response)) => {
+                let call_context_id = call_context_manager
+                    .and_then(|ccm| ccm.callback(response.originator_reply_callback))
+                    .map(|callback| callback.call_context_id);
+                let method_name = call_context_id
+                    .and_then(|id| state.call_contexts.get(&(canister_id, id)))
+                    .cloned()
+                    .unwrap_or_else(|| UNKNOWN_METHOD_NAME.to_string());
+                let ingress_message_id = call_context_id
+                    .and_then(|id| call_context_manager.and_then(|ccm| ccm.call_origin(id)))
+                    .and_then(|origin| match origin {
+                        CallOrigin::Ingress(_, message_id) => Some(message_id),
+                        _ => None,
+                    });
+                (method_name, ingress_message_id)
+            }
+            CanisterMessageOrTask::Task(task) => {
+                (SystemMethod::from(task.clone()).to_string(), None)
+            }
+        };
+        state.started.insert(
+            canister_id,
+            StartedMessage {
+                method_name,
+                ingress_message_id,
+                time,
+                consumed_cycles_by_use_cases: canister
+                    .system_state
+                    .canister_metrics
+                    .get_consumed_cycles_by_use_cases()
+                    .clone(),
+                next_callback_id: call_context_manager.map_or(0, |ccm| ccm.next_callback_id()),
+            },
+        );
+    }
+
+    /// Records the profile of a message whose execution has completed.
+    /// Paused executions are ignored until they complete.
+    pub(crate) fn observe(&self, result: &ExecuteMessageResult) {
+        let (canister, instructions_used, heap_delta, stable_memory_delta) = match result {
+            ExecuteMessageResult::Finished {
+                canister,
+                instructions_used,
+                heap_delta,
+                stable_memory_delta,
+                ..
+            } => (
+                canister,
+                *instructions_used,
+                *heap_delta,
+                *stable_memory_delta,
+            ),
+            ExecuteMessageResult::Paused { .. } => return,
+        };
+        let canister_id = canister.canister_id();
+        let mut state = self.state.lock().unwrap();
+        let started = match state.started.remove(&canister_id) {
+            Some(started) => started,
+            None => return,
+        };
+
+        let consumed_cycles_by_use_cases = canister
+            .system_state
+            .canister_metrics
+            .get_consumed_cycles_by_use_cases();
+        let cycles_by_use_case = consumed_cycles_by_use_cases
+            .iter()
+            .filter_map(|(use_case, consumed)| {
+                let before = started
+                    .consumed_cycles_by_use_cases
+                    .get(use_case)
+                    .map_or(0, |consumed| consumed.get());
+                let charged = consumed.get().saturating_sub(before);
+                (charged > 0).then_some((*use_case, Cycles::new(charged)))
+            })
+            .collect();
+
+        let call_context_manager = canister.system_state.call_context_manager();
+        let next_callback_id = call_context_manager.map_or(0, |ccm| ccm.next_callback_id());
+        if let Some(ccm) = call_context_manager {
+            for (callback_id, callback) in ccm.callbacks().iter() {
+                if callback_id.get() > started.next_callback_id {
+                    state
+                        .call_contexts
+                        .entry((canister_id, callback.call_context_id))
+                        .or_insert_with(|| started.method_name.clone());
+                }
+            }
+        }
+        state.call_contexts.retain(|(id, call_context_id), _| {
+            *id != canister_id
+                || call_context_manager
+                    .is_some_and(|ccm| ccm.call_context(*call_context_id).is_some())
+        });
+
+        if state.profiles.len() == MAX_MESSAGE_PROFILES {
+            state.profiles.pop_front();
+        }
+        state.profiles.push_back(MessageProfile {
+            canister_id,
+            method_name: started.method_name,
+            ingress_message_id: started.ingress_message_id,
+            time: started.time,
+            instructions: instructions_used,
+            cycles_by_use_case,
+            // The heap delta includes the stable memory delta.
+            heap_dirty_pages: dirty_pages(NumBytes::new(
+                heap_delta.get().saturating_sub(stable_memory_delta.get()),
+            )),
+            stable_dirty_pages: dirty_pages(stable_memory_delta),
+            downstream_calls: next_callback_id.saturating_sub(started.next_callback_id),
+        });
+    }
+}
+
+fn dirty_pages(heap_delta: NumBytes) -> u64 {
+    heap_delta.get() / ic_sys::PAGE_SIZE as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_environment::{
+        ExecutionResponse, PausedExecution, RoundContext, RoundLimits,
+    };
+    use crate::metrics::CallTreeMetrics;
+    use ic_logger::ReplicaLogger;
+    use ic_test_utilities_state::get_running_canister;
+    use ic_test_utilities_types::messages::{IngressBuilder, ResponseBuilder};
+    use ic_types::messages::{CallbackId, NO_DEADLINE};
+    use ic_types::methods::{Callback, WasmClosure};
+    use ic_types_test_utils::ids::{canister_test_id, message_test_id, user_test_id};
+    use ic_utils_thread::deallocator_thread::DeallocationSender;
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct FakePausedExecution(CanisterMessageOrTask);
+
+    impl PausedExecution for FakePausedExecution {
+        fn resume(
+            self: Box<Self>,
+            _canister: CanisterState,
+            _round_context: RoundContext,
+            _round_limits: &mut RoundLimits,
+            _subnet_size: usize,
+            _call_tree_metrics: &dyn CallTreeMetrics,
+            _deallocation_sender: &DeallocationSender,
+        ) -> ExecuteMessageResult {
+            unimplemented!()
+        }
+
+        fn abort(self: Box<Self>, _log: &ReplicaLogger) -> (CanisterMessageOrTask, Cycles) {
+            (self.0, Cycles::zero())
+        }
+
+        fn input(&self) -> CanisterMessageOrTask {
+            self.0.clone()
+        }
+    }
+
+    fn enabled_profiler() -> MessageProfiler {
+        let profiler = MessageProfiler::default();
+        profiler.set_enabled(true);
+        profiler
+    }
+
+    fn time(seconds: u64) -> Time {
+        Time::from_secs_since_unix_epoch(seconds).unwrap()
+    }
+
+    fn ingress(method_name: &str, message_id: MessageId) -> CanisterMessageOrTask {
+        CanisterMessageOrTask::Message(CanisterMessage::Ingress(Arc::new(
+            IngressBuilder::new()
+                .receiver(canister_test_id(1))
+                .method_name(method_name)
+                .message_id(message_id)
+                .build(),
+        )))
+    }
+
+    fn response(callback_id: CallbackId) -> CanisterMessageOrTask {
+        CanisterMessageOrTask::Message(CanisterMessage::Response(Arc::new(
+            ResponseBuilder::new()
+                .originator(canister_test_id(1))
+                .respondent(canister_test_id(2))
+                .originator_reply_callback(callback_id)
+                .build(),
+        )))
+    }
+
+    /// Registers a callback for a call made in a new call context with the given origin.
+    fn call(canister: &mut CanisterState, call_origin: CallOrigin) -> CallbackId {
+        let call_context_id = canister
+            .system_state
+            .new_call_context(call_origin, Cycles::zero(), time(0), Default::default())
+            .unwrap();
+        canister
+            .system_state
+            .register_callback(Callback::new(
+                call_context_id,
+                canister_test_id(1),
+                canister_test_id(2),
+                Cycles::zero(),
+                Cycles::zero(),
+                Cycles::zero(),
+                WasmClosure::new(0, 0),
+                WasmClosure::new(0, 0),
+                None,
+                NO_DEADLINE,
+            ))
+            .unwrap()
+    }
+
+    fn finished(
+        canister: &CanisterState,
+        instructions_used: u64,
+        heap_delta: u64,
+        stable_memory_delta: u64,
+    ) -> ExecuteMessageResult {
+        ExecuteMessageResult::Finished {
+            canister: canister.clone(),
+            response: ExecutionResponse::Empty,
+            instructions_used: NumInstructions::new(instructions_used),
+            heap_delta: NumBytes::new(heap_delta),
+            stable_memory_delta: NumBytes::new(stable_memory_delta),
+            call_duration: None,
+        }
+    }
+
+    #[test]
+    fn response_is_attributed_to_calling_method() {
+        let profiler = enabled_profiler();
+        let mut canister = get_running_canister(canister_test_id(1));
+        // A call made before the profiler observed the calling message.
+        let unknown_callback = call(&mut canister, CallOrigin::SystemTask);
+
+        profiler.start(&canister, &ingress("foo", message_test_id(1)), time(1));
+        let callback = call(
+            &mut canister,
+            CallOrigin::Ingress(user_test_id(1), message_test_id(1)),
+        );
+        profiler.observe(&finished(&canister, 10, 0, 0));
+
+        profiler.start(&canister, &response(callback), time(2));
+        profiler.observe(&finished(&canister, 20, 0, 0));
+
+        profiler.start(&canister, &response(unknown_callback), time(3));
+        profiler.observe(&finished(&canister, 30, 0, 0));
+
+        let profiles: Vec<_> = profiler
+            .profiles()
+            .into_iter()
+            .map(|profile| {
+                (
+                    profile.method_name,
+                    profile.ingress_message_id,
+                    profile.time,
+                    profile.downstream_calls,
+                )
+            })
+            .collect();
+        assert_eq!(
+            profiles,
+            vec![
+                ("foo".to_string(), Some(message_test_id(1)), time(1), 1),
+                ("foo".to_string(), Some(message_test_id(1)), time(2), 0),
+                (UNKNOWN_METHOD_NAME.to_string(), None, time(3), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn paused_message_keeps_its_start_across_slices() {
+        let profiler = enabled_profiler();
+        let mut canister = get_running_canister(canister_test_id(1));
+        let input = ingress("foo", message_test_id(1));
+        let paused = |canister: &CanisterState| ExecuteMessageResult::Paused {
+            canister: canister.clone(),
+            paused_execution: Box::new(FakePausedExecution(input.clone())),
+            ingress_status: None,
+        };
+
+        profiler.start(&canister, &input, time(1));
+        canister
+            .system_state
+            .remove_cycles(Cycles::new(100), CyclesUseCase::Instructions);
+        profiler.observe(&paused(&canister));
+        canister
+            .system_state
+            .remove_cycles(Cycles::new(50), CyclesUseCase::Instructions);
+        profiler.observe(&paused(&canister));
+        assert!(profiler.profiles().is_empty());
+
+        // The execution is aborted and restarted in a later round.
+        profiler.start(&canister, &input, time(2));
+        canister
+            .system_state
+            .remove_cycles(Cycles::new(25), CyclesUseCase::Instructions);
+        profiler.observe(&finished(&canister, 300, 0, 0));
+
+        assert_eq!(
+            profiler.profiles(),
+            vec![MessageProfile {
+                canister_id: canister_test_id(1),
+                method_name: "foo".to_string(),
+                ingress_message_id: Some(message_test_id(1)),
+                time: time(1),
+                instructions: NumInstructions::new(300),
+                cycles_by_use_case: BTreeMap::from([(
+                    CyclesUseCase::Instructions,
+                    Cycles::new(175)
+                )]),
+                heap_dirty_pages: 0,
+                stable_dirty_pages: 0,
+                downstream_calls: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn profiles_are_capped() {
+        let profiler = enabled_profiler();
+        let canister = get_running_canister(canister_test_id(1));
+        let input = ingress("foo", message_test_id(1));
+        let result = finished(&canister, 0, 0, 0);
+        for seconds in 0..=MAX_MESSAGE_PROFILES as u64 {
+            profiler.start(&canister, &input, time(seconds));
+            profiler.observe(&result);
+        }
+
+        let profiles = profiler.profiles();
+        assert_eq!(profiles.len(), MAX_MESSAGE_PROFILES);
+        // The oldest profile is discarded.
+        assert_eq!(profiles.first().unwrap().time, time(1));
+        assert_eq!(
+            profiles.last().unwrap().time,
+            time(MAX_MESSAGE_PROFILES as u64)
+        );
+    }
+
+    #[test]
+    fn dirty_pages_are_split_into_heap_and_stable_memory() {
+        let profiler = enabled_profiler();
+        let canister = get_running_canister(canister_test_id(1));
+        let input = ingress("foo", message_test_id(1));
+        let page_size = ic_sys::PAGE_SIZE as u64;
+
+        profiler.start(&canister, &input, time(1));
+        profiler.observe(&finished(&canister, 0, 5 * page_size, 2 * page_size));
+        // A stable memory delta exceeding the heap delta does not underflow.
+        profiler.start(&canister, &input, time(2));
+        profiler.observe(&finished(&canister, 0, page_size, 2 * page_size));
+
+        let dirty_pages: Vec<_> = profiler
+            .profiles()
+            .into_iter()
+            .map(|profile| (profile.heap_dirty_pages, profile.stable_dirty_pages))
+            .collect();
+        assert_eq!(dirty_pages, vec![(3, 2), (0, 2)]);
+    }
+}
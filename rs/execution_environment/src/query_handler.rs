@@ -333,6 +333,8 @@ fn fetch_canister_logs(
             .canister_log
             .records()
             .iter()
+            .filter(|record| args.matches(record))
+            .take(args.page_size())
             .cloned()
             .collect(),
     };
@@ -1471,6 +1471,7 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::CallOnCleanup
         | SystemApiCallId::CallPerform
         | SystemApiCallId::CallWithBestEffortResponse
+        | SystemApiCallId::CallWithChunkedPayload
         | SystemApiCallId::CanisterCycleBalance
         | SystemApiCallId::CanisterCycleBalance128
         | SystemApiCallId::CanisterLiquidCycleBalance128
@@ -1493,8 +1494,11 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::GlobalTimerSet
         | SystemApiCallId::InReplicatedExecution
         | SystemApiCallId::IsController
+        | SystemApiCallId::Log
         | SystemApiCallId::MintCycles
         | SystemApiCallId::MintCycles128
+        | SystemApiCallId::MsgArgChunkCount
+        | SystemApiCallId::MsgArgChunkIndex
         | SystemApiCallId::MsgArgDataCopy
         | SystemApiCallId::MsgArgDataSize
         | SystemApiCallId::MsgCallerCopy
@@ -1527,6 +1531,9 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::StableSize
         | SystemApiCallId::StableWrite
         | SystemApiCallId::Time
+        | SystemApiCallId::TimerNameCopy
+        | SystemApiCallId::TimerNameSize
+        | SystemApiCallId::TimerSet
         | SystemApiCallId::Trap
         | SystemApiCallId::TryGrowWasmMemory => {
             ////////////////////////////////////////////////////////////////////
@@ -377,7 +377,8 @@ impl SchedulerImpl {
 
             let may_schedule_heartbeat = canister.exports_heartbeat_method();
             let may_schedule_global_timer = canister.exports_global_timer_method()
-                && canister.system_state.global_timer.has_reached_deadline(now);
+                && (canister.system_state.global_timer.has_reached_deadline(now)
+                    || canister.system_state.named_timers.has_reached_deadline(now));
 
             if !may_schedule_heartbeat && !may_schedule_global_timer {
                 // Canister has no heartbeat and no (schedulable) global timer.
@@ -2282,6 +2283,7 @@ fn get_instructions_limits_for_subnet_message(
             | NodeMetricsHistory
             | SubnetInfo
             | FetchCanisterLogs
+            | CanisterMethodProfile
             | ProvisionalCreateCanisterWithCycles
             | ProvisionalTopUpCanister
             | UploadChunk
@@ -2291,6 +2293,9 @@ fn get_instructions_limits_for_subnet_message(
             | LoadCanisterSnapshot
             | ListCanisterSnapshots
             | DeleteCanisterSnapshot
+            | CanisterSnapshotDiff
+            | CloneCanisterSnapshot
+            | CloneCanisterSnapshotChunk
             | ReadCanisterSnapshotMetadata
             | ReadCanisterSnapshotData
             | UploadCanisterSnapshotMetadata
@@ -1211,6 +1211,7 @@ impl TestWasmExecutorCore {
                 allocated_guaranteed_response_message_bytes: NumBytes::from(0),
                 instance_stats: InstanceStats::default(),
                 system_api_call_counters: SystemApiCallCounters::default(),
+                wasm_profile: None,
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -1258,6 +1259,7 @@ impl TestWasmExecutorCore {
             num_instructions_left: instructions_left,
             instance_stats,
             system_api_call_counters: SystemApiCallCounters::default(),
+            wasm_profile: None,
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
@@ -1363,6 +1365,7 @@ impl TestWasmExecutorCore {
                 on_reject: closure,
                 on_cleanup: None,
                 deadline,
+                pending_chunks: None,
             })
             .map_err(|err| err.to_string())?;
         let request = Request {
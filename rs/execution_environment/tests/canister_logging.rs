@@ -1,10 +1,12 @@
 use ic_base_types::PrincipalId;
 use ic_config::execution_environment::Config as ExecutionConfig;
+use ic_config::flag_status::FlagStatus;
 use ic_config::subnet_config::SubnetConfig;
 use ic_management_canister_types_private::{
-    self as ic00, BoundedAllowedViewers, CanisterIdRecord, CanisterInstallMode, CanisterLogRecord,
-    CanisterSettingsArgs, CanisterSettingsArgsBuilder, DataSize, EmptyBlob,
-    FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibilityV2, Payload,
+    self as ic00, BoundedAllowedViewers, CanisterIdRecord, CanisterInstallMode, CanisterLogField,
+    CanisterLogLevel, CanisterLogRecord, CanisterSettingsArgs, CanisterSettingsArgsBuilder,
+    DataSize, EmptyBlob, FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibilityV2,
+    Payload,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
@@ -41,6 +43,7 @@ fn canister_log_response(data: Vec<(u64, u64, Vec<u8>)>) -> FetchCanisterLogsRes
                 idx,
                 timestamp_nanos,
                 content,
+                ..Default::default()
             })
             .collect(),
     }
@@ -70,13 +73,19 @@ fn readable_logs_without_backtraces(
         .collect()
 }
 
+fn execution_config() -> ExecutionConfig {
+    let mut config = ExecutionConfig::default();
+    config.embedders_config.feature_flags.canister_log_levels = FlagStatus::Enabled;
+    config
+}
+
 fn setup(settings: CanisterSettingsArgs) -> (StateMachine, CanisterId) {
     let subnet_type = SubnetType::Application;
     let mut subnet_config = SubnetConfig::new(subnet_type);
     subnet_config.scheduler_config.max_instructions_per_round = MAX_INSTRUCTIONS_PER_ROUND;
     subnet_config.scheduler_config.max_instructions_per_message = MAX_INSTRUCTIONS_PER_MESSAGE;
     subnet_config.scheduler_config.max_instructions_per_slice = MAX_INSTRUCTIONS_PER_SLICE;
-    let config = StateMachineConfig::new(subnet_config, ExecutionConfig::default());
+    let config = StateMachineConfig::new(subnet_config, execution_config());
     let env = StateMachineBuilder::new()
         .with_config(Some(config))
         .with_subnet_type(subnet_type)
@@ -114,7 +123,7 @@ fn setup_with_controller(wasm: Vec<u8>) -> (StateMachine, CanisterId, PrincipalI
 fn restart_node(env: StateMachine) -> StateMachine {
     env.restart_node_with_config(StateMachineConfig::new(
         SubnetConfig::new(SubnetType::Application),
-        ExecutionConfig::default(),
+        execution_config(),
     ))
 }
 
@@ -455,6 +464,234 @@ fn test_canister_log_record_index_increment_for_different_calls() {
     );
 }
 
+#[test]
+fn test_fetch_canister_logs_with_filters_and_page_size() {
+    let (env, canister_id, controller) = setup_with_controller(
+        wat_canister()
+            .update(
+                "test1",
+                wat_fn().debug_print(b"info 0").debug_print(b"error 1"),
+            )
+            .update(
+                "test2",
+                wat_fn().debug_print(b"info 2").debug_print(b"error 3"),
+            )
+            .build_wasm(),
+    );
+    env.advance_time(Duration::from_secs(1));
+    let timestamp_01 = system_time_to_nanos(env.time());
+    let _ = env.execute_ingress(canister_id, "test1", vec![]);
+    env.advance_time(Duration::from_secs(1));
+    let timestamp_23 = system_time_to_nanos(env.time());
+    let _ = env.execute_ingress(canister_id, "test2", vec![]);
+
+    let fetch = |request: FetchCanisterLogsRequest| {
+        let result = env.query_as(
+            controller,
+            CanisterId::ic_00(),
+            "fetch_canister_logs",
+            request.encode(),
+        );
+        FetchCanisterLogsResponse::decode(&get_reply(result)).unwrap()
+    };
+    let request = || FetchCanisterLogsRequest::new(canister_id);
+
+    // Records starting at an index.
+    assert_eq!(
+        fetch(request().with_start_idx(2)),
+        canister_log_response(vec![
+            (2, timestamp_23, b"info 2".to_vec()),
+            (3, timestamp_23, b"error 3".to_vec()),
+        ])
+    );
+    // Records within a time range.
+    assert_eq!(
+        fetch(request().with_time_range(timestamp_01, timestamp_23)),
+        canister_log_response(vec![
+            (0, timestamp_01, b"info 0".to_vec()),
+            (1, timestamp_01, b"error 1".to_vec()),
+        ])
+    );
+    // Records containing a substring.
+    assert_eq!(
+        fetch(request().with_content_contains(b"error".to_vec())),
+        canister_log_response(vec![
+            (1, timestamp_01, b"error 1".to_vec()),
+            (3, timestamp_23, b"error 3".to_vec()),
+        ])
+    );
+    // Tailing the log page by page.
+    assert_eq!(
+        fetch(request().with_page_size(3)),
+        canister_log_response(vec![
+            (0, timestamp_01, b"info 0".to_vec()),
+            (1, timestamp_01, b"error 1".to_vec()),
+            (2, timestamp_23, b"info 2".to_vec()),
+        ])
+    );
+    assert_eq!(
+        fetch(request().with_start_idx(3).with_page_size(3)),
+        canister_log_response(vec![(3, timestamp_23, b"error 3".to_vec())])
+    );
+    assert_eq!(
+        fetch(request().with_start_idx(4).with_page_size(3)),
+        canister_log_response(vec![])
+    );
+}
+
+#[test]
+fn test_structured_logging_with_levels_and_fields() {
+    let wat = r#"
+        (module
+            (import "ic0" "log"
+                (func $ic0_log (param i32) (param i32) (param i32) (param i32) (param i32)))
+            (func (export "canister_update test")
+                (call $ic0_log (i32.const 0) (i32.const 0) (i32.const 5) (i32.const 0) (i32.const 0))
+                (call $ic0_log (i32.const 1) (i32.const 5) (i32.const 4) (i32.const 0) (i32.const 0))
+                (call $ic0_log (i32.const 2) (i32.const 9) (i32.const 7) (i32.const 100) (i32.const 9))
+                (call $ic0_log (i32.const 3) (i32.const 16) (i32.const 5) (i32.const 0) (i32.const 0))
+            )
+            (func (export "canister_update invalid_level")
+                (call $ic0_log (i32.const 4) (i32.const 0) (i32.const 5) (i32.const 0) (i32.const 0))
+            )
+            (func (export "canister_update invalid_fields")
+                (call $ic0_log (i32.const 1) (i32.const 0) (i32.const 5) (i32.const 0) (i32.const 5))
+            )
+            (memory 1)
+            (data (i32.const 0) "debuginfowarningerror")
+            (data (i32.const 100) "a=1\nb=x=y")
+        )"#;
+    let (env, canister_id) = setup_and_install_wasm(
+        CanisterSettingsArgsBuilder::new()
+            .with_log_visibility(LogVisibilityV2::Public)
+            .with_log_min_level(CanisterLogLevel::Info)
+            .build(),
+        wat::parse_str(wat).unwrap(),
+    );
+    env.advance_time(Duration::from_secs(1));
+    let timestamp = system_time_to_nanos(env.time());
+    let _ = env.execute_ingress(canister_id, "test", vec![]);
+
+    let record = |idx, level, content: &[u8], fields: &[(&str, &str)]| CanisterLogRecord {
+        idx,
+        timestamp_nanos: timestamp,
+        content: content.to_vec(),
+        level,
+        fields: fields
+            .iter()
+            .map(|(key, value)| CanisterLogField {
+                key: key.to_string(),
+                value: value.to_string(),
+            })
+            .collect(),
+    };
+    let fetch = |request: FetchCanisterLogsRequest| {
+        let result = env.query(CanisterId::ic_00(), "fetch_canister_logs", request.encode());
+        FetchCanisterLogsResponse::decode(&get_reply(result))
+            .unwrap()
+            .canister_log_records
+    };
+
+    // The debug record is below the minimum level of the canister and dropped.
+    assert_eq!(
+        fetch(FetchCanisterLogsRequest::new(canister_id)),
+        vec![
+            record(0, CanisterLogLevel::Info, b"info", &[]),
+            record(
+                1,
+                CanisterLogLevel::Warning,
+                b"warning",
+                &[("a", "1"), ("b", "x=y")]
+            ),
+            record(2, CanisterLogLevel::Error, b"error", &[]),
+        ]
+    );
+    assert_eq!(
+        fetch(FetchCanisterLogsRequest::new(canister_id).with_min_level(CanisterLogLevel::Error)),
+        vec![record(2, CanisterLogLevel::Error, b"error", &[])]
+    );
+
+    for method in ["invalid_level", "invalid_fields"] {
+        let err = env
+            .execute_ingress(canister_id, method, vec![])
+            .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    }
+}
+
+#[test]
+fn test_default_log_min_level_keeps_debug_records() {
+    let wat = r#"
+        (module
+            (import "ic0" "log"
+                (func $ic0_log (param i32) (param i32) (param i32) (param i32) (param i32)))
+            (func (export "canister_update test")
+                (call $ic0_log (i32.const 0) (i32.const 0) (i32.const 5) (i32.const 0) (i32.const 0))
+            )
+            (memory 1)
+            (data (i32.const 0) "debug")
+        )"#;
+    let (env, canister_id) = setup_and_install_wasm(
+        CanisterSettingsArgsBuilder::new()
+            .with_log_visibility(LogVisibilityV2::Public)
+            .build(),
+        wat::parse_str(wat).unwrap(),
+    );
+    let log_min_level = |env: &StateMachine| {
+        env.canister_status(canister_id)
+            .unwrap()
+            .unwrap()
+            .settings()
+            .log_min_level()
+    };
+    assert_eq!(log_min_level(&env), CanisterLogLevel::Debug);
+
+    let _ = env.execute_ingress(canister_id, "test", vec![]);
+    let result = fetch_canister_logs(&env, PrincipalId::new_anonymous(), canister_id);
+    let records = FetchCanisterLogsResponse::decode(&get_reply(result))
+        .unwrap()
+        .canister_log_records;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].level, CanisterLogLevel::Debug);
+    assert_eq!(records[0].content, b"debug".to_vec());
+
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_log_min_level(CanisterLogLevel::Warning)
+            .build(),
+    )
+    .unwrap();
+    assert_eq!(log_min_level(&env), CanisterLogLevel::Warning);
+}
+
+#[test]
+fn test_log_min_level_keeps_debug_print_and_trap_records() {
+    let (env, canister_id) = setup_and_install_wasm(
+        CanisterSettingsArgsBuilder::new()
+            .with_log_visibility(LogVisibilityV2::Public)
+            .with_log_min_level(CanisterLogLevel::Error)
+            .build(),
+        wat_canister()
+            .update("test", wat_fn().debug_print(b"message"))
+            .update("trap", wat_fn().trap_with_blob(b"failure"))
+            .build_wasm(),
+    );
+    let _ = env.execute_ingress(canister_id, "test", vec![]);
+    let _ = env.execute_ingress(canister_id, "trap", vec![]);
+
+    let result = fetch_canister_logs(&env, PrincipalId::new_anonymous(), canister_id);
+    let records = FetchCanisterLogsResponse::decode(&get_reply(result))
+        .unwrap()
+        .canister_log_records;
+    // Both records are below the minimum level but are not written by `ic0.log`.
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].level, CanisterLogLevel::Info);
+    assert_eq!(records[0].content, b"message".to_vec());
+    assert_eq!(records[1].level, CanisterLogLevel::Error);
+    assert!(records[1].content.starts_with(b"[TRAP]: failure"));
+}
+
 #[test]
 fn test_canister_log_record_index_increment_after_node_restart() {
     // Test that the index of the log records is incremented for each log message
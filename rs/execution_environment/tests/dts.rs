@@ -13,12 +13,12 @@ use ic_config::{
 use ic_management_canister_types_private::{
     CanisterIdRecord, CanisterInfoRequest, CanisterInstallMode, CanisterInstallModeV2,
     CanisterSettingsArgsBuilder, CanisterSnapshotDataKind, CanisterSnapshotDataOffset,
-    ClearChunkStoreArgs, DeleteCanisterSnapshotArgs, EmptyBlob, GlobalTimer,
-    InstallChunkedCodeArgs, InstallCodeArgs, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
-    Method, OnLowWasmMemoryHookStatus, Payload, ReadCanisterSnapshotDataArgs,
-    ReadCanisterSnapshotMetadataArgs, StoredChunksArgs, TakeCanisterSnapshotArgs,
-    UninstallCodeArgs, UpdateSettingsArgs, UploadCanisterSnapshotDataArgs,
-    UploadCanisterSnapshotMetadataArgs, UploadChunkArgs, IC_00,
+    CanisterSnapshotDiffArgs, ClearChunkStoreArgs, CloneCanisterSnapshotArgs,
+    DeleteCanisterSnapshotArgs, EmptyBlob, GlobalTimer, InstallChunkedCodeArgs, InstallCodeArgs,
+    ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, Method, OnLowWasmMemoryHookStatus, Payload,
+    ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotMetadataArgs, StoredChunksArgs,
+    TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs,
+    UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs, UploadChunkArgs, IC_00,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::{execution_state::NextScheduledMethod, NextExecution};
@@ -1029,7 +1029,10 @@ fn dts_aborted_execution_does_not_block_subnet_messages() {
         }
 
         let (method, args) = f(aborted_canister_id);
-        if method == Method::DeleteCanisterSnapshot {
+        if method == Method::DeleteCanisterSnapshot
+            || method == Method::CanisterSnapshotDiff
+            || method == Method::CloneCanisterSnapshot
+        {
             env.take_canister_snapshot(TakeCanisterSnapshotArgs::new(aborted_canister_id, None))
                 .unwrap();
         }
@@ -1096,12 +1099,13 @@ fn dts_aborted_execution_does_not_block_subnet_messages() {
     for method in Method::iter() {
         match method {
             // Supported methods accepting just one argument.
-            Method::CanisterStatus | Method::DepositCycles | Method::StartCanister => {
-                test_supported(|aborted_canister_id| {
-                    let args = CanisterIdRecord::from(aborted_canister_id).encode();
-                    (method, call_args().other_side(args))
-                })
-            }
+            Method::CanisterStatus
+            | Method::CanisterMethodProfile
+            | Method::DepositCycles
+            | Method::StartCanister => test_supported(|aborted_canister_id| {
+                let args = CanisterIdRecord::from(aborted_canister_id).encode();
+                (method, call_args().other_side(args))
+            }),
             Method::CanisterInfo => test_supported(|aborted_canister_id| {
                 let args = CanisterInfoRequest::new(aborted_canister_id, None).encode();
                 (method, call_args().other_side(args))
@@ -1204,6 +1208,7 @@ fn dts_aborted_execution_does_not_block_subnet_messages() {
                 let args = TakeCanisterSnapshotArgs {
                     canister_id: aborted_canister_id.get(),
                     replace_snapshot: None,
+                    base_snapshot: None,
                 }
                 .encode();
                 (method, call_args().other_side(args))
@@ -1230,6 +1235,26 @@ fn dts_aborted_execution_does_not_block_subnet_messages() {
                 .encode();
                 (method, call_args().other_side(args))
             }),
+            Method::CanisterSnapshotDiff => test_supported(|aborted_canister_id| {
+                let args = CanisterSnapshotDiffArgs::new(
+                    aborted_canister_id,
+                    (aborted_canister_id, 0).into(),
+                )
+                .encode();
+                (method, call_args().other_side(args))
+            }),
+            Method::CloneCanisterSnapshot => test_supported(|aborted_canister_id| {
+                let args = CloneCanisterSnapshotArgs::new(
+                    aborted_canister_id,
+                    (aborted_canister_id, 0).into(),
+                    aborted_canister_id,
+                    None,
+                )
+                .encode();
+                (method, call_args().other_side(args))
+            }),
+            // Only callable by other subnets.
+            Method::CloneCanisterSnapshotChunk => {}
             Method::ReadCanisterSnapshotMetadata => test_supported(|aborted_canister_id| {
                 let args = ReadCanisterSnapshotMetadataArgs::new(
                     aborted_canister_id,
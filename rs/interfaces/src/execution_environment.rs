@@ -148,6 +148,8 @@ pub enum SystemApiCallId {
     CallPerform,
     /// Tracker for `ic0.call_with_best_effort_response()`
     CallWithBestEffortResponse,
+    /// Tracker for `ic0.call_with_chunked_payload()`
+    CallWithChunkedPayload,
     /// Tracker for `ic0.canister_cycle_balance()`
     CanisterCycleBalance,
     /// Tracker for `ic0.canister_cycle_balance128()`
@@ -192,10 +194,16 @@ pub enum SystemApiCallId {
     InReplicatedExecution,
     /// Tracker for `ic0.is_controller()`
     IsController,
+    /// Tracker for `ic0.log()`
+    Log,
     /// Tracker for `ic0.mint_cycles()`
     MintCycles,
     /// Tracker for `ic0.mint_cycles128()`
     MintCycles128,
+    /// Tracker for `ic0.msg_arg_chunk_count()`
+    MsgArgChunkCount,
+    /// Tracker for `ic0.msg_arg_chunk_index()`
+    MsgArgChunkIndex,
     /// Tracker for `ic0.msg_arg_data_copy()`
     MsgArgDataCopy,
     /// Tracker for `ic0.msg_arg_data_size()`
@@ -260,6 +268,12 @@ pub enum SystemApiCallId {
     StableWrite,
     /// Tracker for `ic0.time()`
     Time,
+    /// Tracker for `ic0.timer_name_copy()`
+    TimerNameCopy,
+    /// Tracker for `ic0.timer_name_size()`
+    TimerNameSize,
+    /// Tracker for `ic0.timer_set()`
+    TimerSet,
     /// Tracker for `ic0.trap()`
     Trap,
     /// Tracker for `__.try_grow_wasm_memory()`
@@ -754,6 +768,21 @@ pub trait SystemApi {
     /// Outputs the specified bytes on the heap as a string on STDOUT.
     fn ic0_debug_print(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()>;
 
+    /// Appends a record with the given level (0: debug, 1: info, 2: warning,
+    /// 3: error) to the canister's logs. The message is copied from
+    /// heap[src..src+size] and the fields, given as newline-separated
+    /// `key=value` pairs, from heap[fields_src..fields_src+fields_size].
+    /// Records below the canister's minimum log level are dropped.
+    fn ic0_log(
+        &mut self,
+        level: u32,
+        src: usize,
+        size: usize,
+        fields_src: usize,
+        fields_size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()>;
+
     /// Traps, with a possibly helpful message
     fn ic0_trap(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()>;
 
@@ -802,6 +831,30 @@ pub trait SystemApi {
     /// Otherwise, it traps. A different timeout can be specified for each call.
     fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()>;
 
+    /// Allows the argument of the call under construction to exceed the
+    /// maximum message size, up to `MAX_CHUNKED_CALL_PAYLOAD_IN_BYTES`. The
+    /// system splits the argument into chunks and sends each chunk as a
+    /// separate request to the same method, sending the next chunk only after
+    /// the previous one was replied to. The reply or reject of the last chunk
+    /// (or the first reject) is delivered to the caller's callbacks.
+    ///
+    /// This method can be called only in between `ic0.call_new` and
+    /// `ic0.call_perform`, and not for calls with best-effort responses.
+    /// Otherwise, it traps.
+    ///
+    /// Until certification version 20 is current, chunked calls are only
+    /// delivered to canisters on the same subnet; a chunked call to another
+    /// subnet is rejected with `DestinationInvalid`.
+    fn ic0_call_with_chunked_payload(&mut self) -> HypervisorResult<()>;
+
+    /// Returns the index of the argument chunk being processed, if the current
+    /// message carries a chunk of a chunked call; 0 otherwise.
+    fn ic0_msg_arg_chunk_index(&self) -> HypervisorResult<i32>;
+
+    /// Returns the number of chunks in the argument of the chunked call that
+    /// the current message is a part of; 1 if the call is not chunked.
+    fn ic0_msg_arg_chunk_count(&self) -> HypervisorResult<i32>;
+
     /// The deadline, in nanoseconds since 1970-01-01, after which the caller might stop waiting for a response.
     ///
     /// For calls with best-effort responses, the deadline is computed based on the time the call was made, and
@@ -870,6 +923,42 @@ pub trait SystemApi {
     /// The canister can set a global one-off timer at the specific time.
     fn ic0_global_timer_set(&mut self, time: Time) -> HypervisorResult<Time>;
 
+    /// Sets the named timer with the name stored in `heap[name_src..name_src +
+    /// name_size]` to expire at the specific time and, if `interval_nanos` is
+    /// not zero, every `interval_nanos` nanoseconds after that. Setting the
+    /// time to zero cancels the timer.
+    ///
+    /// Returns the previous deadline of the timer, or zero if the timer was
+    /// not set. Traps if the name is too long or if the canister would have
+    /// too many named timers.
+    ///
+    /// Expired named timers are executed via `canister_global_timer` and take
+    /// precedence over the global timer: while a named timer is due, the
+    /// global timer stays active and is only executed in a later round.
+    fn ic0_timer_set(
+        &mut self,
+        name_src: usize,
+        name_size: usize,
+        time: Time,
+        interval_nanos: u64,
+        heap: &[u8],
+    ) -> HypervisorResult<Time>;
+
+    /// Returns the length of the name of the named timer that triggered the
+    /// execution of `canister_global_timer`; 0 if the execution was triggered
+    /// by the global timer.
+    fn ic0_timer_name_size(&self) -> HypervisorResult<usize>;
+
+    /// Copies the name of the named timer that triggered the execution of
+    /// `canister_global_timer` to the heap.
+    fn ic0_timer_name_copy(
+        &self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
     /// The canister can query the IC for its version.
     fn ic0_canister_version(&self) -> HypervisorResult<u64>;
 
@@ -1375,6 +1464,9 @@ pub struct WasmExecutionOutput {
     pub instance_stats: InstanceStats,
     /// How many times each tracked System API call was invoked.
     pub system_api_call_counters: SystemApiCallCounters,
+    /// The call-stack samples collected by the Wasm profiler in the
+    /// collapsed-stack format, if the `wasm_profiling` feature is enabled.
+    pub wasm_profile: Option<String>,
 }
 
 impl fmt::Display for WasmExecutionOutput {
@@ -1,6 +1,7 @@
 use crate::message_routing::{
     LatencyMetrics, MessageRoutingMetrics, CRITICAL_ERROR_INDUCT_RESPONSE_FAILED,
 };
+use ic_certification_version::CertificationVersion;
 use ic_config::embedders::BestEffortResponsesFeature;
 use ic_error_types::RejectCode;
 use ic_limits::SYSTEM_SUBNET_STREAM_MSG_LIMIT;
@@ -326,6 +327,8 @@ impl StreamBuilderImpl {
         let mut requests_to_reject = Vec::new();
         let mut best_effort_requests_to_unsupported_subnets = Vec::new();
         let mut oversized_requests = Vec::new();
+        let mut chunked_requests_to_unsupported_subnets = Vec::new();
+        let certification_version = state.metadata.certification_version;
 
         let mut output_iter = state.output_into_iter();
         let mut last_output_size = usize::MAX;
@@ -412,6 +415,23 @@ impl StreamBuilderImpl {
                             best_effort_requests_to_unsupported_subnets.push(req);
                         }
 
+                        // Remote request carrying a chunk of a chunked call, when the
+                        // stream encoding does not support chunked calls yet.
+                        //
+                        // TODO: Drop this once certification version 20 is fully deployed.
+                        RequestOrResponse::Request(req)
+                            if req.metadata.payload_chunk().is_some()
+                                && dst_subnet_id != self.subnet_id
+                                && certification_version < CertificationVersion::V20 =>
+                        {
+                            warn!(
+                                self.log,
+                                "Chunked call to remote subnet not yet supported from {}",
+                                req.sender
+                            );
+                            chunked_requests_to_unsupported_subnets.push(req);
+                        }
+
                         // Response above the payload size limit.
                         RequestOrResponse::Response(ref mut rep)
                             if rep.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES =>
@@ -508,6 +528,18 @@ impl StreamBuilderImpl {
             );
         }
 
+        for req in chunked_requests_to_unsupported_subnets {
+            self.reject_local_request(
+                &mut state,
+                &req,
+                RejectCode::DestinationInvalid,
+                format!(
+                    "Chunked call to remote subnet not yet supported: {} -> {}",
+                    req.sender, req.receiver
+                ),
+            );
+        }
+
         for req in oversized_requests {
             let sender = req.sender;
             self.reject_local_request(
@@ -51,6 +51,7 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 chain_key_config: None,
+                block_rate_config: None,
             };
 
             let key = make_subnet_record_key(subnet_id);
@@ -168,6 +169,7 @@ fn test_submit_and_accept_update_subnet_proposal() {
                     ssh_readonly_access: vec!["pub_key_0".to_string()],
                     ssh_backup_access: vec!["pub_key_1".to_string()],
                     chain_key_config: None,
+                    block_rate_config: None,
                 }
             );
             Ok(())
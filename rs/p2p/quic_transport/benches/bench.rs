@@ -59,6 +59,7 @@ fn spawn_transport(
         watch_rx,
         create_udp_socket(&rt, node_addr),
         Router::new().route("/", any(pong)),
+        Default::default(),
     ));
     (transport, node_id, node_addr)
 }
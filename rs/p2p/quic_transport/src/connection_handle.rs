@@ -1,6 +1,9 @@
 //! The module implements the RPC abstraction over an established QUIC connection.
 //!
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 use bytes::Bytes;
 use http::{Method, Request, Response, Version};
@@ -13,6 +16,7 @@ use crate::{
         observe_conn_error, observe_read_to_end_error, observe_stopped_error, observe_write_error,
         QuicTransportMetrics, INFALIBBLE,
     },
+    traffic_shaping::PeerShaper,
     ConnId, MessagePriority, ResetStreamOnDrop, MAX_MESSAGE_SIZE_BYTES,
 };
 
@@ -23,15 +27,17 @@ pub struct ConnectionHandle {
     conn: Connection,
     metrics: QuicTransportMetrics,
     conn_id: ConnId,
+    shaper: Arc<PeerShaper>,
 }
 
 impl ConnectionHandle {
-    pub fn new(conn: Connection, metrics: QuicTransportMetrics) -> Self {
+    pub fn new(conn: Connection, metrics: QuicTransportMetrics, shaper: Arc<PeerShaper>) -> Self {
         let conn_id = CONN_ID_SEQ.fetch_add(1, Ordering::SeqCst);
         Self {
             conn,
             conn_id: conn_id.into(),
             metrics,
+            shaper,
         }
     }
 
@@ -42,6 +48,12 @@ impl ConnectionHandle {
     pub fn conn(&self) -> &Connection {
         &self.conn
     }
+
+    /// The shaper of the outgoing traffic of this connection.
+    pub(crate) fn shaper(&self) -> Arc<PeerShaper> {
+        self.shaper.clone()
+    }
+
     /// Executes an RPC operation over an already-established connection.
     ///
     /// This method leverages the QUIC transport layer, which continuously monitors the connection’s health
@@ -83,6 +95,17 @@ impl ConnectionHandle {
             .unwrap_or_default();
         let _ = send_stream.set_priority(priority.into());
 
+        let throttled = self
+            .shaper
+            .acquire(request.uri().path(), priority, request.body().len() as u64)
+            .await;
+        if !throttled.is_zero() {
+            self.metrics
+                .connection_handle_throttled_bytes_total
+                .with_label_values(&[request.uri().path()])
+                .inc_by(request.body().len() as u64);
+        }
+
         bytes_sent_counter.inc_by(request.body().len() as u64);
         let request_bytes = into_request_bytes(request);
 
@@ -30,11 +30,12 @@ use std::{
 };
 
 use axum::{
-    body::Body, body::HttpBody, extract::Request, extract::State, middleware::from_fn_with_state,
-    middleware::Next, Router,
+    body::Body, body::HttpBody, extract::MatchedPath, extract::Request, extract::State,
+    middleware::from_fn_with_state, middleware::Next, Router,
 };
 use futures::StreamExt;
 use ic_base_types::NodeId;
+use ic_config::transport::TrafficShapingConfig;
 use ic_crypto_tls_interfaces::{SomeOrAllNodes, TlsConfig};
 use ic_crypto_utils_tls::node_id_from_certificate_der;
 use ic_http_endpoints_async_utils::JoinMap;
@@ -56,6 +57,7 @@ use tokio_util::{sync::CancellationToken, time::DelayQueue};
 use crate::{
     connection_handle::ConnectionHandle,
     metrics::{CONNECTION_RESULT_FAILED_LABEL, CONNECTION_RESULT_SUCCESS_LABEL},
+    traffic_shaping::PeerShaper,
     Shutdown, SubnetTopology,
 };
 use crate::{metrics::QuicTransportMetrics, request_handler::start_stream_acceptor};
@@ -125,6 +127,7 @@ struct ConnectionManager {
     /// Endpoint config
     endpoint: Endpoint,
     transport_config: Arc<quinn::TransportConfig>,
+    traffic_shaping: TrafficShapingConfig,
     router: Router,
 }
 
@@ -187,6 +190,7 @@ pub(crate) fn start_connection_manager(
     watcher: tokio::sync::watch::Receiver<SubnetTopology>,
     socket: Arc<dyn AsyncUdpSocket>,
     router: Router,
+    traffic_shaping: TrafficShapingConfig,
 ) -> Shutdown {
     let topology = watcher.borrow().clone();
 
@@ -250,6 +254,7 @@ pub(crate) fn start_connection_manager(
         watcher,
         endpoint,
         transport_config,
+        traffic_shaping,
         outbound_connecting: JoinMap::new(),
         inbound_connecting: JoinSet::new(),
         active_connections: JoinMap::new(),
@@ -498,7 +503,11 @@ impl ConnectionManager {
         // This should be done while holding a write lock to the peer map
         // such that the next read call sees the new id.
 
-        let connection_handle = ConnectionHandle::new(connection, self.metrics.clone());
+        let connection_handle = ConnectionHandle::new(
+            connection,
+            self.metrics.clone(),
+            Arc::new(PeerShaper::new(&self.traffic_shaping)),
+        );
 
         // dropping the old connection will result in closing it
         if let Some(old_conn) = peer_map_mut.insert(peer_id, connection_handle.clone()) {
@@ -601,7 +610,12 @@ async fn collect_metrics(
     let out_counter = state
         .request_handle_bytes_sent_total
         .with_label_values(&[request.uri().path()]);
-    let response = next.run(request).await;
+    let matched_path = request.extensions().get::<MatchedPath>().cloned();
+    let mut response = next.run(request).await;
     out_counter.inc_by(response.body().size_hint().lower());
+    // Lets the request handler attribute the response to the route that handled the request.
+    if let Some(matched_path) = matched_path {
+        response.extensions_mut().insert(matched_path);
+    }
     response
 }
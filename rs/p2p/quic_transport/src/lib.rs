@@ -10,12 +10,16 @@
 //!  - Request Handler (request_handler.rs): Accepts streams on an active connection.
 //!    Spawned by the connection manager for each connection.
 //!  - Connection Handle (connection_handle.rs): Provides rpc and push interfaces to a peer.
+//!  - Traffic Shaping (traffic_shaping.rs): Limits the outgoing bandwidth per peer and per route.
+//!    Shared by the connection handle and the request handler of a connection.
 //!
 //! API:
 //!  - Constructor takes a topology watcher. The topology defines the
 //!    set of peers, to which transport tries to keep active connections.
 //!  - Constructor also takes a Router. Incoming requests are routed to a handler
 //!    based on the URI specified in the request.
+//!  - Constructor also takes a `TrafficShapingConfig` that is applied to the outgoing
+//!    requests and responses of every connection.
 //!  - `get_conn_handle`: Can be used to get a `ConnectionHandle` to a peer.
 //!     The connection handle is small wrapper around the actual quic connection
 //!     with an rpc/push interface. Passed in requests need to specify an URI to get
@@ -44,6 +48,7 @@ use axum::{
 };
 use bytes::Bytes;
 use ic_base_types::{NodeId, RegistryVersion};
+use ic_config::transport::TrafficShapingConfig;
 use ic_crypto_tls_interfaces::TlsConfig;
 use ic_interfaces_registry::RegistryClient;
 use ic_logger::{info, ReplicaLogger};
@@ -62,6 +67,7 @@ mod connection_handle;
 mod connection_manager;
 mod metrics;
 mod request_handler;
+mod traffic_shaping;
 pub use crate::connection_manager::create_udp_socket;
 
 /// On purpose the value is big, otherwise there is risk of not processing important consensus messages.
@@ -148,6 +154,7 @@ impl QuicTransport {
         udp_socket: Arc<dyn AsyncUdpSocket>,
         // Make sure this is respected https://docs.rs/axum/latest/axum/struct.Router.html#a-note-about-performance
         router: Router,
+        traffic_shaping: TrafficShapingConfig,
     ) -> QuicTransport {
         info!(log, "Starting Quic transport.");
 
@@ -164,6 +171,7 @@ impl QuicTransport {
             topology_watcher,
             udp_socket,
             router,
+            traffic_shaping,
         );
 
         QuicTransport {
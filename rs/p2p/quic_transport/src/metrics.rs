@@ -26,6 +26,8 @@ const ERROR_TRANSPORT_ERROR: &str = "transport_error_conn";
 const ERROR_LOCALLY_CLOSED_CONN: &str = "locally_closed_conn";
 
 pub(crate) const STREAM_TYPE_BIDI: &str = "bidi";
+/// Handler label of responses to requests that did not match any route.
+pub(crate) const UNMATCHED_HANDLER: &str = "unmatched";
 
 #[derive(Clone, Debug)]
 pub struct QuicTransportMetrics {
@@ -47,11 +49,13 @@ pub struct QuicTransportMetrics {
     pub request_handle_bytes_received_total: IntCounterVec,
     pub request_handle_bytes_sent_total: IntCounterVec,
     pub request_handle_duration_seconds: HistogramVec,
+    pub request_handle_throttled_bytes_total: IntCounterVec,
     // Connection handle
     pub connection_handle_bytes_received_total: IntCounterVec,
     pub connection_handle_bytes_sent_total: IntCounterVec,
     pub connection_handle_duration_seconds: HistogramVec,
     pub connection_handle_errors_total: IntCounterVec,
+    pub connection_handle_throttled_bytes_total: IntCounterVec,
     // Quinn
     quinn_path_rtt_seconds: GaugeVec,
     quinn_path_congestion_window: IntGaugeVec,
@@ -134,6 +138,11 @@ impl QuicTransportMetrics {
                 decimal_buckets(-2, 0),
                 &[HANDLER_LABEL],
             ),
+            request_handle_throttled_bytes_total: metrics_registry.int_counter_vec(
+                "quic_transport_request_handle_throttled_bytes_total",
+                "Response bytes held back by traffic shaping by handler.",
+                &[HANDLER_LABEL],
+            ),
             // Connection handler
             connection_handle_bytes_received_total: metrics_registry.int_counter_vec(
                 "quic_transport_connection_handle_bytes_received_total",
@@ -156,6 +165,11 @@ impl QuicTransportMetrics {
                 "Request handler errors by stream type and error type.",
                 &[QUINN_API_LABEL, ERROR_TYPE_LABEL],
             ),
+            connection_handle_throttled_bytes_total: metrics_registry.int_counter_vec(
+                "quic_transport_connection_handle_throttled_bytes_total",
+                "Request bytes held back by traffic shaping by handler.",
+                &[HANDLER_LABEL],
+            ),
             // Quinn stats
             quinn_path_rtt_seconds: metrics_registry.gauge_vec(
                 "quic_transport_quinn_path_rtt_seconds",
@@ -10,9 +10,9 @@
 //!
 //! Please note that the connection manager is responsible for closing connections.
 //!
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use axum::{body::Body, Router}; // TODO: try to remove the axum dep here
+use axum::{body::Body, extract::MatchedPath, Router}; // TODO: try to remove the axum dep here
 use bytes::Bytes;
 use http::{Method, Request, Response, Version};
 use ic_base_types::NodeId;
@@ -26,9 +26,10 @@ use crate::{
     connection_handle::ConnectionHandle,
     metrics::{
         observe_conn_error, observe_read_to_end_error, observe_stopped_error, observe_write_error,
-        QuicTransportMetrics, ERROR_TYPE_APP, INFALIBBLE, STREAM_TYPE_BIDI,
+        QuicTransportMetrics, ERROR_TYPE_APP, INFALIBBLE, STREAM_TYPE_BIDI, UNMATCHED_HANDLER,
     },
-    ConnId, ResetStreamOnDrop, MAX_MESSAGE_SIZE_BYTES,
+    traffic_shaping::PeerShaper,
+    ConnId, MessagePriority, ResetStreamOnDrop, MAX_MESSAGE_SIZE_BYTES,
 };
 
 const QUIC_METRIC_SCRAPE_INTERVAL: Duration = Duration::from_secs(5);
@@ -67,6 +68,7 @@ pub async fn start_stream_acceptor(
                                 handle_bi_stream(
                                     peer_id,
                                     conn_handle.conn_id(),
+                                    conn_handle.shaper(),
                                     metrics.clone(),
                                     router.clone(),
                                     send_stream,
@@ -105,6 +107,7 @@ pub async fn start_stream_acceptor(
 async fn handle_bi_stream(
     peer_id: NodeId,
     conn_id: ConnId,
+    shaper: Arc<PeerShaper>,
     metrics: QuicTransportMetrics,
     router: Router,
     mut send_stream_guard: ResetStreamOnDrop,
@@ -136,7 +139,22 @@ async fn handle_bi_stream(
     // We can ignore the errors because if both peers follow the protocol an errors will only occur
     // if the other peer has closed the connection. In this case `accept_bi` in the peer event
     // loop will close this connection.
+    // Only routes matched by the router are used as shaping keys and metric labels since
+    // the peer is free to send requests for arbitrary paths.
+    let matched_path = response.extensions().get::<MatchedPath>().cloned();
+    let route = matched_path
+        .as_ref()
+        .map_or(UNMATCHED_HANDLER, |path| path.as_str());
     let response_bytes = to_response_bytes(response).await?;
+    let throttled = shaper
+        .acquire(route, MessagePriority::Low, response_bytes.len() as u64)
+        .await;
+    if !throttled.is_zero() {
+        metrics
+            .request_handle_throttled_bytes_total
+            .with_label_values(&[route])
+            .inc_by(response_bytes.len() as u64);
+    }
     send_stream
         .write_all(&response_bytes)
         .await
@@ -0,0 +1,206 @@
+//! Token bucket based shaping of the outgoing traffic of a connection.
+//!
+//! Each connection owns a [`PeerShaper`] that is shared by the connection handle (outgoing
+//! requests) and the request handler (outgoing responses). Before a message is written to a
+//! stream the shaper charges its size to
+//!     - the per-peer bucket, which limits the total bandwidth used towards the peer, and
+//!     - the bucket of the route (URI path) of the message, if one is configured.
+//!
+//! Messages are never dropped, instead the writer waits until the buckets are refilled.
+//! High priority messages are charged to the per-peer bucket but never wait for it. This way
+//! bulk traffic, e.g. state sync chunks, backs off when consensus artifacts are sent over a
+//! constrained link, while the consensus artifacts themselves are only limited by the bucket
+//! of their own route.
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use ic_config::transport::{TokenBucketConfig, TrafficShapingConfig};
+use tokio::time::Instant;
+
+use crate::MessagePriority;
+
+#[derive(Debug)]
+struct TokenBucket {
+    config: TokenBucketConfig,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    /// Available tokens. Negative if more bytes were charged than were available.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(config: TokenBucketConfig, now: Instant) -> Self {
+        Self {
+            config,
+            state: Mutex::new(BucketState {
+                tokens: config.burst_bytes as f64,
+                last_refill: now,
+            }),
+        }
+    }
+
+    /// Charges `bytes` to the bucket and returns how long the caller needs to wait until the
+    /// charged bytes are covered by the refill.
+    fn charge(&self, bytes: u64, now: Instant) -> Duration {
+        let rate = self.config.bytes_per_second.get() as f64;
+        let mut state = self.state.lock().unwrap();
+        let elapsed = now.saturating_duration_since(state.last_refill);
+        state.tokens =
+            (state.tokens + elapsed.as_secs_f64() * rate).min(self.config.burst_bytes as f64);
+        state.last_refill = now;
+        state.tokens -= bytes as f64;
+
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / rate)
+        }
+    }
+}
+
+/// Shapes the outgoing traffic of a single connection.
+#[derive(Debug)]
+pub(crate) struct PeerShaper {
+    per_peer: Option<TokenBucket>,
+    per_route: HashMap<String, TokenBucket>,
+}
+
+impl PeerShaper {
+    pub(crate) fn new(config: &TrafficShapingConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            per_peer: config.per_peer.map(|c| TokenBucket::new(c, now)),
+            per_route: config
+                .per_route
+                .iter()
+                .map(|(route, c)| (route.clone(), TokenBucket::new(*c, now)))
+                .collect(),
+        }
+    }
+
+    /// Charges a message of `bytes` on `route` and returns the time the message needs to be
+    /// held back.
+    fn reserve(
+        &self,
+        route: &str,
+        priority: MessagePriority,
+        bytes: u64,
+        now: Instant,
+    ) -> Duration {
+        let route_wait = self
+            .per_route
+            .get(route)
+            .map(|bucket| bucket.charge(bytes, now))
+            .unwrap_or_default();
+        let peer_wait = self
+            .per_peer
+            .as_ref()
+            .map(|bucket| bucket.charge(bytes, now))
+            .unwrap_or_default();
+        match priority {
+            MessagePriority::High => route_wait,
+            MessagePriority::Low => route_wait.max(peer_wait),
+        }
+    }
+
+    /// Waits until a message of `bytes` on `route` can be sent and returns the time waited.
+    ///
+    /// Note: The method is cancel-safe. The message stays charged if the future is dropped.
+    pub(crate) async fn acquire(
+        &self,
+        route: &str,
+        priority: MessagePriority,
+        bytes: u64,
+    ) -> Duration {
+        let wait = self.reserve(route, priority, bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        wait
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::BTreeMap, num::NonZeroU64};
+
+    const CHUNK_ROUTE: &str = "/state-sync/chunk";
+    const ARTIFACT_ROUTE: &str = "/consensus/update";
+
+    fn bucket(bytes_per_second: u64, burst_bytes: u64) -> TokenBucketConfig {
+        TokenBucketConfig {
+            bytes_per_second: NonZeroU64::new(bytes_per_second).unwrap(),
+            burst_bytes,
+        }
+    }
+
+    #[test]
+    fn token_bucket_refills_up_to_burst() {
+        let now = Instant::now();
+        let bucket = TokenBucket::new(bucket(1000, 500), now);
+
+        assert_eq!(bucket.charge(500, now), Duration::ZERO);
+        // The bucket is empty, so the next message waits for its own size.
+        assert_eq!(bucket.charge(250, now), Duration::from_millis(250));
+        // The debt of 250 bytes is paid after 250ms.
+        assert_eq!(
+            bucket.charge(100, now + Duration::from_millis(350)),
+            Duration::ZERO
+        );
+        // After a long idle period the bucket holds at most `burst_bytes`.
+        let later = now + Duration::from_secs(10);
+        assert_eq!(bucket.charge(500, later), Duration::ZERO);
+        assert_eq!(bucket.charge(500, later), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn unconfigured_routes_are_not_shaped() {
+        let shaper = PeerShaper::new(&TrafficShapingConfig::default());
+        let now = Instant::now();
+        assert_eq!(
+            shaper.reserve(CHUNK_ROUTE, MessagePriority::Low, u64::MAX, now),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn high_priority_is_charged_but_does_not_wait_for_peer_bucket() {
+        let shaper = PeerShaper::new(&TrafficShapingConfig {
+            per_peer: Some(bucket(1000, 1000)),
+            per_route: BTreeMap::new(),
+        });
+        let now = Instant::now();
+
+        assert_eq!(
+            shaper.reserve(ARTIFACT_ROUTE, MessagePriority::High, 2000, now),
+            Duration::ZERO
+        );
+        // Low priority traffic pays for the bandwidth used by high priority traffic.
+        assert_eq!(
+            shaper.reserve(CHUNK_ROUTE, MessagePriority::Low, 1000, now),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn route_bucket_applies_to_all_priorities() {
+        let shaper = PeerShaper::new(&TrafficShapingConfig {
+            per_peer: None,
+            per_route: BTreeMap::from([(CHUNK_ROUTE.to_string(), bucket(100, 100))]),
+        });
+        let now = Instant::now();
+
+        assert_eq!(
+            shaper.reserve(CHUNK_ROUTE, MessagePriority::High, 200, now),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            shaper.reserve(ARTIFACT_ROUTE, MessagePriority::Low, 200, now),
+            Duration::ZERO
+        );
+    }
+}
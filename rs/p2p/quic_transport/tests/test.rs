@@ -111,6 +111,7 @@ fn test_graceful_shutdown() {
             topology_watcher.clone(),
             create_udp_socket(rt.handle(), socket_1),
             ConnectivityChecker::router(),
+            Default::default(),
         ));
 
         let mut transport_2 = Arc::new(QuicTransport::start(
@@ -123,6 +124,7 @@ fn test_graceful_shutdown() {
             topology_watcher,
             create_udp_socket(rt.handle(), socket_2),
             ConnectivityChecker::router(),
+            Default::default(),
         ));
 
         registry_handler.add_node(
@@ -188,6 +190,7 @@ fn test_real_socket() {
             topology_watcher.clone(),
             create_udp_socket(rt.handle(), socket_1),
             ConnectivityChecker::router(),
+            Default::default(),
         ));
 
         let transport_2 = Arc::new(QuicTransport::start(
@@ -200,6 +203,7 @@ fn test_real_socket() {
             topology_watcher,
             create_udp_socket(rt.handle(), socket_2),
             ConnectivityChecker::router(),
+            Default::default(),
         ));
 
         registry_handler.add_node(
@@ -257,6 +261,7 @@ fn test_real_socket_large_msg() {
             topology_watcher.clone(),
             create_udp_socket(rt.handle(), socket_1),
             ConnectivityChecker::router(),
+            Default::default(),
         ));
 
         let transport_2 = Arc::new(QuicTransport::start(
@@ -269,6 +274,7 @@ fn test_real_socket_large_msg() {
             topology_watcher,
             create_udp_socket(rt.handle(), socket_2),
             ConnectivityChecker::router(),
+            Default::default(),
         ));
 
         registry_handler.add_node(
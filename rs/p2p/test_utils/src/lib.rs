@@ -214,6 +214,7 @@ pub fn fully_connected_localhost_subnet(
             topology_watcher.clone(),
             create_udp_socket(rt, socket),
             router,
+            Default::default(),
         )) as Arc<_>;
         registry_handler.add_node(
             RegistryVersion::from(i as u64 + 1),
@@ -411,6 +411,7 @@ pub fn add_transport_to_sim<F>(
                 topology_watcher_clone.clone(),
                 Arc::new(custom_udp),
                 router.unwrap_or_default(),
+                Default::default(),
             ));
 
             if let Some((_, con_manager)) = con {
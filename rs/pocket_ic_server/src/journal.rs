@@ -0,0 +1,336 @@
+//! Record and replay of mutating operations on a PocketIC instance.
+//!
+//! A journal is a file in the JSON Lines format. Its first line is a [`JournalHeader`]
+//! holding the configuration of the journaled instance and every subsequent line is a
+//! [`JournalEntry`] holding a mutating operation together with the state label of the
+//! instance after that operation has been executed.
+//!
+//! The state label stored in a journal is a fingerprint of the latest certified state
+//! of every subnet (and not the state label used to identify states in the graph of
+//! computations which is only unique within a single server). Hence, replaying a journal
+//! against a fresh instance with the same configuration must yield the same state labels
+//! and the first operation whose state label diverges is reported.
+//!
+//! Only operations performed through the PocketIC REST API are journaled:
+//! requests to the IC HTTP interface of an instance (e.g., via its HTTP gateway)
+//! and canister HTTP outcalls processed in live mode are not journaled.
+
+use crate::pocket_ic::{
+    AddCycles, AdvanceTimeAndTick, AwaitIngressMessage, MockCanisterHttp, PocketIc,
+    SetCertifiedTime, SetStableMemory, SetTime, SetXNetFaults, SubmitIngressMessage, Tick,
+};
+use crate::state_api::state::OpOut;
+use crate::{OpId, Operation};
+use pocket_ic::common::rest::{InstanceConfig, JournalDivergence};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// A mutating operation that is recorded in a journal.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum JournaledOperation {
+    SetTime(SetTime),
+    SetCertifiedTime(SetCertifiedTime),
+    Tick(Tick),
+    AdvanceTimeAndTick(AdvanceTimeAndTick),
+    SubmitIngressMessage(SubmitIngressMessage),
+    AwaitIngressMessage(AwaitIngressMessage),
+    MockCanisterHttp(MockCanisterHttp),
+    SetStableMemory(SetStableMemory),
+    AddCycles(AddCycles),
+    SetXNetFaults(SetXNetFaults),
+}
+
+impl JournaledOperation {
+    fn operation(&self) -> &dyn Operation {
+        match self {
+            JournaledOperation::SetTime(op) => op,
+            JournaledOperation::SetCertifiedTime(op) => op,
+            JournaledOperation::Tick(op) => op,
+            JournaledOperation::AdvanceTimeAndTick(op) => op,
+            JournaledOperation::SubmitIngressMessage(op) => op,
+            JournaledOperation::AwaitIngressMessage(op) => op,
+            JournaledOperation::MockCanisterHttp(op) => op,
+            JournaledOperation::SetStableMemory(op) => op,
+            JournaledOperation::AddCycles(op) => op,
+            JournaledOperation::SetXNetFaults(op) => op,
+        }
+    }
+
+    pub fn compute(&self, pocket_ic: &mut PocketIc) -> OpOut {
+        self.operation().compute(pocket_ic)
+    }
+
+    pub fn id(&self) -> OpId {
+        self.operation().id()
+    }
+}
+
+/// The first line of a journal.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalHeader {
+    /// The configuration of the journaled instance.
+    pub instance_config: InstanceConfig,
+}
+
+/// A single journaled operation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalEntry {
+    pub operation: JournaledOperation,
+    /// The state label of the instance after executing the operation.
+    pub state_label: String,
+}
+
+/// A journal that is being recorded.
+pub struct Journal {
+    writer: BufWriter<File>,
+    // Set if writing to the journal has failed. No further entries are appended
+    // to an incomplete journal so that it never skips an operation.
+    incomplete: bool,
+}
+
+impl Journal {
+    /// Creates a new journal at the given path (truncating an existing file) and writes its header.
+    pub fn create(path: &Path, header: &JournalHeader) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create journal {}: {}", path.display(), e))?;
+        let mut journal = Self {
+            writer: BufWriter::new(file),
+            incomplete: false,
+        };
+        journal.write_line(header)?;
+        Ok(journal)
+    }
+
+    /// Appends an entry to the journal.
+    /// The entry is flushed so that the journal is complete even if the server crashes.
+    /// Fails if this or a previous entry could not be written.
+    pub fn append(&mut self, entry: &JournalEntry) -> Result<(), String> {
+        if self.incomplete {
+            return Err(
+                "The journal is incomplete since a previous entry could not be written".to_string(),
+            );
+        }
+        let result = self.write_line(entry);
+        self.incomplete = result.is_err();
+        result
+    }
+
+    fn write_line<T: Serialize>(&mut self, line: &T) -> Result<(), String> {
+        serde_json::to_writer(&mut self.writer, line)
+            .map_err(|e| format!("Failed to serialize journal line: {}", e))?;
+        self.writer
+            .write_all(b"\n")
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Failed to write journal: {}", e))
+    }
+}
+
+/// Reads the header and all entries of the journal at the given path.
+pub fn read_journal(path: &Path) -> Result<(JournalHeader, Vec<JournalEntry>), String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open journal {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+    let header = match lines.next() {
+        Some(line) => {
+            let line = line.map_err(|e| format!("Failed to read journal: {}", e))?;
+            serde_json::from_str(&line)
+                .map_err(|e| format!("Failed to parse journal header: {}", e))?
+        }
+        None => return Err("The journal is empty".to_string()),
+    };
+    let mut entries = vec![];
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| format!("Failed to read journal: {}", e))?;
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| format!("Failed to parse journal entry {}: {}", index, e))?;
+        entries.push(entry);
+    }
+    Ok((header, entries))
+}
+
+/// Re-executes the journaled operations against the given instance
+/// and returns the first operation whose resulting state label
+/// diverges from the journaled state label (if any).
+/// No further operations are executed after a divergence.
+pub fn replay(pocket_ic: &mut PocketIc, entries: &[JournalEntry]) -> Option<JournalDivergence> {
+    for (index, entry) in entries.iter().enumerate() {
+        entry.operation.compute(pocket_ic);
+        pocket_ic.bump_state_label();
+        let state_label = pocket_ic.state_fingerprint();
+        if state_label != entry.state_label {
+            return Some(JournalDivergence {
+                index,
+                operation_id: entry.operation.id().0,
+                expected_state_label: entry.state_label.clone(),
+                actual_state_label: state_label,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pocket_ic::common::rest::{ExtendedSubnetConfigSet, SubnetSpec, TickConfigs};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::runtime::Runtime;
+
+    fn instance_config() -> InstanceConfig {
+        InstanceConfig {
+            subnet_config_set: ExtendedSubnetConfigSet {
+                application: vec![SubnetSpec::default()],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn pocket_ic(runtime: Arc<Runtime>, seed: u64) -> PocketIc {
+        PocketIc::try_new(
+            runtime,
+            seed,
+            instance_config().subnet_config_set,
+            None,
+            false,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+    }
+
+    fn operations() -> Vec<JournaledOperation> {
+        vec![
+            JournaledOperation::Tick(Tick {
+                configs: TickConfigs::default(),
+            }),
+            JournaledOperation::AdvanceTimeAndTick(AdvanceTimeAndTick(Duration::from_secs(1))),
+            JournaledOperation::Tick(Tick {
+                configs: TickConfigs::default(),
+            }),
+        ]
+    }
+
+    /// Executes the given operations on the given instance and journals them to the given file.
+    fn record(pocket_ic: &mut PocketIc, path: &Path, operations: Vec<JournaledOperation>) {
+        let header = JournalHeader {
+            instance_config: instance_config(),
+        };
+        pocket_ic.set_journal(Journal::create(path, &header).unwrap());
+        for operation in operations {
+            operation.compute(pocket_ic);
+            pocket_ic.bump_state_label();
+            pocket_ic.journal_operation(operation).unwrap();
+        }
+    }
+
+    #[test]
+    fn read_journal_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        let header = JournalHeader {
+            instance_config: instance_config(),
+        };
+        let mut journal = Journal::create(&path, &header).unwrap();
+        for (index, operation) in operations().into_iter().enumerate() {
+            let entry = JournalEntry {
+                operation,
+                state_label: format!("state{}", index),
+            };
+            journal.append(&entry).unwrap();
+        }
+        drop(journal);
+
+        let (read_header, entries) = read_journal(&path).unwrap();
+        assert_eq!(read_header.instance_config, header.instance_config);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.operation.id().0, entry.state_label.clone()))
+                .collect::<Vec<_>>(),
+            operations()
+                .iter()
+                .enumerate()
+                .map(|(index, operation)| (operation.id().0, format!("state{}", index)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn read_invalid_journal_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(
+            read_journal(&path).unwrap_err(),
+            "The journal is empty".to_string()
+        );
+
+        let header = serde_json::to_string(&JournalHeader {
+            instance_config: instance_config(),
+        })
+        .unwrap();
+        // A partially written entry.
+        std::fs::write(&path, format!("{}\n{{\"operation\":", header)).unwrap();
+        assert!(read_journal(&path)
+            .unwrap_err()
+            .starts_with("Failed to parse journal entry 0"));
+    }
+
+    #[tokio::test]
+    async fn replay_journal() {
+        let runtime = Arc::new(Runtime::new().unwrap());
+        tokio::task::spawn_blocking(move || {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("journal.jsonl");
+            let mut journaled = pocket_ic(runtime.clone(), 0);
+            record(&mut journaled, &path, operations());
+            let (_, entries) = read_journal(&path).unwrap();
+            assert_eq!(entries.len(), 3);
+            assert_eq!(
+                entries.last().unwrap().state_label,
+                journaled.state_fingerprint()
+            );
+
+            // Replaying against a fresh instance (with another seed) reproduces the state labels.
+            let mut replayed = pocket_ic(runtime.clone(), 1);
+            assert_eq!(replay(&mut replayed, &entries), None);
+            assert_eq!(replayed.state_fingerprint(), journaled.state_fingerprint());
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn replay_reports_first_divergence() {
+        let runtime = Arc::new(Runtime::new().unwrap());
+        tokio::task::spawn_blocking(move || {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("journal.jsonl");
+            let mut journaled = pocket_ic(runtime.clone(), 0);
+            record(&mut journaled, &path, operations());
+            let (_, mut entries) = read_journal(&path).unwrap();
+            let actual_state_label = entries[1].state_label.clone();
+            entries[1].state_label = "diverged".to_string();
+
+            let mut replayed = pocket_ic(runtime.clone(), 1);
+            assert_eq!(
+                replay(&mut replayed, &entries),
+                Some(JournalDivergence {
+                    index: 1,
+                    operation_id: entries[1].operation.id().0,
+                    expected_state_label: "diverged".to_string(),
+                    actual_state_label: actual_state_label.clone(),
+                })
+            );
+            // No operation is executed after the divergence.
+            assert_eq!(replayed.state_fingerprint(), actual_state_label);
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -34,9 +34,11 @@
 //! The start state is a dedicated state that always exists independent of which computations have
 //! been carried out. A state which has no outcoming computations is called a leaf.
 
+pub mod journal;
 pub mod pocket_ic;
 pub mod state_api;
 
+use crate::journal::JournaledOperation;
 use crate::state_api::state::OpOut;
 use ::pocket_ic::common::rest::{BinaryBlob, BlobId, RawSubnetBlockmaker};
 use async_trait::async_trait;
@@ -59,6 +61,12 @@ pub trait Operation {
 
     /// Returns the unique identifier of this operation.
     fn id(&self) -> OpId;
+
+    /// Returns this operation as it should be recorded in the journal of an instance
+    /// or `None` if this operation does not mutate the instance and thus is not journaled.
+    fn journal(&self) -> Option<JournaledOperation> {
+        None
+    }
 }
 
 /// Uniquely identifies an operation.
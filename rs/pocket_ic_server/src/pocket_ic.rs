@@ -1,3 +1,4 @@
+use crate::journal::{Journal, JournalEntry, JournaledOperation};
 use crate::state_api::state::{HasStateLabel, OpOut, PocketIcError, StateLabel};
 use crate::{BlobStore, OpId, Operation, SubnetBlockmaker};
 use askama::Template;
@@ -51,8 +52,10 @@ use ic_registry_routing_table::{
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
-    add_global_registry_records, add_initial_registry_records, FakeVerifier, StateMachine,
-    StateMachineBuilder, StateMachineConfig, StateMachineStateDir, SubmitIngressError, Subnets,
+    add_global_registry_records, add_initial_registry_records, FakeVerifier,
+    MessageProfile as StateMachineMessageProfile, StateMachine, StateMachineBuilder,
+    StateMachineConfig, StateMachineStateDir, SubmitIngressError, Subnets,
+    XNetFault as StateMachineXNetFault, XNetFaultKind as StateMachineXNetFaultKind,
 };
 use ic_state_manager::StateManagerImpl;
 use ic_types::batch::BlockmakerMetrics;
@@ -78,9 +81,10 @@ use ic_validator_ingress_message::StandaloneIngressSigVerifier;
 use itertools::Itertools;
 use pocket_ic::common::rest::{
     self, BinaryBlob, BlobCompression, CanisterHttpHeader, CanisterHttpMethod, CanisterHttpRequest,
-    CanisterHttpResponse, ExtendedSubnetConfigSet, MockCanisterHttpResponse, RawAddCycles,
-    RawCanisterCall, RawCanisterId, RawEffectivePrincipal, RawMessageId, RawSetStableMemory,
-    SubnetInstructionConfig, SubnetKind, TickConfigs, Topology,
+    CanisterHttpResponse, ExtendedSubnetConfigSet, MessageCost, MessageProfile, MessageProfiles,
+    MethodProfile, MockCanisterHttpResponse, RawAddCycles, RawCanisterCall, RawCanisterId,
+    RawEffectivePrincipal, RawMessageId, RawSetStableMemory, RawTime, SubnetInstructionConfig,
+    SubnetKind, TickConfigs, Topology, XNetFault, XNetFaultKind, XNetFaultTarget,
 };
 use pocket_ic::{copy_dir, ErrorCode, RejectCode, RejectResponse};
 use serde::{Deserialize, Serialize};
@@ -454,6 +458,8 @@ struct PocketIcSubnets {
     nonmainnet_features: bool,
     log_level: Option<Level>,
     bitcoind_addr: Option<Vec<SocketAddr>>,
+    message_profiling: bool,
+    wasm_profile_dir: Option<PathBuf>,
     _bitcoin_adapter_parts: Option<BitcoinAdapterParts>,
 }
 
@@ -469,6 +475,7 @@ impl PocketIcSubnets {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoin_adapter_uds_path: Option<PathBuf>,
+        wasm_profile_dir: Option<PathBuf>,
     ) -> StateMachineBuilder {
         let subnet_type = conv_type(subnet_kind);
         let subnet_size = subnet_size(subnet_kind);
@@ -502,6 +509,14 @@ impl PocketIcSubnets {
             .embedders_config
             .feature_flags
             .rate_limiting_of_debug_prints = FlagStatus::Disabled;
+        // profile canister code if requested
+        if let Some(wasm_profile_dir) = wasm_profile_dir {
+            hypervisor_config
+                .embedders_config
+                .feature_flags
+                .wasm_profiling = FlagStatus::Enabled;
+            hypervisor_config.embedders_config.wasm_profiling_output_dir = Some(wasm_profile_dir);
+        }
         let state_machine_config = StateMachineConfig::new(subnet_config, hypervisor_config);
         let t = time
             .duration_since(SystemTime::UNIX_EPOCH)
@@ -527,6 +542,7 @@ impl PocketIcSubnets {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoind_addr: Option<Vec<SocketAddr>>,
+        wasm_profile_dir: Option<PathBuf>,
     ) -> Self {
         let registry_data_provider = Arc::new(ProtoRegistryDataProvider::new());
         add_initial_registry_records(registry_data_provider.clone());
@@ -543,6 +559,8 @@ impl PocketIcSubnets {
             nonmainnet_features,
             log_level,
             bitcoind_addr,
+            message_profiling: false,
+            wasm_profile_dir,
             _bitcoin_adapter_parts: None,
         }
     }
@@ -633,6 +651,7 @@ impl PocketIcSubnets {
             self.nonmainnet_features,
             self.log_level,
             bitcoin_adapter_uds_path.clone(),
+            self.wasm_profile_dir.clone(),
         );
 
         if let Some(subnet_id) = subnet_id {
@@ -674,6 +693,7 @@ impl PocketIcSubnets {
         }
 
         let sm = builder.build_with_subnets(self.subnets.clone());
+        sm.set_message_profiling(self.message_profiling);
 
         // The actual subnet ID (matching the subnet ID in the input `SubnetConfigInfo`
         // if one was provided).
@@ -788,6 +808,11 @@ pub struct PocketIc {
     state_label: StateLabel,
     subnets: PocketIcSubnets,
     topology: TopologyInternal,
+    // The state directory of a forked instance (deleted when the instance is dropped).
+    // This field must be dropped after all `StateMachine`s of the instance.
+    _fork_state_dir: Option<TempDir>,
+    // The journal of mutating operations on this instance (if journaling is enabled).
+    journal: Option<Journal>,
 }
 
 impl Drop for PocketIc {
@@ -800,26 +825,7 @@ impl Drop for PocketIc {
             for subnet in &subnets {
                 subnet.state_machine.await_state_hash();
             }
-            let subnet_configs = self
-                .topology
-                .subnet_configs
-                .iter()
-                .map(|(seed, config)| {
-                    let time = self.subnets.get(config.subnet_id).unwrap().time();
-                    (
-                        hex::encode(seed),
-                        RawSubnetConfigInternal {
-                            subnet_config: config.clone(),
-                            time,
-                        },
-                    )
-                })
-                .collect();
-            let raw_topology: RawTopologyInternal = RawTopologyInternal {
-                subnet_configs,
-                default_effective_canister_id: self.topology.default_effective_canister_id.into(),
-            };
-            let topology_json = serde_json::to_string(&raw_topology).unwrap();
+            let topology_json = serde_json::to_string(&self.raw_topology()).unwrap();
             let mut topology_file = File::create(state_dir.join("topology.json")).unwrap();
             topology_file.write_all(topology_json.as_bytes()).unwrap();
         }
@@ -896,6 +902,7 @@ impl PocketIc {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoind_addr: Option<Vec<SocketAddr>>,
+        wasm_profile_dir: Option<PathBuf>,
     ) -> Result<Self, String> {
         let topology: Option<RawTopologyInternal> = if let Some(ref state_dir) = state_dir {
             let topology_file_path = state_dir.join("topology.json");
@@ -1062,6 +1069,7 @@ impl PocketIc {
             nonmainnet_features,
             log_level,
             bitcoind_addr,
+            wasm_profile_dir,
         );
         let mut subnet_configs = BTreeMap::new();
         for subnet_config_info in subnet_config_info.into_iter() {
@@ -1097,9 +1105,131 @@ impl PocketIc {
             state_label,
             subnets,
             topology,
+            _fork_state_dir: None,
+            journal: None,
         })
     }
 
+    /// Creates an independent instance starting from the current state of this instance.
+    /// The latest state of every subnet is checkpointed (at its current height, so this
+    /// instance is left unchanged) and the checkpoint files are shared copy-on-write with
+    /// the new instance. Ingress messages that have been submitted,
+    /// but not yet executed, are submitted to the new instance, too.
+    pub(crate) fn fork(&self, seed: u64) -> Result<Self, String> {
+        let fork_state_dir =
+            TempDir::new().map_err(|e| format!("Failed to create state directory: {}", e))?;
+        for (subnet_seed, config) in &self.topology.subnet_configs {
+            let state_machine = self.subnets.get(config.subnet_id).unwrap();
+            state_machine
+                .fork_state_dir(&fork_state_dir.path().join(hex::encode(subnet_seed)))
+                .map_err(|e| {
+                    format!(
+                        "Failed to fork the state of subnet {}: {}",
+                        config.subnet_id, e
+                    )
+                })?;
+        }
+        let topology_json = serde_json::to_string(&self.raw_topology()).unwrap();
+        std::fs::write(fork_state_dir.path().join("topology.json"), topology_json)
+            .map_err(|e| format!("Failed to write topology: {}", e))?;
+
+        let mut fork = Self::try_new(
+            self.runtime.clone(),
+            seed,
+            ExtendedSubnetConfigSet::default(),
+            Some(fork_state_dir.path().to_path_buf()),
+            self.subnets.nonmainnet_features,
+            self.subnets.log_level,
+            self.subnets.bitcoind_addr.clone(),
+            self.subnets.wasm_profile_dir.clone(),
+        )?;
+        // The state of the fork is not persisted when the fork is dropped.
+        fork.subnets.state_dir = None;
+        fork._fork_state_dir = Some(fork_state_dir);
+        fork.set_message_profiling(self.subnets.message_profiling);
+
+        for subnet in self.subnets.get_all() {
+            let fork_state_machine = fork.subnets.get(subnet.get_subnet_id()).unwrap();
+            for msg in subnet.state_machine.pending_ingress_messages() {
+                fork_state_machine.push_signed_ingress(msg);
+            }
+        }
+
+        Ok(fork)
+    }
+
+    fn raw_topology(&self) -> RawTopologyInternal {
+        let subnet_configs = self
+            .topology
+            .subnet_configs
+            .iter()
+            .map(|(seed, config)| {
+                let time = self.subnets.get(config.subnet_id).unwrap().time();
+                (
+                    hex::encode(seed),
+                    RawSubnetConfigInternal {
+                        subnet_config: config.clone(),
+                        time,
+                    },
+                )
+            })
+            .collect();
+        RawTopologyInternal {
+            subnet_configs,
+            default_effective_canister_id: self.topology.default_effective_canister_id.into(),
+        }
+    }
+
+    /// Enables or disables recording the cost of every completed canister message
+    /// on all (current and future) subnets of this instance.
+    pub(crate) fn set_message_profiling(&mut self, enabled: bool) {
+        self.subnets.message_profiling = enabled;
+        for subnet in self.subnets.get_all() {
+            subnet.state_machine.set_message_profiling(enabled);
+        }
+    }
+
+    /// Enables journaling of mutating operations on this instance.
+    pub(crate) fn set_journal(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    /// Appends the given operation that has just been executed on this instance
+    /// to the journal of this instance (if journaling is enabled).
+    pub(crate) fn journal_operation(
+        &mut self,
+        operation: JournaledOperation,
+    ) -> Result<(), String> {
+        if self.journal.is_none() {
+            return Ok(());
+        }
+        let entry = JournalEntry {
+            operation,
+            state_label: self.state_fingerprint(),
+        };
+        self.journal.as_mut().unwrap().append(&entry)
+    }
+
+    /// Returns a fingerprint of the latest certified state of all subnets.
+    /// In contrast to the state label, the fingerprint only depends on the
+    /// operations executed on this instance and thus it is reproducible
+    /// across instances and servers.
+    pub(crate) fn state_fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for subnet in self.subnets.get_all() {
+            hasher.write(subnet.get_subnet_id().get_ref().as_slice());
+            if let Some((height, hash)) = subnet
+                .state_machine
+                .state_manager
+                .latest_state_certification_hash()
+            {
+                hasher.write(&height.get().to_be_bytes());
+                hasher.write(&hash.0);
+            }
+        }
+        hex::encode(hasher.finish())
+    }
+
     pub(crate) fn bump_state_label(&mut self) {
         self.state_label.bump();
     }
@@ -1295,7 +1425,7 @@ pub struct ConversionError {
     message: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SetTime {
     pub time: Time,
 }
@@ -1332,9 +1462,13 @@ impl Operation for SetTime {
     fn id(&self) -> OpId {
         OpId(format!("set_time_{}", self.time))
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::SetTime(self.clone()))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SetCertifiedTime {
     pub time: Time,
 }
@@ -1347,6 +1481,10 @@ impl Operation for SetCertifiedTime {
     fn id(&self) -> OpId {
         OpId(format!("set_certified_time_{}", self.time))
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::SetCertifiedTime(self.clone()))
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -1381,6 +1519,84 @@ impl Operation for GetTime {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct GetMessageProfiles;
+
+impl GetMessageProfiles {
+    fn message_profile(profile: StateMachineMessageProfile) -> MessageProfile {
+        MessageProfile {
+            canister_id: profile.canister_id.get().0.into(),
+            method_name: profile.method_name,
+            ingress_message_id: profile
+                .ingress_message_id
+                .map(|message_id| hex::encode(message_id.as_bytes())),
+            time: RawTime {
+                nanos_since_epoch: profile.time.as_nanos_since_unix_epoch(),
+            },
+            cost: MessageCost {
+                instructions: profile.instructions.get(),
+                cycles_by_use_case: profile
+                    .cycles_by_use_case
+                    .into_iter()
+                    .map(|(use_case, cycles)| (use_case.as_str().to_string(), cycles.get()))
+                    .collect(),
+                heap_dirty_pages: profile.heap_dirty_pages,
+                stable_dirty_pages: profile.stable_dirty_pages,
+                downstream_calls: profile.downstream_calls,
+            },
+        }
+    }
+}
+
+impl Operation for GetMessageProfiles {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        if !pic.subnets.message_profiling {
+            return OpOut::Error(PocketIcError::MessageProfilingDisabled);
+        }
+        let mut messages: Vec<_> = pic
+            .subnets
+            .get_all()
+            .into_iter()
+            .flat_map(|subnet| subnet.state_machine.message_profiles())
+            .map(Self::message_profile)
+            .collect();
+        messages.sort_by_key(|message| message.time.nanos_since_epoch);
+
+        let mut methods: BTreeMap<(RawCanisterId, String), MethodProfile> = BTreeMap::new();
+        for message in &messages {
+            let method = methods
+                .entry((message.canister_id.clone(), message.method_name.clone()))
+                .or_insert_with(|| MethodProfile {
+                    canister_id: message.canister_id.clone(),
+                    method_name: message.method_name.clone(),
+                    messages: 0,
+                    cost: MessageCost::default(),
+                });
+            method.messages += 1;
+            method.cost.instructions += message.cost.instructions;
+            for (use_case, cycles) in &message.cost.cycles_by_use_case {
+                *method
+                    .cost
+                    .cycles_by_use_case
+                    .entry(use_case.clone())
+                    .or_default() += cycles;
+            }
+            method.cost.heap_dirty_pages += message.cost.heap_dirty_pages;
+            method.cost.stable_dirty_pages += message.cost.stable_dirty_pages;
+            method.cost.downstream_calls += message.cost.downstream_calls;
+        }
+
+        OpOut::MessageProfiles(MessageProfiles {
+            messages,
+            methods: methods.into_values().collect(),
+        })
+    }
+
+    fn id(&self) -> OpId {
+        OpId("get_message_profiles".into())
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct GetCanisterHttp;
 
@@ -1676,7 +1892,7 @@ fn process_mock_canister_https_response(
     OpOut::NoOutput
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MockCanisterHttp {
     pub mock_canister_http_response: MockCanisterHttpResponse,
 }
@@ -1692,6 +1908,98 @@ impl Operation for MockCanisterHttp {
             self.mock_canister_http_response
         ))
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::MockCanisterHttp(self.clone()))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SetXNetFaults {
+    pub xnet_faults: Vec<XNetFault>,
+}
+
+impl Operation for SetXNetFaults {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        // Faults on a stream are injected into the destination subnet,
+        // faults on a subnet are injected into that subnet (for all its incoming streams)
+        // and into all other subnets (for their incoming streams from that subnet),
+        // and faults on a canister are injected into all subnets
+        // (inducting messages sent by or to that canister).
+        let mut faults_per_subnet: BTreeMap<SubnetId, Vec<StateMachineXNetFault>> = pic
+            .subnets
+            .get_all()
+            .iter()
+            .map(|subnet| (subnet.get_subnet_id(), vec![]))
+            .collect();
+        for xnet_fault in &self.xnet_faults {
+            let kind = match xnet_fault.kind {
+                XNetFaultKind::Drop => StateMachineXNetFaultKind::Drop,
+                XNetFaultKind::Delay { rounds } => StateMachineXNetFaultKind::Delay { rounds },
+                XNetFaultKind::Reject => StateMachineXNetFaultKind::Reject,
+                XNetFaultKind::Unavailable => StateMachineXNetFaultKind::Unavailable,
+            };
+            match xnet_fault.target {
+                XNetFaultTarget::Stream {
+                    source,
+                    destination,
+                } => {
+                    let source_subnet_id = SubnetId::new(PrincipalId(source));
+                    if pic.subnets.get(source_subnet_id).is_none() {
+                        return OpOut::Error(PocketIcError::SubnetNotFound(source));
+                    }
+                    let destination_subnet_id = SubnetId::new(PrincipalId(destination));
+                    let Some(faults) = faults_per_subnet.get_mut(&destination_subnet_id) else {
+                        return OpOut::Error(PocketIcError::SubnetNotFound(destination));
+                    };
+                    faults.push(StateMachineXNetFault {
+                        source: Some(source_subnet_id),
+                        canister: None,
+                        kind,
+                    });
+                }
+                XNetFaultTarget::Subnet(subnet) => {
+                    let subnet_id = SubnetId::new(PrincipalId(subnet));
+                    if !faults_per_subnet.contains_key(&subnet_id) {
+                        return OpOut::Error(PocketIcError::SubnetNotFound(subnet));
+                    }
+                    for (destination_subnet_id, faults) in faults_per_subnet.iter_mut() {
+                        faults.push(StateMachineXNetFault {
+                            source: (*destination_subnet_id != subnet_id).then_some(subnet_id),
+                            canister: None,
+                            kind,
+                        });
+                    }
+                }
+                XNetFaultTarget::Canister(canister_id) => {
+                    let canister_id =
+                        CanisterId::unchecked_from_principal(PrincipalId(canister_id));
+                    for faults in faults_per_subnet.values_mut() {
+                        faults.push(StateMachineXNetFault {
+                            source: None,
+                            canister: Some(canister_id),
+                            kind,
+                        });
+                    }
+                }
+            }
+        }
+        for subnet in pic.subnets.get_all() {
+            let faults = faults_per_subnet
+                .remove(&subnet.get_subnet_id())
+                .unwrap_or_default();
+            subnet.state_machine.set_xnet_faults(faults);
+        }
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("set_xnet_faults({:?})", self.xnet_faults))
+    }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::SetXNetFaults(self.clone()))
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -1713,7 +2021,7 @@ impl Operation for PubKey {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Tick {
     pub configs: TickConfigs,
 }
@@ -1795,9 +2103,13 @@ impl Operation for Tick {
     fn id(&self) -> OpId {
         OpId("tick".to_string())
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::Tick(self.clone()))
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 pub struct AdvanceTimeAndTick(pub Duration);
 
 impl Operation for AdvanceTimeAndTick {
@@ -1812,9 +2124,13 @@ impl Operation for AdvanceTimeAndTick {
     fn id(&self) -> OpId {
         OpId(format!("advance_time_and_tick({:?})", self.0))
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::AdvanceTimeAndTick(self.clone()))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SubmitIngressMessage(pub CanisterCall);
 
 impl Operation for SubmitIngressMessage {
@@ -1851,9 +2167,13 @@ impl Operation for SubmitIngressMessage {
         let call_id = self.0.id();
         OpId(format!("submit_update_{}", call_id.0))
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::SubmitIngressMessage(self.clone()))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MessageId {
     effective_principal: EffectivePrincipal,
     msg_id: OtherMessageId,
@@ -1883,7 +2203,7 @@ impl TryFrom<RawMessageId> for MessageId {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AwaitIngressMessage(pub MessageId);
 
 impl Operation for AwaitIngressMessage {
@@ -1929,6 +2249,10 @@ impl Operation for AwaitIngressMessage {
     fn id(&self) -> OpId {
         OpId(format!("await_update_{}", self.0.msg_id))
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::AwaitIngressMessage(self.clone()))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -2488,7 +2812,7 @@ impl From<EffectivePrincipal> for RawEffectivePrincipal {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CanisterCall {
     pub effective_principal: EffectivePrincipal,
     pub sender: PrincipalId,
@@ -2548,7 +2872,7 @@ impl CanisterCall {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub struct SetStableMemory {
     pub canister_id: CanisterId,
     pub data: Vec<u8>,
@@ -2625,6 +2949,10 @@ impl Operation for SetStableMemory {
         let hash = Digest(hasher.finish());
         OpId(format!("set_stable_memory({}_{})", self.canister_id, hash))
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::SetStableMemory(self.clone()))
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -2733,7 +3061,7 @@ impl Operation for GetSubnet {
 /// # Panics
 ///
 /// Panics if the canister does not exist.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AddCycles {
     canister_id: CanisterId,
     amount: u128,
@@ -2777,6 +3105,10 @@ impl Operation for AddCycles {
     fn id(&self) -> OpId {
         OpId(format!("add_cycles({},{})", self.canister_id, self.amount))
     }
+
+    fn journal(&self) -> Option<JournaledOperation> {
+        Some(JournaledOperation::AddCycles(self.clone()))
+    }
 }
 
 struct Digest([u8; 32]);
@@ -2972,6 +3304,7 @@ mod tests {
                 false,
                 None,
                 None,
+                None,
             )
             .unwrap();
             let mut pic1 = PocketIc::try_new(
@@ -2985,6 +3318,7 @@ mod tests {
                 false,
                 None,
                 None,
+                None,
             )
             .unwrap();
             assert_ne!(pic0.get_state_label(), pic1.get_state_label());
@@ -6,12 +6,13 @@
 /// deterministically update the PocketIc state machine.
 ///
 use super::state::{ApiState, OpOut, PocketIcError, StateLabel, UpdateReply};
+use crate::journal::{read_journal, replay, Journal, JournalHeader};
 use crate::pocket_ic::{
     AddCycles, AwaitIngressMessage, CallRequest, CallRequestVersion, CanisterReadStateRequest,
-    DashboardRequest, GetCanisterHttp, GetControllers, GetCyclesBalance, GetStableMemory,
-    GetSubnet, GetTime, GetTopology, IngressMessageStatus, MockCanisterHttp, PubKey, Query,
-    QueryRequest, SetCertifiedTime, SetStableMemory, SetTime, StatusRequest, SubmitIngressMessage,
-    SubnetReadStateRequest, Tick,
+    DashboardRequest, GetCanisterHttp, GetControllers, GetCyclesBalance, GetMessageProfiles,
+    GetStableMemory, GetSubnet, GetTime, GetTopology, IngressMessageStatus, MockCanisterHttp,
+    PubKey, Query, QueryRequest, SetCertifiedTime, SetStableMemory, SetTime, SetXNetFaults,
+    StatusRequest, SubmitIngressMessage, SubnetReadStateRequest, Tick,
 };
 use crate::{async_trait, pocket_ic::PocketIc, BlobStore, InstanceId, OpId, Operation};
 use aide::{
@@ -37,10 +38,11 @@ use ic_http_endpoints_public::cors_layer;
 use ic_types::{CanisterId, SubnetId};
 use pocket_ic::common::rest::{
     self, ApiResponse, AutoProgressConfig, ExtendedSubnetConfigSet, HttpGatewayConfig,
-    HttpGatewayDetails, InstanceConfig, MockCanisterHttpResponse, RawAddCycles, RawCanisterCall,
-    RawCanisterHttpRequest, RawCanisterId, RawCanisterResult, RawCycles, RawIngressStatusArgs,
-    RawMessageId, RawMockCanisterHttpResponse, RawPrincipalId, RawSetStableMemory, RawStableMemory,
-    RawSubnetId, RawTime, TickConfigs, Topology,
+    HttpGatewayDetails, InstanceConfig, MessageProfiles, MockCanisterHttpResponse, RawAddCycles,
+    RawCanisterCall, RawCanisterHttpRequest, RawCanisterId, RawCanisterResult, RawCycles,
+    RawIngressStatusArgs, RawMessageId, RawMockCanisterHttpResponse, RawPrincipalId,
+    RawReplayJournal, RawSetStableMemory, RawStableMemory, RawSubnetId, RawTime, RawXNetFault,
+    ReplayJournalResponse, TickConfigs, Topology,
 };
 use pocket_ic::RejectResponse;
 use serde::Serialize;
@@ -77,6 +79,7 @@ where
         .directory_route("/topology", get(handler_topology))
         .directory_route("/get_time", get(handler_get_time))
         .directory_route("/get_canister_http", get(handler_get_canister_http))
+        .directory_route("/get_message_profiles", get(handler_get_message_profiles))
         .directory_route("/get_controllers", post(handler_get_controllers))
         .directory_route("/get_cycles", post(handler_get_cycles))
         .directory_route("/get_stable_memory", post(handler_get_stable_memory))
@@ -105,6 +108,7 @@ where
         .directory_route("/set_stable_memory", post(handler_set_stable_memory))
         .directory_route("/tick", post(handler_tick))
         .directory_route("/mock_canister_http", post(handler_mock_canister_http))
+        .directory_route("/set_xnet_faults", post(handler_set_xnet_faults))
 }
 
 pub fn instance_api_v2_routes<S>() -> ApiRouter<S>
@@ -180,6 +184,14 @@ where
         // Deletes an instance.
         .directory_route("/{id}", delete(delete_instance))
         //
+        // Creates a new IC instance starting from the current state of an existing instance.
+        // Returns an InstanceId.
+        .api_route("/{id}/fork", post(fork_instance))
+        //
+        // Creates a new IC instance and re-executes the operations journaled in a file.
+        // Returns an InstanceId and the first operation whose resulting state label diverges.
+        .api_route("/replay", post(replay_journal))
+        //
         // All the read-only endpoints
         .nest("/{id}/read", instance_read_routes())
         //
@@ -488,6 +500,16 @@ impl TryFrom<OpOut> for Result<RawMessageId, RejectResponse> {
     }
 }
 
+impl TryFrom<OpOut> for MessageProfiles {
+    type Error = OpConversionError;
+    fn try_from(value: OpOut) -> Result<Self, Self::Error> {
+        match value {
+            OpOut::MessageProfiles(message_profiles) => Ok(message_profiles),
+            _ => Err(OpConversionError),
+        }
+    }
+}
+
 impl TryFrom<OpOut> for Vec<RawCanisterHttpRequest> {
     type Error = OpConversionError;
     fn try_from(value: OpOut) -> Result<Self, Self::Error> {
@@ -585,6 +607,17 @@ pub async fn handler_get_canister_http(
     (code, Json(response))
 }
 
+pub async fn handler_get_message_profiles(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+) -> (StatusCode, Json<ApiResponse<MessageProfiles>>) {
+    let timeout = timeout_or_default(headers);
+    let op = GetMessageProfiles {};
+    let (code, response) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
 pub async fn handler_mock_canister_http(
     State(AppState { api_state, .. }): State<AppState>,
     headers: HeaderMap,
@@ -603,6 +636,23 @@ pub async fn handler_mock_canister_http(
     (code, Json(response))
 }
 
+pub async fn handler_set_xnet_faults(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+    axum::extract::Json(raw_xnet_faults): axum::extract::Json<Vec<RawXNetFault>>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let timeout = timeout_or_default(headers);
+    let op = SetXNetFaults {
+        xnet_faults: raw_xnet_faults
+            .into_iter()
+            .map(|fault| fault.into())
+            .collect(),
+    };
+    let (code, response) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
 pub async fn handler_get_controllers(
     State(AppState { api_state, .. }): State<AppState>,
     Path(instance_id): Path<InstanceId>,
@@ -897,6 +947,9 @@ async fn op_out_to_response(op_out: OpOut) -> Response {
             )),
         )
             .into_response(),
+        OpOut::MessageProfiles(message_profiles) => {
+            (StatusCode::OK, Json(ApiResponse::Success(message_profiles))).into_response()
+        }
         OpOut::RawResponse(fut) => {
             let (status, headers, bytes) = fut.await;
             let code = StatusCode::from_u16(status).unwrap();
@@ -1146,6 +1199,14 @@ pub async fn create_instance(
     }): State<AppState>,
     extract::Json(instance_config): extract::Json<InstanceConfig>,
 ) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    // The journal header contains the instance config so that the journal can be replayed
+    // against a fresh instance with the same config.
+    let journal_header = JournalHeader {
+        instance_config: InstanceConfig {
+            journal_file: None,
+            ..instance_config.clone()
+        },
+    };
     let subnet_configs = instance_config.subnet_config_set;
 
     let skip_validate_subnet_configs = instance_config
@@ -1153,6 +1214,14 @@ pub async fn create_instance(
         .as_ref()
         .map(|state_dir| File::open(state_dir.clone().join("topology.json")).is_ok())
         .unwrap_or_default();
+    if skip_validate_subnet_configs && instance_config.journal_file.is_some() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(rest::CreateInstanceResponse::Error {
+                message: "Journaling is not supported for instances with state loaded from a state directory".to_owned(),
+            }),
+        );
+    }
     if !skip_validate_subnet_configs {
         if let Err(e) = subnet_configs.validate() {
             return (
@@ -1191,7 +1260,7 @@ pub async fn create_instance(
 
     match api_state
         .add_instance(move |seed| {
-            PocketIc::try_new(
+            let mut pocket_ic = PocketIc::try_new(
                 runtime,
                 seed,
                 subnet_configs,
@@ -1199,7 +1268,13 @@ pub async fn create_instance(
                 instance_config.nonmainnet_features,
                 log_level,
                 instance_config.bitcoind_addr,
-            )
+                instance_config.wasm_profile_dir,
+            )?;
+            if let Some(journal_file) = instance_config.journal_file {
+                pocket_ic.set_journal(Journal::create(&journal_file, &journal_header)?);
+            }
+            pocket_ic.set_message_profiling(instance_config.message_profiling);
+            Ok(pocket_ic)
         })
         .await
     {
@@ -1217,6 +1292,87 @@ pub async fn create_instance(
     }
 }
 
+pub async fn fork_instance(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(id): Path<InstanceId>,
+) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    match api_state.fork_instance(id).await {
+        Ok((instance_id, topology)) => (
+            StatusCode::CREATED,
+            Json(rest::CreateInstanceResponse::Created {
+                instance_id,
+                topology,
+            }),
+        ),
+        Err(err) => (
+            StatusCode::BAD_REQUEST,
+            Json(rest::CreateInstanceResponse::Error { message: err }),
+        ),
+    }
+}
+
+pub async fn replay_journal(
+    State(AppState {
+        api_state, runtime, ..
+    }): State<AppState>,
+    extract::Json(RawReplayJournal { journal_file }): extract::Json<RawReplayJournal>,
+) -> (StatusCode, Json<ReplayJournalResponse>) {
+    let (header, entries) = match read_journal(&journal_file) {
+        Ok(journal) => journal,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ReplayJournalResponse::Error { message }),
+            )
+        }
+    };
+    let instance_config = header.instance_config;
+    let log_level = instance_config
+        .log_level
+        .and_then(|log_level| Level::from_str(&log_level).ok());
+
+    match api_state
+        .add_instance_with_output(move |seed| {
+            // The state directory of the journaled instance is not reused
+            // since it might contain the state persisted by the journaled instance.
+            let mut pocket_ic = PocketIc::try_new(
+                runtime,
+                seed,
+                instance_config.subnet_config_set,
+                None,
+                instance_config.nonmainnet_features,
+                log_level,
+                instance_config.bitcoind_addr,
+                // The replayed instance does not append to the Wasm profiles
+                // written by the journaled instance.
+                None,
+            )?;
+            pocket_ic.set_message_profiling(instance_config.message_profiling);
+            let divergence = replay(&mut pocket_ic, &entries);
+            let replayed_operations = divergence
+                .as_ref()
+                .map(|divergence| divergence.index + 1)
+                .unwrap_or(entries.len());
+            Ok((pocket_ic, (replayed_operations, divergence)))
+        })
+        .await
+    {
+        Ok((instance_id, topology, (replayed_operations, divergence))) => (
+            StatusCode::CREATED,
+            Json(ReplayJournalResponse::Replayed {
+                instance_id,
+                topology,
+                replayed_operations,
+                divergence,
+            }),
+        ),
+        Err(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(ReplayJournalResponse::Error { message }),
+        ),
+    }
+}
+
 pub async fn list_instances(
     State(AppState { api_state, .. }): State<AppState>,
 ) -> Json<Vec<String>> {
@@ -35,7 +35,7 @@ use ic_types::{canister_http::CanisterHttpRequestId, CanisterId, NodeId, Princip
 use itertools::Itertools;
 use pocket_ic::common::rest::{
     CanisterHttpRequest, HttpGatewayBackend, HttpGatewayConfig, HttpGatewayDetails,
-    HttpGatewayInfo, Topology,
+    HttpGatewayInfo, MessageProfiles, Topology,
 };
 use pocket_ic::RejectResponse;
 use reqwest::Url;
@@ -242,6 +242,7 @@ pub enum OpOut {
     MessageId((EffectivePrincipal, Vec<u8>)),
     Topology(Topology),
     CanisterHttp(Vec<CanisterHttpRequest>),
+    MessageProfiles(MessageProfiles),
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Deserialize, Serialize)]
@@ -258,6 +259,8 @@ pub enum PocketIcError {
     Forbidden(String),
     BlockmakerNotFound(NodeId),
     BlockmakerContainedInFailed(NodeId),
+    MessageProfilingDisabled,
+    JournalWriteFailed(String),
 }
 
 impl std::fmt::Debug for OpOut {
@@ -322,6 +325,12 @@ impl std::fmt::Debug for OpOut {
             OpOut::Error(PocketIcError::Forbidden(msg)) => {
                 write!(f, "Forbidden({})", msg)
             }
+            OpOut::Error(PocketIcError::MessageProfilingDisabled) => {
+                write!(f, "MessageProfilingDisabled")
+            }
+            OpOut::Error(PocketIcError::JournalWriteFailed(msg)) => {
+                write!(f, "JournalWriteFailed({})", msg)
+            }
             OpOut::Bytes(bytes) => write!(f, "Bytes({})", base64::encode(bytes)),
             OpOut::StableMemBytes(bytes) => write!(f, "StableMemory({})", base64::encode(bytes)),
             OpOut::MaybeSubnetId(Some(subnet_id)) => write!(f, "SubnetId({})", subnet_id),
@@ -349,6 +358,9 @@ impl std::fmt::Debug for OpOut {
             OpOut::CanisterHttp(canister_http_reqeusts) => {
                 write!(f, "CanisterHttp({:?})", canister_http_reqeusts)
             }
+            OpOut::MessageProfiles(message_profiles) => {
+                write!(f, "MessageProfiles({:?})", message_profiles)
+            }
         }
     }
 }
@@ -748,10 +760,26 @@ impl ApiState {
     pub async fn add_instance<F>(&self, f: F) -> Result<(InstanceId, Topology), String>
     where
         F: FnOnce(u64) -> Result<PocketIc, String> + std::marker::Send + 'static,
+    {
+        self.add_instance_with_output(move |seed| f(seed).map(|instance| (instance, ())))
+            .await
+            .map(|(instance_id, topology, ())| (instance_id, topology))
+    }
+
+    /// Like `add_instance`, but the function creating the instance also returns
+    /// an additional output (e.g., the outcome of operations executed on the instance
+    /// before it is added).
+    pub async fn add_instance_with_output<F, T>(
+        &self,
+        f: F,
+    ) -> Result<(InstanceId, Topology, T), String>
+    where
+        F: FnOnce(u64) -> Result<(PocketIc, T), String> + std::marker::Send + 'static,
+        T: std::marker::Send + 'static,
     {
         let seed = self.seed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         // create the instance using `spawn_blocking` before acquiring a lock
-        let instance = tokio::task::spawn_blocking(move || f(seed))
+        let (instance, output) = tokio::task::spawn_blocking(move || f(seed))
             .await
             .expect("Failed to create PocketIC instance")?;
         let topology = instance.topology();
@@ -761,7 +789,73 @@ impl ApiState {
             progress_thread: None,
             state: InstanceState::Available(instance),
         }));
-        Ok((instance_id, topology))
+        Ok((instance_id, topology, output))
+    }
+
+    /// Creates a new instance starting from the current state of an existing instance.
+    /// The existing instance is marked as busy while its state is being forked.
+    pub async fn fork_instance(
+        &self,
+        instance_id: InstanceId,
+    ) -> Result<(InstanceId, Topology), String> {
+        let seed = self.seed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let pocket_ic = loop {
+            let instances = self.instances.read().await;
+            let mut instance = instances
+                .get(instance_id)
+                .ok_or_else(|| "Instance not found".to_string())?
+                .lock()
+                .await;
+            match &instance.state {
+                InstanceState::Available(pocket_ic) => {
+                    let busy = InstanceState::Busy {
+                        state_label: pocket_ic.get_state_label(),
+                        op_id: OpId("fork".to_string()),
+                    };
+                    let InstanceState::Available(pocket_ic) =
+                        std::mem::replace(&mut instance.state, busy)
+                    else {
+                        unreachable!()
+                    };
+                    break pocket_ic;
+                }
+                InstanceState::Deleted => {
+                    return Err("Instance was deleted".to_string());
+                }
+                InstanceState::Busy { .. } => {}
+            }
+            drop(instance);
+            drop(instances);
+            sleep(MIN_OPERATION_DELAY).await;
+        };
+
+        // Forking writes a checkpoint on every subnet of the existing instance
+        // and thus we run it using `spawn_blocking` without holding any locks.
+        let result = spawn_blocking(move || {
+            let fork = pocket_ic.fork(seed);
+            (pocket_ic, fork)
+        })
+        .await;
+
+        let mut instances = self.instances.write().await;
+        let fork = match result {
+            Ok((pocket_ic, fork)) => {
+                instances[instance_id].get_mut().state = InstanceState::Available(pocket_ic);
+                fork?
+            }
+            Err(e) => {
+                // The existing instance was moved into the failed task and is lost.
+                instances[instance_id].get_mut().state = InstanceState::Deleted;
+                return Err(format!("Failed to fork PocketIC instance: {}", e));
+            }
+        };
+        let topology = fork.topology();
+        let fork_instance_id = instances.len();
+        instances.push(Mutex::new(Instance {
+            progress_thread: None,
+            state: InstanceState::Available(fork),
+        }));
+        Ok((fork_instance_id, topology))
     }
 
     pub async fn delete_instance(&self, instance_id: InstanceId) {
@@ -1164,6 +1258,10 @@ impl ApiState {
         O: Operation + Send + Sync + 'static,
     {
         let op_id = op.id().0;
+        // The operation to be journaled is determined before looking up the instance
+        // (and any cached result) so that every operation executed on the instance
+        // is recorded in its journal.
+        let journaled_op = op.journal();
         trace!(
             "update_with_timeout::start instance_id={} op_id={}",
             instance_id,
@@ -1215,8 +1313,19 @@ impl ApiState {
                                 old_state_label,
                                 op_id.0,
                             );
-                            let result = op.compute(&mut pocket_ic);
+                            let mut result = op.compute(&mut pocket_ic);
                             pocket_ic.bump_state_label();
+                            // A journal that misses an operation cannot be replayed faithfully
+                            // and thus the failure is returned to the client.
+                            if let Some(journaled_op) = journaled_op {
+                                if let Err(e) = pocket_ic.journal_operation(journaled_op) {
+                                    error!(
+                                        "Failed to journal operation {} on instance {}: {}",
+                                        op_id.0, instance_id, e
+                                    );
+                                    result = OpOut::Error(PocketIcError::JournalWriteFailed(e));
+                                }
+                            }
                             let new_state_label = pocket_ic.get_state_label();
                             // add result to graph, but grab instance lock first!
                             let instances = instances.blocking_read();
@@ -14,7 +14,8 @@ use ic_utils::interfaces::ManagementCanister;
 use nix::sys::signal::Signal;
 use pocket_ic::common::rest::{
     CreateHttpGatewayResponse, HttpGatewayBackend, HttpGatewayConfig, HttpGatewayDetails,
-    HttpsConfig, InstanceConfig, SubnetConfigSet, SubnetKind, Topology,
+    HttpsConfig, InstanceConfig, RawReplayJournal, ReplayJournalResponse, SubnetConfigSet,
+    SubnetKind, Topology,
 };
 use pocket_ic::{update_candid, PocketIc, PocketIcBuilder};
 use rcgen::{CertificateParams, KeyPair};
@@ -190,6 +191,9 @@ fn test_creation_of_instance_extended() {
         nonmainnet_features: false,
         log_level: None,
         bitcoind_addr: None,
+        journal_file: None,
+        message_profiling: false,
+        wasm_profile_dir: None,
     };
     let response = client
         .post(url.join("instances").unwrap())
@@ -1760,3 +1764,57 @@ fn test_gateway_address_in_use() {
         port
     )));
 }
+
+#[test]
+fn replay_journal() {
+    let journal_file = NamedTempFile::new().unwrap();
+    let journal_file_path = journal_file.path().to_path_buf();
+
+    // Create a PocketIC instance journaling its operations.
+    let (server_url, _) = start_server_helper(None, None, false, false);
+    let pic = PocketIcBuilder::new()
+        .with_server_url(server_url.clone())
+        .with_application_subnet()
+        .with_journal_file(journal_file_path.clone())
+        .build();
+
+    // Perform a few mutating operations on the instance.
+    let canister_id = deploy_counter_canister_to_any_subnet(&pic);
+    pic.advance_time(Duration::from_secs(1));
+    pic.tick();
+    pic.update_call(canister_id, Principal::anonymous(), "write", vec![])
+        .unwrap();
+    check_counter(&pic, canister_id, 1);
+    let time = pic.get_time();
+    drop(pic);
+
+    // Replay the journal against a fresh instance.
+    let client = Client::new();
+    let response = client
+        .post(server_url.join("instances/replay").unwrap())
+        .json(&RawReplayJournal {
+            journal_file: journal_file_path,
+        })
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let (instance_id, replayed_operations) = match response.json().unwrap() {
+        ReplayJournalResponse::Replayed {
+            instance_id,
+            replayed_operations,
+            divergence,
+            ..
+        } => {
+            // The state labels of all operations must match.
+            assert_eq!(divergence, None);
+            (instance_id, replayed_operations)
+        }
+        ReplayJournalResponse::Error { message } => panic!("Failed to replay journal: {}", message),
+    };
+    assert!(replayed_operations > 0);
+
+    // The replayed instance ends up in the same state as the journaled instance.
+    let replayed_pic = PocketIc::new_from_existing_instance(server_url, instance_id, None);
+    check_counter(&replayed_pic, canister_id, 1);
+    assert_eq!(replayed_pic.get_time(), time);
+}
@@ -41,6 +41,11 @@ pub struct CanisterSnapshotBits {
     pub on_low_wasm_memory_hook_status: ::core::option::Option<i32>,
     #[prost(enumeration = "SnapshotSource", tag = "14")]
     pub source: i32,
+    #[prost(uint64, optional, tag = "15")]
+    pub base_snapshot_id: ::core::option::Option<u64>,
+    /// Whether the snapshot is still being cloned from a canister on another subnet.
+    #[prost(bool, tag = "16")]
+    pub import_in_progress: bool,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -91,6 +91,20 @@ pub struct Callback {
     /// If non-zero, this is a best-effort call.
     #[prost(uint32, tag = "10")]
     pub deadline_seconds: u32,
+    /// Set iff the callback belongs to a chunked call with chunks left to send.
+    #[prost(message, optional, tag = "11")]
+    pub pending_chunks: ::core::option::Option<PendingPayloadChunks>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PendingPayloadChunks {
+    #[prost(string, tag = "1")]
+    pub method_name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub metadata: ::core::option::Option<super::super::queues::v1::RequestMetadata>,
+    #[prost(uint32, tag = "3")]
+    pub count: u32,
+    #[prost(bytes = "vec", repeated, tag = "4")]
+    pub chunks: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CallbackEntry {
@@ -532,6 +546,13 @@ pub mod log_visibility_v2 {
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLogField {
+    #[prost(string, tag = "1")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterLogRecord {
     #[prost(uint64, tag = "1")]
     pub idx: u64,
@@ -539,6 +560,11 @@ pub struct CanisterLogRecord {
     pub timestamp_nanos: u64,
     #[prost(bytes = "vec", tag = "3")]
     pub content: ::prost::alloc::vec::Vec<u8>,
+    /// Records written before log levels were introduced are unspecified.
+    #[prost(enumeration = "CanisterLogLevel", tag = "4")]
+    pub level: i32,
+    #[prost(message, repeated, tag = "5")]
+    pub fields: ::prost::alloc::vec::Vec<CanisterLogField>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SnapshotId {
@@ -558,6 +584,52 @@ pub struct TaskQueue {
     #[prost(message, repeated, tag = "3")]
     pub queue: ::prost::alloc::vec::Vec<ExecutionTask>,
 }
+/// Execution statistics of a single exported method of a canister.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MethodProfileEntry {
+    #[prost(string, tag = "1")]
+    pub method_name: ::prost::alloc::string::String,
+    /// The number of completed executions of the method.
+    #[prost(uint64, tag = "2")]
+    pub num_calls: u64,
+    /// The total number of instructions executed.
+    #[prost(uint64, tag = "3")]
+    pub instructions: u64,
+    /// The maximum number of instructions executed by a single call.
+    #[prost(uint64, tag = "4")]
+    pub max_instructions: u64,
+    /// The total amount of cycles charged for the executed instructions.
+    #[prost(message, optional, tag = "5")]
+    pub cycles: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// The total number of (OS) pages dirtied.
+    #[prost(uint64, tag = "6")]
+    pub dirty_pages: u64,
+    /// The number of calls per bucket of executed instructions.
+    #[prost(uint64, repeated, tag = "7")]
+    pub instruction_histogram: ::prost::alloc::vec::Vec<u64>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MethodProfile {
+    #[prost(message, repeated, tag = "1")]
+    pub methods: ::prost::alloc::vec::Vec<MethodProfileEntry>,
+}
+/// A named timer of a canister.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NamedTimer {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// The time at which the timer expires next.
+    #[prost(uint64, tag = "2")]
+    pub deadline_nanos: u64,
+    /// The period of a periodic timer; absent for one-off timers.
+    #[prost(uint64, optional, tag = "3")]
+    pub interval_nanos: ::core::option::Option<u64>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterTimers {
+    #[prost(message, repeated, tag = "1")]
+    pub timers: ::prost::alloc::vec::Vec<NamedTimer>,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterStateBits {
     #[prost(uint64, tag = "2")]
@@ -639,6 +711,9 @@ pub struct CanisterStateBits {
     /// The index of the next log record to be created.
     #[prost(uint64, tag = "44")]
     pub next_canister_log_record_idx: u64,
+    /// The minimum level of log records that are kept.
+    #[prost(enumeration = "CanisterLogLevel", tag = "55")]
+    pub log_min_level: i32,
     /// The Wasm memory limit. This is a field in developer-visible canister
     /// settings that allows the developer to limit the usage of the Wasm memory
     /// by the canister to leave some room in 4GiB for upgrade calls.
@@ -661,6 +736,12 @@ pub struct CanisterStateBits {
     /// canister.
     #[prost(message, optional, tag = "54")]
     pub tasks: ::core::option::Option<TaskQueue>,
+    /// Execution statistics per exported method of the canister.
+    #[prost(message, optional, tag = "56")]
+    pub method_profile: ::core::option::Option<MethodProfile>,
+    /// Named timers of the canister.
+    #[prost(message, optional, tag = "57")]
+    pub named_timers: ::core::option::Option<CanisterTimers>,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -811,6 +892,41 @@ impl CyclesUseCase {
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum CanisterLogLevel {
+    Unspecified = 0,
+    Debug = 1,
+    Info = 2,
+    Warning = 3,
+    Error = 4,
+}
+impl CanisterLogLevel {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "CANISTER_LOG_LEVEL_UNSPECIFIED",
+            Self::Debug => "CANISTER_LOG_LEVEL_DEBUG",
+            Self::Info => "CANISTER_LOG_LEVEL_INFO",
+            Self::Warning => "CANISTER_LOG_LEVEL_WARNING",
+            Self::Error => "CANISTER_LOG_LEVEL_ERROR",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CANISTER_LOG_LEVEL_UNSPECIFIED" => Some(Self::Unspecified),
+            "CANISTER_LOG_LEVEL_DEBUG" => Some(Self::Debug),
+            "CANISTER_LOG_LEVEL_INFO" => Some(Self::Info),
+            "CANISTER_LOG_LEVEL_WARNING" => Some(Self::Warning),
+            "CANISTER_LOG_LEVEL_ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum LongExecutionMode {
     Unspecified = 0,
     Opportunistic = 1,
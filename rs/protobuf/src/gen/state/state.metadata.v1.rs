@@ -327,6 +327,35 @@ pub struct RawRandContext {
     pub execution_round_id: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSnapshotCloneContext {
+    #[prost(message, optional, tag = "1")]
+    pub request: ::core::option::Option<super::super::queues::v1::Request>,
+    #[prost(message, optional, tag = "2")]
+    pub snapshot_id: ::core::option::Option<super::super::canister_state_bits::v1::SnapshotId>,
+    #[prost(message, optional, tag = "3")]
+    pub target_canister_id: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(message, optional, tag = "4")]
+    pub replace_snapshot_id:
+        ::core::option::Option<super::super::canister_state_bits::v1::SnapshotId>,
+    #[prost(message, optional, tag = "5")]
+    pub target_snapshot_id:
+        ::core::option::Option<super::super::canister_state_bits::v1::SnapshotId>,
+    #[prost(enumeration = "CanisterSnapshotCloneCursorKind", tag = "6")]
+    pub cursor_kind: i32,
+    /// Byte offset or chunk index, depending on `cursor_kind`.
+    #[prost(uint64, tag = "7")]
+    pub cursor_position: u64,
+    #[prost(message, optional, tag = "8")]
+    pub time: ::core::option::Option<Time>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSnapshotCloneContextTree {
+    #[prost(uint64, tag = "1")]
+    pub callback_id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub context: ::core::option::Option<CanisterSnapshotCloneContext>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SubnetCallContextManager {
     #[prost(uint64, tag = "1")]
     pub next_callback_id: u64,
@@ -356,6 +385,9 @@ pub struct SubnetCallContextManager {
     pub reshare_chain_key_contexts: ::prost::alloc::vec::Vec<ReshareChainKeyContextTree>,
     #[prost(message, repeated, tag = "18")]
     pub sign_with_threshold_contexts: ::prost::alloc::vec::Vec<SignWithThresholdContextTree>,
+    #[prost(message, repeated, tag = "19")]
+    pub canister_snapshot_clone_contexts:
+        ::prost::alloc::vec::Vec<CanisterSnapshotCloneContextTree>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SubnetMetrics {
@@ -533,3 +565,53 @@ impl HttpMethod {
         }
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CanisterSnapshotCloneCursorKind {
+    Unspecified = 0,
+    Metadata = 1,
+    WasmModule = 2,
+    MainMemory = 3,
+    StableMemory = 4,
+    WasmChunk = 5,
+    Finish = 6,
+    Done = 7,
+    Aborted = 8,
+    Discarded = 9,
+}
+impl CanisterSnapshotCloneCursorKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_UNSPECIFIED",
+            Self::Metadata => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_METADATA",
+            Self::WasmModule => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_WASM_MODULE",
+            Self::MainMemory => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_MAIN_MEMORY",
+            Self::StableMemory => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_STABLE_MEMORY",
+            Self::WasmChunk => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_WASM_CHUNK",
+            Self::Finish => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_FINISH",
+            Self::Done => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_DONE",
+            Self::Aborted => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_ABORTED",
+            Self::Discarded => "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_DISCARDED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_UNSPECIFIED" => Some(Self::Unspecified),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_METADATA" => Some(Self::Metadata),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_WASM_MODULE" => Some(Self::WasmModule),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_MAIN_MEMORY" => Some(Self::MainMemory),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_STABLE_MEMORY" => Some(Self::StableMemory),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_WASM_CHUNK" => Some(Self::WasmChunk),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_FINISH" => Some(Self::Finish),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_DONE" => Some(Self::Done),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_ABORTED" => Some(Self::Aborted),
+            "CANISTER_SNAPSHOT_CLONE_CURSOR_KIND_DISCARDED" => Some(Self::Discarded),
+            _ => None,
+        }
+    }
+}
@@ -58,6 +58,16 @@ pub struct RequestMetadata {
     /// Reserved for future use (guaranteed replies won't be affected).
     #[prost(uint64, optional, tag = "3")]
     pub call_subtree_deadline_nanos: ::core::option::Option<u64>,
+    /// Set iff the request carries one chunk of the payload of a chunked call.
+    #[prost(message, optional, tag = "4")]
+    pub payload_chunk: ::core::option::Option<PayloadChunk>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct PayloadChunk {
+    #[prost(uint32, tag = "1")]
+    pub index: u32,
+    #[prost(uint32, tag = "2")]
+    pub count: u32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Request {
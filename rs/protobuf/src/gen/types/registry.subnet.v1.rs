@@ -70,6 +70,10 @@ pub struct SubnetRecord {
     /// key. If the removed key is not held by another subnet, it will be lost.
     #[prost(message, optional, tag = "29")]
     pub chain_key_config: ::core::option::Option<ChainKeyConfig>,
+    /// Configures how the block rate of the subnet adapts to its load. If not set, the fixed
+    /// `unit_delay_millis` and `initial_notary_delay_millis` are used.
+    #[prost(message, optional, tag = "30")]
+    pub block_rate_config: ::core::option::Option<BlockRateConfig>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EcdsaInitialization {
@@ -346,6 +350,19 @@ pub struct ChainKeyConfig {
     #[prost(uint64, optional, tag = "3")]
     pub idkg_key_rotation_period_ms: ::core::option::Option<u64>,
 }
+/// Per-subnet configuration of the block rate.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockRateConfig {
+    /// The strategy used by block makers and notaries to decide how long to wait in a round.
+    #[prost(enumeration = "BlockRateStrategy", tag = "1")]
+    pub strategy: i32,
+    /// The round duration targeted when recent blocks are full, in milliseconds.
+    #[prost(uint64, tag = "2")]
+    pub min_round_delay_millis: u64,
+    /// The round duration targeted when recent blocks are empty, in milliseconds.
+    #[prost(uint64, tag = "3")]
+    pub max_round_delay_millis: u64,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum IDkgTranscriptOperation {
@@ -425,3 +442,34 @@ impl SubnetType {
         }
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum BlockRateStrategy {
+    Unspecified = 0,
+    /// Always use the fixed delays of the subnet record.
+    Fixed = 1,
+    /// Target a round duration between the configured bounds, shorter the fuller recent blocks are.
+    LoadAdaptive = 2,
+}
+impl BlockRateStrategy {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "BLOCK_RATE_STRATEGY_UNSPECIFIED",
+            Self::Fixed => "BLOCK_RATE_STRATEGY_FIXED",
+            Self::LoadAdaptive => "BLOCK_RATE_STRATEGY_LOAD_ADAPTIVE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "BLOCK_RATE_STRATEGY_UNSPECIFIED" => Some(Self::Unspecified),
+            "BLOCK_RATE_STRATEGY_FIXED" => Some(Self::Fixed),
+            "BLOCK_RATE_STRATEGY_LOAD_ADAPTIVE" => Some(Self::LoadAdaptive),
+            _ => None,
+        }
+    }
+}
@@ -565,6 +565,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             chain_key_config: None,
+            block_rate_config: None,
         };
 
         let key_id = EcdsaKeyId {
@@ -655,6 +656,7 @@ mod tests {
                 max_number_of_canisters: 10,
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
+                block_rate_config: None,
             }
         );
     }
@@ -680,6 +682,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             chain_key_config: None,
+            block_rate_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -739,6 +742,7 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 chain_key_config: None,
+                block_rate_config: None,
             }
         );
     }
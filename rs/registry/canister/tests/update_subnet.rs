@@ -149,6 +149,7 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             chain_key_config: None,
+            block_rate_config: None,
         };
 
         // An attacker got a canister that is trying to pass for the governance
@@ -271,6 +272,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                             ssh_readonly_access: vec![],
                             ssh_backup_access: vec![],
                             chain_key_config: None,
+                            block_rate_config: None,
                         }
                         .encode_to_vec(),
                     )],
@@ -361,6 +363,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
                 chain_key_config: None,
+                block_rate_config: None,
             }
         );
 
@@ -436,6 +439,7 @@ fn test_subnets_configuration_chain_key_fields_are_updated_correctly(key_id: Mas
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             chain_key_config: None,
+            block_rate_config: None,
         };
 
         // Just create the registry canister and wait until the subnet_handler ID is
@@ -7,7 +7,10 @@ use ic_protobuf::{
     registry::{
         node::v1::NodeRecord,
         replica_version::v1::ReplicaVersionRecord,
-        subnet::v1::{CatchUpPackageContents, SubnetListRecord, SubnetRecord, SubnetType},
+        subnet::v1::{
+            BlockRateConfig, BlockRateStrategy as BlockRateStrategyProto, CatchUpPackageContents,
+            SubnetListRecord, SubnetRecord, SubnetType,
+        },
     },
     types::v1::SubnetId as SubnetIdProto,
 };
@@ -37,6 +40,46 @@ impl Default for NotarizationDelaySettings {
     }
 }
 
+/// The strategy used by block makers and notaries to decide how long to wait
+/// in a round.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub enum BlockRateStrategy {
+    /// Always use the fixed [NotarizationDelaySettings].
+    #[default]
+    Fixed,
+    /// Target a round duration between the given bounds, shorter the fuller
+    /// recent blocks are.
+    LoadAdaptive {
+        min_round_delay: Duration,
+        max_round_delay: Duration,
+    },
+}
+
+impl TryFrom<BlockRateConfig> for BlockRateStrategy {
+    type Error = String;
+
+    fn try_from(config: BlockRateConfig) -> Result<Self, Self::Error> {
+        match BlockRateStrategyProto::try_from(config.strategy) {
+            Ok(BlockRateStrategyProto::Fixed) => Ok(BlockRateStrategy::Fixed),
+            Ok(BlockRateStrategyProto::LoadAdaptive) => {
+                if config.min_round_delay_millis > config.max_round_delay_millis {
+                    return Err(format!(
+                        "min_round_delay_millis ({}) exceeds max_round_delay_millis ({})",
+                        config.min_round_delay_millis, config.max_round_delay_millis
+                    ));
+                }
+                Ok(BlockRateStrategy::LoadAdaptive {
+                    min_round_delay: Duration::from_millis(config.min_round_delay_millis),
+                    max_round_delay: Duration::from_millis(config.max_round_delay_millis),
+                })
+            }
+            Ok(BlockRateStrategyProto::Unspecified) | Err(_) => {
+                Err(format!("Invalid block rate strategy: {}", config.strategy))
+            }
+        }
+    }
+}
+
 pub struct IngressMessageSettings {
     /// Maximum number of bytes per message. This is a hard cap, which means
     /// ingress messages greater than the limit will be dropped.
@@ -100,6 +143,14 @@ pub trait SubnetRegistry {
         version: RegistryVersion,
     ) -> RegistryClientResult<NotarizationDelaySettings>;
 
+    /// Returns the strategy used to adapt the block rate to the load of the
+    /// subnet, or [BlockRateStrategy::Fixed] if none is configured.
+    fn get_block_rate_strategy(
+        &self,
+        subnet_id: SubnetId,
+        version: RegistryVersion,
+    ) -> RegistryClientResult<BlockRateStrategy>;
+
     /// Returns the upper bound for the number of dealings we allow in a block.
     fn get_dkg_dealings_per_block(
         &self,
@@ -304,6 +355,24 @@ impl<T: RegistryClient + ?Sized> SubnetRegistry for T {
         )
     }
 
+    fn get_block_rate_strategy(
+        &self,
+        subnet_id: SubnetId,
+        version: RegistryVersion,
+    ) -> RegistryClientResult<BlockRateStrategy> {
+        let bytes = self.get_value(&make_subnet_record_key(subnet_id), version);
+        deserialize_registry_value::<SubnetRecord>(bytes)?
+            .map(|subnet| {
+                subnet
+                    .block_rate_config
+                    .map_or(Ok(BlockRateStrategy::Fixed), BlockRateStrategy::try_from)
+            })
+            .transpose()
+            .map_err(|err| DecodeError {
+                error: format!("get_block_rate_strategy() failed with {}", err),
+            })
+    }
+
     fn get_dkg_dealings_per_block(
         &self,
         subnet_id: SubnetId,
@@ -707,6 +776,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_get_block_rate_strategy_from_subnet() {
+        let subnet_id = subnet_id(4);
+        let version = RegistryVersion::from(2);
+
+        let test_cases = [
+            (None, Ok(Some(BlockRateStrategy::Fixed))),
+            (
+                Some(BlockRateConfig {
+                    strategy: BlockRateStrategyProto::Fixed.into(),
+                    ..Default::default()
+                }),
+                Ok(Some(BlockRateStrategy::Fixed)),
+            ),
+            (
+                Some(BlockRateConfig {
+                    strategy: BlockRateStrategyProto::LoadAdaptive.into(),
+                    min_round_delay_millis: 300,
+                    max_round_delay_millis: 3000,
+                }),
+                Ok(Some(BlockRateStrategy::LoadAdaptive {
+                    min_round_delay: Duration::from_millis(300),
+                    max_round_delay: Duration::from_millis(3000),
+                })),
+            ),
+            (
+                Some(BlockRateConfig {
+                    strategy: BlockRateStrategyProto::LoadAdaptive.into(),
+                    min_round_delay_millis: 3000,
+                    max_round_delay_millis: 300,
+                }),
+                Err(()),
+            ),
+            (Some(BlockRateConfig::default()), Err(())),
+        ];
+        for (block_rate_config, expected) in test_cases {
+            let subnet_record = SubnetRecord {
+                block_rate_config,
+                ..Default::default()
+            };
+
+            let registry =
+                create_test_registry_client(version, vec![(subnet_id, subnet_record)], None);
+
+            assert_eq!(
+                registry
+                    .get_block_rate_strategy(subnet_id, version)
+                    .map_err(|_| ()),
+                expected
+            );
+        }
+    }
+
     #[test]
     fn can_get_halt_at_cup_height_from_subnet() {
         let subnet_id = subnet_id(4);
@@ -15,7 +15,9 @@ use ic_consensus_certification::{CertificationCrypto, CertifierBouncer, Certifie
 use ic_consensus_dkg::DkgBouncer;
 use ic_consensus_idkg::{IDkgBouncer, IDkgStatsImpl};
 use ic_consensus_manager::{AbortableBroadcastChannel, AbortableBroadcastChannelBuilder};
-use ic_consensus_utils::{crypto::ConsensusCrypto, pool_reader::PoolReader};
+use ic_consensus_utils::{
+    crypto::ConsensusCrypto, finalization_trace::FinalizationTracer, pool_reader::PoolReader,
+};
 use ic_consensus_vetkd::VetKdPayloadBuilderImpl;
 use ic_crypto_interfaces_sig_verification::IngressSigVerifier;
 use ic_crypto_tls_interfaces::TlsConfig;
@@ -433,6 +435,7 @@ pub fn setup_consensus_and_p2p(
         topology_watcher.clone(),
         create_udp_socket(rt_handle, transport_addr),
         p2p_router,
+        transport_config.traffic_shaping.clone(),
     ));
 
     // Start the main event loops for StateSync and Consensus
@@ -556,6 +559,7 @@ fn start_consensus(
 
     let mut join_handles = vec![];
 
+    let finalization_tracer = Arc::new(FinalizationTracer::default());
     let consensus_impl = ConsensusImpl::new(
         replica_config.clone(),
         Arc::clone(&registry_client),
@@ -575,6 +579,7 @@ fn start_consensus(
         Arc::clone(&time_source) as Arc<_>,
         registry_poll_delay_duration_ms,
         malicious_flags.clone(),
+        Arc::clone(&finalization_tracer),
         metrics_registry.clone(),
         log.clone(),
     );
@@ -604,6 +609,7 @@ fn start_consensus(
         Arc::clone(&certifier_crypto),
         Arc::clone(&state_manager) as Arc<_>,
         Arc::clone(&consensus_pool_cache) as Arc<_>,
+        finalization_tracer,
         metrics_registry.clone(),
         log.clone(),
         max_certified_height_tx,
@@ -5,7 +5,7 @@ use ic_config::subnet_config::CyclesAccountManagerConfig;
 use ic_config::Config;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_management_canister_types_private::{
-    self as ic00, CanisterChange, CanisterIdRecord, CanisterInstallMode,
+    self as ic00, CanisterChange, CanisterIdRecord, CanisterInstallMode, CanisterLogLevel,
     CanisterSettingsArgsBuilder, CanisterStatusResultV2, CanisterStatusType, EmptyBlob,
     InstallCodeArgs, Method, Payload, UpdateSettingsArgs, IC_00,
 };
@@ -732,6 +732,7 @@ fn can_get_canister_information() {
                 0u128,
                 Some(DEFAULT_WASM_MEMORY_LIMIT.get()),
                 0u64,
+                CanisterLogLevel::Debug,
             )
         );
 
@@ -799,6 +800,7 @@ fn can_get_canister_information() {
                     0u128,
                     Some(DEFAULT_WASM_MEMORY_LIMIT.get()),
                     0u64,
+                    CanisterLogLevel::Debug,
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
                 2 * BALANCE_EPSILON,
@@ -1,9 +1,15 @@
 use crate::{
     canister_state::execution_state::Memory,
-    canister_state::system_state::wasm_chunk_store::WasmChunkStore, CanisterState, NumWasmPages,
-    PageMap,
+    canister_state::system_state::wasm_chunk_store::{self, WasmChunkStore},
+    canister_state::WASM_PAGE_SIZE_IN_BYTES,
+    num_bytes_try_from,
+    page_map::{Buffer, PageAllocatorFileDescriptor},
+    CanisterState, NumWasmPages, PageMap,
+};
+use ic_management_canister_types_private::{
+    CanisterSnapshotChunk, ChunkHash, Global, GlobalTimer, OnLowWasmMemoryHookStatus,
+    ReadCanisterSnapshotMetadataResponse, SnapshotSource,
 };
-use ic_management_canister_types_private::{Global, OnLowWasmMemoryHookStatus, SnapshotSource};
 use ic_sys::PAGE_SIZE;
 use ic_types::{CanisterId, CanisterTimer, NumBytes, SnapshotId, Time};
 use ic_validate_eq::ValidateEq;
@@ -12,6 +18,7 @@ use ic_wasm_types::CanisterModule;
 
 use std::{
     collections::{BTreeMap, BTreeSet},
+    ops::Range,
     sync::Arc,
 };
 
@@ -61,8 +68,57 @@ impl CanisterSnapshots {
     /// which represents the new backup accumulated since the last flush to the disk.
     pub fn push(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) -> SnapshotId {
         let canister_id = snapshot.canister_id();
-        self.unflushed_changes
-            .push(SnapshotOperation::Backup(canister_id, snapshot_id));
+        self.insert(
+            snapshot_id,
+            snapshot,
+            SnapshotOperation::Backup(canister_id, snapshot_id),
+        )
+    }
+
+    /// Adds a new snapshot sharing its contents with the snapshot identified
+    /// by `source_snapshot_id` (e.g. a snapshot cloned into another canister
+    /// on the same subnet).
+    ///
+    /// Additionally, adds a new item to the `unflushed_changes`
+    /// which represents the duplicated snapshot since the last flush to the disk.
+    pub fn push_duplicate(
+        &mut self,
+        source_snapshot_id: SnapshotId,
+        snapshot_id: SnapshotId,
+        snapshot: Arc<CanisterSnapshot>,
+    ) -> SnapshotId {
+        self.insert(
+            snapshot_id,
+            snapshot,
+            SnapshotOperation::Duplicate(source_snapshot_id, snapshot_id),
+        )
+    }
+
+    /// Adds a new snapshot whose contents are not derived from any canister or
+    /// snapshot on this subnet (e.g. a snapshot cloned from another subnet).
+    ///
+    /// Additionally, adds a new item to the `unflushed_changes`
+    /// which represents the imported snapshot since the last flush to the disk.
+    pub fn push_import(
+        &mut self,
+        snapshot_id: SnapshotId,
+        snapshot: Arc<CanisterSnapshot>,
+    ) -> SnapshotId {
+        self.insert(
+            snapshot_id,
+            snapshot,
+            SnapshotOperation::Import(snapshot_id),
+        )
+    }
+
+    fn insert(
+        &mut self,
+        snapshot_id: SnapshotId,
+        snapshot: Arc<CanisterSnapshot>,
+        operation: SnapshotOperation,
+    ) -> SnapshotId {
+        let canister_id = snapshot.canister_id();
+        self.unflushed_changes.push(operation);
         self.memory_usage += snapshot.size();
         self.snapshots.insert(snapshot_id, snapshot);
         let snapshot_ids = self.snapshot_ids.entry(canister_id).or_default();
@@ -188,6 +244,32 @@ impl CanisterSnapshots {
             .push(SnapshotOperation::Restore(canister_id, snapshot_id))
     }
 
+    /// Adds a new import snapshot operation in the unflushed changes, unless
+    /// it is already the most recent one.
+    ///
+    /// Must be called whenever the contents of an imported snapshot change,
+    /// so that they are written to disk at the next flush.
+    pub fn add_import_operation(&mut self, snapshot_id: SnapshotId) {
+        let operation = SnapshotOperation::Import(snapshot_id);
+        if self.unflushed_changes.last() != Some(&operation) {
+            self.unflushed_changes.push(operation);
+        }
+    }
+
+    /// Returns true if the snapshot identified by `snapshot_id` is the base
+    /// of at least one incremental snapshot.
+    ///
+    /// Such a snapshot cannot be removed by the canister's controllers as long
+    /// as the snapshots depending on it exist.
+    pub fn is_base_snapshot(&self, snapshot_id: SnapshotId) -> bool {
+        self.snapshot_ids
+            .get(&snapshot_id.get_canister_id())
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.snapshots.get(id))
+            .any(|snapshot| snapshot.base_snapshot() == Some(snapshot_id))
+    }
+
     /// Returns true if snapshot ID can be found in the collection.
     pub fn contains(&self, snapshot_id: &SnapshotId) -> bool {
         self.snapshots.contains_key(snapshot_id)
@@ -316,7 +398,12 @@ pub struct CanisterSnapshot {
     /// The canister version at the time of taking the snapshot.
     canister_version: u64,
     /// Amount of memory used by a snapshot in bytes.
+    ///
+    /// For incremental snapshots, this excludes the data shared with the
+    /// base snapshot.
     size: NumBytes,
+    /// The snapshot this snapshot was taken incrementally against, if any.
+    base_snapshot: Option<SnapshotId>,
     /// The certified data blob belonging to the canister.
     certified_data: Vec<u8>,
     /// Snapshot of chunked store.
@@ -324,6 +411,10 @@ pub struct CanisterSnapshot {
     chunk_store: WasmChunkStore,
     #[validate_eq(CompareWithValidateEq)]
     execution_snapshot: ExecutionStateSnapshot,
+    /// Whether the snapshot is still being cloned from a canister on another
+    /// subnet. Such a snapshot is incomplete and cannot be used until the
+    /// import finishes.
+    import_in_progress: bool,
 }
 
 impl CanisterSnapshot {
@@ -336,6 +427,8 @@ impl CanisterSnapshot {
         chunk_store: WasmChunkStore,
         execution_snapshot: ExecutionStateSnapshot,
         size: NumBytes,
+        base_snapshot: Option<SnapshotId>,
+        import_in_progress: bool,
     ) -> CanisterSnapshot {
         Self {
             canister_id,
@@ -346,6 +439,8 @@ impl CanisterSnapshot {
             chunk_store,
             execution_snapshot,
             size,
+            base_snapshot,
+            import_in_progress,
         }
     }
 
@@ -379,9 +474,178 @@ impl CanisterSnapshot {
             chunk_store: canister.system_state.wasm_chunk_store.clone(),
             execution_snapshot,
             size: canister.snapshot_size_bytes(),
+            base_snapshot: None,
+            import_in_progress: false,
+        })
+    }
+
+    /// Creates the snapshot of `canister_id` into which a snapshot described
+    /// by `metadata` is cloned from another subnet.
+    ///
+    /// The Wasm module and the memories are zero-filled and sized as in
+    /// `metadata`, the Wasm chunk store is empty. The size of the snapshot
+    /// already accounts for all the data still to be transferred.
+    pub fn new_import(
+        canister_id: CanisterId,
+        taken_at_timestamp: Time,
+        metadata: &ReadCanisterSnapshotMetadataResponse,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+    ) -> Result<Self, String> {
+        if metadata.wasm_module_size == 0 {
+            return Err("The snapshot has an empty Wasm module".to_string());
+        }
+        let memory = |size_bytes: u64| {
+            if size_bytes % WASM_PAGE_SIZE_IN_BYTES as u64 != 0 {
+                return Err(format!(
+                    "Memory size {} is not a multiple of the Wasm page size",
+                    size_bytes
+                ));
+            }
+            Ok(PageMemory {
+                page_map: PageMap::new(Arc::clone(&fd_factory)),
+                size: NumWasmPages::new((size_bytes / WASM_PAGE_SIZE_IN_BYTES as u64) as usize),
+            })
+        };
+        let execution_snapshot = ExecutionStateSnapshot {
+            wasm_binary: CanisterModule::new(vec![0; metadata.wasm_module_size as usize]),
+            exported_globals: metadata.exported_globals.clone(),
+            stable_memory: memory(metadata.stable_memory_size)?,
+            wasm_memory: memory(metadata.wasm_memory_size)?,
+            global_timer: metadata.global_timer.map(CanisterTimer::from),
+            on_low_wasm_memory_hook_status: metadata.on_low_wasm_memory_hook_status,
+        };
+
+        Ok(CanisterSnapshot {
+            canister_id,
+            source: SnapshotSource::UploadedManually,
+            taken_at_timestamp,
+            canister_version: metadata.canister_version,
+            certified_data: metadata.certified_data.clone(),
+            chunk_store: WasmChunkStore::new(fd_factory),
+            execution_snapshot,
+            size: Self::size_from_metadata(metadata),
+            base_snapshot: None,
+            import_in_progress: true,
         })
     }
 
+    /// Returns the amount of memory used by a full snapshot described by
+    /// `metadata`.
+    pub fn size_from_metadata(metadata: &ReadCanisterSnapshotMetadataResponse) -> NumBytes {
+        let chunk_store_size = (metadata.wasm_chunk_store.len() as u64)
+            .saturating_mul(wasm_chunk_store::chunk_size().get());
+        NumBytes::new(
+            metadata
+                .wasm_module_size
+                .saturating_add(metadata.wasm_memory_size)
+                .saturating_add(metadata.stable_memory_size)
+                .saturating_add(8 * metadata.exported_globals.len() as u64)
+                .saturating_add(chunk_store_size)
+                .saturating_add(metadata.certified_data.len() as u64),
+        )
+    }
+
+    /// Returns a copy of this snapshot owned by `canister_id`, as created when
+    /// cloning the snapshot into a canister on the same subnet.
+    ///
+    /// The copy shares its memories with this snapshot, but is always a full
+    /// snapshot: its size includes any data shared with a base snapshot.
+    pub fn cloned_into(&self, canister_id: CanisterId, taken_at_timestamp: Time) -> Self {
+        CanisterSnapshot {
+            canister_id,
+            source: SnapshotSource::UploadedManually,
+            taken_at_timestamp,
+            size: Self::size_from_metadata(&self.metadata()),
+            base_snapshot: None,
+            import_in_progress: false,
+            ..self.clone()
+        }
+    }
+
+    /// Turns this snapshot into an incremental snapshot on top of the snapshot
+    /// identified by `base_snapshot_id`.
+    ///
+    /// `shared_bytes` is the amount of data the snapshot shares with its base
+    /// (see `CanisterSnapshotDiff::shared_bytes`) and is no longer accounted
+    /// for in the size of this snapshot.
+    pub fn with_base_snapshot(
+        mut self,
+        base_snapshot_id: SnapshotId,
+        shared_bytes: NumBytes,
+    ) -> Self {
+        self.size = self.size.saturating_sub(&shared_bytes);
+        self.base_snapshot = Some(base_snapshot_id);
+        self
+    }
+
+    /// Returns the number of delta pages that `diff()` traverses to compare
+    /// this snapshot with the current state of `canister`.
+    ///
+    /// The cost of `diff()` is proportional to this number, which is bounded
+    /// by the memory modified since the last checkpoint rather than by the
+    /// size of the memories, so it can be charged for upfront.
+    pub fn diff_cost(&self, canister: &CanisterState) -> u64 {
+        let Some(execution_state) = canister.execution_state.as_ref() else {
+            return 0;
+        };
+        MemoryDiff::cost(
+            &self.execution_snapshot.wasm_memory,
+            &execution_state.wasm_memory,
+        ) + MemoryDiff::cost(
+            &self.execution_snapshot.stable_memory,
+            &execution_state.stable_memory,
+        )
+    }
+
+    /// Compares this snapshot with the current state of `canister`.
+    ///
+    /// Memory pages are not compared by content. Instead, a page is
+    /// considered unchanged only if it is physically shared between the
+    /// snapshot and the canister, see `PageMap::unshared_pages()`. Pages are
+    /// only shared until the next checkpoint, after which all pages are
+    /// reported as changed.
+    ///
+    /// A canister without execution state is treated as having empty
+    /// memories and no Wasm module.
+    pub fn diff(&self, canister: &CanisterState) -> CanisterSnapshotDiff {
+        let snapshot_wasm_memory = &self.execution_snapshot.wasm_memory;
+        let snapshot_stable_memory = &self.execution_snapshot.stable_memory;
+        let wasm_chunk_store_changed = !self
+            .chunk_store
+            .keys()
+            .eq(canister.system_state.wasm_chunk_store.keys());
+
+        let Some(execution_state) = canister.execution_state.as_ref() else {
+            let num_pages = |memory: &PageMemory| memory.page_map.num_host_pages() as u64;
+            return CanisterSnapshotDiff {
+                wasm_memory: all_pages(num_pages(snapshot_wasm_memory)),
+                stable_memory: all_pages(num_pages(snapshot_stable_memory)),
+                wasm_module_changed: true,
+                wasm_chunk_store_changed,
+                shared_bytes: NumBytes::new(0),
+            };
+        };
+
+        let wasm_memory = MemoryDiff::new(snapshot_wasm_memory, &execution_state.wasm_memory);
+        let stable_memory = MemoryDiff::new(snapshot_stable_memory, &execution_state.stable_memory);
+        let wasm_module_changed = self.execution_snapshot.wasm_binary.module_hash()
+            != execution_state.wasm_binary.binary.module_hash();
+        let shared_module_bytes = match wasm_module_changed {
+            true => NumBytes::new(0),
+            false => NumBytes::new(execution_state.wasm_binary.binary.len() as u64),
+        };
+
+        CanisterSnapshotDiff {
+            shared_bytes: wasm_memory.shared_bytes
+                + stable_memory.shared_bytes
+                + shared_module_bytes,
+            wasm_memory: wasm_memory.changed_pages,
+            stable_memory: stable_memory.changed_pages,
+            wasm_module_changed,
+            wasm_chunk_store_changed,
+        }
+    }
+
     pub fn canister_id(&self) -> CanisterId {
         self.canister_id
     }
@@ -402,6 +666,19 @@ impl CanisterSnapshot {
         self.size
     }
 
+    pub fn base_snapshot(&self) -> Option<SnapshotId> {
+        self.base_snapshot
+    }
+
+    pub fn import_in_progress(&self) -> bool {
+        self.import_in_progress
+    }
+
+    /// Marks a snapshot cloned from another subnet as complete.
+    pub fn finish_import(&mut self) {
+        self.import_in_progress = false;
+    }
+
     pub fn execution_snapshot(&self) -> &ExecutionStateSnapshot {
         &self.execution_snapshot
     }
@@ -438,6 +715,164 @@ impl CanisterSnapshot {
         &mut self.execution_snapshot
     }
 
+    /// Returns the metadata of this snapshot, i.e. everything but the contents
+    /// of the Wasm module, the memories and the Wasm chunks.
+    pub fn metadata(&self) -> ReadCanisterSnapshotMetadataResponse {
+        let memory_size = |memory: &PageMemory| {
+            num_bytes_try_from(memory.size)
+                .expect("could not convert from wasm memory number of pages to bytes")
+                .get()
+        };
+        ReadCanisterSnapshotMetadataResponse {
+            source: self.source,
+            taken_at_timestamp: self.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            wasm_module_size: self.canister_module().len() as u64,
+            exported_globals: self.exported_globals().clone(),
+            wasm_memory_size: memory_size(self.wasm_memory()),
+            stable_memory_size: memory_size(self.stable_memory()),
+            wasm_chunk_store: self
+                .chunk_store
+                .keys()
+                .map(|hash| ChunkHash {
+                    hash: hash.to_vec(),
+                })
+                .collect(),
+            canister_version: self.canister_version,
+            certified_data: self.certified_data.clone(),
+            global_timer: self.execution_snapshot.global_timer.map(GlobalTimer::from),
+            on_low_wasm_memory_hook_status: self.execution_snapshot.on_low_wasm_memory_hook_status,
+        }
+    }
+
+    /// Returns the piece of this snapshot at `cursor`, holding at most
+    /// `max_chunk_size` bytes of data, together with the cursor of the next
+    /// piece. Empty parts of the snapshot are skipped.
+    ///
+    /// Returns `None` once the snapshot has been exported completely (or the
+    /// export was aborted).
+    pub fn export_chunk(
+        &self,
+        cursor: CanisterSnapshotCloneCursor,
+        max_chunk_size: usize,
+    ) -> Option<(CanisterSnapshotChunk, CanisterSnapshotCloneCursor)> {
+        use CanisterSnapshotCloneCursor as Cursor;
+
+        let read_memory = |memory: &PageMemory, offset: u64| {
+            let memory_size = num_bytes_try_from(memory.size)
+                .expect("could not convert from wasm memory number of pages to bytes")
+                .get();
+            let len = memory_size
+                .saturating_sub(offset)
+                .min(max_chunk_size as u64);
+            let mut data = vec![0; len as usize];
+            Buffer::new(memory.page_map.clone()).read(&mut data, offset as usize);
+            data
+        };
+
+        let mut cursor = cursor;
+        loop {
+            match cursor {
+                Cursor::Metadata => {
+                    return Some((
+                        CanisterSnapshotChunk::Metadata(self.metadata()),
+                        Cursor::WasmModule { offset: 0 },
+                    ))
+                }
+                Cursor::WasmModule { offset } => {
+                    let module = self.canister_module().as_slice();
+                    let start = (offset as usize).min(module.len());
+                    let end = start.saturating_add(max_chunk_size).min(module.len());
+                    if start == end {
+                        cursor = Cursor::MainMemory { offset: 0 };
+                        continue;
+                    }
+                    return Some((
+                        CanisterSnapshotChunk::WasmModule {
+                            offset,
+                            data: module[start..end].to_vec(),
+                        },
+                        Cursor::WasmModule { offset: end as u64 },
+                    ));
+                }
+                Cursor::MainMemory { offset } => {
+                    let data = read_memory(self.wasm_memory(), offset);
+                    if data.is_empty() {
+                        cursor = Cursor::StableMemory { offset: 0 };
+                        continue;
+                    }
+                    let next = offset + data.len() as u64;
+                    return Some((
+                        CanisterSnapshotChunk::MainMemory { offset, data },
+                        Cursor::MainMemory { offset: next },
+                    ));
+                }
+                Cursor::StableMemory { offset } => {
+                    let data = read_memory(self.stable_memory(), offset);
+                    if data.is_empty() {
+                        cursor = Cursor::WasmChunk { index: 0 };
+                        continue;
+                    }
+                    let next = offset + data.len() as u64;
+                    return Some((
+                        CanisterSnapshotChunk::StableMemory { offset, data },
+                        Cursor::StableMemory { offset: next },
+                    ));
+                }
+                Cursor::WasmChunk { index } => {
+                    let Some(hash) = self.chunk_store.keys().nth(index as usize) else {
+                        cursor = Cursor::Finish;
+                        continue;
+                    };
+                    let data = self
+                        .chunk_store
+                        .get_chunk_data(hash)
+                        .expect("chunk listed in the chunk store")
+                        .flatten()
+                        .copied()
+                        .collect();
+                    return Some((
+                        CanisterSnapshotChunk::WasmChunk { data },
+                        Cursor::WasmChunk { index: index + 1 },
+                    ));
+                }
+                Cursor::Finish => return Some((CanisterSnapshotChunk::Finish, Cursor::Done)),
+                Cursor::Done | Cursor::Aborted | Cursor::Discarded => return None,
+            }
+        }
+    }
+
+    /// Returns the number of pieces `export_chunk` splits this snapshot into,
+    /// including the metadata and the final `Finish` piece.
+    pub fn export_chunk_count(&self, max_chunk_size: usize) -> u64 {
+        let max_chunk_size = max_chunk_size as u64;
+        let memory_size = |memory: &PageMemory| {
+            num_bytes_try_from(memory.size)
+                .expect("could not convert from wasm memory number of pages to bytes")
+                .get()
+        };
+        2 + (self.canister_module().len() as u64).div_ceil(max_chunk_size)
+            + memory_size(self.wasm_memory()).div_ceil(max_chunk_size)
+            + memory_size(self.stable_memory()).div_ceil(max_chunk_size)
+            + self.chunk_store.keys().count() as u64
+    }
+
+    /// Overwrites part of the Wasm module of a snapshot being imported.
+    pub fn write_wasm_module(&mut self, offset: u64, data: &[u8]) -> Result<(), String> {
+        self.execution_snapshot
+            .wasm_binary
+            .write(data, offset as usize)
+    }
+
+    /// Overwrites part of the Wasm memory of a snapshot being imported.
+    pub fn write_wasm_memory(&mut self, offset: u64, data: &[u8]) -> Result<(), String> {
+        write_memory(&mut self.execution_snapshot.wasm_memory, offset, data)
+    }
+
+    /// Overwrites part of the stable memory of a snapshot being imported.
+    pub fn write_stable_memory(&mut self, offset: u64, data: &[u8]) -> Result<(), String> {
+        write_memory(&mut self.execution_snapshot.stable_memory, offset, data)
+    }
+
     /// Returns the heap delta produced by this snapshot.
     ///
     /// The heap delta includes the delta of the wasm memory, stable memory and
@@ -457,6 +892,134 @@ impl CanisterSnapshot {
     }
 }
 
+/// Writes `data` at `offset` into `memory`, failing if it does not fit within
+/// the size of the memory.
+fn write_memory(memory: &mut PageMemory, offset: u64, data: &[u8]) -> Result<(), String> {
+    let memory_size = num_bytes_try_from(memory.size)?.get();
+    if offset
+        .checked_add(data.len() as u64)
+        .is_none_or(|end| end > memory_size)
+    {
+        return Err(format!(
+            "Write of {} bytes at offset {} exceeds the memory size of {} bytes",
+            data.len(),
+            offset,
+            memory_size
+        ));
+    }
+    let mut buffer = Buffer::new(memory.page_map.clone());
+    buffer.write(data, offset as usize);
+    memory.page_map = buffer.into_page_map();
+    Ok(())
+}
+
+/// The position within a snapshot that is being cloned into a canister on
+/// another subnet, i.e. the next piece of the snapshot to be transferred.
+///
+/// The pieces are transferred in the order of the variants.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum CanisterSnapshotCloneCursor {
+    Metadata,
+    WasmModule {
+        offset: u64,
+    },
+    MainMemory {
+        offset: u64,
+    },
+    StableMemory {
+        offset: u64,
+    },
+    WasmChunk {
+        index: u64,
+    },
+    /// All data has been transferred, the import is to be completed.
+    Finish,
+    /// The import has been completed.
+    Done,
+    /// The import has been aborted.
+    Aborted,
+    /// The clone failed and its caller has already been replied to; the
+    /// partially imported snapshot is being discarded.
+    Discarded,
+}
+
+/// Describes how the state of a canister differs from one of its snapshots.
+///
+/// Memory pages are OS pages of `PAGE_SIZE` bytes.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct CanisterSnapshotDiff {
+    /// Ranges of Wasm memory pages that are not shared with the snapshot.
+    pub wasm_memory: Vec<Range<u64>>,
+    /// Ranges of stable memory pages that are not shared with the snapshot.
+    pub stable_memory: Vec<Range<u64>>,
+    /// Whether the Wasm module differs.
+    pub wasm_module_changed: bool,
+    /// Whether the set of chunks in the Wasm chunk store differs.
+    pub wasm_chunk_store_changed: bool,
+    /// The number of bytes of the canister state that are shared with the
+    /// snapshot: shared memory pages within the size of both memories and
+    /// the Wasm module, if unchanged.
+    pub shared_bytes: NumBytes,
+}
+
+/// The difference between the memory of a snapshot and a canister memory.
+struct MemoryDiff {
+    changed_pages: Vec<Range<u64>>,
+    shared_bytes: NumBytes,
+}
+
+impl MemoryDiff {
+    /// The number of delta pages traversed by `MemoryDiff::new()`.
+    fn cost(snapshot_memory: &PageMemory, memory: &Memory) -> u64 {
+        (snapshot_memory.page_map.num_delta_pages() + memory.page_map.num_delta_pages()) as u64
+    }
+
+    fn new(snapshot_memory: &PageMemory, memory: &Memory) -> Self {
+        let Some(unshared_pages) = snapshot_memory.page_map.unshared_pages(&memory.page_map) else {
+            let num_pages = snapshot_memory
+                .page_map
+                .num_host_pages()
+                .max(memory.page_map.num_host_pages()) as u64;
+            return Self {
+                changed_pages: all_pages(num_pages),
+                shared_bytes: NumBytes::new(0),
+            };
+        };
+        let mut changed_pages: Vec<Range<u64>> = vec![];
+        for index in unshared_pages {
+            let index = index.get();
+            match changed_pages.last_mut() {
+                Some(range) if range.end == index => range.end += 1,
+                _ => changed_pages.push(index..index + 1),
+            }
+        }
+
+        // Only pages within the size of both memories can be shared.
+        let common_pages = num_bytes_try_from(snapshot_memory.size.min(memory.size))
+            .expect("could not convert from wasm memory number of pages to bytes")
+            .get()
+            / PAGE_SIZE as u64;
+        let changed_common_pages: u64 = changed_pages
+            .iter()
+            .map(|range| range.end.min(common_pages).saturating_sub(range.start))
+            .sum();
+        let shared_bytes = NumBytes::new((common_pages - changed_common_pages) * PAGE_SIZE as u64);
+
+        Self {
+            changed_pages,
+            shared_bytes,
+        }
+    }
+}
+
+/// A single range covering the first `num_pages` pages, if any.
+fn all_pages(num_pages: u64) -> Vec<Range<u64>> {
+    match num_pages {
+        0 => vec![],
+        _ => vec![0..num_pages],
+    }
+}
+
 /// Errors that can occur when trying to create a `CanisterSnapshot` from a canister.
 #[derive(Debug)]
 pub enum CanisterSnapshotError {
@@ -470,12 +1033,19 @@ pub enum SnapshotOperation {
     Delete(SnapshotId),
     Backup(CanisterId, SnapshotId),
     Restore(CanisterId, SnapshotId),
+    /// A new snapshot (second) sharing its contents with an existing one (first).
+    Duplicate(SnapshotId, SnapshotId),
+    /// A snapshot whose contents are written in place rather than copied from
+    /// a canister or another snapshot.
+    Import(SnapshotId),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use super::{CanisterSnapshot, CanisterSnapshots, PageMap};
+    use crate::canister_state::WASM_PAGE_SIZE_IN_BYTES;
+    use ic_sys::PageIndex;
     use ic_test_utilities_types::ids::canister_test_id;
     use ic_types::time::UNIX_EPOCH;
     use ic_types::NumBytes;
@@ -508,6 +1078,8 @@ mod tests {
             WasmChunkStore::new_for_testing(),
             execution_snapshot,
             NumBytes::from(0),
+            None,
+            false,
         );
 
         let snapshot_id = SnapshotId::from((canister_id, local_id));
@@ -554,6 +1126,67 @@ mod tests {
         assert_eq!(snapshot_manager.snapshot_ids.get(&canister_id), None);
     }
 
+    #[test]
+    fn test_is_base_snapshot() {
+        let canister_id = canister_test_id(0);
+        let (base_snapshot_id, base_snapshot) = fake_canister_snapshot(canister_id, 1);
+        let (snapshot_id, mut snapshot) = fake_canister_snapshot(canister_id, 2);
+        snapshot.base_snapshot = Some(base_snapshot_id);
+        let (other_snapshot_id, other_snapshot) = fake_canister_snapshot(canister_test_id(1), 3);
+
+        let mut snapshot_manager = CanisterSnapshots::default();
+        snapshot_manager.push(base_snapshot_id, Arc::new(base_snapshot));
+        snapshot_manager.push(snapshot_id, Arc::new(snapshot));
+        snapshot_manager.push(other_snapshot_id, Arc::new(other_snapshot));
+
+        assert!(snapshot_manager.is_base_snapshot(base_snapshot_id));
+        assert!(!snapshot_manager.is_base_snapshot(snapshot_id));
+        assert!(!snapshot_manager.is_base_snapshot(other_snapshot_id));
+
+        snapshot_manager.remove(snapshot_id);
+        assert!(!snapshot_manager.is_base_snapshot(base_snapshot_id));
+    }
+
+    #[test]
+    fn test_memory_diff() {
+        let ones = [1u8; PAGE_SIZE];
+        let twos = [2u8; PAGE_SIZE];
+        let mut page_map = PageMap::new_for_testing();
+        page_map.update(&[(PageIndex::new(0), &ones), (PageIndex::new(1), &ones)]);
+        let snapshot_memory = PageMemory {
+            page_map: page_map.clone(),
+            size: NumWasmPages::new(1),
+        };
+
+        page_map.update(&[
+            (PageIndex::new(1), &twos),
+            (PageIndex::new(2), &twos),
+            (PageIndex::new(20), &twos),
+        ]);
+        let memory = Memory::new(page_map, NumWasmPages::new(2));
+
+        // Both deltas are traversed.
+        assert_eq!(MemoryDiff::cost(&snapshot_memory, &memory), 6);
+        let diff = MemoryDiff::new(&snapshot_memory, &memory);
+        assert_eq!(diff.changed_pages, vec![1..3, 20..21]);
+        // One Wasm page is shared except for the two changed OS pages in it.
+        let os_pages_per_wasm_page = (WASM_PAGE_SIZE_IN_BYTES / PAGE_SIZE) as u64;
+        assert_eq!(
+            diff.shared_bytes,
+            NumBytes::from((os_pages_per_wasm_page - 2) * PAGE_SIZE as u64)
+        );
+
+        // A memory with identical contents but different storage shares nothing.
+        let mut other_page_map = PageMap::new_for_testing();
+        other_page_map.update(&[(PageIndex::new(0), &ones), (PageIndex::new(1), &ones)]);
+        let diff = MemoryDiff::new(
+            &snapshot_memory,
+            &Memory::new(other_page_map, NumWasmPages::new(1)),
+        );
+        assert_eq!(diff.changed_pages, vec![0..2]);
+        assert_eq!(diff.shared_bytes, NumBytes::from(0));
+    }
+
     #[test]
     fn test_construct_canister_snapshot_ids() {
         let snapshots: BTreeMap<_, _> = [
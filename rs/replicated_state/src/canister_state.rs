@@ -372,6 +372,7 @@ impl CanisterState {
             + self.canister_history_memory_usage()
             + self.wasm_chunk_store_memory_usage()
             + self.snapshots_memory_usage()
+            + self.named_timers_memory_usage()
     }
 
     /// Returns the amount of Wasm memory currently used by the canister in bytes.
@@ -437,6 +438,11 @@ impl CanisterState {
         self.system_state.wasm_chunk_store.memory_usage()
     }
 
+    /// Returns the memory usage of the named timers in bytes.
+    pub fn named_timers_memory_usage(&self) -> NumBytes {
+        self.system_state.named_timers.memory_usage()
+    }
+
     pub fn snapshots_memory_usage(&self) -> NumBytes {
         self.system_state.snapshots_memory_usage
     }
@@ -1088,7 +1088,7 @@ impl CanisterQueues {
     //
     // NOTE: DO NOT CHANGE THE VISIBILITY OF THIS METHOD. IT IS ONLY SUPPOSED TO BE
     // CALLED FOR CANISTERS (I.E. NOT FOR THE SUBNET QUEUES).
-    pub(super) fn push_output_request(
+    pub(crate) fn push_output_request(
         &mut self,
         request: Arc<Request>,
         time: Time,
@@ -1,9 +1,11 @@
 mod call_context_manager;
+pub mod method_profile;
 mod task_queue;
 pub mod wasm_chunk_store;
 
 pub use self::task_queue::{is_low_wasm_memory_hook_condition_satisfied, TaskQueue};
 
+use self::method_profile::MethodProfile;
 use self::wasm_chunk_store::{WasmChunkStore, WasmChunkStoreMetadata};
 use super::queues::{can_push, CanisterInput};
 pub use super::queues::{memory_usage_of_request, CanisterOutputQueuesIterator};
@@ -19,8 +21,8 @@ use ic_error_types::RejectCode;
 use ic_interfaces::execution_environment::HypervisorError;
 use ic_logger::{error, ReplicaLogger};
 use ic_management_canister_types_private::{
-    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterStatusType,
-    LogVisibilityV2,
+    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterLogLevel,
+    CanisterStatusType, LogVisibilityV2,
 };
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::canister_state_bits::v1 as pb;
@@ -35,8 +37,8 @@ use ic_types::methods::Callback;
 use ic_types::nominal_cycles::NominalCycles;
 use ic_types::time::CoarseTime;
 use ic_types::{
-    CanisterId, CanisterLog, CanisterTimer, Cycles, MemoryAllocation, NumBytes, NumInstructions,
-    PrincipalId, Time,
+    CanisterId, CanisterLog, CanisterTimer, CanisterTimers, Cycles, MemoryAllocation, NumBytes,
+    NumInstructions, PrincipalId, Time,
 };
 use ic_validate_eq::ValidateEq;
 use ic_validate_eq_derive::ValidateEq;
@@ -174,6 +176,7 @@ pub struct CanisterMetrics {
     pub interrupted_during_execution: u64,
     pub consumed_cycles: NominalCycles,
     consumed_cycles_by_use_cases: BTreeMap<CyclesUseCase, NominalCycles>,
+    pub method_profile: MethodProfile,
 }
 
 impl CanisterMetrics {
@@ -184,6 +187,7 @@ impl CanisterMetrics {
         interrupted_during_execution: u64,
         consumed_cycles: NominalCycles,
         consumed_cycles_by_use_cases: BTreeMap<CyclesUseCase, NominalCycles>,
+        method_profile: MethodProfile,
     ) -> Self {
         Self {
             scheduled_as_first,
@@ -192,6 +196,7 @@ impl CanisterMetrics {
             interrupted_during_execution,
             consumed_cycles,
             consumed_cycles_by_use_cases,
+            method_profile,
         }
     }
 
@@ -365,6 +370,10 @@ pub struct SystemState {
     /// Canister global timer.
     pub global_timer: CanisterTimer,
 
+    /// Named timers managed by the system on behalf of the canister. Expired
+    /// timers are executed via the `canister_global_timer` task.
+    pub named_timers: CanisterTimers,
+
     /// Canister version.
     pub canister_version: u64,
 
@@ -379,6 +388,11 @@ pub struct SystemState {
     /// Log visibility of the canister.
     pub log_visibility: LogVisibilityV2,
 
+    /// The minimum level of log records written by `ic0.log` that are kept.
+    /// Records with a lower level are dropped when they are written. Records
+    /// of `ic0.debug_print` and of traps are kept regardless of their level.
+    pub log_min_level: CanisterLogLevel,
+
     /// Log records of the canister.
     #[validate_eq(CompareWithValidateEq)]
     pub canister_log: CanisterLog,
@@ -772,10 +786,12 @@ impl SystemState {
             canister_metrics: CanisterMetrics::default(),
             task_queue: Default::default(),
             global_timer: CanisterTimer::Inactive,
+            named_timers: CanisterTimers::default(),
             canister_version: 0,
             canister_history: CanisterHistory::default(),
             wasm_chunk_store,
             log_visibility: Default::default(),
+            log_min_level: Default::default(),
             canister_log: Default::default(),
             wasm_memory_limit: None,
             next_snapshot_id: 0,
@@ -800,11 +816,13 @@ impl SystemState {
         reserved_balance_limit: Option<Cycles>,
         task_queue: TaskQueue,
         global_timer: CanisterTimer,
+        named_timers: CanisterTimers,
         canister_version: u64,
         canister_history: CanisterHistory,
         wasm_chunk_store_data: PageMap,
         wasm_chunk_store_metadata: WasmChunkStoreMetadata,
         log_visibility: LogVisibilityV2,
+        log_min_level: CanisterLogLevel,
         canister_log: CanisterLog,
         wasm_memory_limit: Option<NumBytes>,
         next_snapshot_id: u64,
@@ -827,6 +845,7 @@ impl SystemState {
             reserved_balance_limit,
             task_queue,
             global_timer,
+            named_timers,
             canister_version,
             canister_history,
             wasm_chunk_store: WasmChunkStore::from_checkpoint(
@@ -834,6 +853,7 @@ impl SystemState {
                 wasm_chunk_store_metadata,
             ),
             log_visibility,
+            log_min_level,
             canister_log,
             wasm_memory_limit,
             next_snapshot_id,
@@ -1143,6 +1163,35 @@ impl SystemState {
         self.queues.push_output_request(msg, time)
     }
 
+    /// Pushes the request carrying the next chunk of the chunked call with the
+    /// given callback into the output queue (see `push_output_request()`).
+    /// Returns `Ok(false)` if the call has no chunks left to send.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the canister is stopped or if the request could not
+    /// be enqueued. In the latter case, the chunk is dropped and the cycles it
+    /// carried are returned alongside the error, to be refunded.
+    pub fn push_next_chunk_request(
+        &mut self,
+        callback_id: CallbackId,
+        time: Time,
+    ) -> Result<bool, (StateError, Cycles)> {
+        let request = match call_context_manager_mut(&mut self.status)
+            .ok_or((
+                StateError::CanisterStopped(self.canister_id),
+                Cycles::zero(),
+            ))?
+            .take_next_chunk_request(callback_id)
+        {
+            Some(request) => request,
+            None => return Ok(false),
+        };
+        self.push_output_request(Arc::new(request), time)
+            .map_err(|(err, request)| (err, request.payment))?;
+        Ok(true)
+    }
+
     /// See documentation for [`CanisterQueues::reject_subnet_output_request`].
     pub fn reject_subnet_output_request(
         &mut self,
@@ -1571,9 +1620,14 @@ impl SystemState {
     }
 
     /// Returns the memory currently used by or reserved for guaranteed response
-    /// canister messages.
+    /// canister messages, including the chunks of chunked calls that remain to
+    /// be sent.
     pub fn guaranteed_response_message_memory_usage(&self) -> NumBytes {
-        (self.queues.guaranteed_response_memory_usage() as u64).into()
+        let pending_chunks_memory_usage = self
+            .call_context_manager()
+            .map_or(0, |ccm| ccm.pending_chunks_memory_usage());
+        ((self.queues.guaranteed_response_memory_usage() + pending_chunks_memory_usage) as u64)
+            .into()
     }
 
     /// Returns the memory currently used by best-effort canister messages.
@@ -2311,10 +2365,12 @@ pub mod testing {
             reserved_balance_limit: Default::default(),
             task_queue: Default::default(),
             global_timer: CanisterTimer::Inactive,
+            named_timers: Default::default(),
             canister_version: Default::default(),
             canister_history: Default::default(),
             wasm_chunk_store: WasmChunkStore::new_for_testing(),
             log_visibility: Default::default(),
+            log_min_level: Default::default(),
             canister_log: Default::default(),
             wasm_memory_limit: Default::default(),
             next_snapshot_id: Default::default(),
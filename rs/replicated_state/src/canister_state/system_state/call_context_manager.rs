@@ -231,6 +231,10 @@ pub(crate) struct CallContextManagerStats {
     /// The number of guaranteed response (i.e. `deadline == NO_DEADLINE`)
     /// callbacks.
     guaranteed_response_callback_count: usize,
+
+    /// The total byte size of the chunks of chunked calls that remain to be
+    /// sent.
+    pending_chunks_bytes: usize,
 }
 
 impl CallContextManagerStats {
@@ -272,6 +276,7 @@ impl CallContextManagerStats {
         if callback.deadline == NO_DEADLINE {
             self.guaranteed_response_callback_count += 1;
         }
+        self.pending_chunks_bytes += pending_chunks_bytes(callback);
     }
 
     /// Updates the stats following the invocation of a callback.
@@ -279,6 +284,7 @@ impl CallContextManagerStats {
         if callback.deadline == NO_DEADLINE {
             self.guaranteed_response_callback_count -= 1;
         }
+        self.pending_chunks_bytes -= pending_chunks_bytes(callback);
     }
 
     /// Calculates the stats for the given call contexts and callbacks.
@@ -312,11 +318,16 @@ impl CallContextManagerStats {
             .values()
             .filter(|callback| callback.deadline == NO_DEADLINE)
             .count();
+        let pending_chunks_bytes = callbacks
+            .values()
+            .map(|callback| pending_chunks_bytes(callback))
+            .sum();
 
         CallContextManagerStats {
             unresponded_canister_update_call_contexts,
             unresponded_guaranteed_response_call_contexts,
             guaranteed_response_callback_count,
+            pending_chunks_bytes,
         }
     }
 
@@ -799,6 +810,63 @@ impl CallContextManager {
         callback_id
     }
 
+    /// Takes the next chunk of the chunked call with the given callback and
+    /// returns the request carrying it. Returns `None` if the callback does
+    /// not exist or belongs to a call with no chunks left to send.
+    ///
+    /// The callback stays registered: the response to the returned request is
+    /// delivered to it, just like the responses to the previous chunks. The
+    /// cycles attached to the call are sent along with the last chunk.
+    pub(super) fn take_next_chunk_request(&mut self, callback_id: CallbackId) -> Option<Request> {
+        let mut callback = self.callbacks.remove(&callback_id)?;
+        let callback_mut = Arc::make_mut(&mut callback);
+        let (receiver, sender, cycles_sent) = (
+            callback_mut.respondent,
+            callback_mut.originator,
+            callback_mut.cycles_sent,
+        );
+        let request = callback_mut
+            .pending_chunks
+            .as_mut()
+            .and_then(|pending_chunks| {
+                let (payload_chunk, method_payload) = pending_chunks.pop()?;
+                Some(Request {
+                    receiver,
+                    sender,
+                    sender_reply_callback: callback_id,
+                    payment: if payload_chunk.is_last() {
+                        cycles_sent
+                    } else {
+                        Cycles::zero()
+                    },
+                    method_name: pending_chunks.method_name.clone(),
+                    method_payload,
+                    metadata: pending_chunks.metadata.with_payload_chunk(payload_chunk),
+                    deadline: NO_DEADLINE,
+                })
+            });
+        if callback_mut
+            .pending_chunks
+            .as_ref()
+            .is_some_and(|pending_chunks| pending_chunks.chunks.is_empty())
+        {
+            callback_mut.pending_chunks = None;
+        }
+        if let Some(request) = &request {
+            self.stats.pending_chunks_bytes -= request.method_payload.len();
+        }
+        self.callbacks.insert(callback_id, callback);
+        debug_assert!(self.stats_ok());
+
+        request
+    }
+
+    /// Returns the total byte size of the chunks of chunked calls that remain
+    /// to be sent.
+    pub fn pending_chunks_memory_usage(&self) -> usize {
+        self.stats.pending_chunks_bytes
+    }
+
     /// If we get a response for one of the outstanding calls, we unregister
     /// the callback and return it.
     pub(super) fn unregister_callback(&mut self, callback_id: CallbackId) -> Option<Arc<Callback>> {
@@ -1146,6 +1214,14 @@ impl TryFrom<pb::CallContextManager> for CallContextManager {
     }
 }
 
+/// Returns the byte size of the chunks of a chunked call that remain to be sent.
+fn pending_chunks_bytes(callback: &Callback) -> usize {
+    callback
+        .pending_chunks
+        .as_ref()
+        .map_or(0, |pending_chunks| pending_chunks.payload_size_bytes())
+}
+
 /// Calculates the deadlines of all best-effort callbacks.
 ///
 /// Time complexity: `O(n)`.
@@ -3,7 +3,11 @@ use ic_test_utilities_types::{
     ids::{canister_test_id, message_test_id, user_test_id},
     messages::{RequestBuilder, ResponseBuilder},
 };
-use ic_types::{messages::RequestMetadata, methods::WasmClosure, time::UNIX_EPOCH};
+use ic_types::{
+    messages::{PayloadChunk, RequestMetadata},
+    methods::{PendingPayloadChunks, WasmClosure},
+    time::UNIX_EPOCH,
+};
 use maplit::btreemap;
 
 #[test]
@@ -786,3 +790,76 @@ fn roundtrip_encode() {
 
     assert_eq!(ccm, decoded);
 }
+
+#[test]
+fn take_next_chunk_request() {
+    let mut ccm = CallContextManager::default();
+    let this = canister_test_id(13);
+    let other = canister_test_id(14);
+    let call_context_id = ccm.new_call_context(
+        CallOrigin::SystemTask,
+        Cycles::zero(),
+        Time::from_nanos_since_unix_epoch(0),
+        Default::default(),
+    );
+    let metadata = RequestMetadata::new(1, Time::from_nanos_since_unix_epoch(7));
+    let callback_id = ccm.register_callback(
+        Callback::new(
+            call_context_id,
+            this,
+            other,
+            Cycles::new(21),
+            Cycles::new(42),
+            Cycles::new(84),
+            WasmClosure::new(0, 1),
+            WasmClosure::new(2, 3),
+            None,
+            NO_DEADLINE,
+        )
+        .with_pending_chunks(PendingPayloadChunks {
+            method_name: "upload".to_string(),
+            metadata: metadata.clone(),
+            count: 3,
+            chunks: vec![vec![1; 10], vec![2; 5]].into(),
+        }),
+    );
+    assert_eq!(15, ccm.pending_chunks_memory_usage());
+
+    // Pending chunks are encoded along with the callback.
+    let encoded: pb::CallContextManager = (&ccm).into();
+    assert_eq!(ccm, encoded.try_into().unwrap());
+
+    // The second chunk carries no cycles.
+    let request = ccm.take_next_chunk_request(callback_id).unwrap();
+    assert_eq!(
+        (this, other, callback_id, Cycles::zero()),
+        (
+            request.sender,
+            request.receiver,
+            request.sender_reply_callback,
+            request.payment
+        )
+    );
+    assert_eq!("upload", request.method_name);
+    assert_eq!(vec![1; 10], request.method_payload);
+    assert_eq!(
+        Some(&PayloadChunk { index: 1, count: 3 }),
+        request.metadata.payload_chunk()
+    );
+    assert_eq!(5, ccm.pending_chunks_memory_usage());
+
+    // The last chunk carries the cycles attached to the call.
+    let request = ccm.take_next_chunk_request(callback_id).unwrap();
+    assert_eq!(Cycles::new(21), request.payment);
+    assert_eq!(vec![2; 5], request.method_payload);
+    assert_eq!(
+        Some(&PayloadChunk { index: 2, count: 3 }),
+        request.metadata.payload_chunk()
+    );
+    assert_eq!(0, ccm.pending_chunks_memory_usage());
+    assert!(ccm.callback(callback_id).unwrap().pending_chunks.is_none());
+
+    // No chunks left to send; the callback is still registered.
+    assert_eq!(None, ccm.take_next_chunk_request(callback_id));
+    assert!(ccm.unregister_callback(callback_id).is_some());
+}
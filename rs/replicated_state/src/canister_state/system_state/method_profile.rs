@@ -0,0 +1,221 @@
+use ic_protobuf::proxy::ProxyDecodeError;
+use ic_protobuf::state::canister_state_bits::v1 as pb;
+use ic_types::{Cycles, NumInstructions};
+use std::collections::BTreeMap;
+
+/// The maximum number of methods tracked in a `MethodProfile`. Executions of
+/// further methods are accounted under `OTHER_METHODS`.
+pub const MAX_PROFILED_METHODS: usize = 64;
+
+/// The name under which executions of methods beyond `MAX_PROFILED_METHODS`
+/// are accounted.
+pub const OTHER_METHODS: &str = "<other>";
+
+/// The exclusive upper bounds of the buckets of the instruction histogram.
+/// The last bucket (not listed) contains all executions with at least
+/// `10^10` instructions.
+pub const INSTRUCTION_BUCKET_BOUNDS: [u64; 7] = [
+    10_000,
+    100_000,
+    1_000_000,
+    10_000_000,
+    100_000_000,
+    1_000_000_000,
+    10_000_000_000,
+];
+
+/// The number of buckets of the instruction histogram.
+pub const NUM_INSTRUCTION_BUCKETS: usize = INSTRUCTION_BUCKET_BOUNDS.len() + 1;
+
+/// Aggregated execution statistics of a single method.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct MethodStats {
+    /// The number of completed executions of the method.
+    pub num_calls: u64,
+    /// The total number of instructions executed.
+    pub instructions: NumInstructions,
+    /// The maximum number of instructions executed by a single call.
+    pub max_instructions: NumInstructions,
+    /// The total amount of cycles charged for the executed instructions.
+    pub cycles: Cycles,
+    /// The total number of (OS) pages dirtied.
+    pub dirty_pages: u64,
+    /// The number of calls per bucket of `INSTRUCTION_BUCKET_BOUNDS`.
+    pub instruction_histogram: [u64; NUM_INSTRUCTION_BUCKETS],
+}
+
+impl MethodStats {
+    fn observe(&mut self, instructions: NumInstructions, cycles: Cycles, dirty_pages: u64) {
+        self.num_calls = self.num_calls.saturating_add(1);
+        self.instructions =
+            NumInstructions::from(self.instructions.get().saturating_add(instructions.get()));
+        self.max_instructions = self.max_instructions.max(instructions);
+        self.cycles += cycles;
+        self.dirty_pages = self.dirty_pages.saturating_add(dirty_pages);
+        let bucket = INSTRUCTION_BUCKET_BOUNDS
+            .iter()
+            .position(|bound| instructions.get() < *bound)
+            .unwrap_or(NUM_INSTRUCTION_BUCKETS - 1);
+        self.instruction_histogram[bucket] = self.instruction_histogram[bucket].saturating_add(1);
+    }
+}
+
+/// Execution statistics per exported method of a canister.
+///
+/// The statistics are part of the replicated state and are updated
+/// deterministically whenever the execution of a canister message or task
+/// completes. The number of tracked methods is bounded by
+/// `MAX_PROFILED_METHODS`.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct MethodProfile {
+    methods: BTreeMap<String, MethodStats>,
+}
+
+impl MethodProfile {
+    /// Records a completed execution of the given method.
+    pub fn observe(
+        &mut self,
+        method_name: &str,
+        instructions: NumInstructions,
+        cycles: Cycles,
+        dirty_pages: u64,
+    ) {
+        let method_name = if self.methods.contains_key(method_name)
+            || self.methods.len() < MAX_PROFILED_METHODS - 1
+        {
+            method_name
+        } else {
+            OTHER_METHODS
+        };
+        self.methods
+            .entry(method_name.to_string())
+            .or_default()
+            .observe(instructions, cycles, dirty_pages);
+    }
+
+    /// Returns the statistics of the given method (if any).
+    pub fn get(&self, method_name: &str) -> Option<&MethodStats> {
+        self.methods.get(method_name)
+    }
+
+    /// Returns the statistics of all methods ordered by method name.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &MethodStats)> {
+        self.methods.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty()
+    }
+
+    /// Removes the statistics of all methods.
+    pub fn clear(&mut self) {
+        self.methods.clear();
+    }
+}
+
+impl From<&MethodProfile> for pb::MethodProfile {
+    fn from(item: &MethodProfile) -> Self {
+        Self {
+            methods: item
+                .methods
+                .iter()
+                .map(|(method_name, stats)| pb::MethodProfileEntry {
+                    method_name: method_name.clone(),
+                    num_calls: stats.num_calls,
+                    instructions: stats.instructions.get(),
+                    max_instructions: stats.max_instructions.get(),
+                    cycles: Some(stats.cycles.into()),
+                    dirty_pages: stats.dirty_pages,
+                    instruction_histogram: stats.instruction_histogram.to_vec(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::MethodProfile> for MethodProfile {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb::MethodProfile) -> Result<Self, Self::Error> {
+        let mut methods = BTreeMap::new();
+        for entry in item.methods {
+            let num_buckets = entry.instruction_histogram.len();
+            let instruction_histogram = entry.instruction_histogram.try_into().map_err(|_| {
+                ProxyDecodeError::Other(format!(
+                    "Expected {} buckets in the instruction histogram of method {}, got {}",
+                    NUM_INSTRUCTION_BUCKETS, entry.method_name, num_buckets
+                ))
+            })?;
+            methods.insert(
+                entry.method_name,
+                MethodStats {
+                    num_calls: entry.num_calls,
+                    instructions: NumInstructions::from(entry.instructions),
+                    max_instructions: NumInstructions::from(entry.max_instructions),
+                    cycles: entry.cycles.map(|c| c.into()).unwrap_or_else(Cycles::zero),
+                    dirty_pages: entry.dirty_pages,
+                    instruction_histogram,
+                },
+            );
+        }
+        Ok(Self { methods })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observe_accumulates_statistics_and_histogram() {
+        let mut profile = MethodProfile::default();
+        profile.observe("a", NumInstructions::from(500), Cycles::new(10), 1);
+        profile.observe("a", NumInstructions::from(2_000_000), Cycles::new(20), 3);
+        profile.observe(
+            "a",
+            NumInstructions::from(20_000_000_000),
+            Cycles::new(30),
+            0,
+        );
+
+        let stats = profile.get("a").unwrap();
+        assert_eq!(stats.num_calls, 3);
+        assert_eq!(stats.instructions, NumInstructions::from(20_002_000_500));
+        assert_eq!(
+            stats.max_instructions,
+            NumInstructions::from(20_000_000_000)
+        );
+        assert_eq!(stats.cycles, Cycles::new(60));
+        assert_eq!(stats.dirty_pages, 4);
+        assert_eq!(stats.instruction_histogram, [1, 0, 0, 1, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn observe_bounds_the_number_of_methods() {
+        let mut profile = MethodProfile::default();
+        for i in 0..MAX_PROFILED_METHODS + 10 {
+            profile.observe(
+                &format!("m{}", i),
+                NumInstructions::from(1),
+                Cycles::zero(),
+                0,
+            );
+        }
+        // Known methods are still accounted under their own name.
+        profile.observe("m0", NumInstructions::from(1), Cycles::zero(), 0);
+
+        assert_eq!(profile.iter().count(), MAX_PROFILED_METHODS);
+        assert_eq!(profile.get("m0").unwrap().num_calls, 2);
+        assert_eq!(profile.get(OTHER_METHODS).unwrap().num_calls, 11);
+    }
+
+    #[test]
+    fn method_profile_proto_round_trip() {
+        let mut profile = MethodProfile::default();
+        profile.observe("a", NumInstructions::from(500), Cycles::new(10), 1);
+        profile.observe("b", NumInstructions::from(2_000_000), Cycles::new(20), 3);
+
+        let pb_profile = pb::MethodProfile::from(&profile);
+        assert_eq!(MethodProfile::try_from(pb_profile).unwrap(), profile);
+    }
+}
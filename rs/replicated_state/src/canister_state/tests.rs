@@ -20,7 +20,7 @@ use ic_logger::replica_logger::no_op_logger;
 use ic_management_canister_types_private::Global;
 use ic_management_canister_types_private::{
     BoundedAllowedViewers, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin,
-    CanisterLogRecord, LogVisibilityV2,
+    CanisterLogField, CanisterLogLevel, CanisterLogRecord, LogVisibilityV2,
 };
 use ic_metrics::MetricsRegistry;
 use ic_test_utilities_types::ids::{canister_test_id, message_test_id, user_test_id};
@@ -1020,6 +1020,11 @@ fn canister_state_canister_log_record_round_trip() {
         idx: 42,
         timestamp_nanos: 27,
         content: vec![1, 2, 3],
+        level: CanisterLogLevel::Warning,
+        fields: vec![CanisterLogField {
+            key: "key".to_string(),
+            value: "value".to_string(),
+        }],
     };
     let encoded = pb::CanisterLogRecord::from(&initial);
     let round_trip = CanisterLogRecord::from(encoded);
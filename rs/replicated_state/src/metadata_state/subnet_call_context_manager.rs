@@ -1,3 +1,4 @@
+use crate::canister_snapshots::CanisterSnapshotCloneCursor;
 use ic_btc_replica_types::{GetSuccessorsRequestInitial, SendTransactionRequest};
 use ic_logger::{info, ReplicaLogger};
 use ic_management_canister_types_private::{
@@ -15,7 +16,7 @@ use ic_types::{
     crypto::threshold_sig::ni_dkg::{id::ni_dkg_target_id, NiDkgId, NiDkgTargetId},
     messages::{CallbackId, CanisterCall, Request, StopCanisterCallId},
     node_id_into_protobuf, node_id_try_from_option, CanisterId, ExecutionRound, Height, NodeId,
-    RegistryVersion, Time,
+    RegistryVersion, SnapshotId, Time,
 };
 use phantom_newtype::Id;
 use std::{
@@ -40,6 +41,7 @@ pub enum SubnetCallContext {
     BitcoinGetSuccessors(BitcoinGetSuccessorsContext),
     BitcoinSendTransactionInternal(BitcoinSendTransactionInternalContext),
     SignWithThreshold(SignWithThresholdContext),
+    CanisterSnapshotClone(CanisterSnapshotCloneContext),
 }
 
 impl SubnetCallContext {
@@ -51,6 +53,7 @@ impl SubnetCallContext {
             SubnetCallContext::BitcoinGetSuccessors(context) => &context.request,
             SubnetCallContext::BitcoinSendTransactionInternal(context) => &context.request,
             SubnetCallContext::SignWithThreshold(context) => &context.request,
+            SubnetCallContext::CanisterSnapshotClone(context) => &context.request,
         }
     }
 
@@ -62,6 +65,7 @@ impl SubnetCallContext {
             SubnetCallContext::BitcoinGetSuccessors(context) => context.time,
             SubnetCallContext::BitcoinSendTransactionInternal(context) => context.time,
             SubnetCallContext::SignWithThreshold(context) => context.batch_time,
+            SubnetCallContext::CanisterSnapshotClone(context) => context.time,
         }
     }
 }
@@ -219,6 +223,7 @@ pub struct SubnetCallContextManager {
         BTreeMap<CallbackId, BitcoinSendTransactionInternalContext>,
     canister_management_calls: CanisterManagementCalls,
     pub raw_rand_contexts: VecDeque<RawRandContext>,
+    pub canister_snapshot_clone_contexts: BTreeMap<CallbackId, CanisterSnapshotCloneContext>,
 }
 
 impl SubnetCallContextManager {
@@ -253,6 +258,10 @@ impl SubnetCallContextManager {
                 self.bitcoin_send_transaction_internal_contexts
                     .insert(callback_id, context);
             }
+            SubnetCallContext::CanisterSnapshotClone(context) => {
+                self.canister_snapshot_clone_contexts
+                    .insert(callback_id, context);
+            }
         };
 
         callback_id
@@ -339,6 +348,20 @@ impl SubnetCallContextManager {
                         SubnetCallContext::BitcoinSendTransactionInternal(context)
                     })
             })
+            .or_else(|| {
+                self.canister_snapshot_clone_contexts
+                    .remove(&callback_id)
+                    .map(|context| {
+                        info!(
+                            logger,
+                            "Received the response for CloneCanisterSnapshotChunk with callback id {:?} for snapshot {} of {:?}",
+                            callback_id,
+                            context.snapshot_id,
+                            context.request.sender
+                        );
+                        SubnetCallContext::CanisterSnapshotClone(context)
+                    })
+            })
     }
 
     pub fn push_install_code_call(&mut self, call: InstallCodeCall) -> InstallCodeCallId {
@@ -568,6 +591,16 @@ impl From<&SubnetCallContextManager> for pb_metadata::SubnetCallContextManager {
                     },
                 )
                 .collect(),
+            canister_snapshot_clone_contexts: item
+                .canister_snapshot_clone_contexts
+                .iter()
+                .map(
+                    |(callback_id, context)| pb_metadata::CanisterSnapshotCloneContextTree {
+                        callback_id: callback_id.get(),
+                        context: Some(context.into()),
+                    },
+                )
+                .collect(),
         }
     }
 }
@@ -669,6 +702,17 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             raw_rand_contexts.push_back(context);
         }
 
+        let mut canister_snapshot_clone_contexts =
+            BTreeMap::<CallbackId, CanisterSnapshotCloneContext>::new();
+        for entry in item.canister_snapshot_clone_contexts {
+            let pb_context = try_from_option_field(
+                entry.context,
+                "SystemMetadata::CanisterSnapshotCloneContext",
+            )?;
+            let context = CanisterSnapshotCloneContext::try_from((time, pb_context))?;
+            canister_snapshot_clone_contexts.insert(CallbackId::new(entry.callback_id), context);
+        }
+
         Ok(Self {
             next_callback_id: item.next_callback_id,
             setup_initial_dkg_contexts,
@@ -682,6 +726,7 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             },
             raw_rand_contexts,
             reshare_chain_key_contexts,
+            canister_snapshot_clone_contexts,
         })
     }
 }
@@ -1391,6 +1436,112 @@ impl TryFrom<(Time, pb_metadata::RawRandContext)> for RawRandContext {
     }
 }
 
+/// Tracks a snapshot being cloned into a canister on another subnet. The
+/// snapshot is transferred one chunk at a time; the context is stored under
+/// the callback ID of the `clone_canister_snapshot_chunk` request in flight.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct CanisterSnapshotCloneContext {
+    /// The `clone_canister_snapshot` call being executed.
+    pub request: Request,
+    pub snapshot_id: SnapshotId,
+    pub target_canister_id: CanisterId,
+    /// The snapshot of the target canister to be replaced, if any.
+    pub replace_snapshot_id: Option<SnapshotId>,
+    /// The snapshot created by the target subnet, once the metadata chunk has
+    /// been acknowledged.
+    pub target_snapshot_id: Option<SnapshotId>,
+    /// The piece of the snapshot to transfer once the request in flight has
+    /// been acknowledged.
+    pub cursor: CanisterSnapshotCloneCursor,
+    pub time: Time,
+}
+
+impl From<&CanisterSnapshotCloneContext> for pb_metadata::CanisterSnapshotCloneContext {
+    fn from(context: &CanisterSnapshotCloneContext) -> Self {
+        use pb_metadata::CanisterSnapshotCloneCursorKind as Kind;
+        let (cursor_kind, cursor_position) = match context.cursor {
+            CanisterSnapshotCloneCursor::Metadata => (Kind::Metadata, 0),
+            CanisterSnapshotCloneCursor::WasmModule { offset } => (Kind::WasmModule, offset),
+            CanisterSnapshotCloneCursor::MainMemory { offset } => (Kind::MainMemory, offset),
+            CanisterSnapshotCloneCursor::StableMemory { offset } => (Kind::StableMemory, offset),
+            CanisterSnapshotCloneCursor::WasmChunk { index } => (Kind::WasmChunk, index),
+            CanisterSnapshotCloneCursor::Finish => (Kind::Finish, 0),
+            CanisterSnapshotCloneCursor::Done => (Kind::Done, 0),
+            CanisterSnapshotCloneCursor::Aborted => (Kind::Aborted, 0),
+            CanisterSnapshotCloneCursor::Discarded => (Kind::Discarded, 0),
+        };
+        Self {
+            request: Some((&context.request).into()),
+            snapshot_id: Some(context.snapshot_id.into()),
+            target_canister_id: Some(context.target_canister_id.into()),
+            replace_snapshot_id: context.replace_snapshot_id.map(Into::into),
+            target_snapshot_id: context.target_snapshot_id.map(Into::into),
+            cursor_kind: cursor_kind as i32,
+            cursor_position,
+            time: Some(pb_metadata::Time {
+                time_nanos: context.time.as_nanos_since_unix_epoch(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<(Time, pb_metadata::CanisterSnapshotCloneContext)> for CanisterSnapshotCloneContext {
+    type Error = ProxyDecodeError;
+    fn try_from(
+        (time, context): (Time, pb_metadata::CanisterSnapshotCloneContext),
+    ) -> Result<Self, Self::Error> {
+        use pb_metadata::CanisterSnapshotCloneCursorKind as Kind;
+        let request: Request =
+            try_from_option_field(context.request, "CanisterSnapshotCloneContext::request")?;
+        let snapshot_id: SnapshotId = try_from_option_field(
+            context.snapshot_id,
+            "CanisterSnapshotCloneContext::snapshot_id",
+        )?;
+        let target_canister_id: CanisterId = try_from_option_field(
+            context.target_canister_id,
+            "CanisterSnapshotCloneContext::target_canister_id",
+        )?;
+        let position = context.cursor_position;
+        let cursor = match Kind::try_from(context.cursor_kind).ok() {
+            Some(Kind::Metadata) => CanisterSnapshotCloneCursor::Metadata,
+            Some(Kind::WasmModule) => CanisterSnapshotCloneCursor::WasmModule { offset: position },
+            Some(Kind::MainMemory) => CanisterSnapshotCloneCursor::MainMemory { offset: position },
+            Some(Kind::StableMemory) => {
+                CanisterSnapshotCloneCursor::StableMemory { offset: position }
+            }
+            Some(Kind::WasmChunk) => CanisterSnapshotCloneCursor::WasmChunk { index: position },
+            Some(Kind::Finish) => CanisterSnapshotCloneCursor::Finish,
+            Some(Kind::Done) => CanisterSnapshotCloneCursor::Done,
+            Some(Kind::Aborted) => CanisterSnapshotCloneCursor::Aborted,
+            Some(Kind::Discarded) => CanisterSnapshotCloneCursor::Discarded,
+            Some(Kind::Unspecified) | None => {
+                return Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "CanisterSnapshotCloneCursorKind",
+                    err: format!("Unexpected value of cursor kind: {}", context.cursor_kind),
+                })
+            }
+        };
+
+        Ok(CanisterSnapshotCloneContext {
+            request,
+            snapshot_id,
+            target_canister_id,
+            replace_snapshot_id: context
+                .replace_snapshot_id
+                .map(SnapshotId::try_from)
+                .transpose()?,
+            target_snapshot_id: context
+                .target_snapshot_id
+                .map(SnapshotId::try_from)
+                .transpose()?,
+            cursor,
+            time: context
+                .time
+                .map_or(time, |t| Time::from_nanos_since_unix_epoch(t.time_nanos)),
+        })
+    }
+}
+
 mod testing {
     use super::*;
 
@@ -1428,6 +1579,7 @@ mod testing {
             bitcoin_send_transaction_internal_contexts: Default::default(),
             canister_management_calls,
             raw_rand_contexts: Default::default(),
+            canister_snapshot_clone_contexts: Default::default(),
         };
     }
 }
@@ -602,6 +602,42 @@ impl PageMap {
         })
     }
 
+    /// Returns the indices of the host pages that are not physically shared
+    /// between this `PageMap` and `other`, in ascending order, or `None` if
+    /// the two page maps are not backed by the same storage, in which case no
+    /// page is known to be shared.
+    ///
+    /// Page contents are never compared: a page is shared if neither page map
+    /// overrides it in its delta, or if both deltas hold the same page (e.g.
+    /// because one page map is a clone of the other). Hence only the deltas
+    /// are traversed, see `num_delta_pages()`.
+    pub fn unshared_pages(&self, other: &PageMap) -> Option<Vec<PageIndex>> {
+        if !self.storage.is_shared_with(&other.storage) {
+            return None;
+        }
+        let is_shared = |index: &PageIndex, page: &Page, other_delta: &PageDelta| {
+            other_delta
+                .get_page(*index)
+                .is_some_and(|other_page| std::ptr::eq(page.contents(), other_page))
+        };
+        let mut pages: Vec<PageIndex> = self
+            .page_delta
+            .iter()
+            .filter(|(index, page)| !is_shared(index, page, &other.page_delta))
+            .map(|(index, _)| *index)
+            .chain(
+                other
+                    .page_delta
+                    .iter()
+                    .filter(|(index, page)| !is_shared(index, page, &self.page_delta))
+                    .map(|(index, _)| *index),
+            )
+            .collect();
+        pages.sort_unstable();
+        pages.dedup();
+        Some(pages)
+    }
+
     /// Returns the iterator over delta pages in this `PageMap`
     pub fn delta_pages_iter(&self) -> impl Iterator<Item = (&PageIndex, &PageBytes)> + '_ {
         self.page_delta
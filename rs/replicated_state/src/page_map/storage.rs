@@ -214,6 +214,12 @@ impl Storage {
         self.init_or_die().get_page(page_index)
     }
 
+    /// Whether `self` and `other` are backed by the same loaded or unloaded
+    /// files, i.e. one is a clone of the other.
+    pub fn is_shared_with(&self, other: &Storage) -> bool {
+        Arc::ptr_eq(&self.storage_impl, &other.storage_impl)
+    }
+
     pub fn get_base_memory_instructions(&self) -> MemoryInstructions {
         self.init_or_die().get_base_memory_instructions()
     }
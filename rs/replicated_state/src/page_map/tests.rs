@@ -116,6 +116,36 @@ fn new_delta_wins_on_update() {
     assert_eq!(page_map.get_page(PageIndex::new(1)), &page_2);
 }
 
+#[test]
+fn unshared_pages_reports_pages_not_shared_with_the_clone() {
+    let mut page_map = PageMap::new_for_testing();
+    let ones = [1u8; PAGE_SIZE];
+    let twos = [2u8; PAGE_SIZE];
+    page_map.update(&[(PageIndex::new(1), &ones), (PageIndex::new(2), &ones)]);
+
+    let mut other = page_map.clone();
+    assert_eq!(page_map.unshared_pages(&other), Some(vec![]));
+
+    // Pages are not compared, so rewriting identical contents unshares a page.
+    other.update(&[
+        (PageIndex::new(1), &ones),
+        (PageIndex::new(2), &twos),
+        (PageIndex::new(5), &ones),
+    ]);
+    page_map.update(&[(PageIndex::new(7), &ones)]);
+    let unshared = vec![
+        PageIndex::new(1),
+        PageIndex::new(2),
+        PageIndex::new(5),
+        PageIndex::new(7),
+    ];
+    assert_eq!(page_map.unshared_pages(&other), Some(unshared.clone()));
+    assert_eq!(other.unshared_pages(&page_map), Some(unshared));
+
+    // Page maps with different storage share nothing.
+    assert_eq!(page_map.unshared_pages(&PageMap::new_for_testing()), None);
+}
+
 #[test]
 fn persisted_map_is_equivalent_to_the_original() {
     fn persist_check_eq_and_load(
@@ -27,7 +27,9 @@ use ic_registry_subnet_type::SubnetType;
 use ic_types::{
     batch::{ConsensusResponse, RawQueryStats},
     ingress::IngressStatus,
-    messages::{CallbackId, CanisterMessage, Ingress, MessageId, RequestOrResponse, Response},
+    messages::{
+        CallbackId, CanisterMessage, Ingress, MessageId, Request, RequestOrResponse, Response,
+    },
     time::CoarseTime,
     AccumulatedPriority, CanisterId, Cycles, MemoryAllocation, NumBytes, SubnetId, Time,
 };
@@ -761,6 +763,7 @@ impl ReplicatedState {
             wasm_custom_sections_memory_taken,
             canister_history_memory_taken,
             wasm_chunk_store_memory_usage,
+            named_timers_memory_usage,
         ) = self
             .canisters_iter()
             .map(|canister| {
@@ -776,6 +779,7 @@ impl ReplicatedState {
                     canister.wasm_custom_sections_memory_usage(),
                     canister.canister_history_memory_usage(),
                     canister.wasm_chunk_store_memory_usage(),
+                    canister.named_timers_memory_usage(),
                 )
             })
             .reduce(|accum, val| {
@@ -786,6 +790,7 @@ impl ReplicatedState {
                     accum.3 + val.3,
                     accum.4 + val.4,
                     accum.5 + val.5,
+                    accum.6 + val.6,
                 )
             })
             .unwrap_or_default();
@@ -801,6 +806,7 @@ impl ReplicatedState {
             execution: raw_memory_taken
                 + canister_history_memory_taken
                 + wasm_chunk_store_memory_usage
+                + named_timers_memory_usage
                 + canister_snapshots_memory_taken,
             guaranteed_response_messages: guaranteed_response_message_memory_taken,
             best_effort_messages: best_effort_message_memory_taken,
@@ -926,6 +932,24 @@ impl ReplicatedState {
                             input_queue_type,
                         ),
 
+                        // Responses to requests sent by the subnet itself, i.e. chunks
+                        // of canister snapshots cloned to other subnets.
+                        RequestOrResponse::Response(response)
+                            if self
+                                .metadata
+                                .subnet_call_context_manager
+                                .canister_snapshot_clone_contexts
+                                .contains_key(&response.originator_reply_callback) =>
+                        {
+                            push_input(
+                                &mut self.subnet_queues,
+                                msg,
+                                subnet_available_guaranteed_response_memory,
+                                own_subnet_type,
+                                input_queue_type,
+                            )
+                        }
+
                         RequestOrResponse::Response(response) => Err((
                             StateError::non_matching_response(
                                 "Management canister does not accept canister responses",
@@ -1001,6 +1025,20 @@ impl ReplicatedState {
         self.subnet_queues.push_output_response(msg)
     }
 
+    /// Pushes a `Request` sent by the subnet itself into the relevant subnet
+    /// output queue, reserving a slot in the matching input queue for the
+    /// `Response`.
+    ///
+    /// The callback of the request must be tracked by the subnet call context
+    /// manager for the response to be accepted.
+    pub fn push_subnet_output_request(
+        &mut self,
+        msg: Arc<Request>,
+        time: Time,
+    ) -> Result<(), (StateError, Arc<Request>)> {
+        self.subnet_queues.push_output_request(msg, time)
+    }
+
     /// Returns a circular iterator that consumes messages from all canisters'
     /// and the subnet's output queues.
     ///
@@ -1403,10 +1441,11 @@ fn subnet_input_into_canister_message(input: CanisterInput) -> CanisterMessage {
     match input {
         CanisterInput::Ingress(ingress) => CanisterMessage::Ingress(ingress),
         CanisterInput::Request(request) => CanisterMessage::Request(request),
-        CanisterInput::Response(_)
-        | CanisterInput::DeadlineExpired(_)
-        | CanisterInput::ResponseDropped(_) => {
-            unreachable!("Subnet input queues should never hold responses")
+        // Only requests sent by the subnet itself, with guaranteed responses,
+        // get responses enqueued.
+        CanisterInput::Response(response) => CanisterMessage::Response(response),
+        CanisterInput::DeadlineExpired(_) | CanisterInput::ResponseDropped(_) => {
+            unreachable!("Subnet input queues should never hold best-effort responses")
         }
     }
 }
@@ -1,7 +1,7 @@
 use ic_base_types::{NumBytes, NumSeconds};
 use ic_logger::{error, info, warn, ReplicaLogger};
 use ic_management_canister_types_private::{
-    Global, LogVisibilityV2, OnLowWasmMemoryHookStatus, SnapshotSource,
+    CanisterLogLevel, Global, LogVisibilityV2, OnLowWasmMemoryHookStatus, SnapshotSource,
 };
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_protobuf::{
@@ -16,7 +16,8 @@ use ic_replicated_state::{
     canister_state::{
         execution_state::{NextScheduledMethod, WasmMetadata},
         system_state::{
-            wasm_chunk_store::WasmChunkStoreMetadata, CanisterHistory, CyclesUseCase, TaskQueue,
+            method_profile::MethodProfile, wasm_chunk_store::WasmChunkStoreMetadata,
+            CanisterHistory, CyclesUseCase, TaskQueue,
         },
     },
     page_map::{Shard, StorageLayout, StorageResult},
@@ -25,7 +26,7 @@ use ic_replicated_state::{
 use ic_sys::{fs::sync_path, mmap::ScopedMmap};
 use ic_types::{
     batch::TotalQueryStats, nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId,
-    CanisterLog, CanisterTimer, ComputeAllocation, Cycles, ExecutionRound, Height,
+    CanisterLog, CanisterTimer, CanisterTimers, ComputeAllocation, Cycles, ExecutionRound, Height,
     LongExecutionMode, MemoryAllocation, NumInstructions, PrincipalId, SnapshotId, Time,
 };
 use ic_utils::thread::maybe_parallel_map;
@@ -177,11 +178,14 @@ pub struct CanisterStateBits {
     pub wasm_chunk_store_metadata: WasmChunkStoreMetadata,
     pub total_query_stats: TotalQueryStats,
     pub log_visibility: LogVisibilityV2,
+    pub log_min_level: CanisterLogLevel,
     pub canister_log: CanisterLog,
     pub wasm_memory_limit: Option<NumBytes>,
     pub next_snapshot_id: u64,
     pub snapshots_memory_usage: NumBytes,
     pub task_queue: TaskQueue,
+    pub method_profile: MethodProfile,
+    pub named_timers: CanisterTimers,
 }
 
 /// This struct contains bits of the `CanisterSnapshot` that are not already
@@ -216,6 +220,10 @@ pub struct CanisterSnapshotBits {
     pub global_timer: Option<CanisterTimer>,
     /// The state of the low memory hook.
     pub on_low_wasm_memory_hook_status: Option<OnLowWasmMemoryHookStatus>,
+    /// The snapshot an incremental snapshot was taken against.
+    pub base_snapshot_id: Option<SnapshotId>,
+    /// Whether the snapshot is still being cloned from another subnet.
+    pub import_in_progress: bool,
 }
 
 #[derive(Clone)]
@@ -1403,6 +1411,42 @@ impl StateLayout {
         }
     }
 
+    /// Copies the checkpoint located at `src` into the (new) directory `dst`,
+    /// retaining only the state and the snapshots of `canister_id`, i.e. `dst`
+    /// is a checkpoint hosting that single canister.
+    ///
+    /// NOTE: If the function returns an error, the changes to the file
+    /// system applied by this function are not undone.
+    pub fn copy_and_sync_canister_checkpoint(
+        &self,
+        src: &Path,
+        dst: &Path,
+        canister_id: &CanisterId,
+    ) -> std::io::Result<()> {
+        if dst.exists() {
+            return Err(Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                dst.display().to_string(),
+            ));
+        }
+
+        copy_recursively(
+            &self.log,
+            &self.metrics,
+            src,
+            dst,
+            FSync::Yes,
+            |path| {
+                let relative_path = path.strip_prefix(src).unwrap_or(path);
+                match canister_id_from_path(relative_path) {
+                    Some(id) if id != *canister_id => CopyInstruction::Skip,
+                    _ => CopyInstruction::ReadOnly,
+                }
+            },
+            None,
+        )
+    }
+
     /// Renames a path to a temporary path and synchronizes the parent directory of the original path.
     ///
     /// This helper function is useful when removing a checkpoint because renaming the checkpoint to a temporary path
@@ -2603,10 +2647,14 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
                 .map(|record| record.into())
                 .collect(),
             next_canister_log_record_idx: item.canister_log.next_idx(),
+            log_min_level: pb_canister_state_bits::CanisterLogLevel::from(item.log_min_level)
+                .into(),
             wasm_memory_limit: item.wasm_memory_limit.map(|v| v.get()),
             next_snapshot_id: item.next_snapshot_id,
             snapshots_memory_usage: item.snapshots_memory_usage.get(),
             tasks: Some((&item.task_queue).into()),
+            method_profile: Some((&item.method_profile).into()),
+            named_timers: Some((&item.named_timers).into()),
         }
     }
 }
@@ -2666,6 +2714,17 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
 
         let task_queue = TaskQueue::try_from(tasks)?;
 
+        let method_profile = value
+            .method_profile
+            .map(MethodProfile::try_from)
+            .transpose()?
+            .unwrap_or_default();
+
+        let named_timers = value
+            .named_timers
+            .map(CanisterTimers::from)
+            .unwrap_or_default();
+
         Ok(Self {
             controllers,
             last_full_execution_round: value.last_full_execution_round.into(),
@@ -2736,6 +2795,10 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 "CanisterStateBits::log_visibility_v2",
             )
             .unwrap_or_default(),
+            log_min_level: CanisterLogLevel::from_proto_or(
+                value.log_min_level,
+                CanisterLogLevel::default(),
+            ),
             canister_log: CanisterLog::new(
                 value.next_canister_log_record_idx,
                 value
@@ -2748,6 +2811,8 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             next_snapshot_id: value.next_snapshot_id,
             snapshots_memory_usage: NumBytes::from(value.snapshots_memory_usage),
             task_queue,
+            method_profile,
+            named_timers,
         })
     }
 }
@@ -2843,6 +2908,10 @@ impl From<CanisterSnapshotBits> for pb_canister_snapshot_bits::CanisterSnapshotB
                 .on_low_wasm_memory_hook_status
                 .map(|x| pb_canister_state_bits::OnLowWasmMemoryHookStatus::from(&x).into()),
             source: pb_canister_snapshot_bits::SnapshotSource::from(item.source).into(),
+            base_snapshot_id: item
+                .base_snapshot_id
+                .map(|snapshot_id| snapshot_id.get_local_snapshot_id()),
+            import_in_progress: item.import_in_progress,
         }
     }
 }
@@ -2903,6 +2972,10 @@ impl TryFrom<pb_canister_snapshot_bits::CanisterSnapshotBits> for CanisterSnapsh
             global_timer,
             on_low_wasm_memory_hook_status,
             source,
+            base_snapshot_id: item
+                .base_snapshot_id
+                .map(|local_id| SnapshotId::from((canister_id, local_id))),
+            import_in_progress: item.import_in_progress,
         })
     }
 }
@@ -2970,7 +3043,8 @@ enum FSync {
 }
 
 /// Recursively copies `src` to `dst` using the given permission policy for
-/// files. If a thread-pool is provided then files are copied in parallel.
+/// files and directories (a skipped directory is skipped with all of its
+/// contents). If a thread-pool is provided then files are copied in parallel.
 /// Syncs the target files if `fsync` is set to true.
 ///
 /// NOTE: If the function returns an error, the changes to the file
@@ -3109,7 +3183,7 @@ struct CopyAndSyncFile {
 
 #[derive(PartialEq, Eq)]
 enum CopyInstruction {
-    /// The file doesn't need to be copied
+    /// The file (or directory) doesn't need to be copied
     Skip,
     /// The file needs to be copied and should be readonly at the destination
     ReadOnly,
@@ -3129,6 +3203,10 @@ where
 {
     let src_metadata = src.metadata()?;
 
+    if file_copy_instruction(src) == CopyInstruction::Skip {
+        return Ok(());
+    }
+
     if src_metadata.is_dir() {
         // First create the target directory.
         plan.create_and_sync_dir.push(CreateAndSyncDir {
@@ -3143,9 +3221,6 @@ where
             build_copy_plan(&entry.path(), &dst_entry, file_copy_instruction, plan)?;
         }
     } else {
-        if file_copy_instruction(src) == CopyInstruction::Skip {
-            return Ok(());
-        }
         plan.copy_and_sync_file.push(CopyAndSyncFile {
             src: PathBuf::from(src),
             dst: PathBuf::from(dst),
@@ -19,6 +19,7 @@ use itertools::Itertools;
 use proptest::prelude::*;
 use std::fs::File;
 use std::sync::Arc;
+use std::time::Duration;
 
 fn default_canister_state_bits() -> CanisterStateBits {
     CanisterStateBits {
@@ -49,6 +50,8 @@ fn default_canister_state_bits() -> CanisterStateBits {
         install_code_debit: NumInstructions::from(0),
         time_of_last_allocation_charge_nanos: 0,
         task_queue: TaskQueue::default(),
+        method_profile: MethodProfile::default(),
+        named_timers: CanisterTimers::default(),
         global_timer_nanos: None,
         canister_version: 0,
         consumed_cycles_by_use_cases: BTreeMap::new(),
@@ -56,6 +59,7 @@ fn default_canister_state_bits() -> CanisterStateBits {
         wasm_chunk_store_metadata: WasmChunkStoreMetadata::default(),
         total_query_stats: TotalQueryStats::default(),
         log_visibility: Default::default(),
+        log_min_level: Default::default(),
         canister_log: Default::default(),
         wasm_memory_limit: None,
         next_snapshot_id: 0,
@@ -218,6 +222,8 @@ fn test_canister_snapshots_decode() {
         source: SnapshotSource::TakenFromCanister,
         global_timer: Some(CanisterTimer::Inactive),
         on_low_wasm_memory_hook_status: Some(OnLowWasmMemoryHookStatus::ConditionNotSatisfied),
+        base_snapshot_id: Some(SnapshotId::from((canister_id, 2))),
+        import_in_progress: true,
     };
 
     let pb_bits =
@@ -890,6 +896,51 @@ fn test_encode_decode_empty_task_queue() {
     assert_eq!(canister_state_bits.task_queue, task_queue);
 }
 
+#[test]
+fn test_encode_decode_method_profile() {
+    let mut method_profile = MethodProfile::default();
+    method_profile.observe("update", NumInstructions::from(1_000), Cycles::new(10), 2);
+    method_profile.observe(
+        "canister_heartbeat",
+        NumInstructions::from(50),
+        Cycles::new(1),
+        0,
+    );
+    let canister_state_bits = CanisterStateBits {
+        method_profile: method_profile.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.method_profile, method_profile);
+}
+
+#[test]
+fn test_encode_decode_named_timers() {
+    let mut named_timers = CanisterTimers::default();
+    named_timers
+        .set("once".to_string(), UNIX_EPOCH, None)
+        .unwrap();
+    named_timers
+        .set(
+            "periodic".to_string(),
+            UNIX_EPOCH + Duration::from_secs(10),
+            Some(Duration::from_secs(60)),
+        )
+        .unwrap();
+    let canister_state_bits = CanisterStateBits {
+        named_timers: named_timers.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.named_timers, named_timers);
+}
+
 #[test]
 fn test_encode_decode_non_empty_task_queue() {
     let mut task_queue = TaskQueue::default();
@@ -23,7 +23,8 @@ use ic_crypto_tree_hash::{sparse_labeled_tree_from_paths, Label, Path as Labeled
 use ic_crypto_utils_threshold_sig_der::threshold_sig_public_key_to_der;
 use ic_cycles_account_manager::CyclesAccountManager;
 pub use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::{ExecutionServices, IngressHistoryReaderImpl};
+use ic_execution_environment::{ExecutionServices, IngressHistoryReaderImpl, MessageProfiler};
+pub use ic_execution_environment::{MessageProfile, UNKNOWN_METHOD_NAME};
 use ic_http_endpoints_public::{metrics::HttpHandlerMetrics, IngressWatcher, IngressWatcherHandle};
 use ic_https_outcalls_consensus::payload_builder::CanisterHttpPayloadBuilderImpl;
 use ic_ingress_manager::{IngressManager, RandomStateKind};
@@ -33,7 +34,10 @@ use ic_interfaces::{
     certification::{Verifier, VerifierError},
     consensus::{PayloadBuilder as ConsensusPayloadBuilder, PayloadValidationError},
     consensus_pool::ConsensusTime,
-    execution_environment::{IngressFilterService, IngressHistoryReader, QueryExecutionService},
+    execution_environment::{
+        ChainKeyData, ExecutionRoundSummary, ExecutionRoundType, IngressFilterService,
+        IngressHistoryReader, QueryExecutionService, RegistryExecutionSettings, Scheduler,
+    },
     ingress_pool::{
         IngressPool, IngressPoolObject, PoolSection, UnvalidatedIngressArtifact,
         ValidatedIngressArtifact,
@@ -100,6 +104,7 @@ use ic_replicated_state::{
     canister_state::{system_state::CyclesUseCase, NumWasmPages, WASM_PAGE_SIZE_IN_BYTES},
     metadata_state::subnet_call_context_manager::{SignWithThresholdContext, ThresholdArguments},
     page_map::Buffer,
+    replicated_state::ReplicatedStateMessageRouting,
     CheckpointLoadingMetrics, Memory, PageMap, ReplicatedState,
 };
 use ic_state_layout::{CheckpointLayout, ReadOnly};
@@ -138,13 +143,13 @@ use ic_types::{
     messages::{
         Blob, Certificate, CertificateDelegation, HttpCallContent, HttpCanisterUpdate,
         HttpRequestEnvelope, Payload as MsgPayload, Query, QuerySource, RejectContext,
-        SignedIngress, EXPECTED_MESSAGE_ID_LENGTH,
+        RequestOrResponse, SignedIngress, EXPECTED_MESSAGE_ID_LENGTH,
     },
     signature::ThresholdSignature,
     time::GENESIS,
-    xnet::{CertifiedStreamSlice, StreamIndex},
-    CanisterLog, CountBytes, CryptoHashOfPartialState, Height, NodeId, Randomness, RegistryVersion,
-    ReplicaVersion,
+    xnet::{CertifiedStreamSlice, RejectReason, StreamIndex},
+    CanisterLog, CountBytes, CryptoHashOfPartialState, ExecutionRound, Height, NodeId, Randomness,
+    RegistryVersion, ReplicaVersion,
 };
 use ic_types::{
     canister_http::{
@@ -157,8 +162,8 @@ use ic_types::{
     CanisterId, CryptoHashOfState, Cycles, NumBytes, PrincipalId, SubnetId, UserId,
 };
 use ic_xnet_payload_builder::{
-    certified_slice_pool::CertifiedSlicePool, refill_stream_slice_indices, RefillTaskHandle,
-    XNetPayloadBuilderImpl, XNetPayloadBuilderMetrics, XNetSlicePoolImpl,
+    certified_slice_pool::CertifiedSlicePool, refill_stream_slice_indices, ExpectedIndices,
+    RefillTaskHandle, XNetPayloadBuilderImpl, XNetPayloadBuilderMetrics, XNetSlicePoolImpl,
 };
 use rcgen::{CertificateParams, KeyPair};
 use serde::Deserialize;
@@ -640,6 +645,131 @@ pub trait Subnets: Send + Sync {
     fn get(&self, subnet_id: SubnetId) -> Option<Arc<StateMachine>>;
 }
 
+/// The effect of an `XNetFault` on the matching XNet messages.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum XNetFaultKind {
+    /// Messages are lost: the destination subnet acknowledges them to the source subnet
+    /// without inducting them. Callbacks for lost best-effort messages eventually expire.
+    /// Losing guaranteed-response messages breaks the response guarantee of the IC,
+    /// i.e., the corresponding calls never complete.
+    Drop,
+    /// Messages are delayed by the given number of rounds of the destination subnet
+    /// once they reach the head of their stream. Because streams are ordered,
+    /// all subsequent messages of the same stream are delayed, too.
+    Delay { rounds: u64 },
+    /// Requests are rejected by the destination subnet as if the input queue of
+    /// the receiver was full, i.e., the caller gets a `SYS_TRANSIENT` reject response.
+    /// Responses are not affected.
+    Reject,
+    /// The destination subnet receives no stream slices (neither messages nor signals)
+    /// from the source subnet for as long as the fault is set, as if the source subnet
+    /// was unavailable. If the fault only applies to a canister, then the messages
+    /// sent by or to that canister (and all subsequent messages of the same stream)
+    /// are held back instead.
+    Unavailable,
+}
+
+/// A fault injected into the XNet messages inducted by a `StateMachine`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct XNetFault {
+    /// The fault only applies to messages from this subnet (or from all subnets if `None`).
+    pub source: Option<SubnetId>,
+    /// The fault only applies to messages sent by or to this canister
+    /// (or to all messages if `None`).
+    pub canister: Option<CanisterId>,
+    pub kind: XNetFaultKind,
+}
+
+impl XNetFault {
+    fn matches(&self, remote_subnet_id: SubnetId, msg: &RequestOrResponse) -> bool {
+        self.source.is_none_or(|source| source == remote_subnet_id)
+            && self
+                .canister
+                .is_none_or(|canister| canister == msg.sender() || canister == msg.receiver())
+    }
+}
+
+/// The XNet faults of a `StateMachine` together with the remaining number of rounds
+/// for which the delayed messages at the head of their streams are delayed.
+#[derive(Default)]
+struct XNetFaults {
+    faults: Vec<XNetFault>,
+    delayed: BTreeMap<(SubnetId, StreamIndex), u64>,
+}
+
+/// How the stream from a remote subnet is affected by `XNetFaults` in a round.
+enum FaultedSlice {
+    /// No slice from the remote subnet is inducted.
+    Unavailable,
+    /// Only the first `msg_limit` messages after `expected_indices` are inducted.
+    /// They are followed by `signals` for the dropped (`None`) and rejected (`Some`)
+    /// messages that come next in the stream.
+    Truncated {
+        expected_indices: ExpectedIndices,
+        msg_limit: usize,
+        signals: Vec<Option<RejectReason>>,
+    },
+}
+
+/// The signals for the XNet messages dropped or rejected by `XNetFaults` in the current
+/// round, keyed by remote subnet, together with the stream index of the first signal.
+type XNetFaultSignals = BTreeMap<SubnetId, (StreamIndex, Vec<Option<RejectReason>>)>;
+
+/// A `Scheduler` that pushes the `XNetFaultSignals` of the round onto the streams before
+/// executing the round, i.e., right after the stream handler inducted the (truncated)
+/// stream slices of the round, so that the dropped and rejected messages are signalled
+/// as part of the same batch as if the stream handler had not inducted them.
+struct XNetFaultsScheduler {
+    scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
+    signals: Arc<Mutex<XNetFaultSignals>>,
+}
+
+impl Scheduler for XNetFaultsScheduler {
+    type State = ReplicatedState;
+
+    fn execute_round(
+        &self,
+        mut state: ReplicatedState,
+        randomness: Randomness,
+        chain_key_data: ChainKeyData,
+        replica_version: &ReplicaVersion,
+        current_round: ExecutionRound,
+        round_summary: Option<ExecutionRoundSummary>,
+        current_round_type: ExecutionRoundType,
+        registry_settings: &RegistryExecutionSettings,
+    ) -> ReplicatedState {
+        let signals = std::mem::take(&mut *self.signals.lock().unwrap());
+        if !signals.is_empty() {
+            let mut streams = state.take_streams();
+            for (subnet_id, (signals_begin, signals)) in signals {
+                let stream = streams.entry(subnet_id).or_default();
+                // The XNet payload did not contain all messages preceding the dropped
+                // and rejected messages: they are handled in a later round.
+                if stream.signals_end() != signals_begin {
+                    continue;
+                }
+                for signal in signals {
+                    match signal {
+                        Some(reason) => stream.push_reject_signal(reason),
+                        None => stream.push_accept_signal(),
+                    }
+                }
+            }
+            state.put_streams(streams);
+        }
+        self.scheduler.execute_round(
+            state,
+            randomness,
+            chain_key_data,
+            replica_version,
+            current_round,
+            round_summary,
+            current_round_type,
+            registry_settings,
+        )
+    }
+}
+
 /// Struct mocking the XNet layer.
 struct PocketXNetImpl {
     /// Pool of `StateMachine`s from which XNet messages are fetched.
@@ -663,11 +793,21 @@ impl PocketXNetImpl {
         }
     }
 
-    fn refill(&self, registry_version: RegistryVersion, log: ReplicaLogger) {
+    /// Refills the certified slice pool from all remote subnets
+    /// except for the given subnets whose slices are affected by `XNetFaults`.
+    fn refill(
+        &self,
+        registry_version: RegistryVersion,
+        log: ReplicaLogger,
+        faulted_subnets: &BTreeSet<SubnetId>,
+    ) {
         let refill_stream_slice_indices =
             refill_stream_slice_indices(self.pool.clone(), self.own_subnet_id);
 
         for (subnet_id, indices) in refill_stream_slice_indices {
+            if faulted_subnets.contains(&subnet_id) {
+                continue;
+            }
             let sm = self.subnets.get(subnet_id).unwrap();
             match sm.generate_certified_stream_slice(
                 self.own_subnet_id,
@@ -698,6 +838,148 @@ impl PocketXNetImpl {
             }
         }
     }
+
+    /// Determines how the streams from all remote subnets are affected by the given
+    /// `XNetFaults` in the next round given the (latest certified) state of the
+    /// `StateMachine` for which the XNet layer is mocked.
+    fn faulted_slices(
+        &self,
+        state: &ReplicatedState,
+        xnet_faults: &mut XNetFaults,
+    ) -> BTreeMap<SubnetId, FaultedSlice> {
+        let peers: Vec<_> = self
+            .pool
+            .lock()
+            .unwrap()
+            .peers()
+            .filter(|&&subnet_id| subnet_id != self.own_subnet_id)
+            .cloned()
+            .collect();
+
+        let mut faulted_slices = BTreeMap::new();
+        for subnet_id in peers {
+            if xnet_faults.faults.iter().any(|fault| {
+                fault.kind == XNetFaultKind::Unavailable
+                    && fault.canister.is_none()
+                    && fault.source.is_none_or(|source| source == subnet_id)
+            }) {
+                faulted_slices.insert(subnet_id, FaultedSlice::Unavailable);
+                continue;
+            }
+
+            let remote_state = self.subnets.get(subnet_id).unwrap().get_latest_state();
+            let Some(remote_stream) = remote_state.streams().get(&self.own_subnet_id) else {
+                continue;
+            };
+            let expected_indices = state
+                .streams()
+                .get(&subnet_id)
+                .map(|stream| ExpectedIndices {
+                    message_index: stream.signals_end(),
+                    signal_index: stream.messages_begin(),
+                })
+                .unwrap_or_default();
+
+            let mut msg_limit = 0;
+            let mut signals = vec![];
+            let mut truncated = false;
+            for (index, msg) in remote_stream
+                .messages()
+                .iter()
+                .skip_while(|(index, _)| *index < expected_indices.message_index)
+            {
+                let at_head = msg_limit == 0 && signals.is_empty();
+                let fault = xnet_faults.faults.iter().find(|fault| {
+                    fault.matches(subnet_id, msg)
+                        && match fault.kind {
+                            XNetFaultKind::Reject => matches!(msg, RequestOrResponse::Request(_)),
+                            XNetFaultKind::Drop
+                            | XNetFaultKind::Delay { .. }
+                            | XNetFaultKind::Unavailable => true,
+                        }
+                });
+                match fault.map(|fault| fault.kind) {
+                    None if signals.is_empty() => msg_limit += 1,
+                    Some(XNetFaultKind::Drop) => signals.push(None),
+                    Some(XNetFaultKind::Reject) => signals.push(Some(RejectReason::QueueFull)),
+                    Some(XNetFaultKind::Delay { rounds }) if at_head => {
+                        // Only the delay of the message at the head of the stream counts down.
+                        let remaining = xnet_faults
+                            .delayed
+                            .entry((subnet_id, index))
+                            .or_insert(rounds);
+                        if *remaining > 0 {
+                            *remaining -= 1;
+                            truncated = true;
+                            break;
+                        }
+                        xnet_faults.delayed.remove(&(subnet_id, index));
+                        msg_limit += 1;
+                    }
+                    // Messages following dropped or rejected messages, delayed messages
+                    // not at the head of the stream, and messages held back by an
+                    // unavailable canister are not inducted in this round.
+                    None | Some(XNetFaultKind::Delay { .. }) | Some(XNetFaultKind::Unavailable) => {
+                        truncated = true;
+                        break;
+                    }
+                }
+            }
+
+            if !signals.is_empty() || truncated {
+                faulted_slices.insert(
+                    subnet_id,
+                    FaultedSlice::Truncated {
+                        expected_indices,
+                        msg_limit,
+                        signals,
+                    },
+                );
+            }
+        }
+        faulted_slices
+    }
+
+    /// Replaces the pooled slices from the remote subnets whose streams are truncated
+    /// by `XNetFaults` with slices only containing the messages to be inducted.
+    fn put_faulted_slices(
+        &self,
+        faulted_slices: &BTreeMap<SubnetId, FaultedSlice>,
+        registry_version: RegistryVersion,
+        log: ReplicaLogger,
+    ) {
+        for (subnet_id, faulted_slice) in faulted_slices {
+            let FaultedSlice::Truncated {
+                expected_indices,
+                msg_limit,
+                ..
+            } = faulted_slice
+            else {
+                continue;
+            };
+            self.pool
+                .lock()
+                .unwrap()
+                .garbage_collect_slice(*subnet_id, expected_indices.clone());
+            let sm = self.subnets.get(*subnet_id).unwrap();
+            match sm.generate_certified_stream_slice(
+                self.own_subnet_id,
+                Some(expected_indices.message_index),
+                Some(expected_indices.message_index),
+                Some(*msg_limit),
+                None,
+            ) {
+                Ok(slice) => self
+                    .pool
+                    .lock()
+                    .unwrap()
+                    .put(*subnet_id, slice, registry_version, log.clone())
+                    .unwrap(),
+                Err(EncodeStreamError::NoStreamForSubnet(_)) => (),
+                Err(err) => panic!("Unexpected XNetClient error: {}", err),
+            }
+        }
+    }
 }
 
 /// A custom `QueryStatsPayloadBuilderImpl` that uses a single
@@ -859,6 +1141,9 @@ pub struct StateMachine {
     ingress_manager: Arc<IngressManager>,
     pub ingress_filter: Arc<Mutex<IngressFilterService>>,
     pocket_xnet: Arc<RwLock<Option<PocketXNetImpl>>>,
+    xnet_faults: RwLock<XNetFaults>,
+    xnet_fault_signals: Arc<Mutex<XNetFaultSignals>>,
+    message_profiler: Arc<MessageProfiler>,
     payload_builder: Arc<RwLock<Option<PayloadBuilderImpl>>>,
     message_routing: SyncMessageRouting,
     pub metrics_registry: MetricsRegistry,
@@ -1423,6 +1708,77 @@ impl StateMachine {
         self.do_execute_round(Some(blockmaker_metrics));
     }
 
+    /// Replaces the XNet faults injected into the XNet messages inducted by this `StateMachine`.
+    /// Only applies to a `StateMachine` created using `StateMachineBuilder::build_with_subnets`.
+    pub fn set_xnet_faults(&self, faults: Vec<XNetFault>) {
+        *self.xnet_faults.write().unwrap() = XNetFaults {
+            faults,
+            delayed: BTreeMap::new(),
+        };
+    }
+
+    /// Returns the XNet faults injected into the XNet messages inducted by this `StateMachine`.
+    pub fn xnet_faults(&self) -> Vec<XNetFault> {
+        self.xnet_faults.read().unwrap().faults.clone()
+    }
+
+    /// Enables or disables recording the cost of every canister message
+    /// completing its execution on this `StateMachine`.
+    pub fn set_message_profiling(&self, enabled: bool) {
+        self.message_profiler.set_enabled(enabled);
+    }
+
+    /// Returns whether the cost of completed canister messages is recorded.
+    pub fn message_profiling(&self) -> bool {
+        self.message_profiler.is_enabled()
+    }
+
+    /// Returns the cost of all canister messages that completed their execution
+    /// on this `StateMachine` while message profiling was enabled.
+    pub fn message_profiles(&self) -> Vec<MessageProfile> {
+        self.message_profiler.profiles()
+    }
+
+    /// Pools stream slices truncated according to the XNet faults of this `StateMachine`
+    /// and stages the signals for the dropped and rejected messages following the
+    /// truncated slices, to be pushed by the `XNetFaultsScheduler` in the next round.
+    /// Returns how the streams from the remote subnets are affected in the next round.
+    fn apply_xnet_faults(&self, state: &ReplicatedState) -> BTreeMap<SubnetId, FaultedSlice> {
+        let mut xnet_faults = self.xnet_faults.write().unwrap();
+        if xnet_faults.faults.is_empty() {
+            return BTreeMap::new();
+        }
+        let pocket_xnet = self.pocket_xnet.read().unwrap();
+        let Some(pocket_xnet) = pocket_xnet.as_ref() else {
+            return BTreeMap::new();
+        };
+
+        let faulted_slices = pocket_xnet.faulted_slices(state, &mut xnet_faults);
+        pocket_xnet.put_faulted_slices(
+            &faulted_slices,
+            self.registry_client.get_latest_version(),
+            self.replica_logger.clone(),
+        );
+        *self.xnet_fault_signals.lock().unwrap() = faulted_slices
+            .iter()
+            .filter_map(|(subnet_id, faulted_slice)| match faulted_slice {
+                FaultedSlice::Truncated {
+                    expected_indices,
+                    msg_limit,
+                    signals,
+                } if !signals.is_empty() => Some((
+                    *subnet_id,
+                    (
+                        expected_indices.message_index + (*msg_limit as u64).into(),
+                        signals.clone(),
+                    ),
+                )),
+                _ => None,
+            })
+            .collect();
+        faulted_slices
+    }
+
     /// Assemble a payload for a new round using `PayloadBuilderImpl`
     /// and execute a round with this payload.
     /// Note that only ingress messages submitted via `Self::submit_ingress`
@@ -1442,6 +1798,9 @@ impl StateMachine {
             .unwrap()
             .take();
 
+        // XNet faults only affect the stream slices of this round.
+        let faulted_slices = self.apply_xnet_faults(&state);
+
         // Build a payload for the round using `PayloadBuilderImpl`.
         let registry_version = self.registry_client.get_latest_version();
         let validation_context = ValidationContext {
@@ -1458,12 +1817,11 @@ impl StateMachine {
             membership_version: subnet_record.clone(),
             context_version: subnet_record,
         };
-        self.pocket_xnet
-            .read()
-            .unwrap()
-            .as_ref()
-            .unwrap()
-            .refill(registry_version, self.replica_logger.clone());
+        self.pocket_xnet.read().unwrap().as_ref().unwrap().refill(
+            registry_version,
+            self.replica_logger.clone(),
+            &faulted_slices.keys().cloned().collect(),
+        );
         let payload_builder = self.payload_builder.read().unwrap();
         let payload_builder = payload_builder.as_ref().unwrap();
         let batch_payload = payload_builder.get_payload(
@@ -1475,7 +1833,13 @@ impl StateMachine {
 
         // Convert payload produced by `PayloadBuilderImpl` into `PayloadBuilder`
         // used by the function `Self::execute_payload` of the `StateMachine`.
-        let xnet_payload = batch_payload.xnet.clone();
+        let mut xnet_payload = batch_payload.xnet.clone();
+        xnet_payload.stream_slices.retain(|subnet_id, _| {
+            !matches!(
+                faulted_slices.get(subnet_id),
+                Some(FaultedSlice::Unavailable)
+            )
+        });
         let ingress = &batch_payload.ingress;
         let ingress_messages = ingress.clone().try_into().unwrap();
         let (http_responses, _) =
@@ -1726,11 +2090,15 @@ impl StateMachine {
             )
         });
 
+        let xnet_fault_signals = Arc::new(Mutex::new(XNetFaultSignals::new()));
         let message_routing = SyncMessageRouting::new(
             Arc::clone(&state_manager) as _,
             Arc::clone(&state_manager) as _,
             Arc::clone(&execution_services.ingress_history_writer) as _,
-            execution_services.scheduler,
+            Box::new(XNetFaultsScheduler {
+                scheduler: execution_services.scheduler,
+                signals: Arc::clone(&xnet_fault_signals),
+            }),
             hypervisor_config,
             cycles_account_manager.clone(),
             subnet_id,
@@ -1926,6 +2294,9 @@ impl StateMachine {
             ingress_manager: ingress_manager.clone(),
             ingress_filter: Arc::new(Mutex::new(execution_services.ingress_filter)),
             pocket_xnet: Arc::new(RwLock::new(None)), // set by `StateMachineBuilder::build_with_subnets`
+            xnet_faults: RwLock::new(XNetFaults::default()),
+            xnet_fault_signals,
+            message_profiler: execution_services.message_profiler,
             payload_builder: Arc::new(RwLock::new(None)), // set by `StateMachineBuilder::build_with_subnets`
             ingress_history_reader: execution_services.ingress_history_reader,
             message_routing,
@@ -2929,6 +3300,56 @@ impl StateMachine {
         self.set_checkpoint_interval_length(checkpoint_interval_length);
     }
 
+    /// Copies a checkpoint of the latest state into `target_dir`. A new
+    /// `StateMachine` whose state directory is `target_dir` starts from that
+    /// checkpoint.
+    ///
+    /// If the latest state is not a checkpoint yet, it is checkpointed at its
+    /// own height, i.e., neither a round is executed (so that pending ingress
+    /// messages are not inducted) nor the height of this state machine changes.
+    /// Checkpoint files are read-only and hard-linked, so the two state machines
+    /// share them copy-on-write.
+    pub fn fork_state_dir(&self, target_dir: &Path) -> io::Result<()> {
+        let h = self.state_manager.latest_state_height();
+        if !self.state_manager.checkpoint_heights().contains(&h) {
+            // The tip is the latest state, so committing it again at the same
+            // height only writes the checkpoint.
+            let (tip_height, state) = self.state_manager.take_tip();
+            assert_eq!(tip_height, h);
+            self.state_manager
+                .commit_and_certify(state, h, CertificationScope::Full, None);
+        }
+        // Wait until the checkpoint has been written and verified.
+        self.state_manager.flush_tip_channel();
+
+        let state_layout = self.state_manager.state_layout();
+        let checkpoint = state_layout
+            .checkpoint_verified(h)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+        let checkpoints_dir = target_dir.join(ic_state_layout::CHECKPOINTS_DIR);
+        std::fs::create_dir_all(&checkpoints_dir)?;
+        let name = ic_state_layout::StateLayout::checkpoint_name(h);
+        state_layout.copy_and_sync_checkpoint(
+            &format!("fork_{}", name),
+            checkpoint.raw_path(),
+            &checkpoints_dir.join(&name),
+            None,
+        )
+    }
+
+    /// Returns all ingress messages in the ingress pool. Messages that have
+    /// already been inducted are filtered out by the ingress manager when
+    /// building a payload.
+    pub fn pending_ingress_messages(&self) -> Vec<SignedIngress> {
+        self.ingress_pool
+            .read()
+            .unwrap()
+            .validated
+            .values()
+            .map(|artifact| artifact.msg.signed_ingress.clone())
+            .collect()
+    }
+
     /// Replaces the canister state in this state machine with the canister
     /// state in given source replicated state.
     ///
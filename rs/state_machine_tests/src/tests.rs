@@ -1,4 +1,7 @@
+use crate::StateMachine;
+use ic_interfaces_state_manager::StateReader;
 use ic_secp256k1::{DerivationIndex, DerivationPath, PrivateKey, PublicKey};
+use ic_state_layout::{StateLayout, CHECKPOINTS_DIR};
 use proptest::{collection::vec as pvec, prelude::*, prop_assert};
 
 #[test_strategy::proptest]
@@ -93,3 +96,29 @@ fn public_derivation_path() {
         "03fda02786d72d691d807a10a3de60522b664472ec2f06a704cc34ebe2fc26724c"
     );
 }
+
+#[test]
+fn fork_state_dir_does_not_change_the_latest_height() {
+    let env = StateMachine::new();
+    env.tick();
+    let height = env.state_manager.latest_state_height();
+
+    let fork_dir = tempfile::TempDir::new().unwrap();
+    env.fork_state_dir(fork_dir.path()).unwrap();
+    assert_eq!(env.state_manager.latest_state_height(), height);
+    assert!(env.state_manager.checkpoint_heights().contains(&height));
+    let checkpoint = fork_dir
+        .path()
+        .join(CHECKPOINTS_DIR)
+        .join(StateLayout::checkpoint_name(height));
+    assert!(checkpoint.is_dir());
+
+    // Forking again reuses the existing checkpoint.
+    let other_fork_dir = tempfile::TempDir::new().unwrap();
+    env.fork_state_dir(other_fork_dir.path()).unwrap();
+    assert_eq!(env.state_manager.latest_state_height(), height);
+
+    // The original state machine keeps executing rounds.
+    env.tick();
+    assert_eq!(env.state_manager.latest_state_height(), height.increment());
+}
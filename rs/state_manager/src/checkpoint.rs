@@ -376,9 +376,12 @@ pub(crate) fn flush_canister_snapshots_and_page_maps(
     let snapshot_operations = tip_state.canister_snapshots.take_unflushed_changes();
 
     for op in &snapshot_operations {
-        // Only CanisterSnapshots that are new since the last flush will have PageMaps that need to be flushed. They will
-        // have a corresponding Backup in the snapshot operations list.
-        if let SnapshotOperation::Backup(_canister_id, snapshot_id) = op {
+        // Only CanisterSnapshots that are new or imported since the last flush will have PageMaps that need to be flushed.
+        // They will have a corresponding Backup, Duplicate or Import in the snapshot operations list.
+        if let SnapshotOperation::Backup(_, snapshot_id)
+        | SnapshotOperation::Duplicate(_, snapshot_id)
+        | SnapshotOperation::Import(snapshot_id) = op
+        {
             // If we can't find the CanisterSnapshot they must have been already deleted again. Nothing to flush in this case.
             if let Some(canister_snapshot) = tip_state.canister_snapshots.get_mut(*snapshot_id) {
                 let new_snapshot = Arc::make_mut(canister_snapshot);
@@ -846,6 +849,7 @@ pub fn load_canister_state(
         canister_state_bits.interrupted_during_execution,
         canister_state_bits.consumed_cycles,
         canister_state_bits.consumed_cycles_by_use_cases,
+        canister_state_bits.method_profile,
     );
 
     let starting_time = Instant::now();
@@ -873,11 +877,13 @@ pub fn load_canister_state(
         canister_state_bits.reserved_balance_limit,
         canister_state_bits.task_queue,
         CanisterTimer::from_nanos_since_unix_epoch(canister_state_bits.global_timer_nanos),
+        canister_state_bits.named_timers,
         canister_state_bits.canister_version,
         canister_state_bits.canister_history,
         wasm_chunk_store_data,
         canister_state_bits.wasm_chunk_store_metadata,
         canister_state_bits.log_visibility,
+        canister_state_bits.log_min_level,
         canister_state_bits.canister_log,
         canister_state_bits.wasm_memory_limit,
         canister_state_bits.next_snapshot_id,
@@ -1017,6 +1023,8 @@ pub fn load_snapshot(
         wasm_chunk_store,
         execution_snapshot,
         canister_snapshot_bits.total_size,
+        canister_snapshot_bits.base_snapshot_id,
+        canister_snapshot_bits.import_in_progress,
     );
 
     let metrics = LoadCanisterMetrics { durations };
@@ -456,6 +456,13 @@ fn flush_snapshot_changes<T>(
             SnapshotOperation::Restore(canister_id, snapshot_id) => {
                 restore(log, layout, canister_id, snapshot_id)?;
             }
+            SnapshotOperation::Duplicate(source_snapshot_id, snapshot_id) => {
+                duplicate(log, layout, source_snapshot_id, snapshot_id)?;
+            }
+            SnapshotOperation::Import(_snapshot_id) => {
+                // The contents of an imported snapshot are written entirely
+                // by `FlushPageMapDelta` and `serialize_snapshot_to_tip`.
+            }
         }
     }
 
@@ -499,6 +506,43 @@ fn backup<T>(
     Ok(())
 }
 
+/// Represent a duplicate operation on disk.
+/// When a snapshot is cloned on the same subnet, execution creates a `CanisterSnapshot` sharing all its `PageMaps` as well as
+/// its wasm binary with the source snapshot.
+/// This function will run at an unspecified point afterwards (but before the next checkpoint) and it copies all files the
+/// source snapshot had in the tip to the new snapshot directory. Like for backups, unflushed deltas are flushed on top afterwards.
+fn duplicate<T>(
+    log: &ReplicaLogger,
+    layout: &CheckpointLayout<RwPolicy<T>>,
+    source_snapshot_id: SnapshotId,
+    snapshot_id: SnapshotId,
+) -> Result<(), LayoutError> {
+    let source_layout = layout.snapshot(&source_snapshot_id)?;
+    let snapshot_layout = layout.snapshot(&snapshot_id)?;
+
+    PageMapLayout::copy_or_hardlink_files(
+        log,
+        &source_layout.vmemory_0(),
+        &snapshot_layout.vmemory_0(),
+    )?;
+    PageMapLayout::copy_or_hardlink_files(
+        log,
+        &source_layout.stable_memory(),
+        &snapshot_layout.stable_memory(),
+    )?;
+    PageMapLayout::copy_or_hardlink_files(
+        log,
+        &source_layout.wasm_chunk_store(),
+        &snapshot_layout.wasm_chunk_store(),
+    )?;
+
+    // The source wasm binary might not be on disk yet, in which case it is written by
+    // `serialize_snapshot_to_tip` for both snapshots.
+    WasmFile::hardlink_file(&source_layout.wasm(), &snapshot_layout.wasm())?;
+
+    Ok(())
+}
+
 /// Represent a restore operation on disk.
 /// When a restore is triggered, execution creates a `CanisterState` from a `CanisterSnapshot` by copying all its `PageMaps` as well as its wasm binary.
 /// This function will run at an unspecified point afterwards (but before the next checkpoint) and it copies all files the snapshot had in the tip
@@ -976,6 +1020,12 @@ fn serialize_canister_to_tip(
                 .time_of_last_allocation_charge
                 .as_nanos_since_unix_epoch(),
             task_queue: canister_state.system_state.task_queue.clone(),
+            method_profile: canister_state
+                .system_state
+                .canister_metrics
+                .method_profile
+                .clone(),
+            named_timers: canister_state.system_state.named_timers.clone(),
             global_timer_nanos: canister_state
                 .system_state
                 .global_timer
@@ -994,6 +1044,7 @@ fn serialize_canister_to_tip(
                 .clone(),
             total_query_stats: canister_state.scheduler_state.total_query_stats.clone(),
             log_visibility: canister_state.system_state.log_visibility.clone(),
+            log_min_level: canister_state.system_state.log_min_level,
             canister_log: canister_state.system_state.canister_log.clone(),
             wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
             next_snapshot_id: canister_state.system_state.next_snapshot_id,
@@ -1033,6 +1084,8 @@ fn serialize_snapshot_to_tip(
             on_low_wasm_memory_hook_status: canister_snapshot
                 .execution_snapshot()
                 .on_low_wasm_memory_hook_status,
+            base_snapshot_id: canister_snapshot.base_snapshot(),
+            import_in_progress: canister_snapshot.import_in_progress(),
         }
         .into(),
     )?;
@@ -6141,6 +6141,7 @@ fn restore_heap_from_snapshot() {
         .take_canister_snapshot(TakeCanisterSnapshotArgs {
             canister_id: canister_id.into(),
             replace_snapshot: None,
+            base_snapshot: None,
         })
         .unwrap()
         .snapshot_id();
@@ -6213,6 +6214,7 @@ fn restore_stable_memory_from_snapshot() {
         .take_canister_snapshot(TakeCanisterSnapshotArgs {
             canister_id: canister_id.into(),
             replace_snapshot: None,
+            base_snapshot: None,
         })
         .unwrap()
         .snapshot_id();
@@ -6285,6 +6287,7 @@ fn restore_binary_from_snapshot() {
         .take_canister_snapshot(TakeCanisterSnapshotArgs {
             canister_id: canister_id.into(),
             replace_snapshot: None,
+            base_snapshot: None,
         })
         .unwrap()
         .snapshot_id();
@@ -6355,6 +6358,7 @@ fn restore_chunk_store_from_snapshot() {
         .take_canister_snapshot(TakeCanisterSnapshotArgs {
             canister_id: canister_id.into(),
             replace_snapshot: None,
+            base_snapshot: None,
         })
         .unwrap()
         .snapshot_id();
@@ -1,12 +1,16 @@
 //! Command implementations.
+pub mod audit;
+pub mod canister_diff;
 pub mod cdiff;
 pub mod chash;
 pub mod convert_ids;
 pub mod copy;
 pub mod decode;
+pub mod extract_canister;
 pub mod import_state;
 pub mod list;
 pub mod manifest;
+pub mod query;
 pub mod split;
 pub mod split_manifest;
 mod utils;
@@ -0,0 +1,514 @@
+//! Audits the integrity of a checkpoint directory.
+//!
+//! Unlike `verify_manifest`, which checks the hashes of a manifest, the audit
+//! walks the files of a checkpoint on disk and checks that they are internally
+//! consistent: protobuf files must decode, page maps must consist of loadable
+//! base and overlay files, Wasm modules must be present for canisters with an
+//! execution state, and there must be no files that the checkpoint layout does
+//! not know about.
+
+use ic_replicated_state::page_map::{storage::validate, StorageLayout, PAGE_SIZE};
+use ic_replicated_state::{num_bytes_try_from, NumWasmPages};
+use ic_state_layout::{
+    CanisterSnapshotBits, CanisterStateBits, CompleteCheckpointLayout, PageMapLayout, ReadOnly,
+    CANISTER_STATES_DIR, INGRESS_HISTORY_FILE, SNAPSHOTS_DIR, SPLIT_MARKER_FILE, STATS_FILE,
+    SUBNET_QUEUES_FILE, SYSTEM_METADATA_FILE, UNVERIFIED_CHECKPOINT_MARKER,
+};
+use ic_types::{CanisterId, Height};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The severity of a [`Finding`].
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Severity {
+    /// The checkpoint cannot be loaded.
+    Error,
+    /// The checkpoint can be loaded, but contains something unexpected.
+    Warning,
+}
+
+/// An inconsistency found in a checkpoint.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    /// The canister the finding belongs to (if any).
+    pub canister_id: Option<CanisterId>,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "✗ error",
+            Severity::Warning => "! warning",
+        };
+        write!(f, "{}: {}: {}", severity, self.path.display(), self.message)
+    }
+}
+
+/// Collects the findings of an audit.
+#[derive(Default)]
+struct Audit {
+    findings: Vec<Finding>,
+    canister_id: Option<CanisterId>,
+}
+
+impl Audit {
+    fn error(&mut self, path: &Path, message: impl ToString) {
+        self.report(Severity::Error, path, message);
+    }
+
+    fn warning(&mut self, path: &Path, message: impl ToString) {
+        self.report(Severity::Warning, path, message);
+    }
+
+    fn report(&mut self, severity: Severity, path: &Path, message: impl ToString) {
+        self.findings.push(Finding {
+            severity,
+            canister_id: self.canister_id,
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        });
+    }
+
+    /// Reports all entries of `dir` that are not in `expected` as orphans.
+    fn orphans(&mut self, dir: &Path, expected: &BTreeSet<PathBuf>) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => return self.error(dir, format!("failed to read directory: {}", err)),
+        };
+        for entry in entries {
+            match entry {
+                Ok(entry) if !expected.contains(&entry.path()) => {
+                    self.warning(&entry.path(), "orphan file")
+                }
+                Ok(_) => {}
+                Err(err) => self.error(dir, format!("failed to read directory entry: {}", err)),
+            }
+        }
+    }
+
+    /// Checks that the files of a page map are loadable and returns them.
+    /// Page maps that reference pages beyond `max_pages` (if given) are
+    /// reported as errors since the canister cannot access these pages.
+    fn page_map(
+        &mut self,
+        name: &str,
+        layout: &PageMapLayout<ReadOnly>,
+        max_pages: Option<usize>,
+    ) -> Vec<PathBuf> {
+        let base = layout.base();
+        let overlays = match layout.existing_overlays() {
+            Ok(overlays) => overlays,
+            Err(err) => {
+                self.error(
+                    &base,
+                    format!("failed to list overlays of {}: {}", name, err),
+                );
+                return vec![];
+            }
+        };
+
+        for overlay in overlays.iter() {
+            if layout.overlay_height(overlay).is_err() || layout.overlay_shard(overlay).is_err() {
+                self.error(overlay, "malformed overlay file name");
+            }
+            match std::fs::metadata(overlay) {
+                Ok(metadata) if metadata.len() == 0 => self.error(overlay, "empty overlay file"),
+                Ok(_) => {}
+                Err(err) => self.error(overlay, format!("failed to access overlay file: {}", err)),
+            }
+        }
+
+        if let Ok(metadata) = std::fs::metadata(&base) {
+            if metadata.len() % PAGE_SIZE as u64 != 0 {
+                self.error(
+                    &base,
+                    format!(
+                        "size of {} is not a multiple of the page size: {} bytes",
+                        name,
+                        metadata.len()
+                    ),
+                );
+            }
+        }
+        if let Err(err) = validate(layout) {
+            self.error(&base, format!("failed to load {}: {}", name, err));
+        }
+        match (
+            (layout as &dyn StorageLayout).memory_size_pages(),
+            max_pages,
+        ) {
+            (Ok(pages), Some(max_pages)) if pages > max_pages => self.error(
+                &base,
+                format!(
+                    "{} references pages up to {}, but the memory only has {} pages",
+                    name, pages, max_pages
+                ),
+            ),
+            (Err(err), _) => self.error(&base, format!("failed to get size of {}: {}", name, err)),
+            _ => {}
+        }
+
+        base.exists()
+            .then_some(base)
+            .into_iter()
+            .chain(overlays)
+            .collect()
+    }
+}
+
+/// Returns the number of OS pages of a memory of the given size.
+fn os_pages(size: NumWasmPages) -> Option<usize> {
+    num_bytes_try_from(size)
+        .ok()
+        .map(|bytes| bytes.get() as usize / PAGE_SIZE)
+}
+
+fn audit_canister(audit: &mut Audit, layout: &CompleteCheckpointLayout, canister_id: CanisterId) {
+    audit.canister_id = Some(canister_id);
+    let canister_layout = match layout.canister(&canister_id) {
+        Ok(canister_layout) => canister_layout,
+        Err(err) => {
+            audit.error(
+                layout.raw_path(),
+                format!("failed to access canister: {}", err),
+            );
+            return;
+        }
+    };
+    let root = canister_layout.raw_path();
+    let canister_file = canister_layout.canister();
+    let mut expected: BTreeSet<PathBuf> = [
+        canister_file.raw_path().to_path_buf(),
+        canister_layout.queues().raw_path().to_path_buf(),
+    ]
+    .into_iter()
+    .collect();
+
+    let bits = if canister_file.raw_path().exists() {
+        let bits = canister_file
+            .deserialize()
+            .map_err(|err| err.to_string())
+            .and_then(|pb| CanisterStateBits::try_from(pb).map_err(|err| err.to_string()));
+        match bits {
+            Ok(bits) => Some(bits),
+            Err(err) => {
+                audit.error(canister_file.raw_path(), err);
+                None
+            }
+        }
+    } else {
+        audit.error(canister_file.raw_path(), "missing canister state bits");
+        None
+    };
+    if let Err(err) = canister_layout.queues().deserialize() {
+        audit.error(canister_layout.queues().raw_path(), err);
+    }
+
+    let execution_state_bits = bits
+        .as_ref()
+        .and_then(|bits| bits.execution_state_bits.as_ref());
+    let wasm = canister_layout.wasm();
+    if wasm.raw_path().exists() {
+        expected.insert(wasm.raw_path().to_path_buf());
+        if execution_state_bits.is_none() {
+            audit.warning(
+                wasm.raw_path(),
+                "Wasm module of a canister without execution state",
+            );
+        }
+    } else if execution_state_bits.is_some() {
+        audit.error(wasm.raw_path(), "missing Wasm module");
+    }
+
+    let heap_pages = execution_state_bits.and_then(|bits| os_pages(bits.heap_size));
+    let stable_pages = bits
+        .as_ref()
+        .filter(|_| execution_state_bits.is_some())
+        .and_then(|bits| os_pages(bits.stable_memory_size));
+    expected.extend(audit.page_map("Wasm memory", &canister_layout.vmemory_0(), heap_pages));
+    expected.extend(audit.page_map(
+        "stable memory",
+        &canister_layout.stable_memory(),
+        stable_pages,
+    ));
+    expected.extend(audit.page_map(
+        "Wasm chunk store",
+        &canister_layout.wasm_chunk_store(),
+        None,
+    ));
+
+    audit.orphans(&root, &expected);
+}
+
+fn audit_snapshots(audit: &mut Audit, layout: &CompleteCheckpointLayout, canisters: &[CanisterId]) {
+    let snapshot_ids = match layout.snapshot_ids() {
+        Ok(snapshot_ids) => snapshot_ids,
+        Err(err) => {
+            audit.canister_id = None;
+            audit.error(
+                layout.raw_path(),
+                format!("failed to enumerate snapshots: {}", err),
+            );
+            return;
+        }
+    };
+    for snapshot_id in snapshot_ids {
+        let canister_id = snapshot_id.get_canister_id();
+        audit.canister_id = Some(canister_id);
+        let snapshot_layout = match layout.snapshot(&snapshot_id) {
+            Ok(snapshot_layout) => snapshot_layout,
+            Err(err) => {
+                audit.error(
+                    layout.raw_path(),
+                    format!("failed to access snapshot: {}", err),
+                );
+                continue;
+            }
+        };
+        let root = snapshot_layout.raw_path();
+        if !canisters.contains(&canister_id) {
+            audit.warning(&root, "snapshot of a canister that does not exist");
+        }
+
+        let snapshot_file = snapshot_layout.snapshot();
+        let wasm = snapshot_layout.wasm();
+        let mut expected: BTreeSet<PathBuf> = [
+            snapshot_file.raw_path().to_path_buf(),
+            wasm.raw_path().to_path_buf(),
+        ]
+        .into_iter()
+        .collect();
+        let bits = if snapshot_file.raw_path().exists() {
+            let bits = snapshot_file
+                .deserialize()
+                .map_err(|err| err.to_string())
+                .and_then(|pb| CanisterSnapshotBits::try_from(pb).map_err(|err| err.to_string()));
+            match bits {
+                Ok(bits) => Some(bits),
+                Err(err) => {
+                    audit.error(snapshot_file.raw_path(), err);
+                    None
+                }
+            }
+        } else {
+            audit.error(snapshot_file.raw_path(), "missing snapshot bits");
+            None
+        };
+        if !wasm.raw_path().exists() {
+            audit.error(wasm.raw_path(), "missing Wasm module");
+        }
+
+        let heap_pages = bits
+            .as_ref()
+            .and_then(|bits| os_pages(bits.wasm_memory_size));
+        let stable_pages = bits
+            .as_ref()
+            .and_then(|bits| os_pages(bits.stable_memory_size));
+        expected.extend(audit.page_map("Wasm memory", &snapshot_layout.vmemory_0(), heap_pages));
+        expected.extend(audit.page_map(
+            "stable memory",
+            &snapshot_layout.stable_memory(),
+            stable_pages,
+        ));
+        expected.extend(audit.page_map(
+            "Wasm chunk store",
+            &snapshot_layout.wasm_chunk_store(),
+            None,
+        ));
+
+        audit.orphans(&root, &expected);
+    }
+}
+
+/// Audits the checkpoint at `path` and returns all findings.
+pub fn audit_checkpoint(path: PathBuf) -> Result<Vec<Finding>, String> {
+    let layout = CompleteCheckpointLayout::new_untracked(path.clone(), Height::from(0))
+        .map_err(|err| format!("failed to open checkpoint: {}", err))?;
+    let mut audit = Audit::default();
+
+    if layout.unverified_checkpoint_marker().exists() {
+        audit.error(
+            &layout.unverified_checkpoint_marker(),
+            "the checkpoint is marked as unverified",
+        );
+    }
+    if !layout.system_metadata().raw_path().exists() {
+        audit.error(
+            layout.system_metadata().raw_path(),
+            "missing system metadata",
+        );
+    } else if let Err(err) = layout.system_metadata().deserialize() {
+        audit.error(layout.system_metadata().raw_path(), err);
+    }
+    if let Err(err) = layout.ingress_history().deserialize() {
+        audit.error(layout.ingress_history().raw_path(), err);
+    }
+    if let Err(err) = layout.subnet_queues().deserialize() {
+        audit.error(layout.subnet_queues().raw_path(), err);
+    }
+    if let Err(err) = layout.split_marker().deserialize() {
+        audit.error(layout.split_marker().raw_path(), err);
+    }
+    if let Err(err) = layout.stats().deserialize() {
+        audit.error(layout.stats().raw_path(), err);
+    }
+    let expected = [
+        SYSTEM_METADATA_FILE,
+        INGRESS_HISTORY_FILE,
+        SUBNET_QUEUES_FILE,
+        SPLIT_MARKER_FILE,
+        STATS_FILE,
+        UNVERIFIED_CHECKPOINT_MARKER,
+        CANISTER_STATES_DIR,
+        SNAPSHOTS_DIR,
+    ]
+    .into_iter()
+    .map(|name| path.join(name))
+    .collect();
+    audit.orphans(&path, &expected);
+
+    let canisters = match layout.canister_ids() {
+        Ok(canisters) => canisters,
+        Err(err) => {
+            audit.error(
+                &path.join(CANISTER_STATES_DIR),
+                format!("failed to enumerate canisters: {}", err),
+            );
+            vec![]
+        }
+    };
+    for canister_id in canisters.iter() {
+        audit_canister(&mut audit, &layout, *canister_id);
+    }
+    if path.join(SNAPSHOTS_DIR).exists() {
+        audit_snapshots(&mut audit, &layout, &canisters);
+    }
+
+    Ok(audit.findings)
+}
+
+/// `audit` command entry point.
+pub fn do_audit(path: PathBuf) -> Result<(), String> {
+    let findings = audit_checkpoint(path)?;
+    let mut findings_by_canister = BTreeMap::<Option<CanisterId>, Vec<&Finding>>::new();
+    for finding in findings.iter() {
+        findings_by_canister
+            .entry(finding.canister_id)
+            .or_default()
+            .push(finding);
+    }
+    for (canister_id, findings) in findings_by_canister {
+        match canister_id {
+            Some(canister_id) => println!("canister {}:", canister_id),
+            None => println!("checkpoint:"),
+        }
+        for finding in findings {
+            println!("    {}", finding);
+        }
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(format!(
+            "✗ Audit FAILED: {} errors, {} warnings",
+            errors,
+            findings.len() - errors
+        ));
+    }
+    println!("✓ Checkpoint is consistent ({} warnings)", findings.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_state_layout::CANISTER_FILE;
+    use ic_state_machine_tests::{StateMachine, StateMachineBuilder};
+    use ic_types::Cycles;
+    use tempfile::TempDir;
+
+    #[test]
+    fn audit_reports_orphans_and_corrupted_files() {
+        let env = StateMachineBuilder::new().build();
+        let canister_id = env.create_canister_with_cycles(None, Cycles::new(1_000), None);
+        env.checkpointed_tick();
+        env.state_manager.flush_tip_channel();
+
+        // Audit a copy of the checkpoint since checkpoint files are read-only.
+        let dir = TempDir::new().unwrap();
+        let checkpoint = copy_checkpoint(&env, &dir);
+        assert!(audit_checkpoint(checkpoint.clone()).unwrap().is_empty());
+
+        let canister_dir = checkpoint
+            .join(CANISTER_STATES_DIR)
+            .join(hex::encode(canister_id.get_ref().as_slice()));
+        std::fs::write(canister_dir.join("garbage"), b"garbage").unwrap();
+        // The copied files are hard links, so replace rather than overwrite them.
+        std::fs::remove_file(canister_dir.join(CANISTER_FILE)).unwrap();
+        std::fs::write(canister_dir.join(CANISTER_FILE), b"garbage").unwrap();
+
+        let findings = audit_checkpoint(checkpoint).unwrap();
+        assert_eq!(findings.len(), 2, "{:?}", findings);
+        assert!(findings
+            .iter()
+            .all(|finding| finding.canister_id == Some(canister_id)));
+        assert!(findings.contains(&Finding {
+            severity: Severity::Warning,
+            canister_id: Some(canister_id),
+            path: canister_dir.join("garbage"),
+            message: "orphan file".to_string(),
+        }));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Error
+                && finding.path == canister_dir.join(CANISTER_FILE)));
+    }
+
+    #[test]
+    fn audit_reports_missing_canister_state_bits_once() {
+        let env = StateMachineBuilder::new().build();
+        let canister_id = env.create_canister_with_cycles(None, Cycles::new(1_000), None);
+        env.checkpointed_tick();
+        env.state_manager.flush_tip_channel();
+
+        let dir = TempDir::new().unwrap();
+        let checkpoint = copy_checkpoint(&env, &dir);
+        let canister_file = checkpoint
+            .join(CANISTER_STATES_DIR)
+            .join(hex::encode(canister_id.get_ref().as_slice()))
+            .join(CANISTER_FILE);
+        std::fs::remove_file(&canister_file).unwrap();
+
+        assert_eq!(
+            audit_checkpoint(checkpoint).unwrap(),
+            vec![Finding {
+                severity: Severity::Error,
+                canister_id: Some(canister_id),
+                path: canister_file,
+                message: "missing canister state bits".to_string(),
+            }]
+        );
+    }
+
+    /// Copies the checkpoint @1 of `env` into `dir` and returns its path.
+    fn copy_checkpoint(env: &StateMachine, dir: &TempDir) -> PathBuf {
+        let state_layout = env.state_manager.state_layout();
+        let src = state_layout
+            .checkpoint_verified(Height::new(1))
+            .unwrap()
+            .raw_path()
+            .to_path_buf();
+        let checkpoint = dir.path().join("checkpoint");
+        state_layout
+            .copy_and_sync_checkpoint("audit", &src, &checkpoint, None)
+            .unwrap();
+        checkpoint
+    }
+}
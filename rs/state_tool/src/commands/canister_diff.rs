@@ -0,0 +1,373 @@
+//! Computes per-canister semantic differences between checkpoints.
+//!
+//! Unlike `cdiff`, which diffs the canonical trees of the checkpoints, this
+//! command compares the canister states themselves: settings, controllers,
+//! cycles balances, certified data, queues, Wasm modules and the pages of the
+//! Wasm and stable memories.
+
+use crate::commands::utils::load_state;
+use ic_replicated_state::{CanisterState, ExecutionState, PageIndex, PageMap, ReplicatedState};
+use ic_sys::PageBytes;
+use ic_types::CanisterId;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+const ZERO_PAGE: PageBytes = [0; ic_sys::PAGE_SIZE];
+
+/// How a canister differs between the two checkpoints.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanisterDiffKind {
+    /// The canister only exists in the first checkpoint.
+    Removed,
+    /// The canister only exists in the second checkpoint.
+    Added,
+    /// The canister exists in both checkpoints, but its state differs.
+    Changed,
+}
+
+/// A field of the canister state whose value differs between the checkpoints.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct FieldDiff {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// A range `[start, end)` of page indices.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct PageRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// The differences of a single canister between the checkpoints.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct CanisterDiff {
+    pub canister_id: String,
+    pub kind: CanisterDiffKind,
+    /// The fields that differ (empty unless the canister changed).
+    pub fields: Vec<FieldDiff>,
+    /// The (OS) pages of the Wasm memory that differ.
+    pub heap_pages: Vec<PageRange>,
+    /// The (OS) pages of the stable memory that differ.
+    pub stable_pages: Vec<PageRange>,
+}
+
+impl CanisterDiff {
+    fn new(canister_id: CanisterId, kind: CanisterDiffKind) -> Self {
+        Self {
+            canister_id: canister_id.to_string(),
+            kind,
+            fields: vec![],
+            heap_pages: vec![],
+            stable_pages: vec![],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.heap_pages.is_empty() && self.stable_pages.is_empty()
+    }
+}
+
+/// Diffs all canisters (or only the given canisters) of two replicated states.
+pub fn diff_canisters(
+    state_a: &ReplicatedState,
+    state_b: &ReplicatedState,
+    canisters: &[CanisterId],
+) -> Vec<CanisterDiff> {
+    let canister_ids: BTreeSet<CanisterId> = if canisters.is_empty() {
+        state_a
+            .canister_states
+            .keys()
+            .chain(state_b.canister_states.keys())
+            .cloned()
+            .collect()
+    } else {
+        canisters.iter().cloned().collect()
+    };
+
+    canister_ids
+        .into_iter()
+        .filter_map(|canister_id| {
+            match (
+                state_a.canister_state(&canister_id),
+                state_b.canister_state(&canister_id),
+            ) {
+                (None, None) => None,
+                (Some(_), None) => Some(CanisterDiff::new(canister_id, CanisterDiffKind::Removed)),
+                (None, Some(_)) => Some(CanisterDiff::new(canister_id, CanisterDiffKind::Added)),
+                (Some(a), Some(b)) => {
+                    let diff = diff_canister(a, b);
+                    (!diff.is_empty()).then_some(diff)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Diffs the states of the same canister in the two checkpoints.
+fn diff_canister(a: &CanisterState, b: &CanisterState) -> CanisterDiff {
+    let mut diff = CanisterDiff::new(a.canister_id(), CanisterDiffKind::Changed);
+    let mut field = |name: &str, before: String, after: String| {
+        if before != after {
+            diff.fields.push(FieldDiff {
+                field: name.to_string(),
+                before,
+                after,
+            });
+        }
+    };
+
+    let (sa, sb) = (&a.system_state, &b.system_state);
+    field(
+        "status",
+        sa.status_string().to_string(),
+        sb.status_string().to_string(),
+    );
+    field(
+        "controllers",
+        sa.collect_controllers_as_string(),
+        sb.collect_controllers_as_string(),
+    );
+    field(
+        "compute_allocation",
+        a.compute_allocation().to_string(),
+        b.compute_allocation().to_string(),
+    );
+    field(
+        "memory_allocation",
+        a.memory_allocation().to_string(),
+        b.memory_allocation().to_string(),
+    );
+    field(
+        "freezing_threshold",
+        sa.freeze_threshold.to_string(),
+        sb.freeze_threshold.to_string(),
+    );
+    field(
+        "reserved_cycles_limit",
+        format!("{:?}", sa.reserved_balance_limit()),
+        format!("{:?}", sb.reserved_balance_limit()),
+    );
+    field(
+        "wasm_memory_limit",
+        format!("{:?}", sa.wasm_memory_limit),
+        format!("{:?}", sb.wasm_memory_limit),
+    );
+    field(
+        "wasm_memory_threshold",
+        sa.wasm_memory_threshold.to_string(),
+        sb.wasm_memory_threshold.to_string(),
+    );
+    field(
+        "log_visibility",
+        format!("{:?}", sa.log_visibility),
+        format!("{:?}", sb.log_visibility),
+    );
+    field(
+        "cycles_balance",
+        sa.balance().to_string(),
+        sb.balance().to_string(),
+    );
+    field(
+        "reserved_cycles",
+        sa.reserved_balance().to_string(),
+        sb.reserved_balance().to_string(),
+    );
+    field(
+        "certified_data",
+        hex::encode(&sa.certified_data),
+        hex::encode(&sb.certified_data),
+    );
+    field(
+        "canister_version",
+        sa.canister_version.to_string(),
+        sb.canister_version.to_string(),
+    );
+    field(
+        "module_hash",
+        module_hash(a.execution_state.as_ref()),
+        module_hash(b.execution_state.as_ref()),
+    );
+    // The contents of the queues may differ even if their summaries coincide.
+    if sa.queues() != sb.queues() {
+        diff.fields.push(FieldDiff {
+            field: "queues".to_string(),
+            before: queues_summary(a),
+            after: queues_summary(b),
+        });
+    }
+
+    let (ea, eb) = (a.execution_state.as_ref(), b.execution_state.as_ref());
+    diff.heap_pages = diff_pages(
+        ea.map(|e| &e.wasm_memory.page_map),
+        eb.map(|e| &e.wasm_memory.page_map),
+    );
+    diff.stable_pages = diff_pages(
+        ea.map(|e| &e.stable_memory.page_map),
+        eb.map(|e| &e.stable_memory.page_map),
+    );
+    diff
+}
+
+/// Summarizes the number of messages in the queues of a canister.
+fn queues_summary(canister: &CanisterState) -> String {
+    let queues = canister.system_state.queues();
+    format!(
+        "ingress: {}, input: {}, output: {}",
+        queues.ingress_queue_message_count(),
+        queues.input_queues_message_count(),
+        queues.output_queues_message_count()
+    )
+}
+
+fn module_hash(execution_state: Option<&ExecutionState>) -> String {
+    match execution_state {
+        Some(execution_state) => hex::encode(execution_state.wasm_binary.binary.module_hash()),
+        None => "<empty>".to_string(),
+    }
+}
+
+/// Returns the ranges of pages that differ between two page maps.
+/// A missing page map (no Wasm module installed) is treated as an empty memory.
+fn diff_pages(a: Option<&PageMap>, b: Option<&PageMap>) -> Vec<PageRange> {
+    let num_pages = a
+        .map_or(0, |page_map| page_map.num_host_pages())
+        .max(b.map_or(0, |page_map| page_map.num_host_pages())) as u64;
+    let page = |page_map: Option<&PageMap>, index: u64| match page_map {
+        Some(page_map) => *page_map.get_page(PageIndex::new(index)),
+        None => ZERO_PAGE,
+    };
+
+    let mut ranges: Vec<PageRange> = vec![];
+    for index in 0..num_pages {
+        if page(a, index) == page(b, index) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(range) if range.end == index => range.end += 1,
+            _ => ranges.push(PageRange {
+                start: index,
+                end: index + 1,
+            }),
+        }
+    }
+    ranges
+}
+
+fn format_page_ranges(ranges: &[PageRange]) -> String {
+    ranges
+        .iter()
+        .map(|range| {
+            if range.end == range.start + 1 {
+                range.start.to_string()
+            } else {
+                format!("{}..{}", range.start, range.end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `canister_diff` command entry point.
+pub fn do_canister_diff(
+    path_a: PathBuf,
+    path_b: PathBuf,
+    canisters: Vec<CanisterId>,
+    json: bool,
+) -> Result<(), String> {
+    let state_a =
+        load_state(path_a).map_err(|err| format!("✗ Loading checkpoint FAILED:\n\t{}", err))?;
+    let state_b =
+        load_state(path_b).map_err(|err| format!("✗ Loading checkpoint FAILED:\n\t{}", err))?;
+    let diffs = diff_canisters(&state_a, &state_b, &canisters);
+
+    if json {
+        let json = serde_json::to_string_pretty(&diffs)
+            .map_err(|err| format!("failed to serialize diff: {}", err))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if diffs.is_empty() {
+        println!("✓ Canisters are identical");
+    }
+    for diff in diffs {
+        match diff.kind {
+            CanisterDiffKind::Removed => println!("- {}", diff.canister_id),
+            CanisterDiffKind::Added => println!("+ {}", diff.canister_id),
+            CanisterDiffKind::Changed => {
+                println!("~ {}", diff.canister_id);
+                for field in diff.fields {
+                    println!("    {}: {} -> {}", field.field, field.before, field.after);
+                }
+                if !diff.heap_pages.is_empty() {
+                    println!("    heap pages: {}", format_page_ranges(&diff.heap_pages));
+                }
+                if !diff.stable_pages.is_empty() {
+                    println!(
+                        "    stable pages: {}",
+                        format_page_ranges(&diff.stable_pages)
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_state_machine_tests::StateMachineBuilder;
+    use ic_types::{Cycles, Height};
+
+    #[test]
+    fn diff_canisters_reports_cycles_and_added_canisters() {
+        let env = StateMachineBuilder::new()
+            .with_remove_old_states(false)
+            .build();
+        let canister_a = env.create_canister_with_cycles(None, Cycles::new(1_000), None);
+        env.checkpointed_tick();
+        env.add_cycles(canister_a, 500);
+        let canister_b = env.create_canister_with_cycles(None, Cycles::new(1_000), None);
+        env.checkpointed_tick();
+        env.state_manager.flush_tip_channel();
+
+        let layout = env.state_manager.state_layout();
+        let checkpoint = |height| {
+            layout
+                .checkpoint_verified(Height::new(height))
+                .unwrap()
+                .raw_path()
+                .to_path_buf()
+        };
+        let state_a = load_state(checkpoint(1)).unwrap();
+        let state_b = load_state(checkpoint(2)).unwrap();
+
+        let diffs = diff_canisters(&state_a, &state_b, &[]);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].canister_id, canister_a.to_string());
+        assert_eq!(diffs[0].kind, CanisterDiffKind::Changed);
+        let balance = |state: &ReplicatedState| {
+            state
+                .canister_state(&canister_a)
+                .unwrap()
+                .system_state
+                .balance()
+                .to_string()
+        };
+        assert!(diffs[0].fields.contains(&FieldDiff {
+            field: "cycles_balance".to_string(),
+            before: balance(&state_a),
+            after: balance(&state_b),
+        }));
+        assert_eq!(diffs[1].canister_id, canister_b.to_string());
+        assert_eq!(diffs[1].kind, CanisterDiffKind::Added);
+
+        assert_eq!(diff_canisters(&state_a, &state_b, &[canister_b]).len(), 1);
+        assert!(diff_canisters(&state_a, &state_a, &[]).is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+//! Extracts a single canister from a checkpoint into a portable archive.
+//!
+//! The archive is a directory with the following layout:
+//!
+//! ```text
+//! <archive>
+//! ├── canister.json       Canister ID, system state, settings and snapshot IDs.
+//! ├── module.wasm         The Wasm module (if a module is installed).
+//! ├── stable_memory.bin   The contents of the stable memory.
+//! └── checkpoint          A checkpoint hosting only the extracted canister.
+//!     └── ...
+//! ```
+//!
+//! `checkpoint` contains the subnet-level files of the source checkpoint and
+//! the state (Wasm memory, stable memory, chunk store, ...) and snapshots of
+//! the extracted canister, so it can be imported with `import_state` and
+//! inspected with the other commands (e.g. `decode`). The flat files can be
+//! loaded into a fresh canister by tools that do not understand the checkpoint
+//! format (e.g. `module.wasm` can be installed by drun and `stable_memory.bin`
+//! can be set via PocketIC).
+//!
+//! The files of `checkpoint` are hard-linked, so the archive must be created
+//! on the file system of the source checkpoint.
+
+use crate::commands::utils::load_state;
+use ic_logger::no_op_logger;
+use ic_metrics::MetricsRegistry;
+use ic_replicated_state::{
+    num_bytes_try_from, page_map::PAGE_SIZE, CanisterState, Memory, PageIndex,
+};
+use ic_state_layout::{CompleteCheckpointLayout, StateLayout};
+use ic_types::{CanisterId, Height};
+use serde::Serialize;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The name of the file holding the [`CanisterArchiveManifest`].
+pub const CANISTER_ARCHIVE_MANIFEST_FILE: &str = "canister.json";
+
+/// The name of the checkpoint directory of the archive.
+pub const CANISTER_ARCHIVE_CHECKPOINT_DIR: &str = "checkpoint";
+
+/// The description of an extracted canister.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct CanisterArchiveManifest {
+    pub canister_id: String,
+    /// The height of the checkpoint the canister was extracted from.
+    pub height: u64,
+    pub status: String,
+    pub controllers: Vec<String>,
+    pub cycles_balance: u128,
+    pub reserved_cycles: u128,
+    pub compute_allocation: u64,
+    pub memory_allocation: u64,
+    pub freezing_threshold: u64,
+    pub wasm_memory_limit: Option<u64>,
+    pub certified_data: String,
+    pub canister_version: u64,
+    /// The hex-encoded SHA-256 hash of `module.wasm` (if a module is installed).
+    pub module_hash: Option<String>,
+    /// The size of the Wasm memory in bytes.
+    pub heap_size: u64,
+    /// The size of `stable_memory.bin` in bytes.
+    pub stable_memory_size: u64,
+    /// The hex-encoded IDs of the snapshots in `checkpoint`.
+    pub snapshots: Vec<String>,
+}
+
+impl CanisterArchiveManifest {
+    fn new(canister: &CanisterState, height: Height, snapshots: Vec<String>) -> Self {
+        let system_state = &canister.system_state;
+        let execution_state = canister.execution_state.as_ref();
+        Self {
+            canister_id: canister.canister_id().to_string(),
+            height: height.get(),
+            status: system_state.status_string().to_string(),
+            controllers: system_state
+                .controllers
+                .iter()
+                .map(|controller| controller.to_string())
+                .collect(),
+            cycles_balance: system_state.balance().get(),
+            reserved_cycles: system_state.reserved_balance().get(),
+            compute_allocation: canister.compute_allocation().as_percent(),
+            memory_allocation: canister.memory_allocation().bytes().get(),
+            freezing_threshold: system_state.freeze_threshold.get(),
+            wasm_memory_limit: system_state.wasm_memory_limit.map(|limit| limit.get()),
+            certified_data: hex::encode(&system_state.certified_data),
+            canister_version: system_state.canister_version,
+            module_hash: execution_state.map(|execution_state| {
+                hex::encode(execution_state.wasm_binary.binary.module_hash())
+            }),
+            heap_size: execution_state.map_or(0, |execution_state| {
+                memory_size(&execution_state.wasm_memory)
+            }),
+            stable_memory_size: execution_state.map_or(0, |execution_state| {
+                memory_size(&execution_state.stable_memory)
+            }),
+            snapshots,
+        }
+    }
+}
+
+fn memory_size(memory: &Memory) -> u64 {
+    num_bytes_try_from(memory.size).map_or(0, |size| size.get())
+}
+
+/// Writes the contents of a memory (up to its size) to `path`.
+fn write_memory(memory: &Memory, path: &Path) -> Result<(), String> {
+    let file = fs::File::create(path)
+        .map_err(|e| format!("failed to create {}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    let num_pages = memory_size(memory) / PAGE_SIZE as u64;
+    for index in 0..num_pages {
+        writer
+            .write_all(memory.page_map.get_page(PageIndex::new(index)))
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("failed to write {}: {}", path.display(), e))
+}
+
+/// Extracts the canister `canister_id` from the checkpoint at `path` into
+/// the (new) archive directory `output`.
+pub fn extract_canister(
+    path: PathBuf,
+    canister_id: CanisterId,
+    output: &Path,
+) -> Result<CanisterArchiveManifest, String> {
+    if output.exists() {
+        return Err(format!("{} already exists", output.display()));
+    }
+
+    let layout = CompleteCheckpointLayout::new_untracked(path.clone(), Height::from(0))
+        .map_err(|e| format!("failed to open checkpoint: {}", e))?;
+    let height = height_of_checkpoint(&path);
+    let state = load_state(path).map_err(|e| format!("failed to load checkpoint: {}", e))?;
+    let canister = state
+        .canister_state(&canister_id)
+        .ok_or_else(|| format!("canister {} does not exist in the checkpoint", canister_id))?;
+
+    fs::create_dir_all(output)
+        .map_err(|e| format!("failed to create {}: {}", output.display(), e))?;
+
+    if let Some(execution_state) = canister.execution_state.as_ref() {
+        let wasm_path = output.join("module.wasm");
+        fs::write(&wasm_path, execution_state.wasm_binary.binary.as_slice())
+            .map_err(|e| format!("failed to write {}: {}", wasm_path.display(), e))?;
+        write_memory(
+            &execution_state.stable_memory,
+            &output.join("stable_memory.bin"),
+        )?;
+    }
+
+    let checkpoint = output.join(CANISTER_ARCHIVE_CHECKPOINT_DIR);
+    let state_layout = StateLayout::new_no_init(
+        no_op_logger(),
+        output.to_path_buf(),
+        &MetricsRegistry::new(),
+    );
+    state_layout
+        .copy_and_sync_canister_checkpoint(layout.raw_path(), &checkpoint, &canister_id)
+        .map_err(|e| format!("failed to copy {}: {}", checkpoint.display(), e))?;
+
+    let snapshots = layout
+        .snapshot_ids()
+        .map_err(|e| format!("failed to enumerate snapshots: {}", e))?
+        .into_iter()
+        .filter(|snapshot_id| snapshot_id.get_canister_id() == canister_id)
+        .map(|snapshot_id| hex::encode(snapshot_id.as_slice()))
+        .collect();
+
+    let manifest = CanisterArchiveManifest::new(canister, height, snapshots);
+    let manifest_path = output.join(CANISTER_ARCHIVE_MANIFEST_FILE);
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("failed to serialize canister manifest: {}", e))?;
+    fs::write(&manifest_path, json)
+        .map_err(|e| format!("failed to write {}: {}", manifest_path.display(), e))?;
+
+    Ok(manifest)
+}
+
+/// Returns the height encoded in the name of a checkpoint directory
+/// (or zero if the checkpoint was renamed).
+fn height_of_checkpoint(path: &Path) -> Height {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| u64::from_str_radix(name, 16).ok())
+        .map_or(Height::from(0), Height::from)
+}
+
+/// `extract_canister` command entry point.
+pub fn do_extract_canister(
+    path: PathBuf,
+    canister_id: CanisterId,
+    output: PathBuf,
+) -> Result<(), String> {
+    let manifest = extract_canister(path, canister_id, &output)?;
+    println!(
+        "✓ Extracted canister {} ({} snapshots) to {}",
+        manifest.canister_id,
+        manifest.snapshots.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_state_machine_tests::StateMachineBuilder;
+    use ic_types::Cycles;
+    use tempfile::TempDir;
+
+    #[test]
+    fn extract_canister_writes_archive() {
+        let env = StateMachineBuilder::new().build();
+        let canister_id = env.create_canister_with_cycles(None, Cycles::new(1_000), None);
+        let other_canister_id = env.create_canister_with_cycles(None, Cycles::new(1_000), None);
+        env.checkpointed_tick();
+        env.state_manager.flush_tip_channel();
+
+        let checkpoint = env
+            .state_manager
+            .state_layout()
+            .checkpoint_verified(Height::new(1))
+            .unwrap()
+            .raw_path()
+            .to_path_buf();
+        let dir = TempDir::new().unwrap();
+        let output = dir.path().join("archive");
+
+        let manifest = extract_canister(checkpoint.clone(), canister_id, &output).unwrap();
+        assert_eq!(manifest.canister_id, canister_id.to_string());
+        assert_eq!(manifest.height, 1);
+        assert_eq!(manifest.module_hash, None);
+        assert!(output.join(CANISTER_ARCHIVE_MANIFEST_FILE).exists());
+
+        // The archived checkpoint can be loaded and only hosts the canister.
+        let state = load_state(output.join(CANISTER_ARCHIVE_CHECKPOINT_DIR)).unwrap();
+        assert_eq!(
+            state
+                .canister_state(&canister_id)
+                .unwrap()
+                .system_state
+                .balance(),
+            Cycles::new(1_000)
+        );
+        assert!(state.canister_state(&other_canister_id).is_none());
+
+        // The archive must not be overwritten.
+        assert!(extract_canister(checkpoint, canister_id, &output).is_err());
+    }
+}
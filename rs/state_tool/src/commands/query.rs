@@ -0,0 +1,314 @@
+//! Executes a query against a canister in a checkpoint.
+//!
+//! The query is executed offline (without a replica) by loading the canister
+//! from the checkpoint and running the query method through the `Hypervisor`.
+//! The query observes the state and the batch time of the checkpoint. Composite
+//! queries can be executed as long as they do not call other canisters.
+
+use crate::commands::utils::load_state;
+use candid::IDLArgs;
+use ic_config::{
+    execution_environment::Config as HypervisorConfig, flag_status::FlagStatus,
+    subnet_config::SubnetConfig,
+};
+use ic_crypto_tree_hash::{LabeledTree, MixedHashTree};
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
+use ic_embedders::wasmtime_embedder::system_api::{ExecutionParameters, InstructionLimits};
+use ic_error_types::{ErrorCode, UserError};
+use ic_execution_environment::{
+    as_round_instructions, execution::nonreplicated_query::execute_non_replicated_query,
+    Hypervisor, NonReplicatedQueryKind, RoundLimits,
+};
+use ic_interfaces::execution_environment::{ExecutionMode, SubnetAvailableMemory};
+use ic_interfaces_state_manager::{
+    CertifiedStateSnapshot, Labeled, StateManagerError, StateManagerResult, StateReader,
+};
+use ic_metrics::MetricsRegistry;
+use ic_replicated_state::{
+    canister_state::execution_state::WasmExecutionMode,
+    page_map::TestPageAllocatorFileDescriptorImpl, ReplicatedState,
+};
+use ic_types::{
+    consensus::certification::Certification, ingress::WasmResult, methods::WasmMethod, CanisterId,
+    Height, PrincipalId,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The argument of a query.
+pub enum QueryArg {
+    /// An argument in the textual Candid format.
+    Candid(String),
+    /// Raw bytes.
+    Raw(Vec<u8>),
+}
+
+impl QueryArg {
+    fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        match self {
+            QueryArg::Candid(text) => candid_parser::parse_idl_args(text)
+                .map_err(|e| format!("failed to parse Candid argument {}: {}", text, e))?
+                .to_bytes()
+                .map_err(|e| format!("failed to encode Candid argument {}: {}", text, e)),
+            QueryArg::Raw(bytes) => Ok(bytes.clone()),
+        }
+    }
+}
+
+/// Serves the single state loaded from a checkpoint. The `Hypervisor` only
+/// reads states when executing in a sandbox, which the `query` command disables.
+struct CheckpointStateReader {
+    state: Arc<ReplicatedState>,
+    height: Height,
+}
+
+impl StateReader for CheckpointStateReader {
+    type State = ReplicatedState;
+
+    fn get_state_at(&self, height: Height) -> StateManagerResult<Labeled<Arc<ReplicatedState>>> {
+        if height == self.height {
+            Ok(self.get_latest_state())
+        } else {
+            Err(StateManagerError::StateRemoved(height))
+        }
+    }
+
+    fn get_latest_state(&self) -> Labeled<Arc<ReplicatedState>> {
+        Labeled::new(self.height, Arc::clone(&self.state))
+    }
+
+    fn latest_state_height(&self) -> Height {
+        self.height
+    }
+
+    fn latest_certified_height(&self) -> Height {
+        self.height
+    }
+
+    fn read_certified_state(
+        &self,
+        _paths: &LabeledTree<()>,
+    ) -> Option<(Arc<ReplicatedState>, MixedHashTree, Certification)> {
+        None
+    }
+
+    fn get_certified_state_snapshot(
+        &self,
+    ) -> Option<Box<dyn CertifiedStateSnapshot<State = ReplicatedState> + 'static>> {
+        None
+    }
+}
+
+/// Executes the query method `method` of the canister `canister_id` in the
+/// checkpoint at `path` with the given argument and caller.
+pub fn execute_query(
+    path: PathBuf,
+    canister_id: CanisterId,
+    method: String,
+    arg: &[u8],
+    caller: PrincipalId,
+) -> Result<Result<WasmResult, UserError>, String> {
+    let state =
+        Arc::new(load_state(path).map_err(|e| format!("failed to load checkpoint: {}", e))?);
+    let canister = state
+        .canister_state(&canister_id)
+        .ok_or_else(|| format!("canister {} does not exist in the checkpoint", canister_id))?
+        .clone();
+    let own_subnet_id = state.metadata.own_subnet_id;
+    let own_subnet_type = state.metadata.own_subnet_type;
+
+    let subnet_config = SubnetConfig::new(own_subnet_type);
+    let config = HypervisorConfig {
+        canister_sandboxing_flag: FlagStatus::Disabled,
+        ..HypervisorConfig::default()
+    };
+    let metrics_registry = MetricsRegistry::new();
+    let cycles_account_manager = Arc::new(CyclesAccountManager::new(
+        subnet_config.scheduler_config.max_instructions_per_message,
+        own_subnet_type,
+        own_subnet_id,
+        subnet_config.cycles_account_manager_config,
+    ));
+    let hypervisor = Hypervisor::new(
+        config.clone(),
+        &metrics_registry,
+        own_subnet_id,
+        crate::commands::logger(),
+        cycles_account_manager,
+        subnet_config.scheduler_config.dirty_page_overhead,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        Arc::new(CheckpointStateReader {
+            state: Arc::clone(&state),
+            height: Height::from(0),
+        }),
+        &std::env::temp_dir(),
+    );
+
+    let max_canister_memory_size = match canister.execution_state.as_ref() {
+        Some(execution_state)
+            if execution_state.wasm_execution_mode == WasmExecutionMode::Wasm64 =>
+        {
+            config.max_canister_memory_size_wasm64
+        }
+        _ => config.max_canister_memory_size_wasm32,
+    };
+    let instruction_limit = subnet_config
+        .scheduler_config
+        .max_instructions_per_message_without_dts;
+    let execution_parameters = ExecutionParameters {
+        instruction_limits: InstructionLimits::new(
+            FlagStatus::Disabled,
+            instruction_limit,
+            instruction_limit,
+        ),
+        canister_memory_limit: canister.memory_limit(max_canister_memory_size),
+        wasm_memory_limit: canister.wasm_memory_limit(),
+        memory_allocation: canister.memory_allocation(),
+        canister_guaranteed_callback_quota: config.canister_guaranteed_callback_quota as u64,
+        compute_allocation: canister.compute_allocation(),
+        subnet_type: own_subnet_type,
+        execution_mode: ExecutionMode::NonReplicated,
+        subnet_memory_saturation: ResourceSaturation::default(),
+    };
+    let mut round_limits = RoundLimits {
+        instructions: as_round_instructions(instruction_limit),
+        subnet_available_memory: SubnetAvailableMemory::new(
+            config.subnet_memory_capacity.get() as i64,
+            config.guaranteed_response_message_memory_capacity.get() as i64,
+            config.subnet_wasm_custom_sections_memory_capacity.get() as i64,
+        ),
+        subnet_available_callbacks: config.subnet_callback_soft_limit as i64,
+        compute_allocation_used: 0,
+    };
+    let state_changes_error = metrics_registry.int_counter(
+        "state_tool_query_state_changes_error",
+        "Critical error: illegal system state changes during query execution.",
+    );
+
+    let method = match WasmMethod::CompositeQuery(method.clone()) {
+        composite_query if canister.exports_method(&composite_query) => composite_query,
+        _ => WasmMethod::Query(method),
+    };
+    let (_, _, result, _, _) = execute_non_replicated_query(
+        NonReplicatedQueryKind::Pure { caller },
+        method,
+        arg,
+        canister,
+        None,
+        state.time(),
+        execution_parameters,
+        &state.metadata.network_topology,
+        &hypervisor,
+        &mut round_limits,
+        &state_changes_error,
+    );
+    Ok(result.and_then(|result| {
+        result.ok_or_else(|| {
+            UserError::new(
+                ErrorCode::CanisterDidNotReply,
+                format!("Canister {} did not reply to the query", canister_id),
+            )
+        })
+    }))
+}
+
+/// `query` command entry point.
+pub fn do_query(
+    path: PathBuf,
+    canister_id: CanisterId,
+    method: String,
+    arg: QueryArg,
+    caller: PrincipalId,
+) -> Result<(), String> {
+    let arg = arg.to_bytes()?;
+    match execute_query(path, canister_id, method, &arg, caller)? {
+        Ok(WasmResult::Reply(bytes)) => match IDLArgs::from_bytes(&bytes) {
+            Ok(args) => println!("Reply: {}", args),
+            Err(_) => println!("Reply: 0x{}", hex::encode(bytes)),
+        },
+        Ok(WasmResult::Reject(message)) => println!("Reject: {}", message),
+        Err(err) => return Err(format!("✗ Query FAILED:\n\t{}", err)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_state_machine_tests::StateMachineBuilder;
+
+    const COUNTER_CANISTER: &str = r#"
+(module
+    (import "ic0" "msg_reply" (func $msg_reply))
+    (import "ic0" "msg_reply_data_append"
+        (func $msg_reply_data_append (param i32 i32)))
+
+    ;; increment the counter at heap[0]
+    (func $inc
+        (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+        (call $msg_reply)
+    )
+
+    ;; reply with the counter at heap[0]
+    (func $read
+        (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+        (call $msg_reply)
+    )
+
+    (memory $memory 1)
+    (export "memory" (memory $memory))
+    (export "canister_update inc" (func $inc))
+    (export "canister_query read" (func $read))
+)"#;
+
+    #[test]
+    fn execute_query_observes_checkpointed_state() {
+        let env = StateMachineBuilder::new().build();
+        let canister_id = env.install_canister_wat(COUNTER_CANISTER, vec![], None);
+        for _ in 0..2 {
+            env.execute_ingress(canister_id, "inc", vec![]).unwrap();
+        }
+        env.checkpointed_tick();
+        env.state_manager.flush_tip_channel();
+
+        let checkpoint = env
+            .state_manager
+            .state_layout()
+            .checkpoint_verified(env.state_manager.latest_state_height())
+            .unwrap()
+            .raw_path()
+            .to_path_buf();
+        let caller = PrincipalId::new_anonymous();
+
+        assert_eq!(
+            execute_query(
+                checkpoint.clone(),
+                canister_id,
+                "read".to_string(),
+                &[],
+                caller
+            ),
+            Ok(Ok(WasmResult::Reply(2_u32.to_le_bytes().to_vec())))
+        );
+        // Update methods cannot be executed as queries.
+        let err = execute_query(
+            checkpoint.clone(),
+            canister_id,
+            "inc".to_string(),
+            &[],
+            caller,
+        )
+        .unwrap()
+        .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CanisterMethodNotFound);
+        // Unknown canisters are reported as a failure of the command.
+        assert!(execute_query(
+            checkpoint,
+            CanisterId::from_u64(1_000),
+            "read".to_string(),
+            &[],
+            caller
+        )
+        .is_err());
+    }
+}
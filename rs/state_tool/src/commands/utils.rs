@@ -3,8 +3,13 @@
 use ic_config::{config_parser::ConfigSource, ConfigOptional};
 use ic_logger::replica_logger::no_op_logger;
 use ic_metrics::MetricsRegistry;
-use ic_state_layout::StateLayout;
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{page_map::TestPageAllocatorFileDescriptorImpl, ReplicatedState};
+use ic_state_layout::{CompleteCheckpointLayout, StateLayout};
+use ic_state_manager::{checkpoint::load_checkpoint, CheckpointError, CheckpointMetrics};
+use ic_types::Height;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Loads the location of the state root from the given `replica` configuration
 /// file.
@@ -25,3 +30,18 @@ pub fn locate_state_root(config_path: PathBuf) -> Result<StateLayout, String> {
 
     Ok(StateLayout::try_new(no_op_logger(), state_root, &MetricsRegistry::new()).unwrap())
 }
+
+/// Loads the checkpoint at `path` as a replicated state.
+pub fn load_state(path: PathBuf) -> Result<ReplicatedState, CheckpointError> {
+    let unused_height = Height::from(0);
+    let own_subnet_type = SubnetType::Application;
+    let dummy_metrics_registry = MetricsRegistry::new();
+    let dummy_metrics = CheckpointMetrics::new(&dummy_metrics_registry, crate::commands::logger());
+    load_checkpoint(
+        &CompleteCheckpointLayout::new_untracked(path, unused_height)?,
+        own_subnet_type,
+        &dummy_metrics,
+        None,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+}
@@ -1,14 +1,14 @@
 //! IC State Tool
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
-//! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! persisted state files, diff checkpoints and the canisters therein, compute
+//! partial state hashes and checkpoint manifests, import state trees).
 
 use clap::Parser;
 use ic_registry_routing_table::CanisterIdRange;
 use ic_registry_subnet_type::SubnetType;
 use ic_state_tool::commands;
-use ic_types::{Height, PrincipalId, Time};
+use ic_types::{CanisterId, Height, PrincipalId, Time};
 use std::{error::Error, path::PathBuf};
 
 /// Supported `state_tool` commands and their arguments.
@@ -19,6 +19,19 @@ enum Opt {
     #[clap(name = "cdiff")]
     CDiff { path_a: PathBuf, path_b: PathBuf },
 
+    /// Computes per-canister semantic differences between checkpoints.
+    #[clap(name = "canister_diff")]
+    CanisterDiff {
+        path_a: PathBuf,
+        path_b: PathBuf,
+        /// Canisters to diff (all canisters if none are given).
+        #[clap(long, num_args(1..))]
+        canisters: Vec<PrincipalId>,
+        /// Output the differences as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+
     /// Computes partial state hash that is used for certification.
     #[clap(name = "chash")]
     CHash {
@@ -73,6 +86,14 @@ enum Opt {
         file: PathBuf,
     },
 
+    /// Audits the integrity of the files of a checkpoint.
+    #[clap(name = "audit")]
+    Audit {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+    },
+
     /// Enumerates persisted states.
     #[clap(name = "list")]
     ListStates {
@@ -89,6 +110,44 @@ enum Opt {
         file: PathBuf,
     },
 
+    /// Extracts a single canister from a checkpoint into a portable archive.
+    #[clap(name = "extract_canister")]
+    ExtractCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The canister to extract.
+        #[clap(long = "canister_id")]
+        canister_id: PrincipalId,
+        /// Path to the archive directory to create.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Executes a query method of a canister against a checkpoint.
+    #[clap(name = "query")]
+    #[clap(group(clap::ArgGroup::new("argument").args(&["arg", "arg_hex"])))]
+    Query {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The canister to query.
+        #[clap(long = "canister_id")]
+        canister_id: PrincipalId,
+        /// The name of the query method.
+        #[clap(long = "method")]
+        method: String,
+        /// The argument in the textual Candid format (defaults to `()`).
+        #[clap(long = "arg")]
+        arg: Option<String>,
+        /// The argument as a hex-string.
+        #[clap(long = "arg_hex")]
+        arg_hex: Option<String>,
+        /// The caller of the query (defaults to the anonymous principal).
+        #[clap(long = "caller", default_value_t = PrincipalId::new_anonymous())]
+        caller: PrincipalId,
+    },
+
     /// Converts textual principal representation to hex.
     #[clap(name = "canister_id_to_hex")]
     CanisterIdToHex {
@@ -219,6 +278,20 @@ pub(crate) fn main_inner(args: Vec<String>) {
     let opt = Parser::parse_from(args);
     let result = match opt {
         Opt::CDiff { path_a, path_b } => commands::cdiff::do_diff(path_a, path_b),
+        Opt::CanisterDiff {
+            path_a,
+            path_b,
+            canisters,
+            json,
+        } => commands::canister_diff::do_canister_diff(
+            path_a,
+            path_b,
+            canisters
+                .into_iter()
+                .map(CanisterId::unchecked_from_principal)
+                .collect(),
+            json,
+        ),
         Opt::CHash { path } => commands::chash::do_hash(path),
         Opt::ImportState {
             state,
@@ -232,8 +305,42 @@ pub(crate) fn main_inner(args: Vec<String>) {
         } => commands::copy::do_copy(source, destination, heights.into()),
         Opt::Manifest { path } => commands::manifest::do_compute_manifest(path),
         Opt::VerifyManifest { file } => commands::verify_manifest::do_verify_manifest(&file),
+        Opt::Audit { path } => commands::audit::do_audit(path),
         Opt::ListStates { config } => commands::list::do_list(config),
         Opt::Decode { file } => commands::decode::do_decode(file),
+        Opt::ExtractCanister {
+            path,
+            canister_id,
+            output,
+        } => commands::extract_canister::do_extract_canister(
+            path,
+            CanisterId::unchecked_from_principal(canister_id),
+            output,
+        ),
+        Opt::Query {
+            path,
+            canister_id,
+            method,
+            arg,
+            arg_hex,
+            caller,
+        } => match arg_hex.map(|arg_hex| hex::decode(arg_hex.trim_start_matches("0x"))) {
+            Some(Err(e)) => Err(format!("invalid hex argument: {}", e)),
+            Some(Ok(bytes)) => commands::query::do_query(
+                path,
+                CanisterId::unchecked_from_principal(canister_id),
+                method,
+                commands::query::QueryArg::Raw(bytes),
+                caller,
+            ),
+            None => commands::query::do_query(
+                path,
+                CanisterId::unchecked_from_principal(canister_id),
+                method,
+                commands::query::QueryArg::Candid(arg.unwrap_or_else(|| "()".to_string())),
+                caller,
+            ),
+        },
         Opt::CanisterIdToHex { canister_id } => {
             commands::convert_ids::do_canister_id_to_hex(canister_id)
         }
@@ -1206,6 +1206,7 @@ impl ExecutionTest {
                 response,
                 instructions_used,
                 heap_delta,
+                stable_memory_delta: _,
                 call_duration: _,
             } => (canister, response, instructions_used, heap_delta),
             ExecuteMessageResult::Paused { .. } => {
@@ -2126,6 +2127,22 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_chunked_call_payloads(mut self) -> Self {
+        self.execution_config
+            .embedders_config
+            .feature_flags
+            .chunked_call_payloads = FlagStatus::Enabled;
+        self
+    }
+
+    pub fn with_named_timers(mut self) -> Self {
+        self.execution_config
+            .embedders_config
+            .feature_flags
+            .named_timers = FlagStatus::Enabled;
+        self
+    }
+
     pub fn with_wasm64(mut self) -> Self {
         self.execution_config.embedders_config.feature_flags.wasm64 = FlagStatus::Enabled;
         self
@@ -6,7 +6,8 @@ use ic_protobuf::registry::crypto::v1::PublicKey as PublicKeyProto;
 use ic_protobuf::registry::subnet::v1::chain_key_initialization::Initialization;
 use ic_protobuf::registry::subnet::v1::ChainKeyInitialization;
 use ic_protobuf::registry::subnet::v1::{
-    CatchUpPackageContents, InitialNiDkgTranscriptRecord, SubnetListRecord, SubnetRecord,
+    BlockRateConfig, CatchUpPackageContents, InitialNiDkgTranscriptRecord, SubnetListRecord,
+    SubnetRecord,
 };
 use ic_protobuf::types::v1::master_public_key_id::KeyId;
 use ic_registry_client_fake::FakeRegistryClient;
@@ -232,6 +233,7 @@ pub fn test_subnet_record() -> SubnetRecord {
         ssh_readonly_access: vec![],
         ssh_backup_access: vec![],
         chain_key_config: None,
+        block_rate_config: None,
     }
 }
 
@@ -327,6 +329,11 @@ impl SubnetRecordBuilder {
         self
     }
 
+    pub fn with_block_rate_config(mut self, block_rate_config: BlockRateConfig) -> Self {
+        self.record.block_rate_config = Some(block_rate_config);
+        self
+    }
+
     pub fn with_membership(mut self, node_ids: &[NodeId]) -> Self {
         self.record.membership = node_ids
             .iter()
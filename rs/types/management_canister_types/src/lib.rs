@@ -113,6 +113,7 @@ pub enum Method {
     SubnetInfo,
 
     FetchCanisterLogs,
+    CanisterMethodProfile,
 
     // These methods are only available on test IC instances where there is a
     // need to fabricate cycles without burning ICP first.
@@ -129,6 +130,10 @@ pub enum Method {
     LoadCanisterSnapshot,
     ListCanisterSnapshots,
     DeleteCanisterSnapshot,
+    CanisterSnapshotDiff,
+    CloneCanisterSnapshot,
+    // Private API used exclusively between subnets to transfer cloned snapshots.
+    CloneCanisterSnapshotChunk,
 
     // Support for import and export of canister snapshots
     ReadCanisterSnapshotMetadata,
@@ -898,6 +903,7 @@ impl TryFrom<pb_canister_state_bits::LogVisibilityV2> for LogVisibilityV2 {
 ///     log_visibility: log_visibility;
 ///     wasm_memory_limit: nat;
 ///     wasm_memory_threshold: nat;
+///     log_min_level: canister_log_level;
 /// })`
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct DefiniteCanisterSettingsArgs {
@@ -910,9 +916,11 @@ pub struct DefiniteCanisterSettingsArgs {
     log_visibility: LogVisibilityV2,
     wasm_memory_limit: candid::Nat,
     wasm_memory_threshold: candid::Nat,
+    log_min_level: CanisterLogLevel,
 }
 
 impl DefiniteCanisterSettingsArgs {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         controller: PrincipalId,
         controllers: Vec<PrincipalId>,
@@ -923,6 +931,7 @@ impl DefiniteCanisterSettingsArgs {
         log_visibility: LogVisibilityV2,
         wasm_memory_limit: Option<u64>,
         wasm_memory_threshold: u64,
+        log_min_level: CanisterLogLevel,
     ) -> Self {
         let memory_allocation = candid::Nat::from(memory_allocation.unwrap_or(0));
         let reserved_cycles_limit = candid::Nat::from(reserved_cycles_limit.unwrap_or(0));
@@ -937,6 +946,7 @@ impl DefiniteCanisterSettingsArgs {
             log_visibility,
             wasm_memory_limit,
             wasm_memory_threshold: candid::Nat::from(wasm_memory_threshold),
+            log_min_level,
         }
     }
 
@@ -960,6 +970,10 @@ impl DefiniteCanisterSettingsArgs {
         self.wasm_memory_threshold.clone()
     }
 
+    pub fn log_min_level(&self) -> CanisterLogLevel {
+        self.log_min_level
+    }
+
     pub fn compute_allocation(&self) -> candid::Nat {
         self.compute_allocation.clone()
     }
@@ -1070,6 +1084,7 @@ impl CanisterStatusResultV2 {
         query_egress_payload_size: u128,
         wasm_memory_limit: Option<u64>,
         wasm_memory_threshold: u64,
+        log_min_level: CanisterLogLevel,
     ) -> Self {
         Self {
             status,
@@ -1100,6 +1115,7 @@ impl CanisterStatusResultV2 {
                 log_visibility,
                 wasm_memory_limit,
                 wasm_memory_threshold,
+                log_min_level,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
@@ -1805,6 +1821,7 @@ impl DataSize for PrincipalId {
 ///     log_visibility : opt log_visibility;
 ///     wasm_memory_limit: opt nat;
 ///     wasm_memory_threshold: opt nat;
+///     log_min_level: opt canister_log_level;
 /// })`
 #[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
 pub struct CanisterSettingsArgs {
@@ -1816,6 +1833,7 @@ pub struct CanisterSettingsArgs {
     pub log_visibility: Option<LogVisibilityV2>,
     pub wasm_memory_limit: Option<candid::Nat>,
     pub wasm_memory_threshold: Option<candid::Nat>,
+    pub log_min_level: Option<CanisterLogLevel>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1833,6 +1851,7 @@ impl CanisterSettingsArgs {
             log_visibility: None,
             wasm_memory_limit: None,
             wasm_memory_threshold: None,
+            log_min_level: None,
         }
     }
 }
@@ -1847,6 +1866,7 @@ pub struct CanisterSettingsArgsBuilder {
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<candid::Nat>,
     wasm_memory_threshold: Option<candid::Nat>,
+    log_min_level: Option<CanisterLogLevel>,
 }
 
 #[allow(dead_code)]
@@ -1865,6 +1885,7 @@ impl CanisterSettingsArgsBuilder {
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
             wasm_memory_threshold: self.wasm_memory_threshold,
+            log_min_level: self.log_min_level,
         }
     }
 
@@ -1949,6 +1970,15 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets the minimum level of log records written by `ic0.log` that are
+    /// kept. Records of `ic0.debug_print` and of traps are always kept.
+    pub fn with_log_min_level(self, log_min_level: CanisterLogLevel) -> Self {
+        Self {
+            log_min_level: Some(log_min_level),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -3061,6 +3091,46 @@ pub struct SubnetInfoResponse {
 
 impl Payload<'_> for SubnetInfoResponse {}
 
+/// `CandidType` for `CanisterMethodProfileRecord`
+/// ```text
+/// record {
+///     method_name: text;
+///     num_calls: nat64;
+///     instructions: nat64;
+///     max_instructions: nat64;
+///     cycles: nat;
+///     dirty_pages: nat64;
+///     instruction_histogram: vec nat64;
+/// }
+/// ```
+///
+/// The buckets of `instruction_histogram` count the calls that executed
+/// fewer than `10^4`, `10^5`, ..., `10^10` instructions with the last bucket
+/// counting the calls that executed at least `10^10` instructions.
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct CanisterMethodProfileRecord {
+    pub method_name: String,
+    pub num_calls: u64,
+    pub instructions: u64,
+    pub max_instructions: u64,
+    pub cycles: candid::Nat,
+    pub dirty_pages: u64,
+    pub instruction_histogram: Vec<u64>,
+}
+
+/// `CandidType` for `CanisterMethodProfileResponse`
+/// ```text
+/// record {
+///     methods: vec canister_method_profile_record;
+/// }
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct CanisterMethodProfileResponse {
+    pub methods: Vec<CanisterMethodProfileRecord>,
+}
+
+impl Payload<'_> for CanisterMethodProfileResponse {}
+
 /// `CandidType` for `NodeMetricsHistoryArgs`
 /// ```text
 /// record {
@@ -3112,11 +3182,31 @@ impl Payload<'_> for NodeMetricsHistoryResponse {}
 /// ```text
 /// record {
 ///     canister_id: principal;
+///     start_idx: opt nat64;
+///     start_timestamp_nanos: opt nat64;
+///     end_timestamp_nanos: opt nat64;
+///     content_contains: opt blob;
+///     min_level: opt canister_log_level;
+///     page_size: opt nat64;
 /// }
 /// ```
+///
+/// All filters are optional. A record is returned if its index is at least
+/// `start_idx`, its timestamp is within `[start_timestamp_nanos,
+/// end_timestamp_nanos)`, its content contains `content_contains`, and its
+/// level is at least `min_level`.
+/// At most `page_size` matching records (the ones with the lowest indices)
+/// are returned, so that logs can be tailed by passing the index following
+/// the last returned record as `start_idx` of the next request.
 #[derive(Clone, Debug, Default, CandidType, Deserialize)]
 pub struct FetchCanisterLogsRequest {
     pub canister_id: PrincipalId,
+    pub start_idx: Option<u64>,
+    pub start_timestamp_nanos: Option<u64>,
+    pub end_timestamp_nanos: Option<u64>,
+    pub content_contains: Option<Vec<u8>>,
+    pub min_level: Option<CanisterLogLevel>,
+    pub page_size: Option<u64>,
 }
 
 impl Payload<'_> for FetchCanisterLogsRequest {}
@@ -3125,12 +3215,190 @@ impl FetchCanisterLogsRequest {
     pub fn new(canister_id: CanisterId) -> Self {
         Self {
             canister_id: canister_id.into(),
+            ..Default::default()
         }
     }
 
     pub fn get_canister_id(&self) -> CanisterId {
         CanisterId::unchecked_from_principal(self.canister_id)
     }
+
+    pub fn with_start_idx(self, start_idx: u64) -> Self {
+        Self {
+            start_idx: Some(start_idx),
+            ..self
+        }
+    }
+
+    pub fn with_time_range(self, start_timestamp_nanos: u64, end_timestamp_nanos: u64) -> Self {
+        Self {
+            start_timestamp_nanos: Some(start_timestamp_nanos),
+            end_timestamp_nanos: Some(end_timestamp_nanos),
+            ..self
+        }
+    }
+
+    pub fn with_content_contains(self, content_contains: Vec<u8>) -> Self {
+        Self {
+            content_contains: Some(content_contains),
+            ..self
+        }
+    }
+
+    pub fn with_min_level(self, min_level: CanisterLogLevel) -> Self {
+        Self {
+            min_level: Some(min_level),
+            ..self
+        }
+    }
+
+    pub fn with_page_size(self, page_size: u64) -> Self {
+        Self {
+            page_size: Some(page_size),
+            ..self
+        }
+    }
+
+    /// Returns true if the record passes all filters of the request.
+    pub fn matches(&self, record: &CanisterLogRecord) -> bool {
+        self.start_idx.is_none_or(|idx| record.idx >= idx)
+            && self
+                .start_timestamp_nanos
+                .is_none_or(|start| record.timestamp_nanos >= start)
+            && self
+                .end_timestamp_nanos
+                .is_none_or(|end| record.timestamp_nanos < end)
+            && self.min_level.is_none_or(|level| record.level >= level)
+            && self.content_contains.as_ref().is_none_or(|needle| {
+                needle.is_empty()
+                    || record
+                        .content
+                        .windows(needle.len())
+                        .any(|window| window == needle.as_slice())
+            })
+    }
+
+    /// Returns the maximum number of records to return.
+    pub fn page_size(&self) -> usize {
+        self.page_size.map_or(usize::MAX, |page_size| {
+            usize::try_from(page_size).unwrap_or(usize::MAX)
+        })
+    }
+}
+
+/// The level of a canister log record. Records written by `ic0.debug_print`
+/// have level `info`, records of traps have level `error`.
+///
+/// The default is `debug`, the lowest level, so a canister that never sets
+/// `log_min_level` keeps all of its records.
+/// ```text
+/// variant {
+///     debug;
+///     info;
+///     warning;
+///     error;
+/// }
+/// ```
+#[derive(
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    Debug,
+    Default,
+    CandidType,
+    Deserialize,
+    Serialize,
+    EnumIter,
+)]
+pub enum CanisterLogLevel {
+    #[serde(rename = "debug")]
+    #[default]
+    Debug = 0,
+    #[serde(rename = "info")]
+    Info = 1,
+    #[serde(rename = "warning")]
+    Warning = 2,
+    #[serde(rename = "error")]
+    Error = 3,
+}
+
+impl TryFrom<u32> for CanisterLogLevel {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, String> {
+        match value {
+            0 => Ok(CanisterLogLevel::Debug),
+            1 => Ok(CanisterLogLevel::Info),
+            2 => Ok(CanisterLogLevel::Warning),
+            3 => Ok(CanisterLogLevel::Error),
+            _ => Err(format!("Invalid canister log level {}", value)),
+        }
+    }
+}
+
+impl From<CanisterLogLevel> for pb_canister_state_bits::CanisterLogLevel {
+    fn from(item: CanisterLogLevel) -> Self {
+        match item {
+            CanisterLogLevel::Debug => Self::Debug,
+            CanisterLogLevel::Info => Self::Info,
+            CanisterLogLevel::Warning => Self::Warning,
+            CanisterLogLevel::Error => Self::Error,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterLogLevel> for CanisterLogLevel {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::CanisterLogLevel) -> Result<Self, ProxyDecodeError> {
+        match item {
+            pb_canister_state_bits::CanisterLogLevel::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "CanisterLogLevel",
+                    err: format!("Unexpected value of canister log level: {:?}", item),
+                })
+            }
+            pb_canister_state_bits::CanisterLogLevel::Debug => Ok(CanisterLogLevel::Debug),
+            pb_canister_state_bits::CanisterLogLevel::Info => Ok(CanisterLogLevel::Info),
+            pb_canister_state_bits::CanisterLogLevel::Warning => Ok(CanisterLogLevel::Warning),
+            pb_canister_state_bits::CanisterLogLevel::Error => Ok(CanisterLogLevel::Error),
+        }
+    }
+}
+
+impl CanisterLogLevel {
+    /// Decodes a level stored in a protobuf (`level` is the raw enum value).
+    /// Unspecified or unknown levels, e.g. of records that were written before
+    /// levels were introduced, are decoded as `fallback`.
+    pub fn from_proto_or(level: i32, fallback: Self) -> Self {
+        pb_canister_state_bits::CanisterLogLevel::try_from(level)
+            .ok()
+            .and_then(|level| CanisterLogLevel::try_from(level).ok())
+            .unwrap_or(fallback)
+    }
+}
+
+/// `CandidType` for `CanisterLogField`
+/// ```text
+/// record {
+///     key: text;
+///     value: text;
+/// }
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize, Serialize)]
+pub struct CanisterLogField {
+    pub key: String,
+    pub value: String,
+}
+
+impl DataSize for CanisterLogField {
+    fn data_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.key.len() + self.value.len()
+    }
 }
 
 /// `CandidType` for `CanisterLogRecord`
@@ -3139,6 +3407,8 @@ impl FetchCanisterLogsRequest {
 ///     idx: nat64;
 ///     timestamp_nanos: nat64;
 ///     content: blob;
+///     level: canister_log_level;
+///     fields: vec canister_log_field;
 /// }
 /// ```
 #[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize, Serialize)]
@@ -3147,13 +3417,17 @@ pub struct CanisterLogRecord {
     pub timestamp_nanos: u64,
     #[serde(with = "serde_bytes")]
     pub content: Vec<u8>,
+    pub level: CanisterLogLevel,
+    pub fields: Vec<CanisterLogField>,
 }
 
 impl Payload<'_> for CanisterLogRecord {}
 
 impl DataSize for CanisterLogRecord {
     fn data_size(&self) -> usize {
-        std::mem::size_of::<Self>() + self.content.as_slice().data_size()
+        std::mem::size_of::<Self>()
+            + self.content.as_slice().data_size()
+            + self.fields.iter().map(|f| f.data_size()).sum::<usize>()
     }
 }
 
@@ -3163,8 +3437,17 @@ fn test_canister_log_record_data_size() {
         idx: 100,
         timestamp_nanos: 200,
         content: vec![1, 2, 3],
+        level: CanisterLogLevel::Info,
+        fields: vec![CanisterLogField {
+            key: "k".to_string(),
+            value: "vv".to_string(),
+        }],
     };
-    assert_eq!(record.data_size(), 8 + 8 + 24 + 3);
+    // The level is padded to 8 bytes.
+    assert_eq!(
+        record.data_size(),
+        8 + 8 + 24 + 24 + 8 + 3 + (24 + 24 + 1 + 2)
+    );
 }
 
 impl From<&CanisterLogRecord> for pb_canister_state_bits::CanisterLogRecord {
@@ -3173,6 +3456,15 @@ impl From<&CanisterLogRecord> for pb_canister_state_bits::CanisterLogRecord {
             idx: item.idx,
             timestamp_nanos: item.timestamp_nanos,
             content: item.content.clone(),
+            level: pb_canister_state_bits::CanisterLogLevel::from(item.level).into(),
+            fields: item
+                .fields
+                .iter()
+                .map(|field| pb_canister_state_bits::CanisterLogField {
+                    key: field.key.clone(),
+                    value: field.value.clone(),
+                })
+                .collect(),
         }
     }
 }
@@ -3183,6 +3475,15 @@ impl From<pb_canister_state_bits::CanisterLogRecord> for CanisterLogRecord {
             idx: item.idx,
             timestamp_nanos: item.timestamp_nanos,
             content: item.content,
+            level: CanisterLogLevel::from_proto_or(item.level, CanisterLogLevel::Info),
+            fields: item
+                .fields
+                .into_iter()
+                .map(|field| CanisterLogField {
+                    key: field.key,
+                    value: field.value,
+                })
+                .collect(),
         }
     }
 }
@@ -3442,11 +3743,16 @@ impl Payload<'_> for StoredChunksReply {}
 /// `(record {
 ///     canister_id: principal;
 ///     replace_snapshot: opt blob;
+///     base_snapshot: opt blob;
 /// })`
+///
+/// If `base_snapshot` is set, the new snapshot is taken incrementally against
+/// it and is only charged for the data that differs from the base snapshot.
 #[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
 pub struct TakeCanisterSnapshotArgs {
     pub canister_id: PrincipalId,
     pub replace_snapshot: Option<serde_bytes::ByteBuf>,
+    pub base_snapshot: Option<serde_bytes::ByteBuf>,
 }
 
 impl TakeCanisterSnapshotArgs {
@@ -3455,9 +3761,16 @@ impl TakeCanisterSnapshotArgs {
             canister_id: canister_id.get(),
             replace_snapshot: replace_snapshot
                 .map(|snapshot_id| ByteBuf::from(snapshot_id.to_vec())),
+            base_snapshot: None,
         }
     }
 
+    /// Takes the snapshot incrementally against `base_snapshot`.
+    pub fn with_base_snapshot(mut self, base_snapshot: SnapshotId) -> Self {
+        self.base_snapshot = Some(ByteBuf::from(base_snapshot.to_vec()));
+        self
+    }
+
     pub fn get_canister_id(&self) -> CanisterId {
         CanisterId::unchecked_from_principal(self.canister_id)
     }
@@ -3467,15 +3780,24 @@ impl TakeCanisterSnapshotArgs {
             .as_ref()
             .map(|bytes| SnapshotId::try_from(&bytes.clone().into_vec()).unwrap())
     }
+
+    pub fn base_snapshot(&self) -> Option<SnapshotId> {
+        self.base_snapshot
+            .as_ref()
+            .map(|bytes| SnapshotId::try_from(&bytes.clone().into_vec()).unwrap())
+    }
 }
 
 impl<'a> Payload<'a> for TakeCanisterSnapshotArgs {
     fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
         let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
 
-        if let Some(replace_snapshot) = &args.replace_snapshot {
+        for snapshot_id in [&args.replace_snapshot, &args.base_snapshot]
+            .into_iter()
+            .flatten()
+        {
             // Verify that snapshot ID has the correct format.
-            if let Err(err) = SnapshotId::try_from(&replace_snapshot.clone().into_vec()) {
+            if let Err(err) = SnapshotId::try_from(&snapshot_id.clone().into_vec()) {
                 return Err(UserError::new(
                     ErrorCode::InvalidManagementPayload,
                     format!("Payload deserialization error: {err:?}"),
@@ -3624,6 +3946,277 @@ impl<'a> Payload<'a> for DeleteCanisterSnapshotArgs {
     }
 }
 
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct CanisterSnapshotDiffArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub snapshot_id: Vec<u8>,
+}
+
+impl CanisterSnapshotDiffArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: SnapshotId) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.to_vec(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+impl<'a> Payload<'a> for CanisterSnapshotDiffArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+
+        // Verify that snapshot ID has the correct format.
+        if let Err(err) = SnapshotId::try_from(&args.snapshot_id) {
+            return Err(UserError::new(
+                ErrorCode::InvalidManagementPayload,
+                format!("Payload deserialization error: {err:?}"),
+            ));
+        }
+        Ok(args)
+    }
+}
+
+/// A range of memory pages `[start, start + count)`.
+/// `(record {
+///     start: nat64;
+///     count: nat64;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct PageRange {
+    pub start: u64,
+    pub count: u64,
+}
+
+/// Struct to be returned when comparing a canister with one of its snapshots.
+/// `(record {
+///     page_size: nat64;
+///     wasm_memory: vec page_range;
+///     stable_memory: vec page_range;
+///     wasm_module_changed: bool;
+///     wasm_chunk_store_changed: bool;
+///     truncated: bool;
+/// })`
+///
+/// The page ranges list the pages of the respective memory that are not
+/// shared between the snapshot and the current state of the canister. Pages
+/// are not compared by content, so a page that was rewritten with the same
+/// contents is listed, and all pages are listed once the snapshot and the
+/// canister were written to separate files at a checkpoint. If there are too
+/// many ranges to be returned, the lists are cut off and `truncated` is set.
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct CanisterSnapshotDiffResponse {
+    pub page_size: u64,
+    pub wasm_memory: Vec<PageRange>,
+    pub stable_memory: Vec<PageRange>,
+    pub wasm_module_changed: bool,
+    pub wasm_chunk_store_changed: bool,
+    pub truncated: bool,
+}
+
+impl Payload<'_> for CanisterSnapshotDiffResponse {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+///     target_canister_id: principal;
+///     replace_snapshot: opt blob;
+/// })`
+///
+/// Clones the snapshot `snapshot_id` of `canister_id` into a new snapshot of
+/// `target_canister_id`, which may be hosted on another subnet. If
+/// `replace_snapshot` is set, that snapshot of the target canister is replaced.
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct CloneCanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub snapshot_id: Vec<u8>,
+    pub target_canister_id: PrincipalId,
+    pub replace_snapshot: Option<serde_bytes::ByteBuf>,
+}
+
+impl CloneCanisterSnapshotArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+        target_canister_id: CanisterId,
+        replace_snapshot: Option<SnapshotId>,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.to_vec(),
+            target_canister_id: target_canister_id.get(),
+            replace_snapshot: replace_snapshot
+                .map(|snapshot_id| ByteBuf::from(snapshot_id.to_vec())),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+
+    pub fn get_target_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.target_canister_id)
+    }
+
+    pub fn replace_snapshot(&self) -> Option<SnapshotId> {
+        self.replace_snapshot
+            .as_ref()
+            .map(|bytes| SnapshotId::try_from(&bytes.clone().into_vec()).unwrap())
+    }
+}
+
+impl<'a> Payload<'a> for CloneCanisterSnapshotArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+
+        // Verify that snapshot IDs have the correct format.
+        let replace_snapshot = args.replace_snapshot.as_ref().map(|bytes| bytes.to_vec());
+        for snapshot_id in std::iter::once(&args.snapshot_id).chain(replace_snapshot.as_ref()) {
+            if let Err(err) = SnapshotId::try_from(snapshot_id) {
+                return Err(UserError::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!("Payload deserialization error: {err:?}"),
+                ));
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// A piece of a canister snapshot, transferred between subnets when cloning
+/// the snapshot into a canister on another subnet.
+/// `(variant {
+///     metadata: read_canister_snapshot_metadata_response;
+///     wasm_module: record { offset: nat64; data: blob };
+///     main_memory: record { offset: nat64; data: blob };
+///     stable_memory: record { offset: nat64; data: blob };
+///     wasm_chunk: record { data: blob };
+///     finish;
+///     abort;
+/// })`
+#[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub enum CanisterSnapshotChunk {
+    Metadata(ReadCanisterSnapshotMetadataResponse),
+    WasmModule {
+        offset: u64,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+    },
+    MainMemory {
+        offset: u64,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+    },
+    StableMemory {
+        offset: u64,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+    },
+    WasmChunk {
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+    },
+    Finish,
+    Abort,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     caller: principal;
+///     canister_id: principal;
+///     snapshot_id: opt blob;
+///     replace_snapshot: opt blob;
+///     chunk: canister_snapshot_chunk;
+/// })`
+///
+/// Sent by the subnet hosting the source canister of a `clone_canister_snapshot`
+/// call to the subnet hosting the target canister `canister_id`. `caller` is
+/// the principal that called `clone_canister_snapshot`. The initial `metadata`
+/// chunk creates the snapshot (replacing `replace_snapshot`, if set); all
+/// further chunks refer to it via `snapshot_id`.
+#[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub struct CloneCanisterSnapshotChunkArgs {
+    pub caller: PrincipalId,
+    pub canister_id: PrincipalId,
+    pub snapshot_id: Option<serde_bytes::ByteBuf>,
+    pub replace_snapshot: Option<serde_bytes::ByteBuf>,
+    pub chunk: CanisterSnapshotChunk,
+}
+
+impl CloneCanisterSnapshotChunkArgs {
+    pub fn new(
+        caller: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: Option<SnapshotId>,
+        replace_snapshot: Option<SnapshotId>,
+        chunk: CanisterSnapshotChunk,
+    ) -> Self {
+        Self {
+            caller,
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.map(|snapshot_id| ByteBuf::from(snapshot_id.to_vec())),
+            replace_snapshot: replace_snapshot
+                .map(|snapshot_id| ByteBuf::from(snapshot_id.to_vec())),
+            chunk,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> Option<SnapshotId> {
+        self.snapshot_id
+            .as_ref()
+            .map(|bytes| SnapshotId::try_from(&bytes.clone().into_vec()).unwrap())
+    }
+
+    pub fn replace_snapshot(&self) -> Option<SnapshotId> {
+        self.replace_snapshot
+            .as_ref()
+            .map(|bytes| SnapshotId::try_from(&bytes.clone().into_vec()).unwrap())
+    }
+}
+
+impl<'a> Payload<'a> for CloneCanisterSnapshotChunkArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+
+        for snapshot_id in [&args.snapshot_id, &args.replace_snapshot]
+            .into_iter()
+            .flatten()
+        {
+            // Verify that snapshot ID has the correct format.
+            if let Err(err) = SnapshotId::try_from(&snapshot_id.clone().into_vec()) {
+                return Err(UserError::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!("Payload deserialization error: {err:?}"),
+                ));
+            }
+        }
+        Ok(args)
+    }
+}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     canister_id: principal;
@@ -1,5 +1,7 @@
 use candid::Deserialize;
-use ic_management_canister_types_private::{CanisterLogRecord, DataSize};
+use ic_management_canister_types_private::{
+    CanisterLogField, CanisterLogLevel, CanisterLogRecord, DataSize,
+};
 use ic_validate_eq::ValidateEq;
 use ic_validate_eq_derive::ValidateEq;
 use serde::Serialize;
@@ -9,9 +11,18 @@ use std::collections::VecDeque;
 pub const MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
 
 fn truncate_content(mut record: CanisterLogRecord) -> CanisterLogRecord {
-    let max_content_size =
+    let max_data_size =
         MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE - std::mem::size_of::<CanisterLogRecord>();
-    record.content.truncate(max_content_size);
+    let fields_size = |fields: &[CanisterLogField]| -> usize {
+        fields.iter().map(|field| field.data_size()).sum()
+    };
+    // Drop the fields if they alone do not fit into the buffer.
+    if fields_size(&record.fields) > max_data_size {
+        record.fields.clear();
+    }
+    record
+        .content
+        .truncate(max_data_size - fields_size(&record.fields));
     record
 }
 
@@ -154,13 +165,26 @@ impl CanisterLog {
         records.capacity().saturating_sub(records.used_space())
     }
 
-    /// Adds a new log record.
+    /// Adds a new log record with level `info` and without fields.
     pub fn add_record(&mut self, timestamp_nanos: u64, content: Vec<u8>) {
+        self.add_structured_record(timestamp_nanos, CanisterLogLevel::Info, vec![], content);
+    }
+
+    /// Adds a new log record with the given level and fields.
+    pub fn add_structured_record(
+        &mut self,
+        timestamp_nanos: u64,
+        level: CanisterLogLevel,
+        fields: Vec<CanisterLogField>,
+        content: Vec<u8>,
+    ) {
         // Add record and update the next index.
         self.records.push_back(truncate_content(CanisterLogRecord {
             idx: self.next_idx,
             timestamp_nanos,
             content,
+            level,
+            fields,
         }));
         self.next_idx += 1;
     }
@@ -190,6 +214,7 @@ mod tests {
                 idx,
                 timestamp_nanos,
                 content: content.to_vec(),
+                ..Default::default()
             })
             .collect()
     }
@@ -262,6 +287,37 @@ mod tests {
         assert_eq!(log.next_idx(), records_number as u64);
     }
 
+    #[test]
+    fn test_canister_log_add_structured_record_applies_memory_limit() {
+        let mut log = CanisterLog::default();
+        let fields = vec![CanisterLogField {
+            key: "key".to_string(),
+            value: "value".to_string(),
+        }];
+        log.add_structured_record(
+            100,
+            CanisterLogLevel::Warning,
+            fields.clone(),
+            BIGGER_THAN_LIMIT_MESSAGE.to_vec(),
+        );
+        // Assert the content is truncated to make room for the fields.
+        let record = log.records().back().unwrap();
+        assert_eq!(record.level, CanisterLogLevel::Warning);
+        assert_eq!(record.fields, fields);
+        assert_eq!(log.used_space(), TEST_MAX_ALLOWED_SIZE);
+
+        // Assert fields that do not fit into the buffer are dropped.
+        let fields = vec![CanisterLogField {
+            key: "key".to_string(),
+            value: String::from_utf8(BIGGER_THAN_LIMIT_MESSAGE.to_vec()).unwrap(),
+        }];
+        log.add_structured_record(101, CanisterLogLevel::Error, fields, b"message".to_vec());
+        let record = log.records().back().unwrap();
+        assert!(record.fields.is_empty());
+        assert_eq!(record.content, b"message".to_vec());
+        assert_eq!(log.records().len(), 1);
+    }
+
     #[test]
     fn test_canister_log_adds_records() {
         let mut log = CanisterLog::default();
@@ -0,0 +1,296 @@
+//! Named canister timers managed by the system.
+//!
+//! In addition to the single global timer, a canister may register a bounded
+//! number of named timers, each with an optional period. When a timer expires,
+//! the system executes `canister_global_timer` and exposes the name of the
+//! expired timer via `ic0.timer_name_size` and `ic0.timer_name_copy`.
+//!
+//! Expired named timers take precedence over the global timer: an execution of
+//! `canister_global_timer` serves at most one of them, so the global timer
+//! stays active and fires in a later round once no named timer is due.
+
+use crate::{NumBytes, Time};
+use ic_protobuf::state::canister_state_bits::v1 as pb;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// The maximum number of named timers a canister can have at any time.
+pub const MAX_NAMED_TIMERS: usize = 64;
+
+/// The maximum length of the name of a timer in bytes.
+pub const MAX_NAMED_TIMER_NAME_LEN: usize = 64;
+
+/// The number of bytes charged for a named timer on top of the length of its
+/// name. Covers the deadline, the interval and the bookkeeping overhead.
+pub const NAMED_TIMER_OVERHEAD_BYTES: u64 = 32;
+
+/// A single named timer.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct NamedTimer {
+    /// The time at which the timer expires next.
+    pub deadline: Time,
+    /// The period of a periodic timer. `None` for one-off timers.
+    pub interval: Option<Duration>,
+}
+
+/// Errors that may occur when setting a named timer.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum CanisterTimersError {
+    TooManyTimers { limit: usize },
+    NameTooLong { length: usize, limit: usize },
+}
+
+impl fmt::Display for CanisterTimersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyTimers { limit } => {
+                write!(
+                    f,
+                    "The canister cannot have more than {} named timers",
+                    limit
+                )
+            }
+            Self::NameTooLong { length, limit } => write!(
+                f,
+                "The timer name is {} bytes long, but at most {} bytes are allowed",
+                length, limit
+            ),
+        }
+    }
+}
+
+/// The set of named timers of a canister, keyed by name.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub struct CanisterTimers {
+    timers: BTreeMap<String, NamedTimer>,
+}
+
+impl CanisterTimers {
+    /// Returns the number of bytes charged for a timer with the given name.
+    pub fn timer_memory_usage(name: &str) -> NumBytes {
+        NumBytes::new(name.len() as u64 + NAMED_TIMER_OVERHEAD_BYTES)
+    }
+
+    /// Returns the number of bytes charged for all timers.
+    pub fn memory_usage(&self) -> NumBytes {
+        self.timers
+            .keys()
+            .map(|name| Self::timer_memory_usage(name))
+            .sum()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&NamedTimer> {
+        self.timers.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &NamedTimer)> {
+        self.timers.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+
+    /// Checks whether a timer with the given name can be set without
+    /// exceeding the limits.
+    pub fn can_set(&self, name: &str) -> Result<(), CanisterTimersError> {
+        if name.len() > MAX_NAMED_TIMER_NAME_LEN {
+            return Err(CanisterTimersError::NameTooLong {
+                length: name.len(),
+                limit: MAX_NAMED_TIMER_NAME_LEN,
+            });
+        }
+        if !self.timers.contains_key(name) && self.timers.len() >= MAX_NAMED_TIMERS {
+            return Err(CanisterTimersError::TooManyTimers {
+                limit: MAX_NAMED_TIMERS,
+            });
+        }
+        Ok(())
+    }
+
+    /// Sets (or replaces) the timer with the given name. A zero `interval`
+    /// makes the timer one-off. Returns the replaced timer, if any.
+    pub fn set(
+        &mut self,
+        name: String,
+        deadline: Time,
+        interval: Option<Duration>,
+    ) -> Result<Option<NamedTimer>, CanisterTimersError> {
+        self.can_set(&name)?;
+        let timer = NamedTimer {
+            deadline,
+            interval: interval.filter(|interval| !interval.is_zero()),
+        };
+        Ok(self.timers.insert(name, timer))
+    }
+
+    /// Removes the timer with the given name, returning it if it existed.
+    pub fn cancel(&mut self, name: &str) -> Option<NamedTimer> {
+        self.timers.remove(name)
+    }
+
+    /// Returns true if at least one timer has reached its deadline.
+    pub fn has_reached_deadline(&self, now: Time) -> bool {
+        self.timers.values().any(|timer| timer.deadline <= now)
+    }
+
+    /// Takes the timer with the earliest expired deadline (ties broken by
+    /// name) and returns its name.
+    ///
+    /// One-off timers are removed. Periodic timers are moved to their first
+    /// deadline after `now`, so that missed periods are coalesced into a
+    /// single execution.
+    pub fn take_next_expired(&mut self, now: Time) -> Option<String> {
+        let name = self
+            .timers
+            .iter()
+            .filter(|(_, timer)| timer.deadline <= now)
+            .min_by_key(|(name, timer)| (timer.deadline, name.as_str()))
+            .map(|(name, _)| name.clone())?;
+
+        let timer = self.timers.get_mut(&name).unwrap();
+        match timer.interval {
+            None => {
+                self.timers.remove(&name);
+            }
+            Some(interval) => {
+                let interval = interval.as_nanos() as u64;
+                let elapsed = now.saturating_duration_since(timer.deadline).as_nanos() as u64;
+                let periods = elapsed / interval + 1;
+                timer.deadline = Time::from_nanos_since_unix_epoch(
+                    timer
+                        .deadline
+                        .as_nanos_since_unix_epoch()
+                        .saturating_add(periods.saturating_mul(interval)),
+                );
+            }
+        }
+        Some(name)
+    }
+}
+
+impl From<&CanisterTimers> for pb::CanisterTimers {
+    fn from(item: &CanisterTimers) -> Self {
+        Self {
+            timers: item
+                .timers
+                .iter()
+                .map(|(name, timer)| pb::NamedTimer {
+                    name: name.clone(),
+                    deadline_nanos: timer.deadline.as_nanos_since_unix_epoch(),
+                    interval_nanos: timer.interval.map(|interval| interval.as_nanos() as u64),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<pb::CanisterTimers> for CanisterTimers {
+    fn from(item: pb::CanisterTimers) -> Self {
+        Self {
+            timers: item
+                .timers
+                .into_iter()
+                .map(|timer| {
+                    (
+                        timer.name,
+                        NamedTimer {
+                            deadline: Time::from_nanos_since_unix_epoch(timer.deadline_nanos),
+                            interval: timer.interval_nanos.map(Duration::from_nanos),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(seconds: u64) -> Time {
+        Time::from_nanos_since_unix_epoch(seconds * 1_000_000_000)
+    }
+
+    #[test]
+    fn take_next_expired_removes_one_off_timers() {
+        let mut timers = CanisterTimers::default();
+        timers.set("b".to_string(), time(10), None).unwrap();
+        timers.set("a".to_string(), time(10), None).unwrap();
+        timers.set("c".to_string(), time(20), None).unwrap();
+
+        assert_eq!(timers.take_next_expired(time(5)), None);
+        assert_eq!(timers.take_next_expired(time(15)), Some("a".to_string()));
+        assert_eq!(timers.take_next_expired(time(15)), Some("b".to_string()));
+        assert_eq!(timers.take_next_expired(time(15)), None);
+        assert_eq!(timers.len(), 1);
+    }
+
+    #[test]
+    fn take_next_expired_coalesces_missed_periods() {
+        let mut timers = CanisterTimers::default();
+        timers
+            .set("tick".to_string(), time(10), Some(Duration::from_secs(5)))
+            .unwrap();
+
+        assert_eq!(timers.take_next_expired(time(27)), Some("tick".to_string()));
+        assert_eq!(timers.get("tick").unwrap().deadline, time(30));
+        assert_eq!(timers.take_next_expired(time(29)), None);
+    }
+
+    #[test]
+    fn set_enforces_limits() {
+        let mut timers = CanisterTimers::default();
+        let long_name = "x".repeat(MAX_NAMED_TIMER_NAME_LEN + 1);
+        assert_eq!(
+            timers.set(long_name, time(1), None),
+            Err(CanisterTimersError::NameTooLong {
+                length: MAX_NAMED_TIMER_NAME_LEN + 1,
+                limit: MAX_NAMED_TIMER_NAME_LEN
+            })
+        );
+
+        for i in 0..MAX_NAMED_TIMERS {
+            timers.set(i.to_string(), time(1), None).unwrap();
+        }
+        assert_eq!(
+            timers.set("one too many".to_string(), time(1), None),
+            Err(CanisterTimersError::TooManyTimers {
+                limit: MAX_NAMED_TIMERS
+            })
+        );
+        // Replacing an existing timer is always possible.
+        timers.set("0".to_string(), time(2), None).unwrap();
+        assert_eq!(
+            timers.memory_usage(),
+            NumBytes::new(
+                (0..MAX_NAMED_TIMERS)
+                    .map(|i| i.to_string().len() as u64 + NAMED_TIMER_OVERHEAD_BYTES)
+                    .sum()
+            )
+        );
+    }
+
+    #[test]
+    fn proto_round_trip() {
+        let mut timers = CanisterTimers::default();
+        timers.set("once".to_string(), time(1), None).unwrap();
+        timers
+            .set("every".to_string(), time(2), Some(Duration::from_secs(3)))
+            .unwrap();
+
+        let proto = pb::CanisterTimers::from(&timers);
+        assert_eq!(CanisterTimers::from(proto), timers);
+    }
+}
@@ -32,8 +32,8 @@ pub use ingress_messages::{
     SignedIngressContent,
 };
 pub use inter_canister::{
-    CallContextId, CallbackId, Payload, RejectContext, Request, RequestMetadata, RequestOrResponse,
-    Response, MAX_REJECT_MESSAGE_LEN_BYTES, NO_DEADLINE,
+    CallContextId, CallbackId, Payload, PayloadChunk, RejectContext, Request, RequestMetadata,
+    RequestOrResponse, Response, MAX_REJECT_MESSAGE_LEN_BYTES, NO_DEADLINE,
 };
 pub use message_id::{MessageId, MessageIdError, EXPECTED_MESSAGE_ID_LENGTH};
 use phantom_newtype::Id;
@@ -62,6 +62,12 @@ pub const MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64: u64 = 2 * 1024 * 1024; // 2 M
 pub const MAX_INTER_CANISTER_PAYLOAD_IN_BYTES: NumBytes =
     NumBytes::new(MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64); // 2 MiB
 
+/// The maximum total payload size of a chunked call (see `PayloadChunk`). The
+/// payload is split into chunks of at most [MAX_INTER_CANISTER_PAYLOAD_IN_BYTES]
+/// (including the method name) that are sent one after the other.
+pub const MAX_CHUNKED_CALL_PAYLOAD_IN_BYTES: NumBytes =
+    NumBytes::new(16 * MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64); // 32 MiB
+
 /// The maximum size of an inter-canister request or response that the IC can
 /// support.
 ///
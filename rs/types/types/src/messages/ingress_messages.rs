@@ -12,10 +12,11 @@ use crate::{
 };
 use ic_error_types::{ErrorCode, UserError};
 use ic_management_canister_types_private::{
-    CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs, DeleteCanisterSnapshotArgs,
-    InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
-    Method, Payload, ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotMetadataArgs,
-    StoredChunksArgs, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadCanisterSnapshotDataArgs,
+    CanisterIdRecord, CanisterInfoRequest, CanisterSnapshotDiffArgs, ClearChunkStoreArgs,
+    DeleteCanisterSnapshotArgs, InstallChunkedCodeArgs, InstallCodeArgsV2,
+    ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, Method, Payload,
+    ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotMetadataArgs, StoredChunksArgs,
+    TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadCanisterSnapshotDataArgs,
     UploadCanisterSnapshotMetadataArgs, UploadChunkArgs, IC_00,
 };
 use ic_protobuf::{
@@ -498,6 +499,7 @@ pub fn extract_effective_canister_id(
         }
         Ok(Method::StartCanister)
         | Ok(Method::CanisterStatus)
+        | Ok(Method::CanisterMethodProfile)
         | Ok(Method::DeleteCanister)
         | Ok(Method::UninstallCode)
         | Ok(Method::StopCanister) => match CanisterIdRecord::decode(ingress.arg()) {
@@ -552,6 +554,10 @@ pub fn extract_effective_canister_id(
                 Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
             }
         }
+        Ok(Method::CanisterSnapshotDiff) => match CanisterSnapshotDiffArgs::decode(ingress.arg()) {
+            Ok(record) => Ok(Some(record.get_canister_id())),
+            Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
+        },
         Ok(Method::ReadCanisterSnapshotMetadata) => {
             match ReadCanisterSnapshotMetadataArgs::decode(ingress.arg()) {
                 Ok(record) => Ok(Some(record.get_canister_id())),
@@ -599,7 +605,9 @@ pub fn extract_effective_canister_id(
         | Ok(Method::BitcoinGetCurrentFeePercentiles)
         | Ok(Method::NodeMetricsHistory)
         | Ok(Method::SubnetInfo)
-        | Ok(Method::FetchCanisterLogs) => {
+        | Ok(Method::FetchCanisterLogs)
+        | Ok(Method::CloneCanisterSnapshot)
+        | Ok(Method::CloneCanisterSnapshotChunk) => {
             // Subnet method not allowed for ingress.
             Err(ParseIngressError::SubnetMethodNotAllowed)
         }
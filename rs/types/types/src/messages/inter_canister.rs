@@ -7,7 +7,8 @@ use ic_error_types::{RejectCode, UserError};
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
 use ic_management_canister_types_private::{
-    CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs, DeleteCanisterSnapshotArgs,
+    CanisterIdRecord, CanisterInfoRequest, CanisterSnapshotDiffArgs, ClearChunkStoreArgs,
+    CloneCanisterSnapshotArgs, CloneCanisterSnapshotChunkArgs, DeleteCanisterSnapshotArgs,
     InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
     Method, Payload as _, ProvisionalTopUpCanisterArgs, ReadCanisterSnapshotDataArgs,
     ReadCanisterSnapshotMetadataArgs, StoredChunksArgs, TakeCanisterSnapshotArgs,
@@ -55,6 +56,28 @@ pub enum CallContextIdTag {}
 /// Identifies an incoming call.
 pub type CallContextId = Id<CallContextIdTag, u64>;
 
+/// Identifies one chunk of the payload of a chunked call.
+///
+/// The payload of a chunked call is delivered to the callee as a sequence of
+/// `count` requests to the same method. The request carrying chunk `index + 1`
+/// is only sent once the request carrying chunk `index` has been replied to.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct PayloadChunk {
+    pub index: u32,
+    pub count: u32,
+}
+
+impl PayloadChunk {
+    /// The only chunk of a call whose payload is not chunked.
+    pub const WHOLE: Self = Self { index: 0, count: 1 };
+
+    /// Returns `true` if this is the last chunk of the payload.
+    pub fn is_last(&self) -> bool {
+        self.index.saturating_add(1) >= self.count
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
 #[cfg_attr(test, derive(ExhaustiveSet))]
 pub struct RequestMetadata {
@@ -63,6 +86,9 @@ pub struct RequestMetadata {
     /// The block time (on the respective subnet) at the start of the call at the
     /// root of the call tree that this request is part of.
     call_tree_start_time: Time,
+    /// Set iff the request carries one chunk of the payload of a chunked call.
+    /// Unlike the other fields, this is not inherited by downstream calls.
+    payload_chunk: Option<PayloadChunk>,
 }
 
 impl Default for RequestMetadata {
@@ -76,6 +102,16 @@ impl RequestMetadata {
         Self {
             call_tree_depth,
             call_tree_start_time,
+            payload_chunk: None,
+        }
+    }
+
+    /// Returns a copy of this `RequestMetadata` for a request carrying the
+    /// given chunk of a chunked call.
+    pub fn with_payload_chunk(&self, payload_chunk: PayloadChunk) -> Self {
+        Self {
+            payload_chunk: Some(payload_chunk),
+            ..self.clone()
         }
     }
 
@@ -97,6 +133,10 @@ impl RequestMetadata {
     pub fn call_tree_start_time(&self) -> &Time {
         &self.call_tree_start_time
     }
+
+    pub fn payload_chunk(&self) -> Option<&PayloadChunk> {
+        self.payload_chunk.as_ref()
+    }
 }
 
 /// Canister-to-canister request message.
@@ -151,6 +191,7 @@ impl Request {
             Ok(Method::ProvisionalCreateCanisterWithCycles) => None,
             Ok(Method::StartCanister)
             | Ok(Method::CanisterStatus)
+            | Ok(Method::CanisterMethodProfile)
             | Ok(Method::DeleteCanister)
             | Ok(Method::UninstallCode)
             | Ok(Method::DepositCycles)
@@ -220,6 +261,24 @@ impl Request {
                     Err(_) => None,
                 }
             }
+            Ok(Method::CanisterSnapshotDiff) => {
+                match CanisterSnapshotDiffArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::CloneCanisterSnapshot) => {
+                match CloneCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::CloneCanisterSnapshotChunk) => {
+                match CloneCanisterSnapshotChunkArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
             Ok(Method::ReadCanisterSnapshotMetadata) => {
                 match ReadCanisterSnapshotMetadataArgs::decode(&self.method_payload) {
                     Ok(record) => Some(record.get_canister_id()),
@@ -656,6 +715,10 @@ impl From<&RequestMetadata> for pb_queues::RequestMetadata {
             call_tree_depth: metadata.call_tree_depth,
             call_tree_start_time_nanos: metadata.call_tree_start_time.as_nanos_since_unix_epoch(),
             call_subtree_deadline_nanos: None,
+            payload_chunk: metadata.payload_chunk.map(|chunk| pb_queues::PayloadChunk {
+                index: chunk.index,
+                count: chunk.count,
+            }),
         }
     }
 }
@@ -683,6 +746,10 @@ impl From<pb_queues::RequestMetadata> for RequestMetadata {
             call_tree_start_time: Time::from_nanos_since_unix_epoch(
                 metadata.call_tree_start_time_nanos,
             ),
+            payload_chunk: metadata.payload_chunk.map(|chunk| PayloadChunk {
+                index: chunk.index,
+                count: chunk.count,
+            }),
         }
     }
 }
@@ -1,7 +1,11 @@
 //! This module contains a collection of types and structs that define the
 //! various types of methods in the IC.
 
-use crate::{messages::CallContextId, time::CoarseTime, Cycles};
+use crate::{
+    messages::{CallContextId, PayloadChunk, RequestMetadata},
+    time::CoarseTime,
+    Cycles,
+};
 use ic_base_types::{CanisterId, PrincipalId};
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
@@ -10,6 +14,7 @@ use ic_protobuf::state::{canister_state_bits::v1 as pb, queues::v1::Cycles as Pb
 use ic_protobuf::types::v1 as pb_types;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::VecDeque,
     convert::{From, TryFrom},
     fmt,
 };
@@ -258,7 +263,8 @@ pub struct Callback {
     pub originator: CanisterId,
     /// The ID of the principal that the request was addressed to.
     pub respondent: CanisterId,
-    /// The number of cycles that were sent in the original request.
+    /// The number of cycles that were sent in the original request (along with
+    /// the last chunk, for chunked calls).
     pub cycles_sent: Cycles,
     /// Cycles prepaid by the caller for response execution.
     ///
@@ -277,6 +283,8 @@ pub struct Callback {
     pub on_cleanup: Option<WasmClosure>,
     /// If non-zero, this is a best-effort call.
     pub deadline: CoarseTime,
+    /// The chunks of a chunked call that remain to be sent, if any.
+    pub pending_chunks: Option<PendingPayloadChunks>,
 }
 
 impl Callback {
@@ -303,7 +311,85 @@ impl Callback {
             on_reject,
             on_cleanup,
             deadline,
+            pending_chunks: None,
+        }
+    }
+
+    /// Attaches the chunks of a chunked call that remain to be sent after the
+    /// first one.
+    pub fn with_pending_chunks(self, pending_chunks: PendingPayloadChunks) -> Self {
+        Self {
+            pending_chunks: Some(pending_chunks),
+            ..self
+        }
+    }
+}
+
+/// The chunks of the payload of a chunked call that have not been sent yet.
+///
+/// The first chunk is sent when the call is performed. Each following chunk is
+/// sent when the request carrying the previous chunk is replied to.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PendingPayloadChunks {
+    /// The method that all chunks are sent to.
+    pub method_name: String,
+    /// The metadata of the request carrying the first chunk, without
+    /// `payload_chunk`.
+    pub metadata: RequestMetadata,
+    /// The total number of chunks, including the ones already sent.
+    pub count: u32,
+    /// The chunks that remain to be sent, in order.
+    pub chunks: VecDeque<Vec<u8>>,
+}
+
+impl PendingPayloadChunks {
+    /// Returns the chunk metadata and payload of the next chunk to send.
+    pub fn pop(&mut self) -> Option<(PayloadChunk, Vec<u8>)> {
+        let index = self.count - self.chunks.len() as u32;
+        let chunk = self.chunks.pop_front()?;
+        Some((
+            PayloadChunk {
+                index,
+                count: self.count,
+            },
+            chunk,
+        ))
+    }
+
+    /// Returns the number of bytes held by the chunks that remain to be sent.
+    pub fn payload_size_bytes(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.len()).sum()
+    }
+}
+
+impl From<&PendingPayloadChunks> for pb::PendingPayloadChunks {
+    fn from(item: &PendingPayloadChunks) -> Self {
+        Self {
+            method_name: item.method_name.clone(),
+            metadata: Some((&item.metadata).into()),
+            count: item.count,
+            chunks: item.chunks.iter().cloned().collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::PendingPayloadChunks> for PendingPayloadChunks {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::PendingPayloadChunks) -> Result<Self, Self::Error> {
+        if value.chunks.len() >= value.count as usize {
+            return Err(ProxyDecodeError::Other(format!(
+                "PendingPayloadChunks: {} pending chunks out of {}",
+                value.chunks.len(),
+                value.count
+            )));
         }
+        Ok(Self {
+            method_name: value.method_name,
+            metadata: value.metadata.map(From::from).unwrap_or_default(),
+            count: value.count,
+            chunks: value.chunks.into(),
+        })
     }
 }
 
@@ -331,6 +417,7 @@ impl From<&Callback> for pb::Callback {
                 env: on_cleanup.env,
             }),
             deadline_seconds: item.deadline.as_secs_since_unix_epoch(),
+            pending_chunks: item.pending_chunks.as_ref().map(From::from),
         }
     }
 }
@@ -375,6 +462,10 @@ impl TryFrom<pb::Callback> for Callback {
                 env: on_cleanup.env,
             }),
             deadline: CoarseTime::from_secs_since_unix_epoch(value.deadline_seconds),
+            pending_chunks: value
+                .pending_chunks
+                .map(PendingPayloadChunks::try_from)
+                .transpose()?,
         })
     }
 }
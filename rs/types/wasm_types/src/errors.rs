@@ -75,6 +75,10 @@ pub enum WasmValidationError {
     InvalidCustomSection(String),
     /// Module contains an invalid global section
     InvalidGlobalSection(String),
+    /// Module contains an invalid tag section
+    InvalidTagSection(String),
+    /// The binary is an invalid or unsupported Wasm component.
+    InvalidComponent(String),
     /// Module contains too many globals.
     TooManyGlobals { defined: usize, allowed: usize },
     /// Module contains too many functions.
@@ -160,6 +164,12 @@ impl std::fmt::Display for WasmValidationError {
             Self::InvalidGlobalSection(err) => {
                 write!(f, "Wasm module has an invalid global section. {err}")
             }
+            Self::InvalidTagSection(err) => {
+                write!(f, "Wasm module has an invalid tag section. {err}")
+            }
+            Self::InvalidComponent(err) => {
+                write!(f, "Wasm component is invalid or unsupported. {err}")
+            }
             Self::TooManyGlobals { defined, allowed } => write!(
                 f,
                 "Wasm module defined {defined} \
@@ -232,6 +242,8 @@ impl AsErrorHelp for WasmValidationError {
             | WasmValidationError::InvalidDataSection(_)
             | WasmValidationError::InvalidCustomSection(_)
             | WasmValidationError::InvalidGlobalSection(_)
+            | WasmValidationError::InvalidTagSection(_)
+            | WasmValidationError::InvalidComponent(_)
             | WasmValidationError::UnsupportedWasmInstruction { .. }
             | WasmValidationError::TooManyCustomSections { .. } => ErrorHelp::ToolchainError,
             WasmValidationError::DuplicateExport { name } => ErrorHelp::UserError {
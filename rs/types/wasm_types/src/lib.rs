@@ -114,6 +114,32 @@ impl CanisterModule {
     pub fn module_hash(&self) -> [u8; WASM_HASH_LENGTH] {
         self.module_hash
     }
+
+    /// Overwrites the bytes of the module starting at `offset` with `buf` and
+    /// recomputes the module hash. The module cannot grow: `buf` must fit
+    /// within the current length of the module.
+    ///
+    /// Copies the binary unless it is held in memory and not shared.
+    pub fn write(&mut self, buf: &[u8], offset: usize) -> Result<(), String> {
+        let end = offset
+            .checked_add(buf.len())
+            .filter(|end| *end <= self.len())
+            .ok_or_else(|| {
+                format!(
+                    "Write of {} bytes at offset {} exceeds the module size of {} bytes",
+                    buf.len(),
+                    offset,
+                    self.len()
+                )
+            })?;
+        let mut bytes = match &mut self.module {
+            ModuleStorage::Memory(shared) => std::mem::take(Arc::make_mut(shared)),
+            ModuleStorage::File(_, mmap) => mmap.as_slice().to_vec(),
+        };
+        bytes[offset..end].copy_from_slice(buf);
+        *self = Self::new(bytes);
+        Ok(())
+    }
 }
 
 impl fmt::Debug for CanisterModule {
@@ -206,6 +232,16 @@ fn wasmhash_display() {
     assert_eq!(expected, format!("{}", hash));
 }
 
+#[test]
+fn canister_module_write() {
+    let mut module = CanisterModule::new(vec![0; 6]);
+    module.write(&[1, 2, 3], 2).unwrap();
+    assert_eq!(module.as_slice(), &[0, 0, 1, 2, 3, 0]);
+    assert_eq!(module, CanisterModule::new(vec![0, 0, 1, 2, 3, 0]));
+    assert!(module.write(&[1, 2], 5).is_err());
+    assert!(module.write(&[1], usize::MAX).is_err());
+}
+
 // We introduce another enum instead of making `BinaryEncodedWasm` an enum to
 // keep constructors private. We want `BinaryEncodedWasm` to be visible, but not
 // its structure.
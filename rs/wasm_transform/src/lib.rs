@@ -3,7 +3,7 @@ use std::ops::Range;
 
 use wasmparser::{
     BinaryReaderError, Export, GlobalType, Import, MemoryType, Name, Operator, Parser, Payload,
-    RefType, SubType, Subsections, TableType, ValType,
+    RefType, SubType, Subsections, TableType, TagKind, TagType, ValType,
 };
 
 mod convert;
@@ -169,6 +169,8 @@ pub struct Module<'a> {
     /// Each table has a type and optional initialization expression.
     pub tables: Vec<(TableType, Option<Operator<'a>>)>,
     pub memories: Vec<MemoryType>,
+    /// Exception tags of the exception-handling proposal.
+    pub tags: Vec<TagType>,
     pub globals: Vec<Global<'a>>,
     pub data: Vec<DataSegment<'a>>,
     pub data_count_section_exists: bool,
@@ -189,6 +191,7 @@ impl<'a> Module<'a> {
         let mut data = vec![];
         let mut tables = vec![];
         let mut memories = vec![];
+        let mut tags = vec![];
         let mut functions = vec![];
         let mut elements = vec![];
         let mut code_section_count = 0;
@@ -240,6 +243,9 @@ impl<'a> Module<'a> {
                         .into_iter()
                         .collect::<Result<_, _>>()?;
                 }
+                Payload::TagSection(tag_section_reader) => {
+                    tags = tag_section_reader.into_iter().collect::<Result<_, _>>()?;
+                }
                 Payload::FunctionSection(function_section_reader) => {
                     functions = function_section_reader
                         .into_iter()
@@ -335,8 +341,7 @@ impl<'a> Module<'a> {
                     contents: _,
                     range: _,
                 } => return Err(Error::UnknownSection { section_id: id }),
-                Payload::TagSection(_)
-                | Payload::ModuleSection {
+                Payload::ModuleSection {
                     parser: _,
                     unchecked_range: _,
                 }
@@ -378,6 +383,7 @@ impl<'a> Module<'a> {
             functions,
             tables,
             memories,
+            tags,
             globals,
             exports,
             start,
@@ -452,6 +458,20 @@ impl<'a> Module<'a> {
             module.section(&memories);
         }
 
+        if !self.tags.is_empty() {
+            let mut tags = wasm_encoder::TagSection::new();
+            for tag in self.tags {
+                let kind = match tag.kind {
+                    TagKind::Exception => wasm_encoder::TagKind::Exception,
+                };
+                tags.tag(wasm_encoder::TagType {
+                    kind,
+                    func_type_idx: tag.func_type_idx,
+                });
+            }
+            module.section(&tags);
+        }
+
         if !self.globals.is_empty() {
             let mut globals = wasm_encoder::GlobalSection::new();
             for global in self.globals {
@@ -606,6 +626,7 @@ pub struct NameSection<'a> {
     pub memory_names: Vec<(u32, &'a str)>,
     pub global_names: Vec<(u32, &'a str)>,
     pub table_names: Vec<(u32, &'a str)>,
+    pub tag_names: Vec<(u32, &'a str)>,
     pub local_names: Vec<(u32, Vec<(u32, &'a str)>)>,
     pub label_names: Vec<(u32, Vec<(u32, &'a str)>)>,
 }
@@ -641,6 +662,7 @@ impl<'a> NameSection<'a> {
         let mut memory_names = vec![];
         let mut global_names = vec![];
         let mut table_names = vec![];
+        let mut tag_names = vec![];
         let mut local_names = vec![];
         let mut label_names = vec![];
         for subsection_reader in name_section.into_iter() {
@@ -650,6 +672,7 @@ impl<'a> NameSection<'a> {
                 Name::Memory(name_map) => add_names(name_map, &mut memory_names)?,
                 Name::Global(name_map) => add_names(name_map, &mut global_names)?,
                 Name::Table(name_map) => add_names(name_map, &mut table_names)?,
+                Name::Tag(name_map) => add_names(name_map, &mut tag_names)?,
                 Name::Local(indirect_name_map) => {
                     add_indirect_names(indirect_name_map, &mut local_names)?
                 }
@@ -666,6 +689,7 @@ impl<'a> NameSection<'a> {
             memory_names,
             global_names,
             table_names,
+            tag_names,
             local_names,
             label_names,
         })
@@ -712,6 +736,10 @@ impl<'a> NameSection<'a> {
             name_section.tables(&make_name_map(&self.table_names));
         }
 
+        if !self.tag_names.is_empty() {
+            name_section.tags(&make_name_map(&self.tag_names));
+        }
+
         if !self.local_names.is_empty() {
             name_section.locals(&make_indirect_name_map(&self.local_names));
         }
@@ -0,0 +1,30 @@
+(module
+  (tag $e0)
+  (tag $e1 (param i32))
+  (func $thrower (param i32)
+    local.get 0
+    i32.eqz
+    if
+      throw $e0
+    end
+    local.get 0
+    throw $e1
+  )
+  (func $catcher (result i32)
+    (block $caught_e1 (result i32)
+      (block $caught_e0
+        (block $caught_all (result exnref)
+          (try_table (catch $e0 $caught_e0) (catch $e1 $caught_e1) (catch_all_ref $caught_all)
+            i32.const 1
+            call $thrower
+          )
+          i32.const 0
+          return
+        )
+        throw_ref
+      )
+      i32.const -1
+    )
+  )
+  (export "catcher" (func $catcher))
+)
@@ -39,6 +39,7 @@ mod round_trip {
         globals,
         exports,
         start,
-        const_expr
+        const_expr,
+        exceptions
     );
 }